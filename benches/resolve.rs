@@ -0,0 +1,82 @@
+//! Resolving the top of a 500-profile chain, repeatedly, as the TUI does while the
+//! selection moves in Expand mode.
+//!
+//! Run with `cargo bench --bench resolve`. Compares a fresh depth-first walk per call,
+//! as resolution worked before the cache, with the memoized `ProfileGraph`.
+
+use env_manage::config::graph::ProfileGraph;
+use env_manage::config::models::Profile;
+use std::collections::{HashMap, HashSet};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const CHAIN: usize = 500;
+const CALLS: u32 = 200;
+
+fn name(i: usize) -> String {
+    format!("P{i:03}")
+}
+
+/// `P499` depends on `P498`, ... down to `P000`
+fn chain() -> HashMap<String, Profile> {
+    (0..CHAIN)
+        .map(|i| {
+            let mut profile = Profile::new();
+            if i > 0 {
+                profile.profiles.insert(name(i - 1));
+            }
+            (name(i), profile)
+        })
+        .collect()
+}
+
+/// The walk every call used to make
+fn fresh_walk<'a>(
+    profiles: &'a HashMap<String, Profile>,
+    profile: &'a str,
+    resolved: &mut HashSet<&'a str>,
+    result: &mut Vec<String>,
+) {
+    let mut children: Vec<&String> = profiles[profile].profiles.iter().collect();
+    children.sort();
+    for child in children {
+        if !resolved.contains(child.as_str()) {
+            fresh_walk(profiles, child, resolved, result);
+        }
+    }
+    if resolved.insert(profile) {
+        result.push(profile.to_string());
+    }
+}
+
+fn time(label: &str, mut run: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..CALLS {
+        run();
+    }
+    let elapsed = start.elapsed();
+    println!("{label:<24} {:>10.1?} per call", elapsed / CALLS);
+    elapsed
+}
+
+fn main() {
+    let profiles = chain();
+    let top = name(CHAIN - 1);
+    let graph = ProfileGraph::build(&profiles, None).unwrap();
+
+    let fresh = time("fresh walk", || {
+        let mut result = Vec::with_capacity(CHAIN);
+        fresh_walk(&profiles, &top, &mut HashSet::new(), &mut result);
+        black_box(result);
+    });
+    time("memoized, cold cache", || {
+        black_box(graph.clone().resolve_dependencies(&top).unwrap());
+    });
+    let warm = time("memoized, warm cache", || {
+        black_box(graph.resolve_dependencies(&top).unwrap());
+    });
+    println!(
+        "warm cache is {:.1}x faster than a fresh walk",
+        fresh.as_secs_f64() / warm.as_secs_f64()
+    );
+}
@@ -0,0 +1,627 @@
+//! Silent, typed operations for embedding env-manage in other tools.
+//!
+//! Nothing here prints; every failure is reported through [`ApiError`]. The CLI
+//! handlers are thin wrappers around these functions.
+
+use crate::GLOBAL_PROFILE_MARK;
+use crate::config::ConfigManager;
+use crate::config::graph::{DependencyError, display_path};
+use crate::config::loader::LoadError;
+use crate::config::models::{
+    DenyAction, HookKind, Hooks, KeyValidation, MergedEnv, Profile, computed_command,
+};
+use crate::utils::session::SessionState;
+use crate::utils::shell_generate::{ShellGenerate, ShellType};
+use crate::utils::{
+    IdentifierError, template, validate_namespaced_profile_name, validate_variable_key_as,
+};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum ApiError {
+    /// The profile name does not satisfy the naming rules
+    InvalidProfileName(IdentifierError),
+    /// A `KEY=VALUE` item has an invalid key
+    InvalidVariableKey(IdentifierError),
+    ProfileAlreadyExists(String),
+    /// `name` differs from the `existing` profile only in case, and the profiles
+    /// directory ignores case
+    CaseConflict {
+        name: String,
+        existing: String,
+    },
+    ProfileNotFound(String),
+    /// A nested profile to add does not exist
+    DependencyNotFound(String),
+    SelfDependency(String),
+    /// `dependency` is not listed among the nested profiles of `profile`
+    NotADependency {
+        profile: String,
+        dependency: String,
+    },
+    TemplateNotFound(String),
+    /// Placeholders of `template` that no value was given for
+    UnresolvedPlaceholders {
+        template: String,
+        placeholders: Vec<String>,
+    },
+    /// Adding `dependency` to `profile` would close the cycle in `path`; `sources[i]`
+    /// is the file declaring the hop `path[i] -> path[i + 1]`
+    CircularDependency {
+        profile: String,
+        dependency: String,
+        path: Vec<String>,
+        sources: Vec<PathBuf>,
+    },
+    Dependency(DependencyError),
+    /// Activating would set these variables, which the denylist refuses
+    DeniedVariables(Vec<String>),
+    /// Reading or writing the config directory failed
+    Config(Box<dyn Error>),
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::InvalidProfileName(e) => write!(f, "Invalid profile name: {e}"),
+            ApiError::InvalidVariableKey(e) => write!(f, "Invalid variable key: {e}"),
+            ApiError::ProfileAlreadyExists(name) => write!(f, "Profile `{name}` already exists"),
+            ApiError::CaseConflict { name, existing } => write!(
+                f,
+                "Profile `{name}` would share a file with `{existing}`, since the profiles directory ignores case"
+            ),
+            ApiError::ProfileNotFound(name) => write!(f, "Profile `{name}` does not exist"),
+            ApiError::DependencyNotFound(name) => write!(
+                f,
+                "Profile `{name}` does not exist and cannot be added as a nested profile."
+            ),
+            ApiError::SelfDependency(_) => write!(f, "A profile cannot depend on itself."),
+            ApiError::NotADependency {
+                profile,
+                dependency,
+            } => write!(
+                f,
+                "Profile `{dependency}` is not a dependency of `{profile}`"
+            ),
+            ApiError::TemplateNotFound(name) => write!(f, "Template `{name}` does not exist"),
+            ApiError::UnresolvedPlaceholders {
+                template,
+                placeholders,
+            } => write!(
+                f,
+                "Template `{template}` has placeholders without a value: {} (pass them with --set KEY=VALUE)",
+                placeholders.join(", ")
+            ),
+            ApiError::CircularDependency {
+                profile,
+                dependency,
+                path,
+                sources,
+            } => {
+                write!(
+                    f,
+                    "Adding '{dependency}' to '{profile}' would create a circular dependency: {}",
+                    path.join(" -> ")
+                )?;
+                // The first hop is the edge being added, which no file declares yet
+                for (hop, source) in path.windows(2).zip(sources).skip(1) {
+                    write!(
+                        f,
+                        "\n  {} -> {} (declared in {})",
+                        hop[0],
+                        hop[1],
+                        display_path(source)
+                    )?;
+                }
+                Ok(())
+            }
+            ApiError::Dependency(e) => write!(f, "{e}"),
+            ApiError::DeniedVariables(keys) => write!(
+                f,
+                "Refusing to activate, denied variables would be set: {}",
+                keys.join(", ")
+            ),
+            ApiError::Config(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl Error for ApiError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ApiError::InvalidProfileName(e) | ApiError::InvalidVariableKey(e) => Some(e),
+            ApiError::Dependency(e) => Some(e),
+            ApiError::Config(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<DependencyError> for ApiError {
+    fn from(err: DependencyError) -> Self {
+        ApiError::Dependency(err)
+    }
+}
+
+/// Load profile `name` with its dependencies.
+///
+/// Only a missing file is [`ApiError::ProfileNotFound`]; a file that cannot be read or
+/// parsed, or a broken dependency, keeps its own error so it is not mistaken for one.
+pub fn load_profile(config_manager: &mut ConfigManager, name: &str) -> Result<(), ApiError> {
+    config_manager.load_profile(name).map_err(|e| match e {
+        DependencyError::ProfileNotFound(missing) if missing == name => {
+            ApiError::ProfileNotFound(missing)
+        }
+        e => ApiError::Dependency(e),
+    })
+}
+
+impl From<Box<dyn Error>> for ApiError {
+    fn from(err: Box<dyn Error>) -> Self {
+        match err.downcast::<DependencyError>() {
+            Ok(dep_err) => ApiError::Dependency(*dep_err),
+            Err(err) => ApiError::Config(err),
+        }
+    }
+}
+
+/// An item applied to a profile by [`add_items`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AddedItem {
+    Variable(String),
+    Profile(String),
+}
+
+/// Fail unless `name` is free, including on filesystems that ignore case
+fn ensure_new_profile(config_manager: &ConfigManager, name: &str) -> Result<(), ApiError> {
+    if config_manager.profile_exists(name) {
+        return Err(ApiError::ProfileAlreadyExists(name.to_string()));
+    }
+    if let Some(existing) = config_manager.case_conflict(name) {
+        return Err(ApiError::CaseConflict {
+            name: name.to_string(),
+            existing,
+        });
+    }
+    Ok(())
+}
+
+/// Create a new, empty profile on disk
+pub fn create_profile(config_manager: &mut ConfigManager, name: &str) -> Result<(), ApiError> {
+    ensure_new_profile(config_manager, name)?;
+    validate_namespaced_profile_name(name).map_err(ApiError::InvalidProfileName)?;
+
+    config_manager.write_profile(name, &Profile::new())?;
+    Ok(())
+}
+
+/// Create a profile from a template, substituting `{{KEY}}` placeholders in its
+/// description and variable values. Every placeholder must have a value.
+pub fn create_from_template(
+    config_manager: &mut ConfigManager,
+    template: &str,
+    name: &str,
+    values: &HashMap<String, String>,
+) -> Result<(), ApiError> {
+    ensure_new_profile(config_manager, name)?;
+    validate_namespaced_profile_name(name).map_err(ApiError::InvalidProfileName)?;
+
+    let mut profile = config_manager
+        .load_template(template)
+        .map_err(|e| match e {
+            LoadError::NotFound(_) => ApiError::TemplateNotFound(template.to_string()),
+            e => ApiError::Config(Box::new(e)),
+        })?;
+
+    let mut unresolved = BTreeSet::new();
+    profile.description = profile
+        .description
+        .map(|d| template::substitute(&d, values, &mut unresolved));
+    for value in profile.variables.values_mut() {
+        *value = template::substitute(value, values, &mut unresolved);
+    }
+    if !unresolved.is_empty() {
+        return Err(ApiError::UnresolvedPlaceholders {
+            template: template.to_string(),
+            placeholders: unresolved.into_iter().collect(),
+        });
+    }
+
+    config_manager.write_profile(name, &profile)?;
+    Ok(())
+}
+
+/// Delete a profile file without checking for dependents
+pub fn delete_profile(config_manager: &mut ConfigManager, name: &str) -> Result<(), ApiError> {
+    config_manager.delete_profile_file(name)?;
+    Ok(())
+}
+
+/// Load `dependency` before adding it to a profile, reporting a missing one as
+/// [`ApiError::DependencyNotFound`]
+fn load_dependency(config_manager: &mut ConfigManager, dependency: &str) -> Result<(), ApiError> {
+    load_profile(config_manager, dependency).map_err(|e| match e {
+        ApiError::ProfileNotFound(name) => ApiError::DependencyNotFound(name),
+        e => e,
+    })
+}
+
+/// Add `KEY=VALUE` variables and nested profiles to a profile and save it.
+///
+/// Items are applied in order; the profile is only written when all of them succeed.
+pub fn add_items(
+    config_manager: &mut ConfigManager,
+    name: &str,
+    items: &[String],
+) -> Result<Vec<AddedItem>, ApiError> {
+    // Load profile to ensure it exists and graph is populated
+    load_profile(config_manager, name)?;
+    let validation = match config_manager.get_profile(name) {
+        Some(profile) => config_manager.read_settings()?.key_validation(profile),
+        None => KeyValidation::default(),
+    };
+
+    let mut added = Vec::with_capacity(items.len());
+    for item in items {
+        if let Some((key, value)) = item.split_once('=') {
+            validate_variable_key_as(key, validation).map_err(ApiError::InvalidVariableKey)?;
+
+            if let Some(profile) = config_manager.get_profile_mut(name) {
+                profile.add_variable(key, value);
+            }
+            added.push(AddedItem::Variable(key.to_string()));
+        } else {
+            if name == item {
+                return Err(ApiError::SelfDependency(name.to_string()));
+            }
+
+            // Load dependency to check existence (no-op if it is already in memory)
+            load_dependency(config_manager, item)?;
+
+            add_dependency_edge(config_manager, name, item)?;
+
+            if let Some(profile) = config_manager.get_profile_mut(name) {
+                profile.add_profile(name, item);
+            }
+            added.push(AddedItem::Profile(item.clone()));
+        }
+    }
+
+    if let Some(profile) = config_manager.get_profile(name) {
+        config_manager.write_profile(name, profile)?;
+    }
+
+    Ok(added)
+}
+
+/// Add the edge `name -> dependency` incrementally; the graph rejects it if it would
+/// close a cycle
+fn add_dependency_edge(
+    config_manager: &mut ConfigManager,
+    name: &str,
+    dependency: &str,
+) -> Result<(), ApiError> {
+    config_manager
+        .add_dependency_edge(name, dependency)
+        .map_err(|e| match ApiError::from(e) {
+            ApiError::Dependency(DependencyError::CircularDependency { path, sources }) => {
+                ApiError::CircularDependency {
+                    profile: name.to_string(),
+                    dependency: dependency.to_string(),
+                    path,
+                    sources,
+                }
+            }
+            other => other,
+        })
+}
+
+/// Disable the nested profile `dependency` of `name` without removing it, or enable
+/// it again, and save the profile. Returns whether it is now disabled.
+///
+/// Enabling checks the dependency like adding it would: it must exist and must not
+/// close a cycle.
+pub fn toggle_dependency(
+    config_manager: &mut ConfigManager,
+    name: &str,
+    dependency: &str,
+) -> Result<bool, ApiError> {
+    load_profile(config_manager, name)?;
+    let disabled = match config_manager.get_profile(name) {
+        Some(profile) if profile.profiles.contains(dependency) => {
+            !profile.is_profile_disabled(dependency)
+        }
+        _ => {
+            return Err(ApiError::NotADependency {
+                profile: name.to_string(),
+                dependency: dependency.to_string(),
+            });
+        }
+    };
+
+    if disabled {
+        config_manager.remove_dependency_edge(name, dependency)?;
+    } else {
+        load_dependency(config_manager, dependency)?;
+        add_dependency_edge(config_manager, name, dependency)?;
+    }
+
+    if let Some(profile) = config_manager.get_profile_mut(name) {
+        profile.set_profile_disabled(dependency, disabled);
+    }
+    if let Some(profile) = config_manager.get_profile(name) {
+        config_manager.write_profile(name, profile)?;
+    }
+    Ok(disabled)
+}
+
+/// Resolve a profile and its dependencies into the merged set of variables
+pub fn resolve_env(
+    config_manager: &mut ConfigManager,
+    name: &str,
+) -> Result<HashMap<String, String>, ApiError> {
+    config_manager.load_profile(name)?;
+    let profile = config_manager
+        .get_profile(name)
+        .ok_or_else(|| ApiError::ProfileNotFound(name.to_string()))?;
+    Ok(profile.collect_vars(name, config_manager)?)
+}
+
+/// Resolve the variables a profile and its dependencies remove from the environment
+pub fn resolve_unset(
+    config_manager: &mut ConfigManager,
+    name: &str,
+) -> Result<HashSet<String>, ApiError> {
+    config_manager.load_profile(name)?;
+    let profile = config_manager
+        .get_profile(name)
+        .ok_or_else(|| ApiError::ProfileNotFound(name.to_string()))?;
+    Ok(profile.collect_env(name, config_manager)?.1)
+}
+
+/// Resolve several profiles into the variables to set and the variables to unset.
+///
+/// Later profiles win, both when several define the same key and when one sets a
+/// variable that another unsets.
+pub fn resolve_envs(
+    config_manager: &mut ConfigManager,
+    names: &[String],
+) -> Result<(HashMap<String, String>, HashSet<String>), ApiError> {
+    let merged = resolve_merged(config_manager, names)?;
+    Ok((merged.values(), merged.unset))
+}
+
+/// Like [`resolve_envs`], also tracking the profiles behind each variable
+pub fn resolve_merged(
+    config_manager: &mut ConfigManager,
+    names: &[String],
+) -> Result<MergedEnv, ApiError> {
+    let mut merged = MergedEnv::default();
+    merge_profiles(config_manager, &mut merged, names)?;
+    Ok(merged)
+}
+
+/// Merge the profiles `names` with their dependencies on top of `merged`, one after
+/// the other, so a list key extends the entries earlier profiles added to the variable
+pub fn merge_profiles(
+    config_manager: &mut ConfigManager,
+    merged: &mut MergedEnv,
+    names: &[String],
+) -> Result<(), ApiError> {
+    for name in names {
+        config_manager.load_profile(name)?;
+        let profile = config_manager
+            .get_profile(name)
+            .ok_or_else(|| ApiError::ProfileNotFound(name.to_string()))?;
+        profile.merge_into(name, merged, config_manager)?;
+    }
+    Ok(())
+}
+
+/// The source named for variables that come from the global settings
+pub const GLOBAL_SOURCE: &str = "(global)";
+
+/// Resolve the global settings, with the profiles they include, into the layer every
+/// activation starts from, so any activated profile overrides them
+pub fn resolve_global(config_manager: &mut ConfigManager) -> Result<MergedEnv, ApiError> {
+    let global = config_manager.read_global()?;
+    for name in &global.profiles {
+        config_manager.load_profile(name)?;
+    }
+    let mut merged = global.collect_merged(GLOBAL_PROFILE_MARK, config_manager)?;
+    merged.rename_source(GLOBAL_PROFILE_MARK, GLOBAL_SOURCE);
+    Ok(merged)
+}
+
+/// The hooks an activation needs trust for
+#[derive(Clone, Debug, Default)]
+pub struct ActivationHooks {
+    /// Of the activated profiles and their dependencies, dependencies first
+    pub profiles: Vec<(String, Hooks)>,
+    /// Of the global settings, named [`GLOBAL_SOURCE`], and the profiles they include
+    /// that compute variables; only the computed variables take part in an activation
+    pub global: Vec<(String, Hooks)>,
+}
+
+impl ActivationHooks {
+    /// The names of every profile asked to trust its hooks
+    pub fn names(&self) -> Vec<String> {
+        self.global
+            .iter()
+            .chain(&self.profiles)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+}
+
+/// The hooks of activating `names` over the global settings.
+///
+/// Global settings that fail to resolve add no computed variables, since they add no
+/// layer either.
+pub fn activation_hooks(
+    config_manager: &mut ConfigManager,
+    names: &[String],
+) -> Result<ActivationHooks, ApiError> {
+    let profiles = resolve_hooks(
+        config_manager,
+        names,
+        &[
+            HookKind::PreActivate,
+            HookKind::Activate,
+            HookKind::PostActivate,
+        ],
+    )?;
+    let mut global = if names.is_empty() {
+        Vec::new()
+    } else {
+        resolve_global_computed(config_manager).unwrap_or_default()
+    };
+    global.retain(|(name, _)| !profiles.iter().any(|(activated, _)| activated == name));
+    Ok(ActivationHooks { profiles, global })
+}
+
+fn resolve_global_computed(
+    config_manager: &mut ConfigManager,
+) -> Result<Vec<(String, Hooks)>, ApiError> {
+    let global = config_manager.read_global()?;
+    let names: Vec<String> = global.profiles.iter().cloned().collect();
+    let mut hooks = resolve_hooks(config_manager, &names, &[HookKind::Activate])?;
+    hooks.retain(|(_, hooks)| !hooks.computed.is_empty());
+    let computed = global.computed_commands();
+    if !computed.is_empty() {
+        let global_hooks = Hooks {
+            computed,
+            ..Hooks::default()
+        };
+        hooks.push((GLOBAL_SOURCE.to_string(), global_hooks));
+    }
+    Ok(hooks)
+}
+
+/// Remove the computed values that the profiles `untrusted` supplied from `vars`.
+///
+/// Computed values run their commands as hooks do, so they need the same trust.
+pub fn drop_untrusted_computed(
+    vars: &mut HashMap<String, String>,
+    merged: &MergedEnv,
+    untrusted: &[String],
+) {
+    vars.retain(|key, value| {
+        computed_command(value).is_none()
+            || merged
+                .vars
+                .get(key)
+                .and_then(|var| var.sources.first())
+                .is_none_or(|source| !untrusted.contains(source))
+    });
+}
+
+/// Remove the variables the denylist of `config.toml` refuses from `vars`, returning
+/// them sorted, or fail when the denylist refuses the whole activation
+pub fn apply_denylist(
+    config_manager: &ConfigManager,
+    vars: &mut HashMap<String, String>,
+) -> Result<Vec<String>, ApiError> {
+    let denylist = config_manager.read_settings()?.denylist;
+    let denied = denylist.denied_keys(vars);
+    if !denied.is_empty() && denylist.action == DenyAction::Error {
+        return Err(ApiError::DeniedVariables(denied));
+    }
+    vars.retain(|key, _| !denylist.is_denied(key));
+    Ok(denied)
+}
+
+/// Add the commands activating `profiles` to `generate`: export `vars`, unset `unset`,
+/// run the activate hooks of `hooks` and record the activation in the session state
+pub fn write_activation(
+    generate: &mut ShellGenerate,
+    profiles: &[String],
+    vars: &HashMap<String, String>,
+    unset: &HashSet<String>,
+    hooks: &[(String, Hooks)],
+) {
+    let mut session = SessionState::from_env();
+    session.track(profiles, vars, generate);
+    generate.export_from_map(vars);
+    generate.unset_from_set(unset);
+    for (_, profile_hooks) in hooks {
+        generate.run_hook(profile_hooks.commands(HookKind::Activate));
+    }
+    session.write(generate);
+}
+
+/// The hooks of `names` and the profiles they depend on with commands of any of
+/// `kinds`, dependencies first, listing each profile once.
+///
+/// Computed variables run in the shell with the `Activate` hooks, so profiles with
+/// them are listed whenever `kinds` includes it.
+pub fn resolve_hooks(
+    config_manager: &mut ConfigManager,
+    names: &[String],
+    kinds: &[HookKind],
+) -> Result<Vec<(String, Hooks)>, ApiError> {
+    for name in names {
+        config_manager.load_profile(name)?;
+    }
+    let mut hooks: Vec<(String, Hooks)> = Vec::new();
+    for profile_name in config_manager.resolve_many(names)? {
+        let Some(profile) = config_manager.get_profile(&profile_name) else {
+            continue;
+        };
+        let profile_hooks = profile.hooks();
+        if kinds.iter().any(|&kind| {
+            !profile_hooks.commands(kind).is_empty()
+                || (kind == HookKind::Activate && !profile_hooks.computed.is_empty())
+        }) {
+            hooks.push((profile_name, profile_hooks));
+        }
+    }
+    Ok(hooks)
+}
+
+/// Render the commands `em use` runs for `names` in `shell`, without asking anything.
+///
+/// The profiles are laid over the global settings, denied variables are skipped or
+/// refused as `config.toml` says, and only trusted hooks and computed values run.
+pub fn activation_script(
+    config_manager: &mut ConfigManager,
+    names: &[String],
+    shell: ShellType,
+) -> Result<String, ApiError> {
+    let mut merged = if names.is_empty() {
+        MergedEnv::default()
+    } else {
+        resolve_global(config_manager)?
+    };
+    merge_profiles(config_manager, &mut merged, names)?;
+    let mut vars = merged.values();
+    apply_denylist(config_manager, &mut vars)?;
+    let mut unset = merged.unset.clone();
+    // Unset variables are not tracked, so deactivating never brings them back
+    unset.retain(|key| env::var_os(key).is_some());
+
+    let hooks = activation_hooks(config_manager, names)?;
+    let settings = config_manager.read_settings()?;
+    let untrusted: Vec<String> = hooks
+        .global
+        .iter()
+        .chain(&hooks.profiles)
+        .filter(|(name, hooks)| !settings.trusts_hooks(name, hooks))
+        .map(|(name, _)| name.clone())
+        .collect();
+    drop_untrusted_computed(&mut vars, &merged, &untrusted);
+    // The global layer only contributes computed values, its hooks never run
+    let trusted: Vec<(String, Hooks)> = hooks
+        .profiles
+        .into_iter()
+        .filter(|(name, _)| !untrusted.contains(name))
+        .collect();
+
+    let mut generate = ShellGenerate::with_shell(shell);
+    write_activation(&mut generate, names, &vars, &unset, &trusted);
+    Ok(generate.script())
+}
@@ -1,5 +1,6 @@
 use clap::builder::styling::{AnsiColor, Effects, Styles};
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
 
 fn styles() -> Styles {
     Styles::styled()
@@ -24,6 +25,29 @@ fn styles() -> Styles {
     styles = styles()
 )]
 pub struct Cli {
+    /// Use a different config directory (defaults to $ENV_MANAGE_CONFIG_DIR or $XDG_CONFIG_HOME/env-manage)
+    #[arg(
+        long = "config-dir",
+        visible_alias = "profile-dir",
+        global = true,
+        value_name = "PATH"
+    )]
+    pub profile_dir: Option<PathBuf>,
+
+    /// Only print errors, warnings and requested output, without success or info messages
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+
+    /// When to color output; `auto` colors when stderr is a terminal and NO_COLOR is not set
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_name = "WHEN",
+        default_value_t = ColorMode::Auto
+    )]
+    pub color: ColorMode,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -33,10 +57,16 @@ pub enum Commands {
     /// Initialize the shell environment for env-manage
     #[command(hide = true)]
     Init {
-        #[arg(help = "Shell to initialize", value_name = "SHELL", required = true)]
-        shell: String,
-        #[arg(long, help = "Print full initialization script", hide = true)]
-        print_full_init: bool,
+        /// Shell to initialize (bash, zsh, fish, powershell); detected when omitted
+        #[arg(value_name = "SHELL")]
+        shell: Option<String>,
+        /// Shell to initialize, the same as the positional argument
+        #[arg(long = "shell", value_name = "SHELL", conflicts_with = "shell")]
+        shell_option: Option<String>,
+        /// Print the full shell integration instead of the line that loads it, to keep it
+        /// in managed dotfiles or inspect it; nothing is written anywhere
+        #[arg(long, alias = "print-full-init")]
+        print: bool,
     },
 
     /// Manage environment profiles
@@ -47,34 +77,160 @@ pub enum Commands {
     /// Activate profiles or specific key-value pairs in the current session
     #[command(visible_alias = "use")]
     Activate {
-        /// Profiles to activate or key-value pairs to set (e.g., work API_KEY=123)
+        /// Profiles or @groups to activate, or key-value pairs to set (e.g., work @backend API_KEY=123)
         #[arg(required = true)]
         items: Vec<String>,
+        /// Print the shell commands instead of applying them
+        #[arg(long)]
+        print: bool,
+        /// Shell to generate commands for (bash, zsh, fish, powershell, nu); detected when omitted
+        #[arg(long)]
+        shell: Option<String>,
+        /// Trust the hook commands of the profiles without asking
+        #[arg(long)]
+        allow_hooks: bool,
+        /// Show which profile each variable comes from instead of activating anything
+        #[arg(long, conflicts_with = "print")]
+        explain: bool,
+        /// Ask which value wins when profiles set a variable to different values
+        #[arg(long)]
+        interactive_conflicts: bool,
+        /// Remember the answers for this session, so activating again does not ask
+        #[arg(long, requires = "interactive_conflicts")]
+        remember_conflicts: bool,
+        /// Refuse to activate when the variables exceed `max_env_size` instead of warning
+        #[arg(long)]
+        strict_size: bool,
     },
 
     /// Deactivate profiles or specific keys in the current session
     #[command(visible_aliases = ["unuse", "drop"])]
     Deactivate {
-        /// Profiles or keys to deactivate
-        #[arg(required = true)]
+        /// Profiles, @groups or keys to deactivate; a name that is no profile but a variable
+        /// set by env-manage, or `profile:KEY`, unsets just that variable
+        #[arg(required_unless_present_any = ["all", "vars"])]
         items: Vec<String>,
+        /// Deactivate everything env-manage has set in the current session
+        #[arg(long, conflicts_with_all = ["items", "vars"])]
+        all: bool,
+        /// Unset just this variable, even when a profile has the same name
+        #[arg(long = "var", value_name = "KEY")]
+        vars: Vec<String>,
+        /// Shell to generate commands for (bash, zsh, fish, powershell, nu); detected when omitted
+        #[arg(long)]
+        shell: Option<String>,
+        /// Trust the hook commands of the profiles without asking
+        #[arg(long)]
+        allow_hooks: bool,
     },
 
     /// Manage global environment settings
     #[command(subcommand)]
     Global(GlobalCommands),
 
+    /// Manage named groups of profiles, activated as `@group`
+    #[command(subcommand)]
+    Group(GroupCommands),
+
     /// Check the status of the current environment
     Status(CommandsStatusArgs),
 
     /// Launch the terminal UI
-    Ui,
+    Ui {
+        /// Browse profiles without being able to change them
+        #[arg(long)]
+        read_only: bool,
+        /// Color theme: default, high-contrast, colorblind-safe or a file in `themes/`
+        #[arg(long, value_name = "NAME")]
+        theme: Option<String>,
+    },
 
     /// Check for issues in the profiles directory (missing files, circular dependencies)
-    Check,
+    Check {
+        /// Only check this profile and the profiles it depends on
+        #[arg(long, value_name = "NAME")]
+        profile: Option<String>,
+        /// Output format of the report
+        #[arg(long, value_enum, default_value_t = CheckFormat::Human)]
+        format: CheckFormat,
+        /// Exit with an error when only warnings were found
+        #[arg(long)]
+        warnings_as_errors: bool,
+        /// Repair the issues found, as `fix` does, and report what was fixed
+        #[arg(long, conflicts_with_all = ["profile", "format"])]
+        fix: bool,
+        /// With --fix, apply the default fixes without prompting
+        #[arg(short, long, requires = "fix")]
+        yes: bool,
+    },
+
+    /// Print the active profiles on one line, for embedding in a shell prompt
+    Prompt {
+        /// Layout of the line; `{names}` is replaced by the profiles, `{count}` by their number
+        #[arg(long, default_value = "{names}")]
+        format: String,
+        /// Text placed between profile names
+        #[arg(long, default_value = ",")]
+        separator: String,
+        /// Icon printed before the line
+        #[arg(long)]
+        symbol: Option<String>,
+        /// Show at most N profiles, summarizing the rest as `+k`
+        #[arg(long, value_name = "N")]
+        max: Option<usize>,
+    },
 
     /// Attempt to fix issues in the profiles directory
-    Fix,
+    Fix {
+        /// Apply the default fix (remove dangling references) without prompting
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Show recent changes to profiles, from the CLI and the terminal UI
+    History {
+        /// Number of entries to show
+        #[arg(short = 'n', long, value_name = "N", default_value_t = 20)]
+        limit: usize,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ColorMode {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum CheckFormat {
+    /// Colored report on stderr
+    Human,
+    /// JSON array of findings on stdout
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ListSort {
+    Name,
+    Modified,
+    Vars,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum GraphFormat {
+    /// Graphviz DOT, for `dot -Tsvg`
+    Dot,
+    /// Mermaid flowchart, for Markdown documents
+    Mermaid,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ExportFormat {
+    /// `KEY=value` lines, for `.env` files
+    Dotenv,
+    /// Export commands for the shell, to `source` directly
+    Shell,
 }
 
 #[derive(Subcommand, Debug)]
@@ -84,9 +240,86 @@ pub enum ProfileCommands {
         /// Whether to expand profile contents in a tree structure
         #[arg(short, long)]
         expand: bool,
+        /// With `--expand`, show the contents of dependencies only this many levels
+        /// deep; deeper ones are shown as `…`
+        #[arg(long, requires = "expand")]
+        depth: Option<usize>,
+        /// Only list profiles carrying this tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Print the profiles as JSON on stdout
+        #[arg(long, conflicts_with = "expand")]
+        json: bool,
+        /// Print each profile on one line on stdout, as `name: KEY=value [deps: a,b]`
+        #[arg(long, conflicts_with_all = ["expand", "json"])]
+        oneline: bool,
+        /// Order of the profiles; `modified` and `vars` put the newest and largest first
+        #[arg(long, value_enum, default_value_t = ListSort::Name)]
+        sort: ListSort,
+    },
+    /// Show the variables and dependencies of a single profile
+    Show {
+        name: String,
+        /// Include the dependency tree and the resolved values inherited from it
+        #[arg(short, long)]
+        expand: bool,
+        /// With `--expand`, show the contents of dependencies only this many levels
+        /// deep; deeper ones are shown as `…`
+        #[arg(long, requires = "expand")]
+        depth: Option<usize>,
+    },
+    /// Find the profiles with variables whose key (or value) matches a query
+    Search {
+        /// Text to look for, case-insensitively; a pattern with `--regex`
+        query: String,
+        /// Match variable values as well as keys
+        #[arg(long)]
+        values: bool,
+        /// Treat the query as a regular expression (case-sensitive)
+        #[arg(long)]
+        regex: bool,
+    },
+    /// Print the dependency graph of all profiles on stdout
+    Graph {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+        format: GraphFormat,
+    },
+    /// Summarize the profiles: how many, their variables and how deep they inherit
+    Stats,
+    /// Write the resolved variables of a profile in dotenv format or as shell commands
+    Export {
+        name: String,
+        /// File to write to instead of stdout; missing directories are created
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ExportFormat::Dotenv)]
+        format: ExportFormat,
+        /// Shell to generate commands for with `--format shell` (bash, zsh, fish, powershell, nu); detected when omitted
+        #[arg(long)]
+        shell: Option<String>,
+    },
+    /// Compare the variables of two profiles
+    Diff {
+        a: String,
+        b: String,
+        /// Compare the resolved variables, including inherited ones
+        #[arg(long)]
+        expand: bool,
+        /// Print an uncolored, unified-diff-like comparison on stdout, for piping
+        #[arg(long)]
+        plain: bool,
     },
+    /// Open a profile's file in $VISUAL or $EDITOR, rejecting edits that do not load
+    Edit { name: String },
     /// Create a new, empty profile
-    Create { name: String },
+    Create {
+        name: String,
+        /// Open the new profile in the TUI editor; leaving it returns to the profile list
+        #[arg(long)]
+        edit: bool,
+    },
     /// Rename a profile
     Rename(ProfileRenameArgs),
     /// Delete a profile
@@ -110,6 +343,37 @@ pub enum ProfileCommands {
         #[arg(required = true)]
         items: Vec<String>,
     },
+    /// Disable a dependency without removing it, or enable it again
+    ToggleDep {
+        /// The name of the profile to modify
+        name: String,
+        /// The nested profile to disable or enable
+        dependency: String,
+    },
+    /// Create a profile from a template, filling in its `{{KEY}}` placeholders
+    NewFromTemplate {
+        /// The template to instantiate (from the `templates` directory)
+        #[arg(required = true)]
+        template: String,
+        /// The name of the profile to create
+        #[arg(required = true)]
+        name: String,
+        /// Placeholder values (e.g., --set SERVICE=foo)
+        #[arg(long = "set", value_name = "KEY=VALUE")]
+        values: Vec<String>,
+    },
+    /// Set the description and tags of a profile
+    SetMeta {
+        /// The name of the profile to modify
+        #[arg(required = true)]
+        name: String,
+        /// A short description of the profile (an empty string clears it)
+        #[arg(long)]
+        description: Option<String>,
+        /// Comma-separated tags (e.g., backend,db; an empty string clears them)
+        #[arg(long)]
+        tags: Option<String>,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -134,11 +398,44 @@ pub enum GlobalCommands {
     },
     /// Clear all global settings and unset corresponding environment variables in current shell
     Clean,
+    /// Open the global settings file in $VISUAL or $EDITOR, offering to fix or discard
+    /// edits that do not load
+    Edit,
     /// Initialize the shell environment
     #[command(hide = true)]
     Init,
 }
 
+#[derive(Subcommand, Debug)]
+pub enum GroupCommands {
+    /// Create a group, optionally with its first members
+    Create {
+        name: String,
+        /// Profiles or @groups the group contains
+        members: Vec<String>,
+    },
+    /// Add profiles or @groups to a group
+    Add {
+        /// The name of the group to modify
+        #[arg(required = true)]
+        name: String,
+        /// Profiles or @groups to add
+        #[arg(required = true)]
+        members: Vec<String>,
+    },
+    /// Remove members from a group, or the whole group when none are given
+    #[command(visible_alias = "rm")]
+    Remove {
+        /// The name of the group to modify
+        #[arg(required = true)]
+        name: String,
+        /// Profiles or @groups to remove
+        members: Vec<String>,
+    },
+    /// List all groups and their members
+    List,
+}
+
 #[derive(Debug, Args)]
 pub struct CommandsStatusArgs {
     /// Check the activation status of specific profiles
@@ -146,6 +443,9 @@ pub struct CommandsStatusArgs {
     /// Whether to expand profile contents in a tree structure
     #[arg(short, long)]
     pub expand: bool,
+    /// Print the expected and current values as JSON on stdout
+    #[arg(long, conflicts_with = "expand")]
+    pub json: bool,
 }
 
 #[derive(Debug, Args)]
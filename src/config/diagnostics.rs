@@ -0,0 +1,654 @@
+use super::ConfigManager;
+use super::env_size::EnvSize;
+use super::graph::DependencyError;
+use super::groups::{GroupError, group_reference};
+use super::history;
+use super::journal;
+use super::loader::{self, SkipReason};
+use super::models::{KeyValidation, VarOp};
+use crate::GLOBAL_PROFILE_MARK;
+use crate::utils::placeholder::unknown_placeholders;
+use crate::utils::{IdentifierError, validate_namespaced_profile_name, validate_variable_key_as};
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Worth knowing about, never a failure
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// A single problem found while loading the profiles directory
+#[derive(Debug)]
+pub enum Diagnostic {
+    InvalidName {
+        profile: String,
+        error: IdentifierError,
+    },
+    /// Loading `profile` failed with `error`, which holds no `MultipleErrors`
+    Dependency {
+        profile: String,
+        error: DependencyError,
+    },
+    /// The value of `key` uses a `${...}` placeholder that is not built in
+    UnknownPlaceholder {
+        profile: String,
+        key: String,
+        placeholder: String,
+    },
+    /// `profile` lists itself in its `profiles`
+    SelfDependency { profile: String },
+    /// `groups.toml` lists `member`, a profile or `@group` that does not exist
+    MissingGroupMember { group: String, member: String },
+    /// Expanding `group` leads back to a group in `path`
+    CircularGroup { group: String, path: Vec<String> },
+    /// `profile` runs `commands` shell commands when it is (de)activated
+    Hooks {
+        profile: String,
+        commands: usize,
+        trusted: bool,
+    },
+    /// `profile` computes `keys` with commands it has not been trusted to run, so
+    /// activating it skips them
+    UntrustedComputed { profile: String, keys: Vec<String> },
+    /// `file`, relative to the profiles directory, is not read as a profile
+    SkippedFile { file: String, reason: SkipReason },
+    /// `profile` sets `key`, which the global profile sets to another value
+    ShadowedGlobal {
+        profile: String,
+        key: String,
+        global_value: String,
+        value: String,
+    },
+    /// The longest chain of dependencies from `profile` is `depth` profiles long,
+    /// more than the configured `limit`
+    DeepInheritance {
+        profile: String,
+        depth: usize,
+        limit: usize,
+    },
+    /// The variables `profile` resolves to take more than `limit` bytes
+    LargeEnvironment {
+        profile: String,
+        size: EnvSize,
+        limit: usize,
+    },
+    /// `key` of a strict profile is not a valid ALL_CAPS name
+    InvalidKey {
+        profile: String,
+        key: String,
+        error: IdentifierError,
+    },
+    /// The operation recorded in `journal`, a file of the journal directory, did not
+    /// finish: `applied` of its `total` files are written
+    IncompleteOperation {
+        journal: String,
+        operation: String,
+        started: String,
+        applied: usize,
+        total: usize,
+    },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::InvalidName { profile, error } => {
+                write!(f, "Invalid profile name '{profile}': {error}")
+            }
+            Diagnostic::Dependency { error, .. } => write!(f, "{error}"),
+            Diagnostic::UnknownPlaceholder {
+                profile,
+                key,
+                placeholder,
+            } => write!(
+                f,
+                "Unknown placeholder '${{{placeholder}}}' in '{key}' of profile '{profile}'"
+            ),
+            Diagnostic::SelfDependency { profile } => {
+                write!(f, "Profile '{profile}' lists itself as a dependency")
+            }
+            Diagnostic::MissingGroupMember { group, member } => {
+                write!(f, "Group '{group}' references missing member '{member}'")
+            }
+            Diagnostic::CircularGroup { path, .. } => {
+                write!(f, "{}", GroupError::CircularGroup(path.clone()))
+            }
+            Diagnostic::Hooks {
+                profile,
+                commands,
+                trusted,
+            } => {
+                let plural = if *commands == 1 { "" } else { "s" };
+                let trust = if *trusted { "trusted" } else { "not trusted" };
+                write!(
+                    f,
+                    "Profile '{profile}' runs {commands} hook command{plural} ({trust})"
+                )
+            }
+            Diagnostic::UntrustedComputed { profile, keys } => write!(
+                f,
+                "Profile '{profile}' computes {} with commands that are not trusted; activate it with --allow-hooks to trust them",
+                keys.join(", ")
+            ),
+            Diagnostic::SkippedFile { file, reason } => {
+                write!(f, "Skipped '{file}' in the profiles directory: {reason}")
+            }
+            Diagnostic::ShadowedGlobal {
+                profile,
+                key,
+                global_value,
+                value,
+            } => write!(
+                f,
+                "Profile '{profile}' overrides GLOBAL variable '{key}': '{global_value}' -> '{value}'"
+            ),
+            Diagnostic::DeepInheritance {
+                profile,
+                depth,
+                limit,
+            } => write!(
+                f,
+                "Profile '{profile}' inherits {depth} levels deep, more than the limit of {limit}"
+            ),
+            Diagnostic::LargeEnvironment {
+                profile,
+                size,
+                limit,
+            } => write!(
+                f,
+                "Profile '{profile}' resolves to {}",
+                size.warning(*limit)
+            ),
+            Diagnostic::InvalidKey {
+                profile,
+                key,
+                error,
+            } => write!(
+                f,
+                "Invalid variable key '{key}' in strict profile '{profile}': {error}"
+            ),
+            Diagnostic::IncompleteOperation {
+                operation,
+                started,
+                applied,
+                total,
+                ..
+            } => write!(
+                f,
+                "Operation '{operation}' started at {started} did not finish ({applied} of {total} files written); run `em fix` to roll it forward or back"
+            ),
+        }
+    }
+}
+
+impl Diagnostic {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Diagnostic::SkippedFile {
+                reason: SkipReason::Reserved,
+                ..
+            } => Severity::Warning,
+            Diagnostic::Hooks { .. } | Diagnostic::SkippedFile { .. } => Severity::Info,
+            Diagnostic::InvalidName { .. }
+            | Diagnostic::UnknownPlaceholder { .. }
+            | Diagnostic::ShadowedGlobal { .. }
+            | Diagnostic::DeepInheritance { .. }
+            | Diagnostic::LargeEnvironment { .. }
+            | Diagnostic::UntrustedComputed { .. } => Severity::Warning,
+            Diagnostic::Dependency { .. }
+            | Diagnostic::SelfDependency { .. }
+            | Diagnostic::InvalidKey { .. }
+            | Diagnostic::MissingGroupMember { .. }
+            | Diagnostic::CircularGroup { .. }
+            | Diagnostic::IncompleteOperation { .. } => Severity::Error,
+        }
+    }
+
+    /// The profile, or group, whose validation produced this diagnostic
+    pub fn profile(&self) -> &str {
+        match self {
+            Diagnostic::InvalidName { profile, .. }
+            | Diagnostic::Dependency { profile, .. }
+            | Diagnostic::UnknownPlaceholder { profile, .. }
+            | Diagnostic::SelfDependency { profile }
+            | Diagnostic::Hooks { profile, .. }
+            | Diagnostic::UntrustedComputed { profile, .. }
+            | Diagnostic::ShadowedGlobal { profile, .. }
+            | Diagnostic::DeepInheritance { profile, .. }
+            | Diagnostic::LargeEnvironment { profile, .. }
+            | Diagnostic::InvalidKey { profile, .. } => profile,
+            Diagnostic::SkippedFile { file, .. } => file,
+            Diagnostic::IncompleteOperation { journal, .. } => journal,
+            Diagnostic::MissingGroupMember { group, .. }
+            | Diagnostic::CircularGroup { group, .. } => group,
+        }
+    }
+
+    /// A stable identifier for the kind of problem, for machine-readable reports
+    pub fn kind(&self) -> &'static str {
+        let error = match self {
+            Diagnostic::InvalidName { .. } => return "invalid_name",
+            Diagnostic::UnknownPlaceholder { .. } => return "unknown_placeholder",
+            Diagnostic::SelfDependency { .. } => return "self_dependency",
+            Diagnostic::MissingGroupMember { .. } => return "group_member_not_found",
+            Diagnostic::CircularGroup { .. } => return "circular_group",
+            Diagnostic::Hooks { .. } => return "hooks",
+            Diagnostic::UntrustedComputed { .. } => return "untrusted_computed",
+            Diagnostic::SkippedFile { .. } => return "skipped_file",
+            Diagnostic::ShadowedGlobal { .. } => return "shadowed_global",
+            Diagnostic::DeepInheritance { .. } => return "deep_inheritance",
+            Diagnostic::LargeEnvironment { .. } => return "large_environment",
+            Diagnostic::InvalidKey { .. } => return "invalid_key",
+            Diagnostic::IncompleteOperation { .. } => return "incomplete_operation",
+            Diagnostic::Dependency { error, .. } => error,
+        };
+        match root_cause(error) {
+            DependencyError::CircularDependency { .. } => "circular_dependency",
+            DependencyError::DependencyNotFound { .. } => "dependency_not_found",
+            DependencyError::GlobalDependency { .. } => "global_dependency",
+            DependencyError::ProfileNotFound(_) => "profile_not_found",
+            DependencyError::ProfileIoError(_, _) => "io_error",
+            DependencyError::ProfileParseError(_, _) => "parse_error",
+            DependencyError::ProfileInvalid(_, _) => "invalid_field",
+            DependencyError::DependencyChain { .. } | DependencyError::MultipleErrors(_) => {
+                "dependency_error"
+            }
+        }
+    }
+
+    /// Whether this reports a profile file that exists but cannot be parsed, as opposed
+    /// to a missing one
+    pub fn is_malformed_file(&self) -> bool {
+        matches!(self.kind(), "parse_error" | "invalid_field")
+    }
+
+    /// The profile file the problem is located in
+    pub fn file(&self, profiles_dir: &Path) -> PathBuf {
+        let profile_file = |name: &str| profiles_dir.join(format!("{name}.toml"));
+        if matches!(
+            self,
+            Diagnostic::MissingGroupMember { .. } | Diagnostic::CircularGroup { .. }
+        ) {
+            return profiles_dir.with_file_name("groups.toml");
+        }
+        if let Diagnostic::SkippedFile { file, .. } = self {
+            return profiles_dir.join(file);
+        }
+        if let Diagnostic::IncompleteOperation { journal, .. } = self {
+            return journal::journal_dir(profiles_dir.parent().unwrap_or(profiles_dir))
+                .join(journal);
+        }
+        let Diagnostic::Dependency { profile, error } = self else {
+            return profile_file(self.profile());
+        };
+        match root_cause(error) {
+            DependencyError::CircularDependency { sources, .. } if !sources.is_empty() => {
+                sources[0].clone()
+            }
+            DependencyError::DependencyNotFound {
+                source: Some(source),
+                ..
+            } => source.clone(),
+            DependencyError::DependencyNotFound { parent, .. } => profile_file(parent),
+            DependencyError::GlobalDependency {
+                source: Some(source),
+                ..
+            } => source.clone(),
+            DependencyError::GlobalDependency { parent, .. } => profile_file(parent),
+            DependencyError::ProfileIoError(name, _)
+            | DependencyError::ProfileParseError(name, _)
+            | DependencyError::ProfileInvalid(name, _) => profile_file(name),
+            _ => profile_file(profile),
+        }
+    }
+
+    /// The `(parent, dependency)` references this diagnostic reports as dangling
+    pub fn dangling_references(&self) -> Vec<(&str, &str)> {
+        let mut references = Vec::new();
+        if let Diagnostic::Dependency { error, .. } = self {
+            collect_dangling(error, &mut references);
+        }
+        references
+    }
+
+    /// The cycles this diagnostic reports, each walking back to its start
+    pub fn cycles(&self) -> Vec<&[String]> {
+        let mut cycles = Vec::new();
+        if let Diagnostic::Dependency { error, .. } = self {
+            collect_cycles(error, &mut cycles);
+        }
+        cycles
+    }
+}
+
+/// Load every profile on disk and collect what is wrong with them.
+///
+/// Profiles are loaded in name order, so a broken dependency shared by several
+/// profiles is reported once per profile that reaches it.
+pub fn collect(config_manager: &mut ConfigManager) -> Result<Vec<Diagnostic>, Box<dyn Error>> {
+    let report = config_manager.scan_profiles()?;
+    let mut diagnostics = collect_names(config_manager, report.files.iter().map(|f| &f.name));
+    diagnostics.extend(
+        report
+            .skipped
+            .into_iter()
+            .map(|skipped| Diagnostic::SkippedFile {
+                file: skipped.name,
+                reason: skipped.reason,
+            }),
+    );
+    diagnostics.extend(collect_groups(config_manager)?);
+    diagnostics.extend(collect_journal(config_manager.base_path())?);
+    Ok(diagnostics)
+}
+
+/// Report the journaled operations that were interrupted
+fn collect_journal(base_path: &Path) -> Result<Vec<Diagnostic>, Box<dyn Error>> {
+    let mut diagnostics = Vec::new();
+    for (path, entry) in journal::incomplete(base_path) {
+        diagnostics.push(Diagnostic::IncompleteOperation {
+            journal: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            started: history::timestamp(UNIX_EPOCH + Duration::from_secs(entry.started)),
+            applied: entry.applied(base_path)?,
+            total: entry.files.len(),
+            operation: entry.operation,
+        });
+    }
+    Ok(diagnostics)
+}
+
+/// Check every group in `groups.toml` for missing members and cycles
+fn collect_groups(config_manager: &ConfigManager) -> Result<Vec<Diagnostic>, Box<dyn Error>> {
+    let groups = config_manager.read_groups()?;
+    let mut diagnostics = Vec::new();
+
+    for (group, members) in &groups.0 {
+        for member in members {
+            let exists = match group_reference(member) {
+                Some(name) => groups.contains(name),
+                None => config_manager.profile_exists(member),
+            };
+            if !exists {
+                diagnostics.push(Diagnostic::MissingGroupMember {
+                    group: group.clone(),
+                    member: member.clone(),
+                });
+            }
+        }
+
+        if let Err(GroupError::CircularGroup(path)) = groups.expand(vec![format!("@{group}")]) {
+            diagnostics.push(Diagnostic::CircularGroup {
+                group: group.clone(),
+                path,
+            });
+        }
+    }
+
+    Ok(diagnostics)
+}
+
+/// Like [`collect`], limited to `name` and the profiles it depends on
+pub fn collect_profile(
+    config_manager: &mut ConfigManager,
+    name: &str,
+) -> Result<Vec<Diagnostic>, Box<dyn Error>> {
+    if !config_manager.profile_exists(name) {
+        return Err(format!("Profile `{name}` does not exist").into());
+    }
+
+    // Walk the files directly, since loading stops at the first broken profile
+    let mut closure = BTreeSet::new();
+    let mut pending = vec![name.to_string()];
+    while let Some(current) = pending.pop() {
+        if !closure.insert(current.clone()) {
+            continue;
+        }
+        if let Ok(profile) = loader::load_profile_from_file(config_manager.base_path(), &current) {
+            pending.extend(
+                profile
+                    .enabled_profiles()
+                    .filter(|dep| config_manager.profile_exists(dep))
+                    .cloned(),
+            );
+        }
+    }
+
+    Ok(collect_names(config_manager, closure.iter()))
+}
+
+/// Check the profiles the global profile depends on: each must exist and load with
+/// its own dependencies. Every problem comes with the dependency it was reached
+/// through; parse errors in the global file itself are returned as `Err`.
+pub fn collect_global(
+    config_manager: &mut ConfigManager,
+) -> Result<Vec<(String, Diagnostic)>, Box<dyn Error>> {
+    let global = config_manager.read_global()?;
+    let mut diagnostics = Vec::new();
+
+    for dependency in &global.profiles {
+        if !config_manager.profile_exists(dependency) {
+            diagnostics.push((
+                dependency.clone(),
+                Diagnostic::Dependency {
+                    profile: GLOBAL_PROFILE_MARK.to_string(),
+                    error: DependencyError::DependencyNotFound {
+                        parent: "GLOBAL".to_string(),
+                        dependency: dependency.clone(),
+                        source: Some(config_manager.global_path()),
+                    },
+                },
+            ));
+            continue;
+        }
+        diagnostics.extend(
+            collect_profile(config_manager, dependency)?
+                .into_iter()
+                .map(|diagnostic| (dependency.clone(), diagnostic)),
+        );
+    }
+    Ok(diagnostics)
+}
+
+fn collect_names<'a>(
+    config_manager: &mut ConfigManager,
+    names: impl Iterator<Item = &'a String>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let settings = config_manager.read_settings().unwrap_or_default();
+    let global = config_manager.read_global().unwrap_or_default();
+
+    for name in names {
+        if let Err(error) = validate_namespaced_profile_name(name) {
+            diagnostics.push(Diagnostic::InvalidName {
+                profile: name.clone(),
+                error,
+            });
+        }
+
+        // Reported on its own rather than as a cycle, which loading would fail with
+        if let Ok(profile) = loader::load_profile_from_file(config_manager.base_path(), name)
+            && profile.enabled_profiles().any(|dep| dep == name)
+        {
+            diagnostics.push(Diagnostic::SelfDependency {
+                profile: name.clone(),
+            });
+        }
+
+        if let Err(e) = config_manager.load_profile(name) {
+            diagnostics.extend(
+                flatten(e)
+                    .into_iter()
+                    .filter(|error| !is_self_cycle(error))
+                    .map(|error| Diagnostic::Dependency {
+                        profile: name.clone(),
+                        error,
+                    }),
+            );
+        }
+
+        let depth = config_manager.max_depth(name);
+        if depth > settings.max_inheritance_depth() {
+            diagnostics.push(Diagnostic::DeepInheritance {
+                profile: name.clone(),
+                depth,
+                limit: settings.max_inheritance_depth(),
+            });
+        }
+
+        if let Some(profile) = config_manager.get_profile(name) {
+            let hooks = profile.hooks();
+            let trusted = settings.trusts_hooks(name, &hooks);
+            let commands = hooks.len();
+            if commands > 0 {
+                diagnostics.push(Diagnostic::Hooks {
+                    profile: name.clone(),
+                    commands,
+                    trusted,
+                });
+            }
+            if !hooks.computed.is_empty() && !trusted {
+                diagnostics.push(Diagnostic::UntrustedComputed {
+                    profile: name.clone(),
+                    keys: hooks.computed.keys().cloned().collect(),
+                });
+            }
+            if let Ok(merged) = profile.collect_merged(name, config_manager) {
+                let size = EnvSize::of(&merged.vars);
+                if size.exceeds(settings.max_env_size()) {
+                    diagnostics.push(Diagnostic::LargeEnvironment {
+                        profile: name.clone(),
+                        size,
+                        limit: settings.max_env_size(),
+                    });
+                }
+            }
+            let validation = settings.key_validation(profile);
+            if validation == KeyValidation::Strict {
+                for key in profile.variables.keys().chain(&profile.unset) {
+                    if let Err(error) = validate_variable_key_as(key, validation) {
+                        diagnostics.push(Diagnostic::InvalidKey {
+                            profile: name.clone(),
+                            key: key.clone(),
+                            error,
+                        });
+                    }
+                }
+            }
+            for (key, value) in &profile.variables {
+                // A list key extends the global value instead of masking it
+                let (var, op) = VarOp::parse(key);
+                let global_entry = global
+                    .variables
+                    .iter()
+                    .find(|(global_key, _)| VarOp::parse(global_key).0 == var);
+                if op == VarOp::Set
+                    && let Some((global_key, global_value)) = global_entry
+                    && (global_value != value || VarOp::parse(global_key).1 != VarOp::Set)
+                {
+                    diagnostics.push(Diagnostic::ShadowedGlobal {
+                        profile: name.clone(),
+                        key: var.to_string(),
+                        global_value: global_value.clone(),
+                        value: value.clone(),
+                    });
+                }
+                for placeholder in unknown_placeholders(value) {
+                    diagnostics.push(Diagnostic::UnknownPlaceholder {
+                        profile: name.clone(),
+                        key: key.clone(),
+                        placeholder,
+                    });
+                }
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// Split `MultipleErrors` into separate errors, repeating the trace of any
+/// `DependencyChain` around them for each one
+fn flatten(error: DependencyError) -> Vec<DependencyError> {
+    match error {
+        DependencyError::MultipleErrors(errors) => errors.into_iter().flat_map(flatten).collect(),
+        DependencyError::DependencyChain { profile, cause } => flatten(*cause)
+            .into_iter()
+            .map(|cause| DependencyError::DependencyChain {
+                profile: profile.clone(),
+                cause: Box::new(cause),
+            })
+            .collect(),
+        error => vec![error],
+    }
+}
+
+/// Whether `error` comes down to a profile listing itself
+fn is_self_cycle(error: &DependencyError) -> bool {
+    match root_cause(error) {
+        DependencyError::CircularDependency { path, .. } => {
+            path.iter().all(|profile| *profile == path[0])
+        }
+        _ => false,
+    }
+}
+
+fn root_cause(error: &DependencyError) -> &DependencyError {
+    match error {
+        DependencyError::DependencyChain { cause, .. } => root_cause(cause),
+        error => error,
+    }
+}
+
+fn collect_dangling<'a>(error: &'a DependencyError, references: &mut Vec<(&'a str, &'a str)>) {
+    match error {
+        DependencyError::DependencyChain { profile, cause } => {
+            // 'profile' references 'target' which is missing
+            if let DependencyError::ProfileNotFound(target) = &**cause {
+                references.push((profile, target));
+            } else {
+                collect_dangling(cause, references);
+            }
+        }
+        DependencyError::DependencyNotFound {
+            parent, dependency, ..
+        } => references.push((parent, dependency)),
+        DependencyError::MultipleErrors(errors) => {
+            for error in errors {
+                collect_dangling(error, references);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn collect_cycles<'a>(error: &'a DependencyError, cycles: &mut Vec<&'a [String]>) {
+    match error {
+        DependencyError::CircularDependency { path, .. } => cycles.push(path),
+        DependencyError::DependencyChain { cause, .. } => collect_cycles(cause, cycles),
+        DependencyError::MultipleErrors(errors) => {
+            for error in errors {
+                collect_cycles(error, cycles);
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,112 @@
+//! Comparison of the variables of two profiles, shared by `profile diff` and the TUI.
+
+use super::ConfigManager;
+use crate::utils::dotenv;
+use std::collections::BTreeMap;
+use std::error::Error;
+
+/// How the variables of profile `b` differ from those of profile `a`, each part sorted by key
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileDiff {
+    /// `(key, value)` set only by `a`
+    pub only_in_a: Vec<(String, String)>,
+    /// `(key, value)` set only by `b`
+    pub only_in_b: Vec<(String, String)>,
+    /// `(key, value in a, value in b)` set by both to different values
+    pub changed: Vec<(String, String, String)>,
+}
+
+impl ProfileDiff {
+    /// Compare two sets of variables
+    pub fn between(a: &BTreeMap<String, String>, b: &BTreeMap<String, String>) -> Self {
+        let mut diff = ProfileDiff::default();
+        for (key, value) in a {
+            match b.get(key) {
+                None => diff.only_in_a.push((key.clone(), value.clone())),
+                Some(other) if other != value => {
+                    diff.changed
+                        .push((key.clone(), value.clone(), other.clone()))
+                }
+                Some(_) => {}
+            }
+        }
+        for (key, value) in b {
+            if !a.contains_key(key) {
+                diff.only_in_b.push((key.clone(), value.clone()));
+            }
+        }
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.only_in_a.is_empty() && self.only_in_b.is_empty() && self.changed.is_empty()
+    }
+
+    /// A unified-diff-like rendering without colors, for piping: a `---`/`+++` header,
+    /// then `-KEY="value"` and `+KEY="value"` lines in key order
+    pub fn plain(&self, a: &str, b: &str) -> String {
+        let mut lines: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        let line = |sign: char, key: &str, value: &str| {
+            format!("{sign}{}", dotenv::render([(key, value)]))
+        };
+        for (key, value) in &self.only_in_a {
+            lines.entry(key).or_default().push(line('-', key, value));
+        }
+        for (key, value) in &self.only_in_b {
+            lines.entry(key).or_default().push(line('+', key, value));
+        }
+        for (key, old, new) in &self.changed {
+            let entry = lines.entry(key).or_default();
+            entry.push(line('-', key, old));
+            entry.push(line('+', key, new));
+        }
+
+        let mut output = format!("--- {a}\n+++ {b}\n");
+        for line in lines.into_values().flatten() {
+            output.push_str(&line);
+        }
+        output
+    }
+}
+
+/// The variables of profile `name`: as written in its file, or with `expand` the
+/// resolved variables of it and every profile it inherits from
+pub fn profile_variables(
+    config_manager: &mut ConfigManager,
+    name: &str,
+    expand: bool,
+) -> Result<BTreeMap<String, String>, Box<dyn Error>> {
+    if !config_manager.has_profile(name) {
+        if !config_manager.profile_exists(name) {
+            return Err(format!("Profile `{name}` does not exist").into());
+        }
+        config_manager.load_profile(name)?;
+    }
+    let Some(profile) = config_manager.get_profile(name) else {
+        return Err(format!("Profile `{name}` does not exist").into());
+    };
+    Ok(if expand {
+        profile
+            .collect_vars(name, config_manager)?
+            .into_iter()
+            .collect()
+    } else {
+        profile
+            .variables
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    })
+}
+
+/// Compare the variables of profiles `a` and `b`, see [`profile_variables`]
+pub fn compare(
+    config_manager: &mut ConfigManager,
+    a: &str,
+    b: &str,
+    expand: bool,
+) -> Result<ProfileDiff, Box<dyn Error>> {
+    let a = profile_variables(config_manager, a, expand)?;
+    let b = profile_variables(config_manager, b, expand)?;
+    Ok(ProfileDiff::between(&a, &b))
+}
@@ -0,0 +1,96 @@
+//! How much room resolved variables take in the environment.
+//!
+//! The environment handed to a new process is limited by the system; once a
+//! profile chain resolves to more than that, every command started from the shell
+//! fails with `E2BIG` ("Argument list too long") or `ENOMEM`.
+
+use super::models::ProvenancedValue;
+
+/// How many of the largest variables a size warning names
+pub const LARGEST_SHOWN: usize = 5;
+
+/// Bytes `key=value` takes in the environment block, with the `=` and the terminating NUL
+pub fn var_size(key: &str, value: &str) -> usize {
+    key.len() + value.len() + 2
+}
+
+/// A variable and the profiles its value came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LargeVariable {
+    pub key: String,
+    pub size: usize,
+    pub sources: Vec<String>,
+}
+
+/// The total size of a set of resolved variables and the largest of them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnvSize {
+    pub total: usize,
+    /// At most [`LARGEST_SHOWN`] variables, largest first
+    pub largest: Vec<LargeVariable>,
+}
+
+impl EnvSize {
+    pub fn of<'a>(vars: impl IntoIterator<Item = (&'a String, &'a ProvenancedValue)>) -> Self {
+        let mut all: Vec<LargeVariable> = vars
+            .into_iter()
+            .map(|(key, var)| LargeVariable {
+                key: key.clone(),
+                size: var_size(key, &var.value),
+                sources: var.sources.clone(),
+            })
+            .collect();
+        let total = all.iter().map(|var| var.size).sum();
+        all.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.key.cmp(&b.key)));
+        all.truncate(LARGEST_SHOWN);
+        EnvSize {
+            total,
+            largest: all,
+        }
+    }
+
+    /// Whether the variables take more than `limit` bytes; exactly `limit` is fine
+    pub fn exceeds(&self, limit: usize) -> bool {
+        self.total > limit
+    }
+
+    /// The finding shared by activation, `check` and the expand pane, for variables
+    /// over `limit`
+    pub fn warning(&self, limit: usize) -> String {
+        format!(
+            "{} of variables, more than the limit of {}; largest: {}",
+            format_size(self.total),
+            format_size(limit),
+            self.describe_largest()
+        )
+    }
+
+    /// The largest variables as `KEY (12.0 KB, from web)`, separated by commas
+    pub fn describe_largest(&self) -> String {
+        self.largest
+            .iter()
+            .map(|var| {
+                let size = format_size(var.size);
+                if var.sources.is_empty() {
+                    format!("{} ({size})", var.key)
+                } else {
+                    format!("{} ({size}, from {})", var.key, var.sources.join(", "))
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// A size in bytes as a short human-readable string: `512 B`, `12.3 KB`, `1.5 MB`
+pub fn format_size(bytes: usize) -> String {
+    const KB: f64 = 1024.0;
+    let bytes_f = bytes as f64;
+    if bytes_f < KB {
+        format!("{bytes} B")
+    } else if bytes_f < KB * KB {
+        format!("{:.1} KB", bytes_f / KB)
+    } else {
+        format!("{:.1} MB", bytes_f / (KB * KB))
+    }
+}
@@ -1,13 +1,32 @@
 use daggy::{Dag, NodeIndex, Walker};
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
+use crate::GLOBAL_PROFILE_MARK;
 use crate::config::models::Profile;
+use crate::config::schema::SchemaError;
 
 #[derive(Debug)]
 pub enum DependencyError {
-    CircularDependency(Vec<String>),
-    /// Profile references a non-existent dependency: (parent_profile, missing_dependency)
-    DependencyNotFound(String, String),
+    /// `path` walks the cycle back to its start; `sources[i]` is the file declaring the
+    /// edge `path[i] -> path[i + 1]` (empty when the graph was built without files)
+    CircularDependency {
+        path: Vec<String>,
+        sources: Vec<PathBuf>,
+    },
+    /// Profile references a non-existent dependency, along with the file declaring it
+    DependencyNotFound {
+        parent: String,
+        dependency: String,
+        source: Option<PathBuf>,
+    },
+    /// A regular profile lists the global profile as a dependency, along with the file declaring it
+    GlobalDependency {
+        parent: String,
+        source: Option<PathBuf>,
+    },
     /// Profile itself does not exist
     ProfileNotFound(String),
     /// Context wrapper for dependency errors
@@ -21,6 +40,8 @@ pub enum DependencyError {
     ProfileIoError(String, std::io::Error),
     /// Parse error during profile loading: (profile, error)
     ProfileParseError(String, toml::de::Error),
+    /// A profile field has the wrong type: (profile, error)
+    ProfileInvalid(String, SchemaError),
 }
 
 impl std::fmt::Display for DependencyError {
@@ -59,14 +80,45 @@ impl std::fmt::Display for DependencyError {
         }
 
         match root_cause {
-            DependencyError::CircularDependency(path) => {
-                write!(f, "Circular dependency detected: {}", path.join(" -> "))
+            DependencyError::CircularDependency { path, sources } => {
+                if sources.is_empty() {
+                    return write!(f, "Circular dependency detected: {}", path.join(" -> "));
+                }
+                write!(f, "Circular dependency detected:")?;
+                for (hop, source) in path.windows(2).zip(sources) {
+                    write!(
+                        f,
+                        "\n  {} -> {} (declared in {})",
+                        hop[0],
+                        hop[1],
+                        display_path(source)
+                    )?;
+                }
+                Ok(())
+            }
+            DependencyError::DependencyNotFound {
+                parent,
+                dependency,
+                source,
+            } => {
+                write!(
+                    f,
+                    "Profile '{parent}' references non-existent profile '{dependency}'"
+                )?;
+                match source {
+                    Some(source) => write!(f, " (declared in {}).", display_path(source)),
+                    None => write!(f, "."),
+                }
             }
-            DependencyError::DependencyNotFound(parent, dep) => {
+            DependencyError::GlobalDependency { parent, source } => {
                 write!(
                     f,
-                    "Profile '{parent}' references non-existent profile '{dep}'."
-                )
+                    "Profile '{parent}' cannot depend on the global profile '{GLOBAL_PROFILE_MARK}'"
+                )?;
+                match source {
+                    Some(source) => write!(f, " (declared in {}).", display_path(source)),
+                    None => write!(f, "."),
+                }
             }
             DependencyError::ProfileNotFound(profile) => {
                 write!(f, "Profile '{profile}' not found.")
@@ -77,6 +129,9 @@ impl std::fmt::Display for DependencyError {
             DependencyError::ProfileParseError(profile, err) => {
                 write!(f, "Failed to parse profile '{profile}': {err}")
             }
+            DependencyError::ProfileInvalid(profile, err) => {
+                write!(f, "Invalid profile '{profile}': {err}")
+            }
             DependencyError::DependencyChain { .. } => unreachable!(),
             DependencyError::MultipleErrors(errors) => {
                 // This can happen if MultipleErrors is nested inside DependencyChain
@@ -95,9 +150,24 @@ impl std::fmt::Display for DependencyError {
 
 impl std::error::Error for DependencyError {}
 
+/// Shorten paths under the home directory to `~/...` for messages
+pub fn display_path(path: &Path) -> String {
+    if let Some(home) = dirs::home_dir()
+        && let Ok(relative) = path.strip_prefix(&home)
+    {
+        return Path::new("~").join(relative).display().to_string();
+    }
+    path.display().to_string()
+}
+
+#[derive(Clone)]
 pub struct ProfileGraph {
     graph: Dag<String, ()>,
     profile_nodes: HashMap<String, NodeIndex>,
+    /// Directory holding the profile files, used to point errors at the declaring file
+    profiles_dir: Option<PathBuf>,
+    /// Resolution order of each profile resolved since the graph last changed
+    resolved: RefCell<HashMap<NodeIndex, Rc<[NodeIndex]>>>,
 }
 
 impl Default for ProfileGraph {
@@ -105,6 +175,8 @@ impl Default for ProfileGraph {
         Self {
             graph: Dag::new(),
             profile_nodes: HashMap::new(),
+            profiles_dir: None,
+            resolved: RefCell::default(),
         }
     }
 }
@@ -114,8 +186,59 @@ impl ProfileGraph {
         Self::default()
     }
 
-    pub fn build(profiles: &HashMap<String, Profile>) -> Result<Self, DependencyError> {
-        let mut profile_graph = Self::new();
+    pub fn with_profiles_dir(profiles_dir: PathBuf) -> Self {
+        Self {
+            profiles_dir: Some(profiles_dir),
+            ..Self::default()
+        }
+    }
+
+    pub fn profiles_dir(&self) -> Option<&Path> {
+        self.profiles_dir.as_deref()
+    }
+
+    /// The file declaring the dependencies of `profile`, if the graph is backed by files
+    fn declared_in(&self, profile: &str) -> Option<PathBuf> {
+        self.profiles_dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{profile}.toml")))
+    }
+
+    fn circular_dependency(&self, path: Vec<String>) -> DependencyError {
+        let sources = if self.profiles_dir.is_some() {
+            path[..path.len().saturating_sub(1)]
+                .iter()
+                .filter_map(|profile| self.declared_in(profile))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        DependencyError::CircularDependency { path, sources }
+    }
+
+    pub fn dependency_not_found(&self, parent: &str, dependency: &str) -> DependencyError {
+        DependencyError::DependencyNotFound {
+            parent: parent.to_string(),
+            dependency: dependency.to_string(),
+            source: self.declared_in(parent),
+        }
+    }
+
+    pub fn global_dependency(&self, parent: &str) -> DependencyError {
+        DependencyError::GlobalDependency {
+            parent: parent.to_string(),
+            source: self.declared_in(parent),
+        }
+    }
+
+    pub fn build(
+        profiles: &HashMap<String, Profile>,
+        profiles_dir: Option<PathBuf>,
+    ) -> Result<Self, DependencyError> {
+        let mut profile_graph = Self {
+            profiles_dir,
+            ..Self::default()
+        };
 
         for name in profiles.keys() {
             let index = profile_graph.graph.add_node(name.clone());
@@ -124,10 +247,14 @@ impl ProfileGraph {
 
         for (name, profile) in profiles {
             let parent_index = profile_graph.profile_nodes[name];
-            for dep_name in &profile.profiles {
-                let &dep_index = profile_graph.profile_nodes.get(dep_name).ok_or_else(|| {
-                    DependencyError::DependencyNotFound(name.clone(), dep_name.clone())
-                })?;
+            for dep_name in profile.enabled_profiles() {
+                if dep_name == GLOBAL_PROFILE_MARK {
+                    return Err(profile_graph.global_dependency(name));
+                }
+                let &dep_index = profile_graph
+                    .profile_nodes
+                    .get(dep_name)
+                    .ok_or_else(|| profile_graph.dependency_not_found(name, dep_name))?;
 
                 if profile_graph
                     .graph
@@ -143,7 +270,7 @@ impl ProfileGraph {
                     // Prepend `name` to show the full cycle: name -> dep_name -> ... -> name
                     path.insert(0, name.clone());
 
-                    return Err(DependencyError::CircularDependency(path));
+                    return Err(profile_graph.circular_dependency(path));
                 }
             }
         }
@@ -152,53 +279,103 @@ impl ProfileGraph {
     }
 
     pub fn resolve_dependencies(&self, profile_name: &str) -> Result<Vec<String>, DependencyError> {
-        let mut resolved = HashSet::new();
-        let mut visiting = Vec::new();
-        let mut result = Vec::new();
-
-        self.dfs_resolve(profile_name, &mut visiting, &mut resolved, &mut result)?;
+        self.resolve_many(&[profile_name])
+    }
 
-        Ok(result)
+    /// The dependencies of every root followed by the root itself, roots in the given
+    /// order and each profile listed once, at its first position
+    pub fn resolve_many<S: AsRef<str>>(&self, roots: &[S]) -> Result<Vec<String>, DependencyError> {
+        let roots = roots
+            .iter()
+            .map(|root| {
+                let root = root.as_ref();
+                self.profile_nodes
+                    .get(root)
+                    .copied()
+                    .ok_or_else(|| DependencyError::ProfileNotFound(root.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let order = match roots.as_slice() {
+            [root] => self.resolution_order(*root).to_vec(),
+            roots => {
+                // One walk for all roots, so shared dependencies are visited once
+                let mut resolved = HashSet::new();
+                let mut order = Vec::new();
+                for &root in roots {
+                    self.walk(root, &mut resolved, &mut order);
+                }
+                order
+            }
+        };
+        Ok(order
+            .into_iter()
+            .map(|index| self.graph[index].clone())
+            .collect())
     }
 
-    fn dfs_resolve<'a>(
-        &'a self,
-        profile_name: &'a str,
-        visiting: &mut Vec<&'a str>,
-        resolved: &mut HashSet<&'a str>,
-        result: &mut Vec<String>,
-    ) -> Result<(), DependencyError> {
-        visiting.push(profile_name);
+    /// Every profile in the graph, each listed after all the profiles it depends on.
+    ///
+    /// Profiles are visited by name, so unrelated profiles keep alphabetical order.
+    pub fn topological_order(&self) -> Result<Vec<String>, DependencyError> {
+        let mut names: Vec<&String> = self.profile_nodes.keys().collect();
+        names.sort();
+        self.resolve_many(&names)
+    }
 
-        if let Some(&node_index) = self.profile_nodes.get(profile_name) {
-            for (_, child_index) in self.graph.children(node_index).iter(&self.graph) {
-                let dep_name: &String = &self.graph[child_index];
+    /// The resolution order of `node_index`, cached until the graph changes
+    fn resolution_order(&self, node_index: NodeIndex) -> Rc<[NodeIndex]> {
+        if let Some(order) = self.resolved.borrow().get(&node_index) {
+            return Rc::clone(order);
+        }
+        let mut order = Vec::new();
+        self.walk(node_index, &mut HashSet::new(), &mut order);
+        let order: Rc<[NodeIndex]> = order.into();
+        self.resolved
+            .borrow_mut()
+            .insert(node_index, Rc::clone(&order));
+        order
+    }
 
-                if resolved.contains(dep_name.as_str()) {
-                    continue;
-                }
+    /// Depth-first walk appending `node_index` after its dependencies, skipping the
+    /// profiles in `resolved`. Children are visited by name so the order does not
+    /// depend on load order.
+    ///
+    /// Walking a profile lists its cached order minus the profiles already resolved,
+    /// since everything a resolved profile depends on is resolved too, so cached
+    /// orders are reused wherever the walk reaches them.
+    fn walk(
+        &self,
+        node_index: NodeIndex,
+        resolved: &mut HashSet<NodeIndex>,
+        order: &mut Vec<NodeIndex>,
+    ) {
+        if let Some(cached) = self.resolved.borrow().get(&node_index) {
+            order.extend(cached.iter().filter(|&&index| resolved.insert(index)));
+            return;
+        }
 
-                if let Some(pos) = visiting.iter().position(|p| p == &dep_name.as_str()) {
-                    let mut cycle_path = visiting[pos..]
-                        .iter()
-                        .map(|s| s.to_string())
-                        .collect::<Vec<_>>();
-                    cycle_path.push(dep_name.clone());
-                    return Err(DependencyError::CircularDependency(cycle_path));
-                }
-                self.dfs_resolve(dep_name, visiting, resolved, result)?;
+        let mut children: Vec<NodeIndex> = self
+            .graph
+            .children(node_index)
+            .iter(&self.graph)
+            .map(|(_, child_index)| child_index)
+            .collect();
+        children.sort_by(|a, b| self.graph[*a].cmp(&self.graph[*b]));
+
+        for child_index in children {
+            if !resolved.contains(&child_index) {
+                self.walk(child_index, resolved, order);
             }
-        } else {
-            // This shouldn't happen if the graph was built correctly, but just in case
-            return Err(DependencyError::ProfileNotFound(profile_name.to_string()));
         }
-
-        visiting.pop();
-        if resolved.insert(profile_name) {
-            result.push(profile_name.to_string());
+        if resolved.insert(node_index) {
+            order.push(node_index);
         }
+    }
 
-        Ok(())
+    /// Forget the cached resolution orders, after any change to the graph
+    fn invalidate(&mut self) {
+        self.resolved.get_mut().clear();
     }
 
     pub fn find_path(&self, start_node: &str, end_node: &str) -> Option<Vec<String>> {
@@ -265,54 +442,150 @@ impl ProfileGraph {
         }
     }
 
+    /// The most dependency edges on a path starting at `name`: 0 for a profile
+    /// without dependencies, and for a profile not in the graph
+    pub fn max_depth(&self, name: &str) -> usize {
+        match self.profile_nodes.get(name) {
+            Some(&index) => self.depth(index, &mut HashMap::new()),
+            None => 0,
+        }
+    }
+
+    /// The most dependency edges on any path through the graph, see [`Self::max_depth`]
+    pub fn max_depth_overall(&self) -> usize {
+        let mut depths = HashMap::new();
+        self.profile_nodes
+            .values()
+            .map(|&index| self.depth(index, &mut depths))
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn depth(&self, node_index: NodeIndex, depths: &mut HashMap<NodeIndex, usize>) -> usize {
+        if let Some(&depth) = depths.get(&node_index) {
+            return depth;
+        }
+        let children: Vec<NodeIndex> = self
+            .graph
+            .children(node_index)
+            .iter(&self.graph)
+            .map(|(_, child_index)| child_index)
+            .collect();
+        let depth = children
+            .into_iter()
+            .map(|child_index| self.depth(child_index, depths) + 1)
+            .max()
+            .unwrap_or(0);
+        depths.insert(node_index, depth);
+        depth
+    }
+
+    /// The profiles no other profile depends on, sorted by name
+    pub fn profiles_without_dependents(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .profile_nodes
+            .iter()
+            .filter(|&(_, &index)| self.graph.parents(index).walk_next(&self.graph).is_none())
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// Every profile that depends on the given one, directly or through other profiles, sorted by name
+    pub fn get_all_dependents(&self, profile_name: &str) -> Option<Vec<String>> {
+        let &start = self.profile_nodes.get(profile_name)?;
+        let mut seen = HashSet::new();
+        let mut stack = vec![start];
+        while let Some(node_index) = stack.pop() {
+            for (_, parent_index) in self.graph.parents(node_index).iter(&self.graph) {
+                if parent_index != start && seen.insert(parent_index) {
+                    stack.push(parent_index);
+                }
+            }
+        }
+        let mut dependents: Vec<String> = seen.into_iter().map(|i| self.graph[i].clone()).collect();
+        dependents.sort();
+        Some(dependents)
+    }
+
+    /// Check that adding profile `name`, depended on by `dependents` and depending on
+    /// `dependencies`, keeps the graph acyclic. The edges are tried on a copy, so this
+    /// graph is left untouched either way.
+    pub fn check_new_profile(
+        &self,
+        name: &str,
+        dependencies: &[String],
+        dependents: &[String],
+    ) -> Result<(), DependencyError> {
+        let mut graph = self.clone();
+        graph.add_node(name.to_string());
+        for dependent in dependents {
+            graph.add_dependency(dependent, name)?;
+        }
+        for dependency in dependencies {
+            graph.add_dependency(name, dependency)?;
+        }
+        Ok(())
+    }
+
     /// Add a dependency edge from parent to child
     pub fn add_dependency(&mut self, parent: &str, child: &str) -> Result<(), DependencyError> {
+        if child == GLOBAL_PROFILE_MARK {
+            return Err(self.global_dependency(parent));
+        }
         let &parent_index = self
             .profile_nodes
             .get(parent)
             .ok_or_else(|| DependencyError::ProfileNotFound(parent.to_string()))?;
 
-        let &child_index = self.profile_nodes.get(child).ok_or_else(|| {
-            DependencyError::DependencyNotFound(parent.to_string(), child.to_string())
-        })?;
+        let &child_index = self
+            .profile_nodes
+            .get(child)
+            .ok_or_else(|| self.dependency_not_found(parent, child))?;
 
         // Try to add the edge
+        self.invalidate();
         if self.graph.add_edge(parent_index, child_index, ()).is_err() {
             // Would create a cycle
             let mut path = self
                 .find_path(child, parent)
                 .unwrap_or_else(|| vec![child.to_string(), parent.to_string()]);
             path.insert(0, parent.to_string());
-            return Err(DependencyError::CircularDependency(path));
+            return Err(self.circular_dependency(path));
         }
 
         Ok(())
     }
 
-    /// Remove a dependency edge from parent to child
-    pub fn remove_dependency(&mut self, parent: &str, child: &str) -> Result<(), DependencyError> {
+    /// Remove the dependency edge from parent to child, returning whether there was one
+    pub fn remove_dependency(
+        &mut self,
+        parent: &str,
+        child: &str,
+    ) -> Result<bool, DependencyError> {
         let &parent_index = self
             .profile_nodes
             .get(parent)
             .ok_or_else(|| DependencyError::ProfileNotFound(parent.to_string()))?;
 
-        let &child_index = self.profile_nodes.get(child).ok_or_else(|| {
-            DependencyError::DependencyNotFound(parent.to_string(), child.to_string())
-        })?;
-
-        // Find and remove the edge
-        if let Some(edge_index) = self.graph.find_edge(parent_index, child_index) {
-            self.graph.remove_edge(edge_index);
-            Ok(())
-        } else {
-            // Edge doesn't exist, but that's okay
-            Ok(())
-        }
+        let &child_index = self
+            .profile_nodes
+            .get(child)
+            .ok_or_else(|| self.dependency_not_found(parent, child))?;
+
+        let Some(edge_index) = self.graph.find_edge(parent_index, child_index) else {
+            return Ok(false);
+        };
+        self.invalidate();
+        self.graph.remove_edge(edge_index);
+        Ok(true)
     }
 
     /// Add a new profile node to the graph
     pub fn add_node(&mut self, name: String) {
         if !self.profile_nodes.contains_key(&name) {
+            self.invalidate();
             let index = self.graph.add_node(name.clone());
             self.profile_nodes.insert(name, index);
         }
@@ -322,8 +595,13 @@ impl ProfileGraph {
     /// Note: This will also remove all edges connected to this node
     pub fn remove_node(&mut self, name: &str) -> Result<(), DependencyError> {
         if let Some(&node_index) = self.profile_nodes.get(name) {
+            self.invalidate();
             self.graph.remove_node(node_index);
             self.profile_nodes.remove(name);
+            // The last node is moved into the freed index
+            if let Some(moved) = self.graph.node_weight(node_index) {
+                self.profile_nodes.insert(moved.clone(), node_index);
+            }
             Ok(())
         } else {
             Err(DependencyError::ProfileNotFound(name.to_string()))
@@ -337,7 +615,8 @@ impl ProfileGraph {
             .get(old_name)
             .ok_or_else(|| DependencyError::ProfileNotFound(old_name.to_string()))?;
 
-        // Update the node's data
+        // Children are visited by name, so orders through this node may change
+        self.invalidate();
         self.graph[node_index] = new_name.clone();
 
         // Update the profile_nodes map
@@ -346,4 +625,112 @@ impl ProfileGraph {
 
         Ok(())
     }
+
+    /// Every profile with its direct dependencies, both sorted by name
+    fn adjacency(&self) -> Vec<(&str, Vec<&str>)> {
+        let mut nodes: Vec<(&str, Vec<&str>)> = self
+            .profile_nodes
+            .iter()
+            .map(|(name, &index)| {
+                let mut deps: Vec<&str> = self
+                    .graph
+                    .children(index)
+                    .iter(&self.graph)
+                    .map(|(_, child)| self.graph[child].as_str())
+                    .collect();
+                deps.sort();
+                deps.dedup();
+                (name.as_str(), deps)
+            })
+            .collect();
+        nodes.sort();
+        nodes
+    }
+
+    /// The graph in Graphviz DOT syntax, with an edge from each profile to its dependencies
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph profiles {\n");
+        for (name, deps) in self.adjacency() {
+            if deps.is_empty() {
+                out.push_str(&format!("    {};\n", dot_id(name)));
+            }
+            for dep in deps {
+                out.push_str(&format!("    {} -> {};\n", dot_id(name), dot_id(dep)));
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// The graph as a Mermaid flowchart, with an edge from each profile to its dependencies.
+    ///
+    /// Node ids keep only ASCII letters, digits and `_`, so names such as `work/api`
+    /// get a sanitized id and are shown through a quoted label.
+    pub fn to_mermaid(&self) -> String {
+        let nodes = self.adjacency();
+        let is_keyword = |id: &str| MERMAID_KEYWORDS.contains(&id.to_ascii_lowercase().as_str());
+        // Names that are already valid ids keep them; the others are sanitized around those
+        let (mut ids, mut taken): (HashMap<&str, String>, HashSet<String>) = nodes
+            .iter()
+            .filter(|(name, _)| {
+                name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') && !is_keyword(name)
+            })
+            .map(|(name, _)| ((*name, name.to_string()), name.to_string()))
+            .unzip();
+        for (name, _) in &nodes {
+            if ids.contains_key(name) {
+                continue;
+            }
+            let base: String = name
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect();
+            let mut id = base.clone();
+            let mut n = 1;
+            while is_keyword(&id) || !taken.insert(id.clone()) {
+                n += 1;
+                id = format!("{base}_{n}");
+            }
+            ids.insert(name, id);
+        }
+
+        let node = |name: &str| {
+            let id = &ids[name];
+            if id == name {
+                id.clone()
+            } else {
+                format!("{id}[\"{}\"]", name.replace('"', "#quot;"))
+            }
+        };
+        let mut out = String::from("graph TD\n");
+        for (name, deps) in &nodes {
+            if deps.is_empty() {
+                out.push_str(&format!("    {}\n", node(name)));
+            }
+            for dep in deps {
+                out.push_str(&format!("    {} --> {}\n", node(name), node(dep)));
+            }
+        }
+        out
+    }
+}
+
+/// Words Mermaid reads as flowchart syntax, which cannot be used as node ids
+const MERMAID_KEYWORDS: &[&str] = &[
+    "end",
+    "graph",
+    "flowchart",
+    "subgraph",
+    "style",
+    "class",
+    "classdef",
+    "click",
+    "linkstyle",
+    "direction",
+    "default",
+];
+
+/// Quote `name` as a DOT identifier
+fn dot_id(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
 }
@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+/// Prefix marking a group reference, both on the command line and inside groups
+pub const GROUP_PREFIX: char = '@';
+
+/// Named lists of profiles read from `groups.toml`.
+///
+/// Members are profile names or other groups written as `@name`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Groups(pub BTreeMap<String, Vec<String>>);
+
+#[derive(Debug)]
+pub enum GroupError {
+    GroupNotFound(String),
+    /// `path` walks the cycle back to its start
+    CircularGroup(Vec<String>),
+}
+
+impl fmt::Display for GroupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GroupError::GroupNotFound(name) => write!(f, "Group `{name}` does not exist"),
+            GroupError::CircularGroup(path) => {
+                let path: Vec<String> = path.iter().map(|name| format!("@{name}")).collect();
+                write!(
+                    f,
+                    "Circular group reference detected: {}",
+                    path.join(" -> ")
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for GroupError {}
+
+/// The group name referenced by `item`, if it is an `@name` reference
+pub fn group_reference(item: &str) -> Option<&str> {
+    item.strip_prefix(GROUP_PREFIX)
+        .filter(|_| !item.contains('='))
+}
+
+impl Groups {
+    pub fn get(&self, name: &str) -> Option<&Vec<String>> {
+        self.0.get(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    /// Replace every `@group` in `items` by the profiles it contains, in place.
+    ///
+    /// Other items are kept as they are; a profile reached more than once is only
+    /// kept at its first position.
+    pub fn expand(&self, items: Vec<String>) -> Result<Vec<String>, GroupError> {
+        let mut expanded = Vec::new();
+        for item in items {
+            match group_reference(&item) {
+                Some(name) => self.expand_group(name, &mut Vec::new(), &mut expanded)?,
+                None => expanded.push(item),
+            }
+        }
+
+        let mut seen = HashSet::new();
+        expanded.retain(|item| seen.insert(item.clone()));
+        Ok(expanded)
+    }
+
+    fn expand_group(
+        &self,
+        name: &str,
+        visiting: &mut Vec<String>,
+        expanded: &mut Vec<String>,
+    ) -> Result<(), GroupError> {
+        if let Some(start) = visiting.iter().position(|visited| visited == name) {
+            let mut path = visiting[start..].to_vec();
+            path.push(name.to_string());
+            return Err(GroupError::CircularGroup(path));
+        }
+        let members = self
+            .get(name)
+            .ok_or_else(|| GroupError::GroupNotFound(name.to_string()))?;
+
+        visiting.push(name.to_string());
+        for member in members {
+            match group_reference(member) {
+                Some(group) => self.expand_group(group, visiting, expanded)?,
+                None => expanded.push(member.clone()),
+            }
+        }
+        visiting.pop();
+        Ok(())
+    }
+}
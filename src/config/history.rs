@@ -0,0 +1,106 @@
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const HISTORY_FILE_NAME: &str = "history.log";
+
+/// Where a change was made from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Cli,
+    Tui,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Cli => write!(f, "cli"),
+            Source::Tui => write!(f, "tui"),
+        }
+    }
+}
+
+/// A change to a profile file, as recorded in the history log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operation<'a> {
+    Create(&'a str),
+    Edit(&'a str),
+    Rename { from: &'a str, to: &'a str },
+    Delete(&'a str),
+}
+
+impl fmt::Display for Operation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operation::Create(name) => write!(f, "create {name}"),
+            Operation::Edit(name) => write!(f, "edit {name}"),
+            Operation::Rename { from, to } => write!(f, "rename {from} -> {to}"),
+            Operation::Delete(name) => write!(f, "delete {name}"),
+        }
+    }
+}
+
+pub fn history_path(base_path: &Path) -> PathBuf {
+    base_path.join(HISTORY_FILE_NAME)
+}
+
+/// Append `operation` to the history log.
+///
+/// The log is best-effort: failures are ignored so they never block the change itself.
+pub fn record(base_path: &Path, source: Source, operation: &Operation) {
+    let line = format!("{} {source} {operation}\n", timestamp(SystemTime::now()));
+    let _ = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history_path(base_path))
+        .and_then(|mut file| file.write_all(line.as_bytes()));
+}
+
+/// The last `limit` lines of the history log, oldest first; empty when there is no log yet
+pub fn read_recent(base_path: &Path, limit: usize) -> io::Result<Vec<String>> {
+    let content = match fs::read_to_string(history_path(base_path)) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let lines: Vec<String> = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(str::to_string)
+        .collect();
+    let skip = lines.len().saturating_sub(limit);
+    Ok(lines.into_iter().skip(skip).collect())
+}
+
+/// Format `time` as an ISO 8601 UTC timestamp, e.g. `2024-05-01T12:30:00Z`
+pub fn timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    let (year, month, day) = civil_from_days(days as i64);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// Convert days since 1970-01-01 into a (year, month, day) date
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // Howard Hinnant's algorithm, eras of 400 years starting on March 1st
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
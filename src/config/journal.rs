@@ -0,0 +1,258 @@
+use super::loader;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const JOURNAL_DIR_NAME: &str = "journal";
+
+/// How long finished entries are kept before a later operation removes them
+pub const JOURNAL_RETENTION_DAYS: u64 = 7;
+
+pub fn journal_dir(base_path: &Path) -> PathBuf {
+    base_path.join(JOURNAL_DIR_NAME)
+}
+
+/// A change to one file, as part of a journaled operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileChange {
+    /// Relative to the config directory
+    pub path: String,
+    /// The content before the operation, absent when the file did not exist
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+    /// The content the operation writes, absent when it deletes the file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+    /// Hash of `after`, telling which writes already happened
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after_hash: Option<String>,
+}
+
+impl FileChange {
+    /// Record that `path` will hold `after`, or be deleted when `None`, capturing its
+    /// current content to roll back to
+    pub fn capture(base_path: &Path, path: &Path, after: Option<String>) -> io::Result<Self> {
+        let relative = path.strip_prefix(base_path).unwrap_or(path);
+        Ok(Self {
+            path: relative.to_string_lossy().into_owned(),
+            before: read_optional(path)?,
+            after_hash: after.as_deref().map(content_hash),
+            after,
+        })
+    }
+
+    /// Whether the file already is as the operation leaves it
+    fn is_applied(&self, base_path: &Path) -> io::Result<bool> {
+        let current = read_optional(&base_path.join(&self.path))?;
+        Ok(current.as_deref().map(content_hash) == self.after_hash)
+    }
+
+    fn set(&self, base_path: &Path, content: Option<&str>) -> io::Result<()> {
+        let path = base_path.join(&self.path);
+        match content {
+            Some(content) => loader::write_atomic(&path, content),
+            None => match fs::remove_file(&path) {
+                Ok(()) => {
+                    loader::remove_empty_namespaces(base_path, path.parent());
+                    Ok(())
+                }
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+                Err(e) => Err(e),
+            },
+        }
+    }
+}
+
+/// The record of an operation that changes several files, written before the first of
+/// them so an interrupted operation can be finished or undone by `fix`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// What the operation does, e.g. `rename api -> web`
+    pub operation: String,
+    /// When it started, in seconds since the Unix epoch
+    pub started: u64,
+    #[serde(default)]
+    pub complete: bool,
+    #[serde(default)]
+    pub files: Vec<FileChange>,
+}
+
+impl Entry {
+    /// How many of the files already are as the operation leaves them
+    pub fn applied(&self, base_path: &Path) -> io::Result<usize> {
+        let mut applied = 0;
+        for change in &self.files {
+            if change.is_applied(base_path)? {
+                applied += 1;
+            }
+        }
+        Ok(applied)
+    }
+
+    /// Make the writes that did not happen yet
+    pub fn roll_forward(&self, base_path: &Path) -> io::Result<()> {
+        for change in &self.files {
+            if !change.is_applied(base_path)? {
+                change.set(base_path, change.after.as_deref())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Put every file back as it was before the operation
+    pub fn roll_back(&self, base_path: &Path) -> io::Result<()> {
+        // Reversed, so a file changed twice ends up with its first pre-image
+        for change in self.files.iter().rev() {
+            if read_optional(&base_path.join(&change.path))? != change.before {
+                change.set(base_path, change.before.as_deref())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A journaled operation in progress
+#[derive(Debug)]
+pub struct Journal {
+    base_path: PathBuf,
+    path: PathBuf,
+    entry: Entry,
+}
+
+impl Journal {
+    /// Write down `operation` and its `files` before any of them changes.
+    ///
+    /// The entry is synced to disk, directory included, before this returns. Finished
+    /// entries older than [`JOURNAL_RETENTION_DAYS`] are removed on the way.
+    pub fn begin(base_path: &Path, operation: &str, files: Vec<FileChange>) -> io::Result<Self> {
+        let dir = journal_dir(base_path);
+        fs::create_dir_all(&dir)?;
+        collect_garbage(base_path, JOURNAL_RETENTION_DAYS * 86_400);
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let path = dir.join(format!("{}-{}.toml", now.as_nanos(), std::process::id()));
+        let journal = Self {
+            base_path: base_path.to_path_buf(),
+            path,
+            entry: Entry {
+                operation: operation.to_string(),
+                started: now.as_secs(),
+                complete: false,
+                files,
+            },
+        };
+        journal.write()?;
+        Ok(journal)
+    }
+
+    /// Mark the operation finished, once every file it changes is synced to disk
+    pub fn complete(mut self) -> io::Result<()> {
+        for change in &self.entry.files {
+            if let Some(parent) = self.base_path.join(&change.path).parent() {
+                sync_dir(parent);
+            }
+        }
+        self.entry.complete = true;
+        self.write()
+    }
+
+    /// Drop the entry of an operation that failed before changing any file
+    pub fn discard(self) {
+        let _ = fs::remove_file(&self.path);
+    }
+
+    fn write(&self) -> io::Result<()> {
+        write_entry(&self.path, &self.entry)
+    }
+}
+
+fn write_entry(path: &Path, entry: &Entry) -> io::Result<()> {
+    let content = toml::to_string_pretty(entry).map_err(io::Error::other)?;
+    // Entries hold whole profiles, which may contain secrets
+    loader::write_private(path, &content)?;
+    if let Some(parent) = path.parent() {
+        sync_dir(parent);
+    }
+    Ok(())
+}
+
+/// The journal entries of operations that did not finish, oldest first. Files that
+/// cannot be read as entries are skipped.
+pub fn incomplete(base_path: &Path) -> Vec<(PathBuf, Entry)> {
+    let mut entries: Vec<(PathBuf, Entry)> = read_entries(base_path)
+        .into_iter()
+        .filter(|(_, entry)| !entry.complete)
+        .collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    entries
+}
+
+/// Mark the entry at `path` finished after `fix` recovered its operation
+pub fn resolve(path: &Path, entry: &Entry) -> io::Result<()> {
+    let mut entry = entry.clone();
+    entry.complete = true;
+    write_entry(path, &entry)
+}
+
+/// Remove finished entries that started more than `max_age_secs` ago
+pub fn collect_garbage(base_path: &Path, max_age_secs: u64) {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    for (path, entry) in read_entries(base_path) {
+        if entry.complete && now.saturating_sub(entry.started) > max_age_secs {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+fn read_entries(base_path: &Path) -> Vec<(PathBuf, Entry)> {
+    let Ok(dir) = fs::read_dir(journal_dir(base_path)) else {
+        return Vec::new();
+    };
+    dir.filter_map(|item| item.ok().map(|item| item.path()))
+        // Temporary files of an unfinished write start with a dot
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == "toml")
+                && !path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+        })
+        .filter_map(|path| {
+            let content = fs::read_to_string(&path).ok()?;
+            let entry = toml::from_str(&content).ok()?;
+            Some((path, entry))
+        })
+        .collect()
+}
+
+fn read_optional(path: &Path) -> io::Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// FNV-1a, as 16 hex digits: stable across builds, unlike the std hasher
+fn content_hash(content: &str) -> String {
+    let hash = content
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+        });
+    format!("{hash:016x}")
+}
+
+/// Make the renames and removals in `dir` durable. Best-effort: not every platform can
+/// open a directory to sync it.
+fn sync_dir(dir: &Path) {
+    if let Ok(dir) = fs::File::open(dir) {
+        let _ = dir.sync_all();
+    }
+}
@@ -1,14 +1,23 @@
-use super::models::Profile;
+use super::groups::Groups;
+use super::models::{Profile, ProfileFileInfo, Settings};
+use super::schema::{self, SchemaError};
+use crate::GLOBAL_PROFILE_MARK;
+use crate::utils::{IdentifierError, NAMESPACE_SEPARATOR, validate_profile_name};
+use std::cell::Cell;
 use std::error::Error;
 use std::fmt;
 use std::fs;
-use std::io;
-use std::path::Path;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 #[derive(Debug)]
 pub enum LoadError {
     Io(io::Error),
+    /// Not valid TOML, or not readable as a profile
     Parse(toml::de::Error),
+    /// Valid TOML with a field of the wrong type
+    Invalid(SchemaError),
     NotFound(String),
 }
 
@@ -17,6 +26,7 @@ impl fmt::Display for LoadError {
         match self {
             LoadError::Io(err) => write!(f, "IO error: {}", err),
             LoadError::Parse(err) => write!(f, "Parse error: {}", err),
+            LoadError::Invalid(err) => write!(f, "Invalid profile: {}", err),
             LoadError::NotFound(name) => write!(f, "Profile '{}' not found", name),
         }
     }
@@ -27,6 +37,7 @@ impl Error for LoadError {
         match self {
             LoadError::Io(err) => Some(err),
             LoadError::Parse(err) => Some(err),
+            LoadError::Invalid(err) => Some(err),
             LoadError::NotFound(_) => None,
         }
     }
@@ -44,36 +55,279 @@ impl From<toml::de::Error> for LoadError {
     }
 }
 
+impl From<SchemaError> for LoadError {
+    fn from(err: SchemaError) -> Self {
+        LoadError::Invalid(err)
+    }
+}
+
+/// Parse the contents of a profile file, checking its fields against the schema
+/// first so a value of the wrong type is reported by name
+pub fn parse_profile(content: &str) -> Result<Profile, LoadError> {
+    let table: toml::Table = toml::from_str(content)?;
+    schema::validate_profile(&table)?;
+    Ok(table.try_into()?)
+}
+
 pub fn scan_profile_names(path: &Path) -> Result<Vec<String>, Box<dyn Error>> {
-    let mut names = Vec::new();
-    if !path.exists() {
-        return Ok(names);
+    let names = scan_profile_files(path)?
+        .into_iter()
+        .map(|info| info.name)
+        .collect();
+    Ok(names)
+}
+
+/// The profile files under `path`, including those in namespace subdirectories,
+/// which are named like `work/api`
+pub fn scan_profile_files(path: &Path) -> Result<Vec<ProfileFileInfo>, Box<dyn Error>> {
+    Ok(scan_profiles(path)?.files)
+}
+
+/// Why a file in the profiles directory is not read as a profile
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    Hidden,
+    /// An editor backup or temporary file, such as `dev.toml~`
+    Backup,
+    NotToml,
+    /// Named after the global profile, which is stored in `global.toml`
+    Reserved,
+    InvalidName(IdentifierError),
+    /// A symbolic link to a directory, which could lead back into the profiles directory
+    LinkedDirectory,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::Hidden => write!(f, "hidden file"),
+            SkipReason::Backup => write!(f, "editor backup or temporary file"),
+            SkipReason::NotToml => write!(f, "not a .toml file"),
+            SkipReason::Reserved => write!(
+                f,
+                "the name is reserved for the global profile, which is stored in global.toml"
+            ),
+            SkipReason::InvalidName(e) => write!(f, "invalid profile name: {e}"),
+            SkipReason::LinkedDirectory => {
+                write!(f, "symbolic links to directories are not followed")
+            }
+        }
     }
-    for entry in fs::read_dir(path)? {
+}
+
+/// A file skipped while scanning, named by its path relative to the profiles directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedFile {
+    pub name: String,
+    pub reason: SkipReason,
+}
+
+/// The result of scanning the profiles directory
+#[derive(Debug, Default)]
+pub struct ScanReport {
+    pub files: Vec<ProfileFileInfo>,
+    pub skipped: Vec<SkippedFile>,
+}
+
+/// Scan `path` for profile files, reporting the files that are not profiles
+pub fn scan_profiles(path: &Path) -> Result<ScanReport, Box<dyn Error>> {
+    let mut report = ScanReport::default();
+    if path.exists() {
+        scan_namespace(path, "", &mut report)?;
+    }
+    Ok(report)
+}
+
+fn scan_namespace(dir: &Path, prefix: &str, report: &mut ScanReport) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
         let entry = entry?;
         let path = entry.path();
-        if path.is_file()
-            && path.extension().and_then(|s| s.to_str()) == Some("toml")
-            && let Some(profile_name) = path.file_stem().and_then(|s| s.to_str())
-        {
-            names.push(profile_name.to_string());
+        let Some(file_name) = path.file_name().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() && path.is_dir() {
+            if !file_name.starts_with('.') {
+                report.skipped.push(SkippedFile {
+                    name: format!("{prefix}{file_name}"),
+                    reason: SkipReason::LinkedDirectory,
+                });
+            }
+        } else if file_type.is_dir() {
+            // Hidden directories are not namespaces (e.g. editor or VCS metadata)
+            if !file_name.starts_with('.') {
+                scan_namespace(
+                    &path,
+                    &format!("{prefix}{file_name}{NAMESPACE_SEPARATOR}"),
+                    report,
+                )?;
+            }
+        } else if path.is_file() {
+            match profile_stem(file_name, prefix.is_empty()) {
+                Ok(profile_name) => report.files.push(ProfileFileInfo {
+                    name: format!("{prefix}{profile_name}"),
+                    modified: entry.metadata().and_then(|m| m.modified()).ok(),
+                }),
+                Err(reason) => report.skipped.push(SkippedFile {
+                    name: format!("{prefix}{file_name}"),
+                    reason,
+                }),
+            }
         }
     }
-    Ok(names)
+    Ok(())
+}
+
+/// The profile name a file called `file_name` holds, or why it holds none. Only
+/// `top_level` files can be named `global`, which is reserved for the global profile.
+fn profile_stem(file_name: &str, top_level: bool) -> Result<&str, SkipReason> {
+    if file_name.starts_with('.') {
+        return Err(SkipReason::Hidden);
+    }
+    if ["~", ".tmp", ".bak"]
+        .iter()
+        .any(|suffix| file_name.ends_with(suffix))
+    {
+        return Err(SkipReason::Backup);
+    }
+    let stem = file_name.strip_suffix(".toml").ok_or(SkipReason::NotToml)?;
+    if stem == GLOBAL_PROFILE_MARK || (top_level && stem.eq_ignore_ascii_case("global")) {
+        return Err(SkipReason::Reserved);
+    }
+    validate_profile_name(stem).map_err(SkipReason::InvalidName)?;
+    Ok(stem)
+}
+
+/// The file storing profile `name`; a namespaced name like `work/api` is stored as
+/// `profiles/work/api.toml`
+pub fn profile_file_path(base_path: &Path, name: &str) -> PathBuf {
+    let mut path = base_path.join("profiles");
+    let mut segments = name.split(NAMESPACE_SEPARATOR).peekable();
+    while let Some(segment) = segments.next() {
+        if segments.peek().is_some() {
+            path.push(segment);
+        } else {
+            path.push(format!("{segment}.toml"));
+        }
+    }
+    path
+}
+
+thread_local! {
+    static ASSUME_CASE_INSENSITIVE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Treat the profiles directory as case-insensitive on this thread, whatever the
+/// filesystem does, so the case handling can be tested on every platform
+pub fn simulate_case_insensitive(enabled: bool) {
+    ASSUME_CASE_INSENSITIVE.with(|flag| flag.set(enabled));
+}
+
+/// Whether `dir` ignores case in file names, as on default macOS and Windows volumes.
+/// Probed by looking the directory up again under a differently cased name.
+pub fn is_case_insensitive(dir: &Path) -> bool {
+    if ASSUME_CASE_INSENSITIVE.with(Cell::get) {
+        return true;
+    }
+    let Some(name) = dir.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    let swapped: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_lowercase() {
+                c.to_ascii_uppercase()
+            } else {
+                c.to_ascii_lowercase()
+            }
+        })
+        .collect();
+    if swapped == name {
+        return false;
+    }
+    match (fs::metadata(dir), fs::metadata(dir.with_file_name(swapped))) {
+        #[cfg(unix)]
+        (Ok(a), Ok(b)) => {
+            use std::os::unix::fs::MetadataExt;
+            a.dev() == b.dev() && a.ino() == b.ino()
+        }
+        #[cfg(not(unix))]
+        (Ok(_), Ok(_)) => true,
+        _ => false,
+    }
+}
+
+/// The stored profile whose name differs from `name` only in case, when the profiles
+/// directory ignores case and the two would therefore share one file
+pub fn case_conflict(base_path: &Path, name: &str) -> Option<String> {
+    let profiles_dir = base_path.join("profiles");
+    if !is_case_insensitive(&profiles_dir) {
+        return None;
+    }
+    let lowercase = name.to_lowercase();
+    scan_profile_names(&profiles_dir)
+        .ok()?
+        .into_iter()
+        .find(|existing| existing != name && existing.to_lowercase() == lowercase)
+}
+
+/// File-level details of a profile, shown next to it in listings
+#[derive(Debug, Clone, Default)]
+pub struct Metadata {
+    pub modified: Option<SystemTime>,
+}
+
+/// The metadata of the file storing profile `name`, empty when it cannot be read
+pub fn profile_metadata(base_path: &Path, name: &str) -> Metadata {
+    let modified = fs::metadata(profile_file_path(base_path, name))
+        .and_then(|meta| meta.modified())
+        .ok();
+    Metadata { modified }
+}
+
+/// Remove the now empty namespace directories from `dir` up to the `profiles` directory
+pub(crate) fn remove_empty_namespaces(base_path: &Path, dir: Option<&Path>) {
+    let profiles_dir = base_path.join("profiles");
+    let mut dir = dir;
+    while let Some(current) = dir
+        && current != profiles_dir
+        && current.starts_with(&profiles_dir)
+    {
+        // Fails, and stops the walk, as soon as a directory still has entries
+        if fs::remove_dir(current).is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
 }
 
 pub fn load_profile_from_file(base_path: &Path, name: &str) -> Result<Profile, LoadError> {
-    let path = base_path.join("profiles").join(format!("{name}.toml"));
+    let path = profile_file_path(base_path, name);
+    // A differently cased name finds the file on a case-insensitive filesystem, but the
+    // profile is stored under the other name
+    if !path.exists() || case_conflict(base_path, name).is_some() {
+        return Err(LoadError::NotFound(name.to_string()));
+    }
+    let content = fs::read_to_string(&path)?;
+    parse_profile(&content)
+}
+
+pub fn load_template_from_file(base_path: &Path, name: &str) -> Result<Profile, LoadError> {
+    let path = base_path.join("templates").join(format!("{name}.toml"));
     if !path.exists() {
         return Err(LoadError::NotFound(name.to_string()));
     }
     let content = fs::read_to_string(&path)?;
-    let profile: Profile = toml::from_str(&content)?;
-    Ok(profile)
+    parse_profile(&content)
+}
+
+/// The file storing the global profile
+pub fn global_config_path(base_path: &Path) -> PathBuf {
+    base_path.join("global.toml")
 }
 
 pub fn read_global_config(base_path: &Path) -> Result<Profile, Box<dyn Error>> {
-    let path = base_path.join("global.toml");
+    let path = global_config_path(base_path);
     if !path.exists() {
         return Ok(Profile::new());
     }
@@ -83,13 +337,51 @@ pub fn read_global_config(base_path: &Path) -> Result<Profile, Box<dyn Error>> {
         return Ok(Profile::new());
     }
 
-    Ok(toml::from_str(&content)?)
+    Ok(parse_profile(&content)?)
+}
+
+pub fn read_settings(base_path: &Path) -> Result<Settings, Box<dyn Error>> {
+    let path = base_path.join("config.toml");
+    if !path.exists() {
+        return Ok(Settings::default());
+    }
+
+    let content = fs::read_to_string(&path)?;
+    toml::from_str(&content).map_err(|e| format!("Invalid {}: {e}", path.display()).into())
+}
+
+pub fn write_settings(base_path: &Path, settings: &Settings) -> Result<(), Box<dyn Error>> {
+    let path = base_path.join("config.toml");
+    let content = toml::to_string_pretty(settings)?;
+    write_atomic(&path, &content)?;
+    Ok(())
+}
+
+pub fn read_groups(base_path: &Path) -> Result<Groups, Box<dyn Error>> {
+    let path = base_path.join("groups.toml");
+    if !path.exists() {
+        return Ok(Groups::default());
+    }
+
+    let content = fs::read_to_string(&path)?;
+    toml::from_str(&content).map_err(|e| format!("Invalid {}: {e}", path.display()).into())
+}
+
+pub fn write_groups(base_path: &Path, groups: &Groups) -> Result<(), Box<dyn Error>> {
+    let path = base_path.join("groups.toml");
+    let content = toml::to_string_pretty(groups)?;
+    write_atomic(&path, &content)?;
+    Ok(())
 }
 
 pub fn write_global_config(base_path: &Path, global: &Profile) -> Result<(), Box<dyn Error>> {
-    let path = base_path.join("global.toml");
     let content = toml::to_string_pretty(global)?;
-    fs::write(path, content)?;
+    write_global_content(base_path, &content)
+}
+
+/// Replace the global profile file with `content` as is
+pub fn write_global_content(base_path: &Path, content: &str) -> Result<(), Box<dyn Error>> {
+    write_atomic(&global_config_path(base_path), content)?;
     Ok(())
 }
 
@@ -98,16 +390,83 @@ pub fn write_profile(
     name: &str,
     profile: &Profile,
 ) -> Result<(), Box<dyn Error>> {
-    let path = base_path.join("profiles").join(format!("{name}.toml"));
+    let path = profile_file_path(base_path, name);
     let content = toml::to_string_pretty(profile)?;
-    fs::write(path, content)?;
+    write_atomic(&path, &content)?;
     Ok(())
 }
 
+/// Replace the file of profile `name` with `content` as it is, without reformatting
+pub fn write_profile_content(base_path: &Path, name: &str, content: &str) -> io::Result<()> {
+    let path = profile_file_path(base_path, name);
+    write_atomic(&path, content)
+}
+
+/// Write `content` to a temporary file next to `path` and rename it into place,
+/// so readers see either the old file or the complete new one, never a partial write.
+///
+/// The new file keeps the permissions of the old one, and a symbolic link is written
+/// through rather than replaced.
+pub(crate) fn write_atomic(path: &Path, content: &str) -> io::Result<()> {
+    let path = match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => {
+            fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+        }
+        _ => path.to_path_buf(),
+    };
+    let permissions = fs::metadata(&path).ok().map(|m| m.permissions());
+    replace_file(&path, content, permissions)
+}
+
+/// Like [`write_atomic`], leaving the file readable and writable by its owner only
+pub(crate) fn write_private(path: &Path, content: &str) -> io::Result<()> {
+    #[cfg(unix)]
+    let permissions = {
+        use std::os::unix::fs::PermissionsExt;
+        Some(fs::Permissions::from_mode(0o600))
+    };
+    #[cfg(not(unix))]
+    let permissions = None;
+    replace_file(path, content, permissions)
+}
+
+/// Write `content` to a temporary file with `permissions` and rename it to `path`
+fn replace_file(
+    path: &Path,
+    content: &str,
+    permissions: Option<fs::Permissions>,
+) -> io::Result<()> {
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid file path"))?;
+    let tmp_path = path.with_file_name(format!(".{file_name}.{}.tmp", std::process::id()));
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let result = (|| {
+        let mut file = fs::File::create(&tmp_path)?;
+        // Before the content is written, so it is never readable by others
+        if let Some(permissions) = permissions {
+            file.set_permissions(permissions)?;
+        }
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
 pub fn delete_profile_file(base_path: &Path, name: &str) -> Result<(), Box<dyn Error>> {
-    let path = base_path.join("profiles").join(format!("{name}.toml"));
+    let path = profile_file_path(base_path, name);
     if path.exists() {
-        fs::remove_file(path)?;
+        fs::remove_file(&path)?;
+        remove_empty_namespaces(base_path, path.parent());
     }
     Ok(())
 }
@@ -117,16 +476,39 @@ pub fn rename_profile_file(
     old_name: &str,
     new_name: &str,
 ) -> Result<(), Box<dyn Error>> {
-    let old_path = base_path.join("profiles").join(format!("{old_name}.toml"));
-    let new_path = base_path.join("profiles").join(format!("{new_name}.toml"));
+    let old_path = profile_file_path(base_path, old_name);
+    let new_path = profile_file_path(base_path, new_name);
 
     if !old_path.exists() {
         return Err(format!("Profile '{old_name}' not found.").into());
     }
-    if new_path.exists() {
-        return Err(format!("Profile '{new_name}' already exists.").into());
-    }
+    // On a case-insensitive filesystem `new_path` exists as soon as any profile differs
+    // from `new_name` only in case, which is the profile itself in a case-only rename
+    let case_only = match case_conflict(base_path, new_name) {
+        Some(existing) if existing == old_name => true,
+        Some(existing) => {
+            return Err(format!(
+                "Profile '{new_name}' would share a file with '{existing}', since the profiles directory ignores case."
+            )
+            .into());
+        }
+        None if new_path.exists() => {
+            return Err(format!("Profile '{new_name}' already exists.").into());
+        }
+        None => false,
+    };
 
-    fs::rename(old_path, new_path)?;
+    if let Some(parent) = new_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if case_only {
+        // Renaming straight to the new case may be a no-op, so go through a temporary name
+        let tmp_path = new_path.with_file_name(format!(".rename.{}.tmp", std::process::id()));
+        fs::rename(&old_path, &tmp_path)?;
+        fs::rename(&tmp_path, &new_path)?;
+    } else {
+        fs::rename(&old_path, &new_path)?;
+    }
+    remove_empty_namespaces(base_path, old_path.parent());
     Ok(())
 }
@@ -0,0 +1,67 @@
+use std::error::Error;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const LOCK_FILE_NAME: &str = ".lock";
+/// How long to wait for another process to release the lock before giving up
+pub const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// An exclusive advisory lock on the config directory, released on drop.
+///
+/// The OS drops the lock when the holding process exits, so a lock is only ever
+/// "stale" while another env-manage process is still running; acquisition times
+/// out instead of waiting on it forever.
+#[derive(Debug)]
+pub struct ConfigLock {
+    file: File,
+}
+
+impl ConfigLock {
+    pub fn acquire(base_path: &Path) -> Result<Self, Box<dyn Error>> {
+        Self::acquire_with_timeout(base_path, LOCK_TIMEOUT)
+    }
+
+    pub fn acquire_with_timeout(
+        base_path: &Path,
+        timeout: Duration,
+    ) -> Result<Self, Box<dyn Error>> {
+        let path = lock_path(base_path);
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)?;
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            match file.try_lock() {
+                Ok(()) => return Ok(Self { file }),
+                Err(TryLockError::WouldBlock) if Instant::now() < deadline => {
+                    thread::sleep(RETRY_INTERVAL);
+                }
+                Err(TryLockError::WouldBlock) => {
+                    return Err(format!(
+                        "Timed out after {}s waiting for the lock on '{}', is another env-manage process running?",
+                        timeout.as_secs(),
+                        path.display()
+                    )
+                    .into());
+                }
+                Err(TryLockError::Error(e)) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+impl Drop for ConfigLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+fn lock_path(base_path: &Path) -> PathBuf {
+    base_path.join(LOCK_FILE_NAME)
+}
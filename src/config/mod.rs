@@ -1,13 +1,43 @@
 use self::graph::{DependencyError, ProfileGraph};
-use self::models::{Profile, ProfileNames};
+use self::groups::Groups;
+use self::history::{Operation, Source};
+use self::journal::{FileChange, Journal};
+use self::lock::ConfigLock;
+use self::models::{Profile, ProfileFileInfo, ProfileNames, Settings};
+use self::recent::RecentProfiles;
+use crate::GLOBAL_PROFILE_MARK;
+use crate::utils::placeholder::Placeholders;
 use std::collections::HashMap;
+use std::env;
 use std::error::Error;
 use std::fs;
 use std::path::PathBuf;
 
+pub mod diagnostics;
+pub mod diff;
+pub mod env_size;
 pub mod graph;
+pub mod groups;
+pub mod history;
+pub mod journal;
 pub mod loader;
+pub mod lock;
 pub mod models;
+pub mod recent;
+pub mod schema;
+pub mod stats;
+
+/// Environment variable that overrides the default config directory
+pub const CONFIG_DIR_ENV_VAR: &str = "ENV_MANAGE_CONFIG_DIR";
+
+/// Older name of [`CONFIG_DIR_ENV_VAR`], still honored when that one is not set
+pub const HOME_ENV_VAR: &str = "ENV_MANAGE_HOME";
+
+/// Each profile that failed to load, with why
+pub type LoadFailures = Vec<(String, DependencyError)>;
+
+/// The profiles that loaded and the ones that did not
+pub type LoadReport = (Vec<String>, LoadFailures);
 
 pub struct AppConfig {
     profiles: HashMap<String, Profile>,
@@ -52,7 +82,8 @@ impl AppConfig {
     }
 
     fn rebuild_graph(&mut self) -> Result<(), DependencyError> {
-        self.graph = ProfileGraph::build(&self.profiles)?;
+        let profiles_dir = self.graph.profiles_dir().map(|dir| dir.to_path_buf());
+        self.graph = ProfileGraph::build(&self.profiles, profiles_dir)?;
         Ok(())
     }
 
@@ -60,21 +91,82 @@ impl AppConfig {
         self.graph.resolve_dependencies(profile_name)
     }
 
+    fn resolve_many<S: AsRef<str>>(&self, roots: &[S]) -> Result<Vec<String>, DependencyError> {
+        self.graph.resolve_many(roots)
+    }
+
+    fn topological_order(&self) -> Result<Vec<String>, DependencyError> {
+        self.graph.topological_order()
+    }
+
     fn find_path(&self, start: &str, end: &str) -> Option<Vec<String>> {
         self.graph.find_path(start, end)
     }
 
+    fn to_dot(&self) -> String {
+        self.graph.to_dot()
+    }
+
+    fn to_mermaid(&self) -> String {
+        self.graph.to_mermaid()
+    }
+
+    fn max_depth(&self, name: &str) -> usize {
+        self.graph.max_depth(name)
+    }
+
+    fn max_depth_overall(&self) -> usize {
+        self.graph.max_depth_overall()
+    }
+
+    fn profiles_without_dependents(&self) -> Vec<String> {
+        self.graph.profiles_without_dependents()
+    }
+
     fn get_parents(&self, profile_name: &str) -> Option<Vec<String>> {
         self.graph.get_parents(profile_name)
     }
 
+    fn get_all_dependents(&self, profile_name: &str) -> Option<Vec<String>> {
+        self.graph.get_all_dependents(profile_name)
+    }
+
     /// Add dependency edge (more efficient than rebuild for single additions)
     fn add_dependency_edge(&mut self, parent: &str, child: &str) -> Result<(), DependencyError> {
         self.graph.add_dependency(parent, child)
     }
 
+    /// Loaded profiles already listing `name` would gain an edge to it once it exists
+    fn check_new_profile(
+        &self,
+        name: &str,
+        dependencies: &[String],
+    ) -> Result<(), DependencyError> {
+        let mut dependents: Vec<String> = self
+            .profiles
+            .iter()
+            .filter(|(_, profile)| profile.enabled_profiles().any(|dep| dep == name))
+            .map(|(dependent, _)| dependent.clone())
+            .collect();
+        dependents.sort();
+        self.graph
+            .check_new_profile(name, dependencies, &dependents)
+    }
+
+    fn dependency_not_found(&self, parent: &str, dependency: &str) -> DependencyError {
+        self.graph.dependency_not_found(parent, dependency)
+    }
+
+    fn global_dependency(&self, parent: &str) -> DependencyError {
+        self.graph.global_dependency(parent)
+    }
+
     /// Remove dependency edge (more efficient than rebuild for single removals)
-    fn remove_dependency_edge(&mut self, parent: &str, child: &str) -> Result<(), DependencyError> {
+    fn remove_dependency_edge(
+        &mut self,
+        parent: &str,
+        child: &str,
+    ) -> Result<bool, DependencyError> {
         self.graph.remove_dependency(parent, child)
     }
 
@@ -101,32 +193,66 @@ impl AppConfig {
 pub struct ConfigManager {
     app_config: AppConfig,
     base_path: PathBuf,
+    lock: Option<ConfigLock>,
 }
 
 impl ConfigManager {
-    pub fn new() -> Result<Self, Box<dyn Error>> {
-        let home = dirs::home_dir().ok_or("Could not find home directory")?;
-        let base_path = home.join(".config").join("env-manage");
+    /// Creates a ConfigManager rooted at `base_path`, or at the default config
+    /// directory when none is given (see [`ConfigManager::resolve_base_path`]).
+    pub fn new(base_path: Option<PathBuf>) -> Result<Self, Box<dyn Error>> {
+        Self::with_base_path(Self::resolve_base_path(base_path)?)
+    }
+
+    /// Creates a ConfigManager rooted at `base_path`, creating its `profiles`
+    /// directory if needed
+    pub fn with_base_path(base_path: PathBuf) -> Result<Self, Box<dyn Error>> {
         let profiles_path = base_path.join("profiles");
         fs::create_dir_all(&profiles_path)?;
 
         // Lazy load: Start with empty profiles and graph
         let profiles = HashMap::new();
-        let graph = ProfileGraph::new();
+        let graph = ProfileGraph::with_profiles_dir(profiles_path);
         let app_config = AppConfig::new(profiles, graph);
 
         Ok(Self {
             app_config,
             base_path,
+            lock: None,
         })
     }
 
     /// Creates a ConfigManager and loads all profiles immediately.
     /// This restores the original behavior where all profiles are loaded at startup.
-    pub fn new_full() -> Result<Self, Box<dyn Error>> {
-        let mut manager = Self::new()?;
-        manager.load_all_profiles()?;
-        Ok(manager)
+    ///
+    /// A profile that fails to load does not stop the others; the failures are
+    /// returned alongside the manager.
+    pub fn new_full(base_path: Option<PathBuf>) -> Result<(Self, LoadFailures), Box<dyn Error>> {
+        let mut manager = Self::new(base_path)?;
+        let (_, failures) = manager.load_all_profiles()?;
+        Ok((manager, failures))
+    }
+
+    /// The config directory to use: `explicit` (the `--config-dir` flag) when given,
+    /// then `$ENV_MANAGE_CONFIG_DIR`, `$ENV_MANAGE_HOME`, `$XDG_CONFIG_HOME/env-manage`
+    /// and finally `~/.config/env-manage`
+    pub fn resolve_base_path(explicit: Option<PathBuf>) -> Result<PathBuf, Box<dyn Error>> {
+        if let Some(path) = explicit {
+            return Ok(path);
+        }
+        for var in [CONFIG_DIR_ENV_VAR, HOME_ENV_VAR] {
+            if let Some(path) = env::var_os(var).filter(|p| !p.is_empty()) {
+                return Ok(PathBuf::from(path));
+            }
+        }
+        // Only an absolute XDG_CONFIG_HOME is valid per the XDG Base Directory spec
+        if let Some(config_home) = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|p| p.is_absolute())
+        {
+            return Ok(config_home.join("env-manage"));
+        }
+        let home = dirs::home_dir().ok_or("Could not find home directory")?;
+        Ok(home.join(".config").join("env-manage"))
     }
 
     pub fn load_profile(&mut self, name: &str) -> Result<(), DependencyError> {
@@ -146,6 +272,11 @@ impl ConfigManager {
             return Ok(());
         }
 
+        // The global profile lives in `global.toml`, never in a profile file
+        if name == GLOBAL_PROFILE_MARK {
+            return Err(DependencyError::ProfileNotFound(name.to_string()));
+        }
+
         visiting.insert(name.to_string());
 
         // Load from file
@@ -159,6 +290,9 @@ impl ConfigManager {
                     loader::LoadError::Parse(err) => {
                         DependencyError::ProfileParseError(name.to_string(), err)
                     }
+                    loader::LoadError::Invalid(err) => {
+                        DependencyError::ProfileInvalid(name.to_string(), err)
+                    }
                     loader::LoadError::NotFound(n) => DependencyError::ProfileNotFound(n),
                 };
                 return Err(dep_err);
@@ -170,13 +304,23 @@ impl ConfigManager {
 
         let mut errors = Vec::new();
 
-        // Load dependencies
-        for dep_name in &profile.profiles {
+        // Load dependencies; disabled ones are neither loaded nor part of the graph
+        for dep_name in profile.enabled_profiles() {
+            if dep_name == GLOBAL_PROFILE_MARK {
+                errors.push(self.app_config.global_dependency(name));
+                continue;
+            }
             if let Err(e) = self.load_profile_recursive(dep_name, visiting) {
-                errors.push(DependencyError::DependencyChain {
-                    profile: name.to_string(),
-                    cause: Box::new(e),
-                });
+                match e {
+                    // A missing direct dependency is reported against the file declaring it
+                    DependencyError::ProfileNotFound(missing) if missing == *dep_name => {
+                        errors.push(self.app_config.dependency_not_found(name, dep_name));
+                    }
+                    e => errors.push(DependencyError::DependencyChain {
+                        profile: name.to_string(),
+                        cause: Box::new(e),
+                    }),
+                }
             } else {
                 // Add dependency edge only if load succeeded (or cycle check passed)
                 // If load failed, adding edge might cause noise or be impossible if node missing.
@@ -199,12 +343,30 @@ impl ConfigManager {
         Ok(())
     }
 
-    pub fn load_all_profiles(&mut self) -> Result<(), Box<dyn Error>> {
+    /// Load every profile on disk, returning the names that loaded and the error
+    /// of each one that did not; only failing to scan the directory is an error
+    pub fn load_all_profiles(&mut self) -> Result<LoadReport, Box<dyn Error>> {
         let names = self.scan_profile_names()?;
-        for name in names.iter() {
-            self.load_profile(name)?;
+        let mut loaded = Vec::new();
+        let mut failures = Vec::new();
+        for name in names.0 {
+            match self.load_profile(&name) {
+                Ok(()) => loaded.push(name),
+                Err(e) => failures.push((name, e)),
+            }
         }
-        Ok(())
+        Ok((loaded, failures))
+    }
+
+    /// Parse the file of profile `name` alone, without loading its dependencies
+    pub fn read_profile_file(&self, name: &str) -> Result<Profile, loader::LoadError> {
+        loader::load_profile_from_file(&self.base_path, name)
+    }
+
+    /// Forget every loaded profile so the next load reads the files again
+    pub fn clear_loaded(&mut self) {
+        let graph = ProfileGraph::with_profiles_dir(self.base_path.join("profiles"));
+        self.app_config = AppConfig::new(HashMap::new(), graph);
     }
 
     pub fn get_profile(&self, name: &str) -> Option<&Profile> {
@@ -230,6 +392,16 @@ impl ConfigManager {
         Ok(ProfileNames(names))
     }
 
+    /// Like `scan_profile_files`, also listing the files that were skipped as not being profiles
+    pub fn scan_profiles(&self) -> Result<loader::ScanReport, Box<dyn Error>> {
+        loader::scan_profiles(&self.base_path.join("profiles"))
+    }
+
+    /// Like `scan_profile_names`, but also returns file metadata such as the modification time
+    pub fn scan_profile_files(&self) -> Result<Vec<ProfileFileInfo>, Box<dyn Error>> {
+        loader::scan_profile_files(&self.base_path.join("profiles"))
+    }
+
     pub fn add_profile(&mut self, name: String, profile: Profile) {
         self.app_config.add_profile(name, profile);
     }
@@ -258,8 +430,7 @@ impl ConfigManager {
         new_dep: &str,
     ) {
         if let Some(profile) = self.app_config.get_profile_mut(profile_name) {
-            profile.remove_profile(old_dep);
-            profile.add_profile(new_dep);
+            profile.rename_profile(profile_name, old_dep, new_dep);
         }
     }
 
@@ -275,14 +446,53 @@ impl ConfigManager {
         self.app_config.resolve_dependencies(profile_name)
     }
 
+    /// The dependencies of several profiles followed by the profiles themselves, each once
+    pub fn resolve_many<S: AsRef<str>>(&self, roots: &[S]) -> Result<Vec<String>, DependencyError> {
+        self.app_config.resolve_many(roots)
+    }
+
+    /// The loaded profiles, each listed after the profiles it depends on
+    pub fn topological_order(&self) -> Result<Vec<String>, DependencyError> {
+        self.app_config.topological_order()
+    }
+
     pub fn find_path(&self, start: &str, end: &str) -> Option<Vec<String>> {
         self.app_config.find_path(start, end)
     }
 
+    /// The dependency graph of the loaded profiles in Graphviz DOT syntax
+    pub fn to_dot(&self) -> String {
+        self.app_config.to_dot()
+    }
+
+    /// The dependency graph of the loaded profiles as a Mermaid flowchart
+    pub fn to_mermaid(&self) -> String {
+        self.app_config.to_mermaid()
+    }
+
     pub fn get_parents(&self, profile_name: &str) -> Option<Vec<String>> {
         self.app_config.get_parents(profile_name)
     }
 
+    /// The longest chain of dependencies starting at `name`, in edges; `name` must be loaded
+    pub fn max_depth(&self, name: &str) -> usize {
+        self.app_config.max_depth(name)
+    }
+
+    /// The longest chain of dependencies among the loaded profiles, in edges
+    pub fn max_depth_overall(&self) -> usize {
+        self.app_config.max_depth_overall()
+    }
+
+    /// The loaded profiles no other profile depends on, sorted by name
+    pub fn profiles_without_dependents(&self) -> Vec<String> {
+        self.app_config.profiles_without_dependents()
+    }
+
+    pub fn get_all_dependents(&self, profile_name: &str) -> Option<Vec<String>> {
+        self.app_config.get_all_dependents(profile_name)
+    }
+
     pub fn rebuild_graph(&mut self) -> Result<(), Box<dyn Error>> {
         self.app_config.rebuild_graph()?;
         Ok(())
@@ -295,14 +505,23 @@ impl ConfigManager {
         Ok(())
     }
 
-    /// Remove dependency edge incrementally (more efficient than rebuild_graph)
+    /// Remove dependency edge incrementally (more efficient than rebuild_graph),
+    /// returning whether `parent` depended on `child`
     pub fn remove_dependency_edge(
         &mut self,
         parent: &str,
         child: &str,
-    ) -> Result<(), Box<dyn Error>> {
-        self.app_config.remove_dependency_edge(parent, child)?;
-        Ok(())
+    ) -> Result<bool, Box<dyn Error>> {
+        Ok(self.app_config.remove_dependency_edge(parent, child)?)
+    }
+
+    /// Simulate creating profile `name` with `dependencies`, without changing the graph
+    pub fn check_new_profile(
+        &self,
+        name: &str,
+        dependencies: &[String],
+    ) -> Result<(), DependencyError> {
+        self.app_config.check_new_profile(name, dependencies)
     }
 
     /// Add a new profile node to the graph
@@ -326,11 +545,44 @@ impl ConfigManager {
         Ok(())
     }
 
+    /// Take the config directory lock and hold it until `unlock` or drop.
+    ///
+    /// Commands that read, modify and write back profiles hold it for their whole
+    /// duration so concurrent invocations serialize instead of losing changes.
+    pub fn lock(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.lock.is_none() {
+            self.lock = Some(ConfigLock::acquire(&self.base_path)?);
+        }
+        Ok(())
+    }
+
+    pub fn unlock(&mut self) {
+        self.lock = None;
+    }
+
+    /// Lock for a single write unless the lock is already held by this manager
+    fn write_guard(&self) -> Result<Option<ConfigLock>, Box<dyn Error>> {
+        if self.lock.is_some() {
+            Ok(None)
+        } else {
+            ConfigLock::acquire(&self.base_path).map(Some)
+        }
+    }
+
     pub fn write_profile(&self, name: &str, profile: &Profile) -> Result<(), Box<dyn Error>> {
+        let _guard = self.write_guard()?;
         loader::write_profile(&self.base_path, name, profile)
     }
 
+    /// Replace the file of profile `name` with `content`, exactly as given
+    pub fn write_profile_content(&self, name: &str, content: &str) -> Result<(), Box<dyn Error>> {
+        let _guard = self.write_guard()?;
+        loader::write_profile_content(&self.base_path, name, content)?;
+        Ok(())
+    }
+
     pub fn delete_profile_file(&self, name: &str) -> Result<(), Box<dyn Error>> {
+        let _guard = self.write_guard()?;
         loader::delete_profile_file(&self.base_path, name)
     }
 
@@ -339,28 +591,144 @@ impl ConfigManager {
         old_name: &str,
         new_name: &str,
     ) -> Result<(), Box<dyn Error>> {
+        let _guard = self.write_guard()?;
         loader::rename_profile_file(&self.base_path, old_name, new_name)
     }
 
+    /// Read `config.toml`, falling back to defaults when it does not exist
+    pub fn read_settings(&self) -> Result<Settings, Box<dyn Error>> {
+        loader::read_settings(&self.base_path)
+    }
+
+    pub fn write_settings(&self, settings: &Settings) -> Result<(), Box<dyn Error>> {
+        let _guard = self.write_guard()?;
+        loader::write_settings(&self.base_path, settings)
+    }
+
+    /// Read `groups.toml`, which is empty when it does not exist
+    pub fn read_groups(&self) -> Result<Groups, Box<dyn Error>> {
+        loader::read_groups(&self.base_path)
+    }
+
+    pub fn write_groups(&self, groups: &Groups) -> Result<(), Box<dyn Error>> {
+        let _guard = self.write_guard()?;
+        loader::write_groups(&self.base_path, groups)
+    }
+
+    /// Read a template profile from the `templates` directory
+    pub fn load_template(&self, name: &str) -> Result<Profile, loader::LoadError> {
+        loader::load_template_from_file(&self.base_path, name)
+    }
+
     pub fn read_global(&self) -> Result<Profile, Box<dyn Error>> {
         loader::read_global_config(&self.base_path)
     }
 
     pub fn write_global(&self, global: &Profile) -> Result<(), Box<dyn Error>> {
+        let _guard = self.write_guard()?;
         loader::write_global_config(&self.base_path, global)
     }
 
+    pub fn write_global_content(&self, content: &str) -> Result<(), Box<dyn Error>> {
+        let _guard = self.write_guard()?;
+        loader::write_global_content(&self.base_path, content)
+    }
+
+    pub fn global_path(&self) -> PathBuf {
+        loader::global_config_path(&self.base_path)
+    }
+
     pub fn base_path(&self) -> &std::path::Path {
         &self.base_path
     }
 
+    /// Append `operation` to the history log, ignoring failures
+    pub fn record_history(&self, source: Source, operation: Operation) {
+        history::record(&self.base_path, source, &operation);
+    }
+
+    /// Journal an operation about to set each path to its content, or delete it when
+    /// `None`, capturing the current contents to roll back to
+    pub fn begin_journal(
+        &self,
+        operation: &str,
+        changes: Vec<(PathBuf, Option<String>)>,
+    ) -> Result<Journal, Box<dyn Error>> {
+        let files = changes
+            .into_iter()
+            .map(|(path, after)| FileChange::capture(&self.base_path, &path, after))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Journal::begin(&self.base_path, operation, files)?)
+    }
+
+    /// Apply `change` to the recently edited profiles stored on disk
+    pub fn update_recent(&self, change: impl FnOnce(&mut RecentProfiles)) {
+        let mut recent = RecentProfiles::load(&self.base_path);
+        let before = recent.clone();
+        change(&mut recent);
+        if recent != before {
+            recent.save(&self.base_path);
+        }
+    }
+
     /// Checks whether a profile with the specified name exists on disk
     ///
     /// Unlike `has_profile`, which only checks if the profile is loaded in memory,
     /// this method actually verifies the existence of the corresponding `.toml` file
     /// in the filesystem.
     pub fn profile_exists(&self, name: &str) -> bool {
-        let path = self.base_path.join("profiles").join(format!("{name}.toml"));
-        path.exists()
+        name != GLOBAL_PROFILE_MARK
+            && self.profile_path(name).exists()
+            && self.case_conflict(name).is_none()
+    }
+
+    /// A profile, loaded or stored, whose name differs from `name` only in case when the
+    /// profiles directory ignores case, so both would be stored in the same file
+    pub fn case_conflict(&self, name: &str) -> Option<String> {
+        let profiles_dir = self.base_path.join("profiles");
+        if !loader::is_case_insensitive(&profiles_dir) {
+            return None;
+        }
+        let lowercase = name.to_lowercase();
+        self.profiles_iter()
+            .map(|(existing, _)| existing.clone())
+            .find(|existing| existing != name && existing.to_lowercase() == lowercase)
+            .or_else(|| loader::case_conflict(&self.base_path, name))
+    }
+
+    /// Whether files can be created in the profiles directory, probed with a throwaway file
+    pub fn is_writable(&self) -> bool {
+        let probe = self
+            .base_path
+            .join("profiles")
+            .join(format!(".write-probe-{}", std::process::id()));
+        match fs::File::create(&probe) {
+            Ok(_) => {
+                let _ = fs::remove_file(&probe);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// The file profile `name` is stored in
+    pub fn profile_path(&self, name: &str) -> PathBuf {
+        loader::profile_file_path(&self.base_path, name)
+    }
+
+    /// The placeholders of the values of profile `name`, `${PROFILE_DIR}` being the
+    /// directory of its file
+    pub fn placeholders(&self, name: &str) -> Placeholders {
+        let path = if name == GLOBAL_PROFILE_MARK {
+            self.global_path()
+        } else {
+            self.profile_path(name)
+        };
+        let profile_dir = path.parent().unwrap_or(&self.base_path);
+        Placeholders::new(&self.base_path, profile_dir)
+    }
+
+    pub fn profile_metadata(&self, name: &str) -> loader::Metadata {
+        loader::profile_metadata(&self.base_path, name)
     }
 }
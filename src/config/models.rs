@@ -1,21 +1,309 @@
-use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::env;
+use std::time::SystemTime;
 
 use crate::config::ConfigManager;
+use crate::utils::placeholder::Placeholders;
 
 // Represents a single profile with its environment variables.
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct Profile {
-    // Using a HashMap to store the key-value pairs for the environment.
-    #[serde(default)]
-    pub variables: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Shell commands run after the profile's variables are set
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_activate: Vec<String>,
+    /// Shell commands run before the profile's variables are removed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_deactivate: Vec<String>,
+    /// Commands env-manage runs itself before activating; a failure aborts the activation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_activate: Vec<String>,
+    /// Commands env-manage runs itself once the activation commands are printed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_activate: Vec<String>,
+    /// How strictly variable keys are checked; unset falls back to the global default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validation: Option<KeyValidation>,
+    // Kept in the order they were written, so saved files keep the user's grouping.
     #[serde(default)]
+    pub variables: IndexMap<String, String>,
+    #[serde(default, serialize_with = "serialize_sorted_set")]
     pub profiles: HashSet<String>,
+    /// Dependencies kept in `profiles` but skipped when resolving, until enabled again
+    #[serde(
+        default,
+        skip_serializing_if = "HashSet::is_empty",
+        serialize_with = "serialize_sorted_set"
+    )]
+    pub disabled_profiles: HashSet<String>,
+    /// Variables removed from the environment, overriding what inherited profiles set
+    #[serde(
+        default,
+        skip_serializing_if = "HashSet::is_empty",
+        serialize_with = "serialize_sorted_set"
+    )]
+    pub unset: HashSet<String>,
+}
+
+/// Case-insensitive alphabetical order, falling back to the exact string so the order is total
+fn sort_key(value: &str) -> (String, &str) {
+    (value.to_lowercase(), value)
+}
+
+// Saved files list dependencies in a fixed order so unchanged profiles serialize byte-identically.
+fn serialize_sorted_set<S: Serializer>(
+    set: &HashSet<String>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut items: Vec<_> = set.iter().collect();
+    items.sort_by(|a, b| sort_key(a).cmp(&sort_key(b)));
+    serializer.collect_seq(items)
+}
+
+/// Separator between the entries of list-like variables such as `PATH`
+pub const LIST_SEPARATOR: char = ':';
+
+/// Prefix of a value that is computed by running the rest of it as a command in the
+/// shell at activation, like `command: date -Iseconds`
+pub const COMMAND_PREFIX: &str = "command:";
+
+/// The command of a computed value, or `None` for a plain value
+pub fn computed_command(value: &str) -> Option<&str> {
+    value.strip_prefix(COMMAND_PREFIX).map(str::trim)
+}
+
+/// How a variable key combines its value with what earlier profiles set.
+///
+/// `PATH+` appends to the `PATH` list and `PATH^` prepends to it; any other key
+/// replaces the value outright.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VarOp {
+    Set,
+    Append,
+    Prepend,
 }
 
+impl VarOp {
+    /// Split a stored key such as `PATH+` into the variable name and its operation
+    pub fn parse(key: &str) -> (&str, VarOp) {
+        if let Some(name) = key.strip_suffix('+') {
+            (name, VarOp::Append)
+        } else if let Some(name) = key.strip_suffix('^') {
+            (name, VarOp::Prepend)
+        } else {
+            (key, VarOp::Set)
+        }
+    }
+}
+
+/// A profile in resolution order, named unless it is the profile being resolved
+type ResolvedProfile<'a> = (Option<String>, &'a Profile);
+
+/// Resolved variables to set, and the variables to unset
+type ResolvedEnv = (HashMap<String, String>, HashSet<String>);
+
 #[derive(Default)]
 pub struct ProfileNames(pub Vec<String>);
 
+/// File-level information about a profile gathered while scanning the profiles directory
+#[derive(Clone, Debug)]
+pub struct ProfileFileInfo {
+    pub name: String,
+    pub modified: Option<SystemTime>,
+}
+
+/// Tool-wide settings read from `config.toml` in the config directory
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Settings {
+    #[serde(default, skip_serializing_if = "Denylist::is_empty")]
+    pub denylist: Denylist,
+    /// Hook commands the user allowed to run, by profile; trust lapses once they change
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub trusted_hooks: BTreeMap<String, Hooks>,
+    /// Key validation of profiles that do not choose their own
+    #[serde(default, skip_serializing_if = "KeyValidation::is_relaxed")]
+    pub validation: KeyValidation,
+    /// Whether the TUI reopens on the profile, pane and sort order it was closed with;
+    /// on when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restore_session: Option<bool>,
+    /// `check` warns about profiles whose chain of dependencies is longer than this
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_inheritance_depth: Option<usize>,
+    /// Bytes of resolved variables above which activation, `check` and the TUI warn
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_env_size: Option<usize>,
+    /// TUI color theme: a built-in one or the name of a file in `themes/`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+}
+
+/// The inheritance depth `check` accepts unless `max_inheritance_depth` says otherwise
+pub const DEFAULT_MAX_INHERITANCE_DEPTH: usize = 8;
+
+/// The environment size, in bytes, that is fine unless `max_env_size` says otherwise
+pub const DEFAULT_MAX_ENV_SIZE: usize = 100 * 1024;
+
+impl Settings {
+    pub fn max_inheritance_depth(&self) -> usize {
+        self.max_inheritance_depth
+            .unwrap_or(DEFAULT_MAX_INHERITANCE_DEPTH)
+    }
+
+    pub fn max_env_size(&self) -> usize {
+        self.max_env_size.unwrap_or(DEFAULT_MAX_ENV_SIZE)
+    }
+
+    pub fn restores_session(&self) -> bool {
+        self.restore_session.unwrap_or(true)
+    }
+
+    pub fn trusts_hooks(&self, name: &str, hooks: &Hooks) -> bool {
+        self.trusted_hooks.get(name) == Some(hooks)
+    }
+
+    /// The key validation that applies to `profile`
+    pub fn key_validation(&self, profile: &Profile) -> KeyValidation {
+        profile.validation.unwrap_or(self.validation)
+    }
+}
+
+/// Which variable keys a profile accepts: `strict` only allows ALL_CAPS names,
+/// `relaxed` also allows lowercase letters and `-`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyValidation {
+    #[default]
+    Relaxed,
+    Strict,
+}
+
+impl KeyValidation {
+    pub fn is_relaxed(&self) -> bool {
+        *self == KeyValidation::Relaxed
+    }
+}
+
+/// The shell commands a profile runs when it is activated and deactivated
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hooks {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_activate: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_deactivate: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_activate: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_activate: Vec<String>,
+    /// Commands of the computed variables, by key; they run in the shell on activation
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub computed: BTreeMap<String, String>,
+}
+
+impl Hooks {
+    pub fn is_empty(&self) -> bool {
+        self.computed.is_empty()
+            && HookKind::ALL
+                .iter()
+                .all(|&kind| self.commands(kind).is_empty())
+    }
+
+    pub fn commands(&self, kind: HookKind) -> &[String] {
+        match kind {
+            HookKind::Activate => &self.on_activate,
+            HookKind::Deactivate => &self.on_deactivate,
+            HookKind::PreActivate => &self.pre_activate,
+            HookKind::PostActivate => &self.post_activate,
+        }
+    }
+
+    /// The number of hook commands across all kinds, not counting computed variables
+    pub fn len(&self) -> usize {
+        HookKind::ALL
+            .iter()
+            .map(|&kind| self.commands(kind).len())
+            .sum()
+    }
+}
+
+/// When a hook runs
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookKind {
+    /// In the shell, after the variables are set
+    Activate,
+    /// In the shell, before the variables are removed
+    Deactivate,
+    /// By env-manage, before the activation commands are printed
+    PreActivate,
+    /// By env-manage, after the activation commands are printed
+    PostActivate,
+}
+
+impl HookKind {
+    pub const ALL: [HookKind; 4] = [
+        HookKind::PreActivate,
+        HookKind::Activate,
+        HookKind::PostActivate,
+        HookKind::Deactivate,
+    ];
+
+    /// How the commands are introduced when listed
+    pub fn label(self) -> &'static str {
+        match self {
+            HookKind::Activate => "on activate",
+            HookKind::Deactivate => "on deactivate",
+            HookKind::PreActivate => "pre activate",
+            HookKind::PostActivate => "post activate",
+        }
+    }
+}
+
+/// Variable names that profiles are never allowed to set
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Denylist {
+    #[serde(default)]
+    pub variables: Vec<String>,
+    #[serde(default)]
+    pub action: DenyAction,
+}
+
+/// What to do when an activation would set a denied variable
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DenyAction {
+    /// Skip the denied variables and warn about them
+    #[default]
+    Warn,
+    /// Refuse the whole activation
+    Error,
+}
+
+impl Denylist {
+    pub fn is_empty(&self) -> bool {
+        self.variables.is_empty() && self.action == DenyAction::default()
+    }
+
+    pub fn is_denied(&self, key: &str) -> bool {
+        self.variables.iter().any(|denied| denied == key)
+    }
+
+    /// The denied keys present in `vars`, sorted
+    pub fn denied_keys(&self, vars: &HashMap<String, String>) -> Vec<String> {
+        let mut keys: Vec<String> = vars
+            .keys()
+            .filter(|key| self.is_denied(key))
+            .cloned()
+            .collect();
+        keys.sort();
+        keys
+    }
+}
+
 impl Profile {
     pub fn new() -> Self {
         Profile::default()
@@ -24,18 +312,93 @@ impl Profile {
     pub fn clear(&mut self) {
         self.variables.clear();
         self.profiles.clear();
+        self.disabled_profiles.clear();
+        self.unset.clear();
+        self.on_activate.clear();
+        self.on_deactivate.clear();
+        self.pre_activate.clear();
+        self.post_activate.clear();
+    }
+
+    pub fn hooks(&self) -> Hooks {
+        Hooks {
+            on_activate: self.on_activate.clone(),
+            on_deactivate: self.on_deactivate.clone(),
+            pre_activate: self.pre_activate.clone(),
+            post_activate: self.post_activate.clone(),
+            computed: self.computed_commands(),
+        }
+    }
+
+    /// The commands of the variables this profile computes, by key. Only plain keys
+    /// are computed; a list key keeps a `command:` value as written.
+    pub fn computed_commands(&self) -> BTreeMap<String, String> {
+        self.variables
+            .iter()
+            .filter(|(key, _)| VarOp::parse(key).1 == VarOp::Set)
+            .filter_map(|(key, value)| {
+                computed_command(value).map(|command| (key.clone(), command.to_string()))
+            })
+            .collect()
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
     }
 
     pub fn is_empty(&self) -> bool {
-        self.variables.is_empty() && self.profiles.is_empty()
+        self.variables.is_empty() && self.profiles.is_empty() && self.unset.is_empty()
     }
 
-    pub fn add_profile(&mut self, name: &str) {
-        self.profiles.insert(name.to_string());
+    /// Add `name` to the dependencies of profile `owner`, enabling it if it was disabled.
+    ///
+    /// Returns `false` without changing anything when `name` is `owner` itself or
+    /// already listed.
+    pub fn add_profile(&mut self, owner: &str, name: &str) -> bool {
+        if name == owner {
+            return false;
+        }
+        self.disabled_profiles.remove(name);
+        self.profiles.insert(name.to_string())
     }
 
-    pub fn remove_profile(&mut self, name: &str) {
-        self.profiles.retain(|p| p != name);
+    /// Remove the dependency `name`, returning whether it was listed
+    pub fn remove_profile(&mut self, name: &str) -> bool {
+        self.disabled_profiles.remove(name);
+        self.profiles.remove(name)
+    }
+
+    /// Replace the dependency `old` with `new`, keeping it disabled if it was;
+    /// returns whether `old` was listed
+    pub fn rename_profile(&mut self, owner: &str, old: &str, new: &str) -> bool {
+        let disabled = self.is_profile_disabled(old);
+        if !self.remove_profile(old) {
+            return false;
+        }
+        if self.add_profile(owner, new) && disabled {
+            self.disabled_profiles.insert(new.to_string());
+        }
+        true
+    }
+
+    pub fn is_profile_disabled(&self, name: &str) -> bool {
+        self.disabled_profiles.contains(name)
+    }
+
+    /// The dependencies that take part in resolution
+    pub fn enabled_profiles(&self) -> impl Iterator<Item = &String> {
+        self.profiles
+            .iter()
+            .filter(|name| !self.disabled_profiles.contains(*name))
+    }
+
+    /// Disable or enable the listed dependency `name`
+    pub fn set_profile_disabled(&mut self, name: &str, disabled: bool) {
+        if !disabled {
+            self.disabled_profiles.remove(name);
+        } else if self.profiles.contains(name) {
+            self.disabled_profiles.insert(name.to_string());
+        }
     }
 
     pub fn add_variable(&mut self, key: &str, value: &str) {
@@ -43,47 +406,314 @@ impl Profile {
     }
 
     pub fn remove_variable(&mut self, key: &str) -> Option<String> {
-        self.variables.remove(key)
+        self.variables.shift_remove(key)
     }
 
+    pub fn add_unset(&mut self, key: &str) {
+        self.unset.insert(key.to_string());
+    }
+
+    pub fn remove_unset(&mut self, key: &str) -> bool {
+        self.unset.remove(key)
+    }
+
+    /// The merged variables of this profile and everything it inherits from, without
+    /// the variables that end up unset.
+    ///
+    /// `name` is this profile's own name, [`GLOBAL_PROFILE_MARK`] for the global one.
+    ///
+    /// [`GLOBAL_PROFILE_MARK`]: crate::GLOBAL_PROFILE_MARK
     pub fn collect_vars(
         &self,
+        name: &str,
         config_manager: &ConfigManager,
     ) -> Result<HashMap<String, String>, Box<dyn std::error::Error>> {
-        let mut all_profiles_to_load = Vec::new();
-        let mut seen_profiles = HashSet::new();
-
-        for profile_name in self.profiles.iter() {
-            let ordered_deps = config_manager.resolve_dependencies(profile_name)?;
-            ordered_deps.into_iter().for_each(|dep| {
-                if seen_profiles.insert(dep.clone()) {
-                    all_profiles_to_load.push(dep);
-                }
-            })
+        Ok(self.collect_env(name, config_manager)?.0)
+    }
+
+    /// The merged variables of this profile, as [`Profile::collect_vars`], and the
+    /// variables resolving it removes from the environment.
+    ///
+    /// A profile's `unset` beats whatever the profiles before it set, and a later
+    /// profile that sets the variable again beats the `unset`.
+    pub fn collect_env(
+        &self,
+        name: &str,
+        config_manager: &ConfigManager,
+    ) -> Result<ResolvedEnv, Box<dyn std::error::Error>> {
+        let mut vars = HashMap::new();
+        let mut unset = HashSet::new();
+        for (profile_name, profile) in self.resolved_profiles(config_manager)? {
+            let placeholders = config_manager.placeholders(profile_name.as_deref().unwrap_or(name));
+            merge_profile(&mut vars, &mut unset, profile, &placeholders);
         }
+        Ok((vars, unset))
+    }
+
+    /// Like [`Profile::collect_vars`], also tracking the profiles behind each value.
+    ///
+    /// `name` is this profile's own name, which its values are attributed to.
+    pub fn collect_merged(
+        &self,
+        name: &str,
+        config_manager: &ConfigManager,
+    ) -> Result<MergedEnv, Box<dyn std::error::Error>> {
+        let mut merged = MergedEnv::default();
+        self.merge_into(name, &mut merged, config_manager)?;
+        Ok(merged)
+    }
+
+    /// Like [`Profile::collect_merged`], on top of the variables already in `merged`,
+    /// so list keys extend their current values rather than the process environment
+    pub fn merge_into(
+        &self,
+        name: &str,
+        merged: &mut MergedEnv,
+        config_manager: &ConfigManager,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        for (profile_name, profile) in self.resolved_profiles(config_manager)? {
+            let source = profile_name.as_deref().unwrap_or(name);
+            merged.merge(source, profile, &config_manager.placeholders(source));
+        }
+        Ok(())
+    }
 
-        // also add the initial profiles themselves
-        for profile_name in self.profiles.iter() {
-            if seen_profiles.insert(profile_name.clone()) {
-                all_profiles_to_load.push(profile_name.clone());
+    /// The entries each list variable gains from the resolved profiles, for variables
+    /// that no profile sets outright. `name` is as for [`Profile::collect_vars`].
+    pub fn collect_list_entries(
+        &self,
+        name: &str,
+        config_manager: &ConfigManager,
+    ) -> Result<HashMap<String, Vec<String>>, Box<dyn std::error::Error>> {
+        let mut entries: HashMap<String, Vec<String>> = HashMap::new();
+        let mut set_outright = HashSet::new();
+        for (profile_name, profile) in self.resolved_profiles(config_manager)? {
+            let placeholders = config_manager.placeholders(profile_name.as_deref().unwrap_or(name));
+            for (key, value) in &profile.variables {
+                match VarOp::parse(key) {
+                    (name, VarOp::Set) => {
+                        set_outright.insert(name.to_string());
+                    }
+                    (name, _) => entries
+                        .entry(name.to_string())
+                        .or_default()
+                        .extend(split_list(&placeholders.expand(value)).map(str::to_string)),
+                }
+            }
+            // Entries added before an unset are gone, later ones extend an empty list
+            for name in &profile.unset {
+                entries.remove(name);
+                set_outright.insert(name.clone());
             }
         }
+        entries.retain(|name, _| !set_outright.contains(name));
+        Ok(entries)
+    }
 
-        //  Collect variables from all resolved profiles in order
-        let mut vars = HashMap::new();
+    /// Every profile this one inherits from in dependency order with its name, followed
+    /// by itself (named `None`)
+    fn resolved_profiles<'a>(
+        &'a self,
+        config_manager: &'a ConfigManager,
+    ) -> Result<Vec<ResolvedProfile<'a>>, Box<dyn std::error::Error>> {
+        let mut direct: Vec<&String> = self.enabled_profiles().collect();
+        direct.sort();
+        let all_profiles_to_load = config_manager.resolve_many(&direct)?;
+
+        let mut profiles = Vec::with_capacity(all_profiles_to_load.len() + 1);
         for profile_name in all_profiles_to_load {
             if let Some(profile) = config_manager.get_profile(&profile_name) {
-                vars.extend(profile.variables.clone());
+                profiles.push((Some(profile_name), profile));
             } else {
                 // This should ideally not happen if resolve_dependencies works correctly
                 return Err(format!("Profile `{profile_name}` not found during activation").into());
             }
         }
+        profiles.push((None, self));
+
+        Ok(profiles)
+    }
+}
+
+/// A resolved variable value and the profiles behind it
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProvenancedValue {
+    pub value: String,
+    /// Profiles making up the value: the last one to set it outright, then those that
+    /// extended the list since
+    pub sources: Vec<String>,
+    /// Every profile that defined the variable since it was last unset, in override order
+    pub chain: Vec<String>,
+}
+
+impl ProvenancedValue {
+    fn record(&mut self, source: &str, op: VarOp) {
+        if op == VarOp::Set {
+            self.sources.clear();
+        }
+        if !self.sources.iter().any(|s| s == source) {
+            self.sources.push(source.to_string());
+        }
+        if self.chain.last().map(String::as_str) != Some(source) {
+            self.chain.push(source.to_string());
+        }
+    }
+}
+
+/// Variables merged from several profiles, remembering where each value came from
+#[derive(Clone, Debug, Default)]
+pub struct MergedEnv {
+    pub vars: HashMap<String, ProvenancedValue>,
+    /// Variables removed from the environment
+    pub unset: HashSet<String>,
+}
+
+impl MergedEnv {
+    /// Merge `profile`, known as `source`, on top of the variables so far
+    pub fn merge(&mut self, source: &str, profile: &Profile, placeholders: &Placeholders) {
+        let mut vars = self.values();
+        let mut provenance = std::mem::take(&mut self.vars);
+        for key in profile.variables.keys() {
+            let (name, op) = VarOp::parse(key);
+            provenance
+                .entry(name.to_string())
+                .or_default()
+                .record(source, op);
+        }
+        merge_profile(&mut vars, &mut self.unset, profile, placeholders);
+        self.vars = Self::attach(vars, provenance);
+    }
+
+    /// Apply a single `key = value` pair on top, as a direct item known as `source`
+    pub fn merge_variable(&mut self, source: &str, key: &str, value: &str) {
+        let mut vars = self.values();
+        let mut provenance = std::mem::take(&mut self.vars);
+        let (name, op) = VarOp::parse(key);
+        provenance
+            .entry(name.to_string())
+            .or_default()
+            .record(source, op);
+        apply_variable(&mut vars, key, value);
+        self.unset.remove(name);
+        self.vars = Self::attach(vars, provenance);
+    }
+
+    /// Attribute to `to` the values that came from the profile known as `from`
+    pub fn rename_source(&mut self, from: &str, to: &str) {
+        for var in self.vars.values_mut() {
+            for source in var.sources.iter_mut().chain(var.chain.iter_mut()) {
+                if source == from {
+                    *source = to.to_string();
+                }
+            }
+        }
+    }
 
-        vars.extend(self.variables.clone());
+    /// The merged values without their provenance
+    pub fn values(&self) -> HashMap<String, String> {
+        self.vars
+            .iter()
+            .map(|(key, var)| (key.clone(), var.value.clone()))
+            .collect()
+    }
 
-        Ok(vars)
+    fn attach(
+        vars: HashMap<String, String>,
+        mut provenance: HashMap<String, ProvenancedValue>,
+    ) -> HashMap<String, ProvenancedValue> {
+        vars.into_iter()
+            .map(|(key, value)| {
+                let mut var = provenance.remove(&key).unwrap_or_default();
+                var.value = value;
+                (key, var)
+            })
+            .collect()
+    }
+}
+
+/// Merge one profile into `vars`, then remove the variables it unsets.
+///
+/// `unset` holds the variables removed so far; setting one again takes it off the set,
+/// and extending one starts from an empty list rather than the process environment.
+fn merge_profile(
+    vars: &mut HashMap<String, String>,
+    unset: &mut HashSet<String>,
+    profile: &Profile,
+    placeholders: &Placeholders,
+) {
+    for key in profile.variables.keys() {
+        let (name, op) = VarOp::parse(key);
+        if unset.remove(name) && op != VarOp::Set {
+            vars.entry(name.to_string()).or_default();
+        }
     }
+    merge_variables(vars, &profile.variables, placeholders);
+    for name in &profile.unset {
+        vars.remove(name);
+        unset.insert(name.clone());
+    }
+}
+
+/// Merge one profile's variables into `vars`.
+///
+/// Plain keys are applied before list keys, so `PATH` and `PATH+` in the same
+/// profile set the list and then extend it. Built-in placeholders in the values are
+/// expanded first.
+pub fn merge_variables(
+    vars: &mut HashMap<String, String>,
+    variables: &IndexMap<String, String>,
+    placeholders: &Placeholders,
+) {
+    let (set, lists): (Vec<_>, Vec<_>) = variables
+        .iter()
+        .partition(|(key, _)| VarOp::parse(key).1 == VarOp::Set);
+    for (key, value) in set.into_iter().chain(lists) {
+        apply_variable(vars, key, &placeholders.expand(value));
+    }
+}
+
+/// Apply a single stored `key = value` pair to `vars`.
+///
+/// A list key extends the current value of the variable, or its value in the process
+/// environment when no profile has set it yet, dropping entries already present.
+pub fn apply_variable(vars: &mut HashMap<String, String>, key: &str, value: &str) {
+    let (name, op) = VarOp::parse(key);
+    let merged = match op {
+        VarOp::Set => value.to_string(),
+        VarOp::Append | VarOp::Prepend => {
+            let current = vars
+                .get(name)
+                .cloned()
+                .or_else(|| env::var(name).ok())
+                .unwrap_or_default();
+            if op == VarOp::Append {
+                join_list(split_list(&current).chain(split_list(value)))
+            } else {
+                join_list(split_list(value).chain(split_list(&current)))
+            }
+        }
+    };
+    vars.insert(name.to_string(), merged);
+}
+
+/// Remove `entries` from a list value, keeping the order of what remains
+pub fn remove_list_entries(value: &str, entries: &[String]) -> String {
+    join_list(split_list(value).filter(|entry| !entries.iter().any(|e| e == entry)))
+}
+
+fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split(LIST_SEPARATOR)
+        .filter(|entry| !entry.is_empty())
+}
+
+/// Join list entries, keeping only the first occurrence of each
+fn join_list<'a>(entries: impl Iterator<Item = &'a str>) -> String {
+    let mut seen = HashSet::new();
+    entries
+        .filter(|entry| seen.insert(*entry))
+        .collect::<Vec<_>>()
+        .join(&LIST_SEPARATOR.to_string())
 }
 
 impl std::ops::Deref for ProfileNames {
@@ -0,0 +1,72 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const RECENT_FILE_NAME: &str = "recent.json";
+
+/// How many recently edited profiles are remembered
+pub const MAX_RECENT: usize = 9;
+
+pub fn recent_path(base_path: &Path) -> PathBuf {
+    base_path.join(RECENT_FILE_NAME)
+}
+
+/// The profiles most recently opened in the TUI editor, most recent first.
+///
+/// Kept in `recent.json` as an array of names. Like the history log it is best-effort:
+/// a missing or unreadable file is an empty list and write failures are ignored.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RecentProfiles {
+    names: Vec<String>,
+}
+
+impl RecentProfiles {
+    pub fn load(base_path: &Path) -> Self {
+        let names = fs::read_to_string(recent_path(base_path))
+            .ok()
+            .and_then(|content| serde_json::from_str::<Vec<String>>(&content).ok())
+            .unwrap_or_default();
+        let mut recent = Self::default();
+        // Oldest first, so the most recent ends up in front
+        for name in names.into_iter().take(MAX_RECENT).rev() {
+            recent.touch(&name);
+        }
+        recent
+    }
+
+    pub fn save(&self, base_path: &Path) {
+        if let Ok(content) = serde_json::to_string(&self.names) {
+            let _ = fs::write(recent_path(base_path), format!("{content}\n"));
+        }
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Move `name` to the front, dropping the oldest entry when the list is full
+    pub fn touch(&mut self, name: &str) {
+        self.names.retain(|n| n != name);
+        self.names.insert(0, name.to_string());
+        self.names.truncate(MAX_RECENT);
+    }
+
+    pub fn rename(&mut self, from: &str, to: &str) {
+        self.names.retain(|n| n != to);
+        if let Some(name) = self.names.iter_mut().find(|n| *n == from) {
+            *name = to.to_string();
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.names.retain(|n| n != name);
+    }
+
+    /// Drop the names `keep` rejects
+    pub fn retain(&mut self, keep: impl Fn(&str) -> bool) {
+        self.names.retain(|n| keep(n));
+    }
+}
@@ -0,0 +1,120 @@
+use std::fmt;
+use toml::{Table, Value};
+
+/// The type a field of a profile file must have
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldType {
+    String,
+    StringList,
+    /// A table whose values are all strings, such as `[variables]`
+    StringTable,
+    /// One of the listed strings
+    OneOf(&'static [&'static str]),
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldType::String => write!(f, "a string"),
+            FieldType::StringList => write!(f, "an array of strings"),
+            FieldType::StringTable => write!(f, "a table of strings"),
+            FieldType::OneOf(values) => {
+                let values: Vec<String> = values.iter().map(|v| format!("\"{v}\"")).collect();
+                write!(f, "one of {}", values.join(", "))
+            }
+        }
+    }
+}
+
+/// Every field a profile file may set, with its type. Fields not listed here are
+/// ignored, as they are when the profile is read.
+pub const PROFILE_SCHEMA: &[(&str, FieldType)] = &[
+    ("description", FieldType::String),
+    ("tags", FieldType::StringList),
+    ("on_activate", FieldType::StringList),
+    ("on_deactivate", FieldType::StringList),
+    ("pre_activate", FieldType::StringList),
+    ("post_activate", FieldType::StringList),
+    ("validation", FieldType::OneOf(&["relaxed", "strict"])),
+    ("variables", FieldType::StringTable),
+    ("profiles", FieldType::StringList),
+    ("disabled_profiles", FieldType::StringList),
+    ("unset", FieldType::StringList),
+];
+
+/// A field of a profile file with the wrong type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaError {
+    /// Path of the offending value, like `variables.PORT` or `tags[1]`
+    pub field: String,
+    pub expected: FieldType,
+    /// What the file has instead, like `integer 8080`
+    pub found: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` must be {}, found {}",
+            self.field, self.expected, self.found
+        )
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+/// Check the fields of a parsed profile file against [`PROFILE_SCHEMA`], reporting the
+/// first value of the wrong type
+pub fn validate_profile(table: &Table) -> Result<(), SchemaError> {
+    for (field, expected) in PROFILE_SCHEMA {
+        if let Some(value) = table.get(*field) {
+            validate_field(field, value, *expected)?;
+        }
+    }
+    Ok(())
+}
+
+fn validate_field(field: &str, value: &Value, expected: FieldType) -> Result<(), SchemaError> {
+    match (expected, value) {
+        (FieldType::String, Value::String(_)) => Ok(()),
+        (FieldType::OneOf(values), Value::String(s)) if values.contains(&s.as_str()) => Ok(()),
+        (FieldType::StringList, Value::Array(items)) => {
+            for (i, item) in items.iter().enumerate() {
+                if !item.is_str() {
+                    return Err(SchemaError {
+                        field: format!("{field}[{i}]"),
+                        expected: FieldType::String,
+                        found: describe(item),
+                    });
+                }
+            }
+            Ok(())
+        }
+        (FieldType::StringTable, Value::Table(entries)) => {
+            for (key, item) in entries {
+                if !item.is_str() {
+                    return Err(SchemaError {
+                        field: format!("{field}.{key}"),
+                        expected: FieldType::String,
+                        found: describe(item),
+                    });
+                }
+            }
+            Ok(())
+        }
+        _ => Err(SchemaError {
+            field: field.to_string(),
+            expected,
+            found: describe(value),
+        }),
+    }
+}
+
+/// The type of `value`, with the value itself when it is short enough to quote
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Array(_) | Value::Table(_) => value.type_str().to_string(),
+        _ => format!("{} {value}", value.type_str()),
+    }
+}
@@ -0,0 +1,51 @@
+//! Figures summarizing the loaded profiles, for `profile stats`.
+
+use super::ConfigManager;
+use super::models::VarOp;
+use crate::GLOBAL_PROFILE_MARK;
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProfileStats {
+    pub profiles: usize,
+    /// Distinct variable names set by any profile; `PATH+` counts as `PATH`
+    pub unique_keys: usize,
+    pub average_variables: f64,
+    /// The longest chain of dependencies, in edges
+    pub max_depth: usize,
+    /// Profiles no other profile depends on
+    pub without_dependents: usize,
+}
+
+impl ProfileStats {
+    /// The figures of the profiles `config_manager` has loaded
+    pub fn of(config_manager: &ConfigManager) -> Self {
+        let mut profiles = 0;
+        let mut variables = 0;
+        let mut keys = HashSet::new();
+        for (name, profile) in config_manager.profiles_iter() {
+            if name == GLOBAL_PROFILE_MARK {
+                continue;
+            }
+            profiles += 1;
+            variables += profile.variables.len();
+            keys.extend(profile.variables.keys().map(|key| VarOp::parse(key).0));
+        }
+
+        ProfileStats {
+            profiles,
+            unique_keys: keys.len(),
+            average_variables: if profiles == 0 {
+                0.0
+            } else {
+                variables as f64 / profiles as f64
+            },
+            max_depth: config_manager.max_depth_overall(),
+            without_dependents: config_manager
+                .profiles_without_dependents()
+                .iter()
+                .filter(|name| *name != GLOBAL_PROFILE_MARK)
+                .count(),
+        }
+    }
+}
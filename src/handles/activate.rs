@@ -1,25 +1,90 @@
+use crate::api;
 use crate::config::ConfigManager;
+use crate::config::env_size::EnvSize;
+use crate::config::models::{self, HookKind, MergedEnv};
 use crate::utils;
-use crate::utils::display;
-use std::collections::HashMap;
+use crate::utils::ask::{Asker, Choice};
+use crate::utils::display::{self, Verbosity};
+use crate::utils::session;
+use crate::utils::shell_generate::ShellGenerate;
+use colored::Colorize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::env;
+use std::io::{self, IsTerminal};
+use std::path::PathBuf;
 
-pub fn handle(items: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
-    let mut config_manager = ConfigManager::new()?;
+/// How to settle a variable that activated profiles set to different values
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Conflicts {
+    /// The last profile wins, without asking
+    #[default]
+    LastWins,
+    /// Ask on the terminal which value wins
+    Ask,
+    /// Ask, and remember the answers for the rest of the session
+    AskAndRemember,
+}
+
+/// The flags of `use`
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    /// Print the shell commands instead of applying them
+    pub print: bool,
+    pub shell: Option<String>,
+    pub allow_hooks: bool,
+    /// Show where each variable comes from instead of activating anything
+    pub explain: bool,
+    pub conflicts: Conflicts,
+    /// Refuse to activate variables larger than `max_env_size` instead of warning
+    pub strict_size: bool,
+    pub verbosity: Verbosity,
+}
+
+pub fn handle(
+    items: Vec<String>,
+    options: Options,
+    profile_dir: Option<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Options {
+        print,
+        shell,
+        allow_hooks,
+        explain,
+        conflicts,
+        strict_size,
+        verbosity,
+    } = options;
+    let mut config_manager = ConfigManager::new(profile_dir)?;
+    // Replace @group references by their profiles before anything else
+    let items = config_manager.read_groups()?.expand(items)?;
+    let items = expand_globs(items, &config_manager)?;
 
     // Separate direct key-value pairs from profile names
     let (key_value_items, profile_items): (Vec<_>, Vec<_>) =
         items.into_iter().partition(|item| item.contains('='));
+    super::ensure_profiles_exist(&config_manager, &profile_items)?;
 
-    let mut vars = HashMap::new();
+    // The global settings are the lowest layer under the activated profiles
+    let mut merged = if profile_items.is_empty() {
+        MergedEnv::default()
+    } else {
+        api::resolve_global(&mut config_manager).unwrap_or_else(|e| {
+            display::show_warning(&format!("Ignoring the global settings: {e}"));
+            MergedEnv::default()
+        })
+    };
+    // List keys of the profiles extend what the global settings added
+    let global_vars = merged.vars.clone();
+    api::merge_profiles(&mut config_manager, &mut merged, &profile_items)?;
+    let profiles_set_nothing = merged.vars == global_vars;
 
-    for profile_name in &profile_items {
-        config_manager.load_profile(profile_name)?;
-        vars.extend(
-            config_manager
-                .get_profile(profile_name)
-                .unwrap()
-                .collect_vars(&config_manager)?,
-        );
+    let choices = if conflicts == Conflicts::LastWins {
+        Vec::new()
+    } else {
+        choose_conflicts(&mut config_manager, &profile_items, &merged)?
+    };
+    for choice in &choices {
+        merged.merge_variable(&choice.source, &choice.key, &choice.value);
     }
 
     // Add direct key-value pairs, potentially overwriting profile variables
@@ -28,28 +93,341 @@ pub fn handle(items: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
         if let Some((key, value)) = item.split_once('=')
             && !key.is_empty()
         {
-            vars.insert(key.to_string(), value.to_string());
-            direct_keys.push(key.to_string());
+            merged.merge_variable(COMMAND_LINE_SOURCE, key, value);
+            direct_keys.push(models::VarOp::parse(key).0.to_string());
+        }
+    }
+    let mut vars = merged.values();
+    let mut unset = merged.unset.clone();
+
+    // Denied variables never reach the shell
+    let denied = api::apply_denylist(&config_manager, &mut vars)?;
+    if !denied.is_empty() {
+        display::show_warning(&format!("Skipping denied variables: {}", denied.join(", ")));
+        direct_keys.retain(|key| !denied.contains(key));
+    }
+
+    // Commands started from an oversized environment fail with E2BIG far from the cause
+    let size = EnvSize::of(
+        merged
+            .vars
+            .iter()
+            .filter(|(key, _)| vars.contains_key(*key)),
+    );
+    let limit = config_manager.read_settings()?.max_env_size();
+    if size.exceeds(limit) {
+        let warning = size.warning(limit);
+        if strict_size {
+            return Err(format!(
+                "Refusing to activate, the environment would be too large: {warning}"
+            )
+            .into());
+        }
+        display::show_warning(&format!(
+            "The environment is large, commands may fail with \"Argument list too long\": {warning}"
+        ));
+    }
+
+    let overridden = overridden_profiles(&mut config_manager, &profile_items, &direct_keys)?;
+
+    // Unset variables are not tracked, so deactivating never brings them back
+    unset.retain(|key| env::var_os(key).is_some());
+
+    if explain {
+        print_explanation(&merged, &vars, &unset, verbosity);
+        return Ok(());
+    }
+
+    let activation_hooks = api::activation_hooks(&mut config_manager, &profile_items)?;
+    let requested = activation_hooks.names();
+    let global_computed =
+        super::hooks::allowed_hooks(&config_manager, activation_hooks.global, allow_hooks, print)?;
+    let hooks = super::hooks::allowed_hooks(
+        &config_manager,
+        activation_hooks.profiles,
+        allow_hooks,
+        print,
+    )?;
+    let untrusted: Vec<String> = requested
+        .into_iter()
+        .filter(|name| {
+            !hooks
+                .iter()
+                .chain(&global_computed)
+                .any(|(allowed, _)| allowed == name)
+        })
+        .collect();
+    api::drop_untrusted_computed(&mut vars, &merged, &untrusted);
+    let in_process = |kind: HookKind| hooks.iter().any(|(_, h)| !h.commands(kind).is_empty());
+    if print && (in_process(HookKind::PreActivate) || in_process(HookKind::PostActivate)) {
+        verbosity.show_info("Not running the pre_activate and post_activate hooks in a preview.");
+    } else if !print {
+        super::hooks::run_in_process(&hooks, HookKind::PreActivate, &vars, &unset)?;
+    }
+
+    let mut generate = ShellGenerate::for_shell(shell.as_deref())?;
+    if conflicts == Conflicts::AskAndRemember {
+        for choice in &choices {
+            session::remember_choice(&choice.key, &choice.value, &mut generate);
         }
     }
+    api::write_activation(&mut generate, &profile_items, &vars, &unset, &hooks);
 
-    let mut generate = utils::shell_generate::ShellGenerate::new();
-    generate.export_from_map(&vars);
+    if print {
+        generate.output_preview();
+        return Ok(());
+    }
     generate.output();
+    if let Err(e) = super::hooks::run_in_process(&hooks, HookKind::PostActivate, &vars, &unset) {
+        display::show_warning(&e.to_string());
+    }
+
+    if !profile_items.is_empty() && profiles_set_nothing && unset.is_empty() {
+        let message = match profile_items.as_slice() {
+            [name] => format!(
+                "Profile '{name}' resolves to no variables. Run `em profile show {name}` to inspect it."
+            ),
+            names => format!(
+                "Profiles {} resolve to no variables. Run `em profile show <name>` to inspect them.",
+                names.join(", ")
+            ),
+        };
+        verbosity.show_info(&message);
+    } else {
+        for (name, keys) in &overridden {
+            verbosity.show_info(&format!(
+                "Profile '{name}' has no effect, later items override all of its variables: {}",
+                keys.join(", ")
+            ));
+        }
+    }
 
     if !profile_items.is_empty() {
-        display::show_success(&format!(
+        verbosity.show_success(&format!(
             "Successfully activated profiles: {}",
             profile_items.join(", ")
         ));
     }
 
     if !direct_keys.is_empty() {
-        display::show_success(&format!(
+        verbosity.show_success(&format!(
             "Set environment variables: {}",
             direct_keys.join(", ")
         ));
     }
 
+    if !unset.is_empty() {
+        let mut keys: Vec<String> = unset.into_iter().collect();
+        keys.sort();
+        verbosity.show_success(&format!("Unset environment variables: {}", keys.join(", ")));
+    }
+
     Ok(())
 }
+
+/// The source of variables given as `KEY=VALUE` items
+const COMMAND_LINE_SOURCE: &str = "(command line)";
+
+/// The source of a value typed in when profiles disagreed
+const CUSTOM_SOURCE: &str = "(custom)";
+/// The source of a value chosen earlier in the session
+const REMEMBERED_SOURCE: &str = "(remembered)";
+
+/// The value that wins for a variable the activated profiles disagree on
+struct ConflictChoice {
+    key: String,
+    value: String,
+    source: String,
+}
+
+/// Ask which value wins for each variable that activated profiles set to different
+/// values, reusing the answers remembered in this session.
+///
+/// Without a terminal on stderr nothing is asked and the last profile wins, with a warning.
+fn choose_conflicts(
+    config_manager: &mut ConfigManager,
+    profile_items: &[String],
+    merged: &MergedEnv,
+) -> Result<Vec<ConflictChoice>, Box<dyn std::error::Error>> {
+    if profile_items.len() < 2 {
+        return Ok(Vec::new());
+    }
+
+    // Each profile's value of every variable, in activation order
+    let mut values: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
+    for name in profile_items {
+        for (key, value) in api::resolve_env(config_manager, name)? {
+            values.entry(key).or_default().push((name.clone(), value));
+        }
+    }
+
+    let mut asker = io::stderr().is_terminal().then(Asker::stdio);
+    let mut choices = Vec::new();
+    let mut unasked = Vec::new();
+    for (key, values) in values {
+        // A later profile may unset the variable, leaving nothing to choose
+        if !merged.vars.contains_key(&key) || values.iter().all(|(_, v)| *v == values[0].1) {
+            continue;
+        }
+        if let Some(value) = session::remembered_choice(&key) {
+            choices.push(ConflictChoice {
+                key,
+                value,
+                source: REMEMBERED_SOURCE.to_string(),
+            });
+            continue;
+        }
+        let Some(asker) = asker.as_mut() else {
+            unasked.push(key);
+            continue;
+        };
+
+        let options: Vec<String> = values
+            .iter()
+            .map(|(name, value)| format!("{name}: {}", value.replace('\n', "\\n")))
+            .collect();
+        let question = format!("Profiles set '{key}' to different values:");
+        let (value, source) = match asker.choose(&question, &options, values.len() - 1)? {
+            Choice::Option(i) => {
+                let (name, value) = values[i].clone();
+                (value, name)
+            }
+            Choice::Custom(value) => (value, CUSTOM_SOURCE.to_string()),
+        };
+        choices.push(ConflictChoice { key, value, source });
+    }
+
+    if !unasked.is_empty() {
+        display::show_warning(&format!(
+            "Cannot ask which value wins without a terminal, the last profile wins for: {}",
+            unasked.join(", ")
+        ));
+    }
+    Ok(choices)
+}
+
+/// Print a table of the variables that would be set, each with the profiles that
+/// supplied its value and every profile that defined it, in override order
+fn print_explanation(
+    merged: &MergedEnv,
+    vars: &HashMap<String, String>,
+    unset: &HashSet<String>,
+    verbosity: Verbosity,
+) {
+    let mut keys: Vec<&String> = vars.keys().collect();
+    keys.sort();
+
+    let header = ["VARIABLE", "VALUE", "SOURCE", "DEFINED BY"].map(str::to_string);
+    let rows: Vec<[String; 4]> = keys
+        .into_iter()
+        .filter_map(|key| merged.vars.get(key).map(|var| (key, var)))
+        .map(|(key, var)| {
+            [
+                key.clone(),
+                var.value.replace('\n', "\\n"),
+                var.sources.join(", "),
+                var.chain.join(" -> "),
+            ]
+        })
+        .collect();
+
+    if rows.is_empty() {
+        verbosity.show_info("No variables would be set.");
+    } else {
+        let mut widths = header.clone().map(|cell| cell.chars().count());
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        let format_row = |row: &[String; 4]| {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{cell:<width$}"))
+                .collect();
+            cells.join("  ").trim_end().to_string()
+        };
+        eprintln!("{}", format_row(&header).yellow());
+        for row in &rows {
+            eprintln!("{}", format_row(row));
+        }
+    }
+
+    if !unset.is_empty() {
+        let mut keys: Vec<&String> = unset.iter().collect();
+        keys.sort();
+        let keys: Vec<&str> = keys.into_iter().map(String::as_str).collect();
+        verbosity.show_info(&format!("Would unset: {}", keys.join(", ")));
+    }
+}
+
+/// A profile name and the variables later items override
+type Overridden = (String, Vec<String>);
+
+/// Profiles whose every variable is set or unset again by a later profile or a direct
+/// `KEY=VALUE` item, paired with those variables
+fn overridden_profiles(
+    config_manager: &mut ConfigManager,
+    profile_items: &[String],
+    direct_keys: &[String],
+) -> Result<Vec<Overridden>, Box<dyn std::error::Error>> {
+    if profile_items.len() < 2 && direct_keys.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut touched = Vec::with_capacity(profile_items.len());
+    for name in profile_items {
+        let (vars, unset) = api::resolve_envs(config_manager, std::slice::from_ref(name))?;
+        let keys: BTreeSet<String> = vars.into_keys().chain(unset).collect();
+        touched.push(keys);
+    }
+
+    let mut later: BTreeSet<String> = direct_keys.iter().cloned().collect();
+    let mut overridden = Vec::new();
+    for (name, keys) in profile_items.iter().zip(&touched).rev() {
+        if !keys.is_empty() && keys.is_subset(&later) {
+            overridden.push((name.clone(), keys.iter().cloned().collect()));
+        }
+        later.extend(keys.iter().cloned());
+    }
+    overridden.reverse();
+    Ok(overridden)
+}
+
+/// Replace each profile glob such as `svc-*` by the profiles it matches, in name order
+fn expand_globs(
+    items: Vec<String>,
+    config_manager: &ConfigManager,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    if !items
+        .iter()
+        .any(|item| !item.contains('=') && utils::is_glob(item))
+    {
+        return Ok(items);
+    }
+
+    let mut profile_names = config_manager.scan_profile_names()?.0;
+    profile_names.sort();
+
+    let mut expanded = Vec::new();
+    for item in items {
+        if item.contains('=') || !utils::is_glob(&item) {
+            expanded.push(item);
+            continue;
+        }
+        let matches: Vec<&String> = profile_names
+            .iter()
+            .filter(|name| utils::glob_match(&item, name))
+            .collect();
+        if matches.is_empty() {
+            return Err(format!("No profiles match `{item}`").into());
+        }
+        for name in matches {
+            if !expanded.contains(name) {
+                expanded.push(name.clone());
+            }
+        }
+    }
+    Ok(expanded)
+}
@@ -1,41 +1,120 @@
+use crate::cli::CheckFormat;
 use crate::config::ConfigManager;
-use crate::utils::display;
+use crate::config::diagnostics::{self, Diagnostic, Severity};
+use crate::utils::display::Verbosity;
+use crate::utils::{display, json};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
 
-pub fn handle() -> Result<(), Box<dyn std::error::Error>> {
-    let mut config_manager = ConfigManager::new()?;
-
-    let profile_names = config_manager.scan_profile_names()?;
-
-    let mut found_issues = false;
+pub fn handle(
+    profile: Option<String>,
+    format: CheckFormat,
+    warnings_as_errors: bool,
+    fix: Option<Fix>,
+    profile_dir: Option<PathBuf>,
+    verbosity: Verbosity,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config_manager = ConfigManager::new(profile_dir)?;
+    if let Some(Fix { yes }) = fix {
+        return check_and_fix(&mut config_manager, yes, warnings_as_errors, verbosity);
+    }
 
-    for name in profile_names.iter() {
-        if let Err(e) = crate::utils::validate_profile_name(name) {
-            found_issues = true;
-            display::show_warning(&format!("Invalid profile name '{name}': {e}"));
-        }
+    let diagnostics = match &profile {
+        Some(name) => diagnostics::collect_profile(&mut config_manager, name)?,
+        None => diagnostics::collect(&mut config_manager)?,
+    };
 
-        if let Err(e) = config_manager.load_profile(name) {
-            found_issues = true;
-            match e {
-                crate::config::graph::DependencyError::MultipleErrors(errors) => {
-                    for err in errors {
-                        display::show_error(&format!("{err}"));
-                    }
-                }
-                _ => {
-                    display::show_error(&format!("{e}"));
-                }
-            }
+    match format {
+        CheckFormat::Human => report_human(&diagnostics, verbosity),
+        CheckFormat::Json => {
+            let profiles_dir = config_manager.base_path().join("profiles");
+            println!("{}", report_json(&diagnostics, &profiles_dir)?);
         }
     }
 
-    if !found_issues {
-        display::show_success("All profiles are valid.");
-    } else {
-        // Return an error to indicate failure? Or just exit?
-        // User said "report ... errors".
+    let failed = diagnostics.iter().any(|d| {
+        d.severity() == Severity::Error || (warnings_as_errors && d.severity() == Severity::Warning)
+    });
+    if failed {
         return Err("Found issues in profiles.".into());
     }
 
     Ok(())
 }
+
+/// `check --fix`; `yes` applies the default fixes without asking
+#[derive(Clone, Copy, Debug)]
+pub struct Fix {
+    pub yes: bool,
+}
+
+/// Report the issues found, repair them as `fix` does, then report what is left
+fn check_and_fix(
+    config_manager: &mut ConfigManager,
+    yes: bool,
+    warnings_as_errors: bool,
+    verbosity: Verbosity,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let found = diagnostics::collect(config_manager)?;
+    report_human(&found, verbosity);
+    if found.iter().all(|d| d.severity() == Severity::Info) {
+        return Ok(());
+    }
+
+    super::fix::require_terminal(yes)?;
+    config_manager.lock()?;
+    let edits = super::fix::apply(config_manager, yes)?;
+    super::fix::report_edits(&edits, verbosity);
+
+    let remaining = super::fix::remaining(config_manager)?;
+    super::fix::report_remaining(config_manager, &remaining);
+    if remaining.is_empty() {
+        verbosity.show_success("All profiles are valid.");
+    }
+
+    let failed = remaining.iter().any(|d| {
+        d.severity() == Severity::Error || (warnings_as_errors && d.severity() == Severity::Warning)
+    });
+    if failed {
+        return Err("Found issues in profiles that could not be fixed.".into());
+    }
+    Ok(())
+}
+
+fn report_human(diagnostics: &[Diagnostic], verbosity: Verbosity) {
+    for diagnostic in diagnostics {
+        match diagnostic.severity() {
+            Severity::Info => verbosity.show_info(&format!("{diagnostic}")),
+            Severity::Warning => display::show_warning(&format!("{diagnostic}")),
+            Severity::Error => display::show_error(&format!("{diagnostic}")),
+        }
+    }
+
+    if diagnostics.iter().all(|d| d.severity() == Severity::Info) {
+        verbosity.show_success("All profiles are valid.");
+    }
+}
+
+/// One finding in the `--format json` report
+#[derive(Serialize)]
+struct FindingJson<'a> {
+    severity: &'static str,
+    profile: &'a str,
+    file: String,
+    kind: &'static str,
+    message: String,
+}
+
+fn report_json(diagnostics: &[Diagnostic], profiles_dir: &Path) -> serde_json::Result<String> {
+    let findings: Vec<FindingJson> = diagnostics
+        .iter()
+        .map(|d| FindingJson {
+            severity: d.severity().as_str(),
+            profile: d.profile(),
+            file: d.file(profiles_dir).to_string_lossy().into_owned(),
+            kind: d.kind(),
+            message: d.to_string(),
+        })
+        .collect();
+    json::to_string_pretty(&findings)
+}
@@ -1,25 +1,56 @@
+use crate::api;
 use crate::config::ConfigManager;
-use crate::utils;
-use crate::utils::display;
-use std::collections::HashMap;
+use crate::config::models::{HookKind, Hooks, VarOp};
+use crate::utils::display::{self, Verbosity};
+use crate::utils::session::SessionState;
+use crate::utils::shell_generate::ShellGenerate;
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
 
-pub fn handle(items: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
-    let mut config_manager = ConfigManager::new()?;
+pub fn handle(
+    items: Vec<String>,
+    vars: Vec<String>,
+    all: bool,
+    shell: Option<String>,
+    allow_hooks: bool,
+    profile_dir: Option<PathBuf>,
+    verbosity: Verbosity,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut generate = ShellGenerate::for_shell(shell.as_deref())?;
+    if all {
+        return deactivate_all(generate, allow_hooks, profile_dir, verbosity);
+    }
+
+    let mut config_manager = ConfigManager::new(profile_dir)?;
+    let items = config_manager.read_groups()?.expand(items)?;
+    let mut session = SessionState::from_env();
 
     //  Separate direct key-value pairs from profile names
-    let (key_value_items, profile_items): (Vec<_>, Vec<_>) =
+    let (key_value_items, named_items): (Vec<_>, Vec<_>) =
         items.into_iter().partition(|item| item.contains('='));
+    for key in &vars {
+        if !is_active_variable(&mut config_manager, &session, key) {
+            return Err(format!(
+                "`{key}` is not a variable env-manage set or an active profile defines"
+            )
+            .into());
+        }
+    }
+    let mut single_keys = vars;
+    let mut profile_items = Vec::new();
+    for item in named_items {
+        match single_variable(&mut config_manager, &session, &item)? {
+            Some(key) => single_keys.push(key),
+            None => profile_items.push(item),
+        }
+    }
+    super::ensure_profiles_exist(&config_manager, &profile_items)?;
 
+    // Variables the profiles unset are not part of the resolved map, so they stay unset
     let mut vars = HashMap::new();
 
     for profile_name in &profile_items {
-        config_manager.load_profile(profile_name)?;
-        vars.extend(
-            config_manager
-                .get_profile(profile_name)
-                .unwrap()
-                .collect_vars(&config_manager)?,
-        );
+        vars.extend(api::resolve_env(&mut config_manager, profile_name)?);
     }
 
     // Add direct key-value pairs, potentially overwriting profile variables
@@ -28,24 +59,36 @@ pub fn handle(items: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
         if let Some((key, value)) = item.split_once('=')
             && !key.is_empty()
         {
-            vars.insert(key.to_string(), value.to_string());
-            direct_keys.push(key.to_string());
+            let name = VarOp::parse(key).0;
+            vars.insert(name.to_string(), value.to_string());
+            direct_keys.push(name.to_string());
         }
     }
+    for key in single_keys {
+        if !direct_keys.contains(&key) {
+            vars.insert(key.clone(), String::new());
+            direct_keys.push(key);
+        }
+    }
+
+    // Hooks run while the variables are still set, dependents before their dependencies
+    let hooks = api::resolve_hooks(&mut config_manager, &profile_items, &[HookKind::Deactivate])?;
+    run_hooks(&config_manager, hooks, allow_hooks, &mut generate)?;
 
-    let mut generate = utils::shell_generate::ShellGenerate::new();
-    generate.unset_from_map(&vars);
+    let keys: Vec<String> = vars.into_keys().collect();
+    session.untrack(&profile_items, &keys, &mut generate);
+    session.write(&mut generate);
     generate.output();
 
     if !profile_items.is_empty() {
-        display::show_success(&format!(
+        verbosity.show_success(&format!(
             "Successfully deactivated profiles: {}",
             profile_items.join(", ")
         ));
     }
 
     if !direct_keys.is_empty() {
-        display::show_success(&format!(
+        verbosity.show_success(&format!(
             "Cleared environment variables: {}",
             direct_keys.join(", ")
         ));
@@ -53,3 +96,121 @@ pub fn handle(items: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+/// The variable `item` names instead of a profile: `profile:KEY` for a variable the
+/// profile sets, or a variable env-manage set in this session that is no profile.
+/// A profile wins over a variable of the same name, with a warning.
+fn single_variable(
+    config_manager: &mut ConfigManager,
+    session: &SessionState,
+    item: &str,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    if let Some((profile, key)) = item.split_once(':')
+        && config_manager.profile_exists(profile)
+    {
+        if !api::resolve_env(config_manager, profile)?.contains_key(key) {
+            return Err(format!("Profile `{profile}` does not set `{key}`").into());
+        }
+        return Ok(Some(key.to_string()));
+    }
+
+    let tracked = session.tracked_vars().iter().any(|key| key == item);
+    if config_manager.profile_exists(item) {
+        if tracked {
+            display::show_warning(&format!(
+                "'{item}' is both a profile and an active variable, deactivating the profile; \
+                 use `--var {item}` to unset the variable instead"
+            ));
+        }
+        return Ok(None);
+    }
+    Ok(tracked.then(|| item.to_string()))
+}
+
+/// Whether env-manage set `key` in this session or one of the active profiles defines it
+fn is_active_variable(
+    config_manager: &mut ConfigManager,
+    session: &SessionState,
+    key: &str,
+) -> bool {
+    session.tracked_vars().iter().any(|tracked| tracked == key)
+        || session.active_profiles().iter().any(|profile| {
+            api::resolve_env(config_manager, profile).is_ok_and(|vars| vars.contains_key(key))
+        })
+}
+
+fn deactivate_all(
+    mut generate: ShellGenerate,
+    allow_hooks: bool,
+    profile_dir: Option<PathBuf>,
+    verbosity: Verbosity,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut session = SessionState::from_env();
+    if session.is_empty() {
+        verbosity.show_info("Nothing is currently active.");
+        return Ok(());
+    }
+
+    let profiles = session.active_profiles().to_vec();
+    let mut keys: BTreeSet<String> = session.tracked_vars().iter().cloned().collect();
+    let mut config_manager = ConfigManager::new(profile_dir)?;
+
+    let mut hooks = Vec::new();
+    for profile_name in &profiles {
+        let resolved = api::resolve_hooks(
+            &mut config_manager,
+            std::slice::from_ref(profile_name),
+            &[HookKind::Deactivate],
+        );
+        match resolved {
+            Ok(resolved) => {
+                for hook in resolved {
+                    if !hooks.iter().any(|(name, _)| *name == hook.0) {
+                        hooks.push(hook);
+                    }
+                }
+            }
+            Err(e) => display::show_warning(&format!(
+                "Skipping the hooks of profile '{profile_name}': {e}"
+            )),
+        }
+    }
+    run_hooks(&config_manager, hooks, allow_hooks, &mut generate)?;
+
+    // Without variable tracking, fall back to the union of the active profiles' variables
+    if keys.is_empty() {
+        for profile_name in &profiles {
+            match api::resolve_env(&mut config_manager, profile_name) {
+                Ok(vars) => keys.extend(vars.into_keys()),
+                Err(e) => display::show_warning(&format!("Skipping profile '{profile_name}': {e}")),
+            }
+        }
+    }
+
+    let keys: Vec<String> = keys.into_iter().collect();
+    session.untrack(&profiles, &keys, &mut generate);
+    session.write(&mut generate);
+    generate.output();
+
+    verbosity.show_success(&format!(
+        "Deactivated {} variables across {} profiles.",
+        keys.len(),
+        profiles.len()
+    ));
+
+    Ok(())
+}
+
+/// Add the trusted deactivation hooks to `generate`, in reverse activation order
+fn run_hooks(
+    config_manager: &ConfigManager,
+    hooks: Vec<(String, Hooks)>,
+    allow_hooks: bool,
+    generate: &mut ShellGenerate,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let hooks = super::hooks::allowed_hooks(config_manager, hooks, allow_hooks, false)?;
+    for (_, hooks) in hooks.iter().rev() {
+        generate.run_hook(hooks.commands(HookKind::Deactivate));
+    }
+    Ok(())
+}
@@ -1,117 +1,481 @@
-use crate::config::{ConfigManager, graph::DependencyError};
-use crate::utils::display;
-
-pub fn handle() -> Result<(), Box<dyn std::error::Error>> {
-    let mut config_manager = ConfigManager::new()?;
-    let profile_names = config_manager.scan_profile_names()?;
-
-    let mut fixed_count = 0;
-
-    for name in profile_names.iter() {
-        // Attempt load
-        match config_manager.load_profile(name) {
-            Ok(_) => continue,
-            Err(e) => {
-                if fix_profile(&mut config_manager, &e)? {
-                    fixed_count += 1;
-                } else {
-                    display::show_error(&format!("Could not fix issue in '{name}': {e}"));
-                }
+use crate::config::ConfigManager;
+use crate::config::diagnostics::{self, Diagnostic, Severity};
+use crate::config::graph::display_path;
+use crate::config::history;
+use crate::config::journal::{self, Entry};
+use crate::config::models::{KeyValidation, Profile};
+use crate::utils::ask::Asker;
+use crate::utils::display::Verbosity;
+use crate::utils::{
+    IdentifierError, closest_match, display, validate_namespaced_profile_name,
+    validate_variable_key_as,
+};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io::{self, IsTerminal};
+use std::path::PathBuf;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// An edit `fix` wrote to disk, listed in the final summary
+pub(super) enum Edit {
+    Removed {
+        profile: String,
+        dependency: String,
+    },
+    Mapped {
+        profile: String,
+        from: String,
+        to: String,
+    },
+    Created(String),
+    RemovedSelf(String),
+    BrokeCycle {
+        profile: String,
+        dependency: String,
+    },
+    UppercasedKey {
+        profile: String,
+        from: String,
+        to: String,
+    },
+    RolledForward(String),
+    RolledBack(String),
+}
+
+impl fmt::Display for Edit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Edit::Removed {
+                profile,
+                dependency,
+            } => write!(
+                f,
+                "Removed dependency '{dependency}' from profile '{profile}'"
+            ),
+            Edit::Mapped { profile, from, to } => {
+                write!(
+                    f,
+                    "Replaced dependency '{from}' with '{to}' in profile '{profile}'"
+                )
+            }
+            Edit::Created(name) => write!(f, "Created empty profile '{name}'"),
+            Edit::RemovedSelf(name) => {
+                write!(f, "Removed profile '{name}' from its own dependencies")
+            }
+            Edit::BrokeCycle {
+                profile,
+                dependency,
+            } => write!(
+                f,
+                "Removed dependency '{dependency}' from profile '{profile}' to break a cycle"
+            ),
+            Edit::UppercasedKey { profile, from, to } => {
+                write!(
+                    f,
+                    "Renamed variable '{from}' to '{to}' in profile '{profile}'"
+                )
             }
+            Edit::RolledForward(operation) => {
+                write!(f, "Finished interrupted operation '{operation}'")
+            }
+            Edit::RolledBack(operation) => write!(f, "Undid interrupted operation '{operation}'"),
+        }
+    }
+}
+
+/// How to recover an operation the journal records as interrupted
+enum Recovery {
+    Forward,
+    Back,
+}
+
+/// How to repair a reference to a profile that does not exist
+enum Repair {
+    Remove,
+    Map(String),
+    Create,
+}
+
+pub fn handle(
+    yes: bool,
+    profile_dir: Option<PathBuf>,
+    verbosity: Verbosity,
+) -> Result<(), Box<dyn std::error::Error>> {
+    require_terminal(yes)?;
+
+    let mut config_manager = ConfigManager::new(profile_dir)?;
+    config_manager.lock()?;
+
+    let edits = apply(&mut config_manager, yes)?;
+    let remaining = remaining(&mut config_manager)?;
+    report_remaining(&config_manager, &remaining);
+    report_edits(&edits, verbosity);
+
+    Ok(())
+}
+
+/// Fail unless the prompts can be answered, or `yes` skips them
+pub(super) fn require_terminal(yes: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if !yes && !io::stdin().is_terminal() {
+        return Err(
+            "Standard input is not a terminal, pass --yes to apply the default fixes.".into(),
+        );
+    }
+    Ok(())
+}
+
+/// Repair what can be repaired, asking how unless `yes`, and return the edits written
+pub(super) fn apply(
+    config_manager: &mut ConfigManager,
+    yes: bool,
+) -> Result<Vec<Edit>, Box<dyn std::error::Error>> {
+    let mut edits = Vec::new();
+    // The other repairs would otherwise work on half-written files
+    recover_journal(config_manager, yes, &mut edits)?;
+    remove_self_dependencies(config_manager, &mut edits)?;
+    fix_dangling_references(config_manager, yes, &mut edits)?;
+    break_cycles(config_manager, &mut edits)?;
+    uppercase_keys(config_manager, yes, &mut edits)?;
+    Ok(edits)
+}
+
+/// The warnings and errors still reported, which could not be repaired automatically
+pub(super) fn remaining(
+    config_manager: &mut ConfigManager,
+) -> Result<Vec<Diagnostic>, Box<dyn std::error::Error>> {
+    config_manager.clear_loaded();
+    Ok(diagnostics::collect(config_manager)?
+        .into_iter()
+        .filter(|d| d.severity() != Severity::Info)
+        .collect())
+}
+
+/// Print what is left, pointing at the files that need editing by hand
+pub(super) fn report_remaining(config_manager: &ConfigManager, remaining: &[Diagnostic]) {
+    let profiles_dir = config_manager.base_path().join("profiles");
+    for diagnostic in remaining {
+        display::show_error(&format!("Could not fix: {diagnostic}"));
+        // Never offered as a missing profile to create, the file is there
+        if diagnostic.is_malformed_file() {
+            eprintln!(
+                "  {} exists but is malformed, correct it by hand",
+                display_path(&diagnostic.file(&profiles_dir))
+            );
         }
     }
+}
 
-    if fixed_count > 0 {
-        display::show_success(&format!("Fixed {fixed_count} profiles."));
+pub(super) fn report_edits(edits: &[Edit], verbosity: Verbosity) {
+    if edits.is_empty() {
+        verbosity.show_info("No fixable issues found.");
     } else {
-        display::show_info("No fixable issues found.");
+        verbosity.show_success(&format!("Wrote {} edits:", edits.len()));
+        for edit in edits {
+            eprintln!("  - {edit}");
+        }
     }
+}
 
+/// Finish or undo every operation the journal records as interrupted, oldest first
+fn recover_journal(
+    config_manager: &mut ConfigManager,
+    yes: bool,
+    edits: &mut Vec<Edit>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let base_path = config_manager.base_path().to_path_buf();
+    for (path, entry) in journal::incomplete(&base_path) {
+        let recovery = if yes {
+            Recovery::Forward
+        } else {
+            prompt_recovery(config_manager, &entry)?
+        };
+        match recovery {
+            Recovery::Forward => {
+                entry.roll_forward(&base_path)?;
+                edits.push(Edit::RolledForward(entry.operation.clone()));
+            }
+            Recovery::Back => {
+                entry.roll_back(&base_path)?;
+                edits.push(Edit::RolledBack(entry.operation.clone()));
+            }
+        }
+        journal::resolve(&path, &entry)?;
+    }
+    config_manager.clear_loaded();
     Ok(())
 }
 
-fn fix_profile(
+fn prompt_recovery(
+    config_manager: &ConfigManager,
+    entry: &Entry,
+) -> Result<Recovery, Box<dyn std::error::Error>> {
+    let started = history::timestamp(UNIX_EPOCH + Duration::from_secs(entry.started));
+    display::show_warning(&format!(
+        "Operation '{}' started at {started} did not finish ({} of {} files written)",
+        entry.operation,
+        entry.applied(config_manager.base_path())?,
+        entry.files.len()
+    ));
+    eprintln!("  [f] roll it forward, writing the remaining files (default)");
+    eprintln!("  [b] roll it back, restoring every file as it was before");
+
+    let mut asker = Asker::stdio();
+    loop {
+        let Some(answer) = asker.line("Choose [f/b]: ")? else {
+            return Ok(Recovery::Forward);
+        };
+        let answer = answer.trim();
+        match answer.to_lowercase().as_str() {
+            "" | "f" | "forward" => return Ok(Recovery::Forward),
+            "b" | "back" => return Ok(Recovery::Back),
+            _ => display::show_warning(&format!("Unknown choice '{answer}'")),
+        }
+    }
+}
+
+fn fix_dangling_references(
     config_manager: &mut ConfigManager,
-    error: &DependencyError,
-) -> Result<bool, Box<dyn std::error::Error>> {
-    match error {
-        DependencyError::DependencyChain { profile, cause } => {
-            // Use pattern matching to check if the cause is immediately a missing profile
-            if let DependencyError::ProfileNotFound(target) = &**cause {
-                // 'profile' references 'target' which is missing. Fix 'profile'.
-                return remove_dependency_from_file(config_manager, profile, target);
+    yes: bool,
+    edits: &mut Vec<Edit>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // The same reference is reported by every profile that reaches it
+    let references: BTreeSet<(String, String)> = diagnostics::collect(config_manager)?
+        .iter()
+        .flat_map(|d| d.dangling_references())
+        .map(|(parent, dependency)| (parent.to_string(), dependency.to_string()))
+        .collect();
+
+    let mut existing = config_manager.scan_profile_names()?.0;
+    for (parent, dependency) in references {
+        // An earlier answer may already have created it
+        if existing.contains(&dependency) {
+            continue;
+        }
+
+        let repair = if yes {
+            Repair::Remove
+        } else {
+            prompt_repair(config_manager, &parent, &dependency, &existing)?
+        };
+
+        match repair {
+            Repair::Remove => {
+                if replace_dependency(config_manager, &parent, &dependency, None)? {
+                    edits.push(Edit::Removed {
+                        profile: parent,
+                        dependency,
+                    });
+                }
+            }
+            Repair::Map(to) => {
+                if replace_dependency(config_manager, &parent, &dependency, Some(&to))? {
+                    edits.push(Edit::Mapped {
+                        profile: parent,
+                        from: dependency,
+                        to,
+                    });
+                }
+            }
+            Repair::Create => {
+                config_manager.write_profile(&dependency, &Profile::new())?;
+                existing.push(dependency.clone());
+                edits.push(Edit::Created(dependency));
             }
-            // Otherwise recurse down the chain
-            fix_profile(config_manager, cause)
         }
-        DependencyError::DependencyNotFound(parent, dep_name) => {
-            remove_dependency_from_file(config_manager, parent, dep_name)
+    }
+
+    Ok(())
+}
+
+/// Drop every profile from its own list of dependencies
+fn remove_self_dependencies(
+    config_manager: &mut ConfigManager,
+    edits: &mut Vec<Edit>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for diagnostic in diagnostics::collect(config_manager)? {
+        if let Diagnostic::SelfDependency { profile } = diagnostic
+            && replace_dependency(config_manager, &profile, &profile, None)?
+        {
+            edits.push(Edit::RemovedSelf(profile));
         }
-        DependencyError::CircularDependency(path) => {
-            if path.len() < 2 {
-                return Ok(false);
+    }
+    config_manager.clear_loaded();
+    Ok(())
+}
+
+/// Remove the closing edge of every reported cycle until none are left
+fn break_cycles(
+    config_manager: &mut ConfigManager,
+    edits: &mut Vec<Edit>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    loop {
+        config_manager.clear_loaded();
+        let edges: BTreeSet<(String, String)> = diagnostics::collect(config_manager)?
+            .iter()
+            .flat_map(|d| d.cycles())
+            .filter(|path| path.len() >= 2)
+            .map(|path| (path[path.len() - 2].clone(), path[path.len() - 1].clone()))
+            .collect();
+
+        let mut removed_any = false;
+        for (profile, dependency) in edges {
+            if replace_dependency(config_manager, &profile, &dependency, None)? {
+                removed_any = true;
+                edits.push(Edit::BrokeCycle {
+                    profile,
+                    dependency,
+                });
             }
+        }
+
+        if !removed_any {
+            return Ok(());
+        }
+    }
+}
 
-            let target = path.last().unwrap();
-            let source = path.get(path.len() - 2).unwrap();
+/// Offer to uppercase the lowercase keys of strict profiles, skipping any key whose
+/// uppercase form the profile already uses
+fn uppercase_keys(
+    config_manager: &mut ConfigManager,
+    yes: bool,
+    edits: &mut Vec<Edit>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    config_manager.clear_loaded();
+    let keys: Vec<(String, String)> = diagnostics::collect(config_manager)?
+        .into_iter()
+        .filter_map(|d| match d {
+            Diagnostic::InvalidKey {
+                profile,
+                key,
+                error: IdentifierError::ContainsLowercase,
+            } => Some((profile, key)),
+            _ => None,
+        })
+        .collect();
 
-            remove_dependency_from_file(config_manager, source, target)
+    for (profile_name, key) in keys {
+        let to = key.to_uppercase();
+        // Uppercasing alone does not help a key that is invalid for another reason too
+        if validate_variable_key_as(&to, KeyValidation::Strict).is_err() {
+            continue;
         }
-        DependencyError::MultipleErrors(errors) => {
-            let mut fixed_any = false;
-            for e in errors {
-                if fix_profile(config_manager, e)? {
-                    fixed_any = true;
-                }
-            }
-            Ok(fixed_any)
+
+        let mut profile = crate::config::loader::load_profile_from_file(
+            config_manager.base_path(),
+            &profile_name,
+        )?;
+        if profile.variables.contains_key(&to) || profile.unset.contains(&to) {
+            display::show_warning(&format!(
+                "Cannot rename '{key}' in profile '{profile_name}': '{to}' is already set"
+            ));
+            continue;
         }
-        DependencyError::ProfileNotFound(_) => {
-            // Top level profile not found? Can't fix.
-            Ok(false)
+        if !yes {
+            let answer = Asker::stdio()
+                .line(&format!(
+                    "Rename '{key}' to '{to}' in profile '{profile_name}'? [Y/n]: "
+                ))?
+                .unwrap_or_default();
+            if !matches!(answer.trim().to_lowercase().as_str(), "" | "y" | "yes") {
+                continue;
+            }
         }
-        DependencyError::ProfileIoError(_, _) => {
-            // IO error? Can't fix automatically.
-            Ok(false)
+
+        if profile.unset.remove(&key) {
+            profile.unset.insert(to.clone());
+        } else {
+            profile.variables = profile
+                .variables
+                .into_iter()
+                .map(|(k, v)| if k == key { (to.clone(), v) } else { (k, v) })
+                .collect();
         }
-        DependencyError::ProfileParseError(_, _) => {
-            // Parse error? Can't fix automatically.
-            Ok(false)
+        config_manager.write_profile(&profile_name, &profile)?;
+        edits.push(Edit::UppercasedKey {
+            profile: profile_name,
+            from: key,
+            to,
+        });
+    }
+
+    Ok(())
+}
+
+fn prompt_repair(
+    config_manager: &ConfigManager,
+    parent: &str,
+    dependency: &str,
+    existing: &[String],
+) -> Result<Repair, Box<dyn std::error::Error>> {
+    // A profile cannot depend on itself
+    let candidates: Vec<String> = existing.iter().filter(|n| *n != parent).cloned().collect();
+    let suggestion = closest_match(dependency, &candidates);
+    let can_create = validate_namespaced_profile_name(dependency).is_ok();
+
+    let path = config_manager.profile_path(parent);
+    display::show_warning(&format!(
+        "Profile '{parent}' references missing profile '{dependency}' (declared in {})",
+        display_path(&path)
+    ));
+    eprintln!("  [r] remove the reference (default)");
+    if let Some(suggestion) = suggestion {
+        eprintln!("  [m] map it to an existing profile (closest: '{suggestion}')");
+    }
+    if can_create {
+        eprintln!("  [c] create an empty profile '{dependency}'");
+    }
+
+    let mut asker = Asker::stdio();
+    loop {
+        let Some(answer) = asker.line("Choose [r/m/c]: ")? else {
+            return Ok(Repair::Remove);
+        };
+        let answer = answer.trim();
+        match answer.to_lowercase().as_str() {
+            "" | "r" | "remove" => return Ok(Repair::Remove),
+            "m" | "map" if let Some(suggestion) = suggestion => {
+                let target = asker
+                    .line(&format!("Map to which profile? [{suggestion}]: "))?
+                    .map(|name| name.trim().to_string())
+                    .filter(|name| !name.is_empty())
+                    .unwrap_or_else(|| suggestion.to_string());
+                if candidates.contains(&target) {
+                    return Ok(Repair::Map(target));
+                }
+                display::show_warning(&format!("Profile '{target}' does not exist"));
+            }
+            "c" | "create" if can_create => return Ok(Repair::Create),
+            _ => display::show_warning(&format!("Unknown choice '{answer}'")),
         }
     }
 }
 
-fn remove_dependency_from_file(
+/// Drop `dep_name` from the profile file, adding `replacement` in its place when given.
+/// Returns false when the file or the reference no longer exists.
+fn replace_dependency(
     config_manager: &mut ConfigManager,
     profile_name: &str,
     dep_name: &str,
+    replacement: Option<&str>,
 ) -> Result<bool, Box<dyn std::error::Error>> {
     // We need to read the profile file raw because load_profile failed.
     // If the file itself is missing, we can't edit it.
-    if !config_manager
-        .base_path()
-        .join("profiles")
-        .join(format!("{profile_name}.toml"))
-        .exists()
-    {
+    if !config_manager.profile_exists(profile_name) {
         return Ok(false);
     }
 
     let mut profile =
         crate::config::loader::load_profile_from_file(config_manager.base_path(), profile_name)?;
 
-    if profile.profiles.contains(dep_name) {
-        profile.remove_profile(dep_name);
-        config_manager.write_profile(profile_name, &profile)?;
-
-        config_manager.remove_profile(profile_name);
-
-        config_manager.remove_profile_node(profile_name)?;
-
-        crate::utils::display::show_success(&format!(
-            "Removed dependency '{dep_name}' from profile '{profile_name}'",
-        ));
-        return Ok(true);
+    if !profile.profiles.contains(dep_name) {
+        return Ok(false);
     }
-    Ok(false)
+
+    match replacement {
+        Some(replacement) => profile.rename_profile(profile_name, dep_name, replacement),
+        None => profile.remove_profile(dep_name),
+    };
+    config_manager.write_profile(profile_name, &profile)?;
+    Ok(true)
 }
@@ -1,15 +1,30 @@
-use crate::cli::GlobalCommands::{self, Add, Clean, Init, List, Remove};
+use crate::GLOBAL_PROFILE_MARK;
+use crate::cli::GlobalCommands::{self, Add, Clean, Edit, Init, List, Remove};
 use crate::config::ConfigManager;
-use crate::utils::display::{show_info, show_success, show_warning};
+use crate::config::diagnostics::{self, Severity};
+use crate::config::models;
+use crate::utils::ask::Asker;
+use crate::utils::display::{Verbosity, show_error, show_warning};
 use crate::utils::{self, validate_variable_key};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
 
-pub fn handle(global_commands: GlobalCommands) -> Result<(), Box<dyn std::error::Error>> {
-    let mut config_manager = ConfigManager::new()?;
+pub fn handle(
+    global_commands: GlobalCommands,
+    profile_dir: Option<PathBuf>,
+    verbosity: Verbosity,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config_manager = ConfigManager::new(profile_dir)?;
+    if !matches!(global_commands, List { .. }) {
+        config_manager.lock()?;
+    }
     match global_commands {
-        List { expand } => list(expand, &mut config_manager),
-        Add { items } => add(items, &mut config_manager),
-        Remove { items } => remove(items, &config_manager),
-        Clean => clean(&mut config_manager),
+        List { expand } => list(expand, &mut config_manager, verbosity),
+        Add { items } => add(items, &mut config_manager, verbosity),
+        Remove { items } => remove(items, &config_manager, verbosity),
+        Clean => clean(&mut config_manager, verbosity),
+        Edit => edit(&mut config_manager, verbosity),
         Init => init(&mut config_manager),
     }
 }
@@ -18,11 +33,12 @@ pub fn handle(global_commands: GlobalCommands) -> Result<(), Box<dyn std::error:
 fn list(
     expand: bool,
     config_manager: &mut ConfigManager,
+    verbosity: Verbosity,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let global = config_manager.read_global()?;
 
     if global.is_empty() {
-        show_info("Global configuration is empty.");
+        verbosity.show_info("Global configuration is empty.");
         return Ok(());
     }
 
@@ -30,11 +46,10 @@ fn list(
         config_manager.load_profile(profile)?;
     }
 
+    eprintln!("global");
     if expand {
-        eprintln!("Global Config (expand view):");
-        global.display_expand(config_manager)?;
+        global.display_expand(config_manager, None)?;
     } else {
-        eprintln!("global");
         global.display_simple();
     }
     Ok(())
@@ -44,6 +59,7 @@ fn list(
 fn add(
     items: Vec<String>,
     config_manager: &mut ConfigManager,
+    verbosity: Verbosity,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let mut global = config_manager.read_global()?;
     let mut added_profiles = Vec::new();
@@ -61,7 +77,7 @@ fn add(
             }
         } else {
             config_manager.load_profile(&item)?;
-            global.add_profile(&item);
+            global.add_profile(GLOBAL_PROFILE_MARK, &item);
             added_profiles.push(item);
         }
     }
@@ -71,13 +87,13 @@ fn add(
     }
 
     if !added_profiles.is_empty() {
-        show_success(&format!(
+        verbosity.show_success(&format!(
             "Added profiles to global config: {}",
             added_profiles.join(", ")
         ));
     }
     if !added_variables.is_empty() {
-        show_success(&format!(
+        verbosity.show_success(&format!(
             "Added variables to global config: {}",
             added_variables.join(", ")
         ));
@@ -89,6 +105,7 @@ fn add(
 fn remove(
     items: Vec<String>,
     config_manager: &ConfigManager,
+    verbosity: Verbosity,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let mut global = config_manager.read_global()?;
     let mut removed_profiles = Vec::new();
@@ -114,13 +131,13 @@ fn remove(
     }
 
     if !removed_profiles.is_empty() {
-        show_success(&format!(
+        verbosity.show_success(&format!(
             "Removed profiles from global config: {}",
             removed_profiles.join(", ")
         ));
     }
     if !removed_variables.is_empty() {
-        show_success(&format!(
+        verbosity.show_success(&format!(
             "Removed variables from global config: {}",
             removed_variables.join(", ")
         ));
@@ -134,22 +151,139 @@ fn remove(
     Ok(())
 }
 
+/// Open the global settings file in the editor until it loads, or restore it when
+/// the editor fails or the user gives up
+fn edit(
+    config_manager: &mut ConfigManager,
+    verbosity: Verbosity,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path = config_manager.global_path();
+    let original = fs::read_to_string(&path).unwrap_or_default();
+
+    loop {
+        // Other processes may write while the editor is open
+        config_manager.unlock();
+        let edited = super::run_editor(&path).and_then(|_| Ok(fs::read_to_string(&path)?));
+        config_manager.lock()?;
+        let edited = match edited {
+            Ok(edited) => edited,
+            Err(e) => {
+                config_manager.write_global_content(&original)?;
+                return Err(format!(
+                    "{e}\nThe edit was discarded and the global settings restored."
+                )
+                .into());
+            }
+        };
+        if edited == original {
+            verbosity.show_info("No changes made to the global settings.");
+            return Ok(());
+        }
+
+        let problems = check_edited_global(config_manager, &edited);
+        if problems.is_empty() {
+            verbosity.show_success("Global settings updated successfully.");
+            return Ok(());
+        }
+        for problem in &problems {
+            show_error(problem);
+        }
+        eprintln!("  [e] edit the file again (default)");
+        eprintln!("  [a] abort and restore the previous global settings");
+        config_manager.unlock();
+        let mut asker = Asker::stdio();
+        let again = loop {
+            match asker.line("Choose [e/a]: ")? {
+                None => break false,
+                Some(answer) => match answer.trim().to_lowercase().as_str() {
+                    "" | "e" | "edit" => break true,
+                    "a" | "abort" => break false,
+                    _ => show_warning(&format!("Unknown choice '{answer}'")),
+                },
+            }
+        };
+        if !again {
+            config_manager.lock()?;
+            config_manager.write_global_content(&original)?;
+            return Err("The edit was rejected and the global settings restored.".into());
+        }
+    }
+}
+
+/// What keeps the edited global settings from loading, each with the line of
+/// `content` it concerns when there is one
+fn check_edited_global(config_manager: &mut ConfigManager, content: &str) -> Vec<String> {
+    let global = match config_manager.read_global() {
+        Ok(global) => global,
+        // TOML errors already point at their line and column
+        Err(e) => return vec![e.to_string().trim_end().to_string()],
+    };
+    let at_line = |needle: &str, message: String| match line_of(content, needle) {
+        Some(line) => format!("line {line}: {message}"),
+        None => message,
+    };
+
+    let mut problems = Vec::new();
+    for key in global.variables.keys().chain(&global.unset) {
+        if let Err(e) = validate_variable_key(models::VarOp::parse(key).0) {
+            problems.push(at_line(key, format!("Invalid variable key `{key}`: {e}")));
+        }
+    }
+    match diagnostics::collect_global(config_manager) {
+        Ok(found) => problems.extend(
+            found
+                .into_iter()
+                .filter(|(_, diagnostic)| diagnostic.severity() == Severity::Error)
+                .map(|(dependency, diagnostic)| at_line(&dependency, diagnostic.to_string())),
+        ),
+        Err(e) => problems.push(e.to_string()),
+    }
+    problems
+}
+
+/// The 1-based line of `content` that sets key `text` or quotes it as a string
+fn line_of(content: &str, text: &str) -> Option<usize> {
+    let quoted = format!("\"{text}\"");
+    content
+        .lines()
+        .position(|line| {
+            line.contains(&quoted)
+                || line
+                    .split_once('=')
+                    .is_some_and(|(key, _)| key.trim() == text)
+        })
+        .map(|index| index + 1)
+}
+
 /// Handles the logic for cleaning the global configuration.
-fn clean(config_manager: &mut ConfigManager) -> Result<(), Box<dyn std::error::Error>> {
+fn clean(
+    config_manager: &mut ConfigManager,
+    verbosity: Verbosity,
+) -> Result<(), Box<dyn std::error::Error>> {
     let mut global_profile = config_manager.read_global()?;
     for profile in global_profile.profiles.iter() {
         config_manager.load_profile(profile)?;
     }
-    let vars = global_profile.collect_vars(config_manager)?;
+    let vars = global_profile.collect_vars(GLOBAL_PROFILE_MARK, config_manager)?;
+    let list_entries = global_profile.collect_list_entries(GLOBAL_PROFILE_MARK, config_manager)?;
     let mut generate = utils::shell_generate::ShellGenerate::new();
 
     global_profile.clear();
     config_manager.write_global(&global_profile)?;
 
-    generate.unset_from_map(&vars);
+    for key in vars.keys() {
+        // Lists such as PATH only lose the entries the global config added to them
+        let remaining = list_entries.get(key).map(|entries| {
+            models::remove_list_entries(&env::var(key).unwrap_or_default(), entries)
+        });
+        match remaining {
+            Some(value) if !value.is_empty() => generate.export(key, &value),
+            _ => generate.unset(key),
+        };
+    }
     generate.output();
 
-    show_success("Global configuration cleaned successfully.");
+    verbosity.show_success("Global configuration cleaned successfully.");
     Ok(())
 }
 
@@ -160,7 +294,7 @@ fn init(config_manager: &mut ConfigManager) -> Result<(), Box<dyn std::error::Er
         config_manager.load_profile(profile)?;
     }
 
-    let vars = global_profile.collect_vars(config_manager)?;
+    let vars = global_profile.collect_vars(GLOBAL_PROFILE_MARK, config_manager)?;
     let mut generate = utils::shell_generate::ShellGenerate::new();
     generate.export_from_map(&vars);
     generate.output();
@@ -0,0 +1,187 @@
+use crate::cli::GroupCommands::{self, Add, Create, List, Remove};
+use crate::config::ConfigManager;
+use crate::config::groups::{Groups, group_reference};
+use crate::utils::display::{self, Verbosity};
+use crate::utils::validate_profile_name;
+use colored::*;
+use std::path::PathBuf;
+
+pub fn handle(
+    group_commands: GroupCommands,
+    profile_dir: Option<PathBuf>,
+    verbosity: Verbosity,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config_manager = ConfigManager::new(profile_dir)?;
+    if !matches!(group_commands, List) {
+        config_manager.lock()?;
+    }
+    match group_commands {
+        List => list(&config_manager, verbosity),
+        Create { name, members } => create(name, members, &config_manager, verbosity),
+        Add { name, members } => add(name, members, &config_manager, verbosity),
+        Remove { name, members } => remove(name, members, &config_manager, verbosity),
+    }
+}
+
+fn list(
+    config_manager: &ConfigManager,
+    verbosity: Verbosity,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let groups = config_manager.read_groups()?;
+    if groups.0.is_empty() {
+        verbosity.show_info("No groups defined.");
+        return Ok(());
+    }
+
+    eprintln!("{}", "Groups:".yellow());
+    let mut groups_iter = groups.0.iter().peekable();
+    while let Some((name, members)) = groups_iter.next() {
+        let branch = if groups_iter.peek().is_some() {
+            "├──"
+        } else {
+            "└──"
+        };
+        let colored_members: Vec<String> = members
+            .iter()
+            .map(|member| match group_reference(member) {
+                Some(_) => member.magenta().to_string(),
+                None => member.blue().to_string(),
+            })
+            .collect();
+        eprintln!(
+            "{branch} {}: [{}]",
+            format!("@{name}").cyan(),
+            colored_members.join(", ")
+        );
+    }
+    Ok(())
+}
+
+fn create(
+    name: String,
+    members: Vec<String>,
+    config_manager: &ConfigManager,
+    verbosity: Verbosity,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Err(e) = validate_profile_name(&name) {
+        return Err(format!("Invalid group name: {e}").into());
+    }
+    let mut groups = config_manager.read_groups()?;
+    if groups.contains(&name) {
+        return Err(format!("Group `{name}` already exists").into());
+    }
+
+    groups.0.insert(name.clone(), Vec::new());
+    add_members(&mut groups, &name, members, config_manager)?;
+    config_manager.write_groups(&groups)?;
+    verbosity.show_success(&format!("Group '{name}' created successfully."));
+    Ok(())
+}
+
+fn add(
+    name: String,
+    members: Vec<String>,
+    config_manager: &ConfigManager,
+    verbosity: Verbosity,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut groups = config_manager.read_groups()?;
+    if !groups.contains(&name) {
+        return Err(format!("Group `{name}` does not exist").into());
+    }
+
+    let added = add_members(&mut groups, &name, members, config_manager)?;
+    if added.is_empty() {
+        verbosity.show_info(&format!("Group '{name}' already contains these members."));
+        return Ok(());
+    }
+    config_manager.write_groups(&groups)?;
+    verbosity.show_success(&format!("Added to group '{name}': {}", added.join(", ")));
+    Ok(())
+}
+
+/// Append the members that are not in group `name` yet, returning them.
+///
+/// Profiles must exist and groups must neither be missing nor close a cycle.
+fn add_members(
+    groups: &mut Groups,
+    name: &str,
+    members: Vec<String>,
+    config_manager: &ConfigManager,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut added = Vec::new();
+    for member in members {
+        match group_reference(&member) {
+            Some(group) if !groups.contains(group) => {
+                return Err(format!("Group `{group}` does not exist").into());
+            }
+            Some(_) => {}
+            None if !config_manager.profile_exists(&member) => {
+                return Err(format!("Profile `{member}` does not exist").into());
+            }
+            None => {}
+        }
+
+        let group_members = groups.0.entry(name.to_string()).or_default();
+        if group_members.contains(&member) {
+            continue;
+        }
+        group_members.push(member.clone());
+        added.push(member);
+    }
+
+    // Expanding the group fails when a new member leads back to it
+    groups.expand(vec![format!("@{name}")])?;
+    Ok(added)
+}
+
+fn remove(
+    name: String,
+    members: Vec<String>,
+    config_manager: &ConfigManager,
+    verbosity: Verbosity,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut groups = config_manager.read_groups()?;
+    let Some(group_members) = groups.0.get_mut(&name) else {
+        return Err(format!("Group `{name}` does not exist").into());
+    };
+
+    if members.is_empty() {
+        groups.0.remove(&name);
+        config_manager.write_groups(&groups)?;
+        verbosity.show_success(&format!("Group '{name}' deleted successfully."));
+
+        let reference = format!("@{name}");
+        let referencing: Vec<&str> = groups
+            .0
+            .iter()
+            .filter(|(_, members)| members.contains(&reference))
+            .map(|(group, _)| group.as_str())
+            .collect();
+        if !referencing.is_empty() {
+            display::show_warning(&format!(
+                "Still referenced by groups: {}",
+                referencing.join(", ")
+            ));
+        }
+        return Ok(());
+    }
+
+    let mut removed = Vec::new();
+    for member in members {
+        if let Some(pos) = group_members.iter().position(|m| *m == member) {
+            group_members.remove(pos);
+            removed.push(member);
+        } else {
+            display::show_warning(&format!("'{member}' is not a member of group '{name}'."));
+        }
+    }
+
+    if !removed.is_empty() {
+        config_manager.write_groups(&groups)?;
+        verbosity.show_success(&format!(
+            "Removed from group '{name}': {}",
+            removed.join(", ")
+        ));
+    }
+    Ok(())
+}
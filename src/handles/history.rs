@@ -0,0 +1,22 @@
+use crate::config::ConfigManager;
+use crate::config::history;
+use crate::utils::display::Verbosity;
+use std::path::PathBuf;
+
+/// Print the last `limit` entries of the history log, oldest first
+pub fn handle(
+    limit: usize,
+    profile_dir: Option<PathBuf>,
+    verbosity: Verbosity,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config_manager = ConfigManager::new(profile_dir)?;
+    let entries = history::read_recent(config_manager.base_path(), limit)?;
+    if entries.is_empty() {
+        verbosity.show_info("No profile changes recorded yet.");
+        return Ok(());
+    }
+    for entry in entries {
+        println!("{entry}");
+    }
+    Ok(())
+}
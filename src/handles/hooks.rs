@@ -0,0 +1,126 @@
+use crate::config::ConfigManager;
+use crate::config::models::{HookKind, Hooks};
+use crate::utils::ask::Asker;
+use crate::utils::display;
+use colored::*;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, IsTerminal};
+use std::process::{Command, Stdio};
+
+/// The profiles of `hooks` whose hooks may run, with their hooks.
+///
+/// Hooks run arbitrary code, so a profile's hooks only run once the user trusted them:
+/// with `--allow-hooks` or by answering the prompt. Trust is kept in `config.toml` and
+/// lapses when the commands change. `preview` neither prompts nor records trust.
+pub fn allowed_hooks(
+    config_manager: &ConfigManager,
+    hooks: Vec<(String, Hooks)>,
+    allow_hooks: bool,
+    preview: bool,
+) -> Result<Vec<(String, Hooks)>, Box<dyn std::error::Error>> {
+    if hooks.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut settings = config_manager.read_settings()?;
+    let mut newly_trusted = false;
+    let mut allowed = Vec::new();
+    for (name, profile_hooks) in hooks {
+        let trusted = settings.trusts_hooks(&name, &profile_hooks)
+            || allow_hooks
+            || (!preview && io::stdin().is_terminal() && confirm_trust(&name, &profile_hooks)?);
+        if !trusted {
+            let what = if profile_hooks.computed.is_empty() {
+                "hooks"
+            } else {
+                "hooks and computed variables"
+            };
+            display::show_warning(&format!(
+                "Skipping the {what} of profile '{name}', they are not trusted. Pass --allow-hooks to trust them."
+            ));
+            continue;
+        }
+        if !preview && !settings.trusts_hooks(&name, &profile_hooks) {
+            settings
+                .trusted_hooks
+                .insert(name.clone(), profile_hooks.clone());
+            newly_trusted = true;
+        }
+        allowed.push((name, profile_hooks));
+    }
+
+    if newly_trusted {
+        config_manager.write_settings(&settings)?;
+    }
+    Ok(allowed)
+}
+
+/// Show every hook command of profile `name` and ask whether to trust them
+fn confirm_trust(name: &str, hooks: &Hooks) -> Result<bool, Box<dyn std::error::Error>> {
+    display::show_warning(&format!(
+        "Profile '{name}' runs shell commands when it is activated or deactivated:"
+    ));
+    for kind in HookKind::ALL {
+        for command in hooks.commands(kind) {
+            let label = format!("{}:", kind.label());
+            eprintln!("  {} {command}", format!("{label:<14}").dimmed());
+        }
+    }
+    for (key, command) in &hooks.computed {
+        let label = format!("{key} =");
+        eprintln!("  {} {command}", format!("{label:<14}").dimmed());
+    }
+    let answer = Asker::stdio()
+        .line("Trust and run them? [y/N]: ")?
+        .unwrap_or_default();
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Run the `kind` commands of every profile in `hooks` with the activation's variables
+/// in their environment, each profile's commands stopping at the first failure.
+///
+/// Their output goes to stderr, since stdout carries the commands for the shell.
+pub fn run_in_process(
+    hooks: &[(String, Hooks)],
+    kind: HookKind,
+    vars: &HashMap<String, String>,
+    unset: &HashSet<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for (name, profile_hooks) in hooks {
+        for command in profile_hooks.commands(kind) {
+            let mut process = shell_command(command);
+            process
+                .envs(vars)
+                .stdout(Stdio::from(io::stderr()))
+                .stdin(Stdio::null());
+            for key in unset {
+                process.env_remove(key);
+            }
+            let status = process.status().map_err(|e| {
+                format!(
+                    "Could not run the {} hook `{command}` of profile '{name}': {e}",
+                    kind.label()
+                )
+            })?;
+            if !status.success() {
+                return Err(format!(
+                    "The {} hook `{command}` of profile '{name}' failed with {status}",
+                    kind.label()
+                )
+                .into());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn shell_command(command: &str) -> Command {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut process = Command::new(shell);
+    process.args([flag, command]);
+    process
+}
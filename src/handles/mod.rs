@@ -1,29 +1,144 @@
 use crate::cli::Cli;
-use crate::cli::Commands::{Activate, Check, Deactivate, Fix, Global, Init, Profile, Status, Ui};
+use crate::cli::Commands::{
+    Activate, Check, Deactivate, Fix, Global, Group, History, Init, Profile, Prompt, Status, Ui,
+};
+use crate::config::ConfigManager;
+use crate::utils;
+use crate::utils::display::{self, Verbosity};
+use std::path::Path;
+use std::process::Command;
 
-mod activate;
+pub(crate) mod activate;
 mod check;
 mod deactivate;
 mod fix;
 mod global;
+mod group;
+mod history;
+mod hooks;
 mod init;
 mod profile;
+mod prompt;
 mod status;
 mod ui;
 
 pub fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let verbosity = if cli.quiet {
+        Verbosity::Quiet
+    } else {
+        Verbosity::Normal
+    };
+    display::set_color(cli.color);
+    let profile_dir = cli.profile_dir;
     match cli.command {
         Init {
             shell,
-            print_full_init,
-        } => init::handle(shell, print_full_init),
-        Profile(profile_commands) => profile::handle(profile_commands),
-        Activate { items } => activate::handle(items),
-        Deactivate { items } => deactivate::handle(items),
-        Global(global_commands) => global::handle(global_commands),
-        Status(status_args) => status::handle(status_args),
-        Ui => ui::handle(),
-        Check => check::handle(),
-        Fix => fix::handle(),
+            shell_option,
+            print,
+        } => init::handle(shell.or(shell_option), print),
+        Profile(profile_commands) => profile::handle(profile_commands, profile_dir, verbosity),
+        Activate {
+            items,
+            print,
+            shell,
+            allow_hooks,
+            explain,
+            interactive_conflicts,
+            remember_conflicts,
+            strict_size,
+        } => {
+            let conflicts = match (interactive_conflicts, remember_conflicts) {
+                (false, _) => activate::Conflicts::LastWins,
+                (true, false) => activate::Conflicts::Ask,
+                (true, true) => activate::Conflicts::AskAndRemember,
+            };
+            let options = activate::Options {
+                print,
+                shell,
+                allow_hooks,
+                explain,
+                conflicts,
+                strict_size,
+                verbosity,
+            };
+            activate::handle(items, options, profile_dir)
+        }
+        Deactivate {
+            items,
+            all,
+            vars,
+            shell,
+            allow_hooks,
+        } => deactivate::handle(items, vars, all, shell, allow_hooks, profile_dir, verbosity),
+        Global(global_commands) => global::handle(global_commands, profile_dir, verbosity),
+        Group(group_commands) => group::handle(group_commands, profile_dir, verbosity),
+        Status(status_args) => status::handle(status_args, profile_dir),
+        Ui { read_only, theme } => ui::handle(read_only, theme, profile_dir, verbosity),
+        Check {
+            profile,
+            format,
+            warnings_as_errors,
+            fix,
+            yes,
+        } => {
+            let fix = fix.then_some(check::Fix { yes });
+            check::handle(
+                profile,
+                format,
+                warnings_as_errors,
+                fix,
+                profile_dir,
+                verbosity,
+            )
+        }
+        Prompt {
+            format,
+            separator,
+            symbol,
+            max,
+        } => prompt::handle(format, separator, symbol, max),
+        Fix { yes } => fix::handle(yes, profile_dir, verbosity),
+        History { limit } => history::handle(limit, profile_dir, verbosity),
+    }
+}
+
+/// Fail on the first profile in `names` that does not exist, suggesting the closest existing name
+fn ensure_profiles_exist(
+    config_manager: &ConfigManager,
+    names: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(missing) = names
+        .iter()
+        .find(|name| !config_manager.profile_exists(name))
+    else {
+        return Ok(());
+    };
+    let candidates = config_manager.scan_profile_names()?.0;
+    let mut message = format!("Profile `{missing}` does not exist");
+    if let Some(suggestion) = utils::suggest_name(missing, &candidates) {
+        message.push_str(&format!(", did you mean `{suggestion}`?"));
+    }
+    Err(message.into())
+}
+
+/// Open `path` in `$VISUAL` or `$EDITOR` (falling back to `vi`) and wait for it to exit
+fn run_editor(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+    // Editors are often configured with arguments, e.g. `code --wait`
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+
+    let status = Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .map_err(|e| format!("Failed to launch editor `{editor}`: {e}"))?;
+    if !status.success() {
+        return Err(format!("Editor `{editor}` exited with {status}").into());
     }
+    Ok(())
 }
@@ -1,95 +1,683 @@
-use crate::cli::ProfileCommands::{self, Add, Create, Delete, List, Remove, Rename};
-use crate::cli::ProfileRenameArgs;
-use crate::config::ConfigManager;
-use crate::config::models::Profile;
-use crate::utils::{display, validate_profile_name, validate_variable_key};
-
-pub fn handle(profile_commands: ProfileCommands) -> Result<(), Box<dyn std::error::Error>> {
-    let mut config_manager = ConfigManager::new()?;
+use crate::api::{self, AddedItem};
+use crate::cli::ProfileCommands::{
+    self, Add, Create, Delete, Diff, Edit, Export, Graph, List, NewFromTemplate, Remove, Rename,
+    Search, SetMeta, Show, Stats, ToggleDep,
+};
+use crate::cli::{ExportFormat, GraphFormat, ListSort, ProfileRenameArgs};
+use crate::config;
+use crate::config::history::{self, Operation, Source};
+use crate::config::loader::SkipReason;
+use crate::config::models::VarOp;
+use crate::config::models::{Profile, ProfileNames};
+use crate::config::stats::ProfileStats;
+use crate::config::{ConfigManager, LoadFailures};
+use crate::tui;
+use crate::utils::display::{ListOptions, Verbosity};
+use crate::utils::shell_generate::ShellGenerate;
+use crate::utils::{
+    display, dotenv, json, output, parse_tags, pattern::Pattern, validate_namespaced_profile_name,
+    validate_tag, validate_variable_key_as,
+};
+use colored::*;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+
+pub fn handle(
+    profile_commands: ProfileCommands,
+    profile_dir: Option<PathBuf>,
+    verbosity: Verbosity,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config_manager = ConfigManager::new(profile_dir.clone())?;
+    if !matches!(
+        profile_commands,
+        List { .. }
+            | Show { .. }
+            | Search { .. }
+            | Graph { .. }
+            | Stats
+            | Export { .. }
+            | Diff { .. }
+    ) {
+        config_manager.lock()?;
+    }
     match profile_commands {
-        List { expand } => list(expand, &mut config_manager),
-        Create { name } => create(name, &mut config_manager),
-        Rename(args) => rename(args, &mut config_manager),
-        Delete { name } => delete(name, &mut config_manager),
-        Add { name, items } => add(name, items, &mut config_manager),
-        Remove { name, items } => remove(name, items, &mut config_manager),
+        List {
+            expand,
+            depth,
+            tag,
+            json,
+            oneline,
+            sort,
+        } => list(
+            ListOptions {
+                expand,
+                depth,
+                oneline,
+            },
+            tag,
+            json,
+            sort,
+            &mut config_manager,
+            verbosity,
+        ),
+        Show {
+            name,
+            expand,
+            depth,
+        } => show(name, expand, depth, &mut config_manager),
+        Search {
+            query,
+            values,
+            regex,
+        } => search(query, values, regex, &mut config_manager, verbosity),
+        Graph { format } => graph(format, &mut config_manager),
+        Stats => stats(&mut config_manager),
+        Export {
+            name,
+            output,
+            format,
+            shell,
+        } => export(name, output, format, shell, &mut config_manager, verbosity),
+        Diff {
+            a,
+            b,
+            expand,
+            plain,
+        } => diff(a, b, expand, plain, &mut config_manager, verbosity),
+        Edit { name } => edit(name, &mut config_manager, verbosity),
+        Create { name, edit } => create(name, edit, profile_dir, &mut config_manager, verbosity),
+        NewFromTemplate {
+            template,
+            name,
+            values,
+        } => new_from_template(template, name, values, &mut config_manager, verbosity),
+        Rename(args) => rename(args, &mut config_manager, verbosity),
+        Delete { name } => delete(name, &mut config_manager, verbosity),
+        Add { name, items } => add(name, items, &mut config_manager, verbosity),
+        Remove { name, items } => remove(name, items, &mut config_manager, verbosity),
+        ToggleDep { name, dependency } => {
+            toggle_dep(name, dependency, &mut config_manager, verbosity)
+        }
+        SetMeta {
+            name,
+            description,
+            tags,
+        } => set_meta(name, description, tags, &mut config_manager, verbosity),
     }
 }
 
 fn list(
-    expand: bool,
+    options: ListOptions,
+    tag: Option<String>,
+    json: bool,
+    sort: ListSort,
     config_manager: &mut ConfigManager,
+    verbosity: Verbosity,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    config_manager.load_all_profiles()?;
-    let profile_names = config_manager.scan_profile_names()?;
-    if profile_names.is_empty() {
-        display::show_info("No profiles found.");
+    let (_, failures) = config_manager.load_all_profiles()?;
+    for (name, e) in &failures {
+        display::show_warning(&format!("Could not load profile '{name}': {e}"));
+    }
+    let report = config_manager.scan_profiles()?;
+    for skipped in &report.skipped {
+        if skipped.reason == SkipReason::Reserved {
+            display::show_warning(&format!("Ignoring '{}': {}", skipped.name, skipped.reason));
+        }
+    }
+    let mut profile_names = ProfileNames(report.files.into_iter().map(|f| f.name).collect());
+    if profile_names.is_empty() && !json {
+        verbosity.show_info("No profiles found.");
         return Ok(());
     }
 
+    if let Some(tag) = &tag {
+        profile_names = ProfileNames(
+            profile_names
+                .iter()
+                .filter(|name| {
+                    config_manager
+                        .get_profile(name)
+                        .is_some_and(|profile| profile.has_tag(tag))
+                })
+                .cloned()
+                .collect(),
+        );
+        if profile_names.is_empty() && !json {
+            verbosity.show_info(&format!("No profiles tagged '{tag}'."));
+            return Ok(());
+        }
+    }
+
     profile_names.iter().for_each(|name| {
-        if let Err(e) = validate_profile_name(name) {
+        if let Err(e) = validate_namespaced_profile_name(name) {
             display::show_warning(&format!("Invalid profile name '{name}': {e}"));
         }
     });
 
-    if expand {
-        profile_names.display_expand(config_manager)?;
+    sort_profiles(&mut profile_names, sort, config_manager);
+    if json {
+        print_profiles_json(&profile_names, &failures, config_manager)?;
+    } else {
+        profile_names.display(config_manager, &options)?;
+    }
+
+    Ok(())
+}
+
+/// Order the profiles by name, or newest or largest first with ties by name
+fn sort_profiles(profile_names: &mut ProfileNames, sort: ListSort, config_manager: &ConfigManager) {
+    profile_names.0.sort();
+    match sort {
+        ListSort::Name => {}
+        ListSort::Modified => profile_names
+            .0
+            .sort_by_cached_key(|name| Reverse(config_manager.profile_metadata(name).modified)),
+        ListSort::Vars => profile_names.0.sort_by_cached_key(|name| {
+            Reverse(
+                config_manager
+                    .get_profile(name)
+                    .map(|profile| profile.variables.len()),
+            )
+        }),
+    }
+}
+
+#[derive(Serialize)]
+struct ProfileListJson<'a> {
+    version: u32,
+    profiles: Vec<ProfileEntryJson<'a>>,
+}
+
+#[derive(Serialize)]
+struct ProfileEntryJson<'a> {
+    name: &'a str,
+    #[serde(flatten)]
+    profile: Option<&'a Profile>,
+    path: String,
+    modified: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+fn print_profiles_json(
+    profile_names: &ProfileNames,
+    failures: &LoadFailures,
+    config_manager: &ConfigManager,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let profiles = profile_names
+        .iter()
+        .map(|name| {
+            let path = config_manager.profile_path(name);
+            let modified = config_manager
+                .profile_metadata(name)
+                .modified
+                .map(history::timestamp);
+            ProfileEntryJson {
+                name,
+                profile: config_manager.get_profile(name),
+                path: path.display().to_string(),
+                modified,
+                error: failures
+                    .iter()
+                    .find(|(failed, _)| failed == name)
+                    .map(|(_, e)| e.to_string()),
+            }
+        })
+        .collect();
+    let report = ProfileListJson {
+        version: json::JSON_SCHEMA_VERSION,
+        profiles,
+    };
+    println!("{}", json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+/// How `profile search` compares its query with keys and values
+enum SearchQuery {
+    /// Lowercased text, matched as a substring ignoring case
+    Text(String),
+    Pattern(Pattern),
+}
+
+impl SearchQuery {
+    fn matches(&self, text: &str) -> bool {
+        match self {
+            SearchQuery::Text(query) => text.to_lowercase().contains(query),
+            SearchQuery::Pattern(pattern) => pattern.is_match(text),
+        }
+    }
+}
+
+fn search(
+    query: String,
+    values: bool,
+    regex: bool,
+    config_manager: &mut ConfigManager,
+    verbosity: Verbosity,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (_, failures) = config_manager.load_all_profiles()?;
+    for (name, e) in &failures {
+        display::show_warning(&format!("Could not load profile '{name}': {e}"));
+    }
+    let matcher = if regex {
+        SearchQuery::Pattern(Pattern::new(&query)?)
     } else {
-        profile_names.display_simple(config_manager)?;
+        SearchQuery::Text(query.to_lowercase())
+    };
+
+    let mut results: Vec<(&String, Vec<(&String, &String)>)> = config_manager
+        .profiles_iter()
+        .filter_map(|(name, profile)| {
+            let hits: Vec<_> = profile
+                .variables
+                .iter()
+                .filter(|(key, value)| matcher.matches(key) || (values && matcher.matches(value)))
+                .collect();
+            (!hits.is_empty()).then_some((name, hits))
+        })
+        .collect();
+    if results.is_empty() {
+        verbosity.show_info(&format!("No variables match '{query}'."));
+        return Ok(());
+    }
+    results.sort_by(|a, b| a.0.cmp(b.0));
+
+    for (name, hits) in results {
+        eprintln!("{}", name.cyan());
+        let mut hits = hits.into_iter().peekable();
+        while let Some((key, value)) = hits.next() {
+            let branch = if hits.peek().is_none() {
+                "└──"
+            } else {
+                "├──"
+            };
+            if values {
+                eprintln!("{branch} {} = {value}", key.green());
+            } else {
+                eprintln!("{branch} {}", key.green());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn show(
+    name: String,
+    expand: bool,
+    depth: Option<usize>,
+    config_manager: &mut ConfigManager,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !config_manager.profile_exists(&name) {
+        return Err(format!("Profile `{name}` does not exist").into());
+    }
+    config_manager.load_profile(&name)?;
+    let Some(profile) = config_manager.get_profile(&name) else {
+        return Err(format!("Profile `{name}` does not exist").into());
+    };
+
+    eprintln!("{}{}", name.cyan(), display::metadata_suffix(Some(profile)));
+    let inheritance_depth = config_manager.max_depth(&name);
+    if !expand {
+        profile.display_simple();
+        eprintln!(
+            "{}",
+            format!("Inheritance depth: {inheritance_depth}").dimmed()
+        );
+        return Ok(());
+    }
+
+    profile.display_expand(config_manager, depth)?;
+
+    let vars: BTreeMap<String, String> = profile
+        .collect_vars(&name, config_manager)?
+        .into_iter()
+        .collect();
+    eprintln!("{}", "Resolved:".yellow());
+    if vars.is_empty() {
+        eprintln!("└── {}", "(no variables)".dimmed());
+    }
+    let mut vars_iter = vars.iter().peekable();
+    while let Some((key, value)) = vars_iter.next() {
+        let branch = if vars_iter.peek().is_some() {
+            "├──"
+        } else {
+            "└──"
+        };
+        eprintln!(
+            "{branch} {} = {}",
+            key.green(),
+            format!("\"{value}\"").truecolor(180, 180, 180)
+        );
+    }
+    eprintln!(
+        "{}",
+        format!("Inheritance depth: {inheritance_depth}").dimmed()
+    );
+    Ok(())
+}
+
+fn graph(
+    format: GraphFormat,
+    config_manager: &mut ConfigManager,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (_, failures) = config_manager.load_all_profiles()?;
+    for (name, e) in &failures {
+        display::show_warning(&format!("Could not load profile '{name}': {e}"));
+    }
+    let graph = match format {
+        GraphFormat::Dot => config_manager.to_dot(),
+        GraphFormat::Mermaid => config_manager.to_mermaid(),
+    };
+    print!("{graph}");
+    Ok(())
+}
+
+fn stats(config_manager: &mut ConfigManager) -> Result<(), Box<dyn std::error::Error>> {
+    let (_, failures) = config_manager.load_all_profiles()?;
+    for (name, e) in &failures {
+        display::show_warning(&format!("Could not load profile '{name}': {e}"));
+    }
+    let stats = ProfileStats::of(config_manager);
+    let rows = [
+        ("Profiles", stats.profiles.to_string()),
+        ("Unique variable keys", stats.unique_keys.to_string()),
+        (
+            "Average variables per profile",
+            format!("{:.1}", stats.average_variables),
+        ),
+        ("Max inheritance depth", stats.max_depth.to_string()),
+        (
+            "Profiles without dependents",
+            stats.without_dependents.to_string(),
+        ),
+    ];
+    for (label, value) in rows {
+        eprintln!("{:<31}{}", format!("{label}:"), value.cyan());
+    }
+    Ok(())
+}
+
+fn export(
+    name: String,
+    output: Option<PathBuf>,
+    format: ExportFormat,
+    shell: Option<String>,
+    config_manager: &mut ConfigManager,
+    verbosity: Verbosity,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if shell.is_some() && matches!(format, ExportFormat::Dotenv) {
+        return Err("--shell only applies to --format shell".into());
+    }
+    if !config_manager.profile_exists(&name) {
+        return Err(format!("Profile `{name}` does not exist").into());
+    }
+    config_manager.load_profile(&name)?;
+    let Some(profile) = config_manager.get_profile(&name) else {
+        return Err(format!("Profile `{name}` does not exist").into());
+    };
+
+    let vars: BTreeMap<String, String> = profile
+        .collect_vars(&name, config_manager)?
+        .into_iter()
+        .collect();
+    let content = match format {
+        ExportFormat::Dotenv => dotenv::render(
+            vars.iter()
+                .map(|(key, value)| (key.as_str(), value.as_str())),
+        ),
+        ExportFormat::Shell => {
+            // The plain commands, without the marker the activation wrapper evaluates
+            let mut generate = ShellGenerate::for_shell(shell.as_deref())?;
+            for (key, value) in &vars {
+                generate.export(key, value);
+            }
+            let script = generate.script();
+            if script.is_empty() {
+                script
+            } else {
+                format!("{script}\n")
+            }
+        }
+    };
+    let mut writer = output::open_output(output.as_deref())?;
+    writer.write_all(content.as_bytes())?;
+    writer.flush()?;
+
+    if let Some(path) = &output {
+        verbosity.show_success(&format!(
+            "Exported {} variables to '{}'",
+            vars.len(),
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+fn diff(
+    a: String,
+    b: String,
+    expand: bool,
+    plain: bool,
+    config_manager: &mut ConfigManager,
+    verbosity: Verbosity,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let diff = config::diff::compare(config_manager, &a, &b, expand)?;
+    if plain {
+        print!("{}", diff.plain(&a, &b));
+        return Ok(());
+    }
+    if diff.is_empty() {
+        verbosity.show_success(&format!("Profiles '{a}' and '{b}' set the same variables."));
+        return Ok(());
+    }
+
+    let quoted = |value: &str| format!("\"{value}\"");
+    if !diff.only_in_a.is_empty() {
+        eprintln!("{}", format!("Only in '{a}':").cyan());
+        for (key, value) in &diff.only_in_a {
+            eprintln!("{}", format!("- {key} = {}", quoted(value)).red());
+        }
+    }
+    if !diff.only_in_b.is_empty() {
+        eprintln!("{}", format!("Only in '{b}':").cyan());
+        for (key, value) in &diff.only_in_b {
+            eprintln!("{}", format!("+ {key} = {}", quoted(value)).green());
+        }
+    }
+    if !diff.changed.is_empty() {
+        eprintln!("{}", "Different values:".cyan());
+        for (key, old, new) in &diff.changed {
+            eprintln!(
+                "{}",
+                format!("~ {key}: {} -> {}", quoted(old), quoted(new)).yellow()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn edit(
+    name: String,
+    config_manager: &mut ConfigManager,
+    verbosity: Verbosity,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !config_manager.profile_exists(&name) {
+        return Err(format!("Profile `{name}` does not exist").into());
+    }
+    let path = config_manager.profile_path(&name);
+    let original = fs::read_to_string(&path)?;
+
+    // Other processes may write while the editor is open
+    config_manager.unlock();
+    let edited = super::run_editor(&path).and_then(|_| Ok(fs::read_to_string(&path)?));
+    config_manager.lock()?;
+    let edited = match edited {
+        Ok(edited) => edited,
+        Err(e) => {
+            config_manager.write_profile_content(&name, &original)?;
+            return Err(e);
+        }
+    };
+    if edited == original {
+        verbosity.show_info(&format!("No changes made to profile '{name}'."));
+        return Ok(());
+    }
+
+    if let Err(e) = check_edited_profile(&name, config_manager) {
+        config_manager.write_profile_content(&name, &original)?;
+        let reason = e.to_string();
+        return Err(format!(
+            "{}\nThe edit was rejected and profile '{name}' restored.",
+            reason.trim_end()
+        )
+        .into());
     }
+    config_manager.record_history(Source::Cli, Operation::Edit(&name));
+    verbosity.show_success(&format!("Profile '{name}' updated successfully."));
+    Ok(())
+}
 
+/// Load profile `name` from its edited file, with its dependencies, and check its keys
+fn check_edited_profile(
+    name: &str,
+    config_manager: &mut ConfigManager,
+) -> Result<(), Box<dyn std::error::Error>> {
+    config_manager.load_profile(name)?;
+    let Some(profile) = config_manager.get_profile(name) else {
+        return Err(format!("Profile `{name}` does not exist").into());
+    };
+    let validation = config_manager.read_settings()?.key_validation(profile);
+    let keys = profile.variables.keys().chain(&profile.unset);
+    for key in keys {
+        if let Err(e) = validate_variable_key_as(VarOp::parse(key).0, validation) {
+            return Err(format!("Invalid variable key `{key}`: {e}").into());
+        }
+    }
     Ok(())
 }
 
 fn create(
     name: String,
+    edit: bool,
+    profile_dir: Option<PathBuf>,
     config_manager: &mut ConfigManager,
+    verbosity: Verbosity,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    if config_manager.profile_exists(&name) {
-        return Err(format!("Profile `{name}` already exists").into());
+    api::create_profile(config_manager, &name)?;
+    config_manager.record_history(Source::Cli, Operation::Create(&name));
+    verbosity.show_success(&format!("Profile '{name}' created successfully."));
+    if !edit {
+        return Ok(());
     }
 
-    if let Err(e) = validate_profile_name(&name) {
-        return Err(format!("Invalid profile name: {}", e).into());
+    if !io::stdin().is_terminal() || !io::stderr().is_terminal() {
+        verbosity.show_info(&format!(
+            "Not opening the editor without an interactive terminal, use `em profile edit {name}` or `em ui` to fill it in."
+        ));
+        return Ok(());
+    }
+    // The TUI takes the lock for each of its own writes
+    config_manager.unlock();
+    tui::run_with(profile_dir, &name, verbosity)
+}
+
+fn new_from_template(
+    template: String,
+    name: String,
+    values: Vec<String>,
+    config_manager: &mut ConfigManager,
+    verbosity: Verbosity,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut placeholder_values = HashMap::new();
+    for item in values {
+        let Some((key, value)) = item.split_once('=') else {
+            return Err(format!("Invalid --set value '{item}', expected KEY=VALUE").into());
+        };
+        placeholder_values.insert(key.trim().to_string(), value.to_string());
     }
 
-    let profile = Profile::new();
-    config_manager.write_profile(&name, &profile)?;
-    display::show_success(&format!("Profile '{name}' created successfully."));
+    api::create_from_template(config_manager, &template, &name, &placeholder_values)?;
+    config_manager.record_history(Source::Cli, Operation::Create(&name));
+    verbosity.show_success(&format!(
+        "Profile '{name}' created from template '{template}'."
+    ));
     Ok(())
 }
 
 fn rename(
     rename_args: ProfileRenameArgs,
     config_manager: &mut ConfigManager,
+    verbosity: Verbosity,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let ProfileRenameArgs {
         src_name,
         dest_name,
     } = rename_args;
 
-    if let Err(e) = validate_profile_name(&dest_name) {
+    if let Err(e) = validate_namespaced_profile_name(&dest_name) {
         return Err(format!("Invalid profile name: {}", e).into());
     }
 
     // Since other profiles may depend on the profile being renamed,
     // all profiles need to be loaded to update their dependency references
-    config_manager.load_all_profiles()?;
-
-    config_manager.rename_profile_file(&src_name, &dest_name)?;
+    let (_, failures) = config_manager.load_all_profiles()?;
+    if let Some((_, e)) = failures.into_iter().next() {
+        return Err(e.into());
+    }
 
-    // Find reverse dependencies and update them (Only checks loaded profiles)
-    if let Some(dependents) = config_manager.get_parents(&src_name) {
-        for dep in dependents {
-            config_manager.update_profile_dependencies(&dep, &src_name, &dest_name);
-            if let Some(profile) = config_manager.get_profile(&dep) {
-                config_manager.write_profile(&dep, profile)?;
-            }
+    // Find reverse dependencies and update them, including those that disabled it
+    let dependents: Vec<String> = config_manager
+        .profiles_iter()
+        .filter(|(_, profile)| profile.profiles.contains(&src_name))
+        .map(|(name, _)| name.clone())
+        .collect();
+    let mut updated = Vec::new();
+    for dep in dependents {
+        config_manager.update_profile_dependencies(&dep, &src_name, &dest_name);
+        if let Some(profile) = config_manager.get_profile(&dep) {
+            updated.push((dep, toml::to_string_pretty(profile)?));
         }
     }
 
-    display::show_success(&format!(
+    // Journaled, so `fix` can finish or undo a rename interrupted halfway
+    let operation = Operation::Rename {
+        from: &src_name,
+        to: &dest_name,
+    };
+    let src_path = config_manager.profile_path(&src_name);
+    if !src_path.exists() {
+        return Err(format!("Profile '{src_name}' not found.").into());
+    }
+    let mut changes = vec![
+        (src_path.clone(), None),
+        (
+            config_manager.profile_path(&dest_name),
+            Some(fs::read_to_string(&src_path)?),
+        ),
+    ];
+    changes.extend(
+        updated
+            .iter()
+            .map(|(dep, content)| (config_manager.profile_path(dep), Some(content.clone()))),
+    );
+    let journal = config_manager.begin_journal(&operation.to_string(), changes)?;
+
+    if let Err(e) = config_manager.rename_profile_file(&src_name, &dest_name) {
+        journal.discard();
+        return Err(e);
+    }
+    config_manager.record_history(Source::Cli, operation);
+    config_manager.update_recent(|recent| recent.rename(&src_name, &dest_name));
+    for (dep, content) in &updated {
+        config_manager.write_profile_content(dep, content)?;
+    }
+    journal.complete()?;
+
+    verbosity.show_success(&format!(
         "Profile '{src_name}' renamed to '{dest_name}' successfully."
     ));
     Ok(())
@@ -98,10 +686,13 @@ fn rename(
 fn delete(
     name: String,
     config_manager: &mut ConfigManager,
+    verbosity: Verbosity,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // No dependency check as requested
-    config_manager.delete_profile_file(&name)?;
-    display::show_success(&format!("Profile '{name}' deleted successfully."));
+    api::delete_profile(config_manager, &name)?;
+    config_manager.record_history(Source::Cli, Operation::Delete(&name));
+    config_manager.update_recent(|recent| recent.remove(&name));
+    verbosity.show_success(&format!("Profile '{name}' deleted successfully."));
     Ok(())
 }
 
@@ -109,60 +700,20 @@ fn add(
     name: String,
     items: Vec<String>,
     config_manager: &mut ConfigManager,
+    verbosity: Verbosity,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    // Load profile to ensure it exists and graph is populated
-    config_manager
-        .load_profile(&name)
-        .map_err(|_| format!("Profile `{name}` does not exist"))?;
-
-    for item in items {
-        if let Some((key, value)) = item.split_once('=') {
-            if let Err(e) = validate_variable_key(key) {
-                return Err(format!("Invalid variable key: {}", e).into());
+    let added = api::add_items(config_manager, &name, &items)?;
+    config_manager.record_history(Source::Cli, Operation::Edit(&name));
+    for item in added {
+        match item {
+            AddedItem::Variable(key) => {
+                verbosity.show_success(&format!("Variable '{key}' added to profile '{name}'."))
             }
-
-            if let Some(profile) = config_manager.get_profile_mut(&name) {
-                profile.add_variable(key, value);
-            }
-            display::show_success(&format!("Variable '{key}' added to profile '{name}'."));
-        } else {
-            let dependency_to_add = &item;
-
-            // Load dependency to check existence
-            if config_manager.load_profile(dependency_to_add).is_err() {
-                return Err(format!(
-                    "Profile `{dependency_to_add}` does not exist and cannot be added as a nested profile."
-                )
-                .into());
-            }
-
-            if name == *dependency_to_add {
-                return Err("A profile cannot depend on itself.".into());
-            }
-
-            // Use the new `find_path` method for a more detailed error message.
-            if let Some(mut path) = config_manager.find_path(dependency_to_add, &name) {
-                path.push(dependency_to_add.to_string()); // Complete the cycle path for display
-                return Err(format!(
-                    "Adding '{dependency_to_add}' to '{name}' would create a circular dependency: {}",
-                    path.join(" -> ")
-                )
-                .into());
-            }
-
-            if let Some(profile) = config_manager.get_profile_mut(&name) {
-                profile.add_profile(dependency_to_add);
-            }
-            display::show_success(&format!(
-                "Nested profile '{dependency_to_add}' added to profile '{name}'."
-            ));
+            AddedItem::Profile(dep) => verbosity.show_success(&format!(
+                "Nested profile '{dep}' added to profile '{name}'."
+            )),
         }
     }
-
-    if let Some(profile) = config_manager.get_profile(&name) {
-        config_manager.write_profile(&name, profile)?;
-    }
-
     Ok(())
 }
 
@@ -170,12 +721,12 @@ fn remove(
     name: String,
     items: Vec<String>,
     config_manager: &mut ConfigManager,
+    verbosity: Verbosity,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // Load profile
-    config_manager
-        .load_profile(&name)
-        .map_err(|_| format!("Profile `{name}` does not exist"))?;
+    api::load_profile(config_manager, &name)?;
 
+    let mut changed = false;
     for item in items {
         let was_variable = if let Some(profile) = config_manager.get_profile_mut(&name) {
             profile.remove_variable(&item).is_some()
@@ -183,27 +734,92 @@ fn remove(
             false
         };
 
-        let was_profile = if let Some(profile) = config_manager.get_profile_mut(&name) {
-            let original_len = profile.profiles.len();
+        // Enabled dependencies were loaded along with the profile, so the graph knows
+        // every edge; disabled ones have none
+        let was_disabled = config_manager
+            .get_profile(&name)
+            .is_some_and(|profile| profile.is_profile_disabled(&item));
+        let was_profile = !was_variable
+            && (was_disabled
+                || (config_manager.has_profile(&item)
+                    && config_manager.remove_dependency_edge(&name, &item)?));
+        if was_profile && let Some(profile) = config_manager.get_profile_mut(&name) {
             profile.remove_profile(&item);
-            profile.profiles.len() < original_len
-        } else {
-            false
-        };
+        }
+        changed |= was_variable || was_profile;
 
         if was_variable {
-            display::show_success(&format!("Variable '{item}' removed from profile '{name}'."));
+            verbosity.show_success(&format!("Variable '{item}' removed from profile '{name}'."));
         } else if was_profile {
-            display::show_success(&format!(
+            verbosity.show_success(&format!(
                 "Nested profile '{item}' removed from profile '{name}'."
             ));
+        } else if config_manager.profile_exists(&item) {
+            display::show_warning(&format!(
+                "Profile '{item}' was not a dependency of '{name}'."
+            ));
         } else {
             display::show_warning(&format!("Item '{item}' not found in profile '{name}'."));
         }
     }
 
+    if changed && let Some(profile) = config_manager.get_profile(&name) {
+        config_manager.write_profile(&name, profile)?;
+        config_manager.record_history(Source::Cli, Operation::Edit(&name));
+    }
+    Ok(())
+}
+
+fn toggle_dep(
+    name: String,
+    dependency: String,
+    config_manager: &mut ConfigManager,
+    verbosity: Verbosity,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let disabled = api::toggle_dependency(config_manager, &name, &dependency)?;
+    config_manager.record_history(Source::Cli, Operation::Edit(&name));
+    let state = if disabled { "disabled" } else { "enabled" };
+    verbosity.show_success(&format!(
+        "Nested profile '{dependency}' {state} in profile '{name}'."
+    ));
+    Ok(())
+}
+
+fn set_meta(
+    name: String,
+    description: Option<String>,
+    tags: Option<String>,
+    config_manager: &mut ConfigManager,
+    verbosity: Verbosity,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if description.is_none() && tags.is_none() {
+        return Err("Nothing to update, pass --description and/or --tags".into());
+    }
+
+    api::load_profile(config_manager, &name)?;
+
+    let tags = tags.map(|tags| parse_tags(&tags));
+    for tag in tags.iter().flatten() {
+        if let Err(e) = validate_tag(tag) {
+            return Err(format!("Invalid tag '{tag}': {}", e).into());
+        }
+    }
+
+    if let Some(profile) = config_manager.get_profile_mut(&name) {
+        if let Some(description) = description {
+            let description = description.trim();
+            profile.description = (!description.is_empty()).then(|| description.to_string());
+        }
+        if let Some(tags) = tags {
+            profile.tags = tags;
+        }
+    }
+
     if let Some(profile) = config_manager.get_profile(&name) {
         config_manager.write_profile(&name, profile)?;
+        config_manager.record_history(Source::Cli, Operation::Edit(&name));
     }
+
+    verbosity.show_success(&format!("Metadata of profile '{name}' updated."));
     Ok(())
 }
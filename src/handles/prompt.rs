@@ -0,0 +1,35 @@
+use crate::utils::session::SessionState;
+
+/// Print the active profiles for a shell prompt.
+///
+/// Only the session tracking variable is read, so this stays cheap enough to run on
+/// every prompt. Nothing is printed when no profile is active.
+pub fn handle(
+    format: String,
+    separator: String,
+    symbol: Option<String>,
+    max: Option<usize>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let session = SessionState::from_env();
+    let profiles = session.active_profiles();
+    if profiles.is_empty() {
+        return Ok(());
+    }
+
+    let shown = max.map_or(profiles.len(), |max| max.min(profiles.len()));
+    let mut names: Vec<String> = profiles[..shown].to_vec();
+    if shown < profiles.len() {
+        names.push(format!("+{}", profiles.len() - shown));
+    }
+
+    let line = format
+        .replace("{names}", &names.join(&separator))
+        .replace("{count}", &profiles.len().to_string());
+
+    // No trailing newline, prompts embed the output as is
+    match symbol {
+        Some(symbol) => print!("{symbol} {line}"),
+        None => print!("{line}"),
+    }
+    Ok(())
+}
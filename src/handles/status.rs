@@ -1,17 +1,24 @@
+use crate::api;
 use crate::cli::CommandsStatusArgs;
 use crate::config::ConfigManager;
-use crate::config::models::Profile;
+use crate::config::models::{LIST_SEPARATOR, Profile, VarOp};
+use crate::utils::json;
+use crate::utils::session::SessionState;
 use colored::*;
+use serde::Serialize;
 use std::collections::BTreeMap;
 use std::error::Error;
+use std::path::PathBuf;
 
 #[derive(Debug)]
 struct VarStatus {
     profile_value: String,
     shell_value: Option<String>,
+    op: VarOp,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
 enum VarState {
     Match,
     Mismatch,
@@ -19,17 +26,20 @@ enum VarState {
 }
 
 impl VarStatus {
+    /// The status of `key` as stored in a profile, which may be a list key like `PATH+`
     fn new(key: &str, profile_value: &str) -> Self {
+        let (name, op) = VarOp::parse(key);
         Self {
             profile_value: profile_value.to_string(),
-            shell_value: std::env::var(key).ok(),
+            shell_value: std::env::var(name).ok(),
+            op,
         }
     }
 
     fn state(&self) -> VarState {
         match &self.shell_value {
             Some(shell_val) => {
-                if shell_val == &self.profile_value {
+                if self.is_applied(shell_val) {
                     VarState::Match
                 } else {
                     VarState::Mismatch
@@ -38,10 +48,32 @@ impl VarStatus {
             None => VarState::ProfileOnly,
         }
     }
+
+    /// Whether the shell value is as the profile leaves it; a list key only needs its
+    /// entries to be in the list
+    fn is_applied(&self, shell_val: &str) -> bool {
+        match self.op {
+            VarOp::Set => shell_val == self.profile_value,
+            VarOp::Append | VarOp::Prepend => {
+                let entries = shell_val.split(LIST_SEPARATOR);
+                self.profile_value
+                    .split(LIST_SEPARATOR)
+                    .filter(|entry| !entry.is_empty())
+                    .all(|entry| entries.clone().any(|e| e == entry))
+            }
+        }
+    }
 }
 
-pub fn handle(args: CommandsStatusArgs) -> Result<(), Box<dyn Error>> {
-    let mut config_manager = ConfigManager::new()?;
+pub fn handle(
+    args: CommandsStatusArgs,
+    profile_dir: Option<PathBuf>,
+) -> Result<(), Box<dyn Error>> {
+    let mut config_manager = ConfigManager::new(profile_dir)?;
+    let session = SessionState::from_env();
+    if args.json {
+        return print_status_json(args.profiles, &session, &mut config_manager);
+    }
     for (i, profile_name) in args.profiles.iter().enumerate() {
         if !config_manager.profile_exists(profile_name) {
             eprintln!(
@@ -53,7 +85,6 @@ pub fn handle(args: CommandsStatusArgs) -> Result<(), Box<dyn Error>> {
 
         config_manager.load_profile(profile_name)?;
 
-        let profile = config_manager.get_profile(profile_name).unwrap();
         let is_last_profile = i == args.profiles.len() - 1;
         let profile_prefix = if is_last_profile {
             "└──"
@@ -61,19 +92,65 @@ pub fn handle(args: CommandsStatusArgs) -> Result<(), Box<dyn Error>> {
             "├──"
         };
 
-        eprintln!("{profile_prefix} {}", profile_name.cyan());
+        // Variables deactivated one by one leave their profile active but no longer tracked
+        let tracked = active_tracked_vars(&session, profile_name);
+        let mut header = format!("{profile_prefix} {}", profile_name.cyan());
+        if let Some(partial) = partial_activation(&mut config_manager, &session, profile_name)? {
+            let note = format!(
+                "[Partially active: {} of {} variables]",
+                partial.remaining.len(),
+                partial.total
+            );
+            header.push_str(&format!(" {}", note.yellow()));
+        }
+        eprintln!("{header}");
 
+        let profile = config_manager.get_profile(profile_name).unwrap();
         let indent = if is_last_profile { "    " } else { "│   " };
-        display_profile_status(profile, &config_manager, args.expand, indent)?;
+        display_profile_status(profile, &config_manager, args.expand, tracked, indent)?;
     }
 
     Ok(())
 }
 
+/// The variables env-manage still tracks when `profile` is active in the current session
+fn active_tracked_vars<'a>(session: &'a SessionState, profile: &str) -> Option<&'a [String]> {
+    let active = session.active_profiles().iter().any(|name| name == profile);
+    (active && !session.tracked_vars().is_empty()).then(|| session.tracked_vars())
+}
+
+/// An active profile some of whose variables were deactivated one by one
+struct PartialActivation {
+    /// The variables still active, sorted
+    remaining: Vec<String>,
+    /// How many variables the profile sets in total
+    total: usize,
+}
+
+/// `None` unless `profile` is active with some of its variables deactivated
+fn partial_activation(
+    config_manager: &mut ConfigManager,
+    session: &SessionState,
+    profile: &str,
+) -> Result<Option<PartialActivation>, Box<dyn Error>> {
+    let Some(tracked) = active_tracked_vars(session, profile) else {
+        return Ok(None);
+    };
+    let resolved = api::resolve_env(config_manager, profile)?;
+    let total = resolved.len();
+    let mut remaining: Vec<String> = resolved
+        .into_keys()
+        .filter(|key| tracked.contains(key))
+        .collect();
+    remaining.sort();
+    Ok((remaining.len() < total).then_some(PartialActivation { remaining, total }))
+}
+
 fn display_profile_status(
     profile: &Profile,
     config_manager: &ConfigManager,
     expand: bool,
+    tracked: Option<&[String]>,
     indent: &str,
 ) -> Result<(), Box<dyn Error>> {
     let mut statuses = BTreeMap::new();
@@ -81,7 +158,7 @@ fn display_profile_status(
         statuses.insert(key.clone(), VarStatus::new(key, value));
     }
 
-    let has_nested_profiles = expand && !profile.profiles.is_empty();
+    let has_nested_profiles = expand && profile.enabled_profiles().next().is_some();
     let mut max_key_len = statuses.keys().map(|k| k.len()).max().unwrap_or(0);
     if has_nested_profiles {
         max_key_len = max_key_len.max("profiles".len());
@@ -95,6 +172,13 @@ fn display_profile_status(
         let key_part = format!("{key}:");
         let padded_key_part = format!("{:<width$}", key_part, width = max_key_len + 2);
 
+        let name = VarOp::parse(key).0;
+        if tracked.is_some_and(|tracked| !tracked.iter().any(|t| t == name)) {
+            let output = format!("{} {}", status.profile_value, "[Deactivated]".dimmed());
+            eprintln!("{} {}{}", line, padded_key_part.dimmed(), output);
+            continue;
+        }
+
         match status.state() {
             VarState::Match => {
                 eprintln!(
@@ -128,7 +212,7 @@ fn display_profile_status(
 
         let nested_indent = format!("{indent}    ");
 
-        let mut profile_iter = profile.profiles.iter().peekable();
+        let mut profile_iter = profile.enabled_profiles().peekable();
         while let Some(nested_name) = profile_iter.next() {
             if let Some(nested_profile) = config_manager.get_profile(nested_name) {
                 let is_last_nested = profile_iter.peek().is_none();
@@ -145,10 +229,93 @@ fn display_profile_status(
 
                 let last_nested_indent = if is_last_nested { "    " } else { "│   " };
                 let final_indent = format!("{nested_indent}{last_nested_indent}");
-                display_profile_status(nested_profile, config_manager, false, &final_indent)?;
+                display_profile_status(
+                    nested_profile,
+                    config_manager,
+                    false,
+                    tracked,
+                    &final_indent,
+                )?;
             }
         }
     }
 
     Ok(())
 }
+
+#[derive(Serialize)]
+struct StatusJson {
+    version: u32,
+    /// Profiles activated in the current shell session
+    active_profiles: Vec<String>,
+    /// Profiles the expected values were resolved from
+    profiles: Vec<String>,
+    /// Active profiles with variables deactivated one by one, and the variables still active
+    partially_active: BTreeMap<String, Vec<String>>,
+    variables: BTreeMap<String, VarStatusJson>,
+}
+
+#[derive(Serialize)]
+struct VarStatusJson {
+    expected: String,
+    current: Option<String>,
+    state: VarState,
+    /// Whether env-manage set this variable in the current shell session
+    tracked: bool,
+}
+
+/// Report the resolved values of `profiles`, or of the session's active profiles, as JSON
+fn print_status_json(
+    profiles: Vec<String>,
+    session: &SessionState,
+    config_manager: &mut ConfigManager,
+) -> Result<(), Box<dyn Error>> {
+    let requested = if profiles.is_empty() {
+        session.active_profiles().to_vec()
+    } else {
+        profiles
+    };
+    let mut profiles = Vec::new();
+    for name in requested {
+        if config_manager.profile_exists(&name) {
+            profiles.push(name);
+        } else {
+            eprintln!(
+                "{}",
+                format!("Warning: Profile '{name}' not found.").yellow()
+            );
+        }
+    }
+
+    let mut partially_active = BTreeMap::new();
+    for name in &profiles {
+        if let Some(partial) = partial_activation(config_manager, session, name)? {
+            partially_active.insert(name.clone(), partial.remaining);
+        }
+    }
+
+    let (vars, _) = api::resolve_envs(config_manager, &profiles)?;
+    let variables = vars
+        .into_iter()
+        .map(|(key, value)| {
+            let status = VarStatus::new(&key, &value);
+            let entry = VarStatusJson {
+                state: status.state(),
+                tracked: session.tracked_vars().contains(&key),
+                expected: status.profile_value,
+                current: status.shell_value,
+            };
+            (key, entry)
+        })
+        .collect();
+
+    let report = StatusJson {
+        version: json::JSON_SCHEMA_VERSION,
+        active_profiles: session.active_profiles().to_vec(),
+        profiles,
+        partially_active,
+        variables,
+    };
+    println!("{}", json::to_string_pretty(&report)?);
+    Ok(())
+}
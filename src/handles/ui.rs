@@ -1,5 +1,22 @@
 use crate::tui::run;
+use crate::utils::display::Verbosity;
+use std::io::{self, IsTerminal};
+use std::path::PathBuf;
 
-pub fn handle() -> Result<(), Box<dyn std::error::Error>> {
-    run()
+pub fn handle(
+    read_only: bool,
+    theme: Option<String>,
+    profile_dir: Option<PathBuf>,
+    verbosity: Verbosity,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Checked before the terminal is touched, so nothing is left half set up
+    if !io::stdin().is_terminal() || !io::stderr().is_terminal() {
+        return Err(
+            "The TUI needs an interactive terminal, but stdin or stderr is not one. \
+                    Use the CLI instead, e.g. `em profile list`, `em profile show <name>` \
+                    or `em profile edit <name>`."
+                .into(),
+        );
+    }
+    run(profile_dir, read_only, theme, verbosity)
 }
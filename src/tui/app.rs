@@ -1,17 +1,30 @@
 use super::event::handle_event;
+use super::session::Session;
+use super::terminal::{self, TerminalGuard};
+use super::theme::{DEFAULT_THEME, Theme};
 use super::ui::ui;
-use super::views::{add_new::AddNewView, edit::EditView, list::ListView};
+use super::utils::CountPrefix;
+use super::views::add_new::AddNewView;
+use super::views::edit::{self, EditView};
+use super::views::expand::ExpandView;
+use super::views::list::{ListView, ProfileEntry, SortMode};
+use super::widgets::diff_popup;
 use crate::GLOBAL_PROFILE_MARK;
 use crate::config::ConfigManager;
-use crate::config::models::Profile;
-use ratatui::crossterm::execute;
-use ratatui::crossterm::terminal::{
-    EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
-};
+use crate::config::diff::{self, ProfileDiff};
+use crate::config::history::{Operation, Source};
+use crate::config::journal::Journal;
+use crate::config::models::{DEFAULT_MAX_ENV_SIZE, Profile};
+use crate::config::recent::RecentProfiles;
+use crate::handles::activate;
+use crate::utils::display::Verbosity;
 use ratatui::prelude::Backend;
 use ratatui::{Terminal, prelude::CrosstermBackend};
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
 
 #[derive(Default, Debug, PartialEq, Eq)]
 pub enum AppState {
@@ -22,13 +35,123 @@ pub enum AppState {
     Rename,
     ConfirmDelete,
     ConfirmExit,
+    /// Leaving the edit view would drop variable rows with invalid keys
+    ConfirmDropInvalid,
+    Export,
+    /// The recent status messages, newest first
+    StatusLog,
+    /// The recently edited profiles, to jump back to one
+    Recent,
+    /// The variables of two profiles compared, read-only
+    Diff,
 }
 
-#[derive(Default, PartialEq, Eq)]
+#[derive(Default, Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum MainRightViewMode {
     #[default]
     Raw,
     Expand,
+    /// The profiles the selected one depends on and the profiles that depend on it
+    Dependents,
+}
+
+/// An action that needs every profile in memory, run once they are all loaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FullLoadAction {
+    /// Search matches descriptions and tags, which are only known once loaded
+    Search,
+    /// The delete check needs every profile that could depend on the selected one
+    Delete,
+    /// Renaming updates every profile that references the old name
+    Rename,
+    /// The selector hides profiles that would close a cycle
+    DependencySelector,
+    /// Dependents can be declared in any profile, loaded or not
+    Dependents,
+}
+
+/// What deleting profiles would affect, prepared when the delete confirmation opens
+#[derive(Debug, Default)]
+pub struct DeleteImpact {
+    /// The selected profile, or every marked one
+    pub names: Vec<String>,
+    pub variable_count: usize,
+    /// Profiles the deleted ones depend on, other than the deleted ones themselves
+    pub dependencies: Vec<String>,
+    /// Profiles that are kept because others that are not deleted inherit from them,
+    /// with those dependents
+    pub blocked: BTreeMap<String, Vec<String>>,
+}
+
+impl DeleteImpact {
+    /// The profiles that will actually be deleted
+    pub fn deletable(&self) -> impl Iterator<Item = &String> {
+        self.names
+            .iter()
+            .filter(|name| !self.blocked.contains_key(*name))
+    }
+
+    /// The first profile keeping a blocked one alive, to jump to and edit
+    pub fn first_dependent(&self) -> Option<&String> {
+        self.blocked.values().next().and_then(|deps| deps.first())
+    }
+}
+
+/// The comparison shown in the diff popup
+#[derive(Debug)]
+pub struct DiffPopup {
+    pub a: String,
+    pub b: String,
+    /// Whether the resolved variables are compared rather than the profiles' own
+    pub expand: bool,
+    pub diff: ProfileDiff,
+    /// Lines scrolled past
+    pub offset: usize,
+}
+
+/// How long a status message stays in the bottom bar unless it is replaced
+const STATUS_DURATION: Duration = Duration::from_secs(4);
+/// How many past status messages the log keeps
+pub const STATUS_HISTORY_LEN: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusSeverity {
+    Info,
+    Success,
+    Error,
+}
+
+/// A message shown in the bottom bar until it expires or is replaced
+#[derive(Debug, Clone)]
+pub struct StatusMessage {
+    pub text: String,
+    pub severity: StatusSeverity,
+    created: Instant,
+}
+
+impl StatusMessage {
+    pub fn new(text: impl Into<String>, severity: StatusSeverity) -> Self {
+        Self {
+            text: text.into(),
+            severity,
+            created: Instant::now(),
+        }
+    }
+
+    /// Time since the message was shown
+    pub fn age(&self) -> Duration {
+        self.created.elapsed()
+    }
+
+    /// Time left before the message expires
+    pub fn remaining(&self) -> Duration {
+        STATUS_DURATION.saturating_sub(self.created.elapsed())
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.remaining().is_zero()
+    }
 }
 
 pub struct App {
@@ -38,10 +161,40 @@ pub struct App {
     pub add_new_view: AddNewView,
     pub edit_view: EditView,
     pub main_right_view_mode: MainRightViewMode,
-    pub expand_env_vars: Option<HashMap<String, String>>,
+    pub expand_view: ExpandView,
     pub list_view: ListView,
-    pub status_message: Option<String>,
+    pub status_message: Option<StatusMessage>,
+    /// Every recent status message, oldest first, bounded by `STATUS_HISTORY_LEN`
+    pub status_history: VecDeque<StatusMessage>,
+    /// How many of the newest messages the status log has scrolled past
+    pub status_log_offset: usize,
     pub pending_deletes: HashMap<String, String>,
+    pub delete_impact: Option<DeleteImpact>,
+    /// Profiles are loaded when first needed; this is set once all of them are in memory
+    fully_loaded: bool,
+    pending_full_load: Option<FullLoadAction>,
+    /// Why each profile that failed to load did so; they are not retried on every frame
+    load_errors: HashMap<String, String>,
+    /// Set by `--read-only` or an unwritable profiles directory; every change is refused
+    pub read_only: bool,
+    /// Profile to activate in the calling shell once the TUI has closed
+    pub activate_on_exit: Option<String>,
+    /// Count typed before a list or edit motion key
+    pub count_prefix: CountPrefix,
+    /// Profiles recently opened in the editor, persisted across sessions
+    pub recent: RecentProfiles,
+    /// Row selected in the recent profiles popup
+    pub recent_index: usize,
+    /// Profile marked with `D` to compare the next one against
+    pub diff_base: Option<String>,
+    pub diff_popup: Option<DiffPopup>,
+    /// Edit views left by opening one of their dependencies, innermost last; `Esc`
+    /// returns to them
+    pub edit_stack: Vec<EditView>,
+    /// Colors of every widget, chosen once at startup
+    pub theme: Theme,
+    /// Bytes of resolved variables above which the expand pane warns
+    pub max_env_size: usize,
 }
 
 impl App {
@@ -57,57 +210,242 @@ impl App {
             edit_view: EditView::new(),
             list_view: ListView::new(),
             status_message: None,
+            status_history: VecDeque::new(),
+            status_log_offset: 0,
             pending_deletes: Default::default(),
             main_right_view_mode: Default::default(),
-            expand_env_vars: Default::default(),
+            expand_view: Default::default(),
+            delete_impact: None,
+            fully_loaded: false,
+            pending_full_load: None,
+            load_errors: HashMap::new(),
+            read_only: false,
+            activate_on_exit: None,
+            count_prefix: CountPrefix::default(),
+            recent: RecentProfiles::default(),
+            recent_index: 0,
+            diff_base: None,
+            diff_popup: None,
+            edit_stack: Vec::new(),
+            theme: Theme::default(),
+            max_env_size: DEFAULT_MAX_ENV_SIZE,
         };
-        app.load_profiles();
+        let settings = app.config_manager.read_settings().unwrap_or_default();
+        app.add_new_view.key_validation = settings.validation;
+        app.max_env_size = settings.max_env_size();
+        app.refresh_profile_list();
+        app.recent = RecentProfiles::load(app.config_manager.base_path());
+        let names: HashSet<&String> = app.list_view.all_profiles().into_iter().collect();
+        app.recent.retain(|name| names.contains(&name.to_string()));
         app
     }
 
+    pub fn show_info(&mut self, text: impl Into<String>) {
+        self.set_status(StatusMessage::new(text, StatusSeverity::Info));
+    }
+
+    pub fn show_success(&mut self, text: impl Into<String>) {
+        self.set_status(StatusMessage::new(text, StatusSeverity::Success));
+    }
+
+    pub fn show_error(&mut self, text: impl Into<String>) {
+        self.set_status(StatusMessage::new(text, StatusSeverity::Error));
+    }
+
+    fn set_status(&mut self, message: StatusMessage) {
+        if self.status_history.len() == STATUS_HISTORY_LEN {
+            self.status_history.pop_front();
+        }
+        self.status_history.push_back(message.clone());
+        self.status_message = Some(message);
+    }
+
+    pub fn open_status_log(&mut self) {
+        self.status_log_offset = 0;
+        self.state = AppState::StatusLog;
+    }
+
+    /// Scroll the status log towards older messages, stopping at the oldest
+    pub fn scroll_status_log_down(&mut self) {
+        let last = self.status_history.len().saturating_sub(1);
+        self.status_log_offset = (self.status_log_offset + 1).min(last);
+    }
+
+    pub fn scroll_status_log_up(&mut self) {
+        self.status_log_offset = self.status_log_offset.saturating_sub(1);
+    }
+
+    /// In read-only mode, tell the user the action is unavailable and return true
+    pub fn refuse_if_read_only(&mut self) -> bool {
+        if self.read_only {
+            self.show_error("Read-only mode, changes are disabled");
+        }
+        self.read_only
+    }
+
+    /// Drop the status message once it has been shown long enough
+    pub fn expire_status(&mut self) {
+        if self.status_message.as_ref().is_some_and(|m| m.is_expired()) {
+            self.status_message = None;
+        }
+    }
+
     pub fn save_selected(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         let name = match self.list_view.current_profile() {
             Some(n) => n.to_string(),
             None => return Ok(()),
         };
 
+        self.config_manager.lock()?;
+        let result = self.save_profile(&name);
+        self.config_manager.unlock();
+        result
+    }
+
+    fn save_profile(&mut self, name: &str) -> Result<(), Box<dyn std::error::Error>> {
         // Optimized logic: O(1) lookup ensures we delete the original file
-        if let Some(old_name) = self.pending_deletes.remove(&name) {
-            self.config_manager.delete_profile_file(&old_name)?;
+        let renamed_from = self.pending_deletes.remove(name);
+        if let Some(old_name) = &renamed_from {
+            self.config_manager.delete_profile_file(old_name)?;
         }
 
-        if self.list_view.is_dirty(&name)
-            && let Some(profile) = self.config_manager.get_profile(&name)
-        {
-            self.config_manager.write_profile(&name, profile)?;
-            self.list_view.clear_dirty(&name);
+        if self.list_view.is_dirty(name) && self.config_manager.has_profile(name) {
+            self.write_profile_file(name, renamed_from.as_deref())?;
+            self.list_view.clear_dirty(name);
+            self.refresh_profile_list();
         }
 
         Ok(())
     }
 
+    /// Write the in-memory profile `name` to disk and record the change in the history log
+    fn write_profile_file(
+        &mut self,
+        name: &str,
+        renamed_from: Option<&str>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(profile) = self.config_manager.get_profile(name) else {
+            return Ok(());
+        };
+        let operation = match renamed_from {
+            Some(from) => Operation::Rename { from, to: name },
+            None if self.config_manager.profile_exists(name) => Operation::Edit(name),
+            None => Operation::Create(name),
+        };
+        self.config_manager.write_profile(name, profile)?;
+        self.config_manager.record_history(Source::Tui, operation);
+        // The saved profile may repair the ones that failed to load, so they are retried
+        self.load_errors.clear();
+        Ok(())
+    }
+
+    /// Save the marked profiles that have unsaved changes
+    pub fn save_marked(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let names: Vec<String> = self
+            .list_view
+            .marked_profiles()
+            .into_iter()
+            .filter(|name| self.list_view.is_dirty(name))
+            .collect();
+        if names.is_empty() {
+            self.show_info("No unsaved changes in the selected profiles");
+            return Ok(());
+        }
+
+        self.config_manager.lock()?;
+        let mut saved = Vec::new();
+        let mut result = Ok(());
+        for name in &names {
+            if let Err(e) = self.save_profile(name) {
+                result = Err(e);
+                break;
+            }
+            if !self.list_view.is_dirty(name) {
+                saved.push(name.as_str());
+            }
+        }
+        self.config_manager.unlock();
+        result?;
+
+        self.show_success(format!(
+            "Saved {} selected profiles: {}",
+            saved.len(),
+            saved.join(", ")
+        ));
+        Ok(())
+    }
+
     pub fn save_all(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.config_manager.lock()?;
+        let result = self.save_dirty_profiles();
+        self.config_manager.unlock();
+        result
+    }
+
+    fn save_dirty_profiles(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         let dirty_names: Vec<String> = self.list_view.dirty_profiles_iter().cloned().collect();
+        let journal = self.begin_save_journal(&dirty_names)?;
         // Process all pending deletes
         let pending_keys: Vec<String> = self.pending_deletes.keys().cloned().collect();
+        let mut renamed_from = HashMap::new();
         for new_name in pending_keys {
             if let Some(old_name) = self.pending_deletes.remove(&new_name) {
                 self.config_manager.delete_profile_file(&old_name)?;
+                renamed_from.insert(new_name, old_name);
             }
         }
+        let mut failed = false;
         for name in dirty_names {
-            if let Some(profile) = self.config_manager.get_profile(&name) {
-                if let Err(e) = self.config_manager.write_profile(&name, profile) {
-                    self.status_message = Some(format!("Error saving profile '{}': {}", name, e));
-                } else {
-                    self.list_view.clear_dirty(&name);
-                }
+            if !self.config_manager.has_profile(&name) {
+                continue;
+            }
+            let old_name = renamed_from.get(&name).map(String::as_str);
+            if let Err(e) = self.write_profile_file(&name, old_name) {
+                self.show_error(format!("Error saving profile '{}': {}", name, e));
+                failed = true;
+            } else {
+                self.list_view.clear_dirty(&name);
             }
         }
+        // A failed write leaves the entry open for `em fix` to finish or undo
+        if let Some(journal) = journal
+            && !failed
+        {
+            journal.complete()?;
+        }
+        self.refresh_profile_list();
 
         Ok(())
     }
 
+    /// Journal saving `dirty_names` and the pending deletes, unless that touches a
+    /// single file
+    fn begin_save_journal(
+        &self,
+        dirty_names: &[String],
+    ) -> Result<Option<Journal>, Box<dyn std::error::Error>> {
+        let mut changes: Vec<(PathBuf, Option<String>)> = self
+            .pending_deletes
+            .values()
+            .map(|old_name| (self.config_manager.profile_path(old_name), None))
+            .collect();
+        let mut saved = Vec::new();
+        for name in dirty_names {
+            if let Some(profile) = self.config_manager.get_profile(name) {
+                let content = toml::to_string_pretty(profile)?;
+                changes.push((self.config_manager.profile_path(name), Some(content)));
+                saved.push(name.as_str());
+            }
+        }
+        if changes.len() < 2 {
+            return Ok(None);
+        }
+        let operation = format!("save {}", saved.join(", "));
+        Ok(Some(
+            self.config_manager.begin_journal(&operation, changes)?,
+        ))
+    }
+
     pub fn rename_profile(&mut self, new_name: String) -> Result<(), Box<dyn std::error::Error>> {
         let old_name = match self.list_view.current_profile() {
             Some(n) => n.to_string(),
@@ -123,11 +461,12 @@ impl App {
         }
 
         // 1. Update Profile Map
-        if let Some(profile) = self.config_manager.remove_profile(&old_name) {
-            self.config_manager.add_profile(new_name.clone(), profile);
-        } else {
+        let Some(mut profile) = self.config_manager.remove_profile(&old_name) else {
             return Err(format!("Profile '{old_name}' not found in memory.").into());
-        }
+        };
+        // Taking the name of a missing dependency would make the profile list itself
+        let dropped_self_reference = profile.remove_profile(&new_name);
+        self.config_manager.add_profile(new_name.clone(), profile);
 
         // 2. Queue old name for deletion (Linked to new name)
         // Path compression: if old_name was itself a rename, point new_name to the original ancestor
@@ -141,9 +480,7 @@ impl App {
         // 3. Update Dependencies (other profiles that use old_name)
         let mut affected_profiles = Vec::new();
         for (name, profile) in self.config_manager.profiles_iter_mut() {
-            if profile.profiles.contains(&old_name) {
-                profile.profiles.remove(&old_name);
-                profile.profiles.insert(new_name.clone());
+            if profile.rename_profile(name, &old_name, &new_name) {
                 affected_profiles.push(name.clone());
             }
         }
@@ -162,60 +499,481 @@ impl App {
         self.config_manager
             .rename_profile_node(&old_name, new_name.clone())?;
 
+        self.recent.rename(&old_name, &new_name);
+        self.recent.save(self.config_manager.base_path());
+
         // 7. Update List Component
-        let mut profiles = self.list_view.all_profiles().to_vec();
-        if let Some(pos) = profiles.iter().position(|n| n == &old_name) {
-            profiles[pos] = new_name.clone();
-        }
-        profiles.sort();
-        self.list_view.update_profiles(profiles);
+        self.refresh_profile_list();
 
         // Fix selected index to follow the renamed item
-        if let Some(new_index) = self
-            .list_view
-            .all_profiles()
-            .iter()
-            .position(|n| n == &new_name)
-        {
-            self.list_view.set_selected_index(new_index);
-        }
+        self.list_view.select_profile(&new_name);
 
-        self.status_message = Some(format!("Renamed '{old_name}' to '{new_name}'"));
+        if dropped_self_reference {
+            self.show_error(format!(
+                "Renamed '{old_name}' to '{new_name}', dropping its dependency on '{new_name}'"
+            ));
+        } else {
+            self.show_success(format!("Renamed '{old_name}' to '{new_name}'"));
+        }
         Ok(())
     }
 
     pub fn start_editing(&mut self, profile_name: &str) {
+        if !self.ensure_loaded(profile_name) {
+            self.start_repairing(profile_name);
+            return;
+        }
         if let Some(profile) = self.config_manager.get_profile(profile_name) {
             self.edit_view = EditView::from_profile(profile_name, profile);
+            self.edit_view
+                .set_default_validation(self.add_new_view.key_validation);
             self.state = AppState::Edit;
+            self.remember_recent(profile_name);
+        }
+    }
+
+    /// Edit a profile that failed to load, starting from its file when it still parses
+    fn start_repairing(&mut self, profile_name: &str) {
+        let profile = match self.config_manager.read_profile_file(profile_name) {
+            Ok(profile) => {
+                self.show_info(format!(
+                    "'{profile_name}' failed to load, editing it as stored on disk"
+                ));
+                profile
+            }
+            Err(e) => {
+                self.show_error(format!(
+                    "Could not read '{profile_name}' ({e}), saving replaces it with this empty profile"
+                ));
+                Profile::new()
+            }
+        };
+        self.edit_view = EditView::from_profile(profile_name, &profile);
+        self.edit_view
+            .set_default_validation(self.add_new_view.key_validation);
+        self.state = AppState::Edit;
+        self.remember_recent(profile_name);
+    }
+
+    fn remember_recent(&mut self, profile_name: &str) {
+        self.recent.touch(profile_name);
+        self.recent.save(self.config_manager.base_path());
+    }
+
+    /// Mark the selected profile as the diff base, or compare it against the base.
+    /// Pressing it on the base again clears it.
+    pub fn diff_with_base(&mut self) {
+        let Some(name) = self.list_view.current_profile().map(|s| s.to_string()) else {
+            return;
+        };
+        match self.diff_base.take() {
+            Some(base) if base == name => self.show_info("Diff base cleared"),
+            Some(base) => {
+                self.diff_base = Some(base.clone());
+                self.open_diff(base, name, false);
+            }
+            None => {
+                self.show_info(format!(
+                    "Diff base '{}', press D on another profile to compare",
+                    display_name(&name)
+                ));
+                self.diff_base = Some(name);
+            }
+        }
+    }
+
+    fn open_diff(&mut self, a: String, b: String, expand: bool) {
+        match diff::compare(&mut self.config_manager, &a, &b, expand) {
+            Ok(diff) => {
+                self.diff_popup = Some(DiffPopup {
+                    a,
+                    b,
+                    expand,
+                    diff,
+                    offset: 0,
+                });
+                self.state = AppState::Diff;
+            }
+            Err(e) => self.show_error(format!("Cannot compare profiles: {e}")),
+        }
+    }
+
+    /// Switch the open diff between the profiles' own and their resolved variables
+    pub fn toggle_diff_expand(&mut self) {
+        if let Some(popup) = self.diff_popup.take() {
+            self.open_diff(popup.a, popup.b, !popup.expand);
+            if self.diff_popup.is_none() {
+                self.state = AppState::List;
+            }
         }
     }
 
-    pub fn load_profiles(&mut self) {
-        let profiles = self.config_manager.list_profile_names().to_vec();
-        self.list_view.update_profiles(profiles);
+    pub fn close_diff(&mut self) {
+        self.diff_popup = None;
+        self.state = AppState::List;
+    }
+
+    /// Scroll the diff popup by `delta` lines, staying within its content
+    pub fn scroll_diff(&mut self, delta: isize) {
+        if let Some(popup) = &mut self.diff_popup {
+            let last = diff_popup::line_count(&popup.diff).saturating_sub(1);
+            popup.offset = popup.offset.saturating_add_signed(delta).min(last);
+        }
+    }
+
+    /// Open the popup listing the recently edited profiles
+    pub fn open_recent(&mut self) {
+        if self.recent.is_empty() {
+            self.show_info("No recently edited profiles yet");
+            return;
+        }
+        self.recent_index = 0;
+        self.state = AppState::Recent;
+    }
+
+    pub fn select_next_recent(&mut self) {
+        let count = self.recent.names().len();
+        if count > 0 {
+            self.recent_index = (self.recent_index + 1) % count;
+        }
+    }
+
+    pub fn select_previous_recent(&mut self) {
+        let count = self.recent.names().len();
+        if count > 0 {
+            self.recent_index = (self.recent_index + count - 1) % count;
+        }
+    }
+
+    /// Close the recent profiles popup and open the `index`th of them in the editor
+    pub fn jump_to_recent(&mut self, index: usize) {
+        let Some(name) = self.recent.names().get(index).cloned() else {
+            return;
+        };
+        self.state = AppState::List;
+        if !self.list_view.select_profile(&name) {
+            self.recent.remove(&name);
+            self.recent.save(self.config_manager.base_path());
+            self.show_error(format!("Profile '{name}' no longer exists"));
+            return;
+        }
+        if !self.read_only {
+            self.start_editing(&name);
+        }
+    }
+
+    /// Select `name` in the list and open it in the editor, as `Enter` on it would, so
+    /// leaving the editor returns to the list
+    pub fn open_in_editor(&mut self, name: &str) {
+        self.state = AppState::List;
+        if !self.list_view.select_profile(name) {
+            self.show_error(format!("Profile '{name}' not found"));
+            return;
+        }
+        if self.read_only {
+            self.show_info(format!("Read-only mode, '{name}' cannot be edited"));
+            return;
+        }
+        self.start_editing(name);
+    }
+
+    /// Whether a profile called `name` is in memory or has a file on disk, including
+    /// files created outside the TUI since it started
+    pub fn is_name_taken(&self, name: &str) -> bool {
+        self.config_manager.has_profile(name)
+            || self
+                .config_manager
+                .scan_profile_names()
+                .is_ok_and(|names| names.iter().any(|existing| existing == name))
+    }
+
+    /// Why profile `name` failed to load, if it did
+    pub fn load_error(&self, name: &str) -> Option<&str> {
+        self.load_errors.get(name).map(String::as_str)
+    }
+
+    /// Load `name` and the profiles it depends on unless they are already in memory.
+    ///
+    /// Failures are reported once in the status bar and kept for the list and the
+    /// right pane to show.
+    pub fn ensure_loaded(&mut self, name: &str) -> bool {
+        if self.config_manager.has_profile(name) {
+            return true;
+        }
+        if self.load_errors.contains_key(name) {
+            return false;
+        }
+
+        match self.config_manager.load_profile(name) {
+            Ok(()) => {
+                if let Some(profile) = self.config_manager.get_profile(name)
+                    && let Some(entry) = self.list_view.entry_mut(name)
+                {
+                    entry.fill_from(profile);
+                }
+                true
+            }
+            Err(e) => {
+                self.show_error(format!("Error loading profile '{name}': {e}"));
+                self.load_errors.insert(name.to_string(), e.to_string());
+                false
+            }
+        }
+    }
+
+    /// Load the selected profile so the right pane can show it
+    pub fn load_selected(&mut self) {
+        if let Some(name) = self.list_view.current_profile().map(|s| s.to_string()) {
+            self.ensure_loaded(&name);
+        }
+    }
+
+    /// Run `action` right away when every profile is loaded, otherwise after the
+    /// next frame has shown the loading message
+    pub fn with_all_profiles(&mut self, action: FullLoadAction) {
+        if self.fully_loaded {
+            self.run_full_load_action(action);
+        } else {
+            self.pending_full_load = Some(action);
+            self.show_info("Loading all profiles…");
+        }
+    }
+
+    /// Load every profile for the pending action, if there is one, then run it
+    pub fn finish_full_load(&mut self) {
+        let Some(action) = self.pending_full_load.take() else {
+            return;
+        };
+        self.status_message = None;
+
+        let names = match self.config_manager.scan_profile_names() {
+            Ok(names) => names.0,
+            Err(e) => {
+                self.show_error(format!("Error scanning profiles: {e}"));
+                return;
+            }
+        };
+        // Files queued for deletion after a rename are not profiles anymore
+        let pending: HashSet<String> = self.pending_deletes.values().cloned().collect();
+        for name in names.iter().filter(|name| !pending.contains(*name)) {
+            self.ensure_loaded(name);
+        }
+        self.fully_loaded = true;
+        self.refresh_profile_list();
+
+        self.run_full_load_action(action);
+    }
+
+    pub fn has_pending_full_load(&self) -> bool {
+        self.pending_full_load.is_some()
+    }
+
+    fn run_full_load_action(&mut self, action: FullLoadAction) {
+        match action {
+            FullLoadAction::Search => self.list_view.enter_search_mode(),
+            FullLoadAction::Delete => self.start_delete(),
+            FullLoadAction::Rename => {
+                self.state = AppState::Rename;
+                self.list_view.start_rename();
+            }
+            FullLoadAction::DependencySelector => edit::open_dependency_selector_handler(self),
+            FullLoadAction::Dependents => self.main_right_view_mode = MainRightViewMode::Dependents,
+        }
+    }
+
+    /// Rebuild the list entries from the profiles in memory and the files on disk.
+    ///
+    /// Profile contents are only loaded on demand when the active sort mode needs the counts.
+    pub fn refresh_profile_list(&mut self) {
+        let mut modified_times = HashMap::new();
+        match self.config_manager.scan_profile_files() {
+            Ok(files) => {
+                for file in files {
+                    modified_times.insert(file.name, file.modified);
+                }
+            }
+            Err(e) => {
+                self.show_error(format!("Error scanning profiles: {e}"));
+            }
+        }
+
+        // Files queued for deletion after a rename must not reappear in the list
+        let pending: HashSet<String> = self.pending_deletes.values().cloned().collect();
+        let mut names = self.config_manager.list_profile_names().to_vec();
+        for name in modified_times.keys() {
+            if !pending.contains(name) && !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+
+        let requires_counts = self.list_view.sort_mode().requires_counts();
+        if requires_counts {
+            for name in &names {
+                if !self.config_manager.has_profile(name)
+                    && let Err(e) = self.config_manager.load_profile(name)
+                {
+                    self.show_error(format!("Error loading profile '{name}': {e}"));
+                }
+            }
+        }
+
+        // Ranks only cover loaded profiles, so compute them once everything needed is in memory
+        let dependency_ranks: HashMap<String, usize> = self
+            .config_manager
+            .topological_order()
+            .map(|order| order.into_iter().enumerate().map(|(i, n)| (n, i)).collect())
+            .unwrap_or_default();
+
+        let mut entries = Vec::with_capacity(names.len());
+        for name in names {
+            let mut entry = ProfileEntry::new(name);
+            entry.modified = modified_times.get(&entry.name).copied().flatten();
+            entry.dependency_rank = dependency_ranks.get(&entry.name).copied();
+            if let Some(profile) = self.config_manager.get_profile(&entry.name) {
+                entry.fill_from(profile);
+            }
+            entries.push(entry);
+        }
+
+        self.list_view.update_profiles(entries);
+    }
+
+    /// Catch up with changes made to the profile files outside the TUI, such as
+    /// `em profile create` in another shell.
+    ///
+    /// New files are loaded, profiles whose file is gone are dropped and changed ones
+    /// are read again. Unsaved edits are kept: a profile deleted on disk while it has
+    /// them is reported as a conflict, and saving writes it back.
+    pub fn reload_from_disk(&mut self) {
+        let on_disk: HashMap<String, Option<SystemTime>> =
+            match self.config_manager.scan_profile_files() {
+                Ok(files) => files.into_iter().map(|f| (f.name, f.modified)).collect(),
+                Err(e) => {
+                    self.show_error(format!("Error scanning profiles: {e}"));
+                    return;
+                }
+            };
+        // When each listed profile was last seen on disk, `None` if it never was
+        let known: HashMap<String, Option<SystemTime>> = self
+            .list_view
+            .all_entries()
+            .iter()
+            .map(|entry| (entry.name.clone(), entry.modified))
+            .collect();
+        let pending: HashSet<String> = self.pending_deletes.values().cloned().collect();
+        // Files that failed to load may have been fixed
+        self.load_errors.clear();
+
+        let (mut added, mut removed, mut changed, mut conflicts) =
+            (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+        for name in self.config_manager.list_profile_names().0 {
+            if name == GLOBAL_PROFILE_MARK {
+                continue;
+            }
+            let seen = known.get(&name).copied().flatten();
+            let dirty = self.list_view.is_dirty(&name);
+            match on_disk.get(&name) {
+                // Created or renamed in the TUI and not saved yet
+                None if seen.is_none() => {}
+                None if dirty => conflicts.push(name),
+                None => {
+                    self.forget_profile(&name);
+                    removed.push(name);
+                }
+                Some(modified) if !dirty && *modified != seen => {
+                    self.reload_profile(&name);
+                    changed.push(name);
+                }
+                Some(_) => {}
+            }
+        }
+        for name in on_disk.keys() {
+            if !known.contains_key(name) && !pending.contains(name) {
+                added.push(name.clone());
+            }
+        }
+        added.sort();
+        for name in &added {
+            self.ensure_loaded(name);
+        }
+        self.refresh_profile_list();
+
+        if !conflicts.is_empty() {
+            conflicts.sort();
+            self.show_error(format!(
+                "Deleted outside the TUI but changed here: {}; saving writes them back",
+                conflicts.join(", ")
+            ));
+        } else if added.is_empty() && removed.is_empty() && changed.is_empty() {
+            self.show_info("Profiles are up to date");
+        } else {
+            self.show_success(format!(
+                "Reloaded from disk: {} new, {} removed, {} changed",
+                added.len(),
+                removed.len(),
+                changed.len()
+            ));
+        }
+    }
+
+    /// Drop profile `name` from memory and the graph after its file went away
+    fn forget_profile(&mut self, name: &str) {
+        self.config_manager.remove_profile(name);
+        // Never part of the graph when it failed to load
+        let _ = self.config_manager.remove_profile_node(name);
+        self.list_view.clear_dirty(name);
+        self.recent.remove(name);
+        self.recent.save(self.config_manager.base_path());
+    }
+
+    /// Read profile `name` from its file again, replacing its dependency edges
+    fn reload_profile(&mut self, name: &str) {
+        if let Some(old) = self.config_manager.remove_profile(name) {
+            for dependency in old.enabled_profiles() {
+                let _ = self.config_manager.remove_dependency_edge(name, dependency);
+            }
+        }
+        if let Err(e) = self.config_manager.load_profile(name) {
+            self.show_error(format!("Error loading profile '{name}': {e}"));
+            self.load_errors.insert(name.to_string(), e.to_string());
+        }
+    }
+
+    pub fn set_sort_mode(&mut self, sort_mode: SortMode) {
+        self.list_view.set_sort_mode(sort_mode);
+        if self.list_view.sort_mode().requires_counts() {
+            self.refresh_profile_list();
+        }
+        self.show_info(format!("Sorted by {}", self.list_view.sort_mode().label()));
     }
 
     pub fn load_expand_vars(&mut self) {
         if let Some(selected_name) = self.list_view.current_profile().map(|s| s.to_string()) {
+            self.ensure_loaded(&selected_name);
             if self.list_view.is_dirty(&selected_name)
-                && let Some(profile) = self.config_manager.get_profile(&selected_name)
+                && self.config_manager.has_profile(&selected_name)
             {
-                if let Err(e) = self.config_manager.write_profile(&selected_name, profile) {
-                    self.status_message = Some(format!("Error saving profile: {}", e));
+                let renamed_from = self.pending_deletes.remove(&selected_name);
+                if let Some(old_name) = &renamed_from
+                    && let Err(e) = self.config_manager.delete_profile_file(old_name)
+                {
+                    self.show_error(format!("Error removing '{old_name}': {e}"));
+                }
+                if let Err(e) = self.write_profile_file(&selected_name, renamed_from.as_deref()) {
+                    self.show_error(format!("Error saving profile: {}", e));
                 } else {
                     self.list_view.clear_dirty(&selected_name);
-                    self.status_message = Some(format!("Saved profile '{}'", selected_name));
+                    self.show_success(format!("Saved profile '{}'", selected_name));
                 }
             }
             if let Some(profile) = self.config_manager.get_profile(&selected_name) {
-                match profile.collect_vars(&self.config_manager) {
+                match profile.collect_merged(&selected_name, &self.config_manager) {
                     Ok(vars) => {
-                        self.expand_env_vars = Some(vars);
+                        self.expand_view.set_vars(vars);
                         self.main_right_view_mode = MainRightViewMode::Expand;
                     }
                     Err(e) => {
-                        self.status_message = Some(format!("Error expanding variables: {e}"));
+                        self.show_error(format!("Error expanding variables: {e}"));
                     }
                 }
             }
@@ -223,22 +981,161 @@ impl App {
     }
 
     pub fn unload_expand_vars(&mut self) {
-        self.expand_env_vars.take();
+        self.expand_view.clear();
         self.main_right_view_mode = MainRightViewMode::Raw;
     }
 
+    /// Show the dependencies and dependents of the selected profile in the right pane
+    pub fn show_dependents(&mut self) {
+        self.with_all_profiles(FullLoadAction::Dependents);
+    }
+
+    /// Cycle the right pane through the raw, expanded and dependents views
+    pub fn switch_right_view(&mut self) {
+        match self.main_right_view_mode {
+            MainRightViewMode::Raw => self.load_expand_vars(),
+            MainRightViewMode::Expand => {
+                self.unload_expand_vars();
+                self.show_dependents();
+            }
+            MainRightViewMode::Dependents => self.main_right_view_mode = MainRightViewMode::Raw,
+        }
+    }
+
+    /// Open the delete confirmation for the marked profiles, or the selected one
+    pub fn start_delete(&mut self) {
+        let names = if self.list_view.marked_count() > 0 {
+            self.list_view.marked_profiles()
+        } else {
+            match self.list_view.current_profile() {
+                Some(name) => vec![name.to_string()],
+                None => return,
+            }
+        };
+
+        let mut impact = DeleteImpact::default();
+        let mut dependencies = HashSet::new();
+        for name in &names {
+            if let Some(profile) = self.config_manager.get_profile(name) {
+                impact.variable_count += profile.variables.len();
+                dependencies.extend(profile.profiles.iter().cloned());
+            }
+        }
+        // A profile stays when anything that is not deleted inherits from it, which in
+        // turn keeps the profiles that one inherits from
+        let mut deleted: HashSet<&String> = names.iter().collect();
+        loop {
+            let newly_blocked: Vec<(String, Vec<String>)> = deleted
+                .iter()
+                .filter_map(|name| {
+                    let mut dependents: Vec<String> = self
+                        .config_manager
+                        .get_parents(name)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter(|dependent| !deleted.contains(dependent))
+                        .collect();
+                    dependents.sort();
+                    (!dependents.is_empty()).then(|| (name.to_string(), dependents))
+                })
+                .collect();
+            if newly_blocked.is_empty() {
+                break;
+            }
+            for (name, dependents) in newly_blocked {
+                deleted.remove(&name);
+                impact.blocked.insert(name, dependents);
+            }
+        }
+        impact.dependencies = dependencies
+            .into_iter()
+            .filter(|dependency| !names.contains(dependency))
+            .collect();
+        impact.dependencies.sort();
+        impact.names = names;
+
+        self.delete_impact = Some(impact);
+        self.state = AppState::ConfirmDelete;
+    }
+
+    /// Delete the profiles of the open delete confirmation that nothing else depends on,
+    /// then report what happened to each of them
+    pub fn delete_confirmed(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(impact) = self.delete_impact.take() else {
+            return Ok(());
+        };
+        let mut remaining: Vec<String> = impact.deletable().cloned().collect();
+        if remaining.is_empty() {
+            return Ok(());
+        }
+
+        // Dependents go first, so every profile is unused by the time it is deleted
+        let mut deleted = Vec::new();
+        while !remaining.is_empty() {
+            let Some(index) = remaining.iter().position(|name| {
+                self.config_manager
+                    .get_parents(name)
+                    .is_none_or(|parents| parents.is_empty())
+            }) else {
+                break;
+            };
+            let name = remaining.remove(index);
+            if self.delete_profile(&name)? {
+                deleted.push(name);
+            }
+        }
+        self.refresh_profile_list();
+
+        if impact.names.len() == 1 {
+            if let Some(name) = deleted.first() {
+                self.show_success(format!("Successfully deleted '{name}'"));
+            }
+            return Ok(());
+        }
+
+        self.list_view.clear_marks();
+        let mut summary = format!("Deleted {} of {}", deleted.len(), impact.names.len());
+        if !deleted.is_empty() {
+            summary.push_str(&format!(": {}", deleted.join(", ")));
+        }
+        let skipped: Vec<String> = impact
+            .blocked
+            .iter()
+            .map(|(name, dependents)| format!("{name} (used by {})", dependents.join(", ")))
+            .chain(
+                remaining
+                    .iter()
+                    .map(|name| format!("{name} (still in use)")),
+            )
+            .collect();
+        if skipped.is_empty() {
+            self.show_success(summary);
+        } else {
+            self.show_error(format!("{summary}; skipped {}", skipped.join(", ")));
+        }
+        Ok(())
+    }
+
     pub fn delete_selected_profile(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         let name_to_delete = match self.list_view.current_profile() {
             Some(n) => n.to_string(),
             None => return Ok(()),
         };
+        if self.delete_profile(&name_to_delete)? {
+            self.refresh_profile_list();
+            self.show_success(format!("Successfully deleted '{name_to_delete}'"));
+        }
+        Ok(())
+    }
 
+    /// Delete profile `name` from disk and memory, unless another profile depends on it
+    fn delete_profile(&mut self, name_to_delete: &str) -> Result<bool, Box<dyn std::error::Error>> {
         // Validation
         if name_to_delete == GLOBAL_PROFILE_MARK {
-            return Ok(());
+            return Ok(false);
         }
 
-        if let Some(dependents) = self.config_manager.get_parents(&name_to_delete)
+        if let Some(dependents) = self.config_manager.get_parents(name_to_delete)
             && !dependents.is_empty()
         {
             let error_message = format!(
@@ -246,57 +1143,113 @@ impl App {
                 name_to_delete,
                 dependents.join(", ")
             );
-            self.status_message = Some(error_message);
-            return Ok(());
-        }
-
-        let mut profiles = self.list_view.all_profiles().to_vec();
-        let selected_idx = self.list_view.selected_index();
-        if selected_idx < profiles.len() {
-            profiles.remove(selected_idx);
+            self.show_error(error_message);
+            return Ok(false);
         }
-        self.list_view.update_profiles(profiles);
 
         // Ensure any original file associated with this profile (if it was a rename) is also deleted
-        if let Some(old_name) = self.pending_deletes.remove(&name_to_delete) {
-            self.config_manager.delete_profile_file(&old_name)?;
+        let renamed_from = self.pending_deletes.remove(name_to_delete);
+        // Log the file that actually goes away, if the profile was ever saved
+        let on_disk = renamed_from
+            .clone()
+            .unwrap_or_else(|| name_to_delete.to_string());
+        let existed = self.config_manager.profile_exists(&on_disk);
+        if let Some(old_name) = &renamed_from {
+            self.config_manager.delete_profile_file(old_name)?;
         }
 
-        self.config_manager.delete_profile_file(&name_to_delete)?;
+        self.config_manager.delete_profile_file(name_to_delete)?;
+        if existed {
+            self.config_manager
+                .record_history(Source::Tui, Operation::Delete(&on_disk));
+        }
 
         // Remove from config manager's in-memory cache
-        self.config_manager.remove_profile(&name_to_delete);
+        self.config_manager.remove_profile(name_to_delete);
+        self.load_errors.remove(name_to_delete);
 
         // Remove from dirty set if it's there
-        self.list_view.clear_dirty(&name_to_delete);
+        self.list_view.clear_dirty(name_to_delete);
+        self.recent.remove(name_to_delete);
+        self.recent.save(self.config_manager.base_path());
 
         // Remove from graph incrementally (more efficient than rebuild)
-        self.config_manager.remove_profile_node(&name_to_delete)?;
-
-        self.status_message = Some(format!("Successfully deleted '{name_to_delete}'"));
+        self.config_manager.remove_profile_node(name_to_delete)?;
 
-        Ok(())
+        Ok(true)
     }
 
-    pub fn run() -> Result<(), Box<dyn std::error::Error>> {
-        let config_manager = ConfigManager::new_full()?;
+    pub fn run(
+        profile_dir: Option<PathBuf>,
+        read_only: bool,
+        theme: Option<String>,
+        edit: Option<&str>,
+        verbosity: Verbosity,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Profiles are read as they are selected, so the first frame does not wait for all of them
+        let config_manager = ConfigManager::new(profile_dir.clone())?;
         let global_profile = config_manager.read_global()?;
+        let unwritable = !read_only && !config_manager.is_writable();
+        let settings = config_manager.read_settings().unwrap_or_default();
+        let restore_session = settings.restores_session();
+        // An unknown or broken theme is reported before the terminal is taken over
+        let theme_name = theme
+            .or(settings.theme)
+            .unwrap_or(DEFAULT_THEME.to_string());
+        let theme = Theme::load(&theme_name, config_manager.base_path())?;
         let mut app = App::new(config_manager, global_profile);
+        app.theme = theme;
+        app.read_only = read_only || unwritable;
+        if unwritable {
+            app.show_info("The profiles directory is not writable, opened in read-only mode");
+        }
+        if restore_session {
+            Session::load(app.config_manager.base_path()).restore(&mut app);
+        }
+        if let Some(name) = edit {
+            app.open_in_editor(name);
+        }
 
-        enable_raw_mode()?;
-        let mut stderr = io::stderr();
-        execute!(stderr, EnterAlternateScreen)?;
-
-        let backend = CrosstermBackend::new(stderr);
+        terminal::install_panic_hook();
+        let mut guard = TerminalGuard::enter(io::stderr(), true)?;
+        let backend = CrosstermBackend::new(io::stderr());
         let mut terminal = Terminal::new(backend)?;
 
         let res = run_app(&mut terminal, &mut app);
 
-        disable_raw_mode()?;
-        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-        terminal.show_cursor()?;
+        guard.restore()?;
+        res?;
+        if restore_session {
+            Session::of(&app).save(app.config_manager.base_path());
+        }
+        // Printed once the terminal is restored, for the shell function to evaluate
+        if let Some(name) = app.activate_on_exit {
+            let options = activate::Options {
+                verbosity,
+                ..Default::default()
+            };
+            activate::handle(vec![name], options, profile_dir)?;
+        }
+        Ok(())
+    }
 
-        res
+    /// Close the TUI and activate the selected profile in the calling shell, asking
+    /// about unsaved changes first
+    pub fn activate_selected_on_exit(&mut self) {
+        let Some(name) = self.list_view.current_profile().map(|s| s.to_string()) else {
+            return;
+        };
+        if name == GLOBAL_PROFILE_MARK {
+            self.show_error("GLOBAL is applied by `em global init`, not activated");
+            return;
+        }
+
+        self.activate_on_exit = Some(name);
+        if self.list_view.unsaved_count() > 0 {
+            self.state = AppState::ConfirmExit;
+        } else {
+            self.shutdown = true;
+        }
     }
 }
 
@@ -309,8 +1262,25 @@ fn run_app<B: Backend>(
             return Ok(());
         }
 
+        app.load_selected();
+        app.expire_status();
         terminal.draw(|frame| ui(frame, app))?;
 
+        // A full load blocks, so it runs after a frame showing the loading message
+        if app.has_pending_full_load() {
+            app.finish_full_load();
+            continue;
+        }
+
         handle_event(app)?;
     }
 }
+
+/// How a profile is called in the TUI, GLOBAL for the global profile
+pub fn display_name(name: &str) -> &str {
+    if name == GLOBAL_PROFILE_MARK {
+        "GLOBAL"
+    } else {
+        name
+    }
+}
@@ -2,12 +2,31 @@ use crate::tui::app::{App, AppState};
 use ratatui::crossterm::event::{KeyCode, KeyEvent};
 
 pub fn handle(app: &mut App, key: KeyEvent) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(impact) = &app.delete_impact else {
+        app.state = AppState::List;
+        return Ok(());
+    };
+    let first_dependent = impact.first_dependent().cloned();
+    let any_deletable = impact.deletable().next().is_some();
+
     match key.code {
-        KeyCode::Char('y') => {
-            app.delete_selected_profile()?;
+        KeyCode::Char('y') if any_deletable => {
+            app.delete_confirmed()?;
+            app.state = AppState::List;
+        }
+        // Jump to the first dependent so its reference can be removed
+        KeyCode::Char('g') => {
+            let Some(dependent) = first_dependent else {
+                return Ok(());
+            };
+            app.delete_impact = None;
             app.state = AppState::List;
+            if app.list_view.select_profile(&dependent) {
+                app.start_editing(&dependent);
+            }
         }
         KeyCode::Char('n') | KeyCode::Esc => {
+            app.delete_impact = None;
             app.state = AppState::List;
         }
         _ => {}
@@ -0,0 +1,15 @@
+use crate::tui::app::{App, AppState};
+use crate::tui::views::edit;
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+
+pub fn handle(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Char('y') | KeyCode::Enter => {
+            edit::finish_editing(app);
+        }
+        KeyCode::Char('n') | KeyCode::Esc => {
+            app.state = AppState::Edit;
+        }
+        _ => {}
+    }
+}
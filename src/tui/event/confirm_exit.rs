@@ -11,6 +11,7 @@ pub fn handle(app: &mut App, key: KeyEvent) -> Result<(), Box<dyn std::error::Er
             app.shutdown = true;
         }
         KeyCode::Esc => {
+            app.activate_on_exit = None;
             app.state = AppState::List;
         }
         _ => {}
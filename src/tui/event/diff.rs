@@ -0,0 +1,17 @@
+use crate::tui::app::App;
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+
+/// Lines scrolled by PageUp and PageDown
+const PAGE: isize = 10;
+
+pub fn handle(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('D') => app.close_diff(),
+        KeyCode::Char('j') | KeyCode::Down => app.scroll_diff(1),
+        KeyCode::Char('k') | KeyCode::Up => app.scroll_diff(-1),
+        KeyCode::PageDown => app.scroll_diff(PAGE),
+        KeyCode::PageUp => app.scroll_diff(-PAGE),
+        KeyCode::Char('e') => app.toggle_diff_expand(),
+        _ => {}
+    }
+}
@@ -1,31 +1,62 @@
 use super::app::App;
 use crate::tui::app::AppState;
-use crate::tui::views::{add_new, edit, list};
-use ratatui::crossterm::event::{self, Event};
+use crate::tui::ui::{MIN_HEIGHT, MIN_WIDTH};
+use crate::tui::views::{add_new, edit, expand, list};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent};
+use ratatui::crossterm::terminal;
 
 mod confirm_delete;
+mod confirm_drop_invalid;
 mod confirm_exit;
+mod diff;
+mod recent;
+mod status_log;
 
 pub fn handle_event(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
-    if let Event::Key(key) = event::read()? {
-        app.status_message = None;
+    // Wake up when the status message expires so the next draw clears it
+    if let Some(message) = &app.status_message
+        && !event::poll(message.remaining())?
+    {
+        return Ok(());
+    }
 
-        if key.kind == event::KeyEventKind::Release {
-            return Ok(());
-        }
+    let key = match event::read()? {
+        Event::Key(key) => key,
+        // After a resize the next draw lays everything out again for the new size
+        _ => return Ok(()),
+    };
 
-        match app.state {
-            AppState::List => list::handle_event(app, key)?,
-            AppState::Edit => {
-                edit::handle_event(app, key);
-            }
-            AppState::ConfirmDelete => confirm_delete::handle(app, key)?,
-            AppState::Rename => list::handle_rename_event(app, key)?,
-            AppState::AddNew => {
-                add_new::handle_event(app, key);
-            }
-            AppState::ConfirmExit => confirm_exit::handle(app, key)?,
+    if key.kind == event::KeyEventKind::Release {
+        return Ok(());
+    }
+
+    // Nothing but the "too small" notice is visible, so only let Esc through
+    let (width, height) = terminal::size()?;
+    if (width < MIN_WIDTH || height < MIN_HEIGHT) && key.code != KeyCode::Esc {
+        return Ok(());
+    }
+
+    handle_key(app, key)
+}
+
+/// Pass `key` to the handler of the current state, which may move the app to another
+pub fn handle_key(app: &mut App, key: KeyEvent) -> Result<(), Box<dyn std::error::Error>> {
+    match app.state {
+        AppState::List => list::handle_event(app, key)?,
+        AppState::Edit => {
+            edit::handle_event(app, key);
+        }
+        AppState::ConfirmDelete => confirm_delete::handle(app, key)?,
+        AppState::Rename => list::handle_rename_event(app, key)?,
+        AppState::AddNew => {
+            add_new::handle_event(app, key);
         }
+        AppState::ConfirmExit => confirm_exit::handle(app, key)?,
+        AppState::ConfirmDropInvalid => confirm_drop_invalid::handle(app, key),
+        AppState::Export => expand::handle_export_event(app, key),
+        AppState::StatusLog => status_log::handle(app, key),
+        AppState::Recent => recent::handle(app, key),
+        AppState::Diff => diff::handle(app, key),
     }
     Ok(())
 }
@@ -0,0 +1,18 @@
+use crate::tui::app::{App, AppState};
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+pub fn handle(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('`') | KeyCode::Char('q') => {
+            app.state = AppState::List;
+        }
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.state = AppState::List;
+        }
+        KeyCode::Char('j') | KeyCode::Down => app.select_next_recent(),
+        KeyCode::Char('k') | KeyCode::Up => app.select_previous_recent(),
+        KeyCode::Enter => app.jump_to_recent(app.recent_index),
+        KeyCode::Char(c @ '1'..='9') => app.jump_to_recent(c as usize - '1' as usize),
+        _ => {}
+    }
+}
@@ -0,0 +1,13 @@
+use crate::tui::app::{App, AppState};
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+
+pub fn handle(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Esc | KeyCode::Char('l') | KeyCode::Char('q') => {
+            app.state = AppState::List;
+        }
+        KeyCode::Char('j') | KeyCode::Down => app.scroll_status_log_down(),
+        KeyCode::Char('k') | KeyCode::Up => app.scroll_status_log_up(),
+        _ => {}
+    }
+}
@@ -1,14 +1,32 @@
 use crate::tui::app::App;
+use crate::utils::display::Verbosity;
+use std::path::PathBuf;
 
 pub mod app;
 
 pub mod event;
+pub mod session;
+pub mod terminal;
 pub mod theme;
 pub mod ui;
 pub mod utils;
 pub mod views;
 pub mod widgets;
 
-pub fn run() -> Result<(), Box<dyn std::error::Error>> {
-    App::run()
+pub fn run(
+    profile_dir: Option<PathBuf>,
+    read_only: bool,
+    theme: Option<String>,
+    verbosity: Verbosity,
+) -> Result<(), Box<dyn std::error::Error>> {
+    App::run(profile_dir, read_only, theme, None, verbosity)
+}
+
+/// Open the TUI straight in the editor for profile `name`
+pub fn run_with(
+    profile_dir: Option<PathBuf>,
+    name: &str,
+    verbosity: Verbosity,
+) -> Result<(), Box<dyn std::error::Error>> {
+    App::run(profile_dir, false, None, Some(name), verbosity)
 }
@@ -0,0 +1,72 @@
+//! What the TUI showed when it was last closed, restored on the next start.
+
+use crate::tui::app::{App, MainRightViewMode};
+use crate::tui::views::list::SortMode;
+use crate::utils::json;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const SESSION_FILE_NAME: &str = "session.json";
+
+pub fn session_path(base_path: &Path) -> PathBuf {
+    base_path.join(SESSION_FILE_NAME)
+}
+
+/// The selected profile, right pane and sort order of the list; the search is not
+/// kept. Like the recent profiles this is best-effort: a missing or unreadable file
+/// is the default session and write failures are ignored.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selected: Option<String>,
+    pub right_pane: MainRightViewMode,
+    pub sort: SortMode,
+}
+
+impl Session {
+    pub fn load(base_path: &Path) -> Self {
+        fs::read_to_string(session_path(base_path))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, base_path: &Path) {
+        if let Ok(content) = json::to_string_pretty(self) {
+            let _ = fs::write(session_path(base_path), format!("{content}\n"));
+        }
+    }
+
+    /// The session `app` is showing
+    pub fn of(app: &App) -> Self {
+        Session {
+            selected: app.list_view.current_profile().map(str::to_string),
+            right_pane: app.main_right_view_mode,
+            sort: app.list_view.sort_mode(),
+        }
+    }
+
+    /// Show this session in `app`. A profile that no longer exists leaves the
+    /// selection, and the right pane, at their defaults.
+    pub fn restore(&self, app: &mut App) {
+        if self.sort != app.list_view.sort_mode() {
+            app.list_view.set_sort_mode(self.sort);
+            if self.sort.requires_counts() {
+                app.refresh_profile_list();
+            }
+        }
+        let Some(selected) = &self.selected else {
+            return;
+        };
+        if !app.list_view.select_profile(selected) {
+            return;
+        }
+        match self.right_pane {
+            MainRightViewMode::Raw => {}
+            MainRightViewMode::Expand => app.load_expand_vars(),
+            MainRightViewMode::Dependents => app.show_dependents(),
+        }
+    }
+}
@@ -0,0 +1,70 @@
+//! Switching the terminal into the TUI's raw, alternate-screen mode and reliably back.
+
+use ratatui::crossterm::cursor::Show;
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{
+    EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+};
+use std::io::{self, Write};
+use std::panic;
+
+/// Keeps the terminal in the alternate screen while alive and restores it when
+/// dropped, including while unwinding from a panic
+pub struct TerminalGuard<W: Write> {
+    writer: W,
+    raw_mode: bool,
+    active: bool,
+}
+
+impl<W: Write> TerminalGuard<W> {
+    /// Enter the alternate screen on `writer`, enabling raw mode first when `raw_mode` is set
+    pub fn enter(writer: W, raw_mode: bool) -> io::Result<Self> {
+        if raw_mode {
+            enable_raw_mode()?;
+        }
+        let mut guard = Self {
+            writer,
+            raw_mode,
+            active: true,
+        };
+        execute!(guard.writer, EnterAlternateScreen)?;
+        Ok(guard)
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Leave the alternate screen and raw mode and show the cursor; only the first call does anything
+    pub fn restore(&mut self) -> io::Result<()> {
+        if !self.active {
+            return Ok(());
+        }
+        self.active = false;
+        // Leave the screen even when raw mode cannot be disabled, then report the first error
+        let raw_mode = if self.raw_mode {
+            disable_raw_mode()
+        } else {
+            Ok(())
+        };
+        let screen = execute!(self.writer, LeaveAlternateScreen, Show);
+        raw_mode.and(screen)
+    }
+}
+
+impl<W: Write> Drop for TerminalGuard<W> {
+    fn drop(&mut self) {
+        let _ = self.restore();
+    }
+}
+
+/// Restore the terminal before the panic message is printed, so it lands on the
+/// normal screen instead of vanishing with the alternate one
+pub fn install_panic_hook() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = disable_raw_mode();
+        let _ = execute!(io::stderr(), LeaveAlternateScreen, Show);
+        previous(info);
+    }));
+}
@@ -1,73 +1,342 @@
 use ratatui::prelude::*;
+use serde::Deserialize;
+use serde::de::{self, Deserializer};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
 
-#[derive(Debug, Clone, Copy, Default)]
-pub struct Theme;
+/// Name of the theme used when neither `--theme` nor the settings choose one
+pub const DEFAULT_THEME: &str = "default";
+
+/// The themes that ship with env-manage
+pub const BUILTIN_THEMES: [&str; 3] = [DEFAULT_THEME, "high-contrast", "colorblind-safe"];
+
+/// Every color the TUI draws with.
+///
+/// A user theme file (`themes/<name>.toml` in the config directory) sets any of
+/// these fields to a color name (`red`, `lightblue`), an index (`42`) or `#rrggbb`;
+/// the fields it leaves out keep their default value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Palette {
+    /// Borders, focuses and active elements
+    #[serde(deserialize_with = "color")]
+    pub primary: Color,
+    /// Highlights and accents
+    #[serde(deserialize_with = "color")]
+    pub secondary: Color,
+    #[serde(deserialize_with = "color")]
+    pub success: Color,
+    #[serde(deserialize_with = "color")]
+    pub error: Color,
+    #[serde(deserialize_with = "color")]
+    pub warning: Color,
+    #[serde(deserialize_with = "color")]
+    pub info: Color,
+    #[serde(deserialize_with = "color")]
+    pub text: Color,
+    #[serde(deserialize_with = "color")]
+    pub text_dim: Color,
+    /// Background of the selected row in the focused pane
+    #[serde(deserialize_with = "color")]
+    pub selection: Color,
+    #[serde(deserialize_with = "color")]
+    pub selection_text: Color,
+    /// Background of the selected row in an unfocused pane
+    #[serde(deserialize_with = "color")]
+    pub selection_inactive: Color,
+    /// Text drawn on `primary` or `warning` backgrounds, such as the cursor
+    #[serde(deserialize_with = "color")]
+    pub on_accent: Color,
+    /// Help hints for keys that cancel or go back
+    #[serde(deserialize_with = "color")]
+    pub key_cancel: Color,
+    /// Help hints for keys that confirm or open
+    #[serde(deserialize_with = "color")]
+    pub key_confirm: Color,
+    /// Help hints for keys that move focus
+    #[serde(deserialize_with = "color")]
+    pub key_focus: Color,
+    /// Help hints for keys that navigate or scroll
+    #[serde(deserialize_with = "color")]
+    pub key_navigate: Color,
+    /// Help hints for keys that edit, save or toggle
+    #[serde(deserialize_with = "color")]
+    pub key_edit: Color,
+    /// Help hints for keys that create something
+    #[serde(deserialize_with = "color")]
+    pub key_create: Color,
+    /// Help hints for keys that change something in place, such as rename
+    #[serde(deserialize_with = "color")]
+    pub key_change: Color,
+    /// Help hints for keys that delete something
+    #[serde(deserialize_with = "color")]
+    pub key_delete: Color,
+    /// Help hints for keys that search, filter or compare
+    #[serde(deserialize_with = "color")]
+    pub key_search: Color,
+    /// Help hints for keys that export or save everything
+    #[serde(deserialize_with = "color")]
+    pub key_export: Color,
+    /// Help hints for keys that dismiss a question without an answer
+    #[serde(deserialize_with = "color")]
+    pub key_dismiss: Color,
+    /// Help hints for keys that are not available right now
+    #[serde(deserialize_with = "color")]
+    pub key_unavailable: Color,
+}
+
+fn color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    Color::from_str(&value).map_err(|_| de::Error::custom(format!("invalid color `{value}`")))
+}
+
+impl Default for Palette {
+    /// Tokyo Night
+    fn default() -> Self {
+        Self {
+            primary: Color::Rgb(122, 162, 247),   // #7aa2f7 (Blue)
+            secondary: Color::Rgb(187, 154, 247), // #bb9af7 (Purple)
+            success: Color::Rgb(158, 206, 106),   // #9ece6a (Green)
+            error: Color::Rgb(247, 118, 142),     // #f7768e (Red)
+            warning: Color::Rgb(224, 175, 104),   // #e0af68 (Yellow/Orange)
+            info: Color::Rgb(125, 207, 255),      // #7dcfff (Cyan)
+            text: Color::Rgb(192, 202, 245),      // #c0caf5 (White-ish)
+            text_dim: Color::Rgb(86, 95, 137),    // #565f89 (Dark Blue-Gray)
+            selection: Color::Rgb(61, 89, 161),   // #3d59a1
+            selection_text: Color::White,
+            selection_inactive: Color::Rgb(41, 46, 66),
+            on_accent: Color::Black,
+            key_cancel: Color::Rgb(255, 107, 107),
+            key_confirm: Color::Rgb(106, 255, 160),
+            key_focus: Color::Rgb(130, 170, 255),
+            key_navigate: Color::Rgb(255, 138, 199),
+            key_edit: Color::LightBlue,
+            key_create: Color::LightGreen,
+            key_change: Color::LightYellow,
+            key_delete: Color::LightRed,
+            key_search: Color::LightMagenta,
+            key_export: Color::LightCyan,
+            key_dismiss: Color::Gray,
+            key_unavailable: Color::DarkGray,
+        }
+    }
+}
+
+impl Palette {
+    /// Plain terminal colors at full strength
+    pub fn high_contrast() -> Self {
+        Self {
+            primary: Color::LightCyan,
+            secondary: Color::LightYellow,
+            success: Color::LightGreen,
+            error: Color::LightRed,
+            warning: Color::Yellow,
+            info: Color::LightCyan,
+            text: Color::White,
+            text_dim: Color::Gray,
+            selection: Color::White,
+            selection_text: Color::Black,
+            selection_inactive: Color::DarkGray,
+            on_accent: Color::Black,
+            key_cancel: Color::LightRed,
+            key_confirm: Color::LightGreen,
+            key_focus: Color::LightCyan,
+            key_navigate: Color::LightYellow,
+            key_edit: Color::LightCyan,
+            key_create: Color::LightGreen,
+            key_change: Color::LightYellow,
+            key_delete: Color::LightRed,
+            key_search: Color::LightMagenta,
+            key_export: Color::LightCyan,
+            key_dismiss: Color::White,
+            key_unavailable: Color::Gray,
+        }
+    }
+
+    /// The Okabe-Ito palette, which stays distinguishable with the common kinds of
+    /// color blindness; success and error are blue and vermillion rather than green and red
+    pub fn colorblind_safe() -> Self {
+        let orange = Color::Rgb(230, 159, 0);
+        let sky_blue = Color::Rgb(86, 180, 233);
+        let bluish_green = Color::Rgb(0, 158, 115);
+        let yellow = Color::Rgb(240, 228, 66);
+        let blue = Color::Rgb(0, 114, 178);
+        let vermillion = Color::Rgb(213, 94, 0);
+        let reddish_purple = Color::Rgb(204, 121, 167);
+        Self {
+            primary: sky_blue,
+            secondary: reddish_purple,
+            success: sky_blue,
+            error: vermillion,
+            warning: yellow,
+            info: bluish_green,
+            text: Color::Rgb(230, 230, 230),
+            text_dim: Color::Rgb(128, 128, 128),
+            selection: blue,
+            selection_text: Color::White,
+            selection_inactive: Color::Rgb(60, 60, 60),
+            on_accent: Color::Black,
+            key_cancel: vermillion,
+            key_confirm: sky_blue,
+            key_focus: bluish_green,
+            key_navigate: reddish_purple,
+            key_edit: sky_blue,
+            key_create: bluish_green,
+            key_change: yellow,
+            key_delete: vermillion,
+            key_search: reddish_purple,
+            key_export: orange,
+            key_dismiss: Color::Gray,
+            key_unavailable: Color::DarkGray,
+        }
+    }
+}
+
+/// What a key in a help hint does, which decides its color
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyRole {
+    Cancel,
+    Confirm,
+    Focus,
+    Navigate,
+    Edit,
+    Create,
+    Change,
+    Delete,
+    Search,
+    Export,
+    Dismiss,
+}
+
+/// The styles of the TUI, built from a [`Palette`].
+///
+/// Constructed once when the TUI starts and borrowed by every render function.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Theme {
+    palette: Palette,
+}
 
 impl Theme {
-    // Tokyo Night Palette
-    /// The primary color for borders, focuses, and active elements.
-    pub const PRIMARY: Color = Color::Rgb(122, 162, 247); // #7aa2f7 (Blue)
-    /// The secondary color for highlights and accents.
-    pub const SECONDARY: Color = Color::Rgb(187, 154, 247); // #bb9af7 (Purple)
-    /// The color used for background surfaces.
-    pub const BACKGROUND: Color = Color::Reset;
-    /// The color for surface overlays (like popups).
-    pub const SURFACE: Color = Color::Reset;
-
-    // Status colors
-    pub const SUCCESS: Color = Color::Rgb(158, 206, 106); // #9ece6a (Green)
-    pub const ERROR: Color = Color::Rgb(247, 118, 142); // #f7768e (Red)
-    pub const WARNING: Color = Color::Rgb(224, 175, 104); // #e0af68 (Yellow/Orange)
-    pub const INFO: Color = Color::Rgb(125, 207, 255); // #7dcfff (Cyan)
-
-    // Text colors
-    pub const TEXT_NORMAL: Color = Color::Rgb(192, 202, 245); // #c0caf5 (White-ish)
-    pub const TEXT_DIM: Color = Color::Rgb(86, 95, 137); // #565f89 (Dark Blue-Gray)
-
-    pub fn new() -> Self {
-        Self
+    pub fn new(palette: Palette) -> Self {
+        Self { palette }
+    }
+
+    /// A built-in theme, or the user theme `themes/<name>.toml` in `base_path`
+    pub fn load(name: &str, base_path: &Path) -> Result<Self, Box<dyn Error>> {
+        let palette = match name {
+            DEFAULT_THEME => Palette::default(),
+            "high-contrast" => Palette::high_contrast(),
+            "colorblind-safe" => Palette::colorblind_safe(),
+            _ => {
+                let path = base_path.join("themes").join(format!("{name}.toml"));
+                if !path.exists() {
+                    return Err(format!(
+                        "Unknown theme `{name}`: use one of {} or create {}",
+                        BUILTIN_THEMES.join(", "),
+                        path.display()
+                    )
+                    .into());
+                }
+                let content = fs::read_to_string(&path)?;
+                toml::from_str(&content).map_err(|e| format!("Invalid {}: {e}", path.display()))?
+            }
+        };
+        Ok(Self::new(palette))
+    }
+
+    pub fn palette(&self) -> &Palette {
+        &self.palette
     }
 
     // --- Block / Border Styles ---
 
     pub fn block_active(&self) -> Style {
         Style::default()
-            .fg(Self::PRIMARY)
+            .fg(self.palette.primary)
             .add_modifier(Modifier::BOLD)
     }
 
     pub fn block_inactive(&self) -> Style {
-        Style::default().fg(Self::TEXT_DIM)
+        Style::default().fg(self.palette.text_dim)
     }
 
     pub fn block_title_active(&self) -> Style {
         Style::default()
-            .fg(Self::PRIMARY)
+            .fg(self.palette.primary)
             .add_modifier(Modifier::BOLD)
     }
 
     pub fn block_title_inactive(&self) -> Style {
-        Style::default().fg(Self::TEXT_DIM)
+        Style::default().fg(self.palette.text_dim)
+    }
+
+    /// Border of popups that ask before something is lost
+    pub fn block_warning(&self) -> Style {
+        Style::default().fg(self.palette.warning)
     }
 
     // --- Text Styles ---
 
     pub fn text_normal(&self) -> Style {
-        Style::default().fg(Self::TEXT_NORMAL)
+        Style::default().fg(self.palette.text)
     }
 
     pub fn text_dim(&self) -> Style {
-        Style::default().fg(Self::TEXT_DIM)
+        Style::default().fg(self.palette.text_dim)
     }
 
     pub fn text_highlight(&self) -> Style {
         Style::default()
-            .fg(Self::SECONDARY)
+            .fg(self.palette.secondary)
             .add_modifier(Modifier::BOLD)
     }
 
+    /// Style for entries kept but switched off, such as disabled dependencies
+    pub fn text_disabled(&self) -> Style {
+        self.text_dim().add_modifier(Modifier::CROSSED_OUT)
+    }
+
     pub fn text_error(&self) -> Style {
-        Style::default().fg(Self::ERROR)
+        Style::default().fg(self.palette.error)
+    }
+
+    pub fn text_success(&self) -> Style {
+        Style::default().fg(self.palette.success)
+    }
+
+    pub fn text_warning(&self) -> Style {
+        Style::default().fg(self.palette.warning)
+    }
+
+    pub fn text_info(&self) -> Style {
+        Style::default().fg(self.palette.info)
+    }
+
+    // --- Help Hint Styles ---
+
+    /// Style of a key in a help hint
+    pub fn key(&self, role: KeyRole) -> Style {
+        let color = match role {
+            KeyRole::Cancel => self.palette.key_cancel,
+            KeyRole::Confirm => self.palette.key_confirm,
+            KeyRole::Focus => self.palette.key_focus,
+            KeyRole::Navigate => self.palette.key_navigate,
+            KeyRole::Edit => self.palette.key_edit,
+            KeyRole::Create => self.palette.key_create,
+            KeyRole::Change => self.palette.key_change,
+            KeyRole::Delete => self.palette.key_delete,
+            KeyRole::Search => self.palette.key_search,
+            KeyRole::Export => self.palette.key_export,
+            KeyRole::Dismiss => self.palette.key_dismiss,
+        };
+        Style::default().fg(color)
+    }
+
+    /// Style of a help hint whose key is not available right now
+    pub fn key_unavailable(&self) -> Style {
+        Style::default().fg(self.palette.key_unavailable)
     }
 
     // --- List / Table Styles ---
@@ -75,35 +344,36 @@ impl Theme {
     /// Style for a selected item in a list or table row (that has focus)
     pub fn selection_active(&self) -> Style {
         Style::default()
-            .bg(Color::Rgb(61, 89, 161)) // #3d59a1 (Selection Background)
-            .fg(Color::White)
+            .bg(self.palette.selection)
+            .fg(self.palette.selection_text)
             .add_modifier(Modifier::BOLD)
     }
 
     /// Style for a selected item that does NOT have focus (e.g. inactive pane)
     pub fn selection_inactive(&self) -> Style {
         Style::default()
-            .bg(Color::Rgb(41, 46, 66))
-            .fg(Self::TEXT_NORMAL) // Darker background
+            .bg(self.palette.selection_inactive)
+            .fg(self.palette.text)
     }
 
     // --- Input / Edit Styles ---
 
     /// Style for an active input field text
     pub fn input_active(&self) -> Style {
-        Style::default().fg(Self::TEXT_NORMAL)
+        Style::default().fg(self.palette.text)
     }
 
     pub fn input_cursor(&self) -> Style {
-        Style::default().bg(Self::PRIMARY).fg(Color::Black)
+        Style::default()
+            .bg(self.palette.primary)
+            .fg(self.palette.on_accent)
     }
 
     /// Style for the SPECIFIC CELL being edited/focused in a table
-    /// High contrast for Tokyo Night
     pub fn cell_focus(&self) -> Style {
         Style::default()
-            .bg(Self::WARNING) // Use the yellow/orange for high attention
-            .fg(Color::Black)
+            .bg(self.palette.warning) // Use the yellow/orange for high attention
+            .fg(self.palette.on_accent)
             .add_modifier(Modifier::BOLD)
     }
 
@@ -1,16 +1,31 @@
 use super::app::App;
-use super::views::{add_new, list};
-use super::widgets::{bottom, confirm_delete_popup, confirm_exit_popup, header};
+use super::theme::Theme;
+use super::views::{add_new, expand, list};
+use super::widgets::{
+    bottom, confirm_delete_popup, confirm_drop_invalid_popup, confirm_exit_popup, diff_popup,
+    header, recent_popup, status_log_popup,
+};
 use crate::tui::app::AppState;
 use crate::tui::widgets::main_right;
 use ratatui::prelude::*;
+use ratatui::widgets::{Paragraph, Wrap};
 use unicode_width::UnicodeWidthStr;
 
+/// Smallest terminal size the regular layout can be drawn in
+pub const MIN_WIDTH: u16 = 40;
+pub const MIN_HEIGHT: u16 = 10;
+
 pub fn ui(frame: &mut Frame<'_>, app: &App) {
+    let area = frame.area();
+    if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+        render_too_small(frame, area, &app.theme);
+        return;
+    }
+
     let layout = Layout::vertical([
         Constraint::Length(3),
         Constraint::Fill(1),
-        Constraint::Length(3),
+        Constraint::Length(bottom::HEIGHT),
     ])
     .split(frame.area());
 
@@ -35,26 +50,65 @@ pub fn ui(frame: &mut Frame<'_>, app: &App) {
         AppState::ConfirmExit => {
             confirm_exit_popup::render(frame, app);
         }
+        AppState::ConfirmDropInvalid => {
+            confirm_drop_invalid_popup::render(frame, app);
+        }
+        AppState::Export => {
+            expand::render_export_popup(frame, app);
+        }
+        AppState::StatusLog => {
+            status_log_popup::render(frame, app);
+        }
+        AppState::Recent => {
+            recent_popup::render(frame, app);
+        }
+        AppState::Diff => {
+            diff_popup::render(frame, app);
+        }
         _ => {}
     }
 }
 
+fn render_too_small(frame: &mut Frame<'_>, area: Rect, theme: &Theme) {
+    let message = format!(
+        "Terminal too small ({}x{}), need at least {MIN_WIDTH}x{MIN_HEIGHT}",
+        area.width, area.height
+    );
+    let [message_area] = Layout::vertical([Constraint::Length(2)])
+        .flex(layout::Flex::Center)
+        .areas(area);
+    let paragraph = Paragraph::new(message)
+        .style(theme.text_error())
+        .centered()
+        .wrap(Wrap { trim: true });
+    frame.render_widget(paragraph, message_area);
+}
+
 fn calculate_main_left_width(app: &App) -> u16 {
-    let profiles = app.list_view.filtered_profiles();
-    let max_len = profiles
+    let entries = app.list_view.filtered_entries();
+    let max_len = entries
         .iter()
-        .map(|name| UnicodeWidthStr::width(name.as_str()))
+        .map(|entry| {
+            let badge_width = entry
+                .badge()
+                .map(|b| UnicodeWidthStr::width(b.as_str()) + 1)
+                .unwrap_or(0);
+            let annotation_width = entry
+                .annotation()
+                .map(|a| UnicodeWidthStr::width(a.as_str()) + 1)
+                .unwrap_or(0);
+            UnicodeWidthStr::width(entry.name.as_str()) + badge_width + annotation_width
+        })
         .max()
         .unwrap_or(0);
-
-    // Calculate title widths to prevent truncation
-    let filtered_count = profiles.len();
-    let current_index = app.list_view.selected_index() + 1;
-    let title_str = if filtered_count == 0 {
-        "Profile List (0/0)".to_string()
+    let marker_width = if app.list_view.is_visual_mode() {
+        UnicodeWidthStr::width(list::MARKED)
     } else {
-        format!("Profile List ({}/{})", current_index, filtered_count)
+        0
     };
+
+    // Calculate title widths to prevent truncation
+    let title_str = list::list_title(&app.list_view, app.list_view.rows().len());
     let title_width = UnicodeWidthStr::width(title_str.as_str());
 
     let unsaved_count = app.list_view.unsaved_count();
@@ -66,7 +120,7 @@ fn calculate_main_left_width(app: &App) -> u16 {
 
     // +4 for borders/gap between titles
     let min_title_width = title_width + unsaved_width + 4;
-    let content_width = max_len + 6;
+    let content_width = max_len + marker_width + 6;
 
     (content_width.max(min_title_width)).clamp(25, 60) as u16
 }
@@ -1,7 +1,16 @@
+use crate::config::models::{KeyValidation, Profile};
+use crate::tui::theme::Theme;
 use crate::utils::{self, IdentifierError};
+use ratatui::crossterm::event::KeyCode;
 use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Paragraph};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 /// A reusable struct to manage state for a text input field, with robust unicode support.
+///
+/// The cursor counts grapheme clusters, so an emoji sequence or a letter with combining
+/// accents is moved over and deleted as one character.
 #[derive(Debug, PartialEq, Eq, Default)]
 pub struct Input {
     text: String,
@@ -16,16 +25,18 @@ impl Input {
 
     /// Create an Input from its individual parts
     pub fn from_parts(text: String, cursor_position: usize, error_message: Option<String>) -> Self {
-        Self {
+        let mut input = Self {
             text,
-            cursor_position,
+            cursor_position: 0,
             error_message,
-        }
+        };
+        input.set_cursor_position(cursor_position);
+        input
     }
 
     /// Create an Input from text, with cursor at end
     pub fn with_text(text: String) -> Self {
-        let cursor_position = text.len();
+        let cursor_position = text.graphemes(true).count();
         Self {
             text,
             cursor_position,
@@ -48,14 +59,22 @@ impl Input {
         self.error_message = None;
     }
 
-    /// Get the cursor position
+    /// Get the cursor position, in grapheme clusters
     pub fn cursor_position(&self) -> usize {
         self.cursor_position
     }
 
-    /// Set the cursor position
+    /// Set the cursor position, in grapheme clusters
     pub fn set_cursor_position(&mut self, position: usize) {
-        self.cursor_position = position.clamp(0, self.text.chars().count());
+        self.cursor_position = position.min(self.grapheme_count());
+    }
+
+    /// Terminal columns taken by the text before the cursor
+    pub fn cursor_display_width(&self) -> usize {
+        self.text[..self.byte_index()]
+            .graphemes(true)
+            .map(UnicodeWidthStr::width)
+            .sum()
     }
 
     /// Get the text content as a string slice
@@ -66,42 +85,47 @@ impl Input {
     /// Set the text content, moving cursor to end
     pub fn set_text(&mut self, text: String) {
         self.text = text;
-        self.cursor_position = self.text.len();
+        self.cursor_position = self.grapheme_count();
+    }
+
+    fn grapheme_count(&self) -> usize {
+        self.text.graphemes(true).count()
+    }
+
+    /// Byte offset of the grapheme cluster the cursor is on
+    fn byte_index(&self) -> usize {
+        self.text
+            .grapheme_indices(true)
+            .nth(self.cursor_position)
+            .map_or(self.text.len(), |(i, _)| i)
     }
 
     pub fn move_cursor_right(&mut self) {
-        let cursor_moved_right = self.cursor_position.saturating_add(1);
-        self.cursor_position = cursor_moved_right.clamp(0, self.text.chars().count());
+        self.set_cursor_position(self.cursor_position.saturating_add(1));
     }
 
     pub fn move_cursor_left(&mut self) {
-        let cursor_moved_left = self.cursor_position.saturating_sub(1);
-        self.cursor_position = cursor_moved_left.clamp(0, self.text.chars().count());
+        self.set_cursor_position(self.cursor_position.saturating_sub(1));
     }
 
+    /// Insert `c` at the cursor. A combining mark or joiner extends the grapheme
+    /// before it instead of starting a new one, leaving the cursor after it.
     pub fn enter_char(&mut self, c: char) {
-        let index = self
-            .text
-            .char_indices()
-            .map(|(i, _)| i)
-            .nth(self.cursor_position)
-            .unwrap_or(self.text.len());
-        self.text.insert(index, c);
-        self.move_cursor_right()
+        let index = self.byte_index() + c.len_utf8();
+        self.text.insert(self.byte_index(), c);
+        self.cursor_position = self.text[..index].graphemes(true).count();
     }
 
+    /// Delete the whole grapheme cluster before the cursor
     pub fn delete_char(&mut self) {
-        let is_not_cursor_leftmost = self.cursor_position != 0;
-        if is_not_cursor_leftmost {
-            let current_index = self.cursor_position;
-            let from_left_to_current_index = current_index - 1;
-
-            let before_char_to_delete = self.text.chars().take(from_left_to_current_index);
-            let after_char_to_delete = self.text.chars().skip(current_index);
-
-            self.text = before_char_to_delete.chain(after_char_to_delete).collect();
-            self.move_cursor_left();
+        if self.cursor_position == 0 {
+            return;
         }
+        let end = self.byte_index();
+        self.cursor_position -= 1;
+        let start = self.byte_index();
+        self.text.replace_range(start..end, "");
+        self.cursor_position = self.text[..start].graphemes(true).count();
     }
 
     pub fn set_error_message(&mut self, error_message: &str) {
@@ -115,6 +139,192 @@ impl Input {
     }
 }
 
+/// State for a multi-line text field, with the cursor as a line and a column.
+///
+/// Like [`Input`], the column counts grapheme clusters.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TextArea {
+    lines: Vec<String>,
+    row: usize,
+    column: usize,
+}
+
+impl Default for TextArea {
+    fn default() -> Self {
+        Self {
+            lines: vec![String::new()],
+            row: 0,
+            column: 0,
+        }
+    }
+}
+
+impl TextArea {
+    /// Create a TextArea holding `text`, with the cursor at its end
+    pub fn with_text(text: &str) -> Self {
+        let lines: Vec<String> = text.split('\n').map(str::to_string).collect();
+        let row = lines.len() - 1;
+        let column = lines[row].graphemes(true).count();
+        Self { lines, row, column }
+    }
+
+    /// The content with its lines joined by `\n`
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    /// The cursor as (line, column in grapheme clusters)
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.row, self.column)
+    }
+
+    /// Terminal columns taken by the text of the cursor line before the cursor
+    pub fn cursor_display_width(&self) -> usize {
+        self.lines[self.row][..self.byte_index()]
+            .graphemes(true)
+            .map(UnicodeWidthStr::width)
+            .sum()
+    }
+
+    fn line_len(&self, row: usize) -> usize {
+        self.lines[row].graphemes(true).count()
+    }
+
+    /// Byte offset of the grapheme cluster the cursor is on
+    fn byte_index(&self) -> usize {
+        let line = &self.lines[self.row];
+        line.grapheme_indices(true)
+            .nth(self.column)
+            .map_or(line.len(), |(i, _)| i)
+    }
+
+    /// Insert `c` at the cursor. A combining mark or joiner extends the grapheme
+    /// before it instead of starting a new one, leaving the cursor after it.
+    pub fn enter_char(&mut self, c: char) {
+        let index = self.byte_index();
+        let line = &mut self.lines[self.row];
+        line.insert(index, c);
+        self.column = line[..index + c.len_utf8()].graphemes(true).count();
+    }
+
+    /// Split the current line at the cursor
+    pub fn insert_newline(&mut self) {
+        let index = self.byte_index();
+        let rest = self.lines[self.row].split_off(index);
+        self.row += 1;
+        self.lines.insert(self.row, rest);
+        self.column = 0;
+    }
+
+    /// Delete the grapheme cluster before the cursor, joining with the previous line at
+    /// its start
+    pub fn delete_char(&mut self) {
+        if self.column > 0 {
+            let end = self.byte_index();
+            self.column -= 1;
+            let start = self.byte_index();
+            let line = &mut self.lines[self.row];
+            line.replace_range(start..end, "");
+            self.column = line[..start].graphemes(true).count();
+        } else if self.row > 0 {
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.column = self.line_len(self.row);
+            self.lines[self.row].push_str(&line);
+        }
+    }
+
+    pub fn move_cursor_left(&mut self) {
+        if self.column > 0 {
+            self.column -= 1;
+        } else if self.row > 0 {
+            self.row -= 1;
+            self.column = self.line_len(self.row);
+        }
+    }
+
+    pub fn move_cursor_right(&mut self) {
+        if self.column < self.line_len(self.row) {
+            self.column += 1;
+        } else if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.column = 0;
+        }
+    }
+
+    pub fn move_cursor_up(&mut self) {
+        if self.row > 0 {
+            self.row -= 1;
+            self.column = self.column.min(self.line_len(self.row));
+        }
+    }
+
+    pub fn move_cursor_down(&mut self) {
+        if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.column = self.column.min(self.line_len(self.row));
+        }
+    }
+
+    pub fn move_cursor_home(&mut self) {
+        self.column = 0;
+    }
+
+    pub fn move_cursor_end(&mut self) {
+        self.column = self.line_len(self.row);
+    }
+}
+
+/// Counts above this are clamped, so a mistyped count cannot stall the UI
+pub const MAX_COUNT: usize = 999;
+
+/// A vim-style count typed before a motion key, as in `5j`
+#[derive(Debug, Default)]
+pub struct CountPrefix {
+    count: Option<usize>,
+}
+
+impl CountPrefix {
+    /// Add `key` to the count if it is a digit, returning whether it was consumed.
+    ///
+    /// A leading `0` is not a count.
+    pub fn push_key(&mut self, key: KeyCode) -> bool {
+        let KeyCode::Char(c) = key else {
+            return false;
+        };
+        let Some(digit) = c.to_digit(10) else {
+            return false;
+        };
+        if digit == 0 && self.count.is_none() {
+            return false;
+        }
+        let count = self.count.unwrap_or(0) * 10 + digit as usize;
+        self.count = Some(count.min(MAX_COUNT));
+        true
+    }
+
+    /// How many times to repeat the next motion, 1 when no count was typed; the count is reset
+    pub fn take(&mut self) -> usize {
+        self.count.take().unwrap_or(1)
+    }
+
+    pub fn pending(&self) -> Option<usize> {
+        self.count
+    }
+}
+
+/// The first line of `value`, followed by `⏎×N` when N more line breaks follow
+pub fn single_line_preview(value: &str) -> String {
+    match value.split_once('\n') {
+        Some((first, _)) => format!("{first} ⏎×{}", value.matches('\n').count()),
+        None => value.to_string(),
+    }
+}
+
 /// Helper function to create a centered rect using up certain percentage of the available rect `r`
 pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::vertical([
@@ -133,7 +343,43 @@ pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 }
 
 pub fn inner(area: Rect) -> Rect {
-    Rect::new(area.x + 1, area.y + 1, area.width - 2, area.height - 2)
+    Rect::new(
+        area.x.saturating_add(1),
+        area.y.saturating_add(1),
+        area.width.saturating_sub(2),
+        area.height.saturating_sub(2),
+    )
+}
+
+/// Fit a manually positioned rect inside `bounds`, shrinking it if needed and then
+/// shifting it back on screen so popups never draw outside the frame
+pub fn clamp_rect(area: Rect, bounds: Rect) -> Rect {
+    let width = area.width.min(bounds.width);
+    let height = area.height.min(bounds.height);
+    let x = area
+        .x
+        .clamp(bounds.x, bounds.right().saturating_sub(width).max(bounds.x));
+    let y = area.y.clamp(
+        bounds.y,
+        bounds.bottom().saturating_sub(height).max(bounds.y),
+    );
+    Rect::new(x, y, width, height)
+}
+
+/// The tags and description of `profile` on one line, `None` when it has neither
+pub fn metadata_line(profile: &Profile, theme: &Theme) -> Option<Line<'static>> {
+    if profile.description.is_none() && profile.tags.is_empty() {
+        return None;
+    }
+    let mut spans: Vec<Span> = profile
+        .tags
+        .iter()
+        .map(|tag| Span::styled(format!("#{tag} "), theme.text_highlight()))
+        .collect();
+    if let Some(description) = &profile.description {
+        spans.push(Span::styled(description.clone(), theme.text_dim()));
+    }
+    Some(Line::from(spans))
 }
 
 pub fn input_to_span<'a>(
@@ -143,20 +389,9 @@ pub fn input_to_span<'a>(
 ) -> Line<'a> {
     if is_focused {
         // Simple cursor simulation: split text at cursor
-        let (left, right) = input.text.split_at(
-            input
-                .text
-                .char_indices()
-                .nth(input.cursor_position)
-                .map(|(i, _)| i)
-                .unwrap_or(input.text.len()),
-        );
-
-        let cursor_char = if right.is_empty() {
-            " "
-        } else {
-            &right[..right.chars().next().unwrap().len_utf8()]
-        };
+        let (left, right) = input.text.split_at(input.byte_index());
+
+        let cursor_char = right.graphemes(true).next().unwrap_or(" ");
 
         let right_rest = if right.is_empty() {
             ""
@@ -174,8 +409,20 @@ pub fn input_to_span<'a>(
     }
 }
 
+/// Validate a profile name input, which may be namespaced as in `work/api`
 pub fn validate_input(input: &mut Input) -> bool {
-    match utils::validate_profile_name(input.text()) {
+    let result = utils::validate_namespaced_profile_name(input.text());
+    report_identifier_error(input, result)
+}
+
+/// Validate a variable key input, which may end in a `+`/`^` list suffix
+pub fn validate_key_input(input: &mut Input, validation: KeyValidation) -> bool {
+    let result = utils::validate_variable_key_as(input.text(), validation);
+    report_identifier_error(input, result)
+}
+
+fn report_identifier_error(input: &mut Input, result: Result<(), IdentifierError>) -> bool {
+    match result {
         Ok(_) => true,
         Err(err) => {
             match err {
@@ -186,9 +433,128 @@ pub fn validate_input(input: &mut Input) -> bool {
                 IdentifierError::InvalidCharacter(ch) => {
                     input.set_error_message(&format!("Cannot contain character '{ch}'"))
                 }
-                _ => {}
+                IdentifierError::LeadingSlash => input.set_error_message("Cannot start with '/'"),
+                IdentifierError::EmptySegment => {
+                    input.set_error_message("Cannot contain empty segments")
+                }
+                IdentifierError::ParentSegment => {
+                    input.set_error_message("Cannot contain a '..' segment")
+                }
+                IdentifierError::Reserved => {
+                    input.set_error_message("Reserved for the global profile")
+                }
+                IdentifierError::Internal => input.set_error_message("Reserved for internal use"),
+                IdentifierError::ContainsLowercase => {
+                    input.set_error_message("Must be all uppercase")
+                }
             }
             false
         }
     }
 }
+
+/// Validate a comma-separated tag list, flagging the first invalid tag
+pub fn validate_tags_input(input: &mut Input) -> bool {
+    input.clear_error();
+    for tag in utils::parse_tags(input.text()) {
+        if utils::validate_tag(&tag).is_err() {
+            input.set_error_message(&format!("Invalid tag '{tag}'"));
+            return false;
+        }
+    }
+    true
+}
+
+/// Render a bordered single-line text field, scrolling horizontally to keep the cursor visible
+pub fn render_input_field(
+    frame: &mut Frame<'_>,
+    area: Rect,
+    input: &Input,
+    title: &str,
+    is_focused: bool,
+    theme: &Theme,
+) {
+    let border_style = if !input.is_valid() {
+        theme.text_error()
+    } else if is_focused {
+        theme.block_active()
+    } else {
+        theme.block_inactive()
+    };
+
+    let mut input_block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(border_style);
+
+    if !input.is_valid()
+        && let Some(err) = input.error_message()
+    {
+        input_block =
+            input_block.title_bottom(Line::from(err).style(theme.text_error()).right_aligned());
+    }
+
+    let text_input_rect = input_block.inner(area);
+    frame.render_widget(input_block, area);
+
+    let input_text = input.text();
+    let cursor_display_pos = input.cursor_display_width() as u16;
+    let scroll_offset = if cursor_display_pos >= text_input_rect.width {
+        cursor_display_pos - text_input_rect.width + 1
+    } else {
+        0
+    };
+
+    let input_paragraph = Paragraph::new(input_text)
+        .style(theme.text_normal())
+        .scroll((0, scroll_offset));
+    frame.render_widget(input_paragraph, text_input_rect);
+
+    if is_focused {
+        frame.set_cursor_position((
+            text_input_rect.x + cursor_display_pos - scroll_offset,
+            text_input_rect.y,
+        ));
+    }
+}
+
+/// Lay out help items (`key: action` span groups) over at most `max_lines` lines of
+/// `width` columns, two spaces apart; items that do not fit are dropped
+pub fn wrap_help_items<'a>(
+    help_info: &[Vec<Span<'a>>],
+    width: u16,
+    max_lines: usize,
+) -> Vec<Line<'a>> {
+    let total_width = width as usize;
+    let mut lines: Vec<Line> = vec![];
+    let mut current_line_spans: Vec<Span> = vec![];
+    let mut current_line_width = 0;
+
+    for info in help_info {
+        if lines.len() >= max_lines {
+            break;
+        }
+        let item_width: usize = info.iter().map(|span| span.width()).sum();
+        let separator_width = if !current_line_spans.is_empty() { 2 } else { 0 };
+
+        if current_line_width + separator_width + item_width > total_width
+            && !current_line_spans.is_empty()
+        {
+            lines.push(Line::from(std::mem::take(&mut current_line_spans)));
+            current_line_width = 0;
+            if lines.len() >= max_lines {
+                break;
+            }
+        }
+        if !current_line_spans.is_empty() {
+            current_line_spans.push(Span::raw("  "));
+            current_line_width += 2;
+        }
+        current_line_spans.extend_from_slice(info);
+        current_line_width += item_width;
+    }
+    if !current_line_spans.is_empty() && lines.len() < max_lines {
+        lines.push(Line::from(current_line_spans));
+    }
+    lines
+}
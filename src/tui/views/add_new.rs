@@ -1,8 +1,19 @@
 use crate::GLOBAL_PROFILE_MARK;
-use crate::config::models::Profile;
+use crate::config::ConfigManager;
+use crate::config::graph::DependencyError;
+use crate::config::models::{KeyValidation, Profile};
 use crate::tui::app::{App, AppState};
 use crate::tui::widgets::empty;
-use crate::tui::{theme::Theme, utils, utils::Input, utils::validate_input};
+use crate::tui::{
+    theme::{KeyRole, Theme},
+    utils,
+    utils::Input,
+    utils::validate_input,
+    utils::validate_key_input,
+    utils::validate_tags_input,
+};
+use crate::utils::parse_tags;
+use indexmap::IndexMap;
 use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::layout::{Constraint, Layout, Margin, Rect};
 use ratatui::prelude::*;
@@ -10,9 +21,7 @@ use ratatui::widgets::{
     Block, Borders, Cell, Clear, List, ListItem, ListState, Paragraph, Row, Scrollbar,
     ScrollbarOrientation, ScrollbarState, Table, TableState,
 };
-use std::collections::{HashMap, HashSet};
-use std::mem;
-use unicode_width::UnicodeWidthStr;
+use std::collections::HashSet;
 
 const MAX_HELP_LINES: usize = 2;
 
@@ -27,6 +36,8 @@ pub const MAX_VARIABLES_HEIGHT: usize = 8;
 pub enum AddNewFocus {
     #[default]
     Name,
+    Description,
+    Tags,
     Profiles,
     Variables,
 }
@@ -41,11 +52,15 @@ pub enum AddNewVariableFocus {
 #[derive(Default)]
 pub struct AddNewView {
     pub name_input: Input,
+    pub description_input: Input,
+    pub tags_input: Input,
 
     // Profiles section
     pub profiles_selection_index: usize,
     pub added_profiles: HashSet<String>,
     pub profile_scroll_offset: usize,
+    /// The cycle the selected profiles would close, checked on every change
+    pub cycle: Option<Vec<String>>,
 
     // Variables section
     pub variables: Vec<(Input, Input)>,
@@ -57,6 +72,9 @@ pub struct AddNewView {
 
     // Focus management
     pub focus: AddNewFocus,
+
+    /// Key validation of new profiles, the global default; kept across resets
+    pub key_validation: KeyValidation,
 }
 
 impl AddNewView {
@@ -66,9 +84,12 @@ impl AddNewView {
 
     pub fn reset(&mut self) {
         self.name_input = Input::default();
+        self.description_input = Input::default();
+        self.tags_input = Input::default();
         self.profiles_selection_index = 0;
         self.added_profiles.clear();
         self.profile_scroll_offset = 0;
+        self.cycle = None;
         self.variables.clear();
         self.selected_variable_index = 0;
         self.variable_scroll_offset = 0;
@@ -98,10 +119,30 @@ impl AddNewView {
         &mut self.name_input
     }
 
+    pub fn description_input(&self) -> &Input {
+        &self.description_input
+    }
+
+    pub fn tags_input(&self) -> &Input {
+        &self.tags_input
+    }
+
+    /// The text field owning the current focus, if any
+    pub fn focused_text_input_mut(&mut self) -> Option<&mut Input> {
+        match self.focus {
+            AddNewFocus::Name => Some(&mut self.name_input),
+            AddNewFocus::Description => Some(&mut self.description_input),
+            AddNewFocus::Tags => Some(&mut self.tags_input),
+            AddNewFocus::Profiles | AddNewFocus::Variables => None,
+        }
+    }
+
     pub fn switch_focus(&mut self, forward: bool) {
         self.focus = if forward {
             match self.focus {
-                AddNewFocus::Name => AddNewFocus::Profiles,
+                AddNewFocus::Name => AddNewFocus::Description,
+                AddNewFocus::Description => AddNewFocus::Tags,
+                AddNewFocus::Tags => AddNewFocus::Profiles,
                 AddNewFocus::Profiles => AddNewFocus::Variables,
                 AddNewFocus::Variables => AddNewFocus::Name,
             }
@@ -109,7 +150,9 @@ impl AddNewView {
             match self.focus {
                 AddNewFocus::Name => AddNewFocus::Variables,
                 AddNewFocus::Variables => AddNewFocus::Profiles,
-                AddNewFocus::Profiles => AddNewFocus::Name,
+                AddNewFocus::Profiles => AddNewFocus::Tags,
+                AddNewFocus::Tags => AddNewFocus::Description,
+                AddNewFocus::Description => AddNewFocus::Name,
             }
         };
     }
@@ -130,6 +173,18 @@ impl AddNewView {
         self.added_profiles.contains(name)
     }
 
+    pub fn cycle(&self) -> Option<&[String]> {
+        self.cycle.as_deref()
+    }
+
+    /// Whether `name` is a selected profile on the cycle the selection would close
+    pub fn closes_cycle(&self, name: &str) -> bool {
+        self.is_profile_added(name)
+            && self
+                .cycle()
+                .is_some_and(|path| path.iter().any(|p| p == name))
+    }
+
     pub fn select_next_profile(&mut self, profiles_count: usize) {
         if profiles_count == 0 {
             return;
@@ -510,11 +565,23 @@ fn save_profile(app: &mut App) {
     if !validate_name(app) {
         return;
     }
+    if !validate_tags_input(&mut app.add_new_view.tags_input) {
+        app.add_new_view.focus = AddNewFocus::Tags;
+        return;
+    }
+    check_cycle(&mut app.add_new_view, &app.config_manager);
+    if let Some(path) = app.add_new_view.cycle() {
+        let message = format!("Circular dependency: {}", path.join(" -> "));
+        app.add_new_view.focus = AddNewFocus::Profiles;
+        app.show_error(message);
+        return;
+    }
 
     let add_new = &mut app.add_new_view;
     let new_name = add_new.name_input().text().trim().to_string();
+    let description = add_new.description_input().text().trim();
 
-    let variables_map: HashMap<String, String> = add_new
+    let variables_map: IndexMap<String, String> = add_new
         .variables_for_rendering()
         .iter()
         .map(|(k, v)| (k.text().to_string(), v.text().to_string()))
@@ -522,8 +589,18 @@ fn save_profile(app: &mut App) {
         .collect();
 
     let new_profile = Profile {
-        profiles: add_new.added_profiles().iter().cloned().collect(),
+        description: (!description.is_empty()).then(|| description.to_string()),
+        tags: parse_tags(add_new.tags_input().text()),
+        // A profile cannot depend on itself; the set drops duplicates
+        profiles: add_new
+            .added_profiles()
+            .iter()
+            .filter(|name| **name != new_name)
+            .cloned()
+            .collect(),
         variables: variables_map,
+        unset: HashSet::new(),
+        ..Profile::default()
     };
 
     // 1. Add profile to memory
@@ -537,30 +614,20 @@ fn save_profile(app: &mut App) {
     // 3. Add dependency edges to graph
     for dep_name in &new_profile.profiles {
         if let Err(e) = app.config_manager.add_dependency_edge(&new_name, dep_name) {
-            app.status_message = Some(format!(
+            app.show_error(format!(
                 "Warning: Failed to add dependency edge to '{dep_name}': {e}"
             ));
         }
     }
 
     // 4. Update UI list
-    let mut profiles = app.list_view.all_profiles().to_vec();
-    profiles.push(new_name.clone());
-    profiles.sort();
-    app.list_view.update_profiles(profiles);
+    app.refresh_profile_list();
 
-    if let Some(index) = app
-        .list_view
-        .all_profiles()
-        .iter()
-        .position(|r| r == &new_name)
-    {
-        app.list_view.set_selected_index(index);
-    }
+    app.list_view.select_profile(&new_name);
 
-    app.status_message = Some(format!("Profile '{new_name}' created."));
+    app.show_success(format!("Profile '{new_name}' created."));
     app.state = AppState::List;
-    add_new.reset();
+    app.add_new_view.reset();
 }
 
 fn close_popup(app: &mut App) {
@@ -569,9 +636,11 @@ fn close_popup(app: &mut App) {
 }
 
 fn attempt_switch_focus(app: &mut App, forward: bool) {
-    // If focused on Name, validate before leaving
-    if app.add_new_view.current_focus() == AddNewFocus::Name && !validate_name(app) {
-        return;
+    // Validate text fields before leaving them
+    match app.add_new_view.current_focus() {
+        AddNewFocus::Name if !validate_name(app) => return,
+        AddNewFocus::Tags if !validate_tags_input(&mut app.add_new_view.tags_input) => return,
+        _ => {}
     }
     app.add_new_view.switch_focus(forward);
 }
@@ -584,23 +653,30 @@ fn dispatch_context_key(app: &mut App, key: KeyEvent) {
             app.add_new_view.reset();
             app.state = AppState::List;
         }
-        KeyCode::Char(c) if focus == AddNewFocus::Name => {
-            app.add_new_view.name_input_mut().enter_char(c);
-            validate_name(app);
-        }
-        KeyCode::Backspace if focus == AddNewFocus::Name => {
-            app.add_new_view.name_input_mut().delete_char();
-            validate_name(app);
-        }
-        KeyCode::Left if focus == AddNewFocus::Name => {
-            app.add_new_view.name_input_mut().move_cursor_left()
-        }
-        KeyCode::Right if focus == AddNewFocus::Name => {
-            app.add_new_view.name_input_mut().move_cursor_right()
-        }
-        KeyCode::Enter if focus == AddNewFocus::Name && validate_name(app) => {
-            app.add_new_view.switch_focus(true);
+        KeyCode::Char(_) | KeyCode::Backspace | KeyCode::Left | KeyCode::Right
+            if is_text_focus(focus) =>
+        {
+            if let Some(input) = app.add_new_view.focused_text_input_mut() {
+                match key.code {
+                    KeyCode::Char(c) => input.enter_char(c),
+                    KeyCode::Backspace => input.delete_char(),
+                    KeyCode::Left => input.move_cursor_left(),
+                    KeyCode::Right => input.move_cursor_right(),
+                    _ => {}
+                }
+            }
+            match focus {
+                AddNewFocus::Name => {
+                    validate_name(app);
+                    check_cycle(&mut app.add_new_view, &app.config_manager);
+                }
+                AddNewFocus::Tags => {
+                    validate_tags_input(&mut app.add_new_view.tags_input);
+                }
+                _ => {}
+            }
         }
+        KeyCode::Enter if is_text_focus(focus) => attempt_switch_focus(app, true),
         _ => {
             // Dispatch to specific handlers for Profiles and Variables
             match focus {
@@ -612,12 +688,19 @@ fn dispatch_context_key(app: &mut App, key: KeyEvent) {
     }
 }
 
+fn is_text_focus(focus: AddNewFocus) -> bool {
+    matches!(
+        focus,
+        AddNewFocus::Name | AddNewFocus::Description | AddNewFocus::Tags
+    )
+}
+
 fn profiles(app: &mut App, key_code: KeyCode) {
     let add_new = &mut app.add_new_view;
     let available_profiles: Vec<_> = app
         .list_view
         .all_profiles()
-        .iter()
+        .into_iter()
         .filter(|name| **name != add_new.name_input().text() && *name != GLOBAL_PROFILE_MARK)
         .collect();
     let count = available_profiles.len();
@@ -629,12 +712,34 @@ fn profiles(app: &mut App, key_code: KeyCode) {
             if let Some(selected_name) = available_profiles.get(add_new.profiles_selection_index())
             {
                 add_new.toggle_current_profile(selected_name.to_string());
+                check_cycle(add_new, &app.config_manager);
             }
         }
         _ => {}
     }
 }
 
+/// Try the typed name and the selected profiles on a copy of the dependency graph,
+/// so a cycle shows up before saving rather than after
+fn check_cycle(add_new: &mut AddNewView, config_manager: &ConfigManager) {
+    let name = add_new.name_input().text().trim();
+    if name.is_empty() || add_new.added_profiles().is_empty() {
+        add_new.cycle = None;
+        return;
+    }
+    let mut dependencies: Vec<String> = add_new
+        .added_profiles()
+        .iter()
+        .filter(|dependency| *dependency != name)
+        .cloned()
+        .collect();
+    dependencies.sort();
+    add_new.cycle = match config_manager.check_new_profile(name, &dependencies) {
+        Err(DependencyError::CircularDependency { path, .. }) => Some(path),
+        _ => None,
+    };
+}
+
 fn variables(app: &mut App, key_code: KeyCode) {
     let add_new = &mut app.add_new_view;
     match key_code {
@@ -650,11 +755,17 @@ fn variables(app: &mut App, key_code: KeyCode) {
 }
 
 fn validate_name(app: &mut App) -> bool {
+    let taken = app.is_name_taken(app.add_new_view.name_input().text());
     let input = app.add_new_view.name_input_mut();
     input.clear_error();
-    if app.config_manager.has_profile(input.text()) {
+    if taken {
         input.set_error_message("Profile already exists");
         false
+    } else if let Some(existing) = app.config_manager.case_conflict(input.text()) {
+        input.set_error_message(&format!(
+            "Same file as '{existing}' (names ignore case here)"
+        ));
+        false
     } else {
         validate_input(input)
     }
@@ -663,9 +774,10 @@ fn validate_name(app: &mut App) -> bool {
 /// Validates the currently focused variable input (if it's a Key).
 /// Returns true if valid, false if invalid.
 fn validate_variable_key_input(add_new: &mut AddNewView) -> bool {
+    let validation = add_new.key_validation;
     if let Some(input) = add_new.get_focused_variable_input_mut() {
         input.clear_error();
-        validate_input(input)
+        validate_key_input(input, validation)
     } else {
         true
     }
@@ -684,7 +796,7 @@ pub fn render(frame: &mut Frame<'_>, app: &App) {
     let area = utils::centered_rect(70, 80, frame.area());
     frame.render_widget(Clear, area);
 
-    let theme = Theme::new();
+    let theme = &app.theme;
     let add_new_state = &app.add_new_view;
 
     let popup_block = Block::default()
@@ -698,14 +810,16 @@ pub fn render(frame: &mut Frame<'_>, app: &App) {
 
     let main_layout = Layout::vertical([
         Constraint::Length(3), // Name section
+        Constraint::Length(3), // Description and tags section
         Constraint::Min(0),    // Flexible middle section
         Constraint::Length(2), // Help section
     ])
     .split(inner_popup_area);
 
     let name_area = main_layout[0];
-    let middle_area = main_layout[1];
-    let help_area = main_layout[2];
+    let details_area = main_layout[1];
+    let middle_area = main_layout[2];
+    let help_area = main_layout[3];
 
     let middle_layout = Layout::vertical([
         Constraint::Percentage(40), // Profiles section
@@ -716,69 +830,40 @@ pub fn render(frame: &mut Frame<'_>, app: &App) {
     let profiles_area = middle_layout[0];
     let variables_area = middle_layout[1];
 
-    render_name_section(frame, add_new_state, name_area, &theme);
-    render_profiles_section(frame, app, profiles_area, &theme);
-    render_variables_section(frame, app, variables_area, &theme);
+    utils::render_input_field(
+        frame,
+        name_area,
+        add_new_state.name_input(),
+        "Name",
+        add_new_state.current_focus() == AddNewFocus::Name,
+        theme,
+    );
+    render_details_section(frame, add_new_state, details_area, theme);
+    render_profiles_section(frame, app, profiles_area, theme);
+    render_variables_section(frame, app, variables_area, theme);
     render_help_section(frame, app, help_area);
 }
 
-fn render_name_section(frame: &mut Frame<'_>, add_new: &AddNewView, area: Rect, theme: &Theme) {
-    let is_focused = add_new.current_focus() == AddNewFocus::Name;
-
-    let border_style = if !add_new.name_input().is_valid() {
-        theme.text_error()
-    } else if is_focused {
-        theme.block_active()
-    } else {
-        theme.block_inactive()
-    };
-
-    let mut input_block = Block::default()
-        .title("Name")
-        .borders(Borders::ALL)
-        .border_style(border_style);
-
-    if !add_new.name_input().is_valid()
-        && let Some(err) = add_new.name_input().error_message()
-    {
-        input_block =
-            input_block.title_bottom(Line::from(err).style(theme.text_error()).right_aligned());
-    }
-
-    let text_input_rect = input_block.inner(area);
-    frame.render_widget(input_block, area);
-
-    let input_text = add_new.name_input().text();
-    let cursor_char_pos = add_new.name_input().cursor_position();
-
-    // Calculate scroll offset for horizontal scrolling
-    let prefix_width = input_text
-        .chars()
-        .take(cursor_char_pos)
-        .map(|c| UnicodeWidthStr::width(c.to_string().as_str()))
-        .sum::<usize>();
-
-    let cursor_display_pos = prefix_width as u16;
-    let input_display_width = text_input_rect.width;
-    let scroll_offset = if cursor_display_pos >= input_display_width {
-        cursor_display_pos - input_display_width + 1
-    } else {
-        0
-    };
-
-    let input_paragraph = Paragraph::new(input_text)
-        .style(theme.text_normal())
-        .scroll((0, scroll_offset));
-    frame.render_widget(input_paragraph, text_input_rect);
-
-    if is_focused {
-        frame.set_cursor_position((
-            text_input_rect.x + cursor_display_pos - scroll_offset,
-            text_input_rect.y,
-        ));
-    }
-
-    // Validation message handled by block title now
+fn render_details_section(frame: &mut Frame<'_>, add_new: &AddNewView, area: Rect, theme: &Theme) {
+    let [description_area, tags_area] =
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(area);
+
+    utils::render_input_field(
+        frame,
+        description_area,
+        add_new.description_input(),
+        "Description",
+        add_new.current_focus() == AddNewFocus::Description,
+        theme,
+    );
+    utils::render_input_field(
+        frame,
+        tags_area,
+        add_new.tags_input(),
+        "Tags (comma-separated)",
+        add_new.current_focus() == AddNewFocus::Tags,
+        theme,
+    );
 }
 
 fn render_profiles_section(frame: &mut Frame, app: &App, area: Rect, theme: &Theme) {
@@ -786,7 +871,7 @@ fn render_profiles_section(frame: &mut Frame, app: &App, area: Rect, theme: &The
     let available_profiles: Vec<_> = app
         .list_view
         .all_profiles()
-        .iter()
+        .into_iter()
         .filter(|name| **name != add_new.name_input().text() && *name != GLOBAL_PROFILE_MARK)
         .collect();
     let total_profiles = available_profiles.len();
@@ -803,6 +888,13 @@ fn render_profiles_section(frame: &mut Frame, app: &App, area: Rect, theme: &The
 
     let right_title =
         Line::from(format!("Selected: {}", add_new.added_profiles().len())).right_aligned();
+    let cycle_title = add_new.cycle().map(|path| {
+        Line::styled(
+            format!(" Cycle: {} ", path.join(" -> ")),
+            theme.text_error(),
+        )
+        .left_aligned()
+    });
 
     let border_style = if is_focused {
         theme.block_active()
@@ -810,11 +902,14 @@ fn render_profiles_section(frame: &mut Frame, app: &App, area: Rect, theme: &The
         theme.block_inactive()
     };
 
-    let profiles_block = Block::default()
+    let mut profiles_block = Block::default()
         .title_top(left_title)
         .title_top(right_title)
         .borders(Borders::ALL)
         .border_style(border_style);
+    if let Some(cycle_title) = cycle_title {
+        profiles_block = profiles_block.title_bottom(cycle_title);
+    }
 
     // Calculate actual visible height for profiles
     let profiles_inner_height = area.height.saturating_sub(2) as usize; // Remove borders
@@ -830,7 +925,12 @@ fn render_profiles_section(frame: &mut Frame, app: &App, area: Rect, theme: &The
         .map(|name| {
             let is_selected = add_new.is_profile_added(name);
             let prefix = if is_selected { "[✓] " } else { "[ ] " };
-            ListItem::new(format!("{prefix}{name}"))
+            let item = ListItem::new(format!("{prefix}{name}"));
+            if add_new.closes_cycle(name) {
+                item.style(theme.text_error())
+            } else {
+                item
+            }
         })
         .collect();
 
@@ -1001,12 +1101,15 @@ fn render_variables_section(frame: &mut Frame, app: &App, area: Rect, theme: &Th
         let column_chunks = layout.split(table_inner_area);
         let cell_area = column_chunks[col_index];
 
-        let popup_area = Rect {
-            x: cell_area.x.saturating_sub(1),
-            y: row_y.saturating_sub(1),
-            width: cell_area.width + 2,
-            height: 3,
-        };
+        let popup_area = utils::clamp_rect(
+            Rect {
+                x: cell_area.x.saturating_sub(1),
+                y: row_y.saturating_sub(1),
+                width: cell_area.width + 2,
+                height: 3,
+            },
+            frame.area(),
+        );
 
         let title = match add_new.variable_column_focus() {
             AddNewVariableFocus::Key => "Edit Variable",
@@ -1046,15 +1149,7 @@ fn render_variable_input_popup(
     let inner_area = block.inner(area);
 
     let text = input.text();
-    let cursor_pos = input.cursor_position();
-
-    let prefix_width = text
-        .chars()
-        .take(cursor_pos)
-        .map(|c| UnicodeWidthStr::width(c.to_string().as_str()))
-        .sum::<usize>();
-
-    let cursor_display_pos = prefix_width as u16;
+    let cursor_display_pos = input.cursor_display_width() as u16;
     let scroll_offset = if cursor_display_pos >= inner_area.width {
         cursor_display_pos - inner_area.width + 1
     } else {
@@ -1073,152 +1168,119 @@ fn render_variable_input_popup(
 
 fn render_help_section(frame: &mut Frame<'_>, app: &App, area: Rect) {
     match app.add_new_view.current_focus() {
-        AddNewFocus::Name => render_name_help(frame, area),
-        AddNewFocus::Profiles => render_profiles_help(frame, area),
-        AddNewFocus::Variables => render_variables_help(frame, app, area),
-    }
-}
-
-fn create_help_spans<'a>(help_info: &'a [Vec<Span<'a>>], area: Rect) -> Vec<Line<'a>> {
-    let total_width = area.width as usize;
-    let mut lines: Vec<Line> = vec![];
-    let mut current_line_spans: Vec<Span> = vec![];
-    let mut current_line_width = 0;
-
-    for info in help_info {
-        if lines.len() >= MAX_HELP_LINES {
-            break;
-        }
-        let item_width: usize = info.iter().map(|span| span.width()).sum();
-        let separator_width = if !current_line_spans.is_empty() { 2 } else { 0 };
-
-        if current_line_width + separator_width + item_width > total_width
-            && !current_line_spans.is_empty()
-        {
-            if lines.len() < MAX_HELP_LINES {
-                lines.push(Line::from(mem::take(&mut current_line_spans)));
-                current_line_width = 0;
-            } else {
-                break;
-            }
+        AddNewFocus::Name | AddNewFocus::Description | AddNewFocus::Tags => {
+            render_name_help(frame, area, &app.theme)
         }
-        if !current_line_spans.is_empty() {
-            current_line_spans.push(Span::raw("  "));
-            current_line_width += 2;
-        }
-        current_line_spans.extend_from_slice(info);
-        current_line_width += item_width;
-    }
-    if !current_line_spans.is_empty() && lines.len() < MAX_HELP_LINES {
-        lines.push(Line::from(current_line_spans));
+        AddNewFocus::Profiles => render_profiles_help(frame, area, &app.theme),
+        AddNewFocus::Variables => render_variables_help(frame, app, area),
     }
-    lines
 }
 
-fn render_name_help(frame: &mut Frame<'_>, area: Rect) {
+fn render_name_help(frame: &mut Frame<'_>, area: Rect, theme: &Theme) {
     let help_info = [
         vec![
-            Span::styled("Esc", Style::default().fg(Color::Rgb(255, 107, 107))),
+            Span::styled("Esc", theme.key(KeyRole::Cancel)),
             Span::raw(": Cancel"),
         ],
         vec![
-            Span::styled("Tab", Style::default().fg(Color::Rgb(130, 170, 255))),
+            Span::styled("Tab", theme.key(KeyRole::Focus)),
             Span::raw(": Focus"),
         ],
         vec![
-            Span::styled("←→", Style::default().fg(Color::Rgb(255, 138, 199))),
+            Span::styled("←→", theme.key(KeyRole::Navigate)),
             Span::raw(" : Move cursor"),
         ],
         vec![
-            Span::styled("Ctrl+s", Style::default().fg(Color::Rgb(106, 255, 160))),
+            Span::styled("Ctrl+s", theme.key(KeyRole::Confirm)),
             Span::raw(": Save"),
         ],
     ];
-    let lines = create_help_spans(&help_info, area);
+    let lines = utils::wrap_help_items(&help_info, area.width, MAX_HELP_LINES);
     let help_paragraph = Paragraph::new(lines).style(Style::default());
     frame.render_widget(help_paragraph, area);
 }
 
-fn render_profiles_help(frame: &mut Frame, area: Rect) {
+fn render_profiles_help(frame: &mut Frame, area: Rect, theme: &Theme) {
     let help_info = [
         vec![
-            Span::styled("Esc", Style::default().fg(Color::Rgb(255, 107, 107))),
+            Span::styled("Esc", theme.key(KeyRole::Cancel)),
             Span::raw(": Cancel"),
         ],
         vec![
-            Span::styled("Tab", Style::default().fg(Color::Rgb(130, 170, 255))),
+            Span::styled("Tab", theme.key(KeyRole::Focus)),
             Span::raw(": Focus"),
         ],
         vec![
-            Span::styled("↑↓", Style::default().fg(Color::Rgb(255, 138, 199))),
+            Span::styled("↑↓", theme.key(KeyRole::Navigate)),
             Span::raw(": Navigate"),
         ],
         vec![
-            Span::styled("Enter", Style::default().fg(Color::LightBlue)),
+            Span::styled("Enter", theme.key(KeyRole::Edit)),
             Span::raw("/"),
-            Span::styled("Space", Style::default().fg(Color::LightBlue)),
+            Span::styled("Space", theme.key(KeyRole::Edit)),
             Span::raw(": Toggle"),
         ],
         vec![
-            Span::styled("Ctrl+s", Style::default().fg(Color::Rgb(106, 255, 160))),
+            Span::styled("Ctrl+s", theme.key(KeyRole::Confirm)),
             Span::raw(": Save"),
         ],
     ];
-    let lines = create_help_spans(&help_info, area);
+    let lines = utils::wrap_help_items(&help_info, area.width, MAX_HELP_LINES);
     let help_paragraph = Paragraph::new(lines).style(Style::default());
     frame.render_widget(help_paragraph, area);
 }
 
 fn render_variables_help(frame: &mut Frame, app: &App, area: Rect) {
     let add_new = &app.add_new_view;
+    let theme = &app.theme;
     let help_info = if add_new.is_editing() {
         vec![
             vec![
-                Span::styled("Esc", Style::default().fg(Color::Rgb(255, 107, 107))),
+                Span::styled("Esc", theme.key(KeyRole::Cancel)),
                 Span::raw(": Cancel Edit"),
             ],
             vec![
-                Span::styled("Tab", Style::default().fg(Color::Rgb(130, 170, 255))),
+                Span::styled("Tab", theme.key(KeyRole::Focus)),
                 Span::raw(": Switch Field"),
             ],
             vec![
-                Span::styled("Enter", Style::default().fg(Color::Rgb(106, 255, 160))),
+                Span::styled("Enter", theme.key(KeyRole::Confirm)),
                 Span::raw(": Confirm"),
             ],
         ]
     } else {
         vec![
             vec![
-                Span::styled("Esc", Style::default().fg(Color::Rgb(255, 107, 107))),
+                Span::styled("Esc", theme.key(KeyRole::Cancel)),
                 Span::raw(": Cancel"),
             ],
             vec![
-                Span::styled("Tab", Style::default().fg(Color::Rgb(130, 170, 255))),
+                Span::styled("Tab", theme.key(KeyRole::Focus)),
                 Span::raw(": Focus"),
             ],
             vec![
-                Span::styled("↑↓←→", Style::default().fg(Color::Rgb(255, 138, 199))),
+                Span::styled("↑↓←→", theme.key(KeyRole::Navigate)),
                 Span::raw(" : Navigate"),
             ],
             vec![
-                Span::styled("a", Style::default().fg(Color::LightYellow)),
+                Span::styled("a", theme.key(KeyRole::Change)),
                 Span::raw(": Add"),
             ],
             vec![
-                Span::styled("d", Style::default().fg(Color::LightRed)),
+                Span::styled("d", theme.key(KeyRole::Delete)),
                 Span::raw(": Delete"),
             ],
             vec![
-                Span::styled("e", Style::default().fg(Color::LightBlue)),
+                Span::styled("e", theme.key(KeyRole::Edit)),
                 Span::raw(": Edit"),
             ],
             vec![
-                Span::styled("Ctrl+s", Style::default().fg(Color::Rgb(106, 255, 160))),
+                Span::styled("Ctrl+s", theme.key(KeyRole::Confirm)),
                 Span::raw(": Save"),
             ],
         ]
     };
-    let lines = create_help_spans(&help_info, area);
+    let lines = utils::wrap_help_items(&help_info, area.width, MAX_HELP_LINES);
     let help_paragraph = Paragraph::new(lines).style(Style::default());
     frame.render_widget(help_paragraph, area);
 }
@@ -1,19 +1,24 @@
 use crate::GLOBAL_PROFILE_MARK;
-use crate::config::models::Profile;
-use crate::tui::app::{App, AppState};
-use crate::tui::theme::Theme;
-use crate::tui::utils::{self, Input, validate_input};
+use crate::config::models::{HookKind, Hooks, KeyValidation, Profile};
+use crate::tui::app::{App, AppState, FullLoadAction};
+use crate::tui::theme::{KeyRole, Theme};
+use crate::tui::utils::{self, Input, TextArea, validate_key_input, validate_tags_input};
 use crate::tui::widgets::empty;
-use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use crate::utils::parse_tags;
+use indexmap::IndexMap;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::layout::{Constraint, Layout, Margin, Rect};
 use ratatui::prelude::*;
 use ratatui::widgets::{
     Block, Borders, Cell, Clear, List, ListItem, ListState, Paragraph, Row, Scrollbar,
     ScrollbarOrientation, ScrollbarState, Table, TableState,
 };
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use unicode_width::UnicodeWidthStr;
 
+/// How many hook commands the edit view shows before cutting the list short
+const MAX_HOOK_ROWS: usize = 4;
+
 // ==================================================================================
 // STATE
 // ==================================================================================
@@ -23,6 +28,8 @@ pub enum EditFocus {
     #[default]
     Variables,
     Profiles,
+    Description,
+    Tags,
 }
 
 #[derive(Default, Debug, PartialEq, Eq, Clone, Copy)]
@@ -32,33 +39,100 @@ pub enum EditVariableFocus {
     Value,
 }
 
+/// Why the dependency selector lists an option before the unrelated ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relevance {
+    /// Another dependency of a profile that depends on the edited one
+    Sibling,
+    /// Recently opened in the editor
+    Recent,
+}
+
+impl Relevance {
+    pub fn label(self) -> &'static str {
+        match self {
+            Relevance::Sibling => "sibling",
+            Relevance::Recent => "recent",
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct DependencySelector {
+    /// Most relevant first
     options: Vec<String>,
+    relevance: HashMap<String, Relevance>,
+    /// Typed text narrowing `options` by substring
+    query: String,
+    /// Indices into `options` matching `query`, in order
+    visible: Vec<usize>,
     current_index: usize,
-    selected_indices: HashSet<usize>,
+    /// Selected profile names, kept across filter changes
+    selected: HashSet<String>,
 }
 
 impl DependencySelector {
     fn new() -> Self {
-        Self {
-            options: Vec::new(),
-            current_index: 0,
-            selected_indices: HashSet::new(),
-        }
+        Self::default()
     }
 
     fn reset(&mut self) {
         self.options.clear();
+        self.relevance.clear();
+        self.query.clear();
+        self.visible.clear();
         self.current_index = 0;
-        self.selected_indices.clear();
+        self.selected.clear();
+    }
+
+    fn set_options(&mut self, options: Vec<String>, relevance: HashMap<String, Relevance>) {
+        self.options = options;
+        self.relevance = relevance;
+        self.apply_filter();
+    }
+
+    fn push_query(&mut self, c: char) {
+        self.query.push(c);
+        self.apply_filter();
+    }
+
+    fn pop_query(&mut self) {
+        if self.query.pop().is_some() {
+            self.apply_filter();
+        }
+    }
+
+    /// Recompute the visible options, keeping the cursor on the same profile when it still matches
+    fn apply_filter(&mut self) {
+        let current = self.current_option().map(str::to_string);
+        let query = self.query.to_lowercase();
+        self.visible = self
+            .options
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| name.to_lowercase().contains(&query))
+            .map(|(i, _)| i)
+            .collect();
+        self.current_index = current
+            .and_then(|name| self.visible_options().position(|option| option == name))
+            .unwrap_or(0);
+    }
+
+    fn visible_options(&self) -> impl Iterator<Item = &str> {
+        self.visible.iter().map(|&i| self.options[i].as_str())
+    }
+
+    fn current_option(&self) -> Option<&str> {
+        self.visible
+            .get(self.current_index)
+            .map(|&i| self.options[i].as_str())
     }
 
     fn select_next(&mut self) {
-        if self.options.is_empty() {
+        if self.visible.is_empty() {
             return;
         }
-        if self.current_index < self.options.len() - 1 {
+        if self.current_index < self.visible.len() - 1 {
             self.current_index += 1;
         } else {
             self.current_index = 0;
@@ -66,40 +140,44 @@ impl DependencySelector {
     }
 
     fn select_previous(&mut self) {
-        if self.options.is_empty() {
+        if self.visible.is_empty() {
             return;
         }
         if self.current_index > 0 {
             self.current_index -= 1;
         } else {
-            self.current_index = self.options.len() - 1;
+            self.current_index = self.visible.len() - 1;
         }
     }
 
     fn toggle_selection(&mut self) {
-        if self.options.is_empty() {
+        let Some(name) = self.current_option().map(str::to_string) else {
             return;
-        }
+        };
 
-        if self.selected_indices.contains(&self.current_index) {
-            self.selected_indices.remove(&self.current_index);
-        } else {
-            self.selected_indices.insert(self.current_index);
+        if !self.selected.remove(&name) {
+            self.selected.insert(name);
         }
     }
 
+    /// Selected names in option order, including ones hidden by the current filter
     fn get_selected_items(&self) -> Vec<String> {
-        let mut indices: Vec<_> = self.selected_indices.iter().cloned().collect();
-        indices.sort();
-        indices.iter().map(|&i| self.options[i].clone()).collect()
+        self.options
+            .iter()
+            .filter(|name| self.selected.contains(*name))
+            .cloned()
+            .collect()
     }
 }
 
 pub struct DependencySelectorState<'a> {
     pub title: &'static str,
-    pub options: &'a [String],
+    pub query: &'a str,
+    /// Options matching `query`, most relevant first
+    pub options: Vec<&'a str>,
+    pub relevance: &'a HashMap<String, Relevance>,
     pub current_index: usize,
-    pub selected_indices: &'a HashSet<usize>,
+    pub selected: &'a HashSet<String>,
 }
 
 pub struct VariableInputState<'a> {
@@ -117,27 +195,46 @@ pub struct EditView {
 
     // Variables section
     variables: Vec<(Input, Input)>,
+    /// Whether each row of `variables` unsets its key instead of setting it
+    unset_rows: Vec<bool>,
     selected_variable_index: usize,
     variable_scroll_offset: usize,
     variable_column_focus: EditVariableFocus,
     is_editing_variable: bool,
     pre_edit_buffer: Option<String>,
+    /// The multi-line editor open on the selected value, if any
+    value_editor: Option<TextArea>,
 
     // Profiles (dependencies) section
     profiles: Vec<String>,
+    /// Dependencies skipped when resolving, shown struck through
+    disabled_profiles: HashSet<String>,
     selected_profile_index: usize,
     profile_scroll_offset: usize,
 
     // Profile name (for display)
     profile_name: String,
 
+    // Metadata section
+    description: Input,
+    tags: Input,
+    /// Hook commands, shown but not editable here
+    hooks: Hooks,
+    /// The profile's own key validation, written back on save
+    validation: Option<KeyValidation>,
+    /// Key validation used when the profile does not set one
+    default_validation: KeyValidation,
+
     // Dependency selector
     dependency_selector: DependencySelector,
     show_dependency_selector: bool,
 
     // Original state for change detection
     original_variables: Vec<(String, String)>,
+    original_unset_rows: Vec<bool>,
     original_profiles: Vec<String>,
+    original_description: String,
+    original_tags: String,
 }
 
 impl EditView {
@@ -147,26 +244,40 @@ impl EditView {
 
     pub fn reset(&mut self) {
         self.variables.clear();
+        self.unset_rows.clear();
         self.pre_edit_buffer.take();
+        self.value_editor = None;
         self.profiles.clear();
+        self.disabled_profiles.clear();
         self.profile_name.clear();
+        self.description.reset();
+        self.tags.reset();
+        self.hooks = Hooks::default();
         self.dependency_selector.reset();
         self.original_profiles.clear();
         self.original_variables.clear();
+        self.original_unset_rows.clear();
     }
 
     pub fn from_profile(name: &str, profile: &Profile) -> Self {
-        // Convert map to vec for editable inputs
-        let mut variables: Vec<(Input, Input)> = profile
+        // Convert map to vec for editable inputs in file order, followed by the unset
+        // keys as rows without a value
+        let mut unset: Vec<&String> = profile.unset.iter().collect();
+        unset.sort();
+        let (variables, unset_rows): (Vec<(Input, Input)>, Vec<bool>) = profile
             .variables
             .iter()
             .map(|(k, v)| {
                 let k_in = Input::with_text(k.clone());
                 let v_in = Input::with_text(v.clone());
-                (k_in, v_in)
+                ((k_in, v_in), false)
             })
-            .collect();
-        variables.sort_by(|a, b| a.0.text().cmp(b.0.text()));
+            .chain(
+                unset
+                    .into_iter()
+                    .map(|k| ((Input::with_text(k.clone()), Input::default()), true)),
+            )
+            .unzip();
 
         let mut profiles: Vec<String> = profile.profiles.iter().cloned().collect();
         profiles.sort();
@@ -177,40 +288,94 @@ impl EditView {
             .map(|(k, v)| (k.text().to_string(), v.text().to_string()))
             .collect();
         let original_profiles = profiles.clone();
+        let original_description = profile.description.clone().unwrap_or_default();
+        let original_tags = profile.tags.join(", ");
 
         Self {
             focus: EditFocus::Variables,
             variables,
+            original_unset_rows: unset_rows.clone(),
+            unset_rows,
             selected_variable_index: 0,
             variable_scroll_offset: 0,
             variable_column_focus: EditVariableFocus::Key,
             is_editing_variable: false,
             pre_edit_buffer: None,
+            value_editor: None,
             profiles,
+            disabled_profiles: profile.disabled_profiles.clone(),
             selected_profile_index: 0,
             profile_scroll_offset: 0,
             profile_name: name.to_string(),
+            description: Input::with_text(original_description.clone()),
+            tags: Input::with_text(original_tags.clone()),
+            hooks: profile.hooks(),
+            validation: profile.validation,
+            default_validation: KeyValidation::default(),
             dependency_selector: DependencySelector::new(),
             show_dependency_selector: false,
             original_variables,
             original_profiles,
+            original_description,
+            original_tags,
         }
     }
 
     pub fn to_profile(&self) -> Profile {
-        let mut variables_map = std::collections::HashMap::new();
-        for (k, v) in &self.variables {
-            if !k.text().is_empty() {
+        let mut variables_map = IndexMap::new();
+        let mut unset = HashSet::new();
+        for ((k, v), &is_unset) in self.variables.iter().zip(&self.unset_rows) {
+            if k.text().is_empty() {
+                continue;
+            }
+            if is_unset {
+                unset.insert(k.text().to_string());
+            } else {
                 variables_map.insert(k.text().to_string(), v.text().to_string());
             }
         }
 
+        let description = self.description.text().trim();
+
         Profile {
+            description: (!description.is_empty()).then(|| description.to_string()),
+            tags: parse_tags(self.tags.text()),
             variables: variables_map,
-            profiles: self.profiles.iter().cloned().collect(),
+            // A profile cannot depend on itself; the set drops duplicates
+            profiles: self
+                .profiles
+                .iter()
+                .filter(|name| **name != self.profile_name)
+                .cloned()
+                .collect(),
+            disabled_profiles: self
+                .disabled_profiles
+                .iter()
+                .filter(|name| self.profiles.contains(name))
+                .cloned()
+                .collect(),
+            unset,
+            validation: self.validation,
+            on_activate: self.hooks.on_activate.clone(),
+            on_deactivate: self.hooks.on_deactivate.clone(),
+            pre_activate: self.hooks.pre_activate.clone(),
+            post_activate: self.hooks.post_activate.clone(),
         }
     }
 
+    pub fn hooks(&self) -> &Hooks {
+        &self.hooks
+    }
+
+    /// Use `default` for keys when the profile does not set its own validation
+    pub fn set_default_validation(&mut self, default: KeyValidation) {
+        self.default_validation = default;
+    }
+
+    pub fn key_validation(&self) -> KeyValidation {
+        self.validation.unwrap_or(self.default_validation)
+    }
+
     pub fn current_focus(&self) -> EditFocus {
         self.focus
     }
@@ -239,10 +404,42 @@ impl EditView {
             }
         }
 
+        if self.unset_rows != self.original_unset_rows {
+            return true;
+        }
+
+        // Check if metadata changed
+        if self.description.text() != self.original_description
+            || self.tags.text() != self.original_tags
+        {
+            return true;
+        }
+
         // Check if profiles changed
         self.profiles != self.original_profiles
     }
 
+    pub fn description_input(&self) -> &Input {
+        &self.description
+    }
+
+    pub fn tags_input(&self) -> &Input {
+        &self.tags
+    }
+
+    /// Whether focus is on one of the metadata text fields
+    pub fn is_metadata_focused(&self) -> bool {
+        matches!(self.focus, EditFocus::Description | EditFocus::Tags)
+    }
+
+    pub fn focused_metadata_input_mut(&mut self) -> Option<&mut Input> {
+        match self.focus {
+            EditFocus::Description => Some(&mut self.description),
+            EditFocus::Tags => Some(&mut self.tags),
+            EditFocus::Variables | EditFocus::Profiles => None,
+        }
+    }
+
     /// Get iterator over variables (key, value) pairs for rendering
     pub fn variables(&self) -> impl Iterator<Item = (&str, &str)> {
         self.variables.iter().map(|(k, v)| (k.text(), v.text()))
@@ -288,8 +485,23 @@ impl EditView {
         &self.variables
     }
 
+    /// Whether the variable at index unsets its key
+    pub fn is_unset(&self, index: usize) -> bool {
+        self.unset_rows.get(index).copied().unwrap_or(false)
+    }
+
+    /// Switch the selected variable between setting and unsetting its key.
+    ///
+    /// The value is kept while the row is unset, so toggling back restores it.
+    pub fn toggle_unset(&mut self) {
+        if let Some(unset) = self.unset_rows.get_mut(self.selected_variable_index) {
+            *unset = !*unset;
+        }
+    }
+
     pub fn add_variable(&mut self) {
         self.variables.push((Input::default(), Input::default()));
+        self.unset_rows.push(false);
         self.selected_variable_index = self.variables.len() - 1;
         self.ensure_variable_visible();
         self.variable_column_focus = EditVariableFocus::Key;
@@ -299,6 +511,7 @@ impl EditView {
     pub fn delete_variable(&mut self) {
         if !self.variables.is_empty() && self.selected_variable_index < self.variables.len() {
             self.variables.remove(self.selected_variable_index);
+            self.unset_rows.remove(self.selected_variable_index);
             if self.selected_variable_index >= self.variables.len() && !self.variables.is_empty() {
                 self.selected_variable_index = self.variables.len() - 1;
             } else if self.variables.is_empty() {
@@ -368,6 +581,22 @@ impl EditView {
         if self.variables.is_empty() {
             return;
         }
+        // Unset rows have no value to edit
+        if self.variable_column_focus == EditVariableFocus::Value
+            && self.is_unset(self.selected_variable_index)
+        {
+            return;
+        }
+        // A single-line input would show every line of the value run together
+        if self.variable_column_focus == EditVariableFocus::Value
+            && self.variables[self.selected_variable_index]
+                .1
+                .text()
+                .contains('\n')
+        {
+            self.open_value_editor();
+            return;
+        }
 
         self.is_editing_variable = true;
         let (k, v) = &self.variables[self.selected_variable_index];
@@ -393,6 +622,41 @@ impl EditView {
         }
     }
 
+    /// Open the multi-line editor on the value of the selected variable
+    pub fn open_value_editor(&mut self) {
+        let index = self.selected_variable_index;
+        if index >= self.variables.len() || self.is_unset(index) {
+            return;
+        }
+        self.variable_column_focus = EditVariableFocus::Value;
+        self.value_editor = Some(TextArea::with_text(self.variables[index].1.text()));
+    }
+
+    pub fn is_value_editor_open(&self) -> bool {
+        self.value_editor.is_some()
+    }
+
+    pub fn value_editor(&self) -> Option<&TextArea> {
+        self.value_editor.as_ref()
+    }
+
+    pub fn value_editor_mut(&mut self) -> Option<&mut TextArea> {
+        self.value_editor.as_mut()
+    }
+
+    /// Store the multi-line editor's text as the selected value and close it
+    pub fn confirm_value_editor(&mut self) {
+        if let Some(editor) = self.value_editor.take()
+            && let Some((_, value)) = self.variables.get_mut(self.selected_variable_index)
+        {
+            value.set_text(editor.text());
+        }
+    }
+
+    pub fn cancel_value_editor(&mut self) {
+        self.value_editor = None;
+    }
+
     pub fn get_focused_variable_input_mut(&mut self) -> Option<&mut Input> {
         if self.selected_variable_index < self.variables.len() {
             let (k, v) = &mut self.variables[self.selected_variable_index];
@@ -420,6 +684,13 @@ impl EditView {
         }
     }
 
+    /// Number of variable rows that would be dropped when saving
+    pub fn invalid_variable_count(&self) -> usize {
+        (0..self.variables.len())
+            .filter(|&index| !self.is_variable_valid(index))
+            .count()
+    }
+
     pub fn profiles(&self) -> &[String] {
         &self.profiles
     }
@@ -428,6 +699,10 @@ impl EditView {
         self.profiles.len()
     }
 
+    pub fn is_profile_disabled(&self, name: &str) -> bool {
+        self.disabled_profiles.contains(name)
+    }
+
     pub fn selected_profile_index(&self) -> usize {
         self.selected_profile_index
     }
@@ -445,7 +720,8 @@ impl EditView {
 
     pub fn remove_profile_dependency(&mut self) {
         if !self.profiles.is_empty() && self.selected_profile_index < self.profiles.len() {
-            self.profiles.remove(self.selected_profile_index);
+            let removed = self.profiles.remove(self.selected_profile_index);
+            self.disabled_profiles.remove(&removed);
             if self.selected_profile_index >= self.profiles.len() && !self.profiles.is_empty() {
                 self.selected_profile_index = self.profiles.len() - 1;
             } else if self.profiles.is_empty() {
@@ -506,7 +782,9 @@ impl EditView {
     pub fn switch_focus(&mut self) {
         self.focus = match self.focus {
             EditFocus::Variables => EditFocus::Profiles,
-            EditFocus::Profiles => EditFocus::Variables,
+            EditFocus::Profiles => EditFocus::Description,
+            EditFocus::Description => EditFocus::Tags,
+            EditFocus::Tags => EditFocus::Variables,
         };
     }
 
@@ -521,19 +799,26 @@ impl EditView {
 
         Some(DependencySelectorState {
             title: "Add Dependency",
-            options: &self.dependency_selector.options,
+            query: &self.dependency_selector.query,
+            options: self.dependency_selector.visible_options().collect(),
+            relevance: &self.dependency_selector.relevance,
             current_index: self.dependency_selector.current_index,
-            selected_indices: &self.dependency_selector.selected_indices,
+            selected: &self.dependency_selector.selected,
         })
     }
 
-    pub fn open_dependency_selector(&mut self, available: Vec<String>) {
+    /// Open the selector on `available`, which is in the order to list it
+    pub fn open_dependency_selector(
+        &mut self,
+        available: Vec<String>,
+        relevance: HashMap<String, Relevance>,
+    ) {
         if self.focus != EditFocus::Profiles {
             return;
         }
 
         self.dependency_selector.reset();
-        self.dependency_selector.options = available;
+        self.dependency_selector.set_options(available, relevance);
         self.show_dependency_selector = true;
     }
 
@@ -542,7 +827,9 @@ impl EditView {
         self.dependency_selector.reset();
     }
 
-    /// Handle input for dependency selector, returns selected items if Esc pressed to confirm
+    /// Handle input for dependency selector, returns selected items if Esc pressed to confirm.
+    ///
+    /// Other characters filter the options; `j`/`k` navigate until a filter has been typed.
     pub fn handle_selector_input(&mut self, key: KeyEvent) -> Option<Vec<String>> {
         if !self.show_dependency_selector {
             return None;
@@ -554,11 +841,19 @@ impl EditView {
                 self.close_dependency_selector();
                 Some(selected)
             }
-            KeyCode::Up | KeyCode::Char('k') => {
+            KeyCode::Up => {
+                self.dependency_selector.select_previous();
+                None
+            }
+            KeyCode::Down => {
+                self.dependency_selector.select_next();
+                None
+            }
+            KeyCode::Char('k') if self.dependency_selector.query.is_empty() => {
                 self.dependency_selector.select_previous();
                 None
             }
-            KeyCode::Down | KeyCode::Char('j') => {
+            KeyCode::Char('j') if self.dependency_selector.query.is_empty() => {
                 self.dependency_selector.select_next();
                 None
             }
@@ -566,6 +861,14 @@ impl EditView {
                 self.dependency_selector.toggle_selection();
                 None
             }
+            KeyCode::Backspace => {
+                self.dependency_selector.pop_query();
+                None
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.dependency_selector.push_query(c);
+                None
+            }
             _ => None,
         }
     }
@@ -581,8 +884,12 @@ pub fn handle_event(app: &mut App, key: KeyEvent) {
         return;
     }
 
-    if app.edit_view.is_editing() {
+    if app.edit_view.is_value_editor_open() {
+        handle_value_editor(app, key);
+    } else if app.edit_view.is_editing() {
         handle_variable_editing_mode(app, key);
+    } else if app.edit_view.is_metadata_focused() {
+        handle_metadata_input(app, key);
     } else {
         handle_navigation_mode(app, key);
     }
@@ -613,7 +920,7 @@ fn add_dependencies_to_profile(app: &mut App, dep_names: Vec<String>) {
                 }
                 Err(e) => {
                     // Failed: show error, don't update UI
-                    app.status_message = Some(format!("Cannot add dependency '{dep_name}': {e}"));
+                    app.show_error(format!("Cannot add dependency '{dep_name}': {e}"));
                 }
             }
         }
@@ -629,28 +936,33 @@ fn remove_dependency_from_profile(app: &mut App) {
         app.edit_view.remove_profile_dependency();
     } else if let Some(removed_dep) = app.edit_view.profiles().get(selected_idx) {
         let removed_dep = removed_dep.clone();
+        let was_disabled = app.edit_view.is_profile_disabled(&removed_dep);
 
         // Update UI component
         app.edit_view.remove_profile_dependency();
 
-        // Update graph immediately (incremental)
-        if let Err(e) = app
-            .config_manager
-            .remove_dependency_edge(&profile_name, &removed_dep)
+        // Update graph immediately (incremental); disabled dependencies have no edge
+        if !was_disabled
+            && let Err(e) = app
+                .config_manager
+                .remove_dependency_edge(&profile_name, &removed_dep)
         {
-            app.status_message = Some(format!("Failed to remove dependency: {e}"));
+            app.show_error(format!("Failed to remove dependency: {e}"));
         }
     }
 
     mark_profile_as_dirty_if_changed(app);
 }
 
-fn open_dependency_selector_handler(app: &mut App) {
+pub fn open_dependency_selector_handler(app: &mut App) {
+    if app.refuse_if_read_only() {
+        return;
+    }
     let current_profile = app.edit_view.profile_name();
     let existing_deps = app.edit_view.profiles();
 
     // Get profiles that depend on current (would create cycle)
-    let ancestors: std::collections::HashSet<String> = app
+    let ancestors: HashSet<String> = app
         .config_manager
         .get_parents(current_profile)
         .unwrap_or_default()
@@ -658,10 +970,10 @@ fn open_dependency_selector_handler(app: &mut App) {
         .collect();
 
     // Filter available profiles
-    let available: Vec<String> = app
+    let mut available: Vec<String> = app
         .list_view
         .all_profiles()
-        .iter()
+        .into_iter()
         .filter(|p| {
             let name = p.as_str();
             name != current_profile           // Exclude self
@@ -672,7 +984,35 @@ fn open_dependency_selector_handler(app: &mut App) {
         .cloned()
         .collect();
 
-    app.edit_view.open_dependency_selector(available);
+    // Siblings, then recently edited profiles, then the rest, each by name
+    let siblings: HashSet<&String> = ancestors
+        .iter()
+        .filter_map(|parent| app.config_manager.get_profile(parent))
+        .flat_map(|parent| parent.profiles.iter())
+        .collect();
+    let recent = app.recent.names();
+    let relevance: HashMap<String, Relevance> = available
+        .iter()
+        .filter_map(|name| {
+            let relevance = if siblings.contains(name) {
+                Relevance::Sibling
+            } else if recent.contains(name) {
+                Relevance::Recent
+            } else {
+                return None;
+            };
+            Some((name.clone(), relevance))
+        })
+        .collect();
+    available.sort_by_cached_key(|name| {
+        (
+            !siblings.contains(name),
+            recent.iter().position(|r| r == name).unwrap_or(usize::MAX),
+            name.clone(),
+        )
+    });
+
+    app.edit_view.open_dependency_selector(available, relevance);
 }
 
 fn handle_variable_editing_mode(app: &mut App, key: KeyEvent) {
@@ -684,6 +1024,34 @@ fn handle_variable_editing_mode(app: &mut App, key: KeyEvent) {
     }
 }
 
+fn handle_value_editor(app: &mut App, key: KeyEvent) {
+    if key.code == KeyCode::Char('s') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        app.edit_view.confirm_value_editor();
+        mark_profile_as_dirty_if_changed(app);
+        return;
+    }
+    if key.code == KeyCode::Esc {
+        app.edit_view.cancel_value_editor();
+        return;
+    }
+
+    let Some(editor) = app.edit_view.value_editor_mut() else {
+        return;
+    };
+    match key.code {
+        KeyCode::Enter => editor.insert_newline(),
+        KeyCode::Char(c) => editor.enter_char(c),
+        KeyCode::Backspace => editor.delete_char(),
+        KeyCode::Left => editor.move_cursor_left(),
+        KeyCode::Right => editor.move_cursor_right(),
+        KeyCode::Up => editor.move_cursor_up(),
+        KeyCode::Down => editor.move_cursor_down(),
+        KeyCode::Home => editor.move_cursor_home(),
+        KeyCode::End => editor.move_cursor_end(),
+        _ => {}
+    }
+}
+
 fn confirm_and_maybe_switch_column(app: &mut App) {
     let should_switch = {
         let edit = &mut app.edit_view;
@@ -759,30 +1127,88 @@ fn handle_text_input(app: &mut App, key_code: KeyCode) {
     }
 }
 
+fn handle_metadata_input(app: &mut App, key: KeyEvent) {
+    let is_tags = app.edit_view.current_focus() == EditFocus::Tags;
+
+    match key.code {
+        KeyCode::Esc | KeyCode::Tab | KeyCode::Enter => {
+            // Invalid tags keep the focus until they are fixed
+            if is_tags && !validate_tags_input(&mut app.edit_view.tags) {
+                return;
+            }
+            if key.code == KeyCode::Esc {
+                exit_edit_mode(app);
+            } else {
+                app.edit_view.switch_focus();
+            }
+        }
+        code => {
+            if let Some(input) = app.edit_view.focused_metadata_input_mut() {
+                match code {
+                    KeyCode::Char(c) => input.enter_char(c),
+                    KeyCode::Backspace => input.delete_char(),
+                    KeyCode::Left => input.move_cursor_left(),
+                    KeyCode::Right => input.move_cursor_right(),
+                    _ => return,
+                }
+            }
+            if is_tags {
+                validate_tags_input(&mut app.edit_view.tags);
+            }
+            mark_profile_as_dirty_if_changed(app);
+        }
+    }
+}
+
 fn handle_navigation_mode(app: &mut App, key: KeyEvent) {
+    if app.count_prefix.push_key(key.code) {
+        return;
+    }
+    let count = app.count_prefix.take();
     match key.code {
         KeyCode::Esc => exit_edit_mode(app),
         KeyCode::Tab => app.edit_view.switch_focus(),
 
         // Navigation
-        KeyCode::Char('j') | KeyCode::Down => navigate_down(app),
-        KeyCode::Char('k') | KeyCode::Up => navigate_up(app),
+        KeyCode::Char('j') | KeyCode::Down => (0..count).for_each(|_| navigate_down(app)),
+        KeyCode::Char('k') | KeyCode::Up => (0..count).for_each(|_| navigate_up(app)),
         KeyCode::Left | KeyCode::Right => switch_column_if_in_variables(app),
 
         // Actions
         KeyCode::Char('a') => add_variable_if_in_variables(app),
         KeyCode::Char('d') => delete_current_item(app),
         KeyCode::Char('e') => start_editing_variable_if_in_variables(app),
+        KeyCode::Char('E') => open_value_editor_if_in_variables(app),
+        KeyCode::Char('u') => toggle_unset_if_in_variables(app),
         KeyCode::Char('n') => open_dependency_selector_if_in_profiles(app),
+        KeyCode::Enter => open_dependency_if_in_profiles(app),
 
         _ => {}
     }
 }
 
 fn exit_edit_mode(app: &mut App) {
+    // Rows with invalid keys are dropped on save, so ask before losing them
+    if app.list_view.is_dirty(app.edit_view.profile_name())
+        && app.edit_view.invalid_variable_count() > 0
+    {
+        app.state = AppState::ConfirmDropInvalid;
+        return;
+    }
+    finish_editing(app);
+}
+
+/// Save the edited profile to memory and go back to the list
+pub fn finish_editing(app: &mut App) {
     // Save profile if there are changes
     if app.list_view.is_dirty(app.edit_view.profile_name()) {
         save_profile_to_memory(app);
+        app.refresh_profile_list();
+    }
+    if let Some(previous) = app.edit_stack.pop() {
+        app.edit_view = previous;
+        app.state = AppState::Edit;
+        return;
     }
     app.state = AppState::List;
     app.edit_view.reset();
@@ -792,6 +1218,7 @@ fn navigate_down(app: &mut App) {
     match app.edit_view.current_focus() {
         EditFocus::Variables => app.edit_view.select_next_variable(),
         EditFocus::Profiles => app.edit_view.select_next_profile(),
+        EditFocus::Description | EditFocus::Tags => {}
     }
 }
 
@@ -799,6 +1226,7 @@ fn navigate_up(app: &mut App) {
     match app.edit_view.current_focus() {
         EditFocus::Variables => app.edit_view.select_previous_variable(),
         EditFocus::Profiles => app.edit_view.select_previous_profile(),
+        EditFocus::Description | EditFocus::Tags => {}
     }
 }
 
@@ -824,6 +1252,7 @@ fn delete_current_item(app: &mut App) {
         EditFocus::Profiles => {
             remove_dependency_from_profile(app);
         }
+        EditFocus::Description | EditFocus::Tags => {}
     }
 }
 
@@ -833,17 +1262,68 @@ fn start_editing_variable_if_in_variables(app: &mut App) {
     }
 }
 
+fn open_value_editor_if_in_variables(app: &mut App) {
+    if app.edit_view.current_focus() == EditFocus::Variables {
+        app.edit_view.open_value_editor();
+    }
+}
+
+fn toggle_unset_if_in_variables(app: &mut App) {
+    if app.edit_view.current_focus() == EditFocus::Variables {
+        app.edit_view.toggle_unset();
+        mark_profile_as_dirty_if_changed(app);
+    }
+}
+
 fn open_dependency_selector_if_in_profiles(app: &mut App) {
     if app.edit_view.current_focus() == EditFocus::Profiles {
-        open_dependency_selector_handler(app);
+        app.with_all_profiles(FullLoadAction::DependencySelector);
+    }
+}
+
+/// Save the edited profile to memory and edit the selected dependency instead,
+/// keeping the current view to return to
+fn open_dependency_if_in_profiles(app: &mut App) {
+    if app.edit_view.current_focus() != EditFocus::Profiles {
+        return;
+    }
+    let Some(dependency) = app
+        .edit_view
+        .profiles()
+        .get(app.edit_view.selected_profile_index())
+        .cloned()
+    else {
+        return;
+    };
+
+    let dirty = app.list_view.is_dirty(app.edit_view.profile_name());
+    if dirty && app.edit_view.invalid_variable_count() > 0 {
+        app.show_error(format!(
+            "Fix or delete the invalid variables before opening '{dependency}'"
+        ));
+        return;
+    }
+    if dirty {
+        save_profile_to_memory(app);
+        app.refresh_profile_list();
+    }
+
+    let previous = std::mem::take(&mut app.edit_view);
+    app.start_editing(&dependency);
+    if app.edit_view.profile_name() == dependency {
+        app.edit_stack.push(previous);
+    } else {
+        app.edit_view = previous;
+        app.state = AppState::Edit;
     }
 }
 
 /// Validate variable key (non-empty, no spaces, not start with digit)
 fn validate_variable_key(edit: &mut EditView) -> bool {
+    let validation = edit.key_validation();
     if let Some(input) = edit.get_focused_variable_input_mut() {
         input.clear_error();
-        validate_input(input)
+        validate_key_input(input, validation)
     } else {
         true
     }
@@ -866,7 +1346,7 @@ fn save_profile_to_memory(app: &mut App) {
 
     if name == GLOBAL_PROFILE_MARK {
         if let Err(e) = app.config_manager.write_global(&new_profile) {
-            app.status_message = Some(format!("Error saving GLOBAL: {}", e));
+            app.show_error(format!("Error saving GLOBAL: {}", e));
         } else {
             app.list_view.clear_dirty(&name);
         }
@@ -888,10 +1368,16 @@ fn mark_profile_as_dirty_if_changed(app: &mut App) {
 // ==================================================================================
 
 pub fn render(frame: &mut Frame, area: Rect, app: &App) {
-    let theme = Theme::new();
+    let theme = &app.theme;
     let edit = &app.edit_view;
     let profile_name = edit.profile_name();
-    let title = format!("Editing '{profile_name}'");
+    let title = match app.edit_stack.last() {
+        Some(previous) => format!(
+            "Editing '{profile_name}' (Esc: back to '{}')",
+            previous.profile_name()
+        ),
+        None => format!("Editing '{profile_name}'"),
+    };
 
     let main_block = Block::default()
         .borders(Borders::ALL)
@@ -906,15 +1392,48 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
     let inner_area = main_block.inner(area);
     frame.render_widget(main_block, area);
 
-    // Vertical Layout: Profiles Top (30%), Variables Bottom (70%)
+    // Vertical Layout: Metadata row, Profiles (30%), Variables (70%), Hooks when there are any
+    let hook_lines = edit.hooks().len();
+    let hooks_height = if hook_lines == 0 {
+        0
+    } else {
+        hook_lines.min(MAX_HOOK_ROWS) as u16 + 2
+    };
     let chunks = Layout::vertical([
-        Constraint::Percentage(30), // Inherited Profiles
-        Constraint::Percentage(70), // Variables
+        Constraint::Length(3),            // Description and tags
+        Constraint::Percentage(30),       // Inherited Profiles
+        Constraint::Percentage(70),       // Variables
+        Constraint::Length(hooks_height), // Hooks
     ])
     .split(inner_area);
 
-    let profiles_area = chunks[0];
-    let variables_area = chunks[1];
+    let details_area = chunks[0];
+    let profiles_area = chunks[1];
+    let variables_area = chunks[2];
+    if hook_lines > 0 {
+        render_hooks(frame, chunks[3], edit.hooks(), theme);
+    }
+
+    // --- METADATA SECTION ---
+    let [description_area, tags_area] =
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .areas(details_area);
+    utils::render_input_field(
+        frame,
+        description_area,
+        edit.description_input(),
+        "Description",
+        edit.current_focus() == EditFocus::Description,
+        theme,
+    );
+    utils::render_input_field(
+        frame,
+        tags_area,
+        edit.tags_input(),
+        "Tags (comma-separated)",
+        edit.current_focus() == EditFocus::Tags,
+        theme,
+    );
 
     // Calculate actual visible rows for variables area
     let variables_inner_height = variables_area.height.saturating_sub(2) as usize;
@@ -948,7 +1467,13 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
         .profiles()
         .iter()
         .skip(render_profile_scroll)
-        .map(|p| ListItem::new(p.as_str()))
+        .map(|p| {
+            if edit.is_profile_disabled(p) {
+                ListItem::new(p.as_str()).style(theme.text_disabled())
+            } else {
+                ListItem::new(p.as_str())
+            }
+        })
         .collect();
 
     let is_empty = profile_items.is_empty();
@@ -1027,7 +1552,12 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
         .enumerate()
         .map(|(idx, (k, v))| {
             let key_text = k.text();
-            let value_text = v.text();
+            let is_unset = edit.is_unset(idx);
+            let value_text = if is_unset {
+                "(unset)".to_string()
+            } else {
+                utils::single_line_preview(v.text())
+            };
             let selected = idx == edit.selected_variable_index();
 
             let (key_style, value_style) = if selected && vars_focus {
@@ -1035,6 +1565,8 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
                     EditVariableFocus::Key => (theme.cell_focus(), theme.selection_active()),
                     EditVariableFocus::Value => (theme.selection_active(), theme.cell_focus()),
                 }
+            } else if is_unset {
+                (theme.text_normal(), theme.text_dim())
             } else {
                 (theme.text_normal(), theme.text_normal())
             };
@@ -1102,12 +1634,15 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
         let column_chunks = layout.split(table_inner_area);
         let cell_area = column_chunks[col_index];
 
-        let popup_area = Rect {
-            x: cell_area.x.saturating_sub(1),
-            y: row_y.saturating_sub(1),
-            width: cell_area.width + 2,
-            height: 3,
-        };
+        let popup_area = utils::clamp_rect(
+            Rect {
+                x: cell_area.x.saturating_sub(1),
+                y: row_y.saturating_sub(1),
+                width: cell_area.width + 2,
+                height: 3,
+            },
+            frame.area(),
+        );
 
         let title = if is_key_focused {
             "Edit Variable"
@@ -1121,14 +1656,22 @@ pub fn render(frame: &mut Frame, area: Rect, app: &App) {
             input_state.error.map(|s| s.to_string()),
         );
 
-        render_variable_input_popup(frame, popup_area, &temp_input, title, &theme);
+        render_variable_input_popup(frame, popup_area, &temp_input, title, theme);
+    }
+
+    if let Some(editor) = edit.value_editor() {
+        let key = edit
+            .variables_for_rendering()
+            .get(edit.selected_variable_index())
+            .map_or("", |(k, _)| k.text());
+        render_value_editor(frame, editor, key, theme);
     }
 
     // Render dependency selector if open
     if edit.is_dependency_selector_open()
         && let Some(selector_state) = edit.dependency_selector_state()
     {
-        render_dependency_selector(frame, selector_state, &theme);
+        render_dependency_selector(frame, selector_state, theme);
     }
 }
 
@@ -1161,15 +1704,7 @@ fn render_variable_input_popup(
     let inner_area = block.inner(area);
 
     let text = input.text();
-    let cursor_pos = input.cursor_position();
-
-    let prefix_width = text
-        .chars()
-        .take(cursor_pos)
-        .map(|c| UnicodeWidthStr::width(c.to_string().as_str()))
-        .sum::<usize>();
-
-    let cursor_display_pos = prefix_width as u16;
+    let cursor_display_pos = input.cursor_display_width() as u16;
     let scroll_offset = if cursor_display_pos >= inner_area.width {
         cursor_display_pos - inner_area.width + 1
     } else {
@@ -1186,6 +1721,85 @@ fn render_variable_input_popup(
     ));
 }
 
+/// Hook commands are listed for reference; they are edited in the profile file
+fn render_hooks(frame: &mut Frame, area: Rect, hooks: &Hooks, theme: &Theme) {
+    let lines: Vec<Line> = HookKind::ALL
+        .iter()
+        .flat_map(|&kind| {
+            hooks
+                .commands(kind)
+                .iter()
+                .map(move |command| (format!("{:<13}", kind.label()), command))
+        })
+        .map(|(label, command)| {
+            Line::from(vec![
+                Span::styled(format!("{label} "), theme.text_dim()),
+                Span::raw(command.as_str()),
+            ])
+        })
+        .collect();
+    let title = if lines.len() > MAX_HOOK_ROWS {
+        format!(
+            "Hooks ({}, read-only, first {MAX_HOOK_ROWS} shown)",
+            lines.len()
+        )
+    } else {
+        format!("Hooks ({}, read-only)", lines.len())
+    };
+    let hooks_view = Paragraph::new(lines).block(
+        Block::new()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(theme.block_inactive()),
+    );
+    frame.render_widget(hooks_view, area);
+}
+
+fn render_value_editor(frame: &mut Frame, editor: &TextArea, key: &str, theme: &Theme) {
+    let area = utils::centered_rect(70, 60, frame.area());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(format!("Edit Value of '{key}'"))
+        .title_bottom(
+            Line::from(vec![
+                Span::styled("^S", theme.key(KeyRole::Confirm)),
+                Span::raw(": Confirm  "),
+                Span::styled("Esc", theme.key(KeyRole::Cancel)),
+                Span::raw(": Cancel  "),
+                Span::styled("Enter", theme.key(KeyRole::Focus)),
+                Span::raw(": New Line"),
+            ])
+            .right_aligned(),
+        )
+        .borders(Borders::ALL)
+        .border_style(theme.block_active())
+        .border_type(ratatui::widgets::BorderType::Thick);
+    let inner_area = block.inner(area);
+    frame.render_widget(block, area);
+
+    // Scroll just enough to keep the cursor inside the popup
+    let (row, _) = editor.cursor();
+    let cursor_x = editor.cursor_display_width() as u16;
+    let cursor_y = row as u16;
+    let scroll_y = cursor_y.saturating_sub(inner_area.height.saturating_sub(1));
+    let scroll_x = cursor_x.saturating_sub(inner_area.width.saturating_sub(1));
+
+    let lines: Vec<Line> = editor
+        .lines()
+        .iter()
+        .map(|line| Line::raw(line.as_str()))
+        .collect();
+    let paragraph = Paragraph::new(lines)
+        .style(theme.text_normal())
+        .scroll((scroll_y, scroll_x));
+    frame.render_widget(paragraph, inner_area);
+    frame.set_cursor_position((
+        inner_area.x + cursor_x - scroll_x,
+        inner_area.y + cursor_y - scroll_y,
+    ));
+}
+
 fn render_dependency_selector(
     frame: &mut Frame,
     selector_state: DependencySelectorState,
@@ -1204,22 +1818,45 @@ fn render_dependency_selector(
     frame.render_widget(outer_block, area);
 
     let chunks = Layout::vertical([
+        Constraint::Length(3), // Filter box
         Constraint::Min(0),    // List area
         Constraint::Length(2), // Help section
     ])
     .split(inner_area);
 
-    let list_area = chunks[0];
-    let help_area = chunks[1];
+    let filter_area = chunks[0];
+    let list_area = chunks[1];
+    let help_area = chunks[2];
+
+    let filter_block = Block::default()
+        .title("Filter")
+        .borders(Borders::ALL)
+        .border_style(theme.block_active());
+    let filter_inner = filter_block.inner(filter_area);
+    frame.render_widget(filter_block, filter_area);
+    let filter_text = if selector_state.query.is_empty() {
+        Line::styled("Type to filter", theme.text_dim())
+    } else {
+        Line::raw(selector_state.query)
+    };
+    frame.render_widget(Paragraph::new(filter_text), filter_inner);
+    let query_width = (selector_state.query.width() as u16).min(filter_inner.width);
+    frame.set_cursor_position((filter_inner.x + query_width, filter_inner.y));
 
     let items: Vec<ListItem> = selector_state
         .options
         .iter()
-        .enumerate()
-        .map(|(idx, name)| {
-            let selected = selector_state.selected_indices.contains(&idx);
+        .map(|&name| {
+            let selected = selector_state.selected.contains(name);
             let marker = if selected { "[✓] " } else { "[ ] " };
-            ListItem::new(format!("{marker}{name}"))
+            let mut spans = vec![Span::raw(format!("{marker}{name}"))];
+            if let Some(relevance) = selector_state.relevance.get(name) {
+                spans.push(Span::styled(
+                    format!("  {}", relevance.label()),
+                    theme.text_dim(),
+                ));
+            }
+            ListItem::new(Line::from(spans))
         })
         .collect();
 
@@ -1231,7 +1868,7 @@ fn render_dependency_selector(
         selector_state.current_index + 1
     };
     let total_count = selector_state.options.len();
-    let selected_count = selector_state.selected_indices.len();
+    let selected_count = selector_state.selected.len();
 
     let left_title = Line::from(format!("{current_pos}/{total_count}")).left_aligned();
     let right_title = Line::from(format!("Selected: {selected_count}")).right_aligned();
@@ -1283,59 +1920,26 @@ fn render_dependency_selector(
 
     let help_info = [
         vec![
-            Span::styled("Esc", Style::default().fg(Color::Rgb(255, 107, 107))),
+            Span::styled("Esc", theme.key(KeyRole::Cancel)),
             Span::raw(": Confirm"),
         ],
         vec![
-            Span::styled("↑↓", Style::default().fg(Color::Rgb(255, 138, 199))),
+            Span::styled("↑↓", theme.key(KeyRole::Navigate)),
             Span::raw(": Navigate"),
         ],
         vec![
-            Span::styled("Enter", Style::default().fg(Color::LightBlue)),
+            Span::styled("Enter", theme.key(KeyRole::Edit)),
             Span::raw("/"),
-            Span::styled("Space", Style::default().fg(Color::LightBlue)),
+            Span::styled("Space", theme.key(KeyRole::Edit)),
             Span::raw(": Toggle"),
         ],
+        vec![
+            Span::styled("Type", theme.key(KeyRole::Change)),
+            Span::raw(": Filter"),
+        ],
     ];
 
-    let help_spans = create_selector_help_spans(&help_info, help_area);
+    let help_spans = utils::wrap_help_items(&help_info, help_area.width, 2);
     let help_paragraph = Paragraph::new(help_spans).style(Style::default());
     frame.render_widget(help_paragraph, help_area);
 }
-
-fn create_selector_help_spans<'a>(help_info: &'a [Vec<Span<'a>>], area: Rect) -> Vec<Line<'a>> {
-    let total_width = area.width as usize;
-    let mut lines: Vec<Line> = vec![];
-    let mut current_line_spans: Vec<Span> = vec![];
-    let mut current_line_width = 0;
-    let max_help_lines = 2;
-
-    for info in help_info {
-        if lines.len() >= max_help_lines {
-            break;
-        }
-        let item_width: usize = info.iter().map(|span| span.width()).sum();
-        let separator_width = if !current_line_spans.is_empty() { 2 } else { 0 };
-
-        if current_line_width + separator_width + item_width > total_width
-            && !current_line_spans.is_empty()
-        {
-            if lines.len() < max_help_lines {
-                lines.push(Line::from(std::mem::take(&mut current_line_spans)));
-                current_line_width = 0;
-            } else {
-                break;
-            }
-        }
-        if !current_line_spans.is_empty() {
-            current_line_spans.push(Span::raw("  "));
-            current_line_width += 2;
-        }
-        current_line_spans.extend_from_slice(info);
-        current_line_width += item_width;
-    }
-    if !current_line_spans.is_empty() && lines.len() < max_help_lines {
-        lines.push(Line::from(current_line_spans));
-    }
-    lines
-}
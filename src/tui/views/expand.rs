@@ -0,0 +1,335 @@
+use crate::GLOBAL_PROFILE_MARK;
+use crate::config::env_size::EnvSize;
+use crate::config::models::{MergedEnv, computed_command};
+use crate::tui::app::{App, AppState};
+use crate::tui::theme::Theme;
+use crate::tui::utils::{self, Input, render_input_field};
+use crate::tui::widgets::empty;
+use crate::utils::{dotenv, output};
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState, Wrap};
+use std::io::Write;
+use std::path::Path;
+
+/// Rows moved by PageUp / PageDown
+pub const PAGE_SIZE: usize = 10;
+
+/// A resolved variable and the profiles its value came from
+struct ExpandedVar {
+    key: String,
+    value: String,
+    source: String,
+}
+
+/// State of the Expand pane: the resolved variables of the selected profile, the
+/// scroll position, the inline filter and the export path popup
+#[derive(Default)]
+pub struct ExpandView {
+    /// Resolved variables sorted by key
+    vars: Vec<ExpandedVar>,
+    /// Size of all the resolved variables, whatever the filter shows
+    size: EnvSize,
+    scroll: usize,
+    is_filtering: bool,
+    filter_input: Input,
+    export_input: Input,
+}
+
+impl ExpandView {
+    /// Show a new set of resolved variables, keeping the filter but scrolling back to the top
+    pub fn set_vars(&mut self, merged: MergedEnv) {
+        self.size = EnvSize::of(&merged.vars);
+        let mut vars: Vec<ExpandedVar> = merged
+            .vars
+            .into_iter()
+            .map(|(key, var)| ExpandedVar {
+                key,
+                value: var.value,
+                source: var.sources.join(", "),
+            })
+            .collect();
+        vars.sort_by(|a, b| a.key.cmp(&b.key));
+        self.vars = vars;
+        self.scroll = 0;
+    }
+
+    pub fn size(&self) -> &EnvSize {
+        &self.size
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// `(key, value, source)` of the variables matching the filter
+    /// (case-insensitive, on any of the three)
+    pub fn visible_vars(&self) -> Vec<(&str, &str, &str)> {
+        let query = self.filter_input.text().to_lowercase();
+        self.vars
+            .iter()
+            .filter(|var| {
+                query.is_empty()
+                    || var.key.to_lowercase().contains(&query)
+                    || var.value.to_lowercase().contains(&query)
+                    || var.source.to_lowercase().contains(&query)
+            })
+            .map(|var| (var.key.as_str(), var.value.as_str(), var.source.as_str()))
+            .collect()
+    }
+
+    pub fn scroll(&self) -> usize {
+        self.scroll
+    }
+
+    pub fn scroll_down(&mut self, rows: usize) {
+        let last = self.visible_vars().len().saturating_sub(1);
+        self.scroll = (self.scroll + rows).min(last);
+    }
+
+    pub fn scroll_up(&mut self, rows: usize) {
+        self.scroll = self.scroll.saturating_sub(rows);
+    }
+
+    pub fn is_filtering(&self) -> bool {
+        self.is_filtering
+    }
+
+    pub fn enter_filter_mode(&mut self) {
+        self.is_filtering = true;
+    }
+
+    /// Stop typing into the filter, keeping it applied
+    pub fn leave_filter_mode(&mut self) {
+        self.is_filtering = false;
+    }
+
+    pub fn clear_filter(&mut self) {
+        self.is_filtering = false;
+        self.filter_input.reset();
+        self.scroll = 0;
+    }
+
+    pub fn filter_input(&self) -> &Input {
+        &self.filter_input
+    }
+
+    pub fn start_export(&mut self, profile_name: &str) {
+        self.export_input = Input::with_text(format!("./{profile_name}.env"));
+    }
+
+    pub fn export_input(&self) -> &Input {
+        &self.export_input
+    }
+
+    pub fn export_input_mut(&mut self) -> &mut Input {
+        &mut self.export_input
+    }
+
+    /// Write the visible variables to the export path in dotenv format
+    pub fn export(&self) -> std::io::Result<usize> {
+        let vars = self.visible_vars();
+        let pairs = vars.iter().map(|&(key, value, _)| (key, value));
+        let mut writer = output::open_output(Some(Path::new(self.export_input.text())))?;
+        writer.write_all(dotenv::render(pairs).as_bytes())?;
+        writer.flush()?;
+        Ok(vars.len())
+    }
+}
+
+pub fn render(frame: &mut Frame<'_>, area: Rect, profile_name: &str, app: &App, theme: &Theme) {
+    let title = format!("Expanded for '{profile_name}'");
+    let main_block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(theme.block_inactive())
+        .title_top(
+            Line::from(title)
+                .left_aligned()
+                .style(theme.block_title_inactive()),
+        );
+
+    let inner_area = main_block.inner(area);
+    frame.render_widget(main_block, area);
+
+    let expand_view = &app.expand_view;
+    let show_filter = expand_view.is_filtering() || !expand_view.filter_input().text().is_empty();
+    let too_large = expand_view.size().exceeds(app.max_env_size);
+    // Description and tags of the profile itself, as in the raw view
+    let metadata = app
+        .config_manager
+        .get_profile(profile_name)
+        .and_then(|profile| utils::metadata_line(profile, theme));
+    let [metadata_area, size_area, filter_area, table_area] = Layout::vertical([
+        Constraint::Length(metadata.is_some() as u16),
+        Constraint::Length(if too_large { 2 } else { 0 }),
+        Constraint::Length(if show_filter { 3 } else { 0 }),
+        Constraint::Min(0),
+    ])
+    .areas(inner_area);
+
+    if let Some(metadata) = metadata {
+        frame.render_widget(Paragraph::new(metadata), metadata_area);
+    }
+
+    if too_large {
+        let warning = Paragraph::new(format!(
+            "Too large to activate safely: {}",
+            expand_view.size().warning(app.max_env_size)
+        ))
+        .style(theme.text_warning())
+        .wrap(Wrap { trim: true });
+        frame.render_widget(warning, size_area);
+    }
+
+    if show_filter {
+        render_input_field(
+            frame,
+            filter_area,
+            expand_view.filter_input(),
+            "Filter",
+            expand_view.is_filtering(),
+            theme,
+        );
+    }
+
+    let visible = expand_view.visible_vars();
+    let var_rows: Vec<Row> = visible
+        .iter()
+        .map(|(k, v, source)| {
+            // Computed in the shell on activation, so the command is all there is to show
+            let value = match computed_command(v) {
+                Some(command) => Line::from(vec![
+                    Span::styled("computed ", theme.text_dim()),
+                    Span::raw(format!("$({})", utils::single_line_preview(command))),
+                ]),
+                None => Line::from(utils::single_line_preview(v)),
+            };
+            Row::new(vec![
+                Cell::from(k.to_string()),
+                Cell::from(value),
+                Cell::from(display_source(source)).style(theme.text_dim()),
+            ])
+        })
+        .collect();
+
+    let is_empty = var_rows.is_empty();
+
+    let table = Table::new(
+        var_rows,
+        [
+            Constraint::Percentage(25),
+            Constraint::Percentage(55),
+            Constraint::Percentage(20),
+        ],
+    )
+    .header(Row::new(vec!["Key", "Value", "From"]).style(theme.text_highlight()))
+    .block(
+        Block::new()
+            .title(format!("Variables ({})", visible.len()))
+            .borders(Borders::ALL)
+            .border_style(theme.block_inactive()),
+    );
+
+    if is_empty {
+        empty::variable_not_defined(frame, table_area);
+    }
+    let mut table_state = TableState::default().with_offset(expand_view.scroll());
+    frame.render_stateful_widget(table, table_area, &mut table_state);
+}
+
+/// Source profiles as shown to the user, with the global marker spelled out
+fn display_source(source: &str) -> String {
+    source
+        .split(", ")
+        .map(|name| {
+            if name == GLOBAL_PROFILE_MARK {
+                "GLOBAL"
+            } else {
+                name
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub fn render_export_popup(frame: &mut Frame<'_>, app: &App) {
+    let theme = &app.theme;
+    let popup = utils::centered_rect(60, 100, frame.area());
+    let [area] = Layout::vertical([Constraint::Length(3)])
+        .flex(layout::Flex::Center)
+        .areas(popup);
+
+    frame.render_widget(Clear, area);
+    render_input_field(
+        frame,
+        area,
+        app.expand_view.export_input(),
+        "Export to (dotenv)",
+        true,
+        theme,
+    );
+}
+
+/// Keys while typing into the inline filter of the Expand pane
+pub fn handle_filter_event(app: &mut App, key: KeyEvent) {
+    let expand_view = &mut app.expand_view;
+    match key.code {
+        KeyCode::Esc => expand_view.clear_filter(),
+        KeyCode::Enter => expand_view.leave_filter_mode(),
+        KeyCode::Char(c) => {
+            expand_view.filter_input.enter_char(c);
+            expand_view.scroll = 0;
+        }
+        KeyCode::Backspace => {
+            expand_view.filter_input.delete_char();
+            expand_view.scroll = 0;
+        }
+        KeyCode::Left => expand_view.filter_input.move_cursor_left(),
+        KeyCode::Right => expand_view.filter_input.move_cursor_right(),
+        KeyCode::Down => expand_view.scroll_down(1),
+        KeyCode::Up => expand_view.scroll_up(1),
+        KeyCode::PageDown => expand_view.scroll_down(PAGE_SIZE),
+        KeyCode::PageUp => expand_view.scroll_up(PAGE_SIZE),
+        _ => {}
+    }
+}
+
+/// Keys while the export path popup is open
+pub fn handle_export_event(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Char(c) => {
+            app.expand_view.export_input_mut().enter_char(c);
+            app.expand_view.export_input_mut().clear_error();
+        }
+        KeyCode::Backspace => {
+            app.expand_view.export_input_mut().delete_char();
+            app.expand_view.export_input_mut().clear_error();
+        }
+        KeyCode::Left => app.expand_view.export_input_mut().move_cursor_left(),
+        KeyCode::Right => app.expand_view.export_input_mut().move_cursor_right(),
+        KeyCode::Esc => app.state = AppState::List,
+        KeyCode::Enter => {
+            if app.expand_view.export_input().text().trim().is_empty() {
+                app.expand_view
+                    .export_input_mut()
+                    .set_error_message("Path cannot be empty");
+                return;
+            }
+            match app.expand_view.export() {
+                Ok(count) => {
+                    app.show_success(format!(
+                        "Exported {count} variables to '{}'",
+                        app.expand_view.export_input().text()
+                    ));
+                    app.state = AppState::List;
+                }
+                Err(e) => {
+                    app.expand_view
+                        .export_input_mut()
+                        .set_error_message(&e.to_string());
+                }
+            }
+        }
+        _ => {}
+    }
+}
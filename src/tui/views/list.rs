@@ -1,25 +1,179 @@
 use crate::GLOBAL_PROFILE_MARK;
-use crate::tui::app::{App, AppState, MainRightViewMode};
+use crate::config::models::Profile;
+use crate::tui::app::{App, AppState, FullLoadAction, MainRightViewMode};
 use crate::tui::theme::Theme;
-use crate::tui::utils::{Input, inner};
+use crate::tui::utils::{Input, clamp_rect, inner};
+use crate::tui::views::expand;
 use crate::tui::widgets::empty;
+use crate::utils::NAMESPACE_SEPARATOR;
 use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::prelude::*;
 use ratatui::widgets::{
     Block, Borders, Clear, List, ListItem, ListState, Paragraph, Scrollbar, ScrollbarOrientation,
     ScrollbarState,
 };
-use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::time::SystemTime;
 use unicode_width::UnicodeWidthStr;
 
+/// A profile row in the list, carrying the metadata used for sorting and annotations
+#[derive(Clone, Debug)]
+pub struct ProfileEntry {
+    pub name: String,
+    pub modified: Option<SystemTime>,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+    pub variable_count: Option<usize>,
+    pub dependency_count: Option<usize>,
+    /// Position in dependency order, with every profile after its dependencies
+    pub dependency_rank: Option<usize>,
+}
+
+impl ProfileEntry {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            modified: None,
+            description: None,
+            tags: Vec::new(),
+            variable_count: None,
+            dependency_count: None,
+            dependency_rank: None,
+        }
+    }
+
+    /// Take the description, tags and counts from the loaded profile
+    pub fn fill_from(&mut self, profile: &Profile) {
+        self.description = profile.description.clone();
+        self.tags = profile.tags.clone();
+        self.variable_count = Some(profile.variables.len());
+        self.dependency_count = Some(profile.profiles.len());
+    }
+
+    /// Dimmed badge after the name with the number of variables the profile sets
+    /// itself (e.g. `(12 vars)`), once the profile is loaded
+    pub fn badge(&self) -> Option<String> {
+        self.variable_count.map(|vars| format!("({vars} vars)"))
+    }
+
+    /// Compact annotation rendered at the right edge of the row (e.g. `3p`)
+    pub fn annotation(&self) -> Option<String> {
+        self.dependency_count.map(|deps| format!("{deps}p"))
+    }
+
+    /// Whether the name, description or any tag contains the (lowercased) query
+    fn matches(&self, query: &str) -> bool {
+        self.name.to_lowercase().contains(query)
+            || self
+                .description
+                .as_ref()
+                .is_some_and(|d| d.to_lowercase().contains(query))
+            || self.tags.iter().any(|t| t.to_lowercase().contains(query))
+    }
+}
+
+#[derive(Default, Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortMode {
+    #[default]
+    Name,
+    Modified,
+    VariableCount,
+    DependencyCount,
+    Dependency,
+}
+
+impl SortMode {
+    pub fn next(self) -> Self {
+        match self {
+            SortMode::Name => SortMode::Modified,
+            SortMode::Modified => SortMode::VariableCount,
+            SortMode::VariableCount => SortMode::DependencyCount,
+            SortMode::DependencyCount => SortMode::Dependency,
+            SortMode::Dependency => SortMode::Name,
+        }
+    }
+
+    /// Switch between alphabetical and dependency order
+    pub fn toggle_dependency_order(self) -> Self {
+        if self == SortMode::Dependency {
+            SortMode::Name
+        } else {
+            SortMode::Dependency
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortMode::Name => "Name",
+            SortMode::Modified => "Modified",
+            SortMode::VariableCount => "Vars",
+            SortMode::DependencyCount => "Deps",
+            SortMode::Dependency => "Layers",
+        }
+    }
+
+    /// Whether this sort order needs the profile contents to be loaded
+    pub fn requires_counts(self) -> bool {
+        matches!(
+            self,
+            SortMode::VariableCount | SortMode::DependencyCount | SortMode::Dependency
+        )
+    }
+
+    fn compare(self, a: &ProfileEntry, b: &ProfileEntry) -> Ordering {
+        // Most recently modified and largest profiles come first, ties fall back to name
+        let ordering = match self {
+            SortMode::Name => Ordering::Equal,
+            SortMode::Modified => b.modified.cmp(&a.modified),
+            SortMode::VariableCount => b.variable_count.cmp(&a.variable_count),
+            SortMode::DependencyCount => b.dependency_count.cmp(&a.dependency_count),
+            // Profiles that failed to load have no rank and go last
+            SortMode::Dependency => {
+                let rank = |entry: &ProfileEntry| entry.dependency_rank.unwrap_or(usize::MAX);
+                rank(a).cmp(&rank(b))
+            }
+        };
+        ordering.then_with(|| a.name.cmp(&b.name))
+    }
+}
+
+/// A row of the profile list: a profile, or the header of a namespace when grouping
+#[derive(Debug, Clone, Copy)]
+pub enum ListRow<'a> {
+    Namespace {
+        name: &'a str,
+        profile_count: usize,
+        collapsed: bool,
+    },
+    Profile(&'a ProfileEntry),
+}
+
+/// The namespace and the last segment of a profile name (`work/api` -> `work`, `api`)
+pub fn split_namespace(name: &str) -> (Option<&str>, &str) {
+    match name.rsplit_once(NAMESPACE_SEPARATOR) {
+        Some((namespace, leaf)) => (Some(namespace), leaf),
+        None => (None, name),
+    }
+}
+
 #[derive(Default)]
 pub struct ListView {
-    profile_names: Vec<String>,
+    profiles: Vec<ProfileEntry>,
+    /// Index into [`ListView::rows`]
     selected_index: usize,
     dirty_profiles: HashSet<String>,
     rename_input: Input,
     in_search_mode: bool,
     search_input: Input,
+    sort_mode: SortMode,
+    group_by_namespace: bool,
+    collapsed_namespaces: HashSet<String>,
+    /// Multi-select mode, where marks are shown and bulk actions apply to them
+    visual_mode: bool,
+    marked: BTreeSet<String>,
 }
 
 impl ListView {
@@ -27,48 +181,216 @@ impl ListView {
         Default::default()
     }
 
-    /// Get the currently selected profile name
+    /// Get the currently selected profile name, `None` when a namespace header is selected
     pub fn current_profile(&self) -> Option<&str> {
-        self.filtered_profiles()
-            .get(self.selected_index)
-            .map(|s| s.as_str())
+        match self.rows().get(self.selected_index) {
+            Some(ListRow::Profile(entry)) => Some(entry.name.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Get the namespace whose header is selected, if any
+    pub fn current_namespace(&self) -> Option<&str> {
+        match self.rows().get(self.selected_index) {
+            Some(ListRow::Namespace { name, .. }) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// The rows shown in the list: the filtered profiles, under namespace headers when
+    /// grouping, with profiles in collapsed namespaces left out.
+    ///
+    /// Profiles without a namespace come first; while searching every namespace with a
+    /// match is shown expanded.
+    pub fn rows(&self) -> Vec<ListRow<'_>> {
+        let entries = self.filtered_entries();
+        if !self.group_by_namespace {
+            return entries.into_iter().map(ListRow::Profile).collect();
+        }
+
+        let mut rows = Vec::new();
+        let mut namespaces: BTreeMap<&str, Vec<&ProfileEntry>> = BTreeMap::new();
+        for entry in entries {
+            match split_namespace(&entry.name) {
+                (Some(namespace), _) => namespaces.entry(namespace).or_default().push(entry),
+                (None, _) => rows.push(ListRow::Profile(entry)),
+            }
+        }
+        for (name, entries) in namespaces {
+            let collapsed = !self.in_search_mode && self.collapsed_namespaces.contains(name);
+            rows.push(ListRow::Namespace {
+                name,
+                profile_count: entries.len(),
+                collapsed,
+            });
+            if !collapsed {
+                rows.extend(entries.into_iter().map(ListRow::Profile));
+            }
+        }
+        rows
+    }
+
+    pub fn is_grouped(&self) -> bool {
+        self.group_by_namespace
+    }
+
+    /// Switch grouping by namespace on or off, keeping the selected profile selected
+    pub fn toggle_grouping(&mut self) {
+        let selected = self
+            .current_profile()
+            .or(self.current_namespace())
+            .map(|s| s.to_string());
+        self.group_by_namespace = !self.group_by_namespace;
+        self.selected_index = 0;
+        if let Some(name) = selected {
+            self.select_profile(&name);
+        }
+    }
+
+    /// Collapse the selected namespace, or expand it when it is collapsed
+    pub fn toggle_current_namespace(&mut self) {
+        if let Some(namespace) = self.current_namespace().map(|s| s.to_string())
+            && !self.collapsed_namespaces.remove(&namespace)
+        {
+            self.collapsed_namespaces.insert(namespace);
+        }
+        self.clamp_selection();
+    }
+
+    /// Keep `selected_index` inside the visible rows after they change
+    fn clamp_selection(&mut self) {
+        let row_count = self.rows().len();
+        if self.selected_index >= row_count {
+            self.selected_index = row_count.saturating_sub(1);
+        }
+    }
+
+    fn row_position(&self, name: &str) -> Option<usize> {
+        self.rows().iter().position(|row| match row {
+            ListRow::Profile(entry) => entry.name == name,
+            ListRow::Namespace { .. } => false,
+        })
+    }
+
+    /// Get all profile entries (unfiltered)
+    pub fn all_entries(&self) -> &[ProfileEntry] {
+        &self.profiles
     }
 
     /// Get all profile names (unfiltered)
-    pub fn all_profiles(&self) -> &[String] {
-        &self.profile_names
+    pub fn all_profiles(&self) -> Vec<&String> {
+        self.profiles.iter().map(|entry| &entry.name).collect()
+    }
+
+    /// The entry of profile `name`, whether or not the search shows it
+    pub fn entry_mut(&mut self, name: &str) -> Option<&mut ProfileEntry> {
+        self.profiles.iter_mut().find(|entry| entry.name == name)
     }
 
     /// Get filtered profiles based on search mode
     pub fn filtered_profiles(&self) -> Vec<&String> {
+        self.filtered_entries()
+            .into_iter()
+            .map(|entry| &entry.name)
+            .collect()
+    }
+
+    /// Get filtered profile entries based on search mode
+    pub fn filtered_entries(&self) -> Vec<&ProfileEntry> {
         if !self.in_search_mode || self.search_input.text().is_empty() {
-            return self.profile_names.iter().collect();
+            return self.profiles.iter().collect();
         }
 
         let search_query = self.search_input.text().to_lowercase();
-        self.profile_names
+        self.profiles
             .iter()
-            .filter(|name| name.to_lowercase().contains(&search_query))
+            .filter(|entry| entry.matches(&search_query))
             .collect()
     }
+
+    pub fn is_visual_mode(&self) -> bool {
+        self.visual_mode
+    }
+
+    /// Enter the multi-select mode, or leave it and drop the marks
+    pub fn toggle_visual_mode(&mut self) {
+        self.visual_mode = !self.visual_mode;
+        self.marked.clear();
+    }
+
+    /// Mark the selected profile, or unmark it, entering the multi-select mode.
+    /// GLOBAL cannot be marked.
+    pub fn toggle_mark_current(&mut self) {
+        let Some(name) = self.current_profile().map(|s| s.to_string()) else {
+            return;
+        };
+        if name == GLOBAL_PROFILE_MARK {
+            return;
+        }
+        self.visual_mode = true;
+        if !self.marked.remove(&name) {
+            self.marked.insert(name);
+        }
+    }
+
+    pub fn is_marked(&self, name: &str) -> bool {
+        self.marked.contains(name)
+    }
+
+    /// The marked profiles, sorted by name
+    pub fn marked_profiles(&self) -> Vec<String> {
+        self.marked.iter().cloned().collect()
+    }
+
+    pub fn marked_count(&self) -> usize {
+        self.marked.len()
+    }
+
+    /// Drop the marks and leave the multi-select mode
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+        self.visual_mode = false;
+    }
+
     /// Update the profile list (e.g., after adding/removing profiles)
-    pub fn update_profiles(&mut self, mut profiles: Vec<String>) {
+    ///
+    /// The list is re-sorted according to the active sort mode, keeping GLOBAL pinned to the
+    /// top and the current selection on the same profile whenever it is still present.
+    pub fn update_profiles(&mut self, mut profiles: Vec<ProfileEntry>) {
+        let selected_name = self.current_profile().map(|s| s.to_string());
+        let sort_mode = self.sort_mode;
         profiles.sort_by(|a, b| {
-            if a == GLOBAL_PROFILE_MARK {
-                std::cmp::Ordering::Less
-            } else if b == GLOBAL_PROFILE_MARK {
-                std::cmp::Ordering::Greater
+            if a.name == GLOBAL_PROFILE_MARK {
+                Ordering::Less
+            } else if b.name == GLOBAL_PROFILE_MARK {
+                Ordering::Greater
             } else {
-                a.cmp(b)
+                sort_mode.compare(a, b)
             }
         });
-        self.profile_names = profiles;
-        // Ensure selected_index is valid
-        if self.selected_index >= self.profile_names.len() && !self.profile_names.is_empty() {
-            self.selected_index = self.profile_names.len() - 1;
-        } else if self.profile_names.is_empty() {
-            self.selected_index = 0;
+        self.profiles = profiles;
+        let names: HashSet<&str> = self.profiles.iter().map(|e| e.name.as_str()).collect();
+        self.marked.retain(|name| names.contains(name.as_str()));
+
+        if let Some(name) = selected_name
+            && let Some(index) = self.row_position(&name)
+        {
+            self.selected_index = index;
+            return;
         }
+
+        self.clamp_selection();
+    }
+
+    pub fn sort_mode(&self) -> SortMode {
+        self.sort_mode
+    }
+
+    /// Switch to `sort_mode` and re-sort the current entries
+    pub fn set_sort_mode(&mut self, sort_mode: SortMode) {
+        self.sort_mode = sort_mode;
+        let profiles = self.profiles.clone();
+        self.update_profiles(profiles);
     }
 
     /// Get current selected index (for rendering)
@@ -78,28 +400,46 @@ impl ListView {
 
     /// Set selected index directly (for after operations that change list)
     pub fn set_selected_index(&mut self, index: usize) {
-        if index < self.profile_names.len() {
+        if index < self.rows().len() {
             self.selected_index = index;
         }
     }
 
+    /// Select the profile called `name`, leaving search mode if the search hides it and
+    /// expanding the namespace it is in
+    pub fn select_profile(&mut self, name: &str) -> bool {
+        if !self.filtered_profiles().iter().any(|n| *n == name) {
+            self.exit_search_mode();
+        }
+        if let (Some(namespace), _) = split_namespace(name) {
+            self.collapsed_namespaces.remove(namespace);
+        }
+        match self.row_position(name) {
+            Some(index) => {
+                self.selected_index = index;
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn next(&mut self) {
-        let filtered = self.filtered_profiles();
-        if filtered.is_empty() {
+        let row_count = self.rows().len();
+        if row_count == 0 {
             self.selected_index = 0;
             return;
         }
-        let i = (self.selected_index + 1) % filtered.len();
+        let i = (self.selected_index + 1) % row_count;
         self.selected_index = i;
     }
 
     pub fn previous(&mut self) {
-        let filtered = self.filtered_profiles();
-        if filtered.is_empty() {
+        let row_count = self.rows().len();
+        if row_count == 0 {
             self.selected_index = 0;
             return;
         }
-        let i = (self.selected_index + filtered.len() - 1) % filtered.len();
+        let i = (self.selected_index + row_count - 1) % row_count;
         self.selected_index = i;
     }
 
@@ -142,19 +482,25 @@ impl ListView {
         if !self.in_search_mode {
             return;
         }
-        let filtered = self.filtered_profiles();
-        if !filtered.is_empty() {
-            let selected_name = filtered[self.selected_index];
-            if let Some(index) = self
-                .profile_names
-                .iter()
-                .position(|name| name == selected_name)
-            {
-                self.selected_index = index;
-            }
-        }
+        let selected_name = self.current_profile().map(|s| s.to_string());
         self.in_search_mode = false;
         self.search_input.reset();
+        self.selected_index = 0;
+        if let Some(name) = selected_name {
+            self.select_profile(&name);
+        }
+    }
+
+    /// Type `c` into the search query, selecting the first match
+    pub fn search_enter_char(&mut self, c: char) {
+        self.search_input.enter_char(c);
+        self.selected_index = 0;
+    }
+
+    /// Delete the character before the cursor in the search query, selecting the first match
+    pub fn search_delete_char(&mut self) {
+        self.search_input.delete_char();
+        self.selected_index = 0;
     }
 
     /// Get mutable reference to search input for event handlers
@@ -170,8 +516,7 @@ impl ListView {
     pub fn start_rename(&mut self) {
         if let Some(current_name) = self.current_profile() {
             let name = current_name.to_string();
-            self.rename_input.set_text(name.clone());
-            self.rename_input.set_cursor_position(name.len());
+            self.rename_input.set_text(name);
             self.rename_input.clear_error();
         }
     }
@@ -192,25 +537,92 @@ impl ListView {
     }
 }
 
+/// Row prefixes in the multi-select mode
+pub const MARKED: &str = "● ";
+pub const UNMARKED: &str = "○ ";
+
+/// The title of the list block, with the position, sort order and selection count
+pub fn list_title(list_view: &ListView, row_count: usize) -> String {
+    let sort_label = list_view.sort_mode().label();
+    let position = if row_count == 0 {
+        0
+    } else {
+        list_view.selected_index() + 1
+    };
+    let mut title = format!("Profile List ({position}/{row_count}) [{sort_label}]");
+    if list_view.is_visual_mode() {
+        title.push_str(&format!(" {} selected", list_view.marked_count()));
+    }
+    title
+}
+
 pub fn render(frame: &mut Frame<'_>, area: Rect, app: &App) {
-    let theme = Theme::new();
-    let profiles = app.list_view.filtered_profiles();
-    let items: Vec<ListItem> = profiles
+    let theme = &app.theme;
+    let rows = app.list_view.rows();
+    // Borders and the highlight symbol take up 4 columns
+    let row_width = area.width.saturating_sub(4) as usize;
+    let items: Vec<ListItem> = rows
         .iter()
-        .map(|name| {
-            let display_name = if *name == GLOBAL_PROFILE_MARK {
+        .map(|row| {
+            let entry = match row {
+                ListRow::Namespace {
+                    name,
+                    profile_count,
+                    collapsed,
+                } => {
+                    let indicator = if *collapsed { "▸ " } else { "▾ " };
+                    return ListItem::new(Line::from(vec![
+                        Span::styled(indicator, theme.text_dim()),
+                        Span::styled(format!("{name}/"), theme.text_highlight()),
+                        Span::styled(format!(" ({profile_count})"), theme.text_dim()),
+                    ]));
+                }
+                ListRow::Profile(entry) => entry,
+            };
+            let name = &entry.name;
+            let display_name = if name == GLOBAL_PROFILE_MARK {
                 "GLOBAL"
+            } else if app.list_view.is_grouped() {
+                split_namespace(name).1
             } else {
                 name.as_str()
             };
-            let display_text = if app.list_view.is_dirty(name) {
-                vec![
-                    Span::styled("*", theme.text_highlight()),
-                    Span::from(display_name),
-                ]
+            let indent = if app.list_view.is_grouped() && split_namespace(name).0.is_some() {
+                "  "
             } else {
-                vec![Span::from(display_name)]
+                ""
             };
+            let mut display_text = Vec::new();
+            if app.list_view.is_visual_mode() {
+                display_text.push(if app.list_view.is_marked(name) {
+                    Span::styled(MARKED, theme.text_highlight())
+                } else if name == GLOBAL_PROFILE_MARK {
+                    Span::raw(" ".repeat(MARKED.chars().count()))
+                } else {
+                    Span::styled(UNMARKED, theme.text_dim())
+                });
+            }
+            display_text.push(Span::raw(indent));
+            if app.list_view.is_dirty(name) {
+                display_text.push(Span::styled("*", theme.text_highlight()));
+            }
+            display_text.push(Span::from(display_name));
+            if let Some(badge) = entry.badge() {
+                display_text.push(Span::styled(format!(" {badge}"), theme.text_dim()));
+            }
+            if app.load_error(name).is_some() {
+                display_text.push(Span::styled(" [!]", theme.text_error()));
+            }
+
+            if let Some(annotation) = entry.annotation() {
+                let used_width: usize = display_text.iter().map(|span| span.width()).sum();
+                let annotation_width = UnicodeWidthStr::width(annotation.as_str());
+                if used_width + annotation_width < row_width {
+                    let padding = row_width - used_width - annotation_width;
+                    display_text.push(Span::raw(" ".repeat(padding)));
+                    display_text.push(Span::styled(annotation, theme.text_dim()));
+                }
+            }
             ListItem::new(Text::from(Line::from(display_text)))
         })
         .collect();
@@ -219,16 +631,7 @@ pub fn render(frame: &mut Frame<'_>, area: Rect, app: &App) {
     let is_empty = total_items == 0;
     let unsaved_count = app.list_view.unsaved_count();
 
-    let title = if is_empty {
-        Line::from("Profile List (0/0)").left_aligned()
-    } else {
-        Line::from(format!(
-            "Profile List ({}/{})",
-            app.list_view.selected_index() + 1,
-            total_items
-        ))
-        .left_aligned()
-    };
+    let title = Line::from(list_title(&app.list_view, total_items)).left_aligned();
 
     let mut list = List::new(items)
         .highlight_style(theme.selection_active())
@@ -255,7 +658,7 @@ pub fn render(frame: &mut Frame<'_>, area: Rect, app: &App) {
     list = list.block(block);
 
     let mut list_state = ListState::default();
-    if !app.list_view.filtered_profiles().is_empty() {
+    if !is_empty {
         list_state.select(Some(app.list_view.selected_index()));
     }
 
@@ -275,7 +678,7 @@ pub fn render(frame: &mut Frame<'_>, area: Rect, app: &App) {
 
     // Render Rename Overlay
     if app.state == AppState::Rename {
-        render_rename_section(frame, &app.list_view, area, &list_state, &theme);
+        render_rename_section(frame, &app.list_view, area, &list_state, theme);
     }
 
     // Render Scrollbar
@@ -326,12 +729,15 @@ fn render_rename_section(
         // Centered on item_y: item_y - 1.
         let overlay_y = item_y.saturating_sub(1);
 
-        let input_area = Rect {
-            x: area.x + 1,
-            y: overlay_y,
-            width,
-            height: 3,
-        };
+        let input_area = clamp_rect(
+            Rect {
+                x: area.x + 1,
+                y: overlay_y,
+                width,
+                height: 3,
+            },
+            frame.area(),
+        );
 
         // Render Background Clear (to wipe underlying list item + borders if overlapping)
         frame.render_widget(Clear, input_area);
@@ -358,15 +764,7 @@ fn render_rename_section(
 
         // Render Input Text
         let text = input.text();
-        let cursor_pos = input.cursor_position();
-
-        let prefix_width = text
-            .chars()
-            .take(cursor_pos)
-            .map(|c| UnicodeWidthStr::width(c.to_string().as_str()))
-            .sum::<usize>();
-
-        let cursor_display_pos = prefix_width as u16;
+        let cursor_display_pos = input.cursor_display_width() as u16;
         let scroll_offset = if cursor_display_pos >= inner_area.width {
             cursor_display_pos - inner_area.width + 1
         } else {
@@ -391,6 +789,16 @@ fn render_rename_section(
 }
 
 pub fn handle_event(app: &mut App, key: KeyEvent) -> Result<(), Box<dyn std::error::Error>> {
+    let expanded = app.main_right_view_mode == MainRightViewMode::Expand;
+    if expanded && app.expand_view.is_filtering() {
+        expand::handle_filter_event(app, key);
+        return Ok(());
+    }
+
+    if is_mutating_key(app, key, expanded) && app.refuse_if_read_only() {
+        return Ok(());
+    }
+
     let list_view = &mut app.list_view;
 
     if list_view.is_searching() {
@@ -399,9 +807,9 @@ pub fn handle_event(app: &mut App, key: KeyEvent) -> Result<(), Box<dyn std::err
                 KeyCode::Char('d') => {
                     if let Some(name) = list_view.current_profile() {
                         if name == GLOBAL_PROFILE_MARK {
-                            app.status_message = Some("Cannot delete GLOBAL profile".to_string());
+                            app.show_error("Cannot delete GLOBAL profile");
                         } else {
-                            app.state = AppState::ConfirmDelete;
+                            app.with_all_profiles(FullLoadAction::Delete);
                         }
                     }
                 }
@@ -421,12 +829,10 @@ pub fn handle_event(app: &mut App, key: KeyEvent) -> Result<(), Box<dyn std::err
                 list_view.exit_search_mode();
             }
             KeyCode::Char(c) => {
-                list_view.search_input_mut().enter_char(c);
-                list_view.set_selected_index(0);
+                list_view.search_enter_char(c);
             }
             KeyCode::Backspace => {
-                list_view.search_input_mut().delete_char();
-                list_view.set_selected_index(0);
+                list_view.search_delete_char();
             }
             KeyCode::Left => {
                 list_view.search_input_mut().move_cursor_left();
@@ -452,28 +858,32 @@ pub fn handle_event(app: &mut App, key: KeyEvent) -> Result<(), Box<dyn std::err
                     app.start_editing(&name);
                 }
             }
-            KeyCode::Tab => match app.main_right_view_mode {
-                MainRightViewMode::Raw => {
-                    app.load_expand_vars();
-                }
-                MainRightViewMode::Expand => {
-                    app.unload_expand_vars();
-                }
-            },
+            KeyCode::Tab => {
+                app.switch_right_view();
+            }
             KeyCode::F(2) => {
                 if let Some(name) = list_view.current_profile() {
                     if name == GLOBAL_PROFILE_MARK {
-                        app.status_message = Some("Cannot rename GLOBAL profile".to_string());
+                        app.show_error("Cannot rename GLOBAL profile");
                     } else {
-                        app.state = AppState::Rename;
-                        list_view.start_rename();
+                        app.with_all_profiles(FullLoadAction::Rename);
                     }
                 }
             }
             _ => {}
         }
     } else {
+        if app.count_prefix.push_key(key.code) {
+            return Ok(());
+        }
+        let count = app.count_prefix.take();
         match key.code {
+            KeyCode::Esc if expanded && !app.expand_view.filter_input().text().is_empty() => {
+                app.expand_view.clear_filter();
+            }
+            KeyCode::Esc if list_view.is_visual_mode() => {
+                list_view.clear_marks();
+            }
             KeyCode::Esc => {
                 if app.list_view.unsaved_count() > 0 {
                     app.state = AppState::ConfirmExit;
@@ -482,46 +892,107 @@ pub fn handle_event(app: &mut App, key: KeyEvent) -> Result<(), Box<dyn std::err
                 }
             }
             KeyCode::Char('/') => {
-                list_view.enter_search_mode();
+                app.with_all_profiles(FullLoadAction::Search);
+            }
+            KeyCode::Char('a') => {
+                app.activate_selected_on_exit();
+            }
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                app.open_recent();
+            }
+            KeyCode::Char('`') => {
+                app.open_recent();
             }
             KeyCode::Char('j') | KeyCode::Down => {
-                app.list_view.next();
+                for _ in 0..count {
+                    app.list_view.next();
+                }
                 if app.main_right_view_mode == MainRightViewMode::Expand {
                     app.load_expand_vars();
                 }
             }
             KeyCode::Char('k') | KeyCode::Up => {
-                app.list_view.previous();
+                for _ in 0..count {
+                    app.list_view.previous();
+                }
                 if app.main_right_view_mode == MainRightViewMode::Expand {
                     app.load_expand_vars();
                 }
             }
+            KeyCode::Enter | KeyCode::Char(' ') if list_view.current_namespace().is_some() => {
+                list_view.toggle_current_namespace();
+            }
+            KeyCode::Char(' ') if list_view.current_profile() == Some(GLOBAL_PROFILE_MARK) => {
+                app.show_error("Cannot select GLOBAL profile");
+            }
+            KeyCode::Char(' ') => {
+                list_view.toggle_mark_current();
+            }
+            KeyCode::Char('v') => {
+                list_view.toggle_visual_mode();
+            }
             KeyCode::Enter => {
                 if let Some(name) = list_view.current_profile() {
                     let name = name.to_string();
                     app.start_editing(&name);
                 }
             }
-            KeyCode::Tab => match app.main_right_view_mode {
-                MainRightViewMode::Raw => {
-                    app.load_expand_vars();
-                }
-                MainRightViewMode::Expand => {
-                    app.unload_expand_vars();
-                }
-            },
+            KeyCode::Tab => {
+                app.switch_right_view();
+            }
+            KeyCode::Char('g') => {
+                list_view.toggle_grouping();
+            }
+            KeyCode::Char('l') => {
+                app.open_status_log();
+            }
+            KeyCode::Char('s') if list_view.marked_count() > 0 => {
+                app.save_marked()?;
+            }
             KeyCode::Char('s') => {
                 app.save_selected()?;
             }
+            KeyCode::Char('D') => {
+                app.diff_with_base();
+            }
+            KeyCode::Char('S') => {
+                app.set_sort_mode(app.list_view.sort_mode().next());
+            }
+            KeyCode::Char('o') => {
+                app.set_sort_mode(app.list_view.sort_mode().toggle_dependency_order());
+            }
+            KeyCode::Char('R') => {
+                app.reload_from_disk();
+            }
+            KeyCode::Char('x') if expanded => {
+                app.expand_view.enter_filter_mode();
+            }
+            KeyCode::Char('w') if expanded => {
+                if let Some(name) = list_view.current_profile() {
+                    let name = name.to_string();
+                    app.expand_view.start_export(&name);
+                    app.state = AppState::Export;
+                }
+            }
+            KeyCode::PageDown if expanded => {
+                app.expand_view.scroll_down(expand::PAGE_SIZE);
+            }
+            KeyCode::PageUp if expanded => {
+                app.expand_view.scroll_up(expand::PAGE_SIZE);
+            }
             KeyCode::Char('w') => {
                 app.save_all()?;
             }
+            // Which marked profiles can go depends on every profile that could use them
+            KeyCode::Char('d') if list_view.marked_count() > 0 => {
+                app.with_all_profiles(FullLoadAction::Delete);
+            }
             KeyCode::Char('d') => {
                 if let Some(name) = list_view.current_profile() {
                     if name == GLOBAL_PROFILE_MARK {
-                        app.status_message = Some("Cannot delete GLOBAL profile".to_string());
+                        app.show_error("Cannot delete GLOBAL profile");
                     } else {
-                        app.state = AppState::ConfirmDelete;
+                        app.start_delete();
                     }
                 }
             }
@@ -532,10 +1003,9 @@ pub fn handle_event(app: &mut App, key: KeyEvent) -> Result<(), Box<dyn std::err
             KeyCode::F(2) => {
                 if let Some(name) = list_view.current_profile() {
                     if name == GLOBAL_PROFILE_MARK {
-                        app.status_message = Some("Cannot rename GLOBAL profile".to_string());
+                        app.show_error("Cannot rename GLOBAL profile");
                     } else {
-                        app.state = AppState::Rename;
-                        list_view.start_rename();
+                        app.with_all_profiles(FullLoadAction::Rename);
                     }
                 }
             }
@@ -545,6 +1015,23 @@ pub fn handle_event(app: &mut App, key: KeyEvent) -> Result<(), Box<dyn std::err
     Ok(())
 }
 
+/// Keys that edit, create, rename, delete or save a profile
+fn is_mutating_key(app: &App, key: KeyEvent, expanded: bool) -> bool {
+    let list_view = &app.list_view;
+    if list_view.is_searching() {
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            return matches!(key.code, KeyCode::Char('d' | 's' | 'w'));
+        }
+        return matches!(key.code, KeyCode::Enter | KeyCode::F(2));
+    }
+    match key.code {
+        KeyCode::Enter => list_view.current_namespace().is_none(),
+        KeyCode::Char('s' | 'd' | 'n') | KeyCode::F(2) => true,
+        KeyCode::Char('w') => !expanded,
+        _ => false,
+    }
+}
+
 pub fn handle_rename_event(app: &mut App, key: KeyEvent) -> Result<(), Box<dyn std::error::Error>> {
     match key.code {
         KeyCode::Char(c) => {
@@ -565,13 +1052,11 @@ pub fn handle_rename_event(app: &mut App, key: KeyEvent) -> Result<(), Box<dyn s
             app.list_view.reset_rename();
             app.state = AppState::List;
         }
-        KeyCode::Enter => {
-            if app.list_view.rename_input_mut().is_valid() {
-                let new_name = app.list_view.rename_input().text().to_string();
-                app.rename_profile(new_name)?;
-                app.list_view.reset_rename();
-                app.state = AppState::List;
-            }
+        KeyCode::Enter if app.list_view.rename_input().is_valid() => {
+            let new_name = app.list_view.rename_input().text().to_string();
+            app.rename_profile(new_name)?;
+            app.list_view.reset_rename();
+            app.state = AppState::List;
         }
         _ => {}
     }
@@ -581,16 +1066,28 @@ pub fn handle_rename_event(app: &mut App, key: KeyEvent) -> Result<(), Box<dyn s
 fn validate_rename_name(app: &mut App) {
     app.list_view.rename_input_mut().clear_error();
 
+    // Renaming back to the name still on disk replaces that file when saved
     if let Some(name) = app.list_view.current_profile()
         && name != app.list_view.rename_input().text()
-        && app
-            .config_manager
-            .has_profile(app.list_view.rename_input().text())
+        && app.is_name_taken(app.list_view.rename_input().text())
+        && app.pending_deletes.get(name).map(String::as_str)
+            != Some(app.list_view.rename_input().text())
     {
         app.list_view
             .rename_input_mut()
             .set_error_message("Profile name already exists");
         return;
     }
+    // Only the profile being renamed may differ from the new name just in case
+    if let Some(existing) = app
+        .config_manager
+        .case_conflict(app.list_view.rename_input().text())
+        && app.list_view.current_profile() != Some(existing.as_str())
+    {
+        app.list_view.rename_input_mut().set_error_message(&format!(
+            "Same file as '{existing}' (names ignore case here)"
+        ));
+        return;
+    }
     crate::tui::utils::validate_input(app.list_view.rename_input_mut());
 }
@@ -1,3 +1,4 @@
 pub mod add_new;
 pub mod edit;
+pub mod expand;
 pub mod list;
@@ -1,146 +1,201 @@
-use crate::tui::app::AppState::{self, List};
-use crate::tui::theme::Theme;
+use crate::tui::app::{App, AppState, MainRightViewMode, StatusSeverity};
+use crate::tui::theme::{KeyRole, Theme};
+use crate::tui::utils;
 use ratatui::prelude::*;
+use ratatui::widgets::Paragraph;
+use unicode_width::UnicodeWidthStr;
 
-pub fn render(frame: &mut Frame<'_>, area: Rect, app: &crate::tui::app::App) {
-    let version_info = Text::from(vec![Line::raw(format!(
-        "Env-Mnage {}",
-        env!("CARGO_PKG_VERSION")
-    ))])
-    .right_aligned();
+/// Rows taken by the bottom bar
+pub const HEIGHT: u16 = 2;
 
-    match app.state {
-        List => list_state(frame, area, app),
-        AppState::Edit => edit_state(frame, area, app),
-        AppState::Rename => rename_state(frame, area),
-        _ => {}
+/// Key hints for the current state on the left, the latest status message (or the
+/// version when there is none) on the right
+pub fn render(frame: &mut Frame<'_>, area: Rect, app: &App) {
+    let right = match &app.status_message {
+        Some(message) => Span::styled(
+            message.text.clone(),
+            severity_style(&app.theme, message.severity),
+        ),
+        None => Span::raw(format!("Env-Mnage {}", env!("CARGO_PKG_VERSION"))),
+    };
+    // Long messages take up to half the bar and wrap onto the second row
+    let right_width = (UnicodeWidthStr::width(right.content.as_ref()) as u16).min(area.width / 2);
+    let [help_area, _, status_area] = Layout::horizontal([
+        Constraint::Fill(1),
+        Constraint::Length(1),
+        Constraint::Length(right_width),
+    ])
+    .areas(area);
+
+    let help_info = match app.state {
+        AppState::List => list_help(app),
+        AppState::Edit => edit_help(app),
+        AppState::Rename | AppState::Export => rename_help(&app.theme),
+        _ => Vec::new(),
+    };
+    let help = Paragraph::new(utils::wrap_help_items(
+        &help_info,
+        help_area.width,
+        HEIGHT as usize,
+    ))
+    .style(app.theme.text_dim());
+    frame.render_widget(help, help_area);
+
+    let status = Paragraph::new(Line::from(right).right_aligned())
+        .wrap(ratatui::widgets::Wrap { trim: true });
+    frame.render_widget(status, status_area);
+}
+
+pub fn severity_style(theme: &Theme, severity: StatusSeverity) -> Style {
+    match severity {
+        StatusSeverity::Info => theme.text_info(),
+        StatusSeverity::Success => theme.text_success(),
+        StatusSeverity::Error => theme.text_error(),
     }
-    frame.render_widget(version_info, area);
 }
 
-fn list_state(frame: &mut Frame<'_>, area: Rect, app: &crate::tui::app::App) {
-    let help_text = if app.list_view.is_searching() {
+fn help_item(
+    theme: &Theme,
+    key: &'static str,
+    role: KeyRole,
+    action: &'static str,
+) -> Vec<Span<'static>> {
+    vec![Span::styled(key, theme.key(role)), Span::raw(action)]
+}
+
+/// A help item for an action that changes profiles, greyed out in read-only mode
+fn change_item(
+    app: &App,
+    key: &'static str,
+    role: KeyRole,
+    action: &'static str,
+) -> Vec<Span<'static>> {
+    if app.read_only {
         vec![
-            Span::styled("Esc", Style::default().fg(Color::Rgb(255, 107, 107))),
-            Span::raw(": Exit Search  "),
-            Span::styled("Enter", Style::default().fg(Color::Rgb(106, 255, 160))),
-            Span::raw(": Edit  "),
-            Span::styled("Tab", Style::default().fg(Color::Rgb(130, 170, 255))),
-            Span::raw(": Switch View  "),
-            Span::styled("↑↓", Style::default().fg(Color::Rgb(255, 138, 199))),
-            Span::raw(": Navigate  "),
-            Span::styled("F2", Style::default().fg(Color::LightYellow)),
-            Span::raw(": Rename  "),
-            Span::styled("^D", Style::default().fg(Color::LightRed)),
-            Span::raw(": Delete  "),
-            Span::styled("^S", Style::default().fg(Color::LightBlue)),
-            Span::raw(": Save  "),
-            Span::styled("^W", Style::default().fg(Color::LightCyan)),
-            Span::raw(": Save All"),
+            Span::styled(key, app.theme.key_unavailable()),
+            Span::styled(action, app.theme.key_unavailable()),
         ]
     } else {
+        help_item(&app.theme, key, role, action)
+    }
+}
+
+fn list_help(app: &App) -> Vec<Vec<Span<'static>>> {
+    let theme = &app.theme;
+    let expanded = app.main_right_view_mode == MainRightViewMode::Expand;
+    if expanded && app.expand_view.is_filtering() {
         vec![
-            Span::styled("Esc", Style::default().fg(Color::Rgb(255, 107, 107))),
-            Span::raw(": Close  "),
-            Span::styled("Enter", Style::default().fg(Color::Rgb(106, 255, 160))),
-            Span::raw(": Edit  "),
-            Span::styled("Tab", Style::default().fg(Color::Rgb(130, 170, 255))),
-            Span::raw(": Switch View  "),
-            Span::styled("↑↓", Style::default().fg(Color::Rgb(255, 138, 199))),
-            Span::raw(": Navigate  "),
-            Span::styled("N", Style::default().fg(Color::LightGreen)),
-            Span::raw(": New  "),
-            Span::styled("F2", Style::default().fg(Color::LightYellow)),
-            Span::raw(": Rename  "),
-            Span::styled("D", Style::default().fg(Color::LightRed)),
-            Span::raw(": Delete  "),
-            Span::styled("S", Style::default().fg(Color::LightBlue)),
-            Span::raw(": Save Selected  "),
-            Span::styled("W", Style::default().fg(Color::LightCyan)),
-            Span::raw(": Save All  "),
-            Span::styled("/", Style::default().fg(Color::LightMagenta)),
-            Span::raw(": Search"),
+            help_item(theme, "Esc", KeyRole::Cancel, ": Clear Filter"),
+            help_item(theme, "Enter", KeyRole::Confirm, ": Apply"),
+            help_item(theme, "↑↓", KeyRole::Navigate, ": Scroll"),
         ]
-    };
-
-    let help = Text::from(Line::from(help_text))
-        .left_aligned()
-        .style(Theme::new().text_dim());
-
-    frame.render_widget(help, area);
+    } else if expanded && !app.list_view.is_searching() {
+        vec![
+            help_item(theme, "Esc", KeyRole::Cancel, ": Close"),
+            change_item(app, "Enter", KeyRole::Confirm, ": Edit"),
+            help_item(theme, "Tab", KeyRole::Focus, ": Switch View"),
+            help_item(theme, "↑↓", KeyRole::Navigate, ": Navigate"),
+            help_item(theme, "PgUp/PgDn", KeyRole::Navigate, ": Scroll"),
+            help_item(theme, "X", KeyRole::Search, ": Filter"),
+            help_item(theme, "W", KeyRole::Export, ": Export"),
+            help_item(theme, "/", KeyRole::Search, ": Search"),
+        ]
+    } else if app.list_view.is_searching() {
+        vec![
+            help_item(theme, "Esc", KeyRole::Cancel, ": Exit Search"),
+            change_item(app, "Enter", KeyRole::Confirm, ": Edit"),
+            help_item(theme, "Tab", KeyRole::Focus, ": Switch View"),
+            help_item(theme, "↑↓", KeyRole::Navigate, ": Navigate"),
+            change_item(app, "F2", KeyRole::Change, ": Rename"),
+            change_item(app, "^D", KeyRole::Delete, ": Delete"),
+            change_item(app, "^S", KeyRole::Edit, ": Save"),
+            change_item(app, "^W", KeyRole::Export, ": Save All"),
+        ]
+    } else if app.list_view.is_visual_mode() {
+        vec![
+            help_item(theme, "Esc/V", KeyRole::Cancel, ": Exit Select"),
+            help_item(theme, "Space", KeyRole::Edit, ": Toggle"),
+            help_item(theme, "↑↓", KeyRole::Navigate, ": Navigate"),
+            change_item(app, "D", KeyRole::Delete, ": Delete Selected"),
+            change_item(app, "S", KeyRole::Edit, ": Save Selected"),
+        ]
+    } else {
+        vec![
+            help_item(theme, "Esc", KeyRole::Cancel, ": Close"),
+            change_item(app, "Enter", KeyRole::Confirm, ": Edit"),
+            help_item(theme, "Tab", KeyRole::Focus, ": Switch View"),
+            help_item(theme, "↑↓", KeyRole::Navigate, ": Navigate"),
+            change_item(app, "N", KeyRole::Create, ": New"),
+            change_item(app, "F2", KeyRole::Change, ": Rename"),
+            change_item(app, "D", KeyRole::Delete, ": Delete"),
+            change_item(app, "S", KeyRole::Edit, ": Save Selected"),
+            help_item(theme, "V", KeyRole::Edit, ": Multi-Select"),
+            help_item(theme, "Shift+D", KeyRole::Search, ": Diff"),
+            help_item(theme, "Shift+S", KeyRole::Edit, ": Sort"),
+            help_item(theme, "O", KeyRole::Edit, ": Layer Order"),
+            help_item(theme, "G", KeyRole::Search, ": Group"),
+            help_item(theme, "Shift+R", KeyRole::Change, ": Reload"),
+            help_item(theme, "L", KeyRole::Change, ": Messages"),
+            change_item(app, "W", KeyRole::Export, ": Save All"),
+            help_item(theme, "A", KeyRole::Create, ": Activate & Quit"),
+            help_item(theme, "^R", KeyRole::Change, ": Recent"),
+            help_item(theme, "/", KeyRole::Search, ": Search"),
+        ]
+    }
 }
 
-fn rename_state(frame: &mut Frame<'_>, area: Rect) {
-    let help = Text::from(Line::from(vec![
-        Span::styled("Esc", Style::default().fg(Color::Rgb(255, 107, 107))),
-        Span::raw(": Cancel  "),
-        Span::styled("Enter", Style::default().fg(Color::Rgb(106, 255, 160))),
-        Span::raw(": Confirm"),
-    ]))
-    .left_aligned()
-    .style(Theme::new().text_dim());
-
-    frame.render_widget(help, area);
+fn rename_help(theme: &Theme) -> Vec<Vec<Span<'static>>> {
+    vec![
+        help_item(theme, "Esc", KeyRole::Cancel, ": Cancel"),
+        help_item(theme, "Enter", KeyRole::Confirm, ": Confirm"),
+    ]
 }
 
-fn edit_state(frame: &mut Frame<'_>, area: Rect, app: &crate::tui::app::App) {
+fn edit_help(app: &App) -> Vec<Vec<Span<'static>>> {
     use crate::tui::views::edit::{EditFocus, EditVariableFocus};
 
-    let help_text = if app.edit_view.is_editing() {
+    let theme = &app.theme;
+
+    if app.edit_view.is_editing() {
         // Editing popup is active - show editing-specific help
         match app.edit_view.variable_column_focus() {
             EditVariableFocus::Key => vec![
-                Span::styled("Esc", Style::default().fg(Color::Rgb(255, 107, 107))),
-                Span::raw(": Cancel  "),
-                Span::styled("Enter", Style::default().fg(Color::Rgb(106, 255, 160))),
-                Span::raw(": Confirm  "),
-                Span::styled("Tab", Style::default().fg(Color::Rgb(130, 170, 255))),
-                Span::raw(": Switch Field"),
+                help_item(theme, "Esc", KeyRole::Cancel, ": Cancel"),
+                help_item(theme, "Enter", KeyRole::Confirm, ": Confirm"),
+                help_item(theme, "Tab", KeyRole::Focus, ": Switch Field"),
             ],
             EditVariableFocus::Value => vec![
-                Span::styled("Esc", Style::default().fg(Color::Rgb(255, 107, 107))),
-                Span::raw(": Cancel  "),
-                Span::styled("Enter", Style::default().fg(Color::Rgb(106, 255, 160))),
-                Span::raw(": Confirm  "),
-                Span::styled("Tab", Style::default().fg(Color::Rgb(130, 170, 255))),
-                Span::raw(": Switch Field"),
+                help_item(theme, "Esc", KeyRole::Cancel, ": Cancel"),
+                help_item(theme, "Enter", KeyRole::Confirm, ": Confirm"),
+                help_item(theme, "Tab", KeyRole::Focus, ": Switch Field"),
             ],
         }
     } else {
         // Navigation mode - show section-specific help
         match app.edit_view.current_focus() {
             EditFocus::Profiles => vec![
-                Span::styled("Esc", Style::default().fg(Color::Rgb(255, 107, 107))),
-                Span::raw(": Back  "),
-                Span::styled("Tab", Style::default().fg(Color::Rgb(130, 170, 255))),
-                Span::raw(": Focus  "),
-                Span::styled("↑/↓", Style::default().fg(Color::Rgb(255, 138, 199))),
-                Span::raw(": Navigate  "),
-                Span::styled("N", Style::default().fg(Color::LightGreen)),
-                Span::raw(": Add Dep  "),
-                Span::styled("D", Style::default().fg(Color::LightRed)),
-                Span::raw(": Del Dep"),
+                help_item(theme, "Esc", KeyRole::Cancel, ": Back"),
+                help_item(theme, "Tab", KeyRole::Focus, ": Focus"),
+                help_item(theme, "↑/↓", KeyRole::Navigate, ": Navigate"),
+                help_item(theme, "Enter", KeyRole::Confirm, ": Open Dep"),
+                help_item(theme, "N", KeyRole::Create, ": Add Dep"),
+                help_item(theme, "D", KeyRole::Delete, ": Del Dep"),
+            ],
+            EditFocus::Description | EditFocus::Tags => vec![
+                help_item(theme, "Esc", KeyRole::Cancel, ": Back"),
+                help_item(theme, "Tab", KeyRole::Focus, ": Focus"),
+                help_item(theme, "←→", KeyRole::Navigate, " : Move cursor"),
             ],
             EditFocus::Variables => vec![
-                Span::styled("Esc", Style::default().fg(Color::Rgb(255, 107, 107))),
-                Span::raw(": Back  "),
-                Span::styled("Tab", Style::default().fg(Color::Rgb(130, 170, 255))),
-                Span::raw(": Focus  "),
-                Span::styled("↑↓←→", Style::default().fg(Color::Rgb(255, 138, 199))),
-                Span::raw(" : Navigate  "),
-                Span::styled("A", Style::default().fg(Color::LightYellow)),
-                Span::raw(": Add Var  "),
-                Span::styled("E", Style::default().fg(Color::LightBlue)),
-                Span::raw(": Edit  "),
-                Span::styled("D", Style::default().fg(Color::LightRed)),
-                Span::raw(": Del Var"),
+                help_item(theme, "Esc", KeyRole::Cancel, ": Back"),
+                help_item(theme, "Tab", KeyRole::Focus, ": Focus"),
+                help_item(theme, "↑↓←→", KeyRole::Navigate, " : Navigate"),
+                help_item(theme, "A", KeyRole::Change, ": Add Var"),
+                help_item(theme, "E", KeyRole::Edit, ": Edit"),
+                help_item(theme, "Shift+E", KeyRole::Edit, ": Multi-line Edit"),
+                help_item(theme, "U", KeyRole::Search, ": Toggle Unset"),
+                help_item(theme, "D", KeyRole::Delete, ": Del Var"),
             ],
         }
-    };
-
-    let help = Text::from(Line::from(help_text))
-        .left_aligned()
-        .style(Theme::new().text_dim());
-
-    frame.render_widget(help, area);
+    }
 }
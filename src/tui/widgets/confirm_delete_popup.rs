@@ -1,80 +1,136 @@
-use crate::tui::{app::App, theme::Theme, utils};
-use ratatui::layout::{Constraint, Layout};
+use crate::tui::{app::App, theme::KeyRole};
+use ratatui::layout::{Constraint, Flex, Layout};
 use ratatui::prelude::*;
-use ratatui::widgets::{Block, Borders, Clear, Paragraph};
-pub fn render(frame: &mut Frame<'_>, app: &App) {
-    // Calculate dynamic dimensions based on content
-    let name = app.list_view.current_profile().unwrap();
-    let text = format!("Are you sure you want to delete '{name}'? (y/n)");
-
-    // Calculate required width
-    let title_width = "Confirm Deletion".len() as u16;
-    let help_width = "Press 'Esc' to exit".len() as u16;
-    let text_width = text.len() as u16;
-
-    let content_width = text_width.max(title_width).max(help_width);
-    let min_width = (content_width + 6).min(frame.area().width.saturating_sub(4));
-
-    // Calculate width percentage (at least 40%, at most 90%)
-    let width_percent = ((min_width * 100) / frame.area().width).clamp(40, 90);
-
-    // Calculate actual available width for text (accounting for borders and padding)
-    let actual_width = (frame.area().width * width_percent / 100).saturating_sub(6);
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
 
-    // Calculate how many lines the text will need
-    let text_lines = if text_width == 0 || text_width <= actual_width {
-        1
-    } else {
-        ((text_width as f32) / (actual_width as f32)).ceil() as u16
+pub fn render(frame: &mut Frame<'_>, app: &App) {
+    let Some(impact) = &app.delete_impact else {
+        return;
+    };
+    let theme = &app.theme;
+    let deletable = impact.deletable().count();
+
+    let join_or_none = |names: &[String]| {
+        if names.is_empty() {
+            "none".to_string()
+        } else {
+            names.join(", ")
+        }
     };
 
-    // Calculate minimum required height in absolute units
-    let min_height = 2 + 1 + text_lines + 1 + 1; // borders + padding + text + padding + help
-
-    // Convert to percentage for centered_rect
-    let height_percent = ((min_height * 100) / frame.area().height).clamp(20, 50);
-
-    let area = utils::centered_rect(width_percent, height_percent, frame.area());
+    let mut lines = Vec::new();
+    if let [name] = impact.names.as_slice() {
+        let dependents = impact.blocked.get(name).map(Vec::as_slice).unwrap_or(&[]);
+        lines.push(Line::from(format!("Delete '{name}'?")).style(theme.text_normal()));
+        lines.push(Line::default());
+        lines.push(
+            Line::from(format!("Variables:  {}", impact.variable_count)).style(theme.text_dim()),
+        );
+        lines.push(
+            Line::from(format!(
+                "Depends on: {}",
+                join_or_none(&impact.dependencies)
+            ))
+            .style(theme.text_dim()),
+        );
+        lines.push(
+            Line::from(format!("Used by:    {}", join_or_none(dependents))).style(
+                if dependents.is_empty() {
+                    theme.text_dim()
+                } else {
+                    theme.text_error()
+                },
+            ),
+        );
+        if !dependents.is_empty() {
+            lines.push(Line::default());
+            lines.push(
+                Line::from("Remove it from the profiles using it first.").style(theme.text_error()),
+            );
+        }
+    } else {
+        lines.push(
+            Line::from(format!(
+                "Delete {deletable} of {} selected profiles?",
+                impact.names.len()
+            ))
+            .style(theme.text_normal()),
+        );
+        lines.push(Line::default());
+        for name in impact.deletable() {
+            lines.push(Line::from(format!("  {name}")).style(theme.text_normal()));
+        }
+        lines.push(Line::default());
+        lines.push(
+            Line::from(format!("Variables:  {}", impact.variable_count)).style(theme.text_dim()),
+        );
+        lines.push(
+            Line::from(format!(
+                "Depends on: {}",
+                join_or_none(&impact.dependencies)
+            ))
+            .style(theme.text_dim()),
+        );
+        if !impact.blocked.is_empty() {
+            lines.push(Line::default());
+            lines.push(Line::from("Skipped, still in use:").style(theme.text_error()));
+            for (name, dependents) in &impact.blocked {
+                lines.push(
+                    Line::from(format!("  {name} (used by {})", dependents.join(", ")))
+                        .style(theme.text_error()),
+                );
+            }
+        }
+    }
+
+    let mut help = Vec::new();
+    if deletable > 0 {
+        help.push(Span::styled("y", theme.key(KeyRole::Cancel)));
+        help.push(Span::raw(": Delete  "));
+    }
+    if let Some(dependent) = impact.first_dependent() {
+        help.push(Span::styled("g", theme.key(KeyRole::Confirm)));
+        help.push(Span::raw(format!(": Edit '{dependent}'  ")));
+    }
+    help.push(Span::styled(
+        if deletable > 0 { "n/Esc" } else { "Esc" },
+        theme.key(KeyRole::Dismiss),
+    ));
+    help.push(Span::raw(": Cancel"));
+    let help = Line::from(help);
+
+    // Size the popup to its content: borders, text, a blank line and the help line
+    let content_width = lines
+        .iter()
+        .chain(std::iter::once(&help))
+        .map(Line::width)
+        .max()
+        .unwrap_or(0) as u16;
+    let width = (content_width + 6).min(frame.area().width);
+    let height = (lines.len() as u16 + 4).min(frame.area().height);
+
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(frame.area());
+    let [area] = Layout::horizontal([Constraint::Length(width)])
+        .flex(Flex::Center)
+        .areas(area);
 
     let block = Block::default()
         .title("Confirm Deletion")
         .borders(Borders::ALL)
-        .border_style(Theme::new().text_error())
+        .border_style(theme.text_error())
         .border_type(ratatui::widgets::BorderType::Thick);
 
-    let inner_area = block.inner(area);
-
-    // Split into content area and help area at the bottom
-    let popup_layout = Layout::vertical([
-        Constraint::Min(0),    // Content area (takes remaining space)
-        Constraint::Length(1), // Help text area (exactly 1 line)
-    ])
-    .split(inner_area);
-
-    let content_area = popup_layout[0];
-    let help_area = popup_layout[1];
-
-    // Vertically center the main text in content area
-    let v_centered_layout = Layout::vertical([
-        Constraint::Percentage(50),
-        Constraint::Length(text_lines),
-        Constraint::Percentage(50),
-    ])
-    .split(content_area);
-
-    let text_area = v_centered_layout[1];
-
-    let main_paragraph = Paragraph::new(text)
-        .alignment(Alignment::Center)
-        .wrap(ratatui::widgets::Wrap { trim: false })
-        .style(Theme::new().text_normal());
-
-    let help_paragraph = Paragraph::new("Press 'Esc' to exit")
-        .alignment(Alignment::Center)
-        .style(Theme::new().text_dim());
+    let inner_area = block.inner(area).inner(Margin {
+        vertical: 0,
+        horizontal: 2,
+    });
+    let [text_area, help_area] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(inner_area);
 
     frame.render_widget(Clear, area);
     frame.render_widget(block, area);
-    frame.render_widget(main_paragraph, text_area);
-    frame.render_widget(help_paragraph, help_area);
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), text_area);
+    frame.render_widget(Paragraph::new(help).alignment(Alignment::Center), help_area);
 }
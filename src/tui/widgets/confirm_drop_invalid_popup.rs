@@ -0,0 +1,52 @@
+use crate::tui::{app::App, theme::KeyRole, utils};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+pub fn render(frame: &mut Frame<'_>, app: &App) {
+    let area = utils::centered_rect(50, 20, frame.area());
+    let theme = &app.theme;
+
+    let block = Block::default()
+        .title("Invalid Variables")
+        .borders(Borders::ALL)
+        .border_style(theme.block_warning())
+        .border_type(ratatui::widgets::BorderType::Thick);
+
+    let inner_area = block.inner(area);
+
+    let popup_layout =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).split(inner_area);
+
+    let content_area = popup_layout[0];
+    let help_area = popup_layout[1];
+
+    let v_centered_layout = Layout::vertical([
+        Constraint::Min(0),
+        Constraint::Length(2),
+        Constraint::Min(0),
+    ])
+    .split(content_area);
+
+    let text_area = v_centered_layout[1];
+
+    let count = app.edit_view.invalid_variable_count();
+    let rows = if count == 1 { "row" } else { "rows" };
+    let text = format!("{count} invalid {rows} will be dropped.\nSave anyway?");
+    let main_paragraph = Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .wrap(ratatui::widgets::Wrap { trim: false })
+        .style(theme.text_normal());
+
+    let help_text = vec![
+        Span::styled("y", theme.key(KeyRole::Confirm)),
+        Span::raw(": Save  "),
+        Span::styled("n", theme.key(KeyRole::Cancel)),
+        Span::raw(": Keep Editing"),
+    ];
+    let help_paragraph = Paragraph::new(Line::from(help_text)).alignment(Alignment::Center);
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+    frame.render_widget(main_paragraph, text_area);
+    frame.render_widget(help_paragraph, help_area);
+}
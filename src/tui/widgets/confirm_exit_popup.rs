@@ -1,15 +1,15 @@
-use crate::tui::{app::App, theme::Theme, utils};
+use crate::tui::{app::App, theme::KeyRole, utils};
 use ratatui::layout::{Constraint, Layout};
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, Clear, Paragraph};
-pub fn render(frame: &mut Frame<'_>, _app: &App) {
+pub fn render(frame: &mut Frame<'_>, app: &App) {
     let area = utils::centered_rect(50, 20, frame.area());
-    let theme = Theme::new();
+    let theme = &app.theme;
 
     let block = Block::default()
         .title("Unsaved Changes")
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Theme::WARNING))
+        .border_style(theme.block_warning())
         .border_type(ratatui::widgets::BorderType::Thick);
 
     let inner_area = block.inner(area);
@@ -36,11 +36,11 @@ pub fn render(frame: &mut Frame<'_>, _app: &App) {
         .style(theme.text_normal());
 
     let help_text = vec![
-        Span::styled("y", Style::default().fg(Color::Rgb(106, 255, 160))),
+        Span::styled("y", theme.key(KeyRole::Confirm)),
         Span::raw(": Save & Quit  "),
-        Span::styled("n", Style::default().fg(Color::Rgb(255, 107, 107))),
+        Span::styled("n", theme.key(KeyRole::Cancel)),
         Span::raw(": Discard & Quit  "),
-        Span::styled("Esc", Style::default().fg(Color::Gray)),
+        Span::styled("Esc", theme.key(KeyRole::Dismiss)),
         Span::raw(": Cancel"),
     ];
     let help_paragraph = Paragraph::new(Line::from(help_text)).alignment(Alignment::Center);
@@ -0,0 +1,101 @@
+use crate::config::diff::ProfileDiff;
+use crate::tui::app::{App, display_name};
+use crate::tui::{
+    theme::{KeyRole, Theme},
+    utils,
+};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+/// The lines of the popup: removals, additions and changes, each under a heading
+fn lines(diff: &ProfileDiff, a: &str, b: &str, theme: &Theme) -> Vec<Line<'static>> {
+    let removed = theme.text_error();
+    let added = theme.text_success();
+    let changed = theme.text_warning();
+
+    let mut lines = Vec::new();
+    if !diff.only_in_a.is_empty() {
+        lines.push(Line::styled(
+            format!("Only in '{a}':"),
+            theme.text_highlight(),
+        ));
+        for (key, value) in &diff.only_in_a {
+            lines.push(Line::styled(format!("- {key} = \"{value}\""), removed));
+        }
+    }
+    if !diff.only_in_b.is_empty() {
+        lines.push(Line::styled(
+            format!("Only in '{b}':"),
+            theme.text_highlight(),
+        ));
+        for (key, value) in &diff.only_in_b {
+            lines.push(Line::styled(format!("+ {key} = \"{value}\""), added));
+        }
+    }
+    if !diff.changed.is_empty() {
+        lines.push(Line::styled("Different values:", theme.text_highlight()));
+        for (key, old, new) in &diff.changed {
+            lines.push(Line::styled(
+                format!("~ {key}: \"{old}\" -> \"{new}\""),
+                changed,
+            ));
+        }
+    }
+    lines
+}
+
+/// How many lines the popup shows for `diff`, the limit for scrolling
+pub fn line_count(diff: &ProfileDiff) -> usize {
+    let section = |entries: usize| if entries == 0 { 0 } else { entries + 1 };
+    section(diff.only_in_a.len()) + section(diff.only_in_b.len()) + section(diff.changed.len())
+}
+
+/// The variables of the diff base compared with another profile
+pub fn render(frame: &mut Frame<'_>, app: &App) {
+    let Some(popup) = &app.diff_popup else {
+        return;
+    };
+    let area = utils::centered_rect(70, 60, frame.area());
+    let theme = &app.theme;
+    let (a, b) = (display_name(&popup.a), display_name(&popup.b));
+
+    let kind = if popup.expand { "resolved" } else { "own" };
+    let block = Block::default()
+        .title(format!("Diff {a} -> {b} ({kind} variables)"))
+        .borders(Borders::ALL)
+        .border_style(theme.block_active())
+        .border_type(ratatui::widgets::BorderType::Thick);
+    let inner_area = block.inner(area);
+    let [text_area, help_area] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(inner_area);
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+
+    if popup.diff.is_empty() {
+        let line =
+            Line::styled("Both profiles set the same variables", theme.text_dim()).centered();
+        super::empty::render(frame, text_area, line, 1);
+    } else {
+        let lines: Vec<Line> = lines(&popup.diff, a, b, theme)
+            .into_iter()
+            .skip(popup.offset)
+            .collect();
+        frame.render_widget(Paragraph::new(lines), text_area);
+    }
+
+    let help = Line::from(vec![
+        Span::styled("Esc", theme.key(KeyRole::Cancel)),
+        Span::raw(": Close  "),
+        Span::styled("↑↓", theme.key(KeyRole::Navigate)),
+        Span::raw(": Scroll  "),
+        Span::styled("E", theme.key(KeyRole::Edit)),
+        Span::raw(if popup.expand {
+            ": Own Variables"
+        } else {
+            ": Resolved Variables"
+        }),
+    ]);
+    frame.render_widget(Paragraph::new(help).centered(), help_area);
+}
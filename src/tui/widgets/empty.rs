@@ -38,3 +38,12 @@ pub fn profile_not_selectable(frame: &mut Frame<'_>, area: Rect) {
         1,
     );
 }
+
+pub fn no_related_profiles(frame: &mut Frame<'_>, area: Rect, message: &'static str) {
+    render(
+        frame,
+        inner(area),
+        Line::styled(message, Style::default().dim()).centered(),
+        1,
+    );
+}
@@ -1,4 +1,4 @@
-use crate::tui::{app::App, theme::Theme};
+use crate::tui::app::App;
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, Paragraph};
 use unicode_width::UnicodeWidthStr;
@@ -10,29 +10,23 @@ pub fn render(frame: &mut Frame<'_>, area: Rect, app: &App) {
         let prefix = "search prompts: ";
 
         let paragraph = Paragraph::new(Line::from(vec![
-            Span::styled(prefix, Theme::new().text_highlight()),
-            Span::styled(search_input.text(), Theme::new().text_normal()), // Use normal or highlight?
+            Span::styled(prefix, app.theme.text_highlight()),
+            Span::styled(search_input.text(), app.theme.text_normal()), // Use normal or highlight?
         ]))
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .border_style(Theme::new().block_active()), // Active color for search
+                .border_style(app.theme.block_active()), // Active color for search
         );
 
         frame.render_widget(paragraph, area);
 
         // Cursor Calculation
         // Calculate the width of text before the cursor
-        let cursor_char_index = search_input.cursor_position();
-        let input_text_before_cursor: String = search_input
-            .text()
-            .chars()
-            .take(cursor_char_index)
-            .collect();
         // Width of "Search: "
         let prefix_width = prefix.width();
         // Width of input text up to cursor
-        let input_width_before_cursor = UnicodeWidthStr::width(input_text_before_cursor.as_str());
+        let input_width_before_cursor = search_input.cursor_display_width();
 
         // Total visual offset x
         let cursor_x = area.x + 1 + (prefix_width + input_width_before_cursor) as u16; // +1 for left border
@@ -42,17 +36,16 @@ pub fn render(frame: &mut Frame<'_>, area: Rect, app: &App) {
         // If overflow, we'd need scrolling. Assuming header width is sufficient for now.
         frame.set_cursor_position((cursor_x, cursor_y));
     } else {
-        let title = if let Some(msg) = &app.status_message {
-            Paragraph::new(Line::from(msg.clone()).centered()).style(Theme::new().text_highlight())
-        } else {
-            Paragraph::new(Line::from("Env-Manage TUI").centered())
-                .style(Theme::new().text_normal())
-        };
+        let mut title = Line::from("Env-Manage TUI");
+        if app.read_only {
+            title.push_span(Span::styled(" [READ-ONLY]", app.theme.text_error()));
+        }
+        let title = Paragraph::new(title.centered()).style(app.theme.text_normal());
 
         let block = title.block(
             Block::default()
                 .borders(Borders::ALL)
-                .border_style(Theme::new().block_active()),
+                .border_style(app.theme.block_active()),
         );
 
         frame.render_widget(block, area);
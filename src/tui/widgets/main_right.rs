@@ -1,24 +1,29 @@
 use super::empty;
 use crate::tui::{
     app::{App, AppState, MainRightViewMode},
-    //    components::edit::{EditFocus, EditVariableFocus}, // Removed unused import
     theme::Theme,
-    utils::inner,
+    utils::{inner, metadata_line, single_line_preview},
 };
 use crate::{GLOBAL_PROFILE_MARK, config::models::Profile};
 use ratatui::prelude::*;
-use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph, Row, Table};
+use ratatui::widgets::{Block, Borders, Cell, List, ListItem, Paragraph, Row, Table, Wrap};
 
 pub fn render(frame: &mut Frame<'_>, area: Rect, app: &App) {
-    let theme = Theme::new();
+    let theme = &app.theme;
 
     if app.list_view.filtered_profiles().is_empty() {
-        render_empty_profiles_view(frame, area, &theme);
+        render_empty_profiles_view(frame, area, theme);
         return;
     }
 
-    // Safe to unwrap because we already checked that filtered_profiles() is not empty
-    let selected_name = app.list_view.current_profile().unwrap();
+    let Some(selected_name) = app.list_view.current_profile() else {
+        if let Some(namespace) = app.list_view.current_namespace() {
+            render_namespace_view(frame, area, app, namespace, theme);
+        } else {
+            render_empty_profiles_view(frame, area, theme);
+        }
+        return;
+    };
 
     let display_name = if selected_name == GLOBAL_PROFILE_MARK {
         "GLOBAL"
@@ -27,7 +32,7 @@ pub fn render(frame: &mut Frame<'_>, area: Rect, app: &App) {
     };
 
     // Check if we are in Edit mode
-    if app.state == AppState::Edit {
+    if matches!(app.state, AppState::Edit | AppState::ConfirmDropInvalid) {
         crate::tui::views::edit::render(frame, area, app);
     } else {
         match app.main_right_view_mode {
@@ -36,15 +41,19 @@ pub fn render(frame: &mut Frame<'_>, area: Rect, app: &App) {
                 let profile = match app.config_manager.get_profile(selected_name) {
                     Some(p) => p,
                     None => {
-                        render_error_state(frame, area, display_name, &theme);
+                        let error = app.load_error(selected_name);
+                        render_error_state(frame, area, display_name, error, theme);
                         return;
                     }
                 };
 
-                render_raw_mode(frame, area, display_name, profile, &theme);
+                render_raw_mode(frame, area, display_name, profile, theme);
             }
             MainRightViewMode::Expand => {
-                render_expand_mode(frame, area, display_name, app, &theme);
+                crate::tui::views::expand::render(frame, area, display_name, app, theme);
+            }
+            MainRightViewMode::Dependents => {
+                render_dependents_mode(frame, area, selected_name, display_name, app, theme);
             }
         }
     }
@@ -65,11 +74,164 @@ fn render_empty_profiles_view(frame: &mut Frame<'_>, area: Rect, theme: &Theme)
     empty::render(frame, inner(inner_area), line, 1);
 }
 
-fn render_error_state(frame: &mut Frame, area: Rect, name: &str, theme: &Theme) {
-    let block = Block::default().borders(Borders::ALL).title("Error");
-    let p = Paragraph::new(format!("Could not find profile '{name}'"))
+/// The profiles in `namespace`, shown while its header is selected
+fn render_namespace_view(
+    frame: &mut Frame<'_>,
+    area: Rect,
+    app: &App,
+    namespace: &str,
+    theme: &Theme,
+) {
+    let prefix = format!("{namespace}/");
+    let items: Vec<ListItem> = app
+        .list_view
+        .all_entries()
+        .iter()
+        .filter_map(|entry| entry.name.strip_prefix(&prefix))
+        .map(|name| ListItem::new(name.to_string()))
+        .collect();
+    let list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme.block_inactive())
+            .title(format!("Namespace '{namespace}'")),
+    );
+    frame.render_widget(list, area);
+}
+
+/// What the selected profile depends on and what depends on it, direct relations first
+fn render_dependents_mode(
+    frame: &mut Frame<'_>,
+    area: Rect,
+    profile_name: &str,
+    display_name: &str,
+    app: &App,
+    theme: &Theme,
+) {
+    let main_block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(theme.block_inactive())
+        .title_top(
+            Line::from(format!("Dependencies of '{display_name}'"))
+                .left_aligned()
+                .style(theme.block_title_inactive()),
+        );
+    let inner_area = main_block.inner(area);
+    frame.render_widget(main_block, area);
+
+    let config_manager = &app.config_manager;
+    let mut direct_dependencies: Vec<String> = config_manager
+        .get_profile(profile_name)
+        .map(|profile| profile.profiles.iter().cloned().collect())
+        .unwrap_or_default();
+    direct_dependencies.sort();
+    let mut all_dependencies = config_manager
+        .resolve_dependencies(profile_name)
+        .unwrap_or_default();
+    all_dependencies.retain(|name| name != profile_name);
+    all_dependencies.sort();
+
+    let mut direct_dependents = config_manager.get_parents(profile_name).unwrap_or_default();
+    direct_dependents.sort();
+    let all_dependents = config_manager
+        .get_all_dependents(profile_name)
+        .unwrap_or_default();
+
+    let [dependents_area, dependencies_area] =
+        Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .areas(inner_area);
+
+    render_related_list(
+        frame,
+        dependents_area,
+        "Used By",
+        &direct_dependents,
+        &all_dependents,
+        "No profile depends on this one",
+        theme,
+    );
+    render_related_list(
+        frame,
+        dependencies_area,
+        "Depends On",
+        &direct_dependencies,
+        &all_dependencies,
+        "This profile depends on no other profile",
+        theme,
+    );
+}
+
+/// `direct` names in normal text, followed by the rest of `all` dimmed as indirect
+fn render_related_list(
+    frame: &mut Frame<'_>,
+    area: Rect,
+    title: &str,
+    direct: &[String],
+    all: &[String],
+    empty_message: &'static str,
+    theme: &Theme,
+) {
+    let indirect: Vec<&String> = all.iter().filter(|name| !direct.contains(name)).collect();
+    let items: Vec<ListItem> = direct
+        .iter()
+        .map(|name| ListItem::new(name.clone()))
+        .chain(indirect.iter().map(|name| {
+            ListItem::new(Line::from(vec![
+                Span::raw(name.to_string()),
+                Span::styled(" (indirect)", theme.text_dim()),
+            ]))
+            .style(theme.text_dim())
+        }))
+        .collect();
+
+    let is_empty = items.is_empty();
+    let list = List::new(items).block(
+        Block::new()
+            .title(format!(
+                "{title} ({} direct, {} indirect)",
+                direct.len(),
+                indirect.len()
+            ))
+            .borders(Borders::ALL)
+            .border_style(theme.block_inactive()),
+    );
+    if is_empty {
+        empty::no_related_profiles(frame, area, empty_message);
+    }
+    frame.render_widget(list, area);
+}
+
+fn render_error_state(
+    frame: &mut Frame,
+    area: Rect,
+    name: &str,
+    load_error: Option<&str>,
+    theme: &Theme,
+) {
+    let Some(load_error) = load_error else {
+        let block = Block::default().borders(Borders::ALL).title("Error");
+        let p = Paragraph::new(format!("Could not find profile '{name}'"))
+            .block(block)
+            .style(theme.text_error());
+        frame.render_widget(p, area);
+        return;
+    };
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("Failed to load '{name}'"));
+    let mut lines: Vec<Line> = load_error
+        .lines()
+        .map(|line| Line::styled(line.to_string(), theme.text_error()))
+        .collect();
+    lines.push(Line::default());
+    lines.push(Line::styled(
+        "Press Enter to edit the profile as stored on disk and save it to repair it.",
+        theme.text_dim(),
+    ));
+    let p = Paragraph::new(lines)
         .block(block)
-        .style(theme.text_error());
+        .wrap(Wrap { trim: false });
     frame.render_widget(p, area);
 }
 
@@ -93,6 +255,18 @@ fn render_raw_mode(
     let inner_area = main_block.inner(area);
     frame.render_widget(main_block, area);
 
+    // Description and tags take a single line above the contents when present
+    let metadata = metadata_line(profile, theme);
+    let [metadata_area, inner_area] = Layout::vertical([
+        Constraint::Length(metadata.is_some() as u16),
+        Constraint::Min(0),
+    ])
+    .areas(inner_area);
+
+    if let Some(metadata) = metadata {
+        frame.render_widget(Paragraph::new(metadata), metadata_area);
+    }
+
     let chunks = Layout::vertical([
         Constraint::Percentage(30), // Inherited Profiles
         Constraint::Percentage(70), // Variables
@@ -103,7 +277,14 @@ fn render_raw_mode(
     let inherited_items: Vec<ListItem> = profile
         .profiles
         .iter()
-        .map(|p_name| ListItem::new(p_name.clone()))
+        .map(|p_name| {
+            let item = ListItem::new(p_name.clone());
+            if profile.is_profile_disabled(p_name) {
+                item.style(theme.text_disabled())
+            } else {
+                item
+            }
+        })
         .collect();
 
     let is_empty = inherited_items.is_empty();
@@ -121,12 +302,24 @@ fn render_raw_mode(
     frame.render_widget(list, chunks[0]);
 
     // Render Variables (View)
-    let mut variables: Vec<_> = profile.variables.iter().collect();
-    variables.sort_by_key(|(k, _)| k.to_string());
+    let variables = profile.variables.iter();
+    let mut unset: Vec<_> = profile.unset.iter().collect();
+    unset.sort();
 
     let var_rows: Vec<Row> = variables
         .into_iter()
-        .map(|(k, v)| Row::new(vec![k.clone(), v.clone()]))
+        .map(|(k, v)| {
+            Row::new(vec![
+                Cell::from(k.clone()),
+                Cell::from(single_line_preview(v)),
+            ])
+        })
+        .chain(unset.into_iter().map(|k| {
+            Row::new(vec![
+                Cell::from(k.clone()),
+                Cell::from("(unset)").style(theme.text_dim()),
+            ])
+        }))
         .collect();
 
     let is_empty = var_rows.is_empty();
@@ -148,55 +341,3 @@ fn render_raw_mode(
     }
     frame.render_widget(table, chunks[1]);
 }
-
-fn render_expand_mode(
-    frame: &mut Frame<'_>,
-    area: Rect,
-    profile_name: &str,
-    app: &App,
-    theme: &Theme,
-) {
-    let title = format!("Expanded for '{profile_name}'");
-    let main_block = Block::default()
-        .borders(Borders::ALL)
-        .border_style(theme.block_inactive())
-        .title_top(
-            Line::from(title)
-                .left_aligned()
-                .style(theme.block_title_inactive()),
-        );
-
-    let inner_area = main_block.inner(area);
-    frame.render_widget(main_block, area);
-
-    if let Some(expanded_vars) = &app.expand_env_vars {
-        let mut variables: Vec<(&String, &String)> = expanded_vars.iter().collect();
-        variables.sort_by_key(|(k, _)| k.to_string());
-
-        let var_rows: Vec<Row> = variables
-            .into_iter()
-            .map(|(k, v)| Row::new(vec![k.clone(), v.clone()]))
-            .collect();
-
-        let is_empty = var_rows.is_empty();
-
-        let table = Table::new(
-            var_rows,
-            [Constraint::Percentage(30), Constraint::Percentage(70)],
-        )
-        .header(Row::new(vec!["Key", "Value"]).style(theme.text_highlight()))
-        .block(
-            Block::new()
-                .title("Variables")
-                .borders(Borders::ALL)
-                .border_style(theme.block_inactive()),
-        );
-
-        if is_empty {
-            empty::variable_not_defined(frame, area);
-        }
-        frame.render_widget(table, inner_area);
-    } else {
-        empty::variable_not_defined(frame, area);
-    }
-}
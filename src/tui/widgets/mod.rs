@@ -1,6 +1,10 @@
 pub mod bottom;
 pub mod confirm_delete_popup;
+pub mod confirm_drop_invalid_popup;
 pub mod confirm_exit_popup;
+pub mod diff_popup;
 pub mod empty;
 pub mod header;
 pub mod main_right;
+pub mod recent_popup;
+pub mod status_log_popup;
@@ -0,0 +1,72 @@
+use crate::GLOBAL_PROFILE_MARK;
+use crate::tui::{app::App, theme::KeyRole, utils};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph};
+
+/// The recently edited profiles, most recent first, numbered for the 1-9 shortcuts
+pub fn render(frame: &mut Frame<'_>, app: &App) {
+    let area = utils::centered_rect(50, 50, frame.area());
+    let theme = &app.theme;
+    frame.render_widget(Clear, area);
+
+    let outer_block = Block::default()
+        .title("Recent Profiles")
+        .borders(Borders::ALL)
+        .border_style(theme.block_active())
+        .border_type(ratatui::widgets::BorderType::Thick);
+    let inner_area = outer_block.inner(area);
+    frame.render_widget(outer_block, area);
+
+    let [list_area, help_area] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(2)]).areas(inner_area);
+
+    let names = app.recent.names();
+    let items: Vec<ListItem> = names
+        .iter()
+        .enumerate()
+        .map(|(idx, name)| {
+            let name = if name == GLOBAL_PROFILE_MARK {
+                "GLOBAL"
+            } else {
+                name
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{} ", idx + 1), theme.text_dim()),
+                Span::raw(name.to_string()),
+            ]))
+        })
+        .collect();
+
+    let left_title = Line::from(format!("{}/{}", app.recent_index + 1, names.len())).left_aligned();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .title_top(left_title)
+                .borders(Borders::ALL)
+                .border_style(theme.block_inactive()),
+        )
+        .highlight_style(theme.row_selected());
+    let mut list_state = ListState::default();
+    list_state.select(Some(app.recent_index));
+    frame.render_stateful_widget(list, list_area, &mut list_state);
+
+    let help_info = [
+        vec![
+            Span::styled("Esc", theme.key(KeyRole::Cancel)),
+            Span::raw(": Close"),
+        ],
+        vec![
+            Span::styled("↑↓", theme.key(KeyRole::Navigate)),
+            Span::raw(": Navigate"),
+        ],
+        vec![
+            Span::styled("Enter", theme.key(KeyRole::Edit)),
+            Span::raw("/"),
+            Span::styled("1-9", theme.key(KeyRole::Edit)),
+            Span::raw(": Open"),
+        ],
+    ];
+    let help_spans = utils::wrap_help_items(&help_info, help_area.width, 2);
+    frame.render_widget(Paragraph::new(help_spans), help_area);
+}
@@ -0,0 +1,65 @@
+use super::bottom;
+use crate::tui::{app::App, theme::KeyRole, utils};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, Paragraph};
+use std::time::Duration;
+
+/// The recent status messages, newest at the top
+pub fn render(frame: &mut Frame<'_>, app: &App) {
+    let area = utils::centered_rect(70, 60, frame.area());
+    let theme = &app.theme;
+
+    let block = Block::default()
+        .title(format!("Messages ({})", app.status_history.len()))
+        .borders(Borders::ALL)
+        .border_style(theme.block_active())
+        .border_type(ratatui::widgets::BorderType::Thick);
+    let inner_area = block.inner(area);
+    let [list_area, help_area] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(inner_area);
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+
+    if app.status_history.is_empty() {
+        let line = Line::styled("No messages yet", theme.text_dim()).centered();
+        super::empty::render(frame, list_area, line, 1);
+    } else {
+        let items: Vec<ListItem> = app
+            .status_history
+            .iter()
+            .rev()
+            .skip(app.status_log_offset)
+            .map(|message| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("{:>8} ", format_age(message.age())),
+                        theme.text_dim(),
+                    ),
+                    Span::styled(
+                        message.text.clone(),
+                        bottom::severity_style(theme, message.severity),
+                    ),
+                ]))
+            })
+            .collect();
+        frame.render_widget(List::new(items), list_area);
+    }
+
+    let help = Line::from(vec![
+        Span::styled("Esc", theme.key(KeyRole::Cancel)),
+        Span::raw(": Close  "),
+        Span::styled("↑↓", theme.key(KeyRole::Navigate)),
+        Span::raw(": Scroll"),
+    ]);
+    frame.render_widget(Paragraph::new(help).centered(), help_area);
+}
+
+fn format_age(age: Duration) -> String {
+    match age.as_secs() {
+        secs if secs < 60 => format!("{secs}s ago"),
+        secs if secs < 3600 => format!("{}m ago", secs / 60),
+        secs => format!("{}h ago", secs / 3600),
+    }
+}
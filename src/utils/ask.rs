@@ -0,0 +1,90 @@
+//! Plain line-based questions on a terminal, for choices made outside the TUI.
+//!
+//! The reader and writer are injected, so the questions can be driven from tests.
+
+use std::io::{self, BufRead, Stderr, StdinLock, Write};
+
+/// The answer to [`Asker::choose`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Choice {
+    /// The index of one of the offered options
+    Option(usize),
+    /// A value typed in instead of picking an option
+    Custom(String),
+}
+
+pub struct Asker<R, W> {
+    reader: R,
+    writer: W,
+}
+
+impl Asker<StdinLock<'static>, Stderr> {
+    /// Ask on stderr and read the answers from stdin, keeping stdout for the output
+    pub fn stdio() -> Self {
+        Self::new(io::stdin().lock(), io::stderr())
+    }
+}
+
+impl<R: BufRead, W: Write> Asker<R, W> {
+    pub fn new(reader: R, writer: W) -> Self {
+        Self { reader, writer }
+    }
+
+    /// Print `prompt` and read one line without its line ending, `None` at end of input
+    pub fn line(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        write!(self.writer, "{prompt}")?;
+        self.writer.flush()?;
+
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let len = line.trim_end_matches(['\n', '\r']).len();
+        line.truncate(len);
+        Ok(Some(line))
+    }
+
+    /// Ask `question`, offering `options` by number and a custom value with `c`.
+    ///
+    /// An empty answer or the end of input picks `default`; any other answer is asked
+    /// again until it is valid.
+    pub fn choose(
+        &mut self,
+        question: &str,
+        options: &[String],
+        default: usize,
+    ) -> io::Result<Choice> {
+        writeln!(self.writer, "{question}")?;
+        for (i, option) in options.iter().enumerate() {
+            writeln!(self.writer, "  {}) {option}", i + 1)?;
+        }
+        writeln!(self.writer, "  c) enter a custom value")?;
+
+        loop {
+            let Some(answer) = self.line(&format!("Choose [{}]: ", default + 1))? else {
+                return Ok(Choice::Option(default));
+            };
+            match answer.trim() {
+                "" => return Ok(Choice::Option(default)),
+                "c" | "C" => {
+                    return Ok(match self.line("Custom value: ")? {
+                        Some(value) => Choice::Custom(value),
+                        None => Choice::Option(default),
+                    });
+                }
+                answer => {
+                    if let Ok(n) = answer.parse::<usize>()
+                        && (1..=options.len()).contains(&n)
+                    {
+                        return Ok(Choice::Option(n - 1));
+                    }
+                    writeln!(
+                        self.writer,
+                        "Please enter a number from 1 to {}, or c.",
+                        options.len()
+                    )?;
+                }
+            }
+        }
+    }
+}
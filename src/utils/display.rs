@@ -1,65 +1,98 @@
+use crate::cli::ColorMode;
 use crate::config::ConfigManager;
+use crate::config::history;
 use crate::config::models::{Profile, ProfileNames};
 use colored::*;
+use std::convert::Infallible;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// How `profile list` prints the profiles
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ListOptions {
+    /// Show the contents of nested profiles as a tree rather than one line each
+    pub expand: bool,
+    /// How many levels of dependencies `expand` shows the contents of, all when `None`
+    pub depth: Option<usize>,
+    /// Print each profile on a single line on stdout, for piping into `grep`
+    pub oneline: bool,
+}
 
 impl ProfileNames {
-    pub fn display_simple(
+    /// Print the profiles as a tree, each header line followed by aligned columns with
+    /// its variable count, direct dependency count and file modification time
+    pub fn display(
         &self,
         config_manager: &ConfigManager,
+        options: &ListOptions,
     ) -> Result<(), Box<dyn std::error::Error>> {
         if self.is_empty() {
             return Ok(());
         }
-
-        eprintln!("{}", "Profiles:".yellow());
-        let mut names_iter = self.iter().peekable();
-        while let Some(name) = names_iter.next() {
-            let is_last_top_level_profile = names_iter.peek().is_none();
-            let top_level_branch = if is_last_top_level_profile {
-                "└──"
-            } else {
-                "├──"
-            };
-            eprintln!("{top_level_branch} {}", name.cyan());
-
-            if let Some(profile_cfg) = config_manager.get_profile(name) {
-                let current_level_indent = if is_last_top_level_profile {
-                    "    "
-                } else {
-                    "│   "
-                };
-                profile_cfg.display_simple_with_indent(current_level_indent);
+        if options.oneline {
+            follow_stdout_color();
+            for name in self.iter() {
+                match config_manager.get_profile(name) {
+                    Some(profile) => profile.display_oneline(name),
+                    None => println!("{}:", name.cyan()),
+                }
             }
-        }
-        Ok(())
-    }
-
-    pub fn display_expand(
-        &self,
-        config_manager: &ConfigManager,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        if self.is_empty() {
             return Ok(());
         }
 
+        let columns: Vec<[String; 4]> = self
+            .iter()
+            .map(|name| {
+                let profile = config_manager.get_profile(name);
+                let count = |n: Option<usize>| n.map_or("-".to_string(), |n| n.to_string());
+                let modified = config_manager
+                    .profile_metadata(name)
+                    .modified
+                    .map_or("-".to_string(), history::timestamp);
+                [
+                    name.clone(),
+                    count(profile.map(|p| p.variables.len())),
+                    count(profile.map(|p| p.profiles.len())),
+                    modified,
+                ]
+            })
+            .collect();
+        // Measured on the plain text, so colors do not shift the columns
+        let width = |i: usize| {
+            columns
+                .iter()
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap_or(0)
+        };
+        let (name_width, vars_width, deps_width) = (width(0), width(1), width(2));
+
         eprintln!("{}", "Profiles:".yellow());
-        let mut names_iter = self.iter().peekable();
-        while let Some(name) = names_iter.next() {
-            let is_last_top_level_profile = names_iter.peek().is_none();
-            let top_level_branch = if is_last_top_level_profile {
-                "└──"
-            } else {
-                "├──"
-            };
-            eprintln!("{top_level_branch} {}", name.cyan());
+        let mut rows = self.iter().zip(&columns).peekable();
+        while let Some((name, [_, vars, deps, modified])) = rows.next() {
+            let is_last_top_level_profile = rows.peek().is_none();
+            let top_level_branch = branch(is_last_top_level_profile);
+            let padding = " ".repeat(name_width - name.chars().count());
+            eprintln!(
+                "{top_level_branch} {}{padding}  {}  {}  {}{}",
+                name.cyan(),
+                format!("{vars:>vars_width$} vars").dimmed(),
+                format!("{deps:>deps_width$} deps").dimmed(),
+                modified.dimmed(),
+                metadata_suffix(config_manager.get_profile(name))
+            );
 
             if let Some(profile_cfg) = config_manager.get_profile(name) {
-                let current_level_indent = if is_last_top_level_profile {
-                    "    "
+                let current_level_indent = pipe(is_last_top_level_profile);
+                if options.expand {
+                    profile_cfg.display_expand_with_indent(
+                        config_manager,
+                        current_level_indent,
+                        options.depth,
+                    )?;
                 } else {
-                    "│   "
-                };
-                profile_cfg.display_expand_with_indent(config_manager, current_level_indent)?;
+                    profile_cfg.display_simple_with_indent(current_level_indent);
+                }
             }
         }
         Ok(())
@@ -67,159 +100,304 @@ impl ProfileNames {
 }
 
 impl Profile {
+    /// Print the profile as a tree with the contents of its dependencies, `depth`
+    /// levels deep (all of them when `None`)
     pub fn display_expand(
         &self,
         config_manager: &ConfigManager,
+        depth: Option<usize>,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        self.display_expand_with_indent(config_manager, "")
+        self.display_expand_with_indent(config_manager, "", depth)
     }
 
     pub fn display_expand_with_indent(
         &self,
-
         config_manager: &ConfigManager,
-
         indent: &str,
+        depth: Option<usize>,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let has_profiles = !self.profiles.is_empty();
-
-        let has_variables = !self.variables.is_empty();
-
-        if !has_profiles && !has_variables {
-            return Ok(());
-        }
-
-        if has_profiles {
-            let profiles_prefix = if has_variables {
-                "├──"
-            } else {
-                "└──"
-            };
-
-            eprintln!("{indent}{profiles_prefix}{}", "profiles".yellow());
-
-            let mut profiles_iter = self.profiles.iter().peekable();
+        self.display_expand_tree(config_manager, indent, depth, &mut Vec::new())
+    }
 
-            let parent_pipe_prefix = if has_variables { "│   " } else { "    " };
+    /// `ancestors` holds the dependencies being printed above this profile, so a
+    /// dependency cycle is marked instead of recursing forever
+    fn display_expand_tree(
+        &self,
+        config_manager: &ConfigManager,
+        indent: &str,
+        depth: Option<usize>,
+        ancestors: &mut Vec<String>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.display_tree(indent, |line, child_indent, profiles| {
+            eprintln!("{line} {}", "profiles".yellow());
 
-            while let Some(profile_name) = profiles_iter.next() {
+            let mut profiles_iter = profiles.iter().peekable();
+            while let Some(&profile_name) = profiles_iter.next() {
                 let is_last_profile = profiles_iter.peek().is_none();
+                let branch_prefix = branch(is_last_profile);
+
+                if self.is_profile_disabled(profile_name) {
+                    eprintln!(
+                        "{child_indent}{branch_prefix} {} {}",
+                        profile_name.strikethrough().dimmed(),
+                        "(disabled)".dimmed()
+                    );
+                    continue;
+                }
+                if ancestors.contains(profile_name) {
+                    eprintln!(
+                        "{child_indent}{branch_prefix} {} {}",
+                        profile_name.cyan(),
+                        "(cycle)".dimmed()
+                    );
+                    continue;
+                }
 
-                let branch_prefix = if is_last_profile {
-                    "└──"
-                } else {
-                    "├──"
-                };
-
-                let next_level_base_indent = format!("{indent}{parent_pipe_prefix}");
-
-                eprintln!(
-                    "{next_level_base_indent}{branch_prefix}{}",
-                    profile_name.cyan()
-                );
-
-                if let Some(nested_profile) = config_manager.get_profile(profile_name) {
-                    let nested_pipe_prefix = if is_last_profile { "    " } else { "│   " };
-
-                    let nested_indent = format!("{next_level_base_indent}{nested_pipe_prefix}");
+                eprintln!("{child_indent}{branch_prefix} {}", profile_name.cyan());
 
-                    if nested_profile.profiles.is_empty() {
-                        nested_profile
-                            .display_expand_with_indent(config_manager, &nested_indent)?;
-                    } else {
-                        nested_profile.display_simple_with_indent(&nested_indent);
+                let Some(nested_profile) = config_manager.get_profile(profile_name) else {
+                    continue;
+                };
+                let nested_indent = format!("{child_indent}{}", pipe(is_last_profile));
+                match depth {
+                    Some(0) => {
+                        if !nested_profile.is_empty() {
+                            eprintln!("{nested_indent}└── {}", "…".dimmed());
+                        }
+                    }
+                    _ => {
+                        ancestors.push(profile_name.clone());
+                        nested_profile.display_expand_tree(
+                            config_manager,
+                            &nested_indent,
+                            depth.map(|depth| depth - 1),
+                            ancestors,
+                        )?;
+                        ancestors.pop();
                     }
                 }
             }
-        }
-
-        if has_variables {
-            let variables_prefix = "└──";
-
-            eprintln!("{}{} {}", indent, variables_prefix, "variables".yellow());
-
-            let mut vars_iter = self.variables.iter().peekable();
-
-            let var_indent = format!("{indent}    ");
+            Ok(())
+        })
+    }
 
-            while let Some((key, value)) = vars_iter.next() {
-                let is_last_var = vars_iter.peek().is_none();
+    pub fn display_simple(&self) {
+        self.display_simple_with_indent("");
+    }
 
-                let var_branch = if is_last_var {
-                    "└──"
-                } else {
-                    "├──"
-                };
+    pub fn display_simple_with_indent(&self, indent: &str) {
+        let Ok(()) = self.display_tree::<Infallible>(indent, |line, _, profiles| {
+            let colored_profiles: Vec<String> = profiles
+                .iter()
+                .map(|p| self.colored_dependency(p))
+                .collect();
+            eprintln!(
+                "{line} {}: [{}]",
+                "profiles".yellow(),
+                colored_profiles.join(", ")
+            );
+            Ok(())
+        });
+    }
 
-                eprintln!(
-                    "{var_indent}{var_branch} {} = {}",
-                    key.green(),
-                    format!("\"{value}\"").truecolor(180, 180, 180)
-                );
-            }
+    /// Print the profile on one line of stdout as `name: KEY=value [deps: a,b]`, followed
+    /// by `[unset: X,Y]` when it unsets variables. Values are quoted and escaped only when
+    /// they would otherwise break the line apart.
+    pub fn display_oneline(&self, name: &str) {
+        let mut line = format!("{}:", name.cyan());
+        for (key, value) in &self.variables {
+            line.push_str(&format!(
+                " {}={}",
+                key.green(),
+                oneline_value(value).truecolor(180, 180, 180)
+            ));
+        }
+        if !self.profiles.is_empty() {
+            let mut profiles: Vec<&String> = self.profiles.iter().collect();
+            profiles.sort();
+            let colored_profiles: Vec<String> = profiles
+                .iter()
+                .map(|p| self.colored_dependency(p))
+                .collect();
+            line.push_str(&format!(
+                " [{}: {}]",
+                "deps".yellow(),
+                colored_profiles.join(",")
+            ));
         }
+        if !self.unset.is_empty() {
+            let colored_keys = self.colored_unset();
+            line.push_str(&format!(
+                " [{}: {}]",
+                "unset".yellow(),
+                colored_keys.join(",")
+            ));
+        }
+        println!("{line}");
+    }
 
-        Ok(())
+    /// A dependency name, struck through when it is disabled
+    fn colored_dependency(&self, name: &str) -> String {
+        if self.is_profile_disabled(name) {
+            name.strikethrough().dimmed().to_string()
+        } else {
+            name.blue().to_string()
+        }
     }
 
-    pub fn display_simple(&self) {
-        self.display_simple_with_indent("");
+    /// The unset keys sorted by name
+    fn colored_unset(&self) -> Vec<String> {
+        let mut keys: Vec<&String> = self.unset.iter().collect();
+        keys.sort();
+        keys.iter().map(|k| k.red().to_string()).collect()
     }
 
-    pub fn display_simple_with_indent(&self, indent: &str) {
-        let has_profiles = !self.profiles.is_empty();
+    /// Print the `profiles`, `unset` and `variables` branches shared by the simple and
+    /// the expanded tree. `display_profiles` prints the profiles branch given the start
+    /// of its line, the indent of the lines below it and the dependencies sorted by name.
+    fn display_tree<E>(
+        &self,
+        indent: &str,
+        display_profiles: impl FnOnce(&str, &str, &[&String]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let has_unset = !self.unset.is_empty();
         let has_variables = !self.variables.is_empty();
 
-        if !has_profiles && !has_variables {
-            return;
+        if !self.profiles.is_empty() {
+            let is_last = !has_unset && !has_variables;
+            let mut profiles: Vec<&String> = self.profiles.iter().collect();
+            profiles.sort();
+            display_profiles(
+                &format!("{indent}{}", branch(is_last)),
+                &format!("{indent}{}", pipe(is_last)),
+                &profiles,
+            )?;
         }
 
-        if has_profiles {
-            let profiles_prefix = if has_variables {
-                "├──"
-            } else {
-                "└──"
-            };
-            let colored_profiles: Vec<String> =
-                self.profiles.iter().map(|p| p.blue().to_string()).collect();
+        if has_unset {
+            let colored_keys = self.colored_unset();
             eprintln!(
-                "{indent}{profiles_prefix} {}: [{}]",
-                "profiles".yellow(),
-                colored_profiles.join(", ")
+                "{indent}{} {}: [{}]",
+                branch(!has_variables),
+                "unset".yellow(),
+                colored_keys.join(", ")
             );
         }
 
         if has_variables {
-            let variables_prefix = "└──";
-            eprintln!("{}{} {}", indent, variables_prefix, "variables".yellow());
-            let mut var_iter = self.variables.iter().peekable();
-            let var_indent = format!("{indent}    ");
-            while let Some((key, value)) = var_iter.next() {
-                let prefix = if var_iter.peek().is_some() {
-                    "├──"
-                } else {
-                    "└──"
-                };
+            eprintln!("{indent}{} {}", branch(true), "variables".yellow());
+            let var_indent = format!("{indent}{}", pipe(true));
+            let mut vars_iter = self.variables.iter().peekable();
+            while let Some((key, value)) = vars_iter.next() {
                 eprintln!(
-                    "{var_indent}{prefix} {} = {}",
+                    "{var_indent}{} {} = {}",
+                    branch(vars_iter.peek().is_none()),
                     key.green(),
                     format!("\"{value}\"").truecolor(180, 180, 180)
                 );
             }
         }
+
+        Ok(())
     }
 }
 
-pub fn show_success(message: &str) {
-    eprintln!("{}", format!("✔ {message}").green());
+/// The branch of a tree line, closing the level on its last line
+fn branch(is_last: bool) -> &'static str {
+    if is_last { "└──" } else { "├──" }
 }
 
-pub fn show_error(message: &str) {
-    eprintln!("{}", format!("✗ {message}").red());
+/// The indent below a tree line, continuing the level's pipe unless it was the last line
+fn pipe(is_last: bool) -> &'static str {
+    if is_last { "    " } else { "│   " }
+}
+
+/// A value as written on a single line: unchanged unless it is empty or holds
+/// whitespace, quotes, backslashes or control characters, which are quoted and escaped
+fn oneline_value(value: &str) -> String {
+    let needs_quotes = value.is_empty()
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || c == '"' || c == '\\');
+    if needs_quotes {
+        format!("{value:?}")
+    } else {
+        value.to_string()
+    }
+}
+
+/// Format the tags and description of a profile for display after its name
+pub fn metadata_suffix(profile: Option<&Profile>) -> String {
+    let Some(profile) = profile else {
+        return String::new();
+    };
+    let mut suffix = String::new();
+    if !profile.tags.is_empty() {
+        let tags: Vec<String> = profile.tags.iter().map(|t| format!("#{t}")).collect();
+        suffix.push_str(&format!(" {}", tags.join(" ").magenta()));
+    }
+    if let Some(description) = &profile.description {
+        suffix.push_str(&format!(" {}", format!("- {description}").dimmed()));
+    }
+    suffix
+}
+
+/// Turn colored output on or off for the rest of the process.
+///
+/// Human output goes to stderr, so `auto` follows stderr rather than stdout like
+/// `colored` would, and is disabled by a non-empty `NO_COLOR`.
+pub fn set_color(mode: ColorMode) {
+    AUTO_COLOR.store(matches!(mode, ColorMode::Auto), Ordering::Relaxed);
+    let enabled = match mode {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => auto_color(std::io::stderr().is_terminal()),
+    };
+    colored::control::set_override(enabled);
+}
+
+/// Whether `--color` was left to `auto`, in which case `follow_stdout_color` may
+/// switch colors for output written to stdout
+static AUTO_COLOR: AtomicBool = AtomicBool::new(true);
+
+/// Color output on stdout by whether stdout is a terminal when `--color` is `auto`, so
+/// piped output stays plain
+pub fn follow_stdout_color() {
+    if AUTO_COLOR.load(Ordering::Relaxed) {
+        colored::control::set_override(auto_color(std::io::stdout().is_terminal()));
+    }
+}
+
+/// Whether `auto` colors output going to a stream, disabled by a non-empty `NO_COLOR`
+fn auto_color(is_terminal: bool) -> bool {
+    std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()) && is_terminal
 }
 
-pub fn show_info(message: &str) {
-    eprintln!("{}", format!("[i] {message}").blue());
+/// How much the CLI reports besides errors, warnings and the output asked for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Verbosity {
+    #[default]
+    Normal,
+    /// `--quiet`: no success or info messages
+    Quiet,
+}
+
+impl Verbosity {
+    pub fn show_success(self, message: &str) {
+        if self == Verbosity::Normal {
+            eprintln!("{}", format!("✔ {message}").green());
+        }
+    }
+
+    pub fn show_info(self, message: &str) {
+        if self == Verbosity::Normal {
+            eprintln!("{}", format!("[i] {message}").blue());
+        }
+    }
+}
+
+pub fn show_error(message: &str) {
+    eprintln!("{}", format!("✗ {message}").red());
 }
 
 pub fn show_warning(message: &str) {
@@ -0,0 +1,14 @@
+/// Render variables as a dotenv file, one `KEY="value"` line per variable.
+///
+/// Values are always double-quoted, with backslashes, quotes and newlines escaped.
+pub fn render<'a>(vars: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+    let mut output = String::new();
+    for (key, value) in vars {
+        let escaped = value
+            .replace('\\', r"\\")
+            .replace('"', "\\\"")
+            .replace('\n', r"\n");
+        output.push_str(&format!("{key}=\"{escaped}\"\n"));
+    }
+    output
+}
@@ -0,0 +1,14 @@
+//! The `--json` output of the CLI, written with `serde_json`.
+
+use serde::Serialize;
+
+/// Version of the `--json` output schema, bumped on incompatible changes
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+/// Serialize `value` as JSON indented by two spaces.
+///
+/// The value goes through a `serde_json::Value` first, whose objects keep their keys
+/// sorted, so the output stays stable for diffing.
+pub fn to_string_pretty<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(&serde_json::to_value(value)?)
+}
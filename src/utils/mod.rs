@@ -1,7 +1,21 @@
 use std::fmt;
 
+use crate::config::models::KeyValidation;
+
+pub mod ask;
 pub mod display;
+pub mod dotenv;
+pub mod json;
+pub mod output;
+pub mod pattern;
+pub mod placeholder;
+pub mod session;
 pub mod shell_generate;
+pub mod shell_init;
+pub mod template;
+
+/// Separates the namespaces of a profile name, as in `work/api`
+pub const NAMESPACE_SEPARATOR: char = '/';
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum IdentifierError {
@@ -13,6 +27,16 @@ pub enum IdentifierError {
     InvalidCharacter(char),
     /// Contains lowercase letters (when uppercase is required)
     ContainsLowercase,
+    /// A namespaced name starting with `/`
+    LeadingSlash,
+    /// A namespaced name with an empty segment, as in `work//api` or `work/`
+    EmptySegment,
+    /// A namespaced name with a `..` segment
+    ParentSegment,
+    /// The name the global profile is stored under, or `global` in any case as a whole name
+    Reserved,
+    /// A marker the shell integration uses internally
+    Internal,
 }
 
 impl fmt::Display for IdentifierError {
@@ -37,6 +61,21 @@ impl fmt::Display for IdentifierError {
             IdentifierError::ContainsLowercase => {
                 write!(f, "Identifier must be all uppercase")
             }
+            IdentifierError::LeadingSlash => {
+                write!(f, "Name cannot start with '/'")
+            }
+            IdentifierError::EmptySegment => {
+                write!(f, "Name cannot contain empty segments between '/'")
+            }
+            IdentifierError::ParentSegment => {
+                write!(f, "Name cannot contain a '..' segment")
+            }
+            IdentifierError::Reserved => {
+                write!(f, "Name is reserved for the global profile")
+            }
+            IdentifierError::Internal => {
+                write!(f, "Name is reserved for internal use")
+            }
         }
     }
 }
@@ -150,9 +189,135 @@ pub fn validate_identifier(
 }
 
 pub fn validate_profile_name(name: &str) -> Result<(), IdentifierError> {
+    if name == crate::GLOBAL_PROFILE_MARK {
+        return Err(IdentifierError::Reserved);
+    }
+    if name == crate::SHELL_MARK {
+        return Err(IdentifierError::Internal);
+    }
     validate_identifier(name, &ValidationConfig::variable_name())
 }
 
+/// Validate a profile name that may be namespaced with `/`, as in `work/api`.
+///
+/// Every segment must be a valid flat profile name, and the whole name cannot be
+/// `global` in any case, which would read as the global profile.
+pub fn validate_namespaced_profile_name(name: &str) -> Result<(), IdentifierError> {
+    if name.eq_ignore_ascii_case("global") {
+        return Err(IdentifierError::Reserved);
+    }
+    if name.starts_with(NAMESPACE_SEPARATOR) {
+        return Err(IdentifierError::LeadingSlash);
+    }
+    for segment in name.split(NAMESPACE_SEPARATOR) {
+        match segment {
+            "" if name.contains(NAMESPACE_SEPARATOR) => return Err(IdentifierError::EmptySegment),
+            ".." => return Err(IdentifierError::ParentSegment),
+            segment => validate_profile_name(segment)?,
+        }
+    }
+    Ok(())
+}
+
+/// Validate a stored variable key, which may carry a `+`/`^` list suffix
 pub fn validate_variable_key(key: &str) -> Result<(), IdentifierError> {
-    validate_identifier(key, &ValidationConfig::variable_name())
+    validate_variable_key_as(key, KeyValidation::Relaxed)
+}
+
+/// Validate a variable key under a profile's key validation. Relaxed keys may also
+/// contain `-`, as they always could.
+pub fn validate_variable_key_as(
+    key: &str,
+    validation: KeyValidation,
+) -> Result<(), IdentifierError> {
+    let (name, _) = crate::config::models::VarOp::parse(key);
+    let config = match validation {
+        KeyValidation::Strict => ValidationConfig::env_var_strict(),
+        KeyValidation::Relaxed => ValidationConfig::variable_name(),
+    };
+    validate_identifier(name, &config)
+}
+
+pub fn validate_tag(tag: &str) -> Result<(), IdentifierError> {
+    validate_identifier(tag, &ValidationConfig::variable_name())
+}
+
+/// Split a comma-separated tag list, trimming whitespace and dropping empty or duplicate tags
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input.split(',').map(str::trim) {
+        if !tag.is_empty() && !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+/// Levenshtein distance between two strings, counted in characters
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+/// The candidate with the smallest edit distance to `target`, first one wins on ties
+pub fn closest_match<'a>(target: &str, candidates: &'a [String]) -> Option<&'a str> {
+    candidates
+        .iter()
+        .min_by_key(|candidate| edit_distance(target, candidate))
+        .map(String::as_str)
+}
+
+/// A close enough `candidate` to offer as a "did you mean" hint for a mistyped `target`
+pub fn suggest_name<'a>(target: &str, candidates: &'a [String]) -> Option<&'a str> {
+    let limit = (target.chars().count() / 3).max(2);
+    closest_match(target, candidates).filter(|candidate| edit_distance(target, candidate) <= limit)
+}
+
+/// Whether `pattern` contains glob wildcards (`*` or `?`)
+pub fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+/// Match `name` against a glob where `*` matches any run of characters and `?` a single one
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and the name position it currently matches up to
+    let mut backtrack = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
 }
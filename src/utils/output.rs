@@ -0,0 +1,17 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Where an export writes to: the file at `path`, creating its missing parent
+/// directories, or stdout when no path is given
+pub fn open_output(path: Option<&Path>) -> io::Result<Box<dyn Write>> {
+    let Some(path) = path else {
+        return Ok(Box::new(io::stdout().lock()));
+    };
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(Box::new(BufWriter::new(File::create(path)?)))
+}
@@ -0,0 +1,35 @@
+//! Regular expressions for `profile search --regex`.
+//!
+//! Matching is unanchored and case-sensitive, and takes time linear in the text
+//! whatever the pattern, so a query like `(a*)*b` cannot hang the search.
+
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub struct PatternError(regex::Error);
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid pattern: {}", self.0)
+    }
+}
+
+impl std::error::Error for PatternError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Pattern(regex::Regex);
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Result<Self, PatternError> {
+        regex::Regex::new(pattern).map(Self).map_err(PatternError)
+    }
+
+    /// Whether the pattern matches anywhere in `text`
+    pub fn is_match(&self, text: &str) -> bool {
+        self.0.is_match(text)
+    }
+}
@@ -0,0 +1,95 @@
+use std::env;
+use std::path::Path;
+
+const OPEN: &str = "${";
+const ESCAPED_OPEN: &str = "$${";
+const CLOSE: char = '}';
+const ENV_PREFIX: &str = "env:";
+
+/// Values of the built-in `${...}` placeholders allowed in profile values.
+///
+/// `${HOME}`, `${CONFIG_DIR}` and `${PROFILE_DIR}` name directories, `${env:NAME}`
+/// reads `NAME` from the invoking environment (empty when it is not set). `${env:}`
+/// names no variable, so like any unknown placeholder it is left as written.
+pub struct Placeholders {
+    home: Option<String>,
+    config_dir: String,
+    profile_dir: String,
+}
+
+impl Placeholders {
+    /// Placeholders for a profile stored in `profile_dir` under the config directory
+    /// `base_path`
+    pub fn new(base_path: &Path, profile_dir: &Path) -> Self {
+        Self {
+            home: dirs::home_dir().map(|home| home.display().to_string()),
+            config_dir: base_path.display().to_string(),
+            profile_dir: profile_dir.display().to_string(),
+        }
+    }
+
+    /// Replace every built-in placeholder in `input` with its value.
+    ///
+    /// Unknown placeholders are left in place and `$${` yields a literal `${`.
+    pub fn expand(&self, input: &str) -> String {
+        scan(input, |name| self.value(name))
+    }
+
+    fn value(&self, name: &str) -> Option<String> {
+        if let Some(var) = name.strip_prefix(ENV_PREFIX) {
+            return (!var.is_empty()).then(|| env::var(var).unwrap_or_default());
+        }
+        match name {
+            "HOME" => self.home.clone(),
+            "CONFIG_DIR" => Some(self.config_dir.clone()),
+            "PROFILE_DIR" => Some(self.profile_dir.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// The names of the placeholders in `input` that are not built in
+pub fn unknown_placeholders(input: &str) -> Vec<String> {
+    let mut unknown = Vec::new();
+    scan(input, |name| {
+        let known = matches!(name, "HOME" | "CONFIG_DIR" | "PROFILE_DIR")
+            || name
+                .strip_prefix(ENV_PREFIX)
+                .is_some_and(|var| !var.is_empty());
+        if !known {
+            unknown.push(name.to_string());
+        }
+        None
+    });
+    unknown
+}
+
+/// Copy `input`, replacing each `${NAME}` for which `value` returns something
+fn scan(input: &str, mut value: impl FnMut(&str) -> Option<String>) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find('$') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(after) = rest.strip_prefix(ESCAPED_OPEN) {
+            output.push_str(OPEN);
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix(OPEN)
+            && let Some(end) = after.find(CLOSE)
+        {
+            match value(&after[..end]) {
+                Some(replacement) => output.push_str(&replacement),
+                None => output.push_str(&rest[..OPEN.len() + end + 1]),
+            }
+            rest = &after[end + 1..];
+        } else {
+            output.push('$');
+            rest = &rest[1..];
+        }
+    }
+
+    output.push_str(rest);
+    output
+}
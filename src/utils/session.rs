@@ -0,0 +1,138 @@
+use super::shell_generate::ShellGenerate;
+use std::collections::HashMap;
+use std::env;
+
+/// Environment variable holding the profiles activated in the current shell session
+pub const ACTIVE_PROFILES_VAR: &str = "__ENV_MANAGE_ACTIVE_PROFILES";
+/// Environment variable holding the keys set by env-manage in the current shell session
+pub const TRACKED_VARS_VAR: &str = "__ENV_MANAGE_TRACKED_VARS";
+/// Prefix of the variables that remember a key's value from before it was activated
+const ORIGINAL_VALUE_PREFIX: &str = "__ENV_MANAGE_ORIG_";
+/// Prefix of the variables that remember which value won when activated profiles disagreed
+const CHOICE_PREFIX: &str = "__ENV_MANAGE_CHOICE_";
+const SEPARATOR: char = ':';
+
+/// Tracks what env-manage has activated in the current shell session.
+///
+/// The state lives in the shell itself: it is read from the environment of the
+/// current process and written back through `ShellGenerate`.
+#[derive(Debug, Default)]
+pub struct SessionState {
+    active_profiles: Vec<String>,
+    tracked_vars: Vec<String>,
+}
+
+impl SessionState {
+    pub fn from_env() -> Self {
+        Self {
+            active_profiles: read_list(ACTIVE_PROFILES_VAR),
+            tracked_vars: read_list(TRACKED_VARS_VAR),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.active_profiles.is_empty() && self.tracked_vars.is_empty()
+    }
+
+    pub fn active_profiles(&self) -> &[String] {
+        &self.active_profiles
+    }
+
+    pub fn tracked_vars(&self) -> &[String] {
+        &self.tracked_vars
+    }
+
+    /// Record an activation, remembering the pre-existing value of every newly tracked key
+    pub fn track(
+        &mut self,
+        profiles: &[String],
+        vars: &HashMap<String, String>,
+        generate: &mut ShellGenerate,
+    ) {
+        for profile in profiles {
+            if !self.active_profiles.contains(profile) {
+                self.active_profiles.push(profile.clone());
+            }
+        }
+
+        let mut keys: Vec<&String> = vars.keys().collect();
+        keys.sort();
+        for key in keys {
+            if self.tracked_vars.contains(key) {
+                continue;
+            }
+            if let Ok(current) = env::var(key) {
+                generate.export(&original_value_var(key), &current);
+            }
+            self.tracked_vars.push(key.clone());
+        }
+    }
+
+    /// Record a deactivation, restoring pre-existing values and unsetting everything else
+    pub fn untrack(&mut self, profiles: &[String], keys: &[String], generate: &mut ShellGenerate) {
+        for key in keys {
+            restore_or_unset(key, generate);
+            if env::var_os(choice_var(key)).is_some() {
+                generate.unset(&choice_var(key));
+            }
+        }
+        self.tracked_vars.retain(|k| !keys.contains(k));
+        self.active_profiles.retain(|p| !profiles.contains(p));
+    }
+
+    /// Emit the commands that persist this state in the shell
+    pub fn write(&self, generate: &mut ShellGenerate) {
+        write_list(ACTIVE_PROFILES_VAR, &self.active_profiles, generate);
+        write_list(TRACKED_VARS_VAR, &self.tracked_vars, generate);
+    }
+}
+
+/// The value chosen earlier in this session for `key`, when activated profiles disagreed on it
+pub fn remembered_choice(key: &str) -> Option<String> {
+    env::var(choice_var(key)).ok()
+}
+
+/// Remember `value` as the winner for `key` until the variable is deactivated
+pub fn remember_choice(key: &str, value: &str, generate: &mut ShellGenerate) {
+    generate.export(&choice_var(key), value);
+}
+
+fn choice_var(key: &str) -> String {
+    format!("{CHOICE_PREFIX}{key}")
+}
+
+fn original_value_var(key: &str) -> String {
+    format!("{ORIGINAL_VALUE_PREFIX}{key}")
+}
+
+fn restore_or_unset(key: &str, generate: &mut ShellGenerate) {
+    let original_var = original_value_var(key);
+    if let Ok(original) = env::var(&original_var) {
+        generate.export(key, &original);
+        generate.unset(&original_var);
+    } else {
+        generate.unset(key);
+    }
+}
+
+fn read_list(var: &str) -> Vec<String> {
+    env::var(var)
+        .map(|value| {
+            value
+                .split(SEPARATOR)
+                .filter(|item| !item.is_empty())
+                .map(|item| item.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn write_list(var: &str, items: &[String], generate: &mut ShellGenerate) {
+    if items.is_empty() {
+        if env::var(var).is_ok() {
+            generate.unset(var);
+        }
+    } else {
+        generate.export(var, &items.join(&SEPARATOR.to_string()));
+    }
+}
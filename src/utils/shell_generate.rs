@@ -1,5 +1,10 @@
 use crate::SHELL_MARK;
-use std::{collections::HashMap, env};
+use crate::config::models::computed_command;
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    path::Path,
+};
 
 #[derive(Debug, Clone, Copy)]
 pub enum ShellType {
@@ -7,11 +12,12 @@ pub enum ShellType {
     Zsh,
     Fish,
     PowerShell,
+    Nu,
 }
 
 impl ShellType {
     fn unsupported_shell_error(shell: &str) -> String {
-        const SUPPORTED: &[&str] = &["bash", "zsh", "fish", "powershell", "pwsh"];
+        const SUPPORTED: &[&str] = &["bash", "zsh", "fish", "powershell", "pwsh", "nu"];
 
         let shells_list = SUPPORTED
             .iter()
@@ -26,17 +32,57 @@ impl ShellType {
             "
         )
     }
-    fn detect() -> Self {
+    /// The name `init` and `--shell` take for this shell
+    pub fn name(&self) -> &'static str {
+        match self {
+            ShellType::Bash => "bash",
+            ShellType::Zsh => "zsh",
+            ShellType::Fish => "fish",
+            ShellType::PowerShell => "powershell",
+            ShellType::Nu => "nu",
+        }
+    }
+
+    /// The shell the commands are generated for.
+    ///
+    /// `EM_SHELL`, set by the shell hook, wins. Otherwise the parent process and then
+    /// `$SHELL` are inspected, falling back to bash-compatible output.
+    pub fn detect() -> Self {
         if let Ok(shell_type) = env::var("EM_SHELL") {
             return match shell_type.to_lowercase().as_str() {
                 "fish" => ShellType::Fish,
                 "pwsh" | "powershell" => ShellType::PowerShell,
                 "bash" => ShellType::Bash,
                 "zsh" => ShellType::Zsh,
+                "nu" | "nushell" => ShellType::Nu,
                 _ => ShellType::Bash,
             };
         }
-        ShellType::Bash
+        parent_process_name()
+            .and_then(|name| Self::from_program(&name))
+            .or_else(|| {
+                env::var("SHELL")
+                    .ok()
+                    .and_then(|path| Self::from_program(&path))
+            })
+            .unwrap_or(ShellType::Bash)
+    }
+
+    /// The shell a program path or process name such as `/usr/bin/fish` or `-zsh` refers to
+    fn from_program(program: &str) -> Option<Self> {
+        let name = Path::new(program.trim())
+            .file_name()?
+            .to_str()?
+            .trim_start_matches('-')
+            .to_lowercase();
+        match name.strip_suffix(".exe").unwrap_or(&name) {
+            "bash" | "sh" | "dash" => Some(ShellType::Bash),
+            "zsh" => Some(ShellType::Zsh),
+            "fish" => Some(ShellType::Fish),
+            "pwsh" | "powershell" => Some(ShellType::PowerShell),
+            "nu" => Some(ShellType::Nu),
+            _ => None,
+        }
     }
 
     fn export_cmd(&self, key: &str, value: &str) -> String {
@@ -60,6 +106,32 @@ impl ShellType {
                     .replace('$', "`$");
                 format!("$env:{key}=\"{escaped_value}\"")
             }
+            Self::Nu => format!("$env.{key} = {}", nu_string(value)),
+        }
+    }
+
+    /// Set `key` to the output of `command`, run by the shell itself.
+    ///
+    /// The command is passed to the shell's `eval` as a single quoted string, so no
+    /// quote or parenthesis in it can end the substitution early.
+    fn computed_cmd(&self, key: &str, command: &str) -> String {
+        match self {
+            Self::Bash | Self::Zsh => {
+                let escaped_command = command.replace('\'', r"'\''");
+                format!("export {key}=\"$(eval '{escaped_command}')\"")
+            }
+            Self::Fish => {
+                let escaped_command = command.replace('\\', r"\\").replace('\'', r"\'");
+                format!("set -gx {key} (eval '{escaped_command}' | string collect)")
+            }
+            Self::PowerShell => {
+                let escaped_command = command.replace('\'', "''");
+                format!("$env:{key}=(Invoke-Expression '{escaped_command}' | Out-String).TrimEnd()")
+            }
+            Self::Nu => format!(
+                "$env.{key} = (nu -c {} | str trim --right)",
+                nu_string(command)
+            ),
         }
     }
 
@@ -68,10 +140,56 @@ impl ShellType {
             Self::Bash | Self::Zsh => format!("unset {key}"),
             Self::Fish => format!("set -e {key}"),
             Self::PowerShell => format!("Remove-Item Env:{key}"),
+            Self::Nu => format!("hide-env -i {key}"),
+        }
+    }
+
+    /// Chain hook commands so later ones only run when the earlier ones succeed,
+    /// where the shell has an operator for it.
+    ///
+    /// Nushell gets one `hook "..."` line per command instead, which the `em` wrapper
+    /// of the README runs with `nu -c`: it applies every other line as a variable.
+    fn hook_cmd(&self, commands: &[String]) -> String {
+        match self {
+            Self::Bash | Self::Zsh | Self::Fish => commands.join(" && "),
+            Self::PowerShell => commands.join("\n"),
+            Self::Nu => commands
+                .iter()
+                .map(|command| format!("{NU_HOOK_PREFIX}{}", nu_string(command)))
+                .collect::<Vec<_>>()
+                .join("\n"),
         }
     }
 }
 
+/// Start of the lines that run a hook command in Nushell
+const NU_HOOK_PREFIX: &str = "hook ";
+
+/// `text` as a double-quoted Nushell string, which is also valid nuon, so a wrapper can
+/// read it back with `from nuon`
+fn nu_string(text: &str) -> String {
+    let escaped = text
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('\n', r"\n")
+        .replace('\r', r"\r")
+        .replace('\t', r"\t");
+    format!("\"{escaped}\"")
+}
+
+/// Name of the process that started env-manage, where the OS exposes it
+fn parent_process_name() -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        let ppid = std::os::unix::process::parent_id();
+        std::fs::read_to_string(format!("/proc/{ppid}/comm")).ok()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
 impl TryFrom<&str> for ShellType {
     type Error = String;
 
@@ -81,11 +199,18 @@ impl TryFrom<&str> for ShellType {
             "pwsh" | "powershell" => Ok(ShellType::PowerShell),
             "bash" => Ok(ShellType::Bash),
             "zsh" => Ok(ShellType::Zsh),
+            "nu" | "nushell" => Ok(ShellType::Nu),
             _ => Err(Self::unsupported_shell_error(value)),
         }
     }
 }
 
+/// The commands in `output` that the shell hook evaluates, i.e. everything after the
+/// leading marker; output without the marker is shown to the user instead
+pub fn marked_script(output: &str) -> Option<&str> {
+    output.strip_prefix(SHELL_MARK)
+}
+
 pub struct ShellGenerate {
     shell: ShellType,
     commands: Vec<String>,
@@ -99,8 +224,21 @@ impl Default for ShellGenerate {
 
 impl ShellGenerate {
     pub fn new() -> Self {
+        Self::with_shell(ShellType::detect())
+    }
+
+    /// Generate for `shell` when given, otherwise for the detected shell
+    pub fn for_shell(shell: Option<&str>) -> Result<Self, String> {
+        let shell = match shell {
+            Some(shell) => ShellType::try_from(shell)?,
+            None => ShellType::detect(),
+        };
+        Ok(Self::with_shell(shell))
+    }
+
+    pub fn with_shell(shell: ShellType) -> Self {
         ShellGenerate {
-            shell: ShellType::detect(),
+            shell,
             commands: Vec::new(),
         }
     }
@@ -110,31 +248,69 @@ impl ShellGenerate {
         self
     }
 
+    /// Set `key` to the output of `command`, computed in the shell when the script runs
+    pub fn export_computed(&mut self, key: &str, command: &str) -> &mut Self {
+        self.commands.push(self.shell.computed_cmd(key, command));
+        self
+    }
+
     pub fn unset(&mut self, key: &str) -> &mut Self {
         self.commands.push(self.shell.unset_cmd(key));
         self
     }
 
+    /// Run the hook `commands` of one profile, as written
+    pub fn run_hook(&mut self, commands: &[String]) -> &mut Self {
+        if !commands.is_empty() {
+            self.commands.push(self.shell.hook_cmd(commands));
+        }
+        self
+    }
+
+    /// Export every variable, in key order so the generated script is stable.
+    /// Computed values are left to the shell to run.
     pub fn export_from_map(&mut self, vars: &HashMap<String, String>) -> &mut Self {
-        for (key, value) in vars {
-            self.export(key, value);
+        let mut keys: Vec<&String> = vars.keys().collect();
+        keys.sort();
+        for key in keys {
+            match computed_command(&vars[key]) {
+                Some(command) => self.export_computed(key, command),
+                None => self.export(key, &vars[key]),
+            };
         }
         self
     }
 
     pub fn unset_from_map(&mut self, vars: &HashMap<String, String>) -> &mut Self {
-        for key in vars.keys() {
+        let mut keys: Vec<&String> = vars.keys().collect();
+        keys.sort();
+        for key in keys {
+            self.unset(key);
+        }
+        self
+    }
+
+    /// Unset every key, in key order
+    pub fn unset_from_set(&mut self, keys: &HashSet<String>) -> &mut Self {
+        let mut keys: Vec<&String> = keys.iter().collect();
+        keys.sort();
+        for key in keys {
             self.unset(key);
         }
         self
     }
 
+    /// The generated commands alone, without the marker the shell hook looks for
+    pub fn script(&self) -> String {
+        self.commands.join("\n")
+    }
+
     pub fn build(&self) -> String {
         if self.commands.is_empty() {
             return String::new();
         }
 
-        format!("{SHELL_MARK}\n{}", self.commands.join("\n"))
+        format!("{SHELL_MARK}\n{}", self.script())
     }
 
     pub fn output(&self) {
@@ -143,4 +319,12 @@ impl ShellGenerate {
             print!("{}", result);
         }
     }
+
+    /// Print the commands without the marker, so the shell hook shows them instead of
+    /// evaluating them
+    pub fn output_preview(&self) {
+        if !self.commands.is_empty() {
+            println!("{}", self.script());
+        }
+    }
 }
@@ -0,0 +1,57 @@
+//! The shell integration `init` installs: a function wrapping the binary that
+//! evaluates the commands it prints, one per supported shell.
+
+use crate::SHELL_MARK;
+use crate::utils::shell_generate::ShellType;
+
+const POSIX_SHELL_WRAPPER_TEMPLATE: &str = include_str!("../../templates/posix.sh");
+const FISH_SHELL_WRAPPER_TEMPLATE: &str = include_str!("../../templates/fish.fish");
+const POWERSHELL_WRAPPER_TEMPLATE: &str = include_str!("../../templates/powershell.ps1");
+
+/// The line to put in the shell's startup file, which loads [`integration`] on every start
+pub fn loader_line(shell: ShellType, binary_path: &str) -> Result<String, String> {
+    let name = shell.name();
+    match shell {
+        ShellType::Bash | ShellType::Zsh => Ok(format!(
+            "eval -- \"$({binary_path} init {name} --print-full-init)\""
+        )),
+        ShellType::Fish => Ok(format!(
+            "{binary_path} init {name} --print-full-init | source"
+        )),
+        ShellType::PowerShell => Ok(format!(
+            "Invoke-Expression (& '{binary_path}' init {name} --print-full-init | Out-String)"
+        )),
+        ShellType::Nu => Err(nu_unsupported()),
+    }
+}
+
+/// The full shell integration for `shell`, calling the binary at `binary_path`
+pub fn integration(shell: ShellType, binary_path: &str) -> Result<String, String> {
+    let name = shell.name();
+    let marker_length = SHELL_MARK.len();
+    let template = match shell {
+        ShellType::Bash | ShellType::Zsh => POSIX_SHELL_WRAPPER_TEMPLATE.to_string(),
+        // fish string sub is 1-based index
+        ShellType::Fish => FISH_SHELL_WRAPPER_TEMPLATE.replace(
+            "{{MARKER_LENGTH_PLUS_ONE}}",
+            &(marker_length + 1).to_string(),
+        ),
+        ShellType::PowerShell => POWERSHELL_WRAPPER_TEMPLATE.to_string(),
+        ShellType::Nu => return Err(nu_unsupported()),
+    };
+    let binary_path = match shell {
+        ShellType::PowerShell => binary_path.replace('"', "`\""),
+        _ => binary_path.to_string(),
+    };
+
+    Ok(template
+        .replace("{{SHELL_TYPE}}", name)
+        .replace("{{BINARY_PATH}}", &binary_path)
+        .replace("{{SHELL_CMD_MARKER}}", SHELL_MARK)
+        .replace("{{MARKER_LENGTH}}", &marker_length.to_string()))
+}
+
+fn nu_unsupported() -> String {
+    "nushell cannot evaluate generated commands, see the README for a wrapper using --shell nu"
+        .to_string()
+}
@@ -0,0 +1,38 @@
+use std::collections::{BTreeSet, HashMap};
+
+const OPEN: &str = "{{";
+const CLOSE: &str = "}}";
+
+/// Replace every `{{NAME}}` placeholder in `input` with its value from `values`.
+///
+/// Placeholders without a value are left in place and their names are added to
+/// `unresolved`, so callers can report all of them at once.
+pub fn substitute(
+    input: &str,
+    values: &HashMap<String, String>,
+    unresolved: &mut BTreeSet<String>,
+) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find(OPEN) {
+        let after_open = &rest[start + OPEN.len()..];
+        let Some(end) = after_open.find(CLOSE) else {
+            break;
+        };
+
+        output.push_str(&rest[..start]);
+        let name = after_open[..end].trim();
+        match values.get(name) {
+            Some(value) => output.push_str(value),
+            None => {
+                unresolved.insert(name.to_string());
+                output.push_str(&rest[start..start + OPEN.len() + end + CLOSE.len()]);
+            }
+        }
+        rest = &after_open[end + CLOSE.len()..];
+    }
+
+    output.push_str(rest);
+    output
+}
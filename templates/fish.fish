@@ -15,4 +15,13 @@ function em
     return $cmd_status
 end
 
+# Set EM_PROMPT before init to show the active profiles in the prompt
+if set -q EM_PROMPT; and functions -q fish_prompt
+    functions -c fish_prompt __em_original_fish_prompt
+    function fish_prompt
+        {{BINARY_PATH}} prompt --format "({names}) "
+        __em_original_fish_prompt
+    end
+end
+
 em global init
@@ -14,4 +14,12 @@ function em() {
     return $exit_code
 }
 
+# Set EM_PROMPT before init to show the active profiles in the prompt
+if [[ -n "${EM_PROMPT:-}" ]]; then
+    if [[ "{{SHELL_TYPE}}" == "zsh" ]]; then
+        setopt prompt_subst
+    fi
+    PS1='$({{BINARY_PATH}} prompt --format "({names}) ")'"$PS1"
+fi
+
 em global init
\ No newline at end of file
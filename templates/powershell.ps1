@@ -27,4 +27,13 @@ function em {
     $global:LASTEXITCODE = $exitCode
 }
 
+# Set EM_PROMPT before init to show the active profiles in the prompt
+if ($env:EM_PROMPT) {
+    $global:EmOriginalPrompt = $function:prompt
+    function global:prompt {
+        $profiles = & "{{BINARY_PATH}}" prompt --format "({names}) "
+        "$profiles$(& $global:EmOriginalPrompt)"
+    }
+}
+
 em global init
@@ -0,0 +1,96 @@
+//! `activate --interactive-conflicts` settles variables that profiles disagree on.
+//!
+//! The tests run without a terminal, so they cover the fallback and remembered answers.
+
+mod common;
+
+use common::{TempDir, run, stderr, stdout, write_profile};
+use std::process::Output;
+
+fn setup(name: &str) -> TempDir {
+    let dir = TempDir::new(name);
+    write_profile(&dir, "a", "[variables]\nPORT = \"1\"\nHOST = \"local\"\n");
+    write_profile(&dir, "b", "[variables]\nPORT = \"2\"\nHOST = \"local\"\n");
+    write_profile(&dir, "c", "unset = [\"PORT\"]\n");
+    dir
+}
+
+fn activate(dir: &TempDir, args: &[&str], env: &[(&str, &str)]) -> Output {
+    let output = common::command(dir)
+        .args(["activate", "--shell", "bash"])
+        .args(args)
+        .env_remove("__ENV_MANAGE_CHOICE_PORT")
+        .envs(env.iter().copied())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    output
+}
+
+#[test]
+fn without_a_terminal_the_last_profile_wins_with_a_warning() {
+    let dir = setup("conflicts-no-tty");
+    let output = activate(&dir, &["a", "b", "--interactive-conflicts"], &[]);
+    assert!(stdout(&output).contains("export PORT='2'"));
+    // Only variables with different values are conflicts
+    assert!(
+        stderr(&output).contains(
+            "Cannot ask which value wins without a terminal, the last profile wins for: PORT"
+        ),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn without_the_flag_nothing_is_reported() {
+    let dir = setup("conflicts-off");
+    let output = activate(&dir, &["a", "b"], &[]);
+    assert!(stdout(&output).contains("export PORT='2'"));
+    assert!(!stderr(&output).contains("Cannot ask"));
+}
+
+#[test]
+fn a_remembered_choice_is_used_without_asking() {
+    let dir = setup("conflicts-remembered");
+    let output = activate(
+        &dir,
+        &["a", "b", "--interactive-conflicts"],
+        &[("__ENV_MANAGE_CHOICE_PORT", "1")],
+    );
+    assert!(stdout(&output).contains("export PORT='1'"));
+    assert!(!stderr(&output).contains("Cannot ask"));
+
+    // Without the flag, the usual precedence applies
+    let output = activate(&dir, &["a", "b"], &[("__ENV_MANAGE_CHOICE_PORT", "1")]);
+    assert!(stdout(&output).contains("export PORT='2'"));
+}
+
+#[test]
+fn a_variable_unset_by_a_later_profile_is_not_a_conflict() {
+    let dir = setup("conflicts-unset");
+    let output = activate(&dir, &["a", "b", "c", "--interactive-conflicts"], &[]);
+    assert!(!stdout(&output).contains("export PORT="));
+    assert!(!stderr(&output).contains("Cannot ask"));
+}
+
+#[test]
+fn remember_requires_interactive_conflicts() {
+    let dir = setup("conflicts-remember-alone");
+    let output = run(dir.path(), &["activate", "a", "b", "--remember-conflicts"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn deactivating_forgets_the_choices() {
+    let dir = setup("conflicts-forget");
+    let output = common::command(dir.path())
+        .args(["deactivate", "--shell", "bash", "--all"])
+        .env("__ENV_MANAGE_ACTIVE_PROFILES", "a")
+        .env("__ENV_MANAGE_TRACKED_VARS", "PORT")
+        .env("__ENV_MANAGE_CHOICE_PORT", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    assert!(stdout(&output).contains("unset __ENV_MANAGE_CHOICE_PORT"));
+}
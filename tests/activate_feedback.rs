@@ -0,0 +1,69 @@
+//! Activation explains itself when it would otherwise change nothing or silently fail.
+
+mod common;
+
+use common::{TempDir, run, stderr, write_profile};
+
+fn setup(name: &str) -> TempDir {
+    let dir = TempDir::new(name);
+    write_profile(&dir, "empty", "");
+    write_profile(&dir, "base", "[variables]\nA = \"1\"\nB = \"2\"\n");
+    write_profile(&dir, "over", "[variables]\nA = \"3\"\nB = \"4\"\n");
+    dir
+}
+
+#[test]
+fn empty_profile_points_at_profile_show() {
+    let dir = setup("activate-empty");
+    let output = run(&dir, &["activate", "empty"]);
+    assert!(output.status.success());
+    assert!(
+        stderr(&output).contains("Profile 'empty' resolves to no variables"),
+        "{}",
+        stderr(&output)
+    );
+    assert!(stderr(&output).contains("em profile show empty"));
+}
+
+#[test]
+fn fully_overridden_profile_lists_its_variables() {
+    let dir = setup("activate-overridden");
+    let output = run(&dir, &["activate", "base", "over"]);
+    assert!(output.status.success());
+    assert!(
+        stderr(&output).contains(
+            "Profile 'base' has no effect, later items override all of its variables: A, B"
+        ),
+        "{}",
+        stderr(&output)
+    );
+
+    let output = run(&dir, &["activate", "over", "A=5"]);
+    assert!(
+        !stderr(&output).contains("has no effect"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn missing_profile_suggests_a_close_name() {
+    let dir = setup("activate-missing");
+    for command in ["activate", "deactivate"] {
+        let output = run(&dir, &[command, "bsae"]);
+        assert!(!output.status.success());
+        assert!(
+            stderr(&output).contains("Profile `bsae` does not exist, did you mean `base`?"),
+            "{}",
+            stderr(&output)
+        );
+    }
+
+    let output = run(&dir, &["activate", "unrelated"]);
+    assert!(!output.status.success());
+    assert!(
+        !stderr(&output).contains("did you mean"),
+        "{}",
+        stderr(&output)
+    );
+}
@@ -0,0 +1,107 @@
+//! `pre_activate` and `post_activate` commands run in env-manage itself, around the
+//! activation, once trusted like the shell hooks.
+
+mod common;
+
+use common::{TempDir, stderr, stdout, write_profile};
+use std::path::Path;
+use std::process::Output;
+
+fn activate(dir: &Path, args: &[&str]) -> Output {
+    common::command(dir)
+        .args(["activate", "--shell", "bash"])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn hooks_run_with_the_variables_and_keep_stdout_for_the_shell() {
+    let dir = TempDir::new("activation-hooks-run");
+    let cache = dir.path().join("cache");
+    write_profile(
+        dir.path(),
+        "app",
+        &format!(
+            "pre_activate = [\"mkdir -p \\\"$CACHE_DIR\\\"\", \"echo pre-ran\"]\npost_activate = [\"touch \\\"$CACHE_DIR/post\\\"\"]\n\n[variables]\nCACHE_DIR = \"{}\"\n",
+            cache.display()
+        ),
+    );
+
+    let output = activate(dir.path(), &["app", "--allow-hooks"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(cache.join("post").exists());
+    assert!(stderr(&output).contains("pre-ran"));
+    assert!(!stdout(&output).contains("pre-ran"));
+    assert!(stdout(&output).contains("export CACHE_DIR="));
+}
+
+#[test]
+fn a_failing_pre_hook_aborts_the_activation() {
+    let dir = TempDir::new("activation-hooks-pre-fails");
+    write_profile(
+        dir.path(),
+        "app",
+        "pre_activate = [\"exit 3\", \"echo never\"]\n\n[variables]\nA = \"1\"\n",
+    );
+
+    let output = activate(dir.path(), &["app", "--allow-hooks"]);
+    assert!(!output.status.success());
+    assert!(stdout(&output).is_empty(), "{}", stdout(&output));
+    assert!(
+        stderr(&output).contains("The pre activate hook `exit 3` of profile 'app' failed"),
+        "{}",
+        stderr(&output)
+    );
+    assert!(!stderr(&output).contains("never"));
+}
+
+#[test]
+fn a_failing_post_hook_only_warns() {
+    let dir = TempDir::new("activation-hooks-post-fails");
+    write_profile(
+        dir.path(),
+        "app",
+        "post_activate = [\"false\"]\n\n[variables]\nA = \"1\"\n",
+    );
+
+    let output = activate(dir.path(), &["app", "--allow-hooks"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(stdout(&output).contains("export A='1'"));
+    assert!(stderr(&output).contains("The post activate hook `false` of profile 'app' failed"));
+}
+
+#[test]
+fn untrusted_hooks_and_previews_do_not_run() {
+    let dir = TempDir::new("activation-hooks-untrusted");
+    let marker = dir.path().join("ran");
+    write_profile(
+        dir.path(),
+        "app",
+        &format!(
+            "pre_activate = [\"touch '{}'\"]\n\n[variables]\nA = \"1\"\n",
+            marker.display()
+        ),
+    );
+
+    let output = activate(dir.path(), &["app"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(stderr(&output).contains("Skipping the hooks of profile 'app'"));
+    assert!(!marker.exists());
+
+    let output = activate(dir.path(), &["app", "--allow-hooks", "--print"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(stderr(&output).contains("Not running the pre_activate and post_activate hooks"));
+    assert!(!marker.exists());
+}
+
+#[test]
+fn profiles_without_hooks_are_unaffected() {
+    let dir = TempDir::new("activation-hooks-none");
+    write_profile(dir.path(), "app", "[variables]\nA = \"1\"\n");
+
+    let output = activate(dir.path(), &["app"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(!stderr(&output).contains("hook"));
+    assert!(!dir.path().join("config.toml").exists());
+}
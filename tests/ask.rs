@@ -0,0 +1,59 @@
+//! `Asker` reads answers from any reader and writes its questions to any writer.
+
+use env_manage::utils::ask::{Asker, Choice};
+use std::io::Cursor;
+
+fn choose(input: &str) -> (Choice, String) {
+    let mut output = Vec::new();
+    let options = ["a: 1".to_string(), "b: 2".to_string()];
+    let choice = Asker::new(Cursor::new(input), &mut output)
+        .choose("Pick one:", &options, 1)
+        .unwrap();
+    (choice, String::from_utf8(output).unwrap())
+}
+
+#[test]
+fn choose_lists_the_options_and_takes_a_number() {
+    let (choice, output) = choose("1\n");
+    assert_eq!(choice, Choice::Option(0));
+    assert_eq!(
+        output,
+        "Pick one:\n  1) a: 1\n  2) b: 2\n  c) enter a custom value\nChoose [2]: "
+    );
+}
+
+#[test]
+fn an_empty_answer_or_the_end_of_input_picks_the_default() {
+    assert_eq!(choose("\n").0, Choice::Option(1));
+    assert_eq!(choose("").0, Choice::Option(1));
+    assert_eq!(choose("c\n").0, Choice::Option(1));
+}
+
+#[test]
+fn invalid_answers_are_asked_again() {
+    let (choice, output) = choose("3\nx\n 2 \n");
+    assert_eq!(choice, Choice::Option(1));
+    assert_eq!(
+        output
+            .matches("Please enter a number from 1 to 2, or c.")
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn c_reads_a_custom_value_verbatim() {
+    let (choice, output) = choose("c\n  spaced value \r\n");
+    assert_eq!(choice, Choice::Custom("  spaced value ".to_string()));
+    assert!(output.ends_with("Custom value: "));
+}
+
+#[test]
+fn line_strips_only_the_line_ending() {
+    let mut output = Vec::new();
+    let mut asker = Asker::new(Cursor::new("first \nsecond"), &mut output);
+    assert_eq!(asker.line("> ").unwrap().as_deref(), Some("first "));
+    assert_eq!(asker.line("> ").unwrap().as_deref(), Some("second"));
+    assert_eq!(asker.line("> ").unwrap(), None);
+    assert_eq!(output, b"> > > ");
+}
@@ -0,0 +1,98 @@
+//! Profile files are written to a temporary file and renamed into place, so a write
+//! that fails halfway leaves the previous file as it was. The new file keeps the mode
+//! of the old one, and journal entries are private to the owner.
+
+#![cfg(unix)]
+
+mod common;
+
+use common::{TempDir, profile_path, read_profile, run, stderr, write_profile};
+use env_manage::config::journal;
+use std::fs;
+use std::os::unix::fs::{PermissionsExt, symlink};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+#[test]
+fn an_interrupted_write_keeps_the_original_file() {
+    let dir = TempDir::new("atomic-interrupted");
+    let original = "[variables]\nA = \"1\"\n";
+    write_profile(dir.path(), "dev", original);
+
+    // Files the binary writes are cut off after a kilobyte, failing the write with
+    // EFBIG since the signal that would kill the process is ignored
+    let value = format!("BIG={}", "x".repeat(8192));
+    let output = Command::new("sh")
+        .args(["-c", "trap '' XFSZ; ulimit -f 2; exec \"$@\"", "sh"])
+        .arg(common::binary())
+        .args(["profile", "add", "dev", &value])
+        .env("ENV_MANAGE_CONFIG_DIR", dir.path())
+        .env("NO_COLOR", "1")
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success(), "{output:?}");
+    assert!(stderr(&output).contains("File too large"), "{output:?}");
+    assert_eq!(read_profile(dir.path(), "dev").as_deref(), Some(original));
+    let leftovers: Vec<_> = fs::read_dir(dir.path().join("profiles"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .filter(|name| name != "dev.toml")
+        .collect();
+    assert!(leftovers.is_empty(), "{leftovers:?}");
+}
+
+fn mode(path: &Path) -> u32 {
+    fs::metadata(path).unwrap().permissions().mode() & 0o777
+}
+
+#[test]
+fn a_rewritten_profile_keeps_its_mode() {
+    let dir = TempDir::new("atomic-mode");
+    write_profile(dir.path(), "secret", "[variables]\nTOKEN = \"x\"\n");
+    let path = profile_path(dir.path(), "secret");
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+
+    let output = run(dir.path(), &["profile", "add", "secret", "B=2"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(mode(&path), 0o600);
+}
+
+#[test]
+fn a_symlinked_profile_is_written_through() {
+    let dir = TempDir::new("atomic-symlink");
+    let target = dir.path().join("shared.toml");
+    fs::write(&target, "[variables]\nA = \"1\"\n").unwrap();
+    let link = profile_path(dir.path(), "linked");
+    fs::create_dir_all(link.parent().unwrap()).unwrap();
+    symlink(&target, &link).unwrap();
+
+    let output = run(dir.path(), &["profile", "add", "linked", "B=2"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(
+        fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink()
+    );
+    assert!(fs::read_to_string(&target).unwrap().contains("B = \"2\""));
+}
+
+#[test]
+fn journal_entries_are_private() {
+    let dir = TempDir::new("atomic-journal-mode");
+    write_profile(dir.path(), "base", "[variables]\nTOKEN = \"x\"\n");
+    write_profile(dir.path(), "app", "profiles = [\"base\"]\n");
+
+    let output = run(dir.path(), &["profile", "rename", "base", "core"]);
+    assert!(output.status.success(), "{output:?}");
+    let entries: Vec<_> = fs::read_dir(journal::journal_dir(dir.path()))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    assert!(!entries.is_empty());
+    for entry in entries {
+        assert_eq!(mode(&entry), 0o600, "{}", entry.display());
+    }
+}
@@ -0,0 +1,98 @@
+//! Profile names that differ only in case share one file on case-insensitive
+//! filesystems. The loader shim makes every platform behave like one.
+
+mod common;
+
+use common::{TempDir, write_profile};
+use env_manage::api::{self, ApiError};
+use env_manage::config::ConfigManager;
+use env_manage::config::loader;
+use std::fs;
+
+fn setup(name: &str) -> (TempDir, ConfigManager) {
+    let dir = TempDir::new(name);
+    write_profile(dir.path(), "API", "[variables]\nURL = \"x\"\n");
+    let config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    (dir, config_manager)
+}
+
+fn file_names(dir: &TempDir) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir.path().join("profiles"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn case_only_rename_keeps_the_file() {
+    loader::simulate_case_insensitive(true);
+    let (dir, mut config_manager) = setup("case-rename");
+
+    config_manager.rename_profile_file("API", "api").unwrap();
+
+    assert_eq!(file_names(&dir), ["api.toml"]);
+    config_manager.load_profile("api").unwrap();
+    let profile = config_manager.get_profile("api").unwrap();
+    assert_eq!(profile.variables.get("URL").map(String::as_str), Some("x"));
+}
+
+#[test]
+fn rename_onto_a_differently_cased_profile_is_refused() {
+    loader::simulate_case_insensitive(true);
+    let (dir, config_manager) = setup("case-rename-onto");
+    fs::write(dir.path().join("profiles/web.toml"), "").unwrap();
+
+    let err = config_manager
+        .rename_profile_file("web", "api")
+        .unwrap_err()
+        .to_string();
+
+    assert!(err.contains("would share a file with 'API'"), "{err}");
+    assert_eq!(file_names(&dir), ["API.toml", "web.toml"]);
+}
+
+#[test]
+fn creating_a_differently_cased_profile_is_refused() {
+    loader::simulate_case_insensitive(true);
+    let (dir, mut config_manager) = setup("case-create");
+
+    let err = api::create_profile(&mut config_manager, "api").unwrap_err();
+
+    assert!(
+        matches!(&err, ApiError::CaseConflict { name, existing } if name == "api" && existing == "API"),
+        "{err}"
+    );
+    assert!(!config_manager.profile_exists("api"));
+    assert!(config_manager.profile_exists("API"));
+    assert_eq!(file_names(&dir), ["API.toml"]);
+}
+
+#[test]
+fn a_differently_cased_name_does_not_load_the_profile() {
+    loader::simulate_case_insensitive(true);
+    let (_dir, mut config_manager) = setup("case-load");
+
+    assert!(config_manager.load_profile("api").is_err());
+    assert!(config_manager.load_profile("API").is_ok());
+    assert!(!config_manager.has_profile("api"));
+    assert_eq!(config_manager.case_conflict("api").as_deref(), Some("API"));
+}
+
+#[test]
+fn case_sensitive_directories_keep_both_names() {
+    let dir = TempDir::new("case-sensitive");
+    let probe = dir.path().join("profiles");
+    fs::create_dir_all(&probe).unwrap();
+    if loader::is_case_insensitive(&probe) {
+        // The test machine's filesystem ignores case itself
+        return;
+    }
+    let (dir, mut config_manager) = setup("case-sensitive-create");
+
+    api::create_profile(&mut config_manager, "api").unwrap();
+
+    assert_eq!(file_names(&dir), ["API.toml", "api.toml"]);
+    assert_eq!(config_manager.case_conflict("api"), None);
+}
@@ -0,0 +1,92 @@
+//! `check --fix` reports the issues found, repairs them and reports the edits.
+
+mod common;
+
+use common::{TempDir, stderr, write_profile};
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+fn check(dir: &Path, args: &[&str]) -> Output {
+    common::command(dir)
+        .arg("check")
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn check_fix_repairs_and_reports() {
+    let dir = TempDir::new("check-fix");
+    write_profile(
+        dir.path(),
+        "app",
+        "profiles = [\"app\", \"gone\", \"lib\"]\n",
+    );
+    write_profile(dir.path(), "lib", "");
+
+    let output = check(dir.path(), &["--fix", "--yes"]);
+    let report = stderr(&output);
+    assert!(output.status.success(), "{report}");
+
+    // The findings come first, then the edits written
+    let found = report.find("'gone'").unwrap();
+    let wrote = report.find("Wrote 2 edits:").unwrap();
+    assert!(found < wrote, "{report}");
+    assert!(report.contains("Removed profile 'app' from its own dependencies"));
+    assert!(report.contains("Removed dependency 'gone' from profile 'app'"));
+    assert!(report.contains("All profiles are valid."));
+
+    let app = fs::read_to_string(dir.path().join("profiles/app.toml")).unwrap();
+    assert!(app.contains("profiles = [\"lib\"]"), "{app}");
+    assert!(check(dir.path(), &[]).status.success());
+}
+
+#[test]
+fn check_without_fix_stays_read_only() {
+    let dir = TempDir::new("check-fix-read-only");
+    write_profile(dir.path(), "app", "profiles = [\"gone\"]\n");
+
+    let output = check(dir.path(), &[]);
+    assert!(!output.status.success());
+    let app = fs::read_to_string(dir.path().join("profiles/app.toml")).unwrap();
+    assert!(app.contains("gone"));
+}
+
+#[test]
+fn check_fix_without_a_terminal_needs_yes() {
+    let dir = TempDir::new("check-fix-no-tty");
+    write_profile(dir.path(), "app", "profiles = [\"gone\"]\n");
+
+    let output = check(dir.path(), &["--fix"]);
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("pass --yes"),
+        "{}",
+        stderr(&output)
+    );
+    let app = fs::read_to_string(dir.path().join("profiles/app.toml")).unwrap();
+    assert!(app.contains("gone"));
+}
+
+#[test]
+fn check_fix_on_valid_profiles_changes_nothing() {
+    let dir = TempDir::new("check-fix-valid");
+    write_profile(dir.path(), "app", "");
+
+    let output = check(dir.path(), &["--fix"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stderr(&output).contains("All profiles are valid."));
+    assert!(!stderr(&output).contains("edits"));
+}
+
+#[test]
+fn yes_requires_fix() {
+    let dir = TempDir::new("check-fix-yes-alone");
+    assert!(!check(dir.path(), &["--yes"]).status.success());
+    assert!(
+        !check(dir.path(), &["--fix", "--format", "json"])
+            .status
+            .success()
+    );
+}
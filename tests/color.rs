@@ -0,0 +1,64 @@
+//! `--color` and `NO_COLOR` decide whether messages carry ANSI escape codes.
+
+mod common;
+
+use common::TempDir;
+use std::process::Output;
+
+/// Run with `NO_COLOR` set to `no_color`, or unset
+fn run_with_no_color(dir: &TempDir, args: &[&str], no_color: Option<&str>) -> Output {
+    let mut command = common::command(dir);
+    command.args(args).env_remove("NO_COLOR");
+    if let Some(value) = no_color {
+        command.env("NO_COLOR", value);
+    }
+    command.output().unwrap()
+}
+
+fn is_colored(output: &Output) -> bool {
+    assert!(!output.stderr.is_empty());
+    String::from_utf8_lossy(&output.stderr).contains('\u{1b}')
+}
+
+#[test]
+fn always_and_never_override_the_terminal_check() {
+    let dir = TempDir::new("color-flag");
+
+    assert!(is_colored(&run_with_no_color(
+        &dir,
+        &["--color", "always", "profile", "show", "missing"],
+        None
+    )));
+    assert!(!is_colored(&run_with_no_color(
+        &dir,
+        &["profile", "show", "missing", "--color=never"],
+        None
+    )));
+}
+
+#[test]
+fn auto_is_plain_when_stderr_is_not_a_terminal() {
+    let dir = TempDir::new("color-auto");
+
+    assert!(!is_colored(&run_with_no_color(
+        &dir,
+        &["profile", "show", "missing"],
+        None
+    )));
+}
+
+#[test]
+fn no_color_is_overridden_by_an_explicit_always() {
+    let dir = TempDir::new("color-no-color");
+
+    assert!(!is_colored(&run_with_no_color(
+        &dir,
+        &["--color=auto", "profile", "show", "missing"],
+        Some("1")
+    )));
+    assert!(is_colored(&run_with_no_color(
+        &dir,
+        &["--color=always", "profile", "show", "missing"],
+        Some("1")
+    )));
+}
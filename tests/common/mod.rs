@@ -0,0 +1,121 @@
+//! Helpers shared by the integration tests
+
+// Every test crate includes this module and uses a different subset of it
+#![allow(dead_code)]
+
+use env_manage::tui::app::App;
+use env_manage::tui::event::handle_key;
+use env_manage::tui::ui::ui;
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+/// A directory removed again when the test ends
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("env-manage-test-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// The binary under test
+pub fn binary() -> &'static str {
+    env!("CARGO_BIN_EXE_env-manage")
+}
+
+/// The binary with `dir` as its config directory, run as from a shell where nothing is
+/// active: without colors, the activation state of the calling shell or a terminal
+pub fn command(dir: &(impl AsRef<Path> + ?Sized)) -> Command {
+    let mut command = Command::new(binary());
+    command
+        .env("ENV_MANAGE_CONFIG_DIR", dir.as_ref())
+        .env("NO_COLOR", "1")
+        .env_remove("__ENV_MANAGE_ACTIVE_PROFILES")
+        .env_remove("__ENV_MANAGE_TRACKED_VARS")
+        .stdin(Stdio::null());
+    command
+}
+
+/// Run the binary with `args`, see [`command`]
+pub fn run(dir: &(impl AsRef<Path> + ?Sized), args: &[&str]) -> Output {
+    command(dir).args(args).output().unwrap()
+}
+
+pub fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+pub fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+/// The file of profile `name`, which may be namespaced, in the config directory `dir`
+pub fn profile_path(dir: &(impl AsRef<Path> + ?Sized), name: &str) -> PathBuf {
+    dir.as_ref().join("profiles").join(format!("{name}.toml"))
+}
+
+/// Write the file of profile `name`, creating its directory
+pub fn write_profile(dir: &(impl AsRef<Path> + ?Sized), name: &str, content: &str) {
+    let path = profile_path(dir, name);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+/// The content of the file of profile `name`, if there is one
+pub fn read_profile(dir: &(impl AsRef<Path> + ?Sized), name: &str) -> Option<String> {
+    fs::read_to_string(profile_path(dir, name)).ok()
+}
+
+/// Send `code` to the TUI as if typed, without modifiers
+pub fn press(app: &mut App, code: KeyCode) {
+    press_with(app, code, KeyModifiers::NONE);
+}
+
+pub fn press_with(app: &mut App, code: KeyCode, modifiers: KeyModifiers) {
+    handle_key(app, KeyEvent::new(code, modifiers)).unwrap();
+}
+
+/// Type every character of `text`
+pub fn type_text(app: &mut App, text: &str) {
+    for c in text.chars() {
+        press(app, KeyCode::Char(c));
+    }
+}
+
+/// The rows of the TUI drawn on a 120x20 screen
+pub fn screen(app: &App) -> Vec<String> {
+    let mut terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+    terminal.draw(|frame| ui(frame, app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    (0..buffer.area.height)
+        .map(|y| {
+            (0..buffer.area.width)
+                .map(|x| buffer[(x, y)].symbol().to_string())
+                .collect()
+        })
+        .collect()
+}
@@ -0,0 +1,163 @@
+//! Values written as `command: ...` are computed by the shell on activation, once the
+//! profile is trusted like its hooks.
+
+mod common;
+
+use common::{TempDir, run, write_profile};
+use env_manage::config::models::{Profile, computed_command};
+use env_manage::utils::shell_generate::{ShellGenerate, ShellType};
+use std::collections::HashMap;
+use std::process::Command;
+
+fn script(shell: ShellType, key: &str, value: &str) -> String {
+    let vars = HashMap::from([(key.to_string(), value.to_string())]);
+    ShellGenerate::with_shell(shell)
+        .export_from_map(&vars)
+        .script()
+}
+
+#[test]
+fn only_plain_keys_with_the_prefix_are_computed() {
+    assert_eq!(
+        computed_command("command: date -Iseconds"),
+        Some("date -Iseconds")
+    );
+    assert_eq!(computed_command("date"), None);
+
+    let mut profile = Profile::new();
+    profile.add_variable("STAMP", "command: date");
+    profile.add_variable("PATH+", "command: ignored");
+    profile.add_variable("PLAIN", "value");
+    let computed = profile.computed_commands();
+    assert_eq!(computed.len(), 1);
+    assert_eq!(computed["STAMP"], "date");
+}
+
+#[test]
+fn each_shell_substitutes_the_quoted_command() {
+    let value = "command: echo 'it''s'";
+    assert_eq!(
+        script(ShellType::Bash, "V", value),
+        r#"export V="$(eval 'echo '\''it'\'''\''s'\''')""#
+    );
+    assert_eq!(
+        script(ShellType::Fish, "V", value),
+        r"set -gx V (eval 'echo \'it\'\'s\'' | string collect)"
+    );
+    assert_eq!(
+        script(ShellType::PowerShell, "V", value),
+        "$env:V=(Invoke-Expression 'echo ''it''''s''' | Out-String).TrimEnd()"
+    );
+    assert_eq!(
+        script(ShellType::Nu, "V", "command: echo \"hi\""),
+        r#"$env.V = (nu -c "echo \"hi\"" | str trim --right)"#
+    );
+}
+
+#[test]
+fn bash_runs_the_command_when_evaluating_the_script() {
+    let generated = script(
+        ShellType::Bash,
+        "GREETING",
+        "command: printf '%s' \"hello $(echo world) :)\"",
+    );
+    let output = Command::new("bash")
+        .args(["-c", &format!("{generated}\nprintf '%s' \"$GREETING\"")])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "hello world :)");
+}
+
+#[test]
+fn untrusted_profiles_do_not_compute() {
+    let dir = TempDir::new("computed-untrusted");
+    write_profile(
+        dir.path(),
+        "app",
+        "[variables]\nSTAMP = \"command: date\"\nNAME = \"app\"\n",
+    );
+
+    let output = run(dir.path(), &["use", "app", "--print", "--shell", "bash"]);
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("export NAME='app'"), "{stdout}");
+    assert!(!stdout.contains("STAMP"), "{stdout}");
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("Skipping the hooks and computed variables of profile 'app'")
+    );
+
+    let output = run(dir.path(), &["check"]);
+    let report = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        report.contains("Profile 'app' computes STAMP with commands that are not trusted"),
+        "{report}"
+    );
+
+    // Trusting the profile also trusts its computed variables
+    let output = run(
+        dir.path(),
+        &["use", "app", "--shell", "bash", "--allow-hooks"],
+    );
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(r#"export STAMP="$(eval 'date')""#),
+        "{stdout}"
+    );
+    let output = run(dir.path(), &["check"]);
+    assert!(
+        !String::from_utf8_lossy(&output.stdout).contains("not trusted"),
+        "{output:?}"
+    );
+}
+
+#[test]
+fn global_computed_values_need_trust_too() {
+    let dir = TempDir::new("computed-global");
+    write_profile(dir.path(), "app", "[variables]\nNAME = \"app\"\n");
+    write_profile(
+        dir.path(),
+        "shared",
+        "[variables]\nS = \"command: hostname\"\n",
+    );
+    std::fs::write(
+        dir.path().join("global.toml"),
+        "profiles = [\"shared\"]\n\n[variables]\nG = \"command: whoami\"\n",
+    )
+    .unwrap();
+
+    let output = run(dir.path(), &["use", "app", "--print", "--shell", "bash"]);
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("export NAME='app'"), "{stdout}");
+    assert!(!stdout.contains("whoami"), "{stdout}");
+    assert!(!stdout.contains("hostname"), "{stdout}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Skipping the hooks and computed variables of profile '(global)'"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("Skipping the hooks and computed variables of profile 'shared'"),
+        "{stderr}"
+    );
+
+    let output = run(
+        dir.path(),
+        &["use", "app", "--print", "--shell", "bash", "--allow-hooks"],
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains(r#"export G="$(eval 'whoami')""#),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains(r#"export S="$(eval 'hostname')""#),
+        "{stdout}"
+    );
+}
@@ -0,0 +1,169 @@
+//! Which config directory the binary uses, and that separate directories do not
+//! see each other's profiles.
+
+mod common;
+
+use common::TempDir;
+use std::path::Path;
+use std::process::{Command, Output};
+
+const CONFIG_DIR_VARS: &[&str] = &[
+    "ENV_MANAGE_CONFIG_DIR",
+    "ENV_MANAGE_HOME",
+    "XDG_CONFIG_HOME",
+    "HOME",
+];
+
+/// Run the binary with only the config directory variables in `vars` set
+fn env_manage(vars: &[(&str, &Path)], args: &[&str]) -> Output {
+    let mut command = Command::new(common::binary());
+    command.args(args);
+    for var in CONFIG_DIR_VARS {
+        command.env_remove(var);
+    }
+    for (var, path) in vars {
+        command.env(var, path);
+    }
+    command.output().unwrap()
+}
+
+/// Create a profile named `probe` and report where its file ended up
+fn created_in(vars: &[(&str, &Path)], args: &[&str], candidates: &[&Path]) -> usize {
+    let mut full_args = args.to_vec();
+    full_args.extend(["profile", "create", "probe"]);
+    let output = env_manage(vars, &full_args);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let found: Vec<usize> = candidates
+        .iter()
+        .enumerate()
+        .filter(|(_, dir)| dir.join("profiles").join("probe.toml").exists())
+        .map(|(i, _)| i)
+        .collect();
+    assert_eq!(found.len(), 1, "probe.toml created in {found:?}");
+    found[0]
+}
+
+/// Remove each candidate in turn, dropping the variable behind it from the end of
+/// `vars`, and check the next candidate is used
+fn assert_precedence(mut vars: Vec<(&str, &Path)>, candidates: &[&Path]) {
+    assert_eq!(created_in(&vars, &[], candidates), 0);
+    for expected in 1..candidates.len() {
+        let _ = std::fs::remove_dir_all(candidates[expected - 1]);
+        vars.pop();
+        assert_eq!(created_in(&vars, &[], candidates), expected);
+    }
+}
+
+#[test]
+fn config_dir_precedence() {
+    let root = TempDir::new("precedence");
+    let flag = root.path().join("flag");
+    let config_dir = root.path().join("config-dir");
+    let home = root.path().join("home");
+    let fallback = home.join(".config").join("env-manage");
+    let vars = vec![
+        ("HOME", home.as_path()),
+        ("ENV_MANAGE_CONFIG_DIR", config_dir.as_path()),
+    ];
+
+    let candidates: [&Path; 3] = [&flag, &config_dir, &fallback];
+    assert_eq!(
+        created_in(
+            &vars,
+            &["--config-dir", flag.to_str().unwrap()],
+            &candidates
+        ),
+        0
+    );
+    assert_precedence(vars, &candidates[1..]);
+}
+
+// XDG_CONFIG_HOME, and ENV_MANAGE_HOME as the older name of ENV_MANAGE_CONFIG_DIR
+
+#[test]
+fn xdg_config_home_comes_after_the_env_manage_variables() {
+    let root = TempDir::new("xdg-precedence");
+    let config_dir = root.path().join("config-dir");
+    let home_var = root.path().join("home-var");
+    let xdg = root.path().join("xdg");
+    let home = root.path().join("home");
+    let candidates: [&Path; 4] = [
+        &config_dir,
+        &home_var,
+        &xdg.join("env-manage"),
+        &home.join(".config").join("env-manage"),
+    ];
+
+    let vars = vec![
+        ("HOME", home.as_path()),
+        ("XDG_CONFIG_HOME", xdg.as_path()),
+        ("ENV_MANAGE_HOME", home_var.as_path()),
+        ("ENV_MANAGE_CONFIG_DIR", config_dir.as_path()),
+    ];
+    assert_precedence(vars, &candidates);
+}
+
+#[test]
+fn relative_xdg_config_home_is_ignored() {
+    let root = TempDir::new("xdg-relative");
+    let home = root.path().join("home");
+    let fallback = home.join(".config").join("env-manage");
+    let vars = [
+        ("HOME", home.as_path()),
+        ("XDG_CONFIG_HOME", Path::new("relative/config")),
+    ];
+    assert_eq!(created_in(&vars, &[], &[&fallback]), 0);
+}
+
+#[test]
+fn profile_dir_is_an_alias_of_config_dir() {
+    let root = TempDir::new("alias");
+    let dir = root.path().join("dir");
+    created_in(
+        &[("HOME", root.path())],
+        &["--profile-dir", dir.to_str().unwrap()],
+        &[&dir],
+    );
+}
+
+#[test]
+fn config_dirs_are_isolated() {
+    let first = TempDir::new("isolated-first");
+    let second = TempDir::new("isolated-second");
+    let in_dir =
+        |dir: &TempDir, args: &[&str]| env_manage(&[("ENV_MANAGE_CONFIG_DIR", dir.path())], args);
+
+    assert!(
+        in_dir(&first, &["profile", "create", "only-first"])
+            .status
+            .success()
+    );
+    assert!(
+        in_dir(&first, &["global", "add", "SHARED=first"])
+            .status
+            .success()
+    );
+
+    let listed = in_dir(&second, &["profile", "list"]);
+    assert!(!String::from_utf8_lossy(&listed.stderr).contains("only-first"));
+    assert!(
+        !in_dir(&second, &["activate", "only-first"])
+            .status
+            .success()
+    );
+
+    let global = in_dir(&second, &["global", "list"]);
+    assert!(!String::from_utf8_lossy(&global.stderr).contains("SHARED"));
+    assert!(
+        !second
+            .path()
+            .join("profiles")
+            .join("only-first.toml")
+            .exists()
+    );
+}
@@ -0,0 +1,63 @@
+//! `profile create --edit` opens the new profile straight in the TUI editor.
+
+mod common;
+
+use common::{TempDir, run, write_profile};
+use env_manage::config::ConfigManager;
+use env_manage::config::models::Profile;
+use env_manage::tui::app::{App, AppState};
+use env_manage::tui::event::handle_key;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+fn new_app(dir: &TempDir) -> App {
+    let config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    App::new(config_manager, Profile::new())
+}
+
+#[test]
+fn the_editor_opens_on_the_profile_and_esc_returns_to_the_list() {
+    let dir = TempDir::new("create-edit-open");
+    write_profile(dir.path(), "alpha", "");
+    write_profile(dir.path(), "new", "");
+
+    let mut app = new_app(&dir);
+    app.open_in_editor("new");
+    assert_eq!(app.state, AppState::Edit);
+    assert_eq!(app.edit_view.profile_name(), "new");
+
+    handle_key(&mut app, KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)).unwrap();
+    assert_eq!(app.state, AppState::List);
+    assert!(!app.shutdown);
+    assert_eq!(app.list_view.current_profile(), Some("new"));
+}
+
+#[test]
+fn a_missing_or_read_only_profile_stays_in_the_list() {
+    let dir = TempDir::new("create-edit-missing");
+    write_profile(dir.path(), "alpha", "");
+
+    let mut app = new_app(&dir);
+    app.open_in_editor("ghost");
+    assert_eq!(app.state, AppState::List);
+
+    app.read_only = true;
+    app.open_in_editor("alpha");
+    assert_eq!(app.state, AppState::List);
+}
+
+#[test]
+fn without_a_terminal_the_profile_is_only_created() {
+    let dir = TempDir::new("create-edit-no-tty");
+    let output = run(dir.path(), &["profile", "create", "web", "--edit"]);
+    assert!(output.status.success(), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Profile 'web' created successfully"),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("Not opening the editor without an interactive terminal"),
+        "{stderr}"
+    );
+    assert!(dir.path().join("profiles").join("web.toml").exists());
+}
@@ -0,0 +1,131 @@
+//! `deactivate` unsets single variables by name, `profile:KEY` or `--var KEY`.
+
+mod common;
+
+use common::{TempDir, run, stderr, stdout, write_profile};
+use std::process::Output;
+
+fn setup(name: &str) -> TempDir {
+    let dir = TempDir::new(name);
+    write_profile(&dir, "web", "[variables]\nPORT = \"1\"\nHOST = \"local\"\n");
+    write_profile(&dir, "HOST", "[variables]\nOTHER = \"x\"\n");
+    dir
+}
+
+/// Run with `web` active and both of its variables tracked
+fn run_active(dir: &TempDir, args: &[&str]) -> Output {
+    let output = common::command(dir)
+        .args(args)
+        .env("__ENV_MANAGE_ACTIVE_PROFILES", "web")
+        .env("__ENV_MANAGE_TRACKED_VARS", "PORT:HOST")
+        .env("PORT", "1")
+        .env("HOST", "local")
+        .env_remove("__ENV_MANAGE_ORIG_PORT")
+        .env_remove("__ENV_MANAGE_ORIG_HOST")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    output
+}
+
+#[test]
+fn a_tracked_variable_is_unset_on_its_own() {
+    let dir = setup("deactivate-var-name");
+    let output = run_active(&dir, &["deactivate", "--shell", "bash", "PORT"]);
+    let script = stdout(&output);
+    assert!(script.contains("unset PORT"));
+    assert!(!script.contains("unset HOST"));
+    // The profile stays active with only the remaining variable tracked
+    assert!(script.contains("export __ENV_MANAGE_ACTIVE_PROFILES='web'"));
+    assert!(script.contains("export __ENV_MANAGE_TRACKED_VARS='HOST'"));
+}
+
+#[test]
+fn profile_key_syntax_names_a_variable_of_the_profile() {
+    let dir = setup("deactivate-var-profile-key");
+    let output = run_active(&dir, &["deactivate", "--shell", "bash", "web:PORT"]);
+    assert!(stdout(&output).contains("unset PORT"));
+
+    let output = run(
+        dir.path(),
+        &["deactivate", "--shell", "bash", "web:MISSING"],
+    );
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("Profile `web` does not set `MISSING`"));
+}
+
+#[test]
+fn a_profile_wins_over_a_variable_unless_var_is_given() {
+    let dir = setup("deactivate-var-ambiguous");
+    let output = run_active(&dir, &["deactivate", "--shell", "bash", "HOST"]);
+    assert!(stderr(&output).contains("use `--var HOST` to unset the variable instead"));
+    assert!(stdout(&output).contains("unset OTHER"));
+    assert!(!stdout(&output).contains("unset HOST"));
+
+    let output = run_active(&dir, &["deactivate", "--shell", "bash", "--var", "HOST"]);
+    assert!(stdout(&output).contains("unset HOST"));
+    assert!(!stdout(&output).contains("unset OTHER"));
+}
+
+#[test]
+fn status_shows_a_partially_deactivated_profile() {
+    let dir = setup("deactivate-var-status");
+    let output = common::command(&dir)
+        .args(["status", "web"])
+        .env("__ENV_MANAGE_ACTIVE_PROFILES", "web")
+        .env("__ENV_MANAGE_TRACKED_VARS", "HOST")
+        .env("HOST", "local")
+        .env_remove("PORT")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    let report = stderr(&output);
+    assert!(report.contains("[Partially active: 1 of 2 variables]"));
+    assert!(report.contains("[Deactivated]"));
+}
+
+#[test]
+fn var_must_name_an_active_variable() {
+    let dir = setup("deactivate-var-unknown");
+    let output = common::command(&dir)
+        .args(["deactivate", "--shell", "bash", "--var", "app"])
+        .env("__ENV_MANAGE_ACTIVE_PROFILES", "web")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(stdout(&output).is_empty());
+    assert!(
+        stderr(&output)
+            .contains("`app` is not a variable env-manage set or an active profile defines")
+    );
+
+    // Without variable tracking, the active profiles' variables still count
+    let output = common::command(&dir)
+        .args(["deactivate", "--shell", "bash", "--var", "PORT"])
+        .env("__ENV_MANAGE_ACTIVE_PROFILES", "web")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    assert!(stdout(&output).contains("unset PORT"));
+}
+
+#[test]
+fn status_matches_list_keys_by_variable_name() {
+    let dir = TempDir::new("deactivate-var-status-list");
+    write_profile(&dir, "p", "[variables]\n\"PATH+\" = \"/x\"\nA = \"1\"\n");
+    let output = common::command(&dir)
+        .args(["status", "p"])
+        .env("__ENV_MANAGE_ACTIVE_PROFILES", "p")
+        .env("__ENV_MANAGE_TRACKED_VARS", "PATH:A")
+        .env("PATH", format!("{}:/x", std::env::var("PATH").unwrap()))
+        .env("A", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    let report = stderr(&output);
+    assert!(report.contains("PATH+:"), "{report}");
+    assert!(!report.contains("Deactivated"), "{report}");
+    assert!(!report.contains("Partially active"), "{report}");
+    assert!(!report.contains("Unset in shell"), "{report}");
+    assert!(!report.contains(" -> "), "{report}");
+}
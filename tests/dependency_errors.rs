@@ -0,0 +1,75 @@
+//! The messages of dependency errors, which `check`, activation and the TUI show as is.
+
+use env_manage::config::graph::DependencyError;
+use std::path::PathBuf;
+
+fn file(name: &str) -> PathBuf {
+    PathBuf::from(format!("/nonexistent/profiles/{name}.toml"))
+}
+
+#[test]
+fn circular_dependency_messages() {
+    let path: Vec<String> = ["a", "b", "a"].map(String::from).to_vec();
+    let error = DependencyError::CircularDependency {
+        path: path.clone(),
+        sources: Vec::new(),
+    };
+    assert_eq!(
+        error.to_string(),
+        "Circular dependency detected: a -> b -> a"
+    );
+
+    let error = DependencyError::CircularDependency {
+        path,
+        sources: vec![file("a"), file("b")],
+    };
+    assert_eq!(
+        error.to_string(),
+        "Circular dependency detected:\n  \
+         a -> b (declared in /nonexistent/profiles/a.toml)\n  \
+         b -> a (declared in /nonexistent/profiles/b.toml)"
+    );
+}
+
+#[test]
+fn dependency_not_found_messages() {
+    let error = DependencyError::DependencyNotFound {
+        parent: "app".to_string(),
+        dependency: "gone".to_string(),
+        source: None,
+    };
+    assert_eq!(
+        error.to_string(),
+        "Profile 'app' references non-existent profile 'gone'."
+    );
+
+    let error = DependencyError::DependencyNotFound {
+        parent: "app".to_string(),
+        dependency: "gone".to_string(),
+        source: Some(file("app")),
+    };
+    assert_eq!(
+        error.to_string(),
+        "Profile 'app' references non-existent profile 'gone' \
+         (declared in /nonexistent/profiles/app.toml)."
+    );
+}
+
+#[test]
+fn errors_below_other_profiles_are_traced() {
+    let error = DependencyError::DependencyChain {
+        profile: "web".to_string(),
+        cause: Box::new(DependencyError::DependencyChain {
+            profile: "app".to_string(),
+            cause: Box::new(DependencyError::DependencyNotFound {
+                parent: "app".to_string(),
+                dependency: "gone".to_string(),
+                source: None,
+            }),
+        }),
+    };
+    assert_eq!(
+        error.to_string(),
+        "Trace: web -> app -> Profile 'app' references non-existent profile 'gone'."
+    );
+}
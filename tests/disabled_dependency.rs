@@ -0,0 +1,97 @@
+//! `profile toggle-dep` switches a dependency off without removing it from the file.
+
+mod common;
+
+use common::{TempDir, read_profile, run, write_profile};
+use env_manage::api::{self, ApiError};
+use env_manage::config::ConfigManager;
+use env_manage::config::models::Profile;
+use std::fs;
+
+fn setup(name: &str) -> TempDir {
+    let dir = TempDir::new(name);
+    write_profile(dir.path(), "base", "[variables]\nA = \"base\"\n");
+    write_profile(
+        dir.path(),
+        "app",
+        "profiles = [\"base\"]\n\n[variables]\nB = \"app\"\n",
+    );
+    dir
+}
+
+fn load_profile(dir: &TempDir, name: &str) -> Profile {
+    toml::from_str(&read_profile(dir, name).unwrap()).unwrap()
+}
+
+fn resolve(dir: &TempDir, name: &str) -> Vec<String> {
+    let mut config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    let mut keys: Vec<String> = api::resolve_env(&mut config_manager, name)
+        .unwrap()
+        .into_keys()
+        .collect();
+    keys.sort();
+    keys
+}
+
+#[test]
+fn existing_files_have_every_dependency_enabled() {
+    let dir = setup("disabled-dep-default");
+    let app = load_profile(&dir, "app");
+    assert!(app.disabled_profiles.is_empty());
+    assert_eq!(resolve(&dir, "app"), ["A", "B"]);
+}
+
+#[test]
+fn a_disabled_dependency_is_kept_but_not_resolved() {
+    let dir = setup("disabled-dep-toggle");
+    let output = run(dir.path(), &["profile", "toggle-dep", "app", "base"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("'base' disabled in profile 'app'"));
+
+    let app = load_profile(&dir, "app");
+    assert!(app.profiles.contains("base"));
+    assert!(app.is_profile_disabled("base"));
+    assert_eq!(resolve(&dir, "app"), ["B"]);
+
+    let mut config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    assert!(!api::toggle_dependency(&mut config_manager, "app", "base").unwrap());
+    assert!(load_profile(&dir, "app").disabled_profiles.is_empty());
+    assert_eq!(resolve(&dir, "app"), ["A", "B"]);
+}
+
+#[test]
+fn a_disabled_dependency_may_be_missing_until_enabled() {
+    let dir = setup("disabled-dep-missing");
+    let mut config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    assert!(api::toggle_dependency(&mut config_manager, "app", "base").unwrap());
+    fs::remove_file(dir.path().join("profiles").join("base.toml")).unwrap();
+    assert_eq!(resolve(&dir, "app"), ["B"]);
+
+    let mut config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    let err = api::toggle_dependency(&mut config_manager, "app", "base").unwrap_err();
+    assert!(matches!(err, ApiError::DependencyNotFound(name) if name == "base"));
+    assert!(load_profile(&dir, "app").is_profile_disabled("base"));
+}
+
+#[test]
+fn only_listed_dependencies_can_be_toggled() {
+    let dir = setup("disabled-dep-unlisted");
+    let mut config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    let err = api::toggle_dependency(&mut config_manager, "base", "app").unwrap_err();
+    assert!(matches!(err, ApiError::NotADependency { .. }));
+}
+
+#[test]
+fn renaming_and_readding_keep_the_disabled_state_consistent() {
+    let mut profile = Profile::new();
+    profile.add_profile("app", "base");
+    profile.set_profile_disabled("base", true);
+    assert!(profile.rename_profile("app", "base", "core"));
+    assert!(profile.is_profile_disabled("core"));
+    assert_eq!(profile.enabled_profiles().count(), 0);
+
+    // Adding a disabled dependency again enables it
+    profile.add_profile("app", "core");
+    assert!(!profile.is_profile_disabled("core"));
+    assert_eq!(profile.enabled_profiles().count(), 1);
+}
@@ -0,0 +1,72 @@
+//! The config directory stays unlocked while the editor is open, so other processes
+//! can write in the meantime.
+
+mod common;
+
+use common::{TempDir, binary, read_profile, write_profile};
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+/// An editor script that adds a variable to profile `other` through another
+/// env-manage process, then appends `line` to the edited file
+fn editor(dir: &Path, line: &str) -> String {
+    let script = dir.join("editor.sh");
+    fs::write(
+        &script,
+        format!(
+            "\"{}\" profile add other FROM_EDITOR=1 || exit 1\necho '{line}' >> \"$1\"\n",
+            binary()
+        ),
+    )
+    .unwrap();
+    format!("sh {}", script.display())
+}
+
+fn edit(dir: &Path, args: &[&str], line: &str) -> Output {
+    common::command(dir)
+        .args(args)
+        .env("VISUAL", editor(dir, line))
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn profile_edit_lets_other_processes_write() {
+    let dir = TempDir::new("editor-lock-profile");
+    write_profile(dir.path(), "app", "[variables]\nA = \"1\"\n");
+    write_profile(dir.path(), "other", "");
+
+    let output = edit(dir.path(), &["profile", "edit", "app"], "B = \"2\"");
+    assert!(output.status.success(), "{output:?}");
+    assert!(
+        read_profile(dir.path(), "app")
+            .unwrap()
+            .contains("B = \"2\"")
+    );
+    assert!(
+        read_profile(dir.path(), "other")
+            .unwrap()
+            .contains("FROM_EDITOR")
+    );
+}
+
+#[test]
+fn global_edit_lets_other_processes_write() {
+    let dir = TempDir::new("editor-lock-global");
+    write_profile(dir.path(), "other", "");
+    fs::write(dir.path().join("global.toml"), "[variables]\nA = \"1\"\n").unwrap();
+
+    let output = edit(dir.path(), &["global", "edit"], "B = \"2\"");
+    assert!(output.status.success(), "{output:?}");
+    assert!(
+        fs::read_to_string(dir.path().join("global.toml"))
+            .unwrap()
+            .contains("B = \"2\"")
+    );
+    assert!(
+        read_profile(dir.path(), "other")
+            .unwrap()
+            .contains("FROM_EDITOR")
+    );
+}
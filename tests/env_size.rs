@@ -0,0 +1,120 @@
+//! Activation, `check` and the expand pane warn about environments too large to pass
+//! to new processes.
+
+mod common;
+
+use common::{TempDir, run, write_profile};
+use env_manage::config::ConfigManager;
+use env_manage::config::env_size::{EnvSize, format_size, var_size};
+use env_manage::config::models::Profile;
+use env_manage::tui::app::App;
+use std::fs;
+
+/// `big` sets `BIG` to `value_len` bytes on top of `base`, which sets `SMALL`;
+/// the limit is 100 bytes
+fn setup(name: &str, value_len: usize) -> TempDir {
+    let dir = TempDir::new(name);
+    fs::write(dir.path().join("config.toml"), "max_env_size = 100\n").unwrap();
+    write_profile(dir.path(), "base", "[variables]\nSMALL = \"1\"\n");
+    write_profile(
+        dir.path(),
+        "big",
+        &format!(
+            "profiles = [\"base\"]\n\n[variables]\nBIG = \"{}\"\n",
+            "x".repeat(value_len)
+        ),
+    );
+    dir
+}
+
+/// Length of `BIG` that fills the limit exactly: `SMALL=1` takes 8 bytes and
+/// `BIG=` with its terminator 5
+const AT_LIMIT: usize = 100 - 8 - 5;
+
+#[test]
+fn sizes_count_key_value_and_two_bytes() {
+    assert_eq!(var_size("KEY", "value"), 10);
+    assert_eq!(format_size(512), "512 B");
+    assert_eq!(format_size(100 * 1024), "100.0 KB");
+    assert_eq!(format_size(3 * 1024 * 1024 / 2), "1.5 MB");
+}
+
+#[test]
+fn activation_at_the_limit_does_not_warn() {
+    let dir = setup("env-size-at-limit", AT_LIMIT);
+    let output = run(&dir, &["use", "big", "--print"]);
+    assert!(output.status.success(), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("more than the limit"), "{stderr}");
+}
+
+#[test]
+fn activation_over_the_limit_warns_and_proceeds() {
+    let dir = setup("env-size-over-limit", AT_LIMIT + 1);
+    let output = run(&dir, &["use", "big", "--print"]);
+    assert!(output.status.success(), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("101 B of variables, more than the limit of 100 B"),
+        "{stderr}"
+    );
+    // Largest first, each with the profile it came from
+    assert!(
+        stderr.contains("largest: BIG (93 B, from big), SMALL (8 B, from base)"),
+        "{stderr}"
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("BIG"));
+}
+
+#[test]
+fn strict_size_refuses_to_activate() {
+    let dir = setup("env-size-strict", AT_LIMIT + 1);
+    let output = run(&dir, &["use", "big", "--print", "--strict-size"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Refusing to activate"), "{stderr}");
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("BIG"));
+
+    let dir = setup("env-size-strict-ok", AT_LIMIT);
+    let output = run(&dir, &["use", "big", "--print", "--strict-size"]);
+    assert!(output.status.success(), "{output:?}");
+}
+
+#[test]
+fn check_reports_each_oversized_closure() {
+    let dir = setup("env-size-check", AT_LIMIT + 1);
+    let output = run(&dir, &["check", "--format", "json"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("\"kind\": \"large_environment\""),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("Profile 'big' resolves to 101 B"),
+        "{stdout}"
+    );
+    // `base` alone stays under the limit
+    assert!(!stdout.contains("Profile 'base' resolves"), "{stdout}");
+}
+
+#[test]
+fn the_expand_pane_measures_the_resolved_variables() {
+    let dir = setup("env-size-expand", AT_LIMIT + 1);
+    let config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    let mut app = App::new(config_manager, Profile::new());
+    assert_eq!(app.max_env_size, 100);
+
+    app.config_manager.load_profile("big").unwrap();
+    let merged = app
+        .config_manager
+        .get_profile("big")
+        .unwrap()
+        .collect_merged("big", &app.config_manager)
+        .unwrap();
+    let size = EnvSize::of(&merged.vars);
+    app.expand_view.set_vars(merged);
+    assert_eq!(app.expand_view.size(), &size);
+    assert!(size.exceeds(app.max_env_size));
+    assert_eq!(size.largest[0].key, "BIG");
+    assert_eq!(size.largest[0].sources, ["big"]);
+}
@@ -0,0 +1,73 @@
+//! `--depth` stops the expanded dependency tree after a number of levels.
+
+mod common;
+
+use common::{TempDir, run, write_profile};
+use std::fs;
+
+/// `app` → `mid` → `base` → `root`, each setting one variable
+fn setup(name: &str) -> TempDir {
+    let dir = TempDir::new(name);
+    let profiles = dir.path().join("profiles");
+    fs::create_dir_all(&profiles).unwrap();
+    write_profile(dir.path(), "root", "[variables]\nROOT = \"0\"\n");
+    for (name, parent) in [("base", "root"), ("mid", "base"), ("app", "mid")] {
+        fs::write(
+            profiles.join(format!("{name}.toml")),
+            format!(
+                "profiles = [\"{parent}\"]\n\n[variables]\n{} = \"1\"\n",
+                name.to_uppercase()
+            ),
+        )
+        .unwrap();
+    }
+    dir
+}
+
+fn show(dir: &TempDir, args: &[&str]) -> String {
+    let output = common::command(dir.path())
+        .args(["profile", "show", "app", "--expand"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8(output.stderr).unwrap()
+}
+
+/// The dependency tree of `show`, without the resolved values after it
+fn tree(stderr: &str) -> &str {
+    stderr.split("Resolved:").next().unwrap()
+}
+
+#[test]
+fn without_depth_the_whole_chain_is_shown() {
+    let dir = setup("expand-depth-unlimited");
+    let stderr = show(&dir, &[]);
+    assert!(tree(&stderr).contains("ROOT = \"0\""), "{stderr}");
+    assert!(!stderr.contains('…'), "{stderr}");
+}
+
+#[test]
+fn depth_truncates_deeper_dependencies() {
+    let dir = setup("expand-depth-limited");
+    let stderr = show(&dir, &["--depth", "1"]);
+    // `mid` is shown with its contents, `base` only by name
+    let shown = tree(&stderr);
+    assert!(shown.contains("MID = \"1\""), "{stderr}");
+    assert!(shown.contains("base"), "{stderr}");
+    assert!(!shown.contains("BASE = \"1\""), "{stderr}");
+    assert!(shown.contains('…'), "{stderr}");
+    // The resolved values still cover the whole chain
+    assert!(stderr.contains("ROOT = \"0\""), "{stderr}");
+
+    let stderr = show(&dir, &["--depth", "0"]);
+    assert!(!tree(&stderr).contains("MID = \"1\""), "{stderr}");
+    assert!(tree(&stderr).contains("APP = \"1\""), "{stderr}");
+}
+
+#[test]
+fn depth_requires_expand() {
+    let dir = setup("expand-depth-requires");
+    let output = run(dir.path(), &["profile", "list", "--depth", "1"]);
+    assert!(!output.status.success());
+}
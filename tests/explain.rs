@@ -0,0 +1,116 @@
+//! `activate --explain` shows where each variable comes from instead of activating.
+
+mod common;
+
+use common::{TempDir, write_profile};
+use env_manage::api;
+use env_manage::config::ConfigManager;
+use std::path::Path;
+use std::process::Output;
+
+/// `app` inherits `left` and `right`, which both inherit `base`
+fn write_diamond(dir: &Path) {
+    for (name, content) in [
+        (
+            "base",
+            "[variables]\nHOST = \"base\"\nPORT = \"1\"\nLIST = \"/base\"\n",
+        ),
+        (
+            "left",
+            "profiles = [\"base\"]\n\n[variables]\nHOST = \"left\"\n\"LIST+\" = \"/left\"\n",
+        ),
+        (
+            "right",
+            "profiles = [\"base\"]\n\n[variables]\nHOST = \"right\"\n\"LIST+\" = \"/right\"\n",
+        ),
+        (
+            "app",
+            "profiles = [\"left\", \"right\"]\nunset = [\"GONE\"]\n\n[variables]\nNAME = \"app\"\n",
+        ),
+        ("extra", "[variables]\nPORT = \"2\"\nNAME = \"extra\"\n"),
+    ] {
+        write_profile(dir, name, content);
+    }
+}
+
+/// Run with `GONE` set, as `app` unsets it
+fn run_with_gone(dir: &Path, args: &[&str]) -> Output {
+    common::command(dir)
+        .args(args)
+        .env("GONE", "1")
+        .output()
+        .unwrap()
+}
+
+fn stderr_lines(output: &Output) -> Vec<String> {
+    String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect()
+}
+
+#[test]
+fn diamond_inheritance_names_the_winning_profile_and_the_chain() {
+    let dir = TempDir::new("explain-diamond");
+    write_diamond(dir.path());
+    let mut config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+
+    let merged = api::resolve_merged(&mut config_manager, &["app".to_string()]).unwrap();
+
+    let host = &merged.vars["HOST"];
+    assert_eq!(host.value, "right");
+    assert_eq!(host.sources, ["right"]);
+    assert_eq!(host.chain, ["base", "left", "right"]);
+
+    let list = &merged.vars["LIST"];
+    assert_eq!(list.value, "/base:/left:/right");
+    assert_eq!(list.sources, ["base", "left", "right"]);
+
+    assert_eq!(merged.vars["PORT"].chain, ["base"]);
+    assert!(merged.unset.contains("GONE"));
+}
+
+#[test]
+fn explain_prints_a_table_instead_of_activating() {
+    let dir = TempDir::new("explain-table");
+    write_diamond(dir.path());
+
+    let output = run_with_gone(
+        dir.path(),
+        &["activate", "--explain", "app", "extra", "PORT=3"],
+    );
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty(), "nothing is activated");
+
+    let lines = stderr_lines(&output);
+    let row = |key: &str| {
+        lines
+            .iter()
+            .find(|line| line.starts_with(&format!("{key} ")))
+            .cloned()
+            .unwrap_or_else(|| panic!("no row for {key} in {lines:?}"))
+    };
+    assert_eq!(row("HOST"), "HOST right right base -> left -> right");
+    assert_eq!(row("NAME"), "NAME extra extra app -> extra");
+    assert_eq!(
+        row("PORT"),
+        "PORT 3 (command line) base -> extra -> (command line)"
+    );
+    assert_eq!(
+        row("LIST"),
+        "LIST /base:/left:/right base, left, right base -> left -> right"
+    );
+    assert!(
+        lines.iter().any(|line| line.contains("Would unset: GONE")),
+        "{lines:?}"
+    );
+}
+
+#[test]
+fn explain_conflicts_with_print() {
+    let dir = TempDir::new("explain-print");
+    write_diamond(dir.path());
+
+    let output = run_with_gone(dir.path(), &["activate", "--explain", "--print", "app"]);
+    assert!(!output.status.success());
+}
@@ -0,0 +1,129 @@
+//! `global edit` keeps the global settings as they were unless the edited file loads.
+
+mod common;
+
+use common::TempDir;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Output, Stdio};
+
+const ORIGINAL: &str = "[variables]\nEDITOR = \"vim\"\n";
+
+/// A config directory with a `base` profile, the global settings and an editor script
+/// that writes each of `edits` in turn, one per run, exiting with `status`
+fn setup(name: &str, edits: &[&str], status: i32) -> TempDir {
+    let dir = TempDir::new(name);
+    fs::create_dir_all(dir.path().join("profiles")).unwrap();
+    fs::write(dir.path().join("profiles/base.toml"), "").unwrap();
+    fs::write(dir.path().join("global.toml"), ORIGINAL).unwrap();
+
+    let mut script = String::from("run=$(cat \"$0.count\" 2>/dev/null || echo 0)\n");
+    for (i, edit) in edits.iter().enumerate() {
+        fs::write(dir.path().join(format!("edit{i}")), edit).unwrap();
+    }
+    script.push_str("cp \"$(dirname \"$0\")/edit$run\" \"$1\"\n");
+    script.push_str("echo $((run + 1)) > \"$0.count\"\n");
+    script.push_str(&format!("exit {status}\n"));
+    fs::write(dir.path().join("editor.sh"), script).unwrap();
+    dir
+}
+
+fn edit(dir: &Path, answers: Option<&str>) -> Output {
+    let mut child = common::command(dir)
+        .args(["global", "edit"])
+        .env("VISUAL", format!("sh {}", dir.join("editor.sh").display()))
+        .stdin(if answers.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    if let Some(answers) = answers {
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(answers.as_bytes())
+            .unwrap();
+    }
+    child.wait_with_output().unwrap()
+}
+
+fn global(dir: &Path) -> String {
+    fs::read_to_string(dir.join("global.toml")).unwrap()
+}
+
+#[test]
+fn a_valid_edit_is_kept() {
+    let edited = "profiles = [\"base\"]\n\n[variables]\nEDITOR = \"nano\"\n";
+    let dir = setup("global-edit-valid", &[edited], 0);
+
+    let output = edit(dir.path(), None);
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Global settings updated"));
+    assert_eq!(global(dir.path()), edited);
+}
+
+#[test]
+fn an_editor_exiting_non_zero_restores_the_backup() {
+    let dir = setup("global-edit-editor-fails", &["profiles = [\"base\"]\n"], 3);
+
+    let output = edit(dir.path(), None);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("exited with"), "{stderr}");
+    assert!(stderr.contains("global settings restored"), "{stderr}");
+    assert_eq!(global(dir.path()), ORIGINAL);
+}
+
+#[test]
+fn a_parse_error_is_shown_with_its_line_and_aborting_restores() {
+    let dir = setup("global-edit-parse", &["[variables]\nEDITOR = \n"], 0);
+
+    let output = edit(dir.path(), Some("a\n"));
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("line 2"), "{stderr}");
+    assert!(stderr.contains("[a] abort"), "{stderr}");
+    assert_eq!(global(dir.path()), ORIGINAL);
+
+    // Without a terminal to answer, the edit is aborted as well
+    fs::remove_file(dir.path().join("editor.sh.count")).unwrap();
+    let output = edit(dir.path(), None);
+    assert!(!output.status.success());
+    assert_eq!(global(dir.path()), ORIGINAL);
+}
+
+#[test]
+fn a_missing_dependency_can_be_fixed_by_editing_again() {
+    let broken = "profiles = [\"ghost\"]\n";
+    let fixed = "profiles = [\"base\"]\n";
+    let dir = setup("global-edit-dependency", &[broken, fixed], 0);
+
+    let output = edit(dir.path(), Some("e\n"));
+
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("line 1: Profile 'GLOBAL' references non-existent profile 'ghost'"),
+        "{stderr}"
+    );
+    assert_eq!(global(dir.path()), fixed);
+}
+
+#[test]
+fn an_unchanged_file_is_reported() {
+    let dir = setup("global-edit-unchanged", &[ORIGINAL], 0);
+
+    let output = edit(dir.path(), None);
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No changes made"));
+}
@@ -0,0 +1,260 @@
+//! The global profile's reserved name cannot be used by regular profiles, and its
+//! variables sit below every activated profile.
+
+mod common;
+
+use common::{TempDir, run, write_profile};
+use env_manage::config::graph::{DependencyError, ProfileGraph};
+use env_manage::config::loader::{self, SkipReason};
+use env_manage::config::models::Profile;
+use env_manage::utils::{IdentifierError, validate_namespaced_profile_name};
+use env_manage::{GLOBAL_PROFILE_MARK, SHELL_MARK};
+use std::collections::HashMap;
+use std::fs;
+use std::process::Output;
+
+/// The findings of `check --format json`
+fn findings(output: &Output) -> Vec<serde_json::Value> {
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+fn profile_depending_on(dependency: &str) -> Profile {
+    let mut profile = Profile::new();
+    profile.profiles.insert(dependency.to_string());
+    profile
+}
+
+#[test]
+fn the_global_mark_is_not_a_valid_profile_name() {
+    assert_eq!(
+        validate_namespaced_profile_name(GLOBAL_PROFILE_MARK),
+        Err(IdentifierError::Reserved)
+    );
+}
+
+#[test]
+fn reserved_and_internal_names_are_not_valid_profile_names() {
+    for name in ["global", "GLOBAL", "Global"] {
+        assert_eq!(
+            validate_namespaced_profile_name(name),
+            Err(IdentifierError::Reserved),
+            "{name}"
+        );
+    }
+    assert_eq!(
+        validate_namespaced_profile_name(SHELL_MARK),
+        Err(IdentifierError::Internal)
+    );
+    assert_eq!(validate_namespaced_profile_name("global_dev"), Ok(()));
+    assert_eq!(validate_namespaced_profile_name("work/global"), Ok(()));
+}
+
+#[test]
+fn creating_a_reserved_profile_fails() {
+    let dir = TempDir::new("global-reserved-create");
+
+    for name in ["global", SHELL_MARK] {
+        let output = run(dir.path(), &["profile", "create", name]);
+        assert!(!output.status.success(), "{name}");
+        assert!(String::from_utf8_lossy(&output.stderr).contains("is reserved"));
+    }
+    let output = run(dir.path(), &["profile", "create", "dev"]);
+    assert!(output.status.success());
+    let output = run(dir.path(), &["profile", "rename", "dev", "GLOBAL"]);
+    assert!(!output.status.success());
+    assert!(dir.path().join("profiles/dev.toml").exists());
+}
+
+#[test]
+fn the_graph_rejects_edges_to_the_global_profile() {
+    let profiles = HashMap::from([
+        (
+            GLOBAL_PROFILE_MARK.to_string(),
+            profile_depending_on("base"),
+        ),
+        ("base".to_string(), Profile::new()),
+        ("app".to_string(), profile_depending_on(GLOBAL_PROFILE_MARK)),
+    ]);
+    assert!(matches!(
+        ProfileGraph::build(&profiles, None),
+        Err(DependencyError::GlobalDependency { parent, .. }) if parent == "app"
+    ));
+
+    // The global profile itself may depend on regular profiles
+    let mut graph = ProfileGraph::new();
+    for name in [GLOBAL_PROFILE_MARK, "base", "app"] {
+        graph.add_node(name.to_string());
+    }
+    graph.add_dependency(GLOBAL_PROFILE_MARK, "base").unwrap();
+    assert!(matches!(
+        graph.add_dependency("app", GLOBAL_PROFILE_MARK),
+        Err(DependencyError::GlobalDependency { parent, .. }) if parent == "app"
+    ));
+}
+
+#[test]
+fn a_profile_file_named_after_the_global_mark_is_skipped_and_reported() {
+    let dir = TempDir::new("global-reserved-file");
+    let profiles = dir.path().join("profiles");
+    fs::create_dir_all(&profiles).unwrap();
+    fs::write(
+        profiles.join(format!("{GLOBAL_PROFILE_MARK}.toml")),
+        "[variables]\nX = \"1\"\n",
+    )
+    .unwrap();
+    write_profile(
+        dir.path(),
+        "app",
+        &format!("profiles = [\"{GLOBAL_PROFILE_MARK}\"]\n"),
+    );
+
+    let report = loader::scan_profiles(&profiles).unwrap();
+    assert_eq!(report.files.len(), 1);
+    assert_eq!(report.skipped[0].reason, SkipReason::Reserved);
+
+    let list = run(dir.path(), &["profile", "list"]);
+    assert!(String::from_utf8_lossy(&list.stderr).contains(&format!(
+        "Ignoring '{GLOBAL_PROFILE_MARK}.toml': the name is reserved for the global profile"
+    )));
+
+    let check = run(dir.path(), &["check", "--format", "json"]);
+    assert!(!check.status.success());
+    let findings = findings(&check);
+    assert!(
+        findings.iter().any(
+            |f| f["severity"] == "warning" && f["profile"] == "__PRIVATE_GLOBAL_PROFILE__.toml"
+        )
+    );
+    assert!(
+        findings
+            .iter()
+            .any(|f| f["profile"] == "app" && f["kind"] == "global_dependency")
+    );
+
+    let activate = run(dir.path(), &["use", GLOBAL_PROFILE_MARK, "--print"]);
+    assert!(!activate.status.success());
+}
+
+#[test]
+fn global_variables_and_dependencies_are_the_lowest_layer() {
+    let dir = TempDir::new("global-precedence");
+    write_profile(
+        dir.path(),
+        "shared",
+        "[variables]\nFROM_SHARED = \"shared\"\nOVERRIDDEN = \"shared\"\n",
+    );
+    write_profile(dir.path(), "app", "[variables]\nOVERRIDDEN = \"app\"\n");
+    fs::write(
+        dir.path().join("global.toml"),
+        "profiles = [\"shared\"]\n\n[variables]\nFROM_GLOBAL = \"global\"\nOVERRIDDEN = \"global\"\n",
+    )
+    .unwrap();
+
+    let output = run(dir.path(), &["use", "app", "--explain"]);
+    let lines: Vec<String> = String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect();
+
+    assert_eq!(
+        lines,
+        [
+            "VARIABLE VALUE SOURCE DEFINED BY",
+            "FROM_GLOBAL global (global) (global)",
+            "FROM_SHARED shared shared shared",
+            "OVERRIDDEN app app shared -> (global) -> app",
+        ]
+    );
+}
+
+#[test]
+fn profile_list_keys_extend_the_global_list() {
+    let dir = TempDir::new("global-list-precedence");
+    write_profile(dir.path(), "a", "[variables]\n\"PATH+\" = \"/a/bin\"\n");
+    write_profile(dir.path(), "reset", "[variables]\nPATH = \"/r/bin\"\n");
+    fs::write(
+        dir.path().join("global.toml"),
+        "[variables]\n\"PATH+\" = \"/g\"\n",
+    )
+    .unwrap();
+
+    let activate = |profile: &str| {
+        let output = common::command(dir.path())
+            .args(["activate", profile, "--print", "--shell", "bash"])
+            .env("PATH", "/usr/bin")
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+    // The global entries come first, the activated profile extends them
+    assert!(activate("a").contains("export PATH='/usr/bin:/g:/a/bin'"));
+    // Setting the list outright still replaces the global entries
+    assert!(activate("reset").contains("export PATH='/r/bin'"));
+
+    let output = run(dir.path(), &["use", "a", "--explain"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr
+            .lines()
+            .any(|line| line.starts_with("PATH") && line.ends_with("(global) -> a")),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn check_compares_list_keys_by_variable_name() {
+    let dir = TempDir::new("global-shadowed-lists");
+    write_profile(dir.path(), "extends", "[variables]\n\"PATH+\" = \"/a\"\n");
+    write_profile(dir.path(), "masks", "[variables]\nPATH = \"/m\"\n");
+    fs::write(
+        dir.path().join("global.toml"),
+        "[variables]\n\"PATH+\" = \"/g\"\n",
+    )
+    .unwrap();
+
+    let output = run(dir.path(), &["check"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("'extends' overrides"), "{stderr}");
+    assert!(!stderr.contains("'PATH+'"), "{stderr}");
+    assert!(
+        stderr.contains("Profile 'masks' overrides GLOBAL variable 'PATH': '/g' -> '/m'"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn check_warns_about_profiles_overriding_global_variables() {
+    let dir = TempDir::new("global-shadowed");
+    write_profile(
+        dir.path(),
+        "app",
+        "[variables]\nPORT = \"8080\"\nREGION = \"eu\"\nOWN = \"app\"\n",
+    );
+    fs::write(
+        dir.path().join("global.toml"),
+        "[variables]\nPORT = \"80\"\nREGION = \"eu\"\n",
+    )
+    .unwrap();
+
+    let output = run(dir.path(), &["check"]);
+    assert!(output.status.success(), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Profile 'app' overrides GLOBAL variable 'PORT': '80' -> '8080'"),
+        "{stderr}"
+    );
+    // Setting the same value masks nothing
+    assert!(!stderr.contains("'REGION'"), "{stderr}");
+
+    let output = run(dir.path(), &["check", "--format", "json"]);
+    assert!(
+        findings(&output).iter().any(|f| f["severity"] == "warning"
+            && f["profile"] == "app"
+            && f["kind"] == "shadowed_global"),
+        "{output:?}"
+    );
+
+    let output = run(dir.path(), &["check", "--warnings-as-errors"]);
+    assert!(!output.status.success());
+}
@@ -0,0 +1,113 @@
+//! Profile changes are appended to `history.log` and shown by `em history`.
+
+mod common;
+
+use common::{TempDir, run};
+use env_manage::config::ConfigManager;
+use env_manage::config::history::timestamp;
+use env_manage::config::models::Profile;
+use env_manage::tui::app::App;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// The operation part of each history line, without the timestamp
+fn operations(dir: &Path) -> Vec<String> {
+    fs::read_to_string(dir.join("history.log"))
+        .unwrap()
+        .lines()
+        .map(|line| line.split_once(' ').unwrap().1.to_string())
+        .collect()
+}
+
+#[test]
+fn timestamps_are_utc_iso_8601() {
+    assert_eq!(timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+    let leap_day = UNIX_EPOCH + Duration::from_secs(951_827_696);
+    assert_eq!(timestamp(leap_day), "2000-02-29T12:34:56Z");
+    let new_year = UNIX_EPOCH + Duration::from_secs(1_735_689_599);
+    assert_eq!(timestamp(new_year), "2024-12-31T23:59:59Z");
+}
+
+#[test]
+fn cli_changes_are_recorded_and_listed() {
+    let dir = TempDir::new("history-cli");
+    for args in [
+        &["profile", "create", "app"][..],
+        &["profile", "add", "app", "KEY=value"],
+        &["profile", "rename", "app", "web"],
+        &["profile", "delete", "web"],
+        &["profile", "show", "missing"],
+    ] {
+        run(dir.path(), args);
+    }
+
+    assert_eq!(
+        operations(dir.path()),
+        [
+            "cli create app",
+            "cli edit app",
+            "cli rename app -> web",
+            "cli delete web"
+        ]
+    );
+
+    let output = run(dir.path(), &["history", "-n", "2"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2, "{stdout}");
+    assert!(lines[0].ends_with(" cli rename app -> web"), "{stdout}");
+    assert!(lines[1].ends_with(" cli delete web"), "{stdout}");
+}
+
+#[test]
+fn empty_history_is_reported() {
+    let dir = TempDir::new("history-empty");
+    let output = run(dir.path(), &["history"]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No profile changes recorded yet"));
+}
+
+#[test]
+fn a_broken_log_does_not_block_changes() {
+    let dir = TempDir::new("history-broken");
+    fs::create_dir_all(dir.path().join("history.log")).unwrap();
+    let output = run(dir.path(), &["profile", "create", "app"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(dir.path().join("profiles").join("app.toml").exists());
+}
+
+#[test]
+fn tui_saves_renames_and_deletes_are_recorded() {
+    let dir = TempDir::new("history-tui");
+    let mut config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    let mut profile = Profile::new();
+    profile.add_variable("KEY", "value");
+    config_manager.write_profile("app", &profile).unwrap();
+    config_manager.add_profile_node("app".to_string());
+    config_manager.add_profile("app".to_string(), profile.clone());
+    config_manager.add_profile_node("fresh".to_string());
+    config_manager.add_profile("fresh".to_string(), profile);
+
+    let mut app = App::new(config_manager, Profile::new());
+    app.list_view.mark_dirty("fresh".to_string());
+    assert!(app.list_view.select_profile("app"));
+    app.rename_profile("web".to_string()).unwrap();
+    app.save_all().unwrap();
+
+    assert!(app.list_view.select_profile("fresh"));
+    app.delete_selected_profile().unwrap();
+
+    let mut recorded = operations(dir.path());
+    recorded[..2].sort();
+    assert_eq!(
+        recorded,
+        [
+            "tui create fresh",
+            "tui rename app -> web",
+            "tui delete fresh"
+        ]
+    );
+}
@@ -0,0 +1,215 @@
+//! Profiles can run shell commands when they are activated and deactivated, once trusted.
+
+mod common;
+
+use common::{TempDir, run, stderr, stdout, write_profile};
+use env_manage::config::models::Profile;
+use env_manage::tui::views::edit::EditView;
+use std::fs;
+use std::path::Path;
+
+fn write_profiles(dir: &Path) {
+    write_profile(
+        dir,
+        "base",
+        "on_activate = [\"echo base-up\"]\n\n[variables]\nB = \"1\"\n",
+    );
+    write_profile(
+        dir,
+        "app",
+        "profiles = [\"base\"]\non_activate = [\"echo app-up\", \"true\"]\non_deactivate = [\"echo app-down\"]\n\n[variables]\nA = \"2\"\n",
+    );
+}
+
+#[test]
+fn untrusted_hooks_are_skipped_without_a_terminal() {
+    let dir = TempDir::new("hooks-untrusted");
+    write_profiles(dir.path());
+
+    let output = run(dir.path(), &["activate", "app", "--shell", "bash"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(!stdout(&output).contains("echo"), "{}", stdout(&output));
+    assert!(stdout(&output).contains("export A='2'"));
+    assert!(
+        stderr(&output).contains("Skipping the hooks of profile 'app'"),
+        "{}",
+        stderr(&output)
+    );
+    assert!(!dir.path().join("config.toml").exists());
+}
+
+#[test]
+fn allowed_hooks_run_after_the_exports_and_stay_trusted() {
+    let dir = TempDir::new("hooks-allowed");
+    write_profiles(dir.path());
+
+    let output = run(
+        dir.path(),
+        &["activate", "app", "--shell", "bash", "--allow-hooks"],
+    );
+    assert!(output.status.success(), "{output:?}");
+    let script = stdout(&output);
+    let lines: Vec<&str> = script.lines().collect();
+    let exports = lines
+        .iter()
+        .rposition(|l| l.starts_with("export B="))
+        .unwrap();
+    let base = lines.iter().position(|l| *l == "echo base-up").unwrap();
+    let app = lines
+        .iter()
+        .position(|l| *l == "echo app-up && true")
+        .unwrap();
+    assert!(exports < base && base < app, "{script}");
+
+    // Trust was recorded, so the flag is no longer needed
+    let output = run(dir.path(), &["activate", "app", "--shell", "fish"]);
+    assert!(stdout(&output).contains("echo app-up && true"));
+
+    // Changing the commands revokes the trust
+    let app_file = dir.path().join("profiles").join("app.toml");
+    let changed = fs::read_to_string(&app_file)
+        .unwrap()
+        .replace("true", "false");
+    fs::write(&app_file, changed).unwrap();
+    let output = run(dir.path(), &["activate", "app", "--shell", "bash"]);
+    assert!(!stdout(&output).contains("app-up"), "{}", stdout(&output));
+    assert!(stdout(&output).contains("echo base-up"));
+}
+
+#[test]
+fn hooks_are_one_command_per_line_in_powershell() {
+    let dir = TempDir::new("hooks-lines");
+    write_profiles(dir.path());
+
+    let output = run(
+        dir.path(),
+        &["activate", "app", "--shell", "powershell", "--allow-hooks"],
+    );
+    let script = stdout(&output);
+    assert!(script.contains("echo app-up\ntrue\n"), "{script}");
+}
+
+#[test]
+fn nu_hooks_are_marked_for_the_wrapper_before_the_state_lines() {
+    let dir = TempDir::new("hooks-nu");
+    write_profiles(dir.path());
+
+    let output = run(
+        dir.path(),
+        &["activate", "app", "--shell", "nu", "--allow-hooks"],
+    );
+    assert!(output.status.success(), "{output:?}");
+    let script = stdout(&output);
+    let lines: Vec<&str> = script.lines().skip(1).collect();
+    // The README wrapper runs `hook` lines and parses every other line as a variable
+    for line in &lines {
+        assert!(
+            line.starts_with("hook \"")
+                || line.starts_with("hide-env ")
+                || line.starts_with("$env.") && line.contains(" = "),
+            "{line}"
+        );
+    }
+    let hook = |line: &str| lines.iter().position(|l| *l == line).unwrap();
+    let state = lines
+        .iter()
+        .position(|l| l.starts_with("$env.__ENV_MANAGE_ACTIVE_PROFILES = "))
+        .unwrap();
+    assert!(hook("hook \"echo base-up\"") < hook("hook \"echo app-up\""));
+    assert!(hook("hook \"echo app-up\"") < hook("hook \"true\""));
+    assert!(hook("hook \"true\"") < state, "{script}");
+    assert!(
+        lines
+            .iter()
+            .any(|l| l.starts_with("$env.__ENV_MANAGE_TRACKED_VARS = ")),
+        "{script}"
+    );
+}
+
+#[test]
+fn print_shows_allowed_hooks_without_trusting_them() {
+    let dir = TempDir::new("hooks-print");
+    write_profiles(dir.path());
+
+    let output = run(
+        dir.path(),
+        &[
+            "activate",
+            "app",
+            "--shell",
+            "bash",
+            "--print",
+            "--allow-hooks",
+        ],
+    );
+    assert!(stdout(&output).contains("echo app-up && true"));
+    assert!(!dir.path().join("config.toml").exists());
+}
+
+#[test]
+fn deactivation_hooks_run_before_the_unsets() {
+    let dir = TempDir::new("hooks-deactivate");
+    write_profiles(dir.path());
+
+    let output = run(
+        dir.path(),
+        &["deactivate", "app", "--shell", "bash", "--allow-hooks"],
+    );
+    assert!(output.status.success(), "{output:?}");
+    let script = stdout(&output);
+    let hook = script.find("echo app-down").expect(&script);
+    let unset = script.find("unset A").expect(&script);
+    assert!(hook < unset, "{script}");
+}
+
+#[test]
+fn trust_is_stored_next_to_the_other_settings() {
+    let dir = TempDir::new("hooks-settings");
+    write_profiles(dir.path());
+    fs::write(
+        dir.path().join("config.toml"),
+        "[denylist]\nvariables = [\"LD_PRELOAD\"]\n",
+    )
+    .unwrap();
+
+    run(
+        dir.path(),
+        &["activate", "base", "--shell", "bash", "--allow-hooks"],
+    );
+    let settings = fs::read_to_string(dir.path().join("config.toml")).unwrap();
+    assert!(settings.contains("LD_PRELOAD"), "{settings}");
+    assert!(settings.contains("[trusted_hooks.base]"), "{settings}");
+}
+
+#[test]
+fn check_lists_profiles_with_hooks_without_failing() {
+    let dir = TempDir::new("hooks-check");
+    write_profiles(dir.path());
+
+    let output = run(
+        dir.path(),
+        &["check", "--format", "json", "--warnings-as-errors"],
+    );
+    assert!(output.status.success(), "{output:?}");
+    let findings: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(
+        findings.iter().any(|f| f["kind"] == "hooks"
+            && f["severity"] == "info"
+            && f["message"] == "Profile 'app' runs 3 hook commands (not trusted)"),
+        "{findings:?}"
+    );
+}
+
+#[test]
+fn editing_in_the_tui_keeps_the_hooks() {
+    let mut profile = Profile::new();
+    profile.on_activate = vec!["echo up".to_string()];
+    profile.on_deactivate = vec!["echo down".to_string()];
+    profile.add_variable("KEY", "value");
+
+    let edit_view = EditView::from_profile("app", &profile);
+    assert_eq!(edit_view.hooks(), &profile.hooks());
+    let saved = edit_view.to_profile();
+    assert_eq!(saved.on_activate, profile.on_activate);
+    assert_eq!(saved.on_deactivate, profile.on_deactivate);
+}
@@ -0,0 +1,114 @@
+//! `Input` and `TextArea` move over, insert and delete whole grapheme clusters, and
+//! measure the text before the cursor in terminal columns.
+
+use env_manage::tui::utils::{Input, TextArea};
+
+const FAMILY: &str = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+const E_ACUTE: &str = "e\u{301}";
+
+fn typed(text: &str) -> Input {
+    let mut input = Input::new();
+    text.chars().for_each(|c| input.enter_char(c));
+    input
+}
+
+#[test]
+fn zwj_emoji_is_one_character() {
+    let mut input = typed(&format!("a{FAMILY}b"));
+    assert_eq!(input.cursor_position(), 3);
+
+    input.move_cursor_left();
+    input.delete_char();
+    assert_eq!(input.text(), "ab");
+    assert_eq!(input.cursor_position(), 1);
+
+    input.set_text(format!("{FAMILY}{FAMILY}"));
+    assert_eq!(input.cursor_position(), 2);
+    input.move_cursor_left();
+    assert_eq!(input.cursor_display_width(), 2);
+}
+
+#[test]
+fn combining_accents_join_the_letter_before_them() {
+    let mut input = typed(&format!("caf{E_ACUTE}"));
+    assert_eq!(input.cursor_position(), 4);
+    assert_eq!(input.cursor_display_width(), 4);
+
+    input.delete_char();
+    assert_eq!(input.text(), "caf");
+
+    // An accent typed after moving left attaches to the letter before the cursor
+    input.move_cursor_left();
+    input.enter_char('\u{301}');
+    assert_eq!(input.text(), "ca\u{301}f");
+    assert_eq!(input.cursor_position(), 2);
+    input.move_cursor_right();
+    assert_eq!(input.cursor_position(), 3);
+}
+
+#[test]
+fn cjk_characters_are_two_columns_wide() {
+    let mut input = typed("中文");
+    assert_eq!(input.cursor_display_width(), 4);
+
+    input.move_cursor_left();
+    assert_eq!(input.cursor_display_width(), 2);
+    input.enter_char('x');
+    assert_eq!(input.text(), "中x文");
+    assert_eq!(input.cursor_display_width(), 3);
+
+    input.delete_char();
+    input.delete_char();
+    assert_eq!(input.text(), "文");
+    assert_eq!(input.cursor_position(), 0);
+    input.delete_char();
+    assert_eq!(input.text(), "文");
+}
+
+#[test]
+fn mixed_width_text_measures_each_cluster() {
+    let input = Input::with_text(format!("a中{E_ACUTE}😀{FAMILY}"));
+    assert_eq!(input.cursor_position(), 5);
+    assert_eq!(input.cursor_display_width(), 1 + 2 + 1 + 2 + 2);
+
+    let mut input = Input::from_parts(input.text().to_string(), 99, None);
+    assert_eq!(input.cursor_position(), 5);
+    input.set_cursor_position(2);
+    assert_eq!(input.cursor_display_width(), 3);
+    input.move_cursor_right();
+    input.move_cursor_right();
+    assert_eq!(input.cursor_display_width(), 6);
+    input.delete_char();
+    assert_eq!(input.text(), format!("a中{E_ACUTE}{FAMILY}"));
+}
+
+#[test]
+fn text_area_columns_are_grapheme_clusters() {
+    let mut area = TextArea::with_text(&format!("first\na{FAMILY}中"));
+    assert_eq!(area.cursor(), (1, 3));
+    assert_eq!(area.cursor_display_width(), 1 + 2 + 2);
+
+    area.move_cursor_left();
+    area.delete_char();
+    assert_eq!(area.text(), "first\na中");
+    assert_eq!(area.cursor(), (1, 1));
+    assert_eq!(area.cursor_display_width(), 1);
+
+    area.move_cursor_end();
+    area.enter_char('e');
+    area.enter_char('\u{301}');
+    assert_eq!(area.text(), format!("first\na中{E_ACUTE}"));
+    assert_eq!(area.cursor(), (1, 3));
+    area.delete_char();
+    assert_eq!(area.text(), "first\na中");
+
+    // Moving up keeps the column within the shorter line, in clusters
+    area.move_cursor_up();
+    area.move_cursor_end();
+    area.move_cursor_down();
+    assert_eq!(area.cursor(), (1, 2));
+    area.move_cursor_home();
+    area.delete_char();
+    assert_eq!(area.text(), "firsta中");
+    assert_eq!(area.cursor(), (0, 5));
+}
@@ -0,0 +1,169 @@
+//! Operations that change several files write a journal entry first, so `check` can
+//! report an interrupted one and `fix` can finish or undo it.
+
+mod common;
+
+use common::{TempDir, profile_path, read_profile, run, write_profile};
+use env_manage::config::ConfigManager;
+use env_manage::config::journal::{self, FileChange, Journal};
+use env_manage::config::models::Profile;
+use env_manage::tui::app::App;
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+fn report(output: &Output) -> String {
+    format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    )
+}
+
+/// Journal rewriting `a`, deleting `b` and creating `c`, then "crash" after the first
+fn interrupted(dir: &Path) -> Journal {
+    write_profile(dir, "a", "[variables]\nA = \"old\"\n");
+    write_profile(dir, "b", "[variables]\nB = \"1\"\n");
+    let changes = [
+        ("a", Some("[variables]\nA = \"new\"\n")),
+        ("b", None),
+        ("c", Some("[variables]\nC = \"1\"\n")),
+    ]
+    .into_iter()
+    .map(|(name, after)| {
+        FileChange::capture(dir, &profile_path(dir, name), after.map(str::to_string)).unwrap()
+    })
+    .collect();
+    let journal = Journal::begin(dir, "move things", changes).unwrap();
+    write_profile(dir, "a", "[variables]\nA = \"new\"\n");
+    journal
+}
+
+#[test]
+fn fix_rolls_an_interrupted_operation_forward() {
+    let dir = TempDir::new("journal-forward");
+    let _crashed = interrupted(dir.path());
+
+    let output = run(dir.path(), &["check"]);
+    assert!(
+        report(&output).contains("Operation 'move things' started at"),
+        "{output:?}"
+    );
+    assert!(report(&output).contains("did not finish (1 of 3 files written)"));
+
+    let output = run(dir.path(), &["fix", "--yes"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(report(&output).contains("Finished interrupted operation 'move things'"));
+    assert_eq!(
+        read_profile(dir.path(), "a").as_deref(),
+        Some("[variables]\nA = \"new\"\n")
+    );
+    assert_eq!(read_profile(dir.path(), "b"), None);
+    assert_eq!(
+        read_profile(dir.path(), "c").as_deref(),
+        Some("[variables]\nC = \"1\"\n")
+    );
+
+    assert!(journal::incomplete(dir.path()).is_empty());
+    assert!(!report(&run(dir.path(), &["check"])).contains("did not finish"));
+}
+
+#[test]
+fn rolling_back_restores_every_file() {
+    let dir = TempDir::new("journal-back");
+    let _crashed = interrupted(dir.path());
+
+    let [(path, entry)]: [_; 1] = journal::incomplete(dir.path()).try_into().unwrap();
+    assert_eq!(entry.applied(dir.path()).unwrap(), 1);
+    entry.roll_back(dir.path()).unwrap();
+    journal::resolve(&path, &entry).unwrap();
+
+    assert_eq!(
+        read_profile(dir.path(), "a").as_deref(),
+        Some("[variables]\nA = \"old\"\n")
+    );
+    assert_eq!(
+        read_profile(dir.path(), "b").as_deref(),
+        Some("[variables]\nB = \"1\"\n")
+    );
+    assert_eq!(read_profile(dir.path(), "c"), None);
+    assert!(journal::incomplete(dir.path()).is_empty());
+}
+
+#[test]
+fn old_finished_entries_are_collected() {
+    let dir = TempDir::new("journal-gc");
+    let journal_dir = journal::journal_dir(dir.path());
+    fs::create_dir_all(&journal_dir).unwrap();
+    fs::write(
+        journal_dir.join("0-1.toml"),
+        "operation = \"old\"\nstarted = 0\ncomplete = true\n",
+    )
+    .unwrap();
+    fs::write(
+        journal_dir.join("0-2.toml"),
+        "operation = \"stuck\"\nstarted = 0\n",
+    )
+    .unwrap();
+
+    journal::collect_garbage(dir.path(), journal::JOURNAL_RETENTION_DAYS * 86_400);
+    assert!(!journal_dir.join("0-1.toml").exists());
+    // Only `fix` resolves an interrupted operation
+    assert!(journal_dir.join("0-2.toml").exists());
+}
+
+#[test]
+fn rename_finishes_its_entry() {
+    let dir = TempDir::new("journal-rename");
+    write_profile(dir.path(), "base", "[variables]\nA = \"1\"\n");
+    write_profile(dir.path(), "app", "profiles = [\"base\"]\n");
+
+    let output = run(dir.path(), &["profile", "rename", "base", "core"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(
+        read_profile(dir.path(), "app")
+            .unwrap()
+            .contains("\"core\"")
+    );
+    assert!(journal::incomplete(dir.path()).is_empty());
+    assert_eq!(
+        fs::read_dir(journal::journal_dir(dir.path()))
+            .unwrap()
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn failed_tui_save_is_left_for_fix() {
+    let dir = TempDir::new("journal-tui");
+    write_profile(dir.path(), "a", "[variables]\nA = \"1\"\n");
+    write_profile(dir.path(), "b", "[variables]\nB = \"1\"\n");
+    let config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    let mut app = App::new(config_manager, Profile::new());
+    for name in ["a", "b"] {
+        assert!(app.ensure_loaded(name));
+        app.config_manager
+            .get_profile_mut(name)
+            .unwrap()
+            .add_variable("NEW", "2");
+        app.list_view.mark_dirty(name.to_string());
+    }
+
+    // Occupy the temporary file `b` is written through, so only its write fails
+    let blocker = dir
+        .path()
+        .join("profiles")
+        .join(format!(".b.toml.{}.tmp", std::process::id()));
+    fs::create_dir_all(blocker.join("full")).unwrap();
+    app.save_all().unwrap();
+    assert!(read_profile(dir.path(), "a").unwrap().contains("NEW"));
+    assert!(!read_profile(dir.path(), "b").unwrap().contains("NEW"));
+    assert_eq!(journal::incomplete(dir.path()).len(), 1);
+
+    fs::remove_dir_all(&blocker).unwrap();
+    let output = run(dir.path(), &["fix", "--yes"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(read_profile(dir.path(), "b").unwrap().contains("NEW"));
+    assert!(journal::incomplete(dir.path()).is_empty());
+}
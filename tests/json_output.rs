@@ -0,0 +1,191 @@
+//! `profile list --json` and `status --json` emit stable, machine-readable reports on stdout.
+
+mod common;
+
+use common::{TempDir, profile_path, write_profile};
+use std::fs::{self, File};
+use std::path::Path;
+use std::process::Output;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Every profile file gets this mtime, 2024-05-01T12:30:00Z
+const MODIFIED: u64 = 1_714_566_600;
+
+fn write_profiles(dir: &Path) {
+    for (name, content) in [
+        (
+            "base",
+            "tags = [\"core\"]\n\n[variables]\nPORT = \"1\"\nHOST = \"local\"\n",
+        ),
+        (
+            "web",
+            "description = \"The \\\"web\\\" app\"\nprofiles = [\"base\"]\n\n[variables]\nNAME = \"web\"\n",
+        ),
+    ] {
+        write_profile(dir, name, content);
+        File::options()
+            .write(true)
+            .open(profile_path(dir, name))
+            .unwrap()
+            .set_modified(UNIX_EPOCH + Duration::from_secs(MODIFIED))
+            .unwrap();
+    }
+}
+
+/// Run with `env` set, and none of the variables the profiles set
+fn run_with(dir: &Path, args: &[&str], env: &[(&str, &str)]) -> Output {
+    let mut command = common::command(dir);
+    command.args(args);
+    for key in ["PORT", "HOST", "NAME"] {
+        command.env_remove(key);
+    }
+    command.envs(env.iter().copied()).output().unwrap()
+}
+
+/// Stdout with the temporary directory replaced, so the snapshot does not depend on it
+fn snapshot(output: &Output, dir: &Path) -> String {
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8_lossy(&output.stdout).replace(&dir.display().to_string(), "$DIR")
+}
+
+#[test]
+fn profile_list_json_snapshot() {
+    let dir = TempDir::new("json-profile-list");
+    write_profiles(dir.path());
+
+    let output = run_with(dir.path(), &["profile", "list", "--json"], &[]);
+
+    assert_eq!(
+        snapshot(&output, dir.path()),
+        r#"{
+  "profiles": [
+    {
+      "modified": "2024-05-01T12:30:00Z",
+      "name": "base",
+      "path": "$DIR/profiles/base.toml",
+      "profiles": [],
+      "tags": [
+        "core"
+      ],
+      "variables": {
+        "HOST": "local",
+        "PORT": "1"
+      }
+    },
+    {
+      "description": "The \"web\" app",
+      "modified": "2024-05-01T12:30:00Z",
+      "name": "web",
+      "path": "$DIR/profiles/web.toml",
+      "profiles": [
+        "base"
+      ],
+      "variables": {
+        "NAME": "web"
+      }
+    }
+  ],
+  "version": 1
+}
+"#
+    );
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn profile_list_json_reports_broken_profiles_and_an_empty_filter() {
+    let dir = TempDir::new("json-profile-list-broken");
+    write_profiles(dir.path());
+    fs::write(dir.path().join("profiles/broken.toml"), "not toml").unwrap();
+
+    let output = run_with(dir.path(), &["profile", "list", "--json"], &[]);
+    let json = snapshot(&output, dir.path());
+    assert!(json.contains("\"name\": \"broken\""));
+    assert!(json.contains("\"error\": \"Failed to parse profile 'broken'"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Could not load profile 'broken'"));
+
+    let output = run_with(
+        dir.path(),
+        &["profile", "list", "--json", "--tag", "missing"],
+        &[],
+    );
+    assert_eq!(
+        snapshot(&output, dir.path()),
+        "{\n  \"profiles\": [],\n  \"version\": 1\n}\n"
+    );
+}
+
+#[test]
+fn status_json_snapshot() {
+    let dir = TempDir::new("json-status");
+    write_profiles(dir.path());
+
+    let output = run_with(
+        dir.path(),
+        &["status", "--json", "web"],
+        &[
+            ("PORT", "1"),
+            ("HOST", "remote"),
+            ("__ENV_MANAGE_ACTIVE_PROFILES", "web"),
+            ("__ENV_MANAGE_TRACKED_VARS", "PORT:NAME"),
+        ],
+    );
+
+    assert_eq!(
+        snapshot(&output, dir.path()),
+        r#"{
+  "active_profiles": [
+    "web"
+  ],
+  "partially_active": {
+    "web": [
+      "NAME",
+      "PORT"
+    ]
+  },
+  "profiles": [
+    "web"
+  ],
+  "variables": {
+    "HOST": {
+      "current": "remote",
+      "expected": "local",
+      "state": "mismatch",
+      "tracked": false
+    },
+    "NAME": {
+      "current": null,
+      "expected": "web",
+      "state": "profile_only",
+      "tracked": true
+    },
+    "PORT": {
+      "current": "1",
+      "expected": "1",
+      "state": "match",
+      "tracked": true
+    }
+  },
+  "version": 1
+}
+"#
+    );
+}
+
+#[test]
+fn status_json_defaults_to_the_active_profiles() {
+    let dir = TempDir::new("json-status-active");
+    write_profiles(dir.path());
+
+    let output = run_with(
+        dir.path(),
+        &["status", "--json"],
+        &[("__ENV_MANAGE_ACTIVE_PROFILES", "base:gone")],
+    );
+
+    let json = snapshot(&output, dir.path());
+    assert!(json.contains("\"profiles\": [\n    \"base\"\n  ]"));
+    assert!(json.contains("\"PORT\": {"));
+    assert!(!json.contains("\"NAME\""));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Profile 'gone' not found"));
+}
@@ -0,0 +1,108 @@
+//! Strict profiles only accept ALL_CAPS variable keys; `check` reports the others
+//! and `check --fix` offers to uppercase them.
+
+mod common;
+
+use common::{TempDir, read_profile, stderr, write_profile};
+use env_manage::api::{self, ApiError};
+use env_manage::config::ConfigManager;
+use env_manage::utils::IdentifierError;
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+fn check(dir: &Path, args: &[&str]) -> Output {
+    common::command(dir)
+        .arg("check")
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn check_reports_lowercase_keys_of_strict_profiles_only() {
+    let dir = TempDir::new("key-validation-check");
+    write_profile(
+        dir.path(),
+        "strict",
+        "validation = \"strict\"\n[variables]\nport = \"1\"\n",
+    );
+    write_profile(dir.path(), "relaxed", "[variables]\nport = \"1\"\n");
+
+    let output = check(dir.path(), &[]);
+    let report = stderr(&output);
+    assert!(!output.status.success(), "{report}");
+    assert!(
+        report.contains("Invalid variable key 'port' in strict profile 'strict'"),
+        "{report}"
+    );
+    assert!(!report.contains("'relaxed'"), "{report}");
+}
+
+#[test]
+fn the_global_default_applies_unless_a_profile_opts_out() {
+    let dir = TempDir::new("key-validation-default");
+    fs::write(dir.path().join("config.toml"), "validation = \"strict\"\n").unwrap();
+    write_profile(dir.path(), "app", "[variables]\nport = \"1\"\n");
+    write_profile(
+        dir.path(),
+        "legacy",
+        "validation = \"relaxed\"\n[variables]\nport = \"1\"\n",
+    );
+
+    let report = stderr(&check(dir.path(), &[]));
+    assert!(report.contains("strict profile 'app'"), "{report}");
+    assert!(!report.contains("'legacy'"), "{report}");
+}
+
+#[test]
+fn check_fix_uppercases_keys_and_skips_collisions() {
+    let dir = TempDir::new("key-validation-fix");
+    write_profile(
+        dir.path(),
+        "app",
+        "validation = \"strict\"\n[variables]\nport = \"1\"\nhost = \"a\"\nHOST = \"b\"\n",
+    );
+
+    let output = check(dir.path(), &["--fix", "--yes"]);
+    let report = stderr(&output);
+    assert!(
+        report.contains("Renamed variable 'port' to 'PORT' in profile 'app'"),
+        "{report}"
+    );
+    assert!(
+        report.contains("Cannot rename 'host' in profile 'app': 'HOST' is already set"),
+        "{report}"
+    );
+    assert!(!output.status.success(), "{report}");
+
+    let app = read_profile(&dir, "app").unwrap();
+    // The renamed key keeps its place in the file
+    let (_, variables) = app.split_once("[variables]\n").unwrap();
+    let order: Vec<&str> = variables.lines().collect();
+    assert_eq!(
+        order,
+        ["PORT = \"1\"", "host = \"a\"", "HOST = \"b\""],
+        "{app}"
+    );
+    assert!(app.contains("validation = \"strict\""), "{app}");
+}
+
+#[test]
+fn adding_a_lowercase_key_to_a_strict_profile_is_refused() {
+    let dir = TempDir::new("key-validation-add");
+    write_profile(dir.path(), "app", "validation = \"strict\"\n");
+    write_profile(dir.path(), "lax", "");
+    let mut config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+
+    let err = api::add_items(&mut config_manager, "app", &["port=1".to_string()]).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            ApiError::InvalidVariableKey(IdentifierError::ContainsLowercase)
+        ),
+        "{err}"
+    );
+    api::add_items(&mut config_manager, "app", &["PORT+=1".to_string()]).unwrap();
+    api::add_items(&mut config_manager, "lax", &["port=1".to_string()]).unwrap();
+}
@@ -0,0 +1,48 @@
+//! The TUI lists every profile from the directory scan but only reads the files it
+//! needs, so the first frame of a large configuration does not wait for all of them.
+
+mod common;
+
+use common::{TempDir, write_profile};
+use env_manage::GLOBAL_PROFILE_MARK;
+use env_manage::config::ConfigManager;
+use env_manage::config::models::Profile;
+use env_manage::tui::app::App;
+use env_manage::tui::ui::ui;
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+
+const PROFILES: usize = 1000;
+
+#[test]
+fn the_first_frame_reads_only_the_selected_profile() {
+    let dir = TempDir::new("lazy-load");
+    for i in 0..PROFILES {
+        write_profile(
+            dir.path(),
+            &format!("p{i:04}"),
+            "[variables]\nA = \"1\"\nB = \"2\"\n",
+        );
+    }
+
+    let config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    let mut app = App::new(config_manager, Profile::new());
+    // What the event loop does before drawing
+    app.load_selected();
+    let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+    terminal.draw(|frame| ui(frame, &app)).unwrap();
+
+    // GLOBAL is always in the list, so every scanned profile is there too
+    assert_eq!(app.list_view.all_profiles().len(), PROFILES + 1);
+    let mut loaded: Vec<&str> = app
+        .config_manager
+        .profiles_iter()
+        .map(|(name, _)| name.as_str())
+        .collect();
+    loaded.sort();
+    let selected = app.list_view.current_profile().unwrap();
+    let mut expected = vec![GLOBAL_PROFILE_MARK, selected];
+    expected.sort();
+    expected.dedup();
+    assert_eq!(loaded, expected);
+}
@@ -0,0 +1,69 @@
+//! `api::activation_script` renders what `em use --print` prints, since both build the
+//! script through the same functions.
+
+mod common;
+
+use common::{TempDir, run, stdout, write_profile};
+use env_manage::api::{self, ApiError};
+use env_manage::config::ConfigManager;
+use env_manage::utils::shell_generate::ShellType;
+use std::fs;
+
+fn script(dir: &TempDir, names: &[&str]) -> Result<String, ApiError> {
+    let mut config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    let names: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+    api::activation_script(&mut config_manager, &names, ShellType::Bash)
+}
+
+#[test]
+fn the_library_script_matches_the_cli() {
+    let dir = TempDir::new("library-activation");
+    write_profile(
+        dir.path(),
+        "app",
+        "on_activate = [\"echo hi\"]\n\n[variables]\nNAME = \"app\"\nSTAMP = \"command: date\"\nSECRET = \"x\"\n",
+    );
+    fs::write(
+        dir.path().join("global.toml"),
+        "[variables]\nREGION = \"eu\"\nG = \"command: whoami\"\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("config.toml"),
+        "[denylist]\nvariables = [\"SECRET\"]\n",
+    )
+    .unwrap();
+
+    let library = script(&dir, &["app"]).unwrap();
+    // The global layer and session tracking are there, denied and untrusted values not
+    assert!(library.contains("export REGION='eu'"), "{library}");
+    assert!(
+        library.contains("__ENV_MANAGE_ACTIVE_PROFILES='app'"),
+        "{library}"
+    );
+    for skipped in ["SECRET", "date", "whoami", "echo hi"] {
+        assert!(!library.contains(skipped), "{skipped} in {library}");
+    }
+
+    let output = run(dir.path(), &["use", "app", "--print", "--shell", "bash"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(stdout(&output).trim_end(), library);
+}
+
+#[test]
+fn a_refusing_denylist_fails_the_script() {
+    let dir = TempDir::new("library-activation-denied");
+    write_profile(dir.path(), "app", "[variables]\nSECRET = \"x\"\n");
+    fs::write(
+        dir.path().join("config.toml"),
+        "[denylist]\nvariables = [\"SECRET\"]\naction = \"error\"\n",
+    )
+    .unwrap();
+
+    let error = script(&dir, &["app"]).unwrap_err();
+    assert!(matches!(&error, ApiError::DeniedVariables(keys) if keys == &["SECRET"]));
+    assert_eq!(
+        error.to_string(),
+        "Refusing to activate, denied variables would be set: SECRET"
+    );
+}
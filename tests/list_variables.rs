@@ -0,0 +1,71 @@
+//! A list key such as `PATH+` extends the variable once per profile, however many
+//! paths lead to that profile through the dependencies, and on top of the entries
+//! other activated profiles added.
+
+mod common;
+
+use common::{TempDir, run, stdout, write_profile};
+use env_manage::api;
+use env_manage::config::ConfigManager;
+
+#[test]
+fn a_shared_dependency_appends_its_entries_once() {
+    let dir = TempDir::new("list-diamond");
+    // a -> b, c -> d
+    write_profile(
+        dir.path(),
+        "a",
+        "profiles = [\"b\", \"c\"]\n\n[variables]\n\"PATH+\" = \"/opt/a\"\n",
+    );
+    for name in ["b", "c"] {
+        write_profile(
+            dir.path(),
+            name,
+            &format!("profiles = [\"d\"]\n\n[variables]\n\"PATH+\" = \"/opt/{name}\"\n"),
+        );
+    }
+    write_profile(dir.path(), "d", "[variables]\n\"PATH+\" = \"/opt/d\"\n");
+
+    let mut config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    let vars = api::resolve_env(&mut config_manager, "a").unwrap();
+    let entries: Vec<&str> = vars["PATH"].split(':').collect();
+    for entry in ["/opt/a", "/opt/b", "/opt/c", "/opt/d"] {
+        assert_eq!(
+            entries.iter().filter(|e| **e == entry).count(),
+            1,
+            "{entry} in {entries:?}"
+        );
+    }
+    // The dependency is applied first, so the profile on top ends up last
+    let position = |entry| entries.iter().position(|e| *e == entry).unwrap();
+    assert!(position("/opt/d") < position("/opt/b"));
+    assert!(position("/opt/c") < position("/opt/a"));
+
+    let output = run(dir.path(), &["activate", "a", "--print", "--shell", "bash"]);
+    assert!(output.status.success(), "{output:?}");
+    let script = stdout(&output);
+    assert_eq!(script.matches("/opt/d").count(), 1, "{script}");
+}
+
+#[test]
+fn activated_profiles_extend_the_same_list() {
+    let dir = TempDir::new("list-activated");
+    write_profile(dir.path(), "a", "[variables]\n\"PATH^\" = \"/a/bin\"\n");
+    write_profile(dir.path(), "b", "[variables]\n\"PATH^\" = \"/b/bin\"\n");
+
+    let output = common::command(dir.path())
+        .args(["activate", "a", "b", "--print", "--shell", "bash"])
+        .env("PATH", "/usr/bin:/bin")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    assert!(
+        stdout(&output).contains("export PATH='/b/bin:/a/bin:/usr/bin:/bin'"),
+        "{output:?}"
+    );
+
+    let mut config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    let merged = api::resolve_merged(&mut config_manager, &["a".into(), "b".into()]).unwrap();
+    assert_eq!(merged.vars["PATH"].sources, ["a", "b"]);
+    assert_eq!(merged.vars["PATH"].chain, ["a", "b"]);
+}
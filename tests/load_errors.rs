@@ -0,0 +1,143 @@
+//! A profile that fails to load is reported on its own instead of stopping the others.
+
+mod common;
+
+use common::{TempDir, run, write_profile};
+use env_manage::config::ConfigManager;
+use env_manage::config::models::Profile;
+use env_manage::tui::app::{App, AppState};
+use std::fs;
+use std::path::Path;
+
+/// `good` loads, `broken` depends on the missing `ghost`, `child` depends on `broken`
+/// and `garbled` is not valid TOML
+fn write_profiles(dir: &Path) {
+    write_profile(dir, "good", "[variables]\nA = \"1\"\n");
+    write_profile(
+        dir,
+        "broken",
+        "profiles = [\"ghost\"]\n\n[variables]\nKEY = \"value\"\n",
+    );
+    write_profile(dir, "child", "profiles = [\"broken\"]\n");
+    write_profile(dir, "garbled", "profiles = [\n");
+}
+
+#[test]
+fn load_all_profiles_collects_failures_per_profile() {
+    let dir = TempDir::new("load-errors-all");
+    write_profiles(dir.path());
+
+    let (manager, failures) = ConfigManager::new_full(Some(dir.path().to_path_buf())).unwrap();
+    assert!(manager.has_profile("good"));
+    let mut failed: Vec<&str> = failures.iter().map(|(name, _)| name.as_str()).collect();
+    failed.sort();
+    assert_eq!(failed, ["broken", "child", "garbled"]);
+    let (_, broken) = failures.iter().find(|(name, _)| name == "broken").unwrap();
+    assert!(broken.to_string().contains("ghost"), "{broken}");
+}
+
+#[test]
+fn profile_list_warns_about_failures_and_lists_the_rest() {
+    let dir = TempDir::new("load-errors-list");
+    write_profiles(dir.path());
+
+    let output = run(dir.path(), &["profile", "list"]);
+    assert!(output.status.success(), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Could not load profile 'broken'"),
+        "{stderr}"
+    );
+    assert!(stderr.contains("good"), "{stderr}");
+}
+
+#[test]
+fn tui_keeps_load_errors_and_repairs_a_broken_profile() {
+    let dir = TempDir::new("load-errors-tui");
+    write_profiles(dir.path());
+    let config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    let mut app = App::new(config_manager, Profile::new());
+
+    assert!(app.list_view.select_profile("broken"));
+    assert!(!app.ensure_loaded("broken"));
+    assert!(!app.ensure_loaded("child"));
+    assert!(app.load_error("broken").unwrap().contains("ghost"));
+    assert!(app.load_error("good").is_none());
+
+    // Editing starts from the file, dependency and variables included
+    app.start_editing("broken");
+    assert_eq!(app.state, AppState::Edit);
+    let mut repaired = app.edit_view.to_profile();
+    assert_eq!(
+        repaired.variables.get("KEY").map(String::as_str),
+        Some("value")
+    );
+    assert!(repaired.profiles.contains("ghost"));
+
+    repaired.remove_profile("ghost");
+    app.config_manager
+        .add_profile("broken".to_string(), repaired);
+    app.list_view.mark_dirty("broken".to_string());
+    app.state = AppState::List;
+    app.save_all().unwrap();
+
+    assert!(app.load_error("broken").is_none());
+    assert!(app.ensure_loaded("child"), "{:?}", app.load_error("child"));
+}
+
+#[test]
+fn tui_edits_an_unparsable_profile_from_scratch() {
+    let dir = TempDir::new("load-errors-garbled");
+    write_profiles(dir.path());
+    let config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    let mut app = App::new(config_manager, Profile::new());
+
+    assert!(!app.ensure_loaded("garbled"));
+    app.start_editing("garbled");
+    assert_eq!(app.state, AppState::Edit);
+    let profile = app.edit_view.to_profile();
+    assert!(profile.profiles.is_empty() && profile.variables.is_empty());
+}
+
+#[test]
+fn a_corrupt_profile_is_not_reported_as_missing() {
+    let dir = TempDir::new("load-errors-corrupt");
+    write_profiles(dir.path());
+
+    let output = run(dir.path(), &["profile", "add", "garbled", "KEY=1"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Failed to parse profile 'garbled'"),
+        "{stderr}"
+    );
+    assert!(!stderr.contains("does not exist"), "{stderr}");
+
+    let output = run(dir.path(), &["profile", "add", "good", "garbled"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Failed to parse profile 'garbled'"),
+        "{stderr}"
+    );
+    assert!(!stderr.contains("does not exist"), "{stderr}");
+
+    let output = run(dir.path(), &["profile", "add", "ghost", "KEY=1"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Profile `ghost` does not exist"));
+}
+
+#[test]
+fn fix_points_at_corrupt_files_instead_of_creating_them() {
+    let dir = TempDir::new("load-errors-fix-corrupt");
+    write_profiles(dir.path());
+
+    let output = run(dir.path(), &["fix", "--yes"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("garbled.toml exists but is malformed"),
+        "{stderr}"
+    );
+    // Left as it was for the user to correct
+    let garbled = dir.path().join("profiles").join("garbled.toml");
+    assert_eq!(fs::read_to_string(garbled).unwrap(), "profiles = [\n");
+}
@@ -0,0 +1,130 @@
+//! Multi-line values: the TUI editor that writes them and the shell commands that export them.
+
+mod common;
+
+use common::{TempDir, press, type_text};
+use env_manage::config::ConfigManager;
+use env_manage::config::models::Profile;
+use env_manage::tui::app::App;
+use env_manage::tui::utils::{TextArea, single_line_preview};
+use env_manage::tui::views::edit;
+use env_manage::utils::shell_generate::{ShellGenerate, ShellType};
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+const PEM: &str = "-----BEGIN KEY-----\nMII'B\n-----END KEY-----";
+
+fn app_editing(dir: &TempDir, value: &str) -> App {
+    let config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    let mut profile = Profile::new();
+    profile.add_variable("CERT", value);
+    config_manager.write_profile("certs", &profile).unwrap();
+    let mut app = App::new(config_manager, Profile::new());
+    app.start_editing("certs");
+    app
+}
+
+#[test]
+fn text_area_moves_across_lines() {
+    let mut area = TextArea::with_text("ab\ncd");
+    assert_eq!(area.cursor(), (1, 2));
+
+    area.move_cursor_up();
+    area.move_cursor_end();
+    area.insert_newline();
+    area.enter_char('é');
+    assert_eq!(area.text(), "ab\né\ncd");
+
+    area.move_cursor_home();
+    area.delete_char();
+    assert_eq!(area.text(), "abé\ncd");
+    assert_eq!(area.cursor(), (0, 2));
+
+    area.move_cursor_right();
+    area.move_cursor_right();
+    assert_eq!(area.cursor(), (1, 0));
+    area.move_cursor_left();
+    assert_eq!(area.cursor(), (0, 3));
+}
+
+#[test]
+fn preview_shows_the_first_line_and_the_line_break_count() {
+    assert_eq!(single_line_preview("plain"), "plain");
+    assert_eq!(single_line_preview(PEM), "-----BEGIN KEY----- ⏎×2");
+    assert_eq!(single_line_preview("trailing\n"), "trailing ⏎×1");
+}
+
+#[test]
+fn editor_writes_newlines_into_the_value() {
+    let dir = TempDir::new("multiline-editor-confirm");
+    let mut app = app_editing(&dir, "first");
+
+    press(&mut app, KeyCode::Char('E'));
+    assert!(app.edit_view.is_value_editor_open());
+    press(&mut app, KeyCode::Enter);
+    type_text(&mut app, "second");
+    edit::handle_event(
+        &mut app,
+        KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL),
+    );
+    assert!(!app.edit_view.is_value_editor_open());
+    assert!(app.list_view.is_dirty("certs"));
+
+    press(&mut app, KeyCode::Esc);
+    app.save_all().unwrap();
+    let mut reloaded = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    reloaded.load_profile("certs").unwrap();
+    assert_eq!(
+        reloaded.get_profile("certs").unwrap().variables["CERT"],
+        "first\nsecond"
+    );
+}
+
+#[test]
+fn editor_cancels_and_opens_for_multi_line_values() {
+    let dir = TempDir::new("multiline-editor-cancel");
+    let mut app = app_editing(&dir, PEM);
+
+    // `e` on a multi-line value goes to the multi-line editor as well
+    press(&mut app, KeyCode::Right);
+    press(&mut app, KeyCode::Char('e'));
+    assert_eq!(
+        app.edit_view.value_editor().map(TextArea::text).as_deref(),
+        Some(PEM)
+    );
+    type_text(&mut app, "junk");
+    press(&mut app, KeyCode::Esc);
+    assert!(!app.edit_view.is_value_editor_open());
+    assert!(!app.list_view.is_dirty("certs"));
+    assert_eq!(app.edit_view.variables().next(), Some(("CERT", PEM)));
+}
+
+#[test]
+fn every_shell_quotes_multi_line_values() {
+    let expected = [
+        (
+            ShellType::Bash,
+            "export CERT='-----BEGIN KEY-----\nMII'\\''B\n-----END KEY-----'",
+        ),
+        (
+            ShellType::Zsh,
+            "export CERT='-----BEGIN KEY-----\nMII'\\''B\n-----END KEY-----'",
+        ),
+        (
+            ShellType::Fish,
+            "set -gx CERT '-----BEGIN KEY-----\nMII\\'B\n-----END KEY-----'",
+        ),
+        (
+            ShellType::PowerShell,
+            "$env:CERT=\"-----BEGIN KEY-----\nMII'B\n-----END KEY-----\"",
+        ),
+        (
+            ShellType::Nu,
+            "$env.CERT = \"-----BEGIN KEY-----\\nMII'B\\n-----END KEY-----\"",
+        ),
+    ];
+    for (shell, command) in expected {
+        let mut generate = ShellGenerate::with_shell(shell);
+        generate.export("CERT", PEM);
+        assert_eq!(generate.script(), command);
+    }
+}
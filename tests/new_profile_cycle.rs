@@ -0,0 +1,72 @@
+//! The create dialog reports a dependency cycle while the profiles are being selected.
+
+mod common;
+
+use common::{TempDir, press};
+use env_manage::config::ConfigManager;
+use env_manage::config::graph::DependencyError;
+use env_manage::config::models::Profile;
+use env_manage::tui::app::{App, AppState};
+use env_manage::tui::views::add_new;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// `app` is loaded and lists `ghost`, which has no file yet, so creating `ghost`
+/// with `app` as a dependency would close the cycle `ghost -> app -> ghost`
+fn config_manager(dir: &TempDir) -> ConfigManager {
+    let mut config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    let mut profile = Profile::new();
+    profile.add_profile("app", "ghost");
+    config_manager.write_profile("app", &profile).unwrap();
+    config_manager.add_profile_node("app".to_string());
+    config_manager.add_profile("app".to_string(), profile);
+    config_manager
+}
+
+#[test]
+fn check_new_profile_leaves_the_graph_untouched() {
+    let dir = TempDir::new("new-profile-cycle-graph");
+    let config_manager = config_manager(&dir);
+
+    let result = config_manager.check_new_profile("ghost", &["app".to_string()]);
+    let Err(DependencyError::CircularDependency { path, .. }) = result else {
+        panic!("expected a cycle, got {result:?}");
+    };
+    assert_eq!(path, ["ghost", "app", "ghost"]);
+    assert!(
+        config_manager
+            .check_new_profile("other", &["app".to_string()])
+            .is_ok()
+    );
+    assert!(config_manager.get_parents("ghost").is_none());
+}
+
+#[test]
+fn selecting_a_cycle_marks_it_and_blocks_saving() {
+    let dir = TempDir::new("new-profile-cycle-view");
+    let mut app = App::new(config_manager(&dir), Profile::new());
+    app.state = AppState::AddNew;
+
+    for c in "ghost".chars() {
+        press(&mut app, KeyCode::Char(c));
+    }
+    for _ in 0..3 {
+        press(&mut app, KeyCode::Tab);
+    }
+    press(&mut app, KeyCode::Char(' '));
+    assert!(app.add_new_view.is_profile_added("app"));
+    assert_eq!(
+        app.add_new_view.cycle(),
+        Some(["ghost", "app", "ghost"].map(String::from).as_slice())
+    );
+    assert!(app.add_new_view.closes_cycle("app"));
+
+    add_new::handle_event(
+        &mut app,
+        KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL),
+    );
+    assert_eq!(app.state, AppState::AddNew);
+    assert!(!app.config_manager.has_profile("ghost"));
+
+    press(&mut app, KeyCode::Char(' '));
+    assert_eq!(app.add_new_view.cycle(), None);
+}
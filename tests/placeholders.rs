@@ -0,0 +1,59 @@
+//! Built-in `${...}` placeholders in profile values
+
+mod common;
+
+use common::{TempDir, run, stderr, write_profile};
+use env_manage::api;
+use env_manage::config::ConfigManager;
+use std::fs;
+
+#[test]
+fn profile_dir_is_the_directory_of_the_declaring_file() {
+    let dir = TempDir::new("placeholders-profile-dir");
+    write_profile(
+        dir.path(),
+        "base",
+        "[variables]\nBASE = \"${PROFILE_DIR}\"\n",
+    );
+    write_profile(
+        dir.path(),
+        "work/api",
+        "profiles = [\"base\"]\n\n[variables]\nAPI = \"${PROFILE_DIR}/api\"\nCONFIG = \"${CONFIG_DIR}\"\n",
+    );
+    fs::write(
+        dir.path().join("global.toml"),
+        "[variables]\nGLOBAL = \"${PROFILE_DIR}\"\n",
+    )
+    .unwrap();
+    let mut config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+
+    let vars = api::resolve_env(&mut config_manager, "work/api").unwrap();
+    let profiles = dir.path().join("profiles");
+    assert_eq!(vars["BASE"], profiles.display().to_string());
+    assert_eq!(
+        vars["API"],
+        format!("{}/api", profiles.join("work").display())
+    );
+    assert_eq!(vars["CONFIG"], dir.path().display().to_string());
+
+    let global = api::resolve_global(&mut config_manager).unwrap();
+    let value = &global.vars["GLOBAL"];
+    assert_eq!(value.value, dir.path().display().to_string());
+    assert_eq!(value.sources, [api::GLOBAL_SOURCE]);
+}
+
+#[test]
+fn empty_env_placeholder_is_left_as_written_and_reported() {
+    let dir = TempDir::new("placeholders-empty-env");
+    write_profile(dir.path(), "app", "[variables]\nA = \"x${env:}y\"\n");
+    let mut config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+
+    let vars = api::resolve_env(&mut config_manager, "app").unwrap();
+    assert_eq!(vars["A"], "x${env:}y");
+
+    let output = run(dir.path(), &["check"]);
+    assert!(
+        stderr(&output).contains("Unknown placeholder '${env:}' in 'A' of profile 'app'"),
+        "{output:?}"
+    );
+}
@@ -0,0 +1,90 @@
+//! Popups are clamped into the frame, however small the terminal gets.
+
+mod common;
+
+use common::{TempDir, press, write_profile};
+use env_manage::config::ConfigManager;
+use env_manage::config::models::Profile;
+use env_manage::tui::app::{App, AppState};
+use env_manage::tui::ui::ui;
+use env_manage::tui::utils::clamp_rect;
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+use ratatui::crossterm::event::KeyCode;
+use ratatui::layout::Rect;
+
+#[test]
+fn a_rect_that_fits_is_unchanged() {
+    let bounds = Rect::new(0, 0, 80, 24);
+    let area = Rect::new(10, 5, 30, 3);
+    assert_eq!(clamp_rect(area, bounds), area);
+}
+
+#[test]
+fn a_rect_past_the_edge_is_shifted_back() {
+    let bounds = Rect::new(2, 1, 40, 10);
+    assert_eq!(
+        clamp_rect(Rect::new(30, 9, 20, 3), bounds),
+        Rect::new(22, 8, 20, 3)
+    );
+    assert_eq!(
+        clamp_rect(Rect::new(0, 0, 5, 5), bounds),
+        Rect::new(2, 1, 5, 5)
+    );
+}
+
+#[test]
+fn a_rect_larger_than_the_bounds_is_shrunk() {
+    let bounds = Rect::new(3, 2, 10, 4);
+    assert_eq!(clamp_rect(Rect::new(0, 0, 50, 20), bounds), bounds);
+    assert_eq!(
+        clamp_rect(Rect::new(8, 3, 50, 1), bounds),
+        Rect::new(3, 3, 10, 1)
+    );
+}
+
+#[test]
+fn tiny_and_empty_bounds_give_a_rect_inside_them() {
+    for bounds in [
+        Rect::new(0, 0, 0, 0),
+        Rect::new(7, 3, 0, 0),
+        Rect::new(7, 3, 1, 1),
+        Rect::new(0, 0, 1, 0),
+        Rect::new(u16::MAX - 1, u16::MAX - 1, 1, 1),
+    ] {
+        for area in [
+            Rect::new(0, 0, 0, 0),
+            Rect::new(0, 0, 40, 3),
+            Rect::new(100, 100, 2, 2),
+            Rect::new(u16::MAX - 5, u16::MAX - 5, 5, 5),
+        ] {
+            let clamped = clamp_rect(area, bounds);
+            assert!(
+                clamped.width <= bounds.width && clamped.height <= bounds.height,
+                "{area:?} in {bounds:?} gave {clamped:?}"
+            );
+            assert!(
+                clamped.x >= bounds.x
+                    && clamped.y >= bounds.y
+                    && clamped.right() <= bounds.right()
+                    && clamped.bottom() <= bounds.bottom(),
+                "{area:?} in {bounds:?} gave {clamped:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn popups_draw_in_a_tiny_terminal() {
+    let dir = TempDir::new("popup-layout-tiny");
+    write_profile(dir.path(), "app", "[variables]\nA = \"1\"\n");
+    let config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    let mut app = App::new(config_manager, Profile::new());
+    press(&mut app, KeyCode::Char('n'));
+    assert_eq!(app.state, AppState::AddNew);
+
+    for (width, height) in [(0, 0), (1, 1), (12, 3), (30, 8)] {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|frame| ui(frame, &app)).unwrap();
+    }
+}
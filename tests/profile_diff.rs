@@ -0,0 +1,108 @@
+//! `profile diff` and the TUI diff popup compare the variables of two profiles.
+
+mod common;
+
+use common::{TempDir, write_profile};
+use env_manage::config::diff::ProfileDiff;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Output;
+
+fn vars(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+    pairs
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+fn setup(name: &str) -> TempDir {
+    let dir = TempDir::new(name);
+    write_profile(dir.path(), "base", "[variables]\nREGION = \"eu\"\n");
+    write_profile(
+        dir.path(),
+        "dev",
+        "profiles = [\"base\"]\n\n[variables]\nHOST = \"localhost\"\nLEVEL = \"debug\"\n",
+    );
+    write_profile(
+        dir.path(),
+        "prod",
+        "[variables]\nHOST = \"example.com\"\nTOKEN = \"secret\"\n",
+    );
+    dir
+}
+
+fn diff(dir: &Path, args: &[&str]) -> Output {
+    common::command(dir)
+        .args(["profile", "diff"])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn between_splits_removed_added_and_changed_keys() {
+    let a = vars(&[("A", "1"), ("B", "2"), ("C", "3")]);
+    let b = vars(&[("B", "2"), ("C", "4"), ("D", "5")]);
+
+    let diff = ProfileDiff::between(&a, &b);
+
+    assert_eq!(diff.only_in_a, [("A".to_string(), "1".to_string())]);
+    assert_eq!(diff.only_in_b, [("D".to_string(), "5".to_string())]);
+    assert_eq!(
+        diff.changed,
+        [("C".to_string(), "3".to_string(), "4".to_string())]
+    );
+    assert!(ProfileDiff::between(&a, &a).is_empty());
+}
+
+#[test]
+fn plain_output_is_sorted_by_key() {
+    let a = vars(&[("A", "1"), ("C", "3")]);
+    let b = vars(&[("B", "2"), ("C", "4")]);
+
+    assert_eq!(
+        ProfileDiff::between(&a, &b).plain("a", "b"),
+        "--- a\n+++ b\n-A=\"1\"\n+B=\"2\"\n-C=\"3\"\n+C=\"4\"\n"
+    );
+}
+
+#[test]
+fn diff_command_groups_the_differences() {
+    let dir = setup("profile-diff-human");
+
+    let output = diff(dir.path(), &["dev", "prod"]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "Only in 'dev':\n\
+         - LEVEL = \"debug\"\n\
+         Only in 'prod':\n\
+         + TOKEN = \"secret\"\n\
+         Different values:\n\
+         ~ HOST: \"localhost\" -> \"example.com\"\n"
+    );
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn diff_command_plain_and_expand() {
+    let dir = setup("profile-diff-plain");
+
+    let output = diff(dir.path(), &["dev", "prod", "--plain", "--expand"]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "--- dev\n+++ prod\n\
+         -HOST=\"localhost\"\n+HOST=\"example.com\"\n\
+         -LEVEL=\"debug\"\n-REGION=\"eu\"\n+TOKEN=\"secret\"\n"
+    );
+
+    let output = diff(dir.path(), &["prod", "prod"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("set the same variables"));
+
+    let output = diff(dir.path(), &["dev", "missing"]);
+    assert!(!output.status.success());
+}
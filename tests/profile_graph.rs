@@ -0,0 +1,114 @@
+//! `profile graph` prints the dependency graph in DOT or Mermaid syntax.
+
+mod common;
+
+use common::{TempDir, run, write_profile};
+use std::fs;
+use std::path::Path;
+
+fn write_profiles(dir: &Path) {
+    for (name, content) in [
+        ("web", "profiles = [\"work/api\", \"end\"]\n"),
+        ("work/api", "profiles = [\"end\"]\n"),
+        ("end", ""),
+        ("work_api", ""),
+    ] {
+        write_profile(dir, name, content);
+    }
+}
+
+fn graph(dir: &Path, args: &[&str]) -> String {
+    let output = common::command(dir)
+        .args(["profile", "graph"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn dot_is_the_default_format() {
+    let dir = TempDir::new("profile-graph-dot");
+    write_profiles(dir.path());
+
+    assert_eq!(
+        graph(dir.path(), &[]),
+        r#"digraph profiles {
+    "end";
+    "web" -> "end";
+    "web" -> "work/api";
+    "work/api" -> "end";
+    "work_api";
+}
+"#
+    );
+}
+
+#[test]
+fn mermaid_sanitizes_ids_and_keeps_names_as_labels() {
+    let dir = TempDir::new("profile-graph-mermaid");
+    write_profiles(dir.path());
+
+    // `end` is a Mermaid keyword, and `work/api` must not take the id of `work_api`
+    assert_eq!(
+        graph(dir.path(), &["--format", "mermaid"]),
+        r#"graph TD
+    end_2["end"]
+    web --> end_2["end"]
+    web --> work_api_2["work/api"]
+    work_api_2["work/api"] --> end_2["end"]
+    work_api
+"#
+    );
+}
+
+#[test]
+fn an_empty_config_prints_an_empty_graph() {
+    let dir = TempDir::new("profile-graph-empty");
+
+    assert_eq!(graph(dir.path(), &[]), "digraph profiles {\n}\n");
+    assert_eq!(graph(dir.path(), &["--format", "mermaid"]), "graph TD\n");
+}
+
+#[test]
+fn max_depth_counts_the_longest_chain() {
+    let dir = TempDir::new("profile-graph-depth");
+    write_profiles(dir.path());
+    let mut config_manager =
+        env_manage::config::ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    config_manager.load_all_profiles().unwrap();
+
+    // web -> work/api -> end is longer than web -> end
+    assert_eq!(config_manager.max_depth("web"), 2);
+    assert_eq!(config_manager.max_depth("work/api"), 1);
+    assert_eq!(config_manager.max_depth("end"), 0);
+    assert_eq!(config_manager.max_depth("missing"), 0);
+    assert_eq!(config_manager.max_depth_overall(), 2);
+}
+
+#[test]
+fn show_prints_the_depth_and_check_flags_deep_profiles() {
+    let dir = TempDir::new("profile-graph-depth-check");
+    write_profiles(dir.path());
+    let run = |args: &[&str]| run(dir.path(), args);
+
+    let show = run(&["profile", "show", "web"]);
+    assert!(String::from_utf8_lossy(&show.stderr).contains("Inheritance depth: 2"));
+    let check = run(&["check"]);
+    assert!(check.status.success());
+    assert!(!String::from_utf8_lossy(&check.stderr).contains("levels deep"));
+
+    fs::write(
+        dir.path().join("config.toml"),
+        "max_inheritance_depth = 1\n",
+    )
+    .unwrap();
+    let check = run(&["check"]);
+    let report = String::from_utf8_lossy(&check.stderr);
+    assert!(
+        report.contains("Profile 'web' inherits 2 levels deep, more than the limit of 1"),
+        "{report}"
+    );
+    assert!(!report.contains("'work/api' inherits"), "{report}");
+}
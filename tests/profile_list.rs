@@ -0,0 +1,129 @@
+//! `profile list` shows aligned counts and modification times, and sorts on request.
+
+mod common;
+
+use common::{TempDir, profile_path, run, write_profile};
+use std::fs::File;
+use std::path::Path;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Write profile `name`, last modified `modified` seconds after the epoch
+fn write_profile_at(dir: &Path, name: &str, content: &str, modified: u64) {
+    write_profile(dir, name, content);
+    File::options()
+        .write(true)
+        .open(profile_path(dir, name))
+        .unwrap()
+        .set_modified(UNIX_EPOCH + Duration::from_secs(modified))
+        .unwrap();
+}
+
+fn setup(name: &str) -> TempDir {
+    let dir = TempDir::new(name);
+    // 2024-05-01T12:30:00Z, then a day later each
+    write_profile_at(dir.path(), "a", "[variables]\nX = \"1\"\n", 1_714_566_600);
+    write_profile_at(
+        dir.path(),
+        "longer-name",
+        "profiles = [\"a\"]\n\n[variables]\n",
+        1_714_653_000,
+    );
+    let many: String = (0..12).map(|i| format!("V{i} = \"{i}\"\n")).collect();
+    write_profile_at(
+        dir.path(),
+        "many",
+        &format!("[variables]\n{many}"),
+        1_714_739_400,
+    );
+    dir
+}
+
+/// The profile header lines of `profile list`
+fn headers(dir: &Path, args: &[&str]) -> Vec<String> {
+    let output = common::command(dir)
+        .args(["profile", "list"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8(output.stderr)
+        .unwrap()
+        .lines()
+        .filter(|line| line.starts_with("├── ") || line.starts_with("└── "))
+        .map(str::to_string)
+        .collect()
+}
+
+/// `text` without ANSI color sequences
+fn strip_colors(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            chars.by_ref().find(|&c| c == 'm');
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[test]
+fn columns_are_aligned() {
+    let dir = setup("list-columns");
+    assert_eq!(
+        headers(dir.path(), &["--color", "never"]),
+        [
+            "├── a             1 vars  0 deps  2024-05-01T12:30:00Z",
+            "├── longer-name   0 vars  1 deps  2024-05-02T12:30:00Z",
+            "└── many         12 vars  0 deps  2024-05-03T12:30:00Z",
+        ]
+    );
+}
+
+#[test]
+fn colors_do_not_shift_the_columns() {
+    let dir = setup("list-columns-color");
+    let plain = headers(dir.path(), &["--color", "never"]);
+    let colored = headers(dir.path(), &["--color", "always"]);
+    assert_ne!(plain, colored);
+    let stripped: Vec<String> = colored.iter().map(|line| strip_colors(line)).collect();
+    assert_eq!(stripped, plain);
+}
+
+#[test]
+fn expand_keeps_the_counts_on_header_lines() {
+    let dir = setup("list-columns-expand");
+    let headers = headers(dir.path(), &["--color", "never", "--expand"]);
+    assert_eq!(
+        headers[1],
+        "├── longer-name   0 vars  1 deps  2024-05-02T12:30:00Z"
+    );
+}
+
+#[test]
+fn sort_orders_by_modification_time_or_variable_count() {
+    let dir = setup("list-sort");
+    let names = |sort: &str| -> Vec<String> {
+        headers(dir.path(), &["--color", "never", "--sort", sort])
+            .iter()
+            .map(|line| line.split_whitespace().nth(1).unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(names("name"), ["a", "longer-name", "many"]);
+    assert_eq!(names("modified"), ["many", "longer-name", "a"]);
+    assert_eq!(names("vars"), ["many", "a", "longer-name"]);
+}
+
+#[test]
+fn sort_applies_to_json_output() {
+    let dir = setup("list-sort-json");
+    let output = run(
+        dir.path(),
+        &["profile", "list", "--json", "--sort", "modified"],
+    );
+    let json = String::from_utf8(output.stdout).unwrap();
+    let position = |name: &str| json.find(&format!("\"name\": \"{name}\"")).unwrap();
+    assert!(position("many") < position("longer-name"));
+    assert!(position("longer-name") < position("a"));
+}
@@ -0,0 +1,63 @@
+//! `profile list --oneline` prints one greppable line per profile on stdout.
+
+mod common;
+
+use common::{TempDir, write_profile};
+use std::process::Output;
+
+fn setup(name: &str) -> TempDir {
+    let dir = TempDir::new(name);
+    write_profile(
+        dir.path(),
+        "base",
+        "[variables]\nA = \"1\"\nSPACED = \"a b\"\nEMPTY = \"\"\nLINES = \"l1\\nl2\"\n",
+    );
+    write_profile(
+        dir.path(),
+        "app",
+        "profiles = [\"off\", \"base\"]\ndisabled_profiles = [\"off\"]\nunset = [\"Z\", \"Y\"]\n\n\
+         [variables]\nURL = \"http://x?a=1\"\n",
+    );
+    write_profile(dir.path(), "off", "");
+    dir
+}
+
+fn list(dir: &TempDir, args: &[&str]) -> Output {
+    common::command(dir.path())
+        .args(["profile", "list", "--oneline"])
+        .args(args)
+        .env_remove("NO_COLOR")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn each_profile_is_one_line_with_minimal_quoting() {
+    let dir = setup("oneline-format");
+    let output = list(&dir, &[]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "\
+app: URL=http://x?a=1 [deps: base,off] [unset: Y,Z]
+base: A=1 SPACED=\"a b\" EMPTY=\"\" LINES=\"l1\\nl2\"
+off:
+"
+    );
+}
+
+#[test]
+fn color_follows_the_flag_and_stdout() {
+    let dir = setup("oneline-color");
+    // `auto` stays plain when stdout is piped
+    assert!(!list(&dir, &[]).stdout.contains(&0x1b));
+    assert!(list(&dir, &["--color", "always"]).stdout.contains(&0x1b));
+    assert!(!list(&dir, &["--color", "never"]).stdout.contains(&0x1b));
+}
+
+#[test]
+fn oneline_conflicts_with_the_other_layouts() {
+    let dir = setup("oneline-conflicts");
+    assert!(!list(&dir, &["--expand"]).status.success());
+    assert!(!list(&dir, &["--json"]).status.success());
+}
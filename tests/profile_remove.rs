@@ -0,0 +1,82 @@
+//! `profile remove` tells whether a dependency was actually removed, and leaves the
+//! profile and the history alone when nothing was.
+
+mod common;
+
+use common::{TempDir, write_profile};
+use env_manage::config::graph::ProfileGraph;
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+fn remove(dir: &Path, items: &[&str]) -> Output {
+    common::command(dir)
+        .args(["profile", "remove", "app"])
+        .args(items)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn remove_dependency_reports_whether_an_edge_existed() {
+    let mut graph = ProfileGraph::new();
+    for name in ["app", "lib"] {
+        graph.add_node(name.to_string());
+    }
+    graph.add_dependency("app", "lib").unwrap();
+
+    assert!(graph.remove_dependency("app", "lib").unwrap());
+    assert!(!graph.remove_dependency("app", "lib").unwrap());
+    assert!(graph.remove_dependency("app", "ghost").is_err());
+}
+
+#[test]
+fn removing_a_profile_that_is_not_a_dependency_says_so() {
+    let dir = TempDir::new("profile-remove");
+    let profiles = dir.path().join("profiles");
+    fs::create_dir_all(&profiles).unwrap();
+    write_profile(dir.path(), "lib", "");
+    write_profile(dir.path(), "other", "");
+    write_profile(
+        dir.path(),
+        "app",
+        "profiles = [\"lib\"]\n\n[variables]\nKEY = \"1\"\n",
+    );
+
+    let output = remove(dir.path(), &["other", "lib", "KEY", "MISSING"]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "⚠ Profile 'other' was not a dependency of 'app'.\n\
+         ✔ Nested profile 'lib' removed from profile 'app'.\n\
+         ✔ Variable 'KEY' removed from profile 'app'.\n\
+         ⚠ Item 'MISSING' not found in profile 'app'.\n"
+    );
+    let content = fs::read_to_string(profiles.join("app.toml")).unwrap();
+    assert!(!content.contains("lib"));
+
+    let output = remove(dir.path(), &["lib"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "⚠ Profile 'lib' was not a dependency of 'app'.\n"
+    );
+}
+
+#[test]
+fn removing_nothing_writes_nothing() {
+    let dir = TempDir::new("profile-remove-nothing");
+    let original = "[variables]\nKEY    =   \"1\"\n";
+    write_profile(dir.path(), "app", original);
+    write_profile(dir.path(), "other", "");
+
+    for _ in 0..3 {
+        let output = remove(dir.path(), &["other", "MISSING"]);
+        assert!(output.status.success(), "{output:?}");
+    }
+    assert_eq!(
+        fs::read_to_string(dir.path().join("profiles/app.toml")).unwrap(),
+        original
+    );
+    assert!(!dir.path().join("history.log").exists());
+}
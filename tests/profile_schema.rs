@@ -0,0 +1,100 @@
+//! Profile files with a field of the wrong type are reported by field, not as missing.
+
+mod common;
+
+use common::{TempDir, run, write_profile};
+use env_manage::config::ConfigManager;
+use env_manage::config::graph::DependencyError;
+use env_manage::config::loader::{self, LoadError};
+use env_manage::config::schema::FieldType;
+
+fn schema_error(content: &str) -> (String, FieldType, String) {
+    match loader::parse_profile(content) {
+        Err(LoadError::Invalid(err)) => (err.field, err.expected, err.found),
+        other => panic!("expected a schema error, got {other:?}"),
+    }
+}
+
+#[test]
+fn the_offending_field_and_expected_type_are_named() {
+    assert_eq!(
+        schema_error("[variables]\nPORT = 8080\n"),
+        (
+            "variables.PORT".to_string(),
+            FieldType::String,
+            "integer 8080".to_string()
+        )
+    );
+    assert_eq!(
+        schema_error("tags = [\"web\", true]\n"),
+        (
+            "tags[1]".to_string(),
+            FieldType::String,
+            "boolean true".to_string()
+        )
+    );
+    assert_eq!(
+        schema_error("profiles = \"base\"\n").1,
+        FieldType::StringList
+    );
+    let (field, expected, _) = schema_error("validation = \"loose\"\n");
+    assert_eq!(field, "validation");
+    assert_eq!(expected.to_string(), "one of \"relaxed\", \"strict\"");
+}
+
+#[test]
+fn valid_files_and_unknown_fields_still_load() {
+    let profile = loader::parse_profile(
+        "description = \"web\"\nfuture_field = 1\nprofiles = [\"base\"]\n\n[variables]\nB = \"2\"\nA = \"1\"\n",
+    )
+    .unwrap();
+    assert_eq!(profile.description.as_deref(), Some("web"));
+    // Variables keep the order of the file
+    let keys: Vec<&String> = profile.variables.keys().collect();
+    assert_eq!(keys, ["B", "A"]);
+}
+
+#[test]
+fn malformed_and_missing_files_are_told_apart() {
+    let dir = TempDir::new("profile-schema-errors");
+    write_profile(&dir, "typed", "[variables]\nPORT = 8080\n");
+    write_profile(&dir, "garbled", "profiles = [\n");
+    write_profile(&dir, "app", "profiles = [\"typed\"]\n");
+
+    let mut config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    let err = config_manager.load_profile("typed").unwrap_err();
+    assert!(matches!(err, DependencyError::ProfileInvalid(ref name, _) if name == "typed"));
+    assert!(
+        err.to_string()
+            .contains("`variables.PORT` must be a string"),
+        "{err}"
+    );
+
+    let err = config_manager.load_profile("garbled").unwrap_err();
+    assert!(
+        matches!(err, DependencyError::ProfileParseError(..)),
+        "{err}"
+    );
+    let err = config_manager.load_profile("ghost").unwrap_err();
+    assert!(matches!(err, DependencyError::ProfileNotFound(..)), "{err}");
+
+    // A dependent reports the invalid field through the chain
+    let err = config_manager.load_profile("app").unwrap_err();
+    assert!(err.to_string().contains("variables.PORT"), "{err}");
+    assert!(!err.to_string().contains("non-existent"), "{err}");
+}
+
+#[test]
+fn check_reports_the_invalid_field() {
+    let dir = TempDir::new("profile-schema-check");
+    write_profile(&dir, "typed", "tags = \"web\"\n");
+
+    let output = run(dir.path(), &["check"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        format!("{stdout}{stderr}")
+            .contains("`tags` must be an array of strings, found string \"web\""),
+        "{stdout}{stderr}"
+    );
+}
@@ -0,0 +1,71 @@
+//! `profile stats` summarizes the profiles and their dependency graph.
+
+mod common;
+
+use common::{TempDir, run, write_profile};
+use env_manage::config::ConfigManager;
+use env_manage::config::stats::ProfileStats;
+use std::path::Path;
+
+fn write_profiles(dir: &Path) {
+    for (name, content) in [
+        (
+            "web",
+            "profiles = [\"api\"]\n[variables]\nPORT = \"80\"\n\"PATH+\" = \"/web\"\n",
+        ),
+        (
+            "api",
+            "profiles = [\"base\"]\n[variables]\nPORT = \"8080\"\n",
+        ),
+        ("base", "[variables]\nPATH = \"/bin\"\nHOME = \"/root\"\n"),
+        ("tool", ""),
+    ] {
+        write_profile(dir, name, content);
+    }
+}
+
+#[test]
+fn stats_count_profiles_keys_and_depth() {
+    let dir = TempDir::new("profile-stats");
+    write_profiles(dir.path());
+    let mut config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    config_manager.load_all_profiles().unwrap();
+
+    assert_eq!(
+        ProfileStats::of(&config_manager),
+        ProfileStats {
+            profiles: 4,
+            unique_keys: 3,
+            average_variables: 1.25,
+            max_depth: 2,
+            without_dependents: 2,
+        }
+    );
+}
+
+#[test]
+fn stats_of_no_profiles_are_zero() {
+    let dir = TempDir::new("profile-stats-empty");
+    let config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+
+    assert_eq!(ProfileStats::of(&config_manager), ProfileStats::default());
+}
+
+#[test]
+fn stats_command_prints_every_figure() {
+    let dir = TempDir::new("profile-stats-command");
+    write_profiles(dir.path());
+
+    let output = run(dir.path(), &["profile", "stats"]);
+    assert!(output.status.success(), "{output:?}");
+    let report = String::from_utf8_lossy(&output.stderr);
+    for line in [
+        "Profiles:                      4",
+        "Unique variable keys:          3",
+        "Average variables per profile: 1.2",
+        "Max inheritance depth:         2",
+        "Profiles without dependents:   2",
+    ] {
+        assert!(report.contains(line), "{report}");
+    }
+}
@@ -0,0 +1,149 @@
+//! The simple and expanded trees of `profile show`, `profile list` and `global list`
+//! share one layout, pinned here line by line.
+
+mod common;
+
+use common::{TempDir, run, write_profile};
+use std::fs;
+
+/// `top` → `app` → `base`, with `app` also keeping the disabled `off`
+fn setup(name: &str) -> TempDir {
+    let dir = TempDir::new(name);
+    write_profile(dir.path(), "base", "[variables]\nA = \"1\"\n");
+    write_profile(dir.path(), "off", "[variables]\nO = \"x\"\n");
+    write_profile(
+        dir.path(),
+        "app",
+        "profiles = [\"off\", \"base\"]\ndisabled_profiles = [\"off\"]\nunset = [\"Z\", \"Y\"]\n\n\
+         [variables]\nC = \"3\"\nB = \"2\"\n",
+    );
+    write_profile(dir.path(), "top", "profiles = [\"app\"]\n");
+    fs::write(
+        dir.path().join("global.toml"),
+        "profiles = [\"base\"]\n\n[variables]\nG = \"g\"\n",
+    )
+    .unwrap();
+    dir
+}
+
+/// What the successful command `args` prints
+fn printed(dir: &TempDir, args: &[&str]) -> String {
+    let output = run(dir.path(), args);
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn simple_tree() {
+    let dir = setup("tree-simple");
+    assert_eq!(
+        printed(&dir, &["profile", "show", "app"]),
+        "\
+app
+├── profiles: [base, off]
+├── unset: [Y, Z]
+└── variables
+    ├── C = \"3\"
+    └── B = \"2\"
+Inheritance depth: 1
+"
+    );
+    assert_eq!(
+        printed(&dir, &["global", "list"]),
+        "\
+global
+├── profiles: [base]
+└── variables
+    └── G = \"g\"
+"
+    );
+}
+
+#[test]
+fn expanded_tree() {
+    let dir = setup("tree-expanded");
+    assert_eq!(
+        printed(&dir, &["profile", "show", "top", "--expand"]),
+        "\
+top
+└── profiles
+    └── app
+        ├── profiles
+        │   ├── base
+        │   │   └── variables
+        │   │       └── A = \"1\"
+        │   └── off (disabled)
+        ├── unset: [Y, Z]
+        └── variables
+            ├── C = \"3\"
+            └── B = \"2\"
+Resolved:
+├── A = \"1\"
+├── B = \"2\"
+└── C = \"3\"
+Inheritance depth: 2
+"
+    );
+    assert_eq!(
+        printed(&dir, &["global", "list", "--expand"]),
+        "\
+global
+├── profiles
+│   └── base
+│       └── variables
+│           └── A = \"1\"
+└── variables
+    └── G = \"g\"
+"
+    );
+}
+
+#[test]
+fn list_nests_the_same_trees() {
+    let dir = setup("tree-list");
+    let body = |args: &[&str]| -> Vec<String> {
+        printed(&dir, args)
+            .lines()
+            .filter(|line| !line.starts_with("├── ") && !line.starts_with("└── "))
+            .map(str::to_string)
+            .collect()
+    };
+    // Header lines carry modification times and are pinned in `profile_list.rs`
+    assert_eq!(
+        body(&["profile", "list"]),
+        [
+            "Profiles:",
+            "│   ├── profiles: [base, off]",
+            "│   ├── unset: [Y, Z]",
+            "│   └── variables",
+            "│       ├── C = \"3\"",
+            "│       └── B = \"2\"",
+            "│   └── variables",
+            "│       └── A = \"1\"",
+            "│   └── variables",
+            "│       └── O = \"x\"",
+            "    └── profiles: [app]",
+        ]
+    );
+    assert_eq!(
+        body(&["profile", "list", "--expand", "--depth", "0"]),
+        [
+            "Profiles:",
+            "│   ├── profiles",
+            "│   │   ├── base",
+            "│   │   │   └── …",
+            "│   │   └── off (disabled)",
+            "│   ├── unset: [Y, Z]",
+            "│   └── variables",
+            "│       ├── C = \"3\"",
+            "│       └── B = \"2\"",
+            "│   └── variables",
+            "│       └── A = \"1\"",
+            "│   └── variables",
+            "│       └── O = \"x\"",
+            "    └── profiles",
+            "        └── app",
+            "            └── …",
+        ]
+    );
+}
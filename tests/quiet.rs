@@ -0,0 +1,46 @@
+//! `--quiet` hides success and info messages but keeps warnings, errors and the
+//! output asked for.
+
+mod common;
+
+use common::{TempDir, run, stderr, stdout, write_profile};
+
+#[test]
+fn quiet_hides_success_and_info_messages() {
+    let dir = TempDir::new("quiet-messages");
+
+    let output = run(dir.path(), &["profile", "list"]);
+    assert!(stderr(&output).contains("No profiles found."), "{output:?}");
+    let output = run(dir.path(), &["--quiet", "profile", "list"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(stderr(&output), "");
+
+    let output = run(dir.path(), &["-q", "profile", "create", "app"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(stderr(&output), "");
+    let output = run(dir.path(), &["-q", "profile", "add", "app", "A=1"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(stderr(&output), "");
+
+    let output = run(dir.path(), &["-q", "activate", "app", "--shell", "bash"]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(stderr(&output), "");
+    assert!(stdout(&output).contains("export A="), "{output:?}");
+}
+
+#[test]
+fn quiet_keeps_warnings_and_errors() {
+    let dir = TempDir::new("quiet-warnings");
+    write_profile(dir.path(), "app", "profiles = [\"gone\"]\n");
+
+    let output = run(dir.path(), &["-q", "check"]);
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("references non-existent profile 'gone'"),
+        "{output:?}"
+    );
+
+    let output = run(dir.path(), &["-q", "profile", "show", "missing"]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("missing"), "{output:?}");
+}
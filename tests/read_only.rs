@@ -0,0 +1,63 @@
+//! In read-only mode the TUI refuses every key that would change a profile.
+
+mod common;
+
+use common::{TempDir, press_with, write_profile};
+use env_manage::config::ConfigManager;
+use env_manage::config::models::Profile;
+use env_manage::tui::app::{App, AppState, StatusSeverity};
+use ratatui::crossterm::event::{KeyCode, KeyModifiers};
+
+fn app(dir: &TempDir) -> App {
+    write_profile(dir.path(), "app", "[variables]\nKEY = \"value\"\n");
+    let config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    let mut app = App::new(config_manager, Profile::new());
+    app.read_only = true;
+    assert!(app.list_view.select_profile("app"));
+    app
+}
+
+/// Press `code` with a fresh status line, so [`refused`] sees only this key
+fn try_key(app: &mut App, code: KeyCode, modifiers: KeyModifiers) {
+    app.status_message = None;
+    press_with(app, code, modifiers);
+}
+
+fn refused(app: &App) -> bool {
+    app.status_message.as_ref().is_some_and(|message| {
+        message.severity == StatusSeverity::Error && message.text.contains("Read-only")
+    })
+}
+
+#[test]
+fn changing_keys_are_refused() {
+    let dir = TempDir::new("read-only-refused");
+    let mut app = app(&dir);
+
+    for code in [
+        KeyCode::Enter,
+        KeyCode::Char('n'),
+        KeyCode::Char('d'),
+        KeyCode::Char('s'),
+        KeyCode::Char('w'),
+        KeyCode::F(2),
+    ] {
+        try_key(&mut app, code, KeyModifiers::NONE);
+        assert!(refused(&app), "{code:?} was not refused");
+        assert_eq!(app.state, AppState::List, "{code:?} left the list");
+    }
+    assert!(dir.path().join("profiles").join("app.toml").exists());
+}
+
+#[test]
+fn browsing_keys_still_work() {
+    let dir = TempDir::new("read-only-browse");
+    let mut app = app(&dir);
+
+    try_key(&mut app, KeyCode::Char('S'), KeyModifiers::SHIFT);
+    assert!(!refused(&app));
+    try_key(&mut app, KeyCode::Tab, KeyModifiers::NONE);
+    assert!(!refused(&app));
+    try_key(&mut app, KeyCode::Char('/'), KeyModifiers::NONE);
+    assert!(!refused(&app));
+}
@@ -0,0 +1,158 @@
+//! The TUI remembers the profiles last opened in the editor across sessions and jumps
+//! back to them from a popup.
+
+mod common;
+
+use common::{TempDir, press, run};
+use env_manage::config::ConfigManager;
+use env_manage::config::models::Profile;
+use env_manage::config::recent::{self, MAX_RECENT, RecentProfiles};
+use env_manage::tui::app::{App, AppState};
+use env_manage::tui::event::handle_key;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fs;
+use std::path::Path;
+
+fn new_app(dir: &Path) -> App {
+    let config_manager = ConfigManager::with_base_path(dir.to_path_buf()).unwrap();
+    App::new(config_manager, Profile::new())
+}
+
+fn write_profiles(dir: &Path, names: &[&str]) {
+    let config_manager = ConfigManager::with_base_path(dir.to_path_buf()).unwrap();
+    for name in names {
+        let mut profile = Profile::new();
+        profile.add_variable("A", "1");
+        config_manager.write_profile(name, &profile).unwrap();
+    }
+}
+
+/// Open `name` in the editor and leave it again
+fn visit(app: &mut App, name: &str) {
+    assert!(app.list_view.select_profile(name));
+    press(app, KeyCode::Enter);
+    assert_eq!(app.state, AppState::Edit);
+    press(app, KeyCode::Esc);
+    assert_eq!(app.state, AppState::List);
+}
+
+#[test]
+fn recent_profiles_survive_a_restart_and_jump_by_number() {
+    let dir = TempDir::new("recent-restart");
+    write_profiles(dir.path(), &["alpha", "beta", "gamma"]);
+
+    let mut app = new_app(dir.path());
+    visit(&mut app, "alpha");
+    visit(&mut app, "beta");
+    visit(&mut app, "alpha");
+    assert_eq!(app.recent.names(), ["alpha", "beta"]);
+    drop(app);
+
+    let mut app = new_app(dir.path());
+    assert_eq!(app.recent.names(), ["alpha", "beta"]);
+
+    handle_key(
+        &mut app,
+        KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL),
+    )
+    .unwrap();
+    assert_eq!(app.state, AppState::Recent);
+    press(&mut app, KeyCode::Char('2'));
+    assert_eq!(app.state, AppState::Edit);
+    assert_eq!(app.edit_view.profile_name(), "beta");
+    press(&mut app, KeyCode::Esc);
+    assert_eq!(app.recent.names(), ["beta", "alpha"]);
+
+    press(&mut app, KeyCode::Char('`'));
+    press(&mut app, KeyCode::Down);
+    press(&mut app, KeyCode::Enter);
+    assert_eq!(app.edit_view.profile_name(), "alpha");
+}
+
+#[test]
+fn renaming_and_deleting_keep_the_list_consistent() {
+    let dir = TempDir::new("recent-rename-delete");
+    write_profiles(dir.path(), &["alpha", "beta"]);
+
+    let mut app = new_app(dir.path());
+    visit(&mut app, "alpha");
+    visit(&mut app, "beta");
+
+    assert!(app.list_view.select_profile("alpha"));
+    app.rename_profile("renamed".to_string()).unwrap();
+    assert_eq!(app.recent.names(), ["beta", "renamed"]);
+
+    assert!(app.list_view.select_profile("beta"));
+    app.delete_selected_profile().unwrap();
+    assert_eq!(app.recent.names(), ["renamed"]);
+    assert_eq!(
+        RecentProfiles::load(dir.path()).names(),
+        ["renamed"],
+        "changes are saved right away"
+    );
+}
+
+#[test]
+fn cli_rename_and_delete_update_the_stored_list() {
+    let dir = TempDir::new("recent-cli");
+    write_profiles(dir.path(), &["alpha", "beta"]);
+    fs::write(recent::recent_path(dir.path()), "[\"alpha\", \"beta\"]\n").unwrap();
+
+    let run = |args: &[&str]| {
+        let output = run(dir.path(), args);
+        assert!(output.status.success(), "{output:?}");
+    };
+    run(&["profile", "rename", "alpha", "first"]);
+    run(&["profile", "delete", "beta"]);
+
+    assert_eq!(RecentProfiles::load(dir.path()).names(), ["first"]);
+}
+
+#[test]
+fn stale_unreadable_and_overlong_lists_are_tolerated() {
+    let dir = TempDir::new("recent-stale");
+    write_profiles(dir.path(), &["alpha"]);
+    let path = recent::recent_path(dir.path());
+
+    fs::write(&path, "not json").unwrap();
+    assert!(RecentProfiles::load(dir.path()).is_empty());
+
+    fs::write(&path, "[\"gone\", \"alpha\"]").unwrap();
+    let mut app = new_app(dir.path());
+    assert_eq!(app.recent.names(), ["alpha"]);
+
+    // A profile deleted behind the TUI's back is dropped when jumping to it
+    fs::write(&path, "[\"alpha\"]").unwrap();
+    app.recent = RecentProfiles::load(dir.path());
+    fs::remove_file(dir.path().join("profiles").join("alpha.toml")).unwrap();
+    app.refresh_profile_list();
+    press(&mut app, KeyCode::Char('`'));
+    press(&mut app, KeyCode::Char('1'));
+    assert_eq!(app.state, AppState::List);
+    assert!(app.recent.is_empty());
+
+    let mut recent = RecentProfiles::default();
+    for i in 0..MAX_RECENT + 3 {
+        recent.touch(&format!("p{i}"));
+    }
+    assert_eq!(recent.names().len(), MAX_RECENT);
+    assert_eq!(recent.names()[0], format!("p{}", MAX_RECENT + 2));
+}
+
+#[test]
+fn escaped_names_round_trip() {
+    let dir = TempDir::new("recent-escapes");
+    let path = recent::recent_path(dir.path());
+
+    // A character outside the basic plane is escaped as a surrogate pair
+    fs::write(&path, "[\"smile-\\ud83d\\ude00\", \"tab\\there\"]\n").unwrap();
+    let recent = RecentProfiles::load(dir.path());
+    assert_eq!(recent.names(), ["smile-😀", "tab\there"]);
+
+    recent.save(dir.path());
+    assert_eq!(RecentProfiles::load(dir.path()), recent);
+
+    // An unknown escape is not valid JSON
+    fs::write(&path, "[\"a\\qb\"]").unwrap();
+    assert!(RecentProfiles::load(dir.path()).is_empty());
+}
@@ -0,0 +1,184 @@
+//! The memoized dependency resolution of `ProfileGraph` matches a fresh depth-first
+//! walk on random graphs, including after the graph changes.
+
+use env_manage::config::graph::ProfileGraph;
+use env_manage::config::models::Profile;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// A small deterministic generator, so failures reproduce
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        self.0 >> 33
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// Dependencies of each profile; edges only point to lower-numbered profiles, so
+/// the graph is acyclic. Names are shuffled so name order differs from edge order.
+fn random_dag(rng: &mut Lcg, nodes: usize) -> BTreeMap<String, BTreeSet<String>> {
+    let mut names: Vec<String> = (0..nodes).map(|i| format!("p{i:02}")).collect();
+    for i in (1..names.len()).rev() {
+        names.swap(i, rng.below(i + 1));
+    }
+    let mut dag = BTreeMap::new();
+    for (i, name) in names.iter().enumerate() {
+        let deps = (0..i)
+            .filter(|_| rng.below(4) == 0)
+            .map(|j| names[j].clone())
+            .collect();
+        dag.insert(name.clone(), deps);
+    }
+    dag
+}
+
+fn build(dag: &BTreeMap<String, BTreeSet<String>>) -> ProfileGraph {
+    let profiles: HashMap<String, Profile> = dag
+        .iter()
+        .map(|(name, deps)| {
+            let mut profile = Profile::new();
+            profile.profiles = deps.iter().cloned().collect();
+            (name.clone(), profile)
+        })
+        .collect();
+    ProfileGraph::build(&profiles, None).unwrap()
+}
+
+/// The uncached walk: dependencies by name, then the profile itself
+fn walk<'a>(
+    dag: &'a BTreeMap<String, BTreeSet<String>>,
+    name: &'a str,
+    resolved: &mut HashSet<&'a str>,
+    order: &mut Vec<String>,
+) {
+    for dep in &dag[name] {
+        if !resolved.contains(dep.as_str()) {
+            walk(dag, dep, resolved, order);
+        }
+    }
+    if resolved.insert(name) {
+        order.push(name.to_string());
+    }
+}
+
+fn expected(dag: &BTreeMap<String, BTreeSet<String>>, roots: &[&String]) -> Vec<String> {
+    let mut resolved = HashSet::new();
+    let mut order = Vec::new();
+    for root in roots {
+        walk(dag, root, &mut resolved, &mut order);
+    }
+    order
+}
+
+fn assert_matches(graph: &ProfileGraph, dag: &BTreeMap<String, BTreeSet<String>>, rng: &mut Lcg) {
+    let names: Vec<&String> = dag.keys().collect();
+    // Twice, so the second pass is served from the cache
+    for _ in 0..2 {
+        for name in &names {
+            assert_eq!(
+                graph.resolve_dependencies(name).unwrap(),
+                expected(dag, &[name]),
+                "{name} in {dag:?}"
+            );
+        }
+        let roots: Vec<&String> = (0..rng.below(5) + 2)
+            .map(|_| names[rng.below(names.len())])
+            .collect();
+        assert_eq!(
+            graph.resolve_many(&roots).unwrap(),
+            expected(dag, &roots),
+            "{roots:?} in {dag:?}"
+        );
+        assert_eq!(graph.topological_order().unwrap(), expected(dag, &names));
+    }
+}
+
+#[test]
+fn memoized_resolution_matches_a_fresh_walk() {
+    let mut rng = Lcg(0x5eed);
+    for _ in 0..50 {
+        let nodes = rng.below(25) + 1;
+        let dag = random_dag(&mut rng, nodes);
+        assert_matches(&build(&dag), &dag, &mut rng);
+    }
+}
+
+#[test]
+fn the_cache_follows_changes_to_the_graph() {
+    let mut rng = Lcg(42);
+    for _ in 0..20 {
+        let nodes = rng.below(15) + 2;
+        let mut dag = random_dag(&mut rng, nodes);
+        let mut graph = build(&dag);
+        assert_matches(&graph, &dag, &mut rng);
+
+        for step in 0..10 {
+            let names: Vec<String> = dag.keys().cloned().collect();
+            match step % 5 {
+                0 => {
+                    let (parent, child) = (
+                        &names[rng.below(names.len())],
+                        &names[rng.below(names.len())],
+                    );
+                    // A refused edge (a cycle) must leave the order untouched
+                    if !dag[parent].contains(child) && graph.add_dependency(parent, child).is_ok() {
+                        dag.get_mut(parent).unwrap().insert(child.clone());
+                    }
+                }
+                1 => {
+                    let parent = &names[rng.below(names.len())];
+                    if let Some(child) = dag[parent].iter().next().cloned() {
+                        assert!(graph.remove_dependency(parent, &child).unwrap());
+                        dag.get_mut(parent).unwrap().remove(&child);
+                    }
+                }
+                2 => {
+                    let old = names[rng.below(names.len())].clone();
+                    let new = format!("r{}", rng.below(1000));
+                    if dag.contains_key(&new) {
+                        continue;
+                    }
+                    graph.rename_node(&old, new.clone()).unwrap();
+                    let deps = dag.remove(&old).unwrap();
+                    dag.insert(new.clone(), deps);
+                    for deps in dag.values_mut() {
+                        if deps.remove(&old) {
+                            deps.insert(new.clone());
+                        }
+                    }
+                }
+                3 => {
+                    let removed = names[rng.below(names.len())].clone();
+                    if dag.len() == 1 {
+                        continue;
+                    }
+                    graph.remove_node(&removed).unwrap();
+                    dag.remove(&removed);
+                    for deps in dag.values_mut() {
+                        deps.remove(&removed);
+                    }
+                }
+                _ => {
+                    let new = format!("n{}", rng.below(1000));
+                    if dag.contains_key(&new) {
+                        continue;
+                    }
+                    graph.add_node(new.clone());
+                    let dependent = &names[rng.below(names.len())];
+                    graph.add_dependency(dependent, &new).unwrap();
+                    dag.insert(new.clone(), BTreeSet::new());
+                    dag.get_mut(dependent).unwrap().insert(new);
+                }
+            }
+            assert_matches(&graph, &dag, &mut rng);
+        }
+    }
+}
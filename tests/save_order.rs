@@ -0,0 +1,58 @@
+//! Saving a profile writes its variables in the order they were written and its
+//! dependencies sorted, so an unchanged profile saves to the same bytes every time.
+
+mod common;
+
+use common::{TempDir, read_profile, run, write_profile};
+use env_manage::config::ConfigManager;
+use std::path::Path;
+
+const CONTENT: &str = "profiles = [\"zeta\", \"Alpha\", \"beta\"]\n\n\
+                       [variables]\nZ = \"1\"\nA = \"2\"\nM = \"3\"\n";
+
+fn setup(name: &str) -> TempDir {
+    let dir = TempDir::new(name);
+    write_profile(dir.path(), "app", CONTENT);
+    for dependency in ["zeta", "Alpha", "beta"] {
+        write_profile(dir.path(), dependency, "[variables]\n");
+    }
+    dir
+}
+
+/// Load `name` with a fresh manager, so its sets hash differently, and save it again
+fn resave(dir: &Path, name: &str) -> String {
+    let mut config_manager = ConfigManager::with_base_path(dir.to_path_buf()).unwrap();
+    config_manager.load_profile(name).unwrap();
+    let profile = config_manager.get_profile(name).unwrap().clone();
+    config_manager.write_profile(name, &profile).unwrap();
+    read_profile(dir, name).unwrap()
+}
+
+#[test]
+fn saving_an_unchanged_profile_is_byte_identical() {
+    let dir = setup("save-order-identical");
+
+    let first = resave(dir.path(), "app");
+    assert!(
+        first.contains("profiles = [\n    \"Alpha\",\n    \"beta\",\n    \"zeta\",\n]"),
+        "{first}"
+    );
+    for _ in 0..5 {
+        assert_eq!(resave(dir.path(), "app"), first);
+    }
+}
+
+#[test]
+fn adding_a_key_keeps_the_existing_order() {
+    let dir = setup("save-order-add");
+    let before = resave(dir.path(), "app");
+
+    let output = run(dir.path(), &["profile", "add", "app", "B=4"]);
+    assert!(output.status.success(), "{output:?}");
+    let after = read_profile(dir.path(), "app").unwrap();
+    assert_eq!(
+        after,
+        before.replace("M = \"3\"\n", "M = \"3\"\nB = \"4\"\n")
+    );
+    assert_eq!(resave(dir.path(), "app"), after);
+}
@@ -0,0 +1,132 @@
+//! Scanning the profiles directory only picks up `.toml` files with valid profile
+//! names, and reports the other files instead of treating them as profiles.
+
+mod common;
+
+use common::{TempDir, run, write_profile};
+use env_manage::config::loader::{self, SkipReason};
+use std::fs;
+use std::path::Path;
+
+fn write_junk(profiles: &Path) {
+    fs::create_dir_all(profiles.join("work")).unwrap();
+    fs::create_dir_all(profiles.join(".git")).unwrap();
+    for file in [
+        "dev.toml",
+        "my-app.toml",
+        "work/api.toml",
+        ".dev.toml.swp",
+        ".hidden.toml",
+        "dev.toml~",
+        "dev.toml.bak",
+        "dev.toml.tmp",
+        "work/api.toml~",
+        "notes.txt",
+        "dev.TOML",
+        "bad name.toml",
+        ".git/config.toml",
+    ] {
+        fs::write(profiles.join(file), "").unwrap();
+    }
+}
+
+#[test]
+fn only_legitimate_profiles_are_scanned() {
+    let dir = TempDir::new("scan-junk");
+    let profiles = dir.path().join("profiles");
+    write_junk(&profiles);
+
+    let mut names = loader::scan_profile_names(&profiles).unwrap();
+    names.sort();
+    assert_eq!(names, ["dev", "my-app", "work/api"]);
+}
+
+#[test]
+fn skipped_files_are_reported_with_a_reason() {
+    let dir = TempDir::new("scan-report");
+    let profiles = dir.path().join("profiles");
+    write_junk(&profiles);
+
+    let report = loader::scan_profiles(&profiles).unwrap();
+    let mut skipped: Vec<(String, SkipReason)> = report
+        .skipped
+        .into_iter()
+        .map(|skipped| (skipped.name, skipped.reason))
+        .collect();
+    skipped.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let expected = [
+        (".dev.toml.swp", SkipReason::Hidden),
+        (".hidden.toml", SkipReason::Hidden),
+        (
+            "bad name.toml",
+            SkipReason::InvalidName(env_manage::utils::IdentifierError::InvalidCharacter(' ')),
+        ),
+        ("dev.TOML", SkipReason::NotToml),
+        ("dev.toml.bak", SkipReason::Backup),
+        ("dev.toml.tmp", SkipReason::Backup),
+        ("dev.toml~", SkipReason::Backup),
+        ("notes.txt", SkipReason::NotToml),
+        ("work/api.toml~", SkipReason::Backup),
+    ]
+    .map(|(name, reason)| (name.to_string(), reason));
+    assert_eq!(skipped, expected);
+}
+
+#[test]
+fn check_lists_skipped_files_without_failing() {
+    let dir = TempDir::new("scan-check");
+    let profiles = dir.path().join("profiles");
+    fs::create_dir_all(&profiles).unwrap();
+    write_profile(dir.path(), "dev", "[variables]\nA = \"1\"\n");
+    fs::write(profiles.join("dev.toml~"), "[variables]\nA = \"0\"\n").unwrap();
+
+    let output = run(dir.path(), &["check"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert!(
+        stderr.contains(
+            "Skipped 'dev.toml~' in the profiles directory: editor backup or temporary file"
+        ),
+        "{stderr}"
+    );
+    assert!(!stderr.contains("Profile 'dev.toml"), "{stderr}");
+}
+
+#[cfg(unix)]
+#[test]
+fn linked_directories_are_reported_instead_of_followed() {
+    let dir = TempDir::new("scan-link");
+    let profiles = dir.path().join("profiles");
+    write_profile(dir.path(), "dev", "[variables]\nA = \"1\"\n");
+    write_profile(dir.path(), "work/api", "[variables]\nA = \"1\"\n");
+    // A link back up would otherwise be scanned again and again as `loop/loop/...`
+    std::os::unix::fs::symlink("..", profiles.join("work").join("loop")).unwrap();
+    std::os::unix::fs::symlink("work", profiles.join("shared")).unwrap();
+    // Links to files are still profiles
+    std::os::unix::fs::symlink("dev.toml", profiles.join("alias.toml")).unwrap();
+
+    let report = loader::scan_profiles(&profiles).unwrap();
+    let mut names: Vec<_> = report.files.into_iter().map(|file| file.name).collect();
+    names.sort();
+    assert_eq!(names, ["alias", "dev", "work/api"]);
+    let mut skipped: Vec<_> = report
+        .skipped
+        .into_iter()
+        .map(|skipped| (skipped.name, skipped.reason))
+        .collect();
+    skipped.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        skipped,
+        [
+            ("shared".to_string(), SkipReason::LinkedDirectory),
+            ("work/loop".to_string(), SkipReason::LinkedDirectory),
+        ]
+    );
+
+    let output = run(dir.path(), &["check"]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(String::from_utf8_lossy(&output.stderr).contains(
+        "Skipped 'work/loop' in the profiles directory: symbolic links to directories are not followed"
+    ));
+}
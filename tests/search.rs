@@ -0,0 +1,130 @@
+//! `profile search` finds variables by key or value, as text or as a pattern.
+
+mod common;
+
+use common::{TempDir, stderr, write_profile};
+use env_manage::utils::pattern::Pattern;
+use std::path::Path;
+use std::process::Output;
+use std::time::{Duration, Instant};
+
+fn write_profiles(dir: &Path) {
+    write_profile(
+        dir,
+        "db",
+        "[variables]\nDB_URL = \"postgres://localhost\"\nDB_PORT = \"5432\"\n",
+    );
+    write_profile(
+        dir,
+        "api",
+        "[variables]\nAPI_URL = \"http://db:8080\"\nTIMEOUT = \"30\"\n",
+    );
+}
+
+fn search(dir: &Path, args: &[&str]) -> Output {
+    common::command(dir)
+        .args(["profile", "search"])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn keys_match_case_insensitively() {
+    let dir = TempDir::new("search-keys");
+    write_profiles(dir.path());
+
+    let output = search(dir.path(), &["url"]);
+
+    assert!(output.status.success());
+    assert_eq!(stderr(&output), "api\n└── API_URL\ndb\n└── DB_URL\n");
+}
+
+#[test]
+fn values_are_only_searched_with_the_flag() {
+    let dir = TempDir::new("search-values");
+    write_profiles(dir.path());
+
+    assert_eq!(
+        stderr(&search(dir.path(), &["localhost"])),
+        "[i] No variables match 'localhost'.\n"
+    );
+    assert_eq!(
+        stderr(&search(dir.path(), &["db", "--values"])),
+        "api\n└── API_URL = http://db:8080\ndb\n├── DB_URL = postgres://localhost\n└── DB_PORT = 5432\n"
+    );
+}
+
+#[test]
+fn regex_queries_match_patterns_and_report_invalid_ones() {
+    let dir = TempDir::new("search-regex");
+    write_profiles(dir.path());
+
+    assert_eq!(
+        stderr(&search(dir.path(), &["--regex", "--values", r"^\d+$"])),
+        "api\n└── TIMEOUT = 30\ndb\n└── DB_PORT = 5432\n"
+    );
+
+    let output = search(dir.path(), &["--regex", "(DB"]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("Invalid pattern:"));
+    assert!(stderr(&output).contains("unclosed group"));
+}
+
+#[test]
+fn nested_quantifiers_do_not_backtrack() {
+    let dir = TempDir::new("search-regex-nested");
+    write_profile(
+        &dir,
+        "long",
+        &format!("[variables]\nAS = \"{}\"\n", "a".repeat(34)),
+    );
+
+    let started = Instant::now();
+    for pattern in ["(a*)*b", "(a|aa)*c"] {
+        let output = search(dir.path(), &["--values", "--regex", pattern]);
+        assert!(output.status.success(), "{output:?}");
+        assert!(stderr(&output).contains("No variables match"), "{pattern}");
+    }
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    let long = "a".repeat(10_000);
+    let started = Instant::now();
+    assert!(!Pattern::new("(a*)*b").unwrap().is_match(&long));
+    assert!(!Pattern::new("(a|aa)*c").unwrap().is_match(&long));
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[test]
+fn pattern_syntax() {
+    let cases = [
+        ("abc", "xxabcxx", true),
+        ("^abc", "xabc", false),
+        ("abc$", "abcx", false),
+        ("a.c", "abc", true),
+        ("ab*c", "ac", true),
+        ("ab+c", "ac", false),
+        ("colou?r", "color", true),
+        ("^(dev|prod)_", "prod_url", true),
+        ("^(dev|prod)_", "stage_url", false),
+        ("[A-Z_]+$", "API_KEY", true),
+        ("^[^0-9]", "9lives", false),
+        (r"\w+@\w+\.com", "me@example.com", true),
+        (r"\s", "no-space", false),
+        (r"\.", "a.b", true),
+        (r"\.", "ab", false),
+        ("(a*)*b", "aaaa", false),
+        ("(?i)^db_", "Db_Url", true),
+        ("", "anything", true),
+    ];
+    for (pattern, text, expected) in cases {
+        assert_eq!(
+            Pattern::new(pattern).unwrap().is_match(text),
+            expected,
+            "{pattern} on {text}"
+        );
+    }
+    for invalid in ["(a", "a)", "*a", "[a-", r"a\", "[z-a]"] {
+        assert!(Pattern::new(invalid).is_err(), "{invalid}");
+    }
+}
@@ -0,0 +1,86 @@
+//! A profile must never end up listing itself as a dependency.
+
+mod common;
+
+use common::{TempDir, read_profile, run, write_profile};
+use env_manage::config::ConfigManager;
+use env_manage::config::models::Profile;
+use env_manage::tui::app::App;
+use std::fs;
+use std::path::Path;
+
+fn load_profile(dir: &TempDir, name: &str) -> Profile {
+    toml::from_str(&read_profile(dir, name).unwrap()).unwrap()
+}
+
+fn check(dir: &Path) -> (bool, String) {
+    let output = run(dir, &["check", "--format", "json"]);
+    (
+        output.status.success(),
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+    )
+}
+
+#[test]
+fn add_profile_refuses_self_references() {
+    let mut profile = Profile::new();
+    assert!(!profile.add_profile("app", "app"));
+    assert!(profile.add_profile("app", "lib"));
+    assert!(!profile.add_profile("app", "lib"));
+    assert_eq!(profile.profiles.len(), 1);
+}
+
+/// `app` sits in memory depending on `ghost`, which has no file (as the TUI keeps a
+/// profile whose dependency edge could not be added); renaming `app` to `ghost`
+/// used to save `ghost` with itself as a dependency
+#[test]
+fn renaming_onto_a_missing_dependency_drops_the_self_reference() {
+    let dir = TempDir::new("self-dependency-rename");
+    let mut config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    let mut app_profile = Profile::new();
+    app_profile.add_profile("app", "ghost");
+    app_profile.add_variable("KEY", "value");
+    config_manager.write_profile("app", &app_profile).unwrap();
+    config_manager.add_profile_node("app".to_string());
+    config_manager.add_profile("app".to_string(), app_profile);
+
+    let mut app = App::new(config_manager, Profile::new());
+    assert!(app.list_view.select_profile("app"));
+    app.rename_profile("ghost".to_string()).unwrap();
+    app.save_all().unwrap();
+
+    assert!(!dir.path().join("profiles").join("app.toml").exists());
+    let ghost = load_profile(&dir, "ghost");
+    assert!(ghost.profiles.is_empty(), "{:?}", ghost.profiles);
+    assert_eq!(
+        ghost.variables.get("KEY").map(String::as_str),
+        Some("value")
+    );
+    assert_eq!(check(dir.path()), (true, "[]\n".to_string()));
+}
+
+#[test]
+fn check_reports_and_fix_removes_a_self_dependency() {
+    let dir = TempDir::new("self-dependency-fix");
+    let profiles = dir.path().join("profiles");
+    fs::create_dir_all(&profiles).unwrap();
+    write_profile(dir.path(), "lib", "profiles = []\n");
+    write_profile(dir.path(), "app", "profiles = [\"app\", \"lib\"]\n");
+
+    let (ok, report) = check(dir.path());
+    assert!(!ok);
+    assert!(report.contains("\"kind\": \"self_dependency\""), "{report}");
+    assert!(report.contains("lists itself as a dependency"), "{report}");
+    assert!(!report.contains("circular_dependency"), "{report}");
+
+    let fixed = run(dir.path(), &["fix", "--yes"]);
+    assert!(fixed.status.success());
+    assert_eq!(
+        load_profile(&dir, "app")
+            .profiles
+            .into_iter()
+            .collect::<Vec<_>>(),
+        ["lib"]
+    );
+    assert!(check(dir.path()).0);
+}
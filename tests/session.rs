@@ -0,0 +1,108 @@
+//! The TUI reopens on the profile, right pane and sort order it was closed with.
+
+mod common;
+
+use common::TempDir;
+use env_manage::config::ConfigManager;
+use env_manage::config::models::{Profile, Settings};
+use env_manage::tui::app::{App, MainRightViewMode};
+use env_manage::tui::session::{self, Session};
+use env_manage::tui::views::list::SortMode;
+use std::fs;
+use std::path::Path;
+
+fn new_app(dir: &Path) -> App {
+    let config_manager = ConfigManager::with_base_path(dir.to_path_buf()).unwrap();
+    App::new(config_manager, Profile::new())
+}
+
+fn write_profiles(dir: &Path, names: &[&str]) {
+    let config_manager = ConfigManager::with_base_path(dir.to_path_buf()).unwrap();
+    for name in names {
+        let mut profile = Profile::new();
+        profile.add_variable("A", "1");
+        config_manager.write_profile(name, &profile).unwrap();
+    }
+}
+
+#[test]
+fn the_session_survives_a_restart() {
+    let dir = TempDir::new("session-restart");
+    write_profiles(dir.path(), &["alpha", "beta", "gamma"]);
+
+    let mut app = new_app(dir.path());
+    app.list_view.set_sort_mode(SortMode::Modified);
+    assert!(app.list_view.select_profile("beta"));
+    app.load_expand_vars();
+    Session::of(&app).save(dir.path());
+    drop(app);
+
+    let mut app = new_app(dir.path());
+    Session::load(dir.path()).restore(&mut app);
+    assert_eq!(app.list_view.current_profile(), Some("beta"));
+    assert_eq!(app.main_right_view_mode, MainRightViewMode::Expand);
+    assert_eq!(app.list_view.sort_mode(), SortMode::Modified);
+}
+
+#[test]
+fn a_deleted_profile_falls_back_to_the_defaults() {
+    let dir = TempDir::new("session-missing");
+    write_profiles(dir.path(), &["alpha", "beta"]);
+    let saved = Session {
+        selected: Some("gone".to_string()),
+        right_pane: MainRightViewMode::Expand,
+        sort: SortMode::VariableCount,
+    };
+    saved.save(dir.path());
+
+    let mut app = new_app(dir.path());
+    let selected = app.list_view.current_profile().map(str::to_string);
+    Session::load(dir.path()).restore(&mut app);
+
+    assert_eq!(Session::load(dir.path()), saved);
+    assert_eq!(
+        app.list_view.current_profile().map(str::to_string),
+        selected
+    );
+    assert_eq!(app.main_right_view_mode, MainRightViewMode::Raw);
+    // The sort order does not depend on the profile and is still restored
+    assert_eq!(app.list_view.sort_mode(), SortMode::VariableCount);
+}
+
+#[test]
+fn a_corrupt_or_missing_file_is_the_default_session() {
+    let dir = TempDir::new("session-corrupt");
+    assert_eq!(Session::load(dir.path()), Session::default());
+
+    for content in [
+        "{\"selected\": \"alpha\"",
+        "not json",
+        "{\"sort\": \"sideways\"}",
+        "[]",
+    ] {
+        fs::write(session::session_path(dir.path()), content).unwrap();
+        assert_eq!(Session::load(dir.path()), Session::default(), "{content}");
+    }
+
+    // Unknown keys and nulls are ignored
+    fs::write(
+        session::session_path(dir.path()),
+        "{\"selected\": null, \"sort\": \"dependency_count\", \"later\": [1, true]}",
+    )
+    .unwrap();
+    assert_eq!(
+        Session::load(dir.path()),
+        Session {
+            sort: SortMode::DependencyCount,
+            ..Session::default()
+        }
+    );
+}
+
+#[test]
+fn restoring_is_on_unless_the_settings_turn_it_off() {
+    let settings: Settings = toml::from_str("").unwrap();
+    assert!(settings.restores_session());
+    let settings: Settings = toml::from_str("restore_session = false\n").unwrap();
+    assert!(!settings.restores_session());
+}
@@ -0,0 +1,313 @@
+//! Runs the built binary the way the shell hook does and evaluates the marked
+//! commands in a real shell, checking the environment that results. The same goes for
+//! `profile export --format shell`, which is sourced as is, and the integration
+//! printed by `init --print`.
+
+mod common;
+
+use common::TempDir;
+use env_manage::utils::shell_generate::marked_script;
+use std::collections::HashMap;
+use std::process::Command;
+
+const TRICKY_VALUES: &[(&str, &str)] = &[
+    ("WITH_SPACES", "a value  with   spaces"),
+    ("SINGLE_QUOTES", "it's 'quoted'"),
+    ("DOUBLE_QUOTES", "say \"hi\""),
+    ("NEWLINES", "first line\nsecond line"),
+    (
+        "PEM",
+        "-----BEGIN KEY-----\nMIIBOgIBAAJBAK\n-----END KEY-----\n",
+    ),
+    (
+        "JSON",
+        "{\n  \"name\": \"it's\",\n  \"path\": \"C:\\\\dir\"\n}",
+    ),
+    ("UNICODE", "héllo 世界 🌱"),
+    ("DOLLAR", "$HOME and ${PATH} and $(whoami) and `id`"),
+    ("BACKSLASHES", r"C:\path\to\dir\"),
+    ("EMPTY", ""),
+];
+
+/// Run the binary for `shell` with `env` as the session environment, returning stdout
+fn env_manage(home: &TempDir, shell: &str, env: &HashMap<String, String>, args: &[&str]) -> String {
+    let output = Command::new(common::binary())
+        .args(args)
+        .env_clear()
+        .envs(env)
+        .env("ENV_MANAGE_HOME", home.path())
+        .env("EM_SHELL", shell)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "env-manage {args:?} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Evaluate `script` in `shell` started with `env`, returning the environment afterwards
+fn eval_in_shell(
+    shell: &str,
+    env: &HashMap<String, String>,
+    script: &str,
+) -> HashMap<String, String> {
+    let output = Command::new(shell)
+        .arg("-c")
+        .arg(format!("{script}\nenv -0"))
+        .env_clear()
+        .envs(env)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{shell} failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .split('\0')
+        .filter_map(|entry| entry.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+fn shell_available(shell: &str) -> bool {
+    Command::new(shell)
+        .args(["-c", "true"])
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// The environment the test shells start from
+fn base_env() -> HashMap<String, String> {
+    let mut env = HashMap::new();
+    env.insert(
+        "PATH".to_string(),
+        std::env::var("PATH").unwrap_or_default(),
+    );
+    env
+}
+
+/// Create profile `name` holding `vars` through the CLI
+fn create_profile(home: &TempDir, name: &str, vars: &[(&str, &str)]) {
+    let env = base_env();
+    env_manage(home, "bash", &env, &["profile", "create", name]);
+    let mut args = vec!["profile".to_string(), "add".to_string(), name.to_string()];
+    args.extend(vars.iter().map(|(key, value)| format!("{key}={value}")));
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    env_manage(home, "bash", &env, &args);
+}
+
+/// Activate `tricky` in `shell` and check every value arrives unchanged
+fn assert_activate_round_trip(shell: &str) {
+    let home = TempDir::new(&format!("activate-{shell}"));
+    create_profile(&home, "tricky", TRICKY_VALUES);
+
+    let env = base_env();
+    let output = env_manage(&home, shell, &env, &["activate", "tricky"]);
+    let script = marked_script(&output).expect("activation output is not marked");
+    let after = eval_in_shell(shell, &env, script);
+
+    for (key, value) in TRICKY_VALUES {
+        assert_eq!(
+            after.get(*key).map(String::as_str),
+            Some(*value),
+            "{key} in {shell}"
+        );
+    }
+    assert_eq!(
+        after
+            .get("__ENV_MANAGE_ACTIVE_PROFILES")
+            .map(String::as_str),
+        Some("tricky")
+    );
+}
+
+#[test]
+fn activate_round_trips_tricky_values_in_bash() {
+    assert_activate_round_trip("bash");
+}
+
+#[test]
+fn activate_round_trips_tricky_values_in_zsh() {
+    if !shell_available("zsh") {
+        eprintln!("zsh not found, skipping");
+        return;
+    }
+    assert_activate_round_trip("zsh");
+}
+
+#[test]
+fn deactivate_restores_previous_values() {
+    let home = TempDir::new("deactivate");
+    create_profile(&home, "tricky", TRICKY_VALUES);
+
+    let mut env = base_env();
+    env.insert(
+        "WITH_SPACES".to_string(),
+        "it was 'here' before".to_string(),
+    );
+
+    let output = env_manage(&home, "bash", &env, &["activate", "tricky"]);
+    let activated = eval_in_shell("bash", &env, marked_script(&output).unwrap());
+
+    // The session state travels in the environment, as it would in the user's shell
+    let output = env_manage(&home, "bash", &activated, &["deactivate", "tricky"]);
+    let script = marked_script(&output).expect("deactivation output is not marked");
+    let after = eval_in_shell("bash", &activated, script);
+
+    assert_eq!(
+        after.get("WITH_SPACES").map(String::as_str),
+        Some("it was 'here' before")
+    );
+    for (key, _) in TRICKY_VALUES
+        .iter()
+        .filter(|(key, _)| *key != "WITH_SPACES")
+    {
+        assert!(!after.contains_key(*key), "{key} is still set");
+    }
+    assert!(!after.contains_key("__ENV_MANAGE_ACTIVE_PROFILES"));
+}
+
+#[test]
+fn print_output_is_not_marked() {
+    let home = TempDir::new("print");
+    create_profile(&home, "plain", &[("KEY", "value")]);
+
+    let output = env_manage(
+        &home,
+        "bash",
+        &base_env(),
+        &["activate", "plain", "--print"],
+    );
+    assert!(marked_script(&output).is_none());
+    assert!(output.contains("export KEY='value'"));
+}
+
+#[test]
+fn shell_export_can_be_sourced() {
+    let home = TempDir::new("export-shell");
+    create_profile(&home, "tricky", TRICKY_VALUES);
+    let file = home.path().join("out/tricky.sh");
+
+    let env = base_env();
+    let output = env_manage(
+        &home,
+        "fish",
+        &env,
+        &[
+            "profile",
+            "export",
+            "tricky",
+            "--format",
+            "shell",
+            "--shell",
+            "bash",
+            "-o",
+            file.to_str().unwrap(),
+        ],
+    );
+    assert!(output.is_empty());
+    let after = eval_in_shell("bash", &env, &format!(". '{}'", file.display()));
+
+    for (key, value) in TRICKY_VALUES {
+        assert_eq!(after.get(*key).map(String::as_str), Some(*value), "{key}");
+    }
+    // Only the variables, none of the session bookkeeping of an activation
+    assert!(!after.contains_key("__ENV_MANAGE_ACTIVE_PROFILES"));
+}
+
+#[test]
+fn shell_export_is_unmarked_and_sorted() {
+    let home = TempDir::new("export-shell-sorted");
+    create_profile(
+        &home,
+        "plain",
+        &[("ZED", "z"), ("ALPHA", "it's"), ("MID", "m")],
+    );
+
+    let output = env_manage(
+        &home,
+        "bash",
+        &base_env(),
+        &["profile", "export", "plain", "--format", "shell"],
+    );
+    assert!(marked_script(&output).is_none());
+    assert_eq!(
+        output,
+        "export ALPHA='it'\\''s'\nexport MID='m'\nexport ZED='z'\n"
+    );
+
+    let output = env_manage(
+        &home,
+        "bash",
+        &base_env(),
+        &[
+            "profile", "export", "plain", "--format", "shell", "--shell", "fish",
+        ],
+    );
+    assert_eq!(
+        output,
+        "set -gx ALPHA 'it\\'s'\nset -gx MID 'm'\nset -gx ZED 'z'\n"
+    );
+}
+
+fn init(args: &[&str]) -> String {
+    let output = Command::new(common::binary())
+        .arg("init")
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "init {args:?}");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn init_print_outputs_the_integration_itself() {
+    for shell in ["bash", "zsh", "fish", "powershell"] {
+        let loader = init(&[shell]);
+        let printed = init(&[shell, "--print"]);
+
+        assert!(loader.contains("--print-full-init"), "{shell}: {loader}");
+        assert!(printed.contains(env_manage::SHELL_MARK), "{shell}");
+        assert!(!printed.contains("{{"), "{shell}: unreplaced placeholder");
+        // Shells set up before `--print` existed keep loading the same script
+        assert_eq!(init(&[shell, "--print-full-init"]), printed, "{shell}");
+    }
+
+    if shell_available("bash") {
+        let script = format!("{}\ntype -t em", init(&["bash", "--print"]));
+        let output = Command::new("bash").args(["-c", &script]).output().unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "function");
+    }
+}
+
+#[test]
+fn init_takes_the_shell_as_an_option_or_detects_it() {
+    for shell in ["bash", "zsh", "fish", "powershell"] {
+        assert_eq!(init(&["--shell", shell]), init(&[shell]), "{shell}");
+        assert_eq!(
+            init(&["--shell", shell, "--print"]),
+            init(&[shell, "--print"]),
+            "{shell}"
+        );
+    }
+    assert!(init(&["--shell", "fish"]).ends_with("| source"));
+    assert!(init(&["--shell", "bash", "--print"]).contains("bash"));
+
+    let detected = Command::new(common::binary())
+        .arg("init")
+        .env("EM_SHELL", "fish")
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&detected.stdout), init(&["fish"]));
+
+    let both = Command::new(common::binary())
+        .args(["init", "bash", "--shell", "fish"])
+        .output()
+        .unwrap();
+    assert!(!both.status.success());
+}
@@ -0,0 +1,56 @@
+//! Status messages are kept in a bounded history that the `l` key opens.
+
+mod common;
+
+use common::TempDir;
+use env_manage::config::ConfigManager;
+use env_manage::config::models::Profile;
+use env_manage::tui::app::{App, AppState, STATUS_HISTORY_LEN};
+use env_manage::tui::views::list;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+fn app(dir: &TempDir) -> App {
+    let config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    App::new(config_manager, Profile::new())
+}
+
+#[test]
+fn history_keeps_the_newest_messages() {
+    let dir = TempDir::new("status-log-history");
+    let mut app = app(&dir);
+
+    for i in 0..STATUS_HISTORY_LEN + 5 {
+        app.show_info(format!("message {i}"));
+    }
+    app.show_error("last");
+
+    assert_eq!(app.status_history.len(), STATUS_HISTORY_LEN);
+    assert_eq!(app.status_history.front().unwrap().text, "message 6");
+    assert_eq!(app.status_history.back().unwrap().text, "last");
+    assert_eq!(app.status_message.as_ref().unwrap().text, "last");
+}
+
+#[test]
+fn log_opens_and_scrolls() {
+    let dir = TempDir::new("status-log-keys");
+    let mut app = app(&dir);
+    app.show_info("first");
+    app.show_success("second");
+
+    list::handle_event(
+        &mut app,
+        KeyEvent::new(KeyCode::Char('l'), KeyModifiers::NONE),
+    )
+    .unwrap();
+    assert_eq!(app.state, AppState::StatusLog);
+    assert_eq!(app.status_log_offset, 0);
+
+    // Scrolling stops at the oldest message
+    for _ in 0..3 {
+        app.scroll_status_log_down();
+    }
+    assert_eq!(app.status_log_offset, 1);
+    app.scroll_status_log_up();
+    app.scroll_status_log_up();
+    assert_eq!(app.status_log_offset, 0);
+}
@@ -0,0 +1,58 @@
+//! Loaded profiles show their variable count after the name in the TUI list, and the
+//! count follows edits.
+
+mod common;
+
+use common::{TempDir, press, screen, write_profile};
+use env_manage::config::ConfigManager;
+use env_manage::config::models::Profile;
+use env_manage::tui::app::{App, AppState};
+use ratatui::crossterm::event::KeyCode;
+
+fn app(dir: &TempDir) -> App {
+    write_profile(dir.path(), "app", "[variables]\nA = \"1\"\nB = \"2\"\n");
+    write_profile(dir.path(), "empty", "");
+    let config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    App::new(config_manager, Profile::new())
+}
+
+fn row_of<'a>(screen: &'a [String], name: &str) -> &'a str {
+    screen
+        .iter()
+        .find(|row| row.contains(&format!("{name} (")))
+        .unwrap()
+}
+
+#[test]
+fn loaded_profiles_show_their_variable_count() {
+    let dir = TempDir::new("tui-badges");
+    let mut app = app(&dir);
+    // Profiles are loaded lazily, and only loaded ones have a count
+    assert!(!screen(&app).concat().contains("app (2 vars)"));
+    assert!(app.ensure_loaded("app"));
+    assert!(app.ensure_loaded("empty"));
+
+    let rows = screen(&app);
+    assert!(row_of(&rows, "app").contains("app (2 vars)"), "{rows:#?}");
+    assert!(row_of(&rows, "empty").contains("empty (0 vars)"));
+
+    // Adding a variable in the editor updates the count, next to the unsaved marker
+    assert!(app.list_view.select_profile("empty"));
+    press(&mut app, KeyCode::Enter);
+    assert_eq!(app.state, AppState::Edit);
+    press(&mut app, KeyCode::Char('a'));
+    for c in "KEY".chars() {
+        press(&mut app, KeyCode::Char(c));
+    }
+    press(&mut app, KeyCode::Enter);
+    press(&mut app, KeyCode::Char('v'));
+    press(&mut app, KeyCode::Enter);
+    press(&mut app, KeyCode::Esc);
+    assert_eq!(app.state, AppState::List);
+
+    let rows = screen(&app);
+    assert!(
+        row_of(&rows, "empty").contains("*empty (1 vars)"),
+        "{rows:#?}"
+    );
+}
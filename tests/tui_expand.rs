@@ -0,0 +1,53 @@
+//! The expand view shows the description and tags of the selected profile above its
+//! resolved variables, like the raw view does.
+
+mod common;
+
+use common::{TempDir, screen, write_profile};
+use env_manage::config::ConfigManager;
+use env_manage::config::models::Profile;
+use env_manage::tui::app::{App, MainRightViewMode};
+
+fn expanded(dir: &TempDir, name: &str) -> Vec<String> {
+    let config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    let mut app = App::new(config_manager, Profile::new());
+    assert!(app.list_view.select_profile(name));
+    app.load_expand_vars();
+    assert_eq!(app.main_right_view_mode, MainRightViewMode::Expand);
+    screen(&app)
+}
+
+#[test]
+fn expand_view_shows_the_metadata_line() {
+    let dir = TempDir::new("tui-expand-metadata");
+    write_profile(
+        dir.path(),
+        "web",
+        "description = \"Frontend server\"\ntags = [\"team\", \"prod\"]\n\n\
+         [variables]\nPORT = \"8080\"\n",
+    );
+
+    let rows = expanded(&dir, "web");
+    let title = rows
+        .iter()
+        .position(|row| row.contains("Expanded for 'web'"))
+        .unwrap();
+    assert!(
+        rows[title + 1].contains("#team #prod Frontend server"),
+        "{rows:#?}"
+    );
+    assert!(rows.concat().contains("8080"));
+}
+
+#[test]
+fn expand_view_without_metadata_starts_with_the_variables() {
+    let dir = TempDir::new("tui-expand-plain");
+    write_profile(dir.path(), "web", "[variables]\nPORT = \"8080\"\n");
+
+    let rows = expanded(&dir, "web");
+    let title = rows
+        .iter()
+        .position(|row| row.contains("Expanded for 'web'"))
+        .unwrap();
+    assert!(rows[title + 1].contains("Variables (1)"), "{rows:#?}");
+}
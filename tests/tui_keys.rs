@@ -0,0 +1,714 @@
+//! Drive the add-new and edit views with key events, as the event loop does, and
+//! check the profiles they produce.
+
+mod common;
+
+use common::{TempDir, press, press_with, type_text};
+use env_manage::GLOBAL_PROFILE_MARK;
+use env_manage::config::ConfigManager;
+use env_manage::config::models::{KeyValidation, Profile};
+use env_manage::tui::app::{App, AppState};
+use ratatui::crossterm::event::{KeyCode, KeyModifiers};
+
+fn app_with(dir: &TempDir, profiles: &[(&str, &[(&str, &str)])]) -> App {
+    let config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    for (name, variables) in profiles {
+        let mut profile = Profile::new();
+        for (key, value) in *variables {
+            profile.add_variable(key, value);
+        }
+        config_manager.write_profile(name, &profile).unwrap();
+    }
+    App::new(config_manager, Profile::new())
+}
+
+fn ctrl(app: &mut App, c: char) {
+    press_with(app, KeyCode::Char(c), KeyModifiers::CONTROL);
+}
+
+fn profile(app: &App, name: &str) -> Profile {
+    app.config_manager.get_profile(name).unwrap().clone()
+}
+
+/// Open the edit view on `name`, with the value column of its first variable focused
+fn edit_first_value(app: &mut App, name: &str) {
+    assert!(app.list_view.select_profile(name));
+    press(app, KeyCode::Enter);
+    assert_eq!(app.state, AppState::Edit);
+    press(app, KeyCode::Right);
+}
+
+#[test]
+fn add_new_creates_the_typed_profile() {
+    let dir = TempDir::new("tui-keys-add-new");
+    let mut app = app_with(&dir, &[]);
+
+    press(&mut app, KeyCode::Char('n'));
+    assert_eq!(app.state, AppState::AddNew);
+    type_text(&mut app, "api");
+    // Name -> Description -> Tags -> Profiles -> Variables
+    for _ in 0..4 {
+        press(&mut app, KeyCode::Tab);
+    }
+    press(&mut app, KeyCode::Char('a'));
+    type_text(&mut app, "PORT");
+    press(&mut app, KeyCode::Enter);
+    type_text(&mut app, "8080");
+    press(&mut app, KeyCode::Enter);
+    ctrl(&mut app, 's');
+
+    assert_eq!(app.state, AppState::List);
+    let api = profile(&app, "api");
+    assert_eq!(api.variables.get("PORT").map(String::as_str), Some("8080"));
+    assert!(app.list_view.is_dirty("api"));
+
+    press(&mut app, KeyCode::Char('w'));
+    assert!(!app.list_view.is_dirty("api"));
+    assert!(app.config_manager.profile_exists("api"));
+}
+
+#[test]
+fn add_new_refuses_an_existing_name() {
+    let dir = TempDir::new("tui-keys-add-existing");
+    let mut app = app_with(&dir, &[("api", &[("PORT", "1")])]);
+
+    press(&mut app, KeyCode::Char('n'));
+    type_text(&mut app, "api");
+    ctrl(&mut app, 's');
+
+    assert_eq!(app.state, AppState::AddNew);
+    press(&mut app, KeyCode::Esc);
+    assert_eq!(app.state, AppState::List);
+    assert!(!app.list_view.is_dirty("api"));
+}
+
+#[test]
+fn add_new_and_rename_refuse_reserved_names() {
+    let dir = TempDir::new("tui-keys-reserved");
+    let mut app = app_with(&dir, &[("api", &[])]);
+
+    press(&mut app, KeyCode::Char('n'));
+    type_text(&mut app, "global");
+    ctrl(&mut app, 's');
+    assert_eq!(app.state, AppState::AddNew);
+    assert_eq!(
+        app.add_new_view.name_input().error_message(),
+        Some("Reserved for the global profile")
+    );
+    press(&mut app, KeyCode::Esc);
+
+    assert!(app.list_view.select_profile("api"));
+    press(&mut app, KeyCode::F(2));
+    app.finish_full_load();
+    assert_eq!(app.state, AppState::Rename);
+    for _ in 0.."api".len() {
+        press(&mut app, KeyCode::Backspace);
+    }
+    type_text(&mut app, env_manage::SHELL_MARK);
+    assert_eq!(
+        app.list_view.rename_input().error_message(),
+        Some("Reserved for internal use")
+    );
+    press(&mut app, KeyCode::Enter);
+    press(&mut app, KeyCode::Esc);
+    assert!(app.config_manager.has_profile("api"));
+}
+
+#[test]
+fn editing_a_value_updates_the_profile_on_exit() {
+    let dir = TempDir::new("tui-keys-edit");
+    let mut app = app_with(&dir, &[("app", &[("KEY", "old")])]);
+
+    edit_first_value(&mut app, "app");
+    press(&mut app, KeyCode::Char('e'));
+    for _ in 0.."old".len() {
+        press(&mut app, KeyCode::Backspace);
+    }
+    type_text(&mut app, "new");
+    press(&mut app, KeyCode::Enter);
+    press(&mut app, KeyCode::Esc);
+
+    assert_eq!(app.state, AppState::List);
+    assert_eq!(
+        profile(&app, "app")
+            .variables
+            .get("KEY")
+            .map(String::as_str),
+        Some("new")
+    );
+    assert!(app.list_view.is_dirty("app"));
+}
+
+#[test]
+fn cancelling_an_edit_restores_the_value() {
+    let dir = TempDir::new("tui-keys-cancel");
+    let mut app = app_with(&dir, &[("app", &[("KEY", "old")])]);
+
+    edit_first_value(&mut app, "app");
+    press(&mut app, KeyCode::Char('e'));
+    type_text(&mut app, "-typo");
+    press(&mut app, KeyCode::Esc);
+    assert!(!app.edit_view.is_editing());
+    press(&mut app, KeyCode::Esc);
+
+    assert_eq!(app.state, AppState::List);
+    assert_eq!(
+        profile(&app, "app")
+            .variables
+            .get("KEY")
+            .map(String::as_str),
+        Some("old")
+    );
+    assert!(!app.list_view.is_dirty("app"));
+}
+
+#[test]
+fn toggling_unset_and_deleting_rows() {
+    let dir = TempDir::new("tui-keys-unset");
+    let mut app = app_with(&dir, &[("app", &[("A", "1"), ("B", "2")])]);
+
+    assert!(app.list_view.select_profile("app"));
+    press(&mut app, KeyCode::Enter);
+    press(&mut app, KeyCode::Char('u'));
+    press(&mut app, KeyCode::Char('j'));
+    press(&mut app, KeyCode::Char('d'));
+    press(&mut app, KeyCode::Esc);
+
+    let app_profile = profile(&app, "app");
+    assert!(app_profile.variables.is_empty(), "{app_profile:?}");
+    assert_eq!(app_profile.unset.into_iter().collect::<Vec<_>>(), ["A"]);
+}
+
+#[test]
+fn reset_clears_the_edit_view_between_profiles() {
+    let dir = TempDir::new("tui-keys-reset");
+    let mut app = app_with(&dir, &[("a", &[("ONE", "1")]), ("b", &[("TWO", "2")])]);
+
+    assert!(app.list_view.select_profile("a"));
+    press(&mut app, KeyCode::Enter);
+    press(&mut app, KeyCode::Esc);
+    assert_eq!(app.edit_view.profile_name(), "");
+
+    assert!(app.list_view.select_profile("b"));
+    press(&mut app, KeyCode::Enter);
+    let b = app.edit_view.to_profile();
+    assert_eq!(b.variables.keys().collect::<Vec<_>>(), ["TWO"]);
+}
+
+#[test]
+fn dependency_selector_filters_and_keeps_selections() {
+    let dir = TempDir::new("tui-keys-dependency-filter");
+    let mut app = app_with(
+        &dir,
+        &[
+            ("app", &[("A", "1")]),
+            ("alpha", &[("B", "2")]),
+            ("beta", &[("C", "3")]),
+            ("bravo", &[("D", "4")]),
+        ],
+    );
+
+    assert!(app.list_view.select_profile("app"));
+    press(&mut app, KeyCode::Enter);
+    press(&mut app, KeyCode::Tab);
+    press(&mut app, KeyCode::Char('n'));
+    app.finish_full_load();
+    assert!(app.edit_view.is_dependency_selector_open());
+
+    type_text(&mut app, "al");
+    let state = app.edit_view.dependency_selector_state().unwrap();
+    assert_eq!(state.query, "al");
+    assert_eq!(state.options, ["alpha"]);
+    press(&mut app, KeyCode::Char(' '));
+
+    press(&mut app, KeyCode::Backspace);
+    press(&mut app, KeyCode::Backspace);
+    // With the query cleared, j/k navigate again instead of filtering
+    type_text(&mut app, "br");
+    assert_eq!(
+        app.edit_view.dependency_selector_state().unwrap().options,
+        ["bravo"]
+    );
+    press(&mut app, KeyCode::Enter);
+    assert_eq!(
+        app.edit_view
+            .dependency_selector_state()
+            .unwrap()
+            .selected
+            .len(),
+        2
+    );
+    press(&mut app, KeyCode::Esc);
+
+    assert!(!app.edit_view.is_dependency_selector_open());
+    assert_eq!(app.edit_view.profiles(), ["alpha", "bravo"]);
+}
+
+#[test]
+fn dependency_selector_types_j_and_k_once_filtering() {
+    let dir = TempDir::new("tui-keys-dependency-jk");
+    let mut app = app_with(
+        &dir,
+        &[
+            ("app", &[("A", "1")]),
+            ("jobs", &[("B", "2")]),
+            ("web", &[("C", "3")]),
+        ],
+    );
+
+    assert!(app.list_view.select_profile("app"));
+    press(&mut app, KeyCode::Enter);
+    press(&mut app, KeyCode::Tab);
+    press(&mut app, KeyCode::Char('n'));
+    app.finish_full_load();
+
+    press(&mut app, KeyCode::Char('j'));
+    let state = app.edit_view.dependency_selector_state().unwrap();
+    assert_eq!((state.query, state.current_index), ("", 1));
+
+    type_text(&mut app, "ob");
+    press(&mut app, KeyCode::Char('k'));
+    assert!(
+        app.edit_view
+            .dependency_selector_state()
+            .unwrap()
+            .options
+            .is_empty()
+    );
+}
+
+#[test]
+fn dependency_selector_lists_siblings_and_recent_profiles_first() {
+    let dir = TempDir::new("tui-keys-dependency-relevance");
+    let mut app = app_with(
+        &dir,
+        &[
+            ("app", &[("A", "1")]),
+            ("alpha", &[("B", "2")]),
+            ("mid", &[("C", "3")]),
+            ("zeta", &[("D", "4")]),
+            ("zulu", &[("E", "5")]),
+        ],
+    );
+    // `web` depends on `app` and `zeta`, making `zeta` a sibling of `app`
+    let mut web = Profile::new();
+    web.add_profile("web", "app");
+    web.add_profile("web", "zeta");
+    app.config_manager.write_profile("web", &web).unwrap();
+    app.finish_full_load();
+    app.recent.touch("zulu");
+    app.recent.touch("mid");
+
+    assert!(app.list_view.select_profile("app"));
+    press(&mut app, KeyCode::Enter);
+    press(&mut app, KeyCode::Tab);
+    press(&mut app, KeyCode::Char('n'));
+    app.finish_full_load();
+    let state = app.edit_view.dependency_selector_state().unwrap();
+    assert_eq!(state.options, ["zeta", "mid", "zulu", "alpha"]);
+
+    // Filtering keeps the order
+    type_text(&mut app, "z");
+    assert_eq!(
+        app.edit_view.dependency_selector_state().unwrap().options,
+        ["zeta", "zulu"]
+    );
+}
+
+fn search(app: &mut App, query: &str) {
+    press(app, KeyCode::Char('/'));
+    app.finish_full_load();
+    assert!(app.list_view.is_searching());
+    type_text(app, query);
+}
+
+fn delete_selected(app: &mut App) {
+    ctrl(app, 'd');
+    app.finish_full_load();
+    assert_eq!(app.state, AppState::ConfirmDelete);
+    press(app, KeyCode::Char('y'));
+}
+
+#[test]
+fn deleting_during_search_keeps_the_selection_in_the_filtered_list() {
+    let dir = TempDir::new("tui-keys-search-delete");
+    let mut app = app_with(
+        &dir,
+        &[
+            ("alpha", &[("A", "1")]),
+            ("web", &[("B", "2")]),
+            ("webapp", &[("C", "3")]),
+        ],
+    );
+
+    search(&mut app, "we");
+    assert_eq!(app.list_view.filtered_profiles(), ["web", "webapp"]);
+    press(&mut app, KeyCode::Down);
+    assert_eq!(app.list_view.current_profile(), Some("webapp"));
+
+    delete_selected(&mut app);
+    assert!(app.list_view.is_searching());
+    assert_eq!(app.list_view.filtered_profiles(), ["web"]);
+    assert_eq!(app.list_view.selected_index(), 0);
+    assert_eq!(app.list_view.current_profile(), Some("web"));
+
+    delete_selected(&mut app);
+    assert!(app.list_view.filtered_profiles().is_empty());
+    assert_eq!(app.list_view.selected_index(), 0);
+    assert_eq!(app.list_view.current_profile(), None);
+
+    press(&mut app, KeyCode::Esc);
+    assert!(!app.config_manager.profile_exists("web"));
+    assert_eq!(app.list_view.current_profile(), Some(GLOBAL_PROFILE_MARK));
+}
+
+#[test]
+fn narrowing_the_search_resets_a_stale_selection() {
+    let dir = TempDir::new("tui-keys-search-narrow");
+    let mut app = app_with(
+        &dir,
+        &[
+            ("alpha", &[("A", "1")]),
+            ("beta", &[("B", "2")]),
+            ("bravo", &[("C", "3")]),
+        ],
+    );
+
+    search(&mut app, "a");
+    press(&mut app, KeyCode::Up);
+    let last = app.list_view.filtered_profiles().len() - 1;
+    assert_eq!(app.list_view.selected_index(), last);
+
+    type_text(&mut app, "zz");
+    assert_eq!(app.list_view.selected_index(), 0);
+    assert_eq!(app.list_view.current_profile(), None);
+
+    press(&mut app, KeyCode::Backspace);
+    press(&mut app, KeyCode::Backspace);
+    type_text(&mut app, "lp");
+    assert_eq!(app.list_view.current_profile(), Some("alpha"));
+}
+
+#[test]
+fn activate_key_closes_the_tui_for_the_selected_profile() {
+    let dir = TempDir::new("tui-keys-activate");
+    let mut app = app_with(&dir, &[("app", &[("A", "1")])]);
+
+    assert!(app.list_view.select_profile(GLOBAL_PROFILE_MARK));
+    press(&mut app, KeyCode::Char('a'));
+    assert!(!app.shutdown);
+    assert_eq!(app.activate_on_exit, None);
+
+    assert!(app.list_view.select_profile("app"));
+    press(&mut app, KeyCode::Char('a'));
+    assert!(app.shutdown);
+    assert_eq!(app.activate_on_exit.as_deref(), Some("app"));
+}
+
+#[test]
+fn activate_key_asks_about_unsaved_changes_first() {
+    let dir = TempDir::new("tui-keys-activate-unsaved");
+    let mut app = app_with(&dir, &[("app", &[("A", "1")])]);
+    app.list_view.mark_dirty("app".to_string());
+
+    assert!(app.list_view.select_profile("app"));
+    press(&mut app, KeyCode::Char('a'));
+    assert_eq!(app.state, AppState::ConfirmExit);
+    assert!(!app.shutdown);
+
+    press(&mut app, KeyCode::Esc);
+    assert_eq!(app.state, AppState::List);
+    assert_eq!(app.activate_on_exit, None);
+
+    press(&mut app, KeyCode::Char('a'));
+    press(&mut app, KeyCode::Char('n'));
+    assert!(app.shutdown);
+    assert_eq!(app.activate_on_exit.as_deref(), Some("app"));
+}
+
+fn numbered_profiles(count: usize) -> Vec<(String, [(&'static str, &'static str); 1])> {
+    (0..count)
+        .map(|i| (format!("p{i:02}"), [("A", "1")]))
+        .collect()
+}
+
+#[test]
+fn count_prefix_repeats_list_motions() {
+    let dir = TempDir::new("tui-keys-count-list");
+    let profiles = numbered_profiles(12);
+    let profiles: Vec<(&str, &[(&str, &str)])> = profiles
+        .iter()
+        .map(|(name, vars)| (name.as_str(), vars.as_slice()))
+        .collect();
+    let mut app = app_with(&dir, &profiles);
+    let start = app.list_view.selected_index();
+
+    type_text(&mut app, "5j");
+    assert_eq!(app.list_view.selected_index(), start + 5);
+    press(&mut app, KeyCode::Char('j'));
+    assert_eq!(app.list_view.selected_index(), start + 6, "count was reset");
+
+    type_text(&mut app, "12k");
+    let rows = app.list_view.rows().len();
+    assert_eq!(
+        app.list_view.selected_index(),
+        (start + 6 + rows - 12) % rows
+    );
+
+    // A digit followed by another key drops the count
+    let before = app.list_view.selected_index();
+    type_text(&mut app, "3");
+    press(&mut app, KeyCode::Char('o'));
+    press(&mut app, KeyCode::Char('o'));
+    press(&mut app, KeyCode::Down);
+    assert_eq!(app.list_view.selected_index(), (before + 1) % rows);
+    assert_eq!(app.count_prefix.pending(), None);
+}
+
+#[test]
+fn count_prefix_repeats_edit_motions_and_is_capped() {
+    let dir = TempDir::new("tui-keys-count-edit");
+    let vars: Vec<(String, String)> = (0..10).map(|i| (format!("K{i}"), i.to_string())).collect();
+    let vars: Vec<(&str, &str)> = vars.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+    let mut app = app_with(&dir, &[("app", &vars)]);
+
+    assert!(app.list_view.select_profile("app"));
+    press(&mut app, KeyCode::Enter);
+    type_text(&mut app, "3j");
+    assert_eq!(app.edit_view.selected_variable_index(), 3);
+
+    // A leading zero is not a count, so `0` is ignored and `j` moves once
+    type_text(&mut app, "0j");
+    assert_eq!(app.edit_view.selected_variable_index(), 4);
+
+    type_text(&mut app, "99999");
+    assert_eq!(app.count_prefix.pending(), Some(999));
+    press(&mut app, KeyCode::Esc);
+    assert_eq!(app.state, AppState::List);
+    assert_eq!(app.count_prefix.pending(), None);
+}
+
+/// Mark each of `names` with Space, which enters the multi-select mode
+fn mark(app: &mut App, names: &[&str]) {
+    for name in names {
+        assert!(app.list_view.select_profile(name));
+        press(app, KeyCode::Char(' '));
+    }
+}
+
+fn status_text(app: &App) -> &str {
+    &app.status_message.as_ref().unwrap().text
+}
+
+#[test]
+fn marking_profiles_in_the_multi_select_mode() {
+    let dir = TempDir::new("tui-keys-marks");
+    let mut app = app_with(&dir, &[("alpha", &[]), ("beta", &[]), ("gamma", &[])]);
+
+    press(&mut app, KeyCode::Char('v'));
+    assert!(app.list_view.is_visual_mode());
+    mark(&mut app, &["gamma", "alpha", "beta"]);
+    press(&mut app, KeyCode::Char(' '));
+    assert_eq!(app.list_view.marked_profiles(), ["alpha", "gamma"]);
+    assert!(env_manage::tui::views::list::list_title(&app.list_view, 4).ends_with("2 selected"));
+
+    assert!(app.list_view.select_profile(GLOBAL_PROFILE_MARK));
+    press(&mut app, KeyCode::Char(' '));
+    assert_eq!(app.list_view.marked_count(), 2);
+    assert_eq!(status_text(&app), "Cannot select GLOBAL profile");
+
+    // Esc leaves the mode instead of closing the TUI
+    press(&mut app, KeyCode::Esc);
+    assert!(!app.list_view.is_visual_mode());
+    assert_eq!(app.list_view.marked_count(), 0);
+    assert!(!app.shutdown);
+}
+
+#[test]
+fn bulk_delete_skips_profiles_still_in_use() {
+    let dir = TempDir::new("tui-keys-bulk-delete");
+    let config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    let mut web = Profile::new();
+    web.add_profile("web", "base");
+    for (name, profile) in [
+        ("base", Profile::new()),
+        ("web", web),
+        ("lone", Profile::new()),
+        ("spare", Profile::new()),
+    ] {
+        config_manager.write_profile(name, &profile).unwrap();
+    }
+    let mut app = App::new(config_manager, Profile::new());
+
+    mark(&mut app, &["base", "lone"]);
+    press(&mut app, KeyCode::Char('d'));
+    app.finish_full_load();
+    assert_eq!(app.state, AppState::ConfirmDelete);
+    let impact = app.delete_impact.as_ref().unwrap();
+    assert_eq!(impact.deletable().collect::<Vec<_>>(), ["lone"]);
+    assert_eq!(impact.blocked["base"], ["web"]);
+
+    press(&mut app, KeyCode::Char('y'));
+    assert_eq!(app.state, AppState::List);
+    assert_eq!(
+        status_text(&app),
+        "Deleted 1 of 2: lone; skipped base (used by web)"
+    );
+    assert!(!app.config_manager.profile_exists("lone"));
+    assert!(app.config_manager.profile_exists("base"));
+    assert!(!app.list_view.is_visual_mode());
+
+    // With its dependent deleted as well, the dependency can go
+    mark(&mut app, &["web", "base"]);
+    press(&mut app, KeyCode::Char('d'));
+    assert_eq!(app.state, AppState::ConfirmDelete);
+    press(&mut app, KeyCode::Char('y'));
+    assert_eq!(status_text(&app), "Deleted 2 of 2: web, base");
+    assert!(!app.config_manager.profile_exists("web"));
+    assert!(!app.config_manager.profile_exists("base"));
+    assert!(app.config_manager.profile_exists("spare"));
+}
+
+#[test]
+fn bulk_save_writes_only_the_marked_profiles() {
+    let dir = TempDir::new("tui-keys-bulk-save");
+    let mut app = app_with(
+        &dir,
+        &[
+            ("one", &[("KEY", "1")]),
+            ("two", &[("KEY", "2")]),
+            ("three", &[("KEY", "3")]),
+        ],
+    );
+    for name in ["one", "two", "three"] {
+        edit_first_value(&mut app, name);
+        press(&mut app, KeyCode::Char('e'));
+        type_text(&mut app, "0");
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Esc);
+        assert!(app.list_view.is_dirty(name));
+    }
+
+    mark(&mut app, &["one", "three"]);
+    press(&mut app, KeyCode::Char('s'));
+
+    assert_eq!(status_text(&app), "Saved 2 selected profiles: one, three");
+    assert!(!app.list_view.is_dirty("one"));
+    assert!(app.list_view.is_dirty("two"));
+    assert!(!app.list_view.is_dirty("three"));
+    let saved = std::fs::read_to_string(dir.path().join("profiles/three.toml")).unwrap();
+    assert!(saved.contains("KEY = \"30\""), "{saved}");
+}
+
+#[test]
+fn diff_key_compares_the_base_with_the_selected_profile() {
+    let dir = TempDir::new("tui-keys-diff");
+    let mut app = app_with(
+        &dir,
+        &[
+            ("alpha", &[("A", "1"), ("B", "2")]),
+            ("beta", &[("B", "3")]),
+        ],
+    );
+
+    assert!(app.list_view.select_profile("alpha"));
+    press(&mut app, KeyCode::Char('D'));
+    assert_eq!(app.state, AppState::List);
+    assert!(status_text(&app).starts_with("Diff base 'alpha'"));
+
+    assert!(app.list_view.select_profile("beta"));
+    press(&mut app, KeyCode::Char('D'));
+    assert_eq!(app.state, AppState::Diff);
+    let popup = app.diff_popup.as_ref().unwrap();
+    assert_eq!((popup.a.as_str(), popup.b.as_str()), ("alpha", "beta"));
+    assert_eq!(popup.diff.only_in_a, [("A".to_string(), "1".to_string())]);
+    assert_eq!(
+        popup.diff.changed,
+        [("B".to_string(), "2".to_string(), "3".to_string())]
+    );
+
+    press(&mut app, KeyCode::Char('j'));
+    press(&mut app, KeyCode::PageDown);
+    assert_eq!(app.diff_popup.as_ref().unwrap().offset, 3);
+    press(&mut app, KeyCode::Char('e'));
+    assert!(app.diff_popup.as_ref().unwrap().expand);
+
+    press(&mut app, KeyCode::Esc);
+    assert_eq!(app.state, AppState::List);
+    assert!(app.diff_popup.is_none());
+    assert!(!app.shutdown);
+}
+
+#[test]
+fn enter_on_a_dependency_edits_it_and_esc_returns() {
+    let dir = TempDir::new("tui-keys-open-dependency");
+    let mut app_profile = Profile::new();
+    app_profile.add_variable("KEY", "old");
+    app_profile.add_profile("app", "base");
+    app_profile.add_profile("app", "lib");
+    let config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    config_manager.write_profile("app", &app_profile).unwrap();
+    let mut app = app_with(&dir, &[("lib", &[("B", "2")]), ("base", &[])]);
+
+    edit_first_value(&mut app, "app");
+    press(&mut app, KeyCode::Char('e'));
+    type_text(&mut app, "!");
+    press(&mut app, KeyCode::Enter);
+    press(&mut app, KeyCode::Tab);
+    press(&mut app, KeyCode::Char('j'));
+    press(&mut app, KeyCode::Enter);
+
+    // The pending change is kept in memory before moving on
+    assert_eq!(app.state, AppState::Edit);
+    assert_eq!(app.edit_view.profile_name(), "lib");
+    assert_eq!(app.edit_stack.len(), 1);
+    assert_eq!(
+        profile(&app, "app")
+            .variables
+            .get("KEY")
+            .map(String::as_str),
+        Some("old!")
+    );
+    assert!(app.list_view.is_dirty("app"));
+
+    press(&mut app, KeyCode::Esc);
+    assert_eq!(app.state, AppState::Edit);
+    assert_eq!(app.edit_view.profile_name(), "app");
+    assert_eq!(app.edit_view.selected_profile_index(), 1);
+    assert!(app.edit_stack.is_empty());
+
+    press(&mut app, KeyCode::Esc);
+    assert_eq!(app.state, AppState::List);
+}
+
+#[test]
+fn strict_profiles_refuse_lowercase_keys() {
+    let dir = TempDir::new("tui-keys-strict");
+    let config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    let mut strict = Profile::new();
+    strict.add_variable("PORT", "1");
+    strict.validation = Some(KeyValidation::Strict);
+    config_manager.write_profile("api", &strict).unwrap();
+    let mut app = App::new(config_manager, Profile::new());
+
+    assert!(app.list_view.select_profile("api"));
+    press(&mut app, KeyCode::Enter);
+    press(&mut app, KeyCode::Char('a'));
+    type_text(&mut app, "host");
+    press(&mut app, KeyCode::Enter);
+
+    let rows = app.edit_view.variables_for_rendering();
+    assert_eq!(rows[1].0.error_message(), Some("Must be all uppercase"));
+    for _ in 0.."host".len() {
+        press(&mut app, KeyCode::Backspace);
+    }
+    type_text(&mut app, "HOST");
+    press(&mut app, KeyCode::Enter);
+    type_text(&mut app, "x");
+    press(&mut app, KeyCode::Enter);
+    press(&mut app, KeyCode::Esc);
+
+    let api = profile(&app, "api");
+    assert_eq!(api.variables.get("HOST").map(String::as_str), Some("x"));
+    assert_eq!(api.validation, Some(KeyValidation::Strict));
+}
@@ -0,0 +1,156 @@
+//! `R` in the profile list catches up with profile files changed outside the TUI, and
+//! new or renamed profiles cannot take a name created on disk meanwhile.
+
+mod common;
+
+use common::{TempDir, press};
+use env_manage::config::ConfigManager;
+use env_manage::config::models::Profile;
+use env_manage::tui::app::{App, AppState};
+use env_manage::tui::event::handle_key;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+fn profiles_dir(dir: &TempDir) -> PathBuf {
+    let profiles = dir.path().join("profiles");
+    fs::create_dir_all(&profiles).unwrap();
+    profiles
+}
+
+fn new_app(dir: &TempDir) -> App {
+    let config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    App::new(config_manager, Profile::new())
+}
+
+/// Write a profile file as another shell would, with a modification time that differs
+/// from any earlier write
+fn write_externally(dir: &TempDir, name: &str, content: &str, age: u64) {
+    let path = profiles_dir(dir).join(format!("{name}.toml"));
+    fs::write(&path, content).unwrap();
+    File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(SystemTime::now() - Duration::from_secs(age))
+        .unwrap();
+}
+
+fn listed(app: &App) -> Vec<String> {
+    app.list_view
+        .all_profiles()
+        .into_iter()
+        .filter(|name| *name != env_manage::GLOBAL_PROFILE_MARK)
+        .cloned()
+        .collect()
+}
+
+#[test]
+fn reload_picks_up_created_removed_and_changed_files() {
+    let dir = TempDir::new("tui-reload");
+    write_externally(&dir, "keep", "[variables]\nA = \"1\"\n", 100);
+    write_externally(&dir, "gone", "", 100);
+    let mut app = new_app(&dir);
+    assert!(app.ensure_loaded("keep"));
+    assert!(app.ensure_loaded("gone"));
+
+    write_externally(&dir, "fresh", "[variables]\nB = \"2\"\n", 0);
+    write_externally(&dir, "keep", "[variables]\nA = \"changed\"\n", 0);
+    fs::remove_file(profiles_dir(&dir).join("gone.toml")).unwrap();
+
+    press(&mut app, KeyCode::Char('R'));
+    assert_eq!(listed(&app), ["fresh", "keep"]);
+    assert!(!app.config_manager.has_profile("gone"));
+    assert!(app.config_manager.has_profile("fresh"));
+    assert_eq!(
+        app.config_manager.get_profile("keep").unwrap().variables["A"],
+        "changed"
+    );
+    assert!(
+        app.status_message
+            .as_ref()
+            .unwrap()
+            .text
+            .contains("1 new, 1 removed, 1 changed")
+    );
+
+    press(&mut app, KeyCode::Char('R'));
+    assert_eq!(
+        app.status_message.as_ref().unwrap().text,
+        "Profiles are up to date"
+    );
+}
+
+#[test]
+fn reload_keeps_unsaved_changes_and_flags_the_conflict() {
+    let dir = TempDir::new("tui-reload-conflict");
+    write_externally(&dir, "edited", "[variables]\nA = \"1\"\n", 100);
+    let mut app = new_app(&dir);
+    assert!(app.ensure_loaded("edited"));
+    app.config_manager
+        .get_profile_mut("edited")
+        .unwrap()
+        .add_variable("B", "2");
+    app.list_view.mark_dirty("edited".to_string());
+
+    fs::remove_file(profiles_dir(&dir).join("edited.toml")).unwrap();
+    app.reload_from_disk();
+    assert!(app.config_manager.has_profile("edited"));
+    assert!(app.list_view.is_dirty("edited"));
+    assert!(
+        app.status_message
+            .as_ref()
+            .unwrap()
+            .text
+            .contains("Deleted outside the TUI but changed here: edited")
+    );
+
+    // Saving writes it back
+    app.list_view.select_profile("edited");
+    app.save_selected().unwrap();
+    assert!(profiles_dir(&dir).join("edited.toml").exists());
+}
+
+#[test]
+fn names_created_on_disk_are_taken() {
+    let dir = TempDir::new("tui-reload-names");
+    write_externally(&dir, "api", "", 100);
+    let mut app = new_app(&dir);
+
+    // Created by another shell after the TUI started, and not reloaded
+    write_externally(&dir, "web", "", 0);
+    press(&mut app, KeyCode::Char('n'));
+    for c in "web".chars() {
+        press(&mut app, KeyCode::Char(c));
+    }
+    handle_key(
+        &mut app,
+        KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL),
+    )
+    .unwrap();
+    assert_eq!(app.state, AppState::AddNew);
+    assert_eq!(
+        app.add_new_view.name_input().error_message(),
+        Some("Profile already exists")
+    );
+    press(&mut app, KeyCode::Esc);
+
+    assert!(app.list_view.select_profile("api"));
+    press(&mut app, KeyCode::F(2));
+    app.finish_full_load();
+    assert_eq!(app.state, AppState::Rename);
+    // Created after every profile was loaded for the rename
+    write_externally(&dir, "db", "", 0);
+    assert!(!app.config_manager.has_profile("db"));
+    for _ in 0.."api".len() {
+        press(&mut app, KeyCode::Backspace);
+    }
+    for c in "db".chars() {
+        press(&mut app, KeyCode::Char(c));
+    }
+    assert_eq!(
+        app.list_view.rename_input().error_message(),
+        Some("Profile name already exists")
+    );
+}
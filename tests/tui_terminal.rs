@@ -0,0 +1,77 @@
+//! The TUI refuses to start without a terminal, and leaves the alternate screen
+//! even when drawing a frame panics.
+
+mod common;
+
+use common::{TempDir, run};
+use env_manage::tui::terminal::TerminalGuard;
+use ratatui::Terminal;
+use ratatui::prelude::CrosstermBackend;
+use std::io::{self, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+
+const ENTER_ALTERNATE_SCREEN: &str = "\x1b[?1049h";
+const LEAVE_ALTERNATE_SCREEN: &str = "\x1b[?1049l";
+
+/// A writer standing in for the terminal, recording everything written to it
+#[derive(Clone, Default)]
+struct FakeTerminal(Arc<Mutex<Vec<u8>>>);
+
+impl FakeTerminal {
+    fn output(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+}
+
+impl Write for FakeTerminal {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn the_guard_restores_the_screen_when_a_frame_panics() {
+    let screen = FakeTerminal::default();
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let _guard = TerminalGuard::enter(screen.clone(), false).unwrap();
+        let mut terminal = Terminal::new(CrosstermBackend::new(screen.clone())).unwrap();
+        terminal.draw(|_| panic!("bug while drawing")).unwrap();
+    }));
+
+    assert!(result.is_err());
+    let output = screen.output();
+    assert!(output.starts_with(ENTER_ALTERNATE_SCREEN), "{output:?}");
+    assert!(output.contains(LEAVE_ALTERNATE_SCREEN), "{output:?}");
+}
+
+#[test]
+fn restoring_happens_once() {
+    let screen = FakeTerminal::default();
+    let mut guard = TerminalGuard::enter(screen.clone(), false).unwrap();
+    assert!(guard.is_active());
+
+    guard.restore().unwrap();
+    guard.restore().unwrap();
+    drop(guard);
+
+    assert_eq!(screen.output().matches(LEAVE_ALTERNATE_SCREEN).count(), 1);
+}
+
+#[test]
+fn ui_without_a_terminal_explains_instead_of_starting() {
+    let dir = TempDir::new("tui-no-tty");
+    let output = run(dir.path(), &["ui"]);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("needs an interactive terminal"), "{stderr}");
+    assert!(stderr.contains("em profile list"), "{stderr}");
+    assert!(!stderr.contains(ENTER_ALTERNATE_SCREEN));
+}
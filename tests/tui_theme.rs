@@ -0,0 +1,112 @@
+//! The TUI draws every color from the chosen theme, help hints and errors included.
+
+mod common;
+
+use common::{TempDir, write_profile};
+use env_manage::config::ConfigManager;
+use env_manage::config::models::Profile;
+use env_manage::tui::app::App;
+use env_manage::tui::theme::{BUILTIN_THEMES, KeyRole, Palette, Theme};
+use env_manage::tui::ui::ui;
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+use ratatui::buffer::Buffer;
+use ratatui::style::Color;
+use std::fs;
+
+fn app(dir: &TempDir, theme: Theme) -> App {
+    write_profile(dir.path(), "app", "[variables]\nKEY = \"value\"\n");
+    let config_manager = ConfigManager::with_base_path(dir.path().to_path_buf()).unwrap();
+    let mut app = App::new(config_manager, Profile::new());
+    app.theme = theme;
+    app.show_error("Something broke");
+    app
+}
+
+fn render(app: &App) -> Buffer {
+    let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+    terminal.draw(|frame| ui(frame, app)).unwrap();
+    terminal.backend().buffer().clone()
+}
+
+/// Foreground of the first cell of `text` in the bottom bar
+fn bottom_fg(buffer: &Buffer, text: &str) -> Color {
+    let area = buffer.area;
+    for y in area.height - 2..area.height {
+        let row: String = (0..area.width)
+            .map(|x| buffer[(x, y)].symbol().to_string())
+            .collect();
+        if let Some(index) = row.find(text) {
+            let x = row[..index].chars().count() as u16;
+            return buffer[(x, y)].fg;
+        }
+    }
+    panic!("`{text}` is not in the bottom bar");
+}
+
+#[test]
+fn switching_themes_changes_help_hints_and_errors() {
+    let dir = TempDir::new("tui-theme-render");
+    let default = Palette::default();
+    let colorblind = Palette::colorblind_safe();
+
+    let buffer = render(&app(&dir, Theme::default()));
+    assert_eq!(bottom_fg(&buffer, "Esc"), default.key_cancel);
+    assert_eq!(bottom_fg(&buffer, "Tab"), default.key_focus);
+    assert_eq!(bottom_fg(&buffer, "Something broke"), default.error);
+
+    let buffer = render(&app(&dir, Theme::new(colorblind)));
+    assert_eq!(bottom_fg(&buffer, "Esc"), colorblind.key_cancel);
+    assert_eq!(bottom_fg(&buffer, "Tab"), colorblind.key_focus);
+    assert_eq!(bottom_fg(&buffer, "Something broke"), colorblind.error);
+    assert_ne!(default.key_cancel, colorblind.key_cancel);
+    assert_ne!(default.error, colorblind.error);
+}
+
+#[test]
+fn builtin_themes_load_by_name() {
+    let dir = TempDir::new("tui-theme-builtin");
+    for name in BUILTIN_THEMES {
+        Theme::load(name, dir.path()).unwrap();
+    }
+    let theme = Theme::load("high-contrast", dir.path()).unwrap();
+    assert_eq!(theme.palette(), &Palette::high_contrast());
+}
+
+#[test]
+fn a_user_theme_keeps_the_defaults_it_does_not_set() {
+    let dir = TempDir::new("tui-theme-user");
+    let themes = dir.path().join("themes");
+    fs::create_dir_all(&themes).unwrap();
+    fs::write(
+        themes.join("mine.toml"),
+        "error = \"#ff0000\"\nkey_cancel = \"magenta\"\n",
+    )
+    .unwrap();
+
+    let theme = Theme::load("mine", dir.path()).unwrap();
+    assert_eq!(theme.palette().error, Color::Rgb(255, 0, 0));
+    assert_eq!(theme.palette().key_cancel, Color::Magenta);
+    assert_eq!(theme.palette().primary, Palette::default().primary);
+    assert_eq!(theme.key(KeyRole::Cancel).fg, Some(Color::Magenta));
+
+    let buffer = render(&app(&dir, theme));
+    assert_eq!(bottom_fg(&buffer, "Esc"), Color::Magenta);
+}
+
+#[test]
+fn unknown_themes_and_bad_colors_are_errors() {
+    let dir = TempDir::new("tui-theme-errors");
+    let err = Theme::load("missing", dir.path()).unwrap_err().to_string();
+    assert!(err.contains("Unknown theme `missing`"), "{err}");
+    assert!(err.contains("colorblind-safe"), "{err}");
+
+    let themes = dir.path().join("themes");
+    fs::create_dir_all(&themes).unwrap();
+    fs::write(themes.join("bad.toml"), "error = \"not-a-color\"\n").unwrap();
+    let err = Theme::load("bad", dir.path()).unwrap_err().to_string();
+    assert!(err.contains("invalid color `not-a-color`"), "{err}");
+
+    fs::write(themes.join("typo.toml"), "eror = \"red\"\n").unwrap();
+    assert!(Theme::load("typo", dir.path()).is_err());
+}